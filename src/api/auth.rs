@@ -0,0 +1,302 @@
+//! JWT authentication middleware.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::handlers::AppState;
+
+/// Shortest HMAC secret accepted, in bytes (the HS256 output size).
+pub const MIN_SECRET_LEN: usize = 32;
+
+/// JWT validation settings.
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    /// HMAC secret used to verify token signatures
+    pub secret: String,
+    /// Signing algorithm (HS256, HS384 or HS512)
+    pub algorithm: String,
+    /// Whether requests without a valid token are rejected
+    pub required: bool,
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            algorithm: "HS256".to_string(),
+            required: false,
+        }
+    }
+}
+
+impl JwtConfig {
+    /// Load JWT settings from environment variables.
+    pub fn from_env() -> Self {
+        Self {
+            secret: std::env::var("JWT_SECRET").unwrap_or_default(),
+            algorithm: std::env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()),
+            required: std::env::var("JWT_REQUIRED")
+                .map(|s| s == "true" || s == "1")
+                .unwrap_or(false),
+        }
+    }
+
+    /// Check that the secret is long enough to resist brute force.
+    ///
+    /// An empty secret would let anyone sign tokens for any subject, so the
+    /// service refuses to start without one when authentication is
+    /// required. With authentication optional and no secret set, there is
+    /// nothing to check: every bearer token is rejected instead.
+    pub fn check_secret(&self) -> anyhow::Result<()> {
+        if !self.required && self.secret.is_empty() {
+            return Ok(());
+        }
+        if self.secret.len() < MIN_SECRET_LEN {
+            anyhow::bail!(
+                "JWT_SECRET must be at least {} bytes, got {}",
+                MIN_SECRET_LEN,
+                self.secret.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Validate a token and return its claims.
+    ///
+    /// Fails for every token when no secret is configured.
+    pub fn validate(&self, token: &str) -> anyhow::Result<Claims> {
+        if self.secret.is_empty() {
+            anyhow::bail!("No JWT secret configured");
+        }
+        let algorithm = Algorithm::from_str(&self.algorithm)?;
+        let mut validation = Validation::new(algorithm);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+
+        let data = decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(self.secret.as_bytes()),
+            &validation,
+        )?;
+        Ok(data.claims)
+    }
+}
+
+/// Claims read from an authenticated request's token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// Subject, used as the owner of submitted jobs
+    pub sub: String,
+    /// Expiry as a Unix timestamp
+    pub exp: u64,
+}
+
+/// Validate the Bearer token on a request.
+///
+/// Valid claims are added to the request extensions. Requests with an
+/// invalid token are always rejected; requests without one are only
+/// rejected when authentication is required.
+pub async fn require_jwt(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match token {
+        Some(token) => {
+            let claims = state.jwt.validate(token).map_err(|e| {
+                debug!(error = %e, "Rejected invalid token");
+                StatusCode::UNAUTHORIZED
+            })?;
+            request.extensions_mut().insert(claims);
+        }
+        None if state.jwt.required => return Err(StatusCode::UNAUTHORIZED),
+        None => {}
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{get, post};
+    use axum::{middleware, Router};
+    use axum_test::TestServer;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    use crate::api::handlers;
+    use crate::types::ChunkingConfig;
+
+    const SECRET: &str = "test-secret";
+
+    fn generate_test_token(secret: &str, sub: &str) -> String {
+        let claims = Claims {
+            sub: sub.to_string(),
+            exp: (chrono::Utc::now().timestamp() + 3600) as u64,
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+    }
+
+    fn server(required: bool) -> (TestServer, Arc<AppState>) {
+        let jwt = JwtConfig {
+            secret: SECRET.to_string(),
+            required,
+            ..Default::default()
+        };
+        let state = Arc::new(AppState::new(ChunkingConfig::default()).with_jwt(jwt));
+        let protected = Router::new()
+            .route("/chunk/jobs", post(handlers::start_chunk_job))
+            .route("/chunk/jobs/:job_id", get(handlers::get_job_status))
+            .route("/chunk/jobs/:job_id/stream", get(handlers::stream_job))
+            .route("/chunk/jobs/:job_id/export", post(handlers::export_job))
+            .route("/health/embedding", get(handlers::embedding_health))
+            .route("/chunk/profiles", get(handlers::list_profiles))
+            .route_layer(middleware::from_fn_with_state(Arc::clone(&state), require_jwt));
+        let app = Router::new()
+            .route("/health", get(handlers::health_check))
+            .merge(protected)
+            .with_state(Arc::clone(&state));
+        (TestServer::new(app).unwrap(), state)
+    }
+
+    #[tokio::test]
+    async fn test_required_token_rejects_unauthenticated() {
+        let (server, _) = server(true);
+
+        server.get("/health").await.assert_status_ok();
+        for path in ["/chunk/profiles", "/health/embedding"] {
+            server.get(path).expect_failure().await.assert_status(StatusCode::UNAUTHORIZED);
+        }
+
+        let wrong_secret = generate_test_token("other-secret", "user-1");
+        server
+            .get("/chunk/profiles")
+            .authorization_bearer(wrong_secret)
+            .expect_failure()
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        server
+            .get("/chunk/profiles")
+            .authorization_bearer(generate_test_token(SECRET, "user-1"))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_optional_token_allows_anonymous() {
+        let (server, _) = server(false);
+        server.get("/chunk/profiles").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_job_attributes_owner() {
+        let (server, state) = server(true);
+        let source_id = uuid::Uuid::new_v4();
+        let body = json!({
+            "source_id": source_id,
+            "source_kind": "document",
+            "items": [{
+                "id": uuid::Uuid::new_v4(),
+                "source_id": source_id,
+                "source_kind": "document",
+                "content_type": "text/plain",
+                "content": "Hello world.",
+                "metadata": {}
+            }],
+            "owner_id": "spoofed"
+        });
+
+        let response = server
+            .post("/chunk/jobs")
+            .authorization_bearer(generate_test_token(SECRET, "user-1"))
+            .json(&body)
+            .await;
+        response.assert_status_ok();
+        let job_id: uuid::Uuid =
+            serde_json::from_value(response.json::<serde_json::Value>()["job_id"].clone()).unwrap();
+
+        let mut chunks = Vec::new();
+        for _ in 0..50 {
+            if let Some(job) = state.job_store.read().await.get_job(job_id) {
+                if job.status == crate::types::ChunkJobStatus::Completed {
+                    chunks = job.chunks.clone();
+                    break;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(!chunks.is_empty());
+        assert!(chunks.iter().all(|c| c.metadata.owner_id.as_deref() == Some("user-1")));
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_private_to_their_owner() {
+        let (server, state) = server(false);
+        let job_id = state.job_store.write().await.create_owned_job(1, Some("user-1".to_string()));
+        state.job_store.write().await.complete_job(job_id);
+
+        let owner = generate_test_token(SECRET, "user-1");
+        let other = generate_test_token(SECRET, "user-2");
+        server
+            .get(&format!("/chunk/jobs/{}", job_id))
+            .authorization_bearer(&owner)
+            .await
+            .assert_status_ok();
+        for path in [format!("/chunk/jobs/{}", job_id), format!("/chunk/jobs/{}/stream", job_id)] {
+            server.get(&path).authorization_bearer(&other).expect_failure().await.assert_status(StatusCode::NOT_FOUND);
+            server.get(&path).expect_failure().await.assert_status(StatusCode::NOT_FOUND);
+        }
+        server
+            .post(&format!("/chunk/jobs/{}/export", job_id))
+            .authorization_bearer(&other)
+            .expect_failure()
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        server
+            .post(&format!("/chunk/jobs/{}/export", job_id))
+            .authorization_bearer(&owner)
+            .await
+            .assert_status_ok();
+    }
+
+    #[test]
+    fn test_short_secret_is_rejected() {
+        let mut jwt = JwtConfig {
+            required: true,
+            ..Default::default()
+        };
+        assert!(jwt.check_secret().is_err());
+        jwt.secret = SECRET.to_string();
+        assert!(jwt.check_secret().is_err());
+        jwt.secret = "x".repeat(MIN_SECRET_LEN);
+        assert!(jwt.check_secret().is_ok());
+    }
+
+    #[test]
+    fn test_optional_auth_without_secret() {
+        // Starting without a secret is fine while authentication is optional
+        let jwt = JwtConfig::default();
+        assert!(jwt.check_secret().is_ok());
+
+        // but no token validates, not even one signed with the empty secret
+        let token = generate_test_token("", "user-1");
+        assert!(jwt.validate(&token).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! HTTP API handlers.
 
+pub mod auth;
 pub mod handlers;
+pub mod openapi;
 
 pub use handlers::*;
@@ -0,0 +1,132 @@
+//! OpenAPI specification for the HTTP API.
+
+use axum::Json;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::{Config, SwaggerUi};
+
+use super::handlers::{
+    self, ActiveProfileResponse, ChunkerInfo, HealthResponse, SetActiveProfileRequest, SyncChunkRequest,
+    ProcessedFileResponse, SyncChunkResponse, ValidationErrorResponse, WebhookResponse,
+};
+use crate::batch::DryRunReport;
+use crate::output::{WebhookEvent, WebhookRegistration};
+use crate::types::language::{LanguageConfidenceReport, LanguageStats};
+use crate::types::{
+    Chunk, ChunkConfig, ChunkJobStatus, ChunkJobStatusResponse, ChunkMetadata, ChunkingProfile, DiffStats, ItemValidationErrors,
+    SourceItem, SourceKind, StartChunkJobRequest, StartChunkJobResponse, ValidationError,
+};
+
+/// OpenAPI document aggregating all chunker operations and schemas.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Chunker Service",
+        description = "Chunking service for RAG pipelines"
+    ),
+    paths(
+        handlers::health_check,
+        handlers::embedding_health,
+        handlers::metrics,
+        handlers::start_chunk_job,
+        handlers::dry_run_chunk_job,
+        handlers::get_job_status,
+        handlers::stream_job,
+        handlers::export_job,
+        handlers::sync_chunk,
+        handlers::get_processed_file,
+        handlers::analyze_languages,
+        handlers::register_webhook,
+        handlers::list_webhooks,
+        handlers::delete_webhook,
+        handlers::list_profiles,
+        handlers::get_active_profile,
+        handlers::set_active_profile,
+        handlers::get_config,
+    ),
+    components(schemas(
+        SourceKind,
+        SourceItem,
+        StartChunkJobRequest,
+        StartChunkJobResponse,
+        ValidationError,
+        ItemValidationErrors,
+        ValidationErrorResponse,
+        DryRunReport,
+        ChunkJobStatus,
+        ChunkJobStatusResponse,
+        Chunk,
+        ChunkMetadata,
+        DiffStats,
+        ChunkConfig,
+        ChunkingProfile,
+        SyncChunkRequest,
+        SyncChunkResponse,
+        ProcessedFileResponse,
+        LanguageConfidenceReport,
+        LanguageStats,
+        HealthResponse,
+        ActiveProfileResponse,
+        SetActiveProfileRequest,
+        ChunkerInfo,
+        WebhookEvent,
+        WebhookRegistration,
+        WebhookResponse,
+    )),
+    tags(
+        (name = "service", description = "Health and metrics"),
+        (name = "jobs", description = "Asynchronous chunking jobs"),
+        (name = "chunking", description = "Synchronous chunking"),
+        (name = "profiles", description = "Chunking profiles"),
+        (name = "webhooks", description = "Job notification webhooks"),
+    )
+)]
+pub struct ChunkerApiDoc;
+
+/// Serve the OpenAPI specification.
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ChunkerApiDoc::openapi())
+}
+
+/// Swagger UI at `/docs`, rendering the spec served at `/openapi.json`.
+///
+/// The UI assets are bundled into the binary rather than loaded from a CDN.
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/docs").config(Config::from("/openapi.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_endpoints() {
+        let spec = ChunkerApiDoc::openapi();
+        for path in ["/health", "/chunk/jobs", "/chunk/jobs/dry-run", "/chunk/jobs/{job_id}", "/chunk/sync", "/chunk/profiles/active"] {
+            assert!(spec.paths.paths.contains_key(path), "missing {}", path);
+        }
+
+        let schemas = &spec.components.as_ref().unwrap().schemas;
+        assert!(schemas.contains_key("StartChunkJobRequest"));
+        assert!(schemas.contains_key("SyncChunkResponse"));
+    }
+
+    #[tokio::test]
+    async fn test_swagger_ui_is_served_locally() {
+        let server = axum_test::TestServer::new(axum::Router::new().merge(swagger_ui())).unwrap();
+
+        let page = server.get("/docs/").await;
+        page.assert_status_ok();
+        assert!(!page.text().contains("unpkg.com"));
+
+        let initializer = server.get("/docs/swagger-initializer.js").await;
+        initializer.assert_status_ok();
+        assert!(initializer.text().contains("/openapi.json"));
+    }
+
+    #[test]
+    fn test_spec_serializes_with_examples() {
+        let json = ChunkerApiDoc::openapi().to_json().unwrap();
+        assert!(json.contains("\"openapi\":\"3."));
+        assert!(json.contains("acme/project"));
+    }
+}
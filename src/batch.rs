@@ -1,66 +1,713 @@
 //! Batch processing utilities for large-scale chunking.
 
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use tokio::sync::mpsc;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Semaphore};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::chunkers::{count_tokens, Chunker};
+use crate::chunkers::repo_chunker::{AstParser, RepositoryContext};
+use crate::file_processor::{FileFilter, FileProcessor, FilterConfig};
 use crate::router::ChunkingRouter;
-use crate::types::{Chunk, ChunkConfig, SourceItem, SourceKind};
+use crate::telemetry::metrics;
+use crate::types::language::{content_type_for, language_from_extension};
+use crate::types::{assign_content_hash_ids, Chunk, ChunkConfig, ContentHash, SourceItem, SourceKind};
 
 /// Configuration for batch processing.
 #[derive(Debug, Clone)]
 pub struct BatchConfig {
     /// Maximum items to process concurrently
     pub concurrency: usize,
-    /// Maximum chunks to buffer before sending downstream
+    /// Maximum chunks to buffer before sending downstream, and capacity of
+    /// the channel created by `BatchProcessor::channel`
     pub buffer_size: usize,
+    /// How long to wait on a full downstream channel before giving up
+    pub backpressure_timeout_ms: u64,
     /// Whether to continue on individual item failures
     pub continue_on_error: bool,
     /// Maximum content size per item (bytes) before splitting
     pub max_content_size: usize,
+    /// Cache of previously chunked files, keyed by path and content hash
+    pub cache: Option<Arc<Mutex<FileHashCache>>>,
+    /// Maximum retries for items failing with a `RetryableError`
+    pub max_retries: u32,
+    /// Base delay before the first retry; doubled on each further attempt
+    pub retry_base_delay_ms: u64,
+    /// Items between checkpoint writes in `process_batch_resumable`
+    pub checkpoint_interval: usize,
+    /// Return chunks flagged `is_test` in `BatchResult::test_chunks`
+    /// instead of alongside production chunks
+    pub chunk_tests_separately: bool,
+    /// Route `SourceKind::Other` items by inspecting their content
+    pub enable_content_routing: bool,
+    /// Start items in descending `SourceItem::priority` order
+    pub respect_priority: bool,
+    /// Sort returned chunks by `Chunk::natural_order_key` so the output
+    /// does not depend on which items finished first
+    pub deterministic_order: bool,
+    /// Drop chunks whose content repeats an earlier chunk of the batch,
+    /// e.g. from copied or vendored files. The copy from the item earliest
+    /// in the input is kept; streamed batches hold back each item's chunks
+    /// until every earlier item has finished
+    pub deduplicate: bool,
+    /// File caching the `RepositoryContext` built by
+    /// `BatchProcessor::build_repository_context` between runs
+    pub repository_context_cache: Option<PathBuf>,
+    /// File filter rules items must pass before they are chunked, also
+    /// applied by `BatchProcessor::dry_run`. Unset, items are only checked
+    /// with `SourceItem::validate`
+    pub filter: Option<FilterConfig>,
+    /// Runs on each item before it is chunked, e.g. to decrypt content
+    pub pre_processor: Option<Arc<dyn PreProcessor + Send + Sync>>,
+    /// Runs on each item's chunks after chunking, e.g. to redact PII or
+    /// stamp tenant IDs
+    pub post_processor: Option<Arc<dyn PostProcessor + Send + Sync>>,
 }
 
+/// Hook run on an item before it is chunked.
+///
+/// An error fails the item like a chunking error.
+pub trait PreProcessor {
+    fn process(&self, item: &mut SourceItem) -> Result<()>;
+}
+
+/// Hook run on an item's chunks after chunking and before caching.
+///
+/// An error fails the item like a chunking error.
+pub trait PostProcessor {
+    fn process(&self, chunks: &mut Vec<Chunk>) -> Result<()>;
+}
+
+impl std::fmt::Debug for dyn PreProcessor + Send + Sync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PreProcessor")
+    }
+}
+
+impl std::fmt::Debug for dyn PostProcessor + Send + Sync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PostProcessor")
+    }
+}
+
+/// Replaces every match of its patterns with `[REDACTED]`.
+///
+/// As a pre-processor it redacts item content before chunking; as a
+/// post-processor it redacts chunk content and recounts chunk tokens.
+/// Chunk offsets keep referring to the original content.
+#[derive(Debug, Clone)]
+pub struct RedactionProcessor {
+    patterns: Vec<Regex>,
+}
+
+impl RedactionProcessor {
+    /// Text that replaces each match.
+    pub const REPLACEMENT: &'static str = "[REDACTED]";
+
+    /// Create a processor redacting matches of any of `patterns`.
+    pub fn new(patterns: Vec<Regex>) -> Self {
+        Self { patterns }
+    }
+
+    /// `text` with every match replaced, or `None` if nothing matched.
+    fn redact(&self, text: &str) -> Option<String> {
+        let mut redacted: Option<String> = None;
+        for pattern in &self.patterns {
+            let current = redacted.as_deref().unwrap_or(text);
+            if pattern.is_match(current) {
+                redacted = Some(pattern.replace_all(current, Self::REPLACEMENT).into_owned());
+            }
+        }
+        redacted
+    }
+}
+
+impl PreProcessor for RedactionProcessor {
+    fn process(&self, item: &mut SourceItem) -> Result<()> {
+        if let Some(content) = self.redact(&item.content) {
+            item.content = content;
+        }
+        Ok(())
+    }
+}
+
+impl PostProcessor for RedactionProcessor {
+    fn process(&self, chunks: &mut Vec<Chunk>) -> Result<()> {
+        for chunk in chunks.iter_mut() {
+            if let Some(content) = self.redact(&chunk.content) {
+                chunk.token_count = count_tokens(&content);
+                chunk.content = content;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Age after which a cached `RepositoryContext` is rebuilt.
+pub const REPOSITORY_CONTEXT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
 impl Default for BatchConfig {
     fn default() -> Self {
         Self {
             concurrency: 4,
             buffer_size: 100,
+            backpressure_timeout_ms: 5000,
             continue_on_error: true,
             max_content_size: 10 * 1024 * 1024, // 10MB
+            cache: None,
+            max_retries: 2,
+            retry_base_delay_ms: 100,
+            checkpoint_interval: 100,
+            chunk_tests_separately: false,
+            enable_content_routing: true,
+            respect_priority: false,
+            deterministic_order: true,
+            deduplicate: false,
+            repository_context_cache: None,
+            filter: None,
+            pre_processor: None,
+            post_processor: None,
         }
     }
 }
 
 /// Result of batch processing.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BatchResult {
     pub total_items: usize,
     pub processed_items: usize,
     pub failed_items: usize,
     pub total_chunks: usize,
     pub errors: Vec<BatchError>,
+    /// Whether processing stopped early because of cancellation
+    #[serde(default)]
+    pub was_cancelled: bool,
+    /// Test chunks, when `BatchConfig::chunk_tests_separately` is set
+    #[serde(skip)]
+    pub test_chunks: Vec<Chunk>,
+    /// Chunks dropped as duplicates, when `BatchConfig::deduplicate` is set
+    #[serde(default)]
+    pub duplicate_chunks_removed: usize,
+    /// Tiny chunks merged into their successor by the chunker
+    #[serde(default)]
+    pub merged_chunks: usize,
+}
+
+impl BatchResult {
+    /// Sort `test_chunks` by `Chunk::natural_order_key`.
+    ///
+    /// Only the test output is held here; the main chunks are returned
+    /// alongside the result, already in this order when
+    /// `BatchConfig::deterministic_order` is set. The sort is stable, so
+    /// chunks starting at the same position of an item (e.g. a parent and
+    /// its first child) keep their emission order.
+    pub fn sort_test_chunks(&mut self) {
+        sort_chunks(&mut self.test_chunks);
+    }
+
+    /// Iterate over `test_chunks` in `Chunk::natural_order_key` order.
+    pub fn sorted_test_chunks(&self) -> impl Iterator<Item = &Chunk> {
+        let mut chunks: Vec<&Chunk> = self.test_chunks.iter().collect();
+        chunks.sort_by_key(|chunk| chunk.natural_order_key());
+        chunks.into_iter()
+    }
+}
+
+/// What processing a batch would do, from `BatchProcessor::dry_run`.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct DryRunReport {
+    /// Accepted items per chunker they would be routed to
+    #[schema(value_type = HashMap<String, usize>)]
+    pub per_chunker_item_count: HashMap<&'static str, usize>,
+    /// Accepted items per source kind
+    #[schema(value_type = HashMap<String, usize>)]
+    pub per_kind_item_count: HashMap<SourceKind, usize>,
+    /// Chunks the accepted items would produce, estimated from token counts
+    pub estimated_total_chunks: usize,
+    /// Items the filter rules reject, as `[id, reason]` pairs
+    #[schema(value_type = Vec<Vec<String>>)]
+    pub rejected_items: Vec<(Uuid, String)>,
+    /// Content size of all items, rejected ones included
+    pub total_content_bytes: usize,
+}
+
+/// Keep chunks whose content hash is not yet in `seen`, adding theirs.
+fn retain_unique(chunks: Vec<Chunk>, seen: &mut HashSet<ContentHash>) -> Vec<Chunk> {
+    chunks.into_iter().filter(|chunk| seen.insert(chunk.content_hash())).collect()
+}
+
+/// Releases item results in input order, whatever order items finish in,
+/// so deduplication always keeps the copy from the earliest item.
+struct InputOrder {
+    /// Input positions of each item ID, in case IDs repeat
+    positions: HashMap<Uuid, VecDeque<usize>>,
+    /// Results waiting for an earlier item
+    pending: BTreeMap<usize, Vec<Chunk>>,
+    next: usize,
+}
+
+impl InputOrder {
+    fn new(items: &[SourceItem]) -> Self {
+        let mut positions: HashMap<Uuid, VecDeque<usize>> = HashMap::new();
+        for (position, item) in items.iter().enumerate() {
+            positions.entry(item.id).or_default().push_back(position);
+        }
+        Self {
+            positions,
+            pending: BTreeMap::new(),
+            next: 0,
+        }
+    }
+
+    /// Record an item's chunks (none if it failed or was cancelled), returning the chunks of
+    /// every item now due, in input order.
+    fn push(&mut self, item_id: Uuid, chunks: Vec<Chunk>) -> Vec<Chunk> {
+        let Some(position) = self.positions.get_mut(&item_id).and_then(VecDeque::pop_front) else {
+            return chunks;
+        };
+        self.pending.insert(position, chunks);
+
+        let mut ready = Vec::new();
+        while let Some(chunks) = self.pending.remove(&self.next) {
+            ready.extend(chunks);
+            self.next += 1;
+        }
+        ready
+    }
+
+    /// Chunks still waiting, e.g. after cancellation, in input order.
+    fn finish(self) -> Vec<Chunk> {
+        self.pending.into_values().flatten().collect()
+    }
+}
+
+/// Number of tiny chunks merged into `chunks`.
+fn merged_chunk_count(chunks: &[Chunk]) -> usize {
+    chunks.iter().map(|chunk| chunk.metadata.merged_chunks).sum()
+}
+
+/// Stable sort of chunks by `Chunk::natural_order_key`.
+fn sort_chunks(chunks: &mut [Chunk]) {
+    chunks.sort_by_key(Chunk::natural_order_key);
 }
 
 /// Error during batch processing.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchError {
     pub item_id: Uuid,
     pub error: String,
 }
 
+/// Progress of a resumable batch, persisted between runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchCheckpoint {
+    /// Last item that finished processing
+    pub last_item_id: Option<Uuid>,
+    /// Every item that was chunked successfully so far
+    pub completed_item_ids: HashSet<Uuid>,
+    /// Items that failed; they are retried when the batch is resumed
+    #[serde(default)]
+    pub failed_item_ids: HashSet<Uuid>,
+    /// Chunks of the completed items, returned again by later runs
+    #[serde(default)]
+    pub chunks: Vec<Chunk>,
+    /// Running totals across all runs
+    pub result: BatchResult,
+}
+
+/// Load a checkpoint written by `BatchProcessor::process_batch_resumable`.
+pub fn load_checkpoint(path: &Path) -> Result<BatchCheckpoint> {
+    let json = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Write a checkpoint atomically by writing a `.tmp` file and renaming it.
+fn save_checkpoint(path: &Path, checkpoint: &BatchCheckpoint) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = std::path::PathBuf::from(tmp);
+
+    std::fs::write(&tmp, serde_json::to_vec(checkpoint)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Error returned for items skipped because the batch was cancelled.
+#[derive(Debug)]
+struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "batch cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Error returned when streamed chunks could not be delivered, because the
+/// receiver was dropped or stayed full for `backpressure_timeout_ms`.
+///
+/// Processing stops at that point, so the chunks sent so far are only part
+/// of the batch.
+#[derive(Debug)]
+pub struct DeliveryStalled {
+    /// Items fully processed before delivery stopped
+    pub processed_items: usize,
+    /// Items in the batch
+    pub total_items: usize,
+}
+
+impl std::fmt::Display for DeliveryStalled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "chunk receiver stalled or dropped after {} of {} items",
+            self.processed_items, self.total_items
+        )
+    }
+}
+
+impl std::error::Error for DeliveryStalled {}
+
+/// Marks a chunking error as transient so `BatchProcessor` retries it.
+///
+/// I/O timeouts, interruptions and dropped connections are wrapped
+/// automatically (see `wrap_transient`). Other errors that are not wrapped
+/// (unsupported language, empty content, and other logic errors) fail the
+/// item on the first attempt.
+#[derive(Debug)]
+pub struct RetryableError(anyhow::Error);
+
+impl RetryableError {
+    /// Wrap an error as retryable.
+    pub fn new(error: impl Into<anyhow::Error>) -> Self {
+        Self(error.into())
+    }
+
+    /// Check whether an error (or anything in its chain) is retryable.
+    pub fn is_retryable(error: &anyhow::Error) -> bool {
+        error.chain().any(|e| e.is::<RetryableError>())
+    }
+
+    /// Wrap `error` as retryable if it is a transient failure: an I/O error
+    /// of a transient kind or an elapsed tokio timeout anywhere in its chain.
+    pub fn wrap_transient(error: anyhow::Error) -> anyhow::Error {
+        if Self::is_retryable(&error) || !is_transient(&error) {
+            return error;
+        }
+        Self(error).into()
+    }
+
+    /// Get the wrapped error.
+    pub fn into_inner(self) -> anyhow::Error {
+        self.0
+    }
+}
+
+impl std::fmt::Display for RetryableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RetryableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
+fn is_transient(error: &anyhow::Error) -> bool {
+    use std::io::ErrorKind;
+
+    error.chain().any(|e| {
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::Interrupted
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+            );
+        }
+        e.is::<tokio::time::error::Elapsed>()
+    })
+}
+
+/// Default number of files a `FileHashCache` holds before evicting the
+/// least recently used.
+pub const DEFAULT_FILE_CACHE_CAPACITY: usize = 10_000;
+
+/// Default age after which a `FileHashCache` entry is no longer used.
+pub const DEFAULT_FILE_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Cache of chunked files used for incremental re-indexing.
+///
+/// Entries are keyed by the owner the file was chunked for, the SHA-256 of
+/// its content (always computed from the content, never taken from the
+/// caller) and the fingerprint of the `ChunkConfig` it was chunked with.
+/// Items matching an entry are not re-chunked. The cache holds at most
+/// `capacity` files, evicting the least recently used, and ignores entries
+/// older than `ttl`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileHashCache {
+    entries: HashMap<String, CachedFile>,
+    /// Use counter, for finding the least recently used entry
+    #[serde(default)]
+    clock: u64,
+    #[serde(skip, default = "default_file_cache_capacity")]
+    capacity: usize,
+    #[serde(skip, default = "default_file_cache_ttl")]
+    ttl: Duration,
+}
+
+fn default_file_cache_capacity() -> usize {
+    DEFAULT_FILE_CACHE_CAPACITY
+}
+
+fn default_file_cache_ttl() -> Duration {
+    DEFAULT_FILE_CACHE_TTL
+}
+
+/// Chunks cached for one file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    /// Subject the file was chunked for, if any
+    owner_id: Option<String>,
+    /// Hex SHA-256 of the file content
+    hash: String,
+    /// Path the file was last chunked under
+    path: String,
+    chunks: Vec<Chunk>,
+    cached_at: DateTime<Utc>,
+    #[serde(default)]
+    last_used: u64,
+}
+
+impl Default for FileHashCache {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            clock: 0,
+            capacity: DEFAULT_FILE_CACHE_CAPACITY,
+            ttl: DEFAULT_FILE_CACHE_TTL,
+        }
+    }
+}
+
+impl FileHashCache {
+    /// Create an empty cache with the default capacity and TTL.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of cached files (at least 1).
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set how long entries stay usable.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    fn key(owner_id: Option<&str>, hash: &str, config: &ChunkConfig) -> String {
+        serde_json::json!([owner_id, hash, config.fingerprint()]).to_string()
+    }
+
+    fn is_expired(&self, entry: &CachedFile) -> bool {
+        chrono::Duration::from_std(self.ttl).is_ok_and(|ttl| Utc::now() - entry.cached_at > ttl)
+    }
+
+    /// Get the chunks cached for content with this hash, chunked for
+    /// `owner_id` with `config`.
+    pub fn get(&mut self, owner_id: Option<&str>, hash: &str, config: &ChunkConfig) -> Option<&Vec<Chunk>> {
+        let key = Self::key(owner_id, hash, config);
+        if self.entries.get(&key).is_some_and(|entry| self.is_expired(entry)) {
+            self.entries.remove(&key);
+            return None;
+        }
+        self.clock += 1;
+        let entry = self.entries.get_mut(&key)?;
+        entry.last_used = self.clock;
+        Some(&entry.chunks)
+    }
+
+    /// Store the chunks produced for a file with `config`, evicting expired
+    /// entries and then the least recently used ones if the cache is full.
+    pub fn insert(&mut self, owner_id: Option<&str>, path: &str, hash: String, config: &ChunkConfig, chunks: Vec<Chunk>) {
+        let key = Self::key(owner_id, &hash, config);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let expired: Vec<String> = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| self.is_expired(entry))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in expired {
+                self.entries.remove(&key);
+            }
+            while self.entries.len() >= self.capacity {
+                let Some(oldest) = self.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone()) else {
+                    break;
+                };
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.clock += 1;
+        let entry = CachedFile {
+            owner_id: owner_id.map(String::from),
+            hash,
+            path: path.to_string(),
+            chunks,
+            cached_at: Utc::now(),
+            last_used: self.clock,
+        };
+        self.entries.insert(key, entry);
+    }
+
+    /// Find a file chunked for `owner_id` by content hash, returning its
+    /// path and chunks.
+    pub fn find_by_hash(&self, owner_id: Option<&str>, hash: &str) -> Option<(&str, &[Chunk])> {
+        self.entries
+            .values()
+            .filter(|entry| entry.hash == hash && entry.owner_id.as_deref() == owner_id && !self.is_expired(entry))
+            .max_by_key(|entry| entry.last_used)
+            .map(|entry| (entry.path.as_str(), entry.chunks.as_slice()))
+    }
+
+    /// Remove every entry for a path.
+    pub fn remove(&mut self, path: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.path != path);
+        self.entries.len() < before
+    }
+
+    /// Number of cached files.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Persist the cache as JSON.
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Load a cache previously written with `save_to_file`, with the
+    /// default capacity and TTL.
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+/// Progress update emitted after each item of a batch completes.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProgress {
+    /// Items completed so far, including failed items
+    pub items_processed: usize,
+    /// Total items in the batch
+    pub items_total: usize,
+    /// Chunks produced so far
+    pub chunks_emitted: usize,
+    /// The item that just completed
+    pub current_item_id: Uuid,
+    /// Time since the batch started
+    pub elapsed_ms: u64,
+}
+
+impl BatchProgress {
+    /// Percentage of items completed (0.0 - 100.0).
+    pub fn percentage(&self) -> f32 {
+        if self.items_total == 0 {
+            return 100.0;
+        }
+        self.items_processed as f32 / self.items_total as f32 * 100.0
+    }
+}
+
+/// Tracks batch progress and forwards updates to an optional channel.
+struct ProgressTracker<'a> {
+    tx: Option<&'a mpsc::Sender<BatchProgress>>,
+    started: Instant,
+    items_total: usize,
+    items_processed: usize,
+    chunks_emitted: usize,
+}
+
+impl<'a> ProgressTracker<'a> {
+    fn new(items_total: usize, tx: Option<&'a mpsc::Sender<BatchProgress>>) -> Self {
+        Self {
+            tx,
+            started: Instant::now(),
+            items_total,
+            items_processed: 0,
+            chunks_emitted: 0,
+        }
+    }
+
+    async fn item_done(&mut self, item_id: Uuid, chunks: usize) {
+        self.items_processed += 1;
+        self.chunks_emitted += chunks;
+
+        if let Some(tx) = self.tx {
+            let update = BatchProgress {
+                items_processed: self.items_processed,
+                items_total: self.items_total,
+                chunks_emitted: self.chunks_emitted,
+                current_item_id: item_id,
+                elapsed_ms: self.started.elapsed().as_millis() as u64,
+            };
+            // A dropped receiver only means nobody is watching
+            let _ = tx.send(update).await;
+        }
+    }
+}
+
 /// Batch processor for large-scale chunking operations.
+///
+/// Items are processed concurrently in spawned Tokio tasks, bounded by
+/// `BatchConfig::concurrency`. Chunks of a single item are always emitted
+/// together and in order; items themselves complete in any order.
+#[derive(Clone)]
 pub struct BatchProcessor {
     router: Arc<ChunkingRouter>,
     config: BatchConfig,
+    /// Bounds the number of in-flight item tasks
+    semaphore: Arc<Semaphore>,
+    /// `BatchConfig::filter`, with its `.gitignore` files loaded once
+    filter: Option<Arc<FileFilter>>,
 }
 
 impl BatchProcessor {
     /// Create a new batch processor.
     pub fn new(router: Arc<ChunkingRouter>, config: BatchConfig) -> Self {
-        Self { router, config }
+        let semaphore = Arc::new(Semaphore::new(config.concurrency.max(1)));
+        let filter = config.filter.clone().map(|filter| Arc::new(FileFilter::new(filter)));
+        Self { router, config, semaphore, filter }
     }
 
     /// Process a batch of items and return all chunks.
@@ -68,120 +715,710 @@ impl BatchProcessor {
         &self,
         items: Vec<SourceItem>,
         chunk_config: &ChunkConfig,
+    ) -> Result<(Vec<Chunk>, BatchResult)> {
+        self.collect_batch(items, chunk_config, None, &CancellationToken::new()).await
+    }
+
+    /// Process a batch of items, reporting progress after each item completes.
+    pub async fn process_batch_with_progress(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        progress_tx: mpsc::Sender<BatchProgress>,
+    ) -> Result<(Vec<Chunk>, BatchResult)> {
+        self.collect_batch(items, chunk_config, Some(&progress_tx), &CancellationToken::new()).await
+    }
+
+    /// Process a batch that stops early when `token` is cancelled.
+    ///
+    /// Items already running when the token fires are allowed to finish;
+    /// no new items are started. The returned `BatchResult` has
+    /// `was_cancelled` set and counts only the items that completed.
+    pub async fn process_batch_cancellable(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        token: CancellationToken,
+    ) -> Result<(Vec<Chunk>, BatchResult)> {
+        self.collect_batch(items, chunk_config, None, &token).await
+    }
+
+    /// Process a batch that can be resumed after the process is killed.
+    ///
+    /// Progress, including the chunks of completed items, is written to
+    /// `checkpoint_path` every `BatchConfig::checkpoint_interval` items. If a
+    /// checkpoint already exists, items it lists as completed are skipped
+    /// and their chunks restored from it, while items that failed are
+    /// retried. The returned chunks and `BatchResult` totals cover all runs.
+    /// The checkpoint is deleted once the batch completes.
+    pub async fn process_batch_resumable(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        checkpoint_path: &Path,
+    ) -> Result<(Vec<Chunk>, BatchResult)> {
+        let mut checkpoint = if checkpoint_path.exists() {
+            load_checkpoint(checkpoint_path)?
+        } else {
+            BatchCheckpoint::default()
+        };
+        checkpoint.result.total_items = items.len();
+
+        // Failed items get another attempt, so forget their earlier errors
+        let retried = std::mem::take(&mut checkpoint.failed_item_ids);
+        checkpoint.result.failed_items = checkpoint.result.failed_items.saturating_sub(retried.len());
+        checkpoint.result.errors.retain(|e| !retried.contains(&e.item_id));
+
+        let remaining: Vec<SourceItem> = items
+            .into_iter()
+            .filter(|item| !checkpoint.completed_item_ids.contains(&item.id))
+            .collect();
+
+        info!(
+            skipped = checkpoint.completed_item_ids.len(),
+            retried = retried.len(),
+            remaining = remaining.len(),
+            "Starting resumable batch processing"
+        );
+
+        let interval = self.config.checkpoint_interval.max(1);
+        let mut since_checkpoint = 0;
+        let mut results = self.spawn_items(remaining, chunk_config, &CancellationToken::new());
+
+        while let Some((item_id, result)) = results.next().await {
+            match result {
+                Ok(chunks) => {
+                    checkpoint.result.processed_items += 1;
+                    checkpoint.result.total_chunks += chunks.len();
+                    checkpoint.result.merged_chunks += merged_chunk_count(&chunks);
+                    checkpoint.completed_item_ids.insert(item_id);
+                    checkpoint.chunks.extend(chunks);
+                }
+                Err(e) => {
+                    checkpoint.failed_item_ids.insert(item_id);
+                    checkpoint.result.failed_items += 1;
+                    checkpoint.result.errors.push(BatchError {
+                        item_id,
+                        error: e.to_string(),
+                    });
+
+                    if !self.config.continue_on_error {
+                        save_checkpoint(checkpoint_path, &checkpoint)?;
+                        return Err(e);
+                    }
+
+                    warn!(item_id = %item_id, error = %e, "Failed to process item");
+                }
+            }
+
+            checkpoint.last_item_id = Some(item_id);
+            since_checkpoint += 1;
+
+            if since_checkpoint >= interval {
+                save_checkpoint(checkpoint_path, &checkpoint)?;
+                since_checkpoint = 0;
+            }
+        }
+
+        if checkpoint_path.exists() {
+            std::fs::remove_file(checkpoint_path)?;
+        }
+
+        let mut test_chunks = Vec::new();
+        let mut all_chunks = self.split_test_chunks(std::mem::take(&mut checkpoint.chunks), &mut test_chunks);
+        checkpoint.result.test_chunks = test_chunks;
+        if self.config.deterministic_order {
+            sort_chunks(&mut all_chunks);
+            checkpoint.result.sort_test_chunks();
+        }
+        Ok((all_chunks, checkpoint.result))
+    }
+
+    async fn collect_batch(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        progress_tx: Option<&mpsc::Sender<BatchProgress>>,
+        token: &CancellationToken,
     ) -> Result<(Vec<Chunk>, BatchResult)> {
         let total_items = items.len();
+        let mut progress = ProgressTracker::new(total_items, progress_tx);
         let mut all_chunks = Vec::new();
+        let mut test_chunks = Vec::new();
         let mut processed_items = 0;
         let mut failed_items = 0;
         let mut errors = Vec::new();
+        let mut was_cancelled = false;
+        let mut merged_chunks = 0;
 
-        info!(total_items, "Starting batch processing");
+        info!(total_items, concurrency = self.config.concurrency, "Starting batch processing");
 
-        for item in items {
-            match self.process_single_item(&item, chunk_config).await {
+        let mut order = self.config.deduplicate.then(|| InputOrder::new(&items));
+        let mut results = self.spawn_items(items, chunk_config, token);
+
+        while let Some((item_id, result)) = results.next().await {
+            match result {
+                // Items that never started; keep draining, since items
+                // already running still deliver their chunks
+                Err(e) if e.is::<Cancelled>() => {
+                    was_cancelled = true;
+                    if let Some(order) = order.as_mut() {
+                        all_chunks.extend(order.push(item_id, Vec::new()));
+                    }
+                }
                 Ok(chunks) => {
-                    all_chunks.extend(chunks);
+                    progress.item_done(item_id, chunks.len()).await;
+                    merged_chunks += merged_chunk_count(&chunks);
+                    let chunks = self.split_test_chunks(chunks, &mut test_chunks);
+                    match order.as_mut() {
+                        Some(order) => all_chunks.extend(order.push(item_id, chunks)),
+                        None => all_chunks.extend(chunks),
+                    }
                     processed_items += 1;
                 }
                 Err(e) => {
                     let error = BatchError {
-                        item_id: item.id,
+                        item_id,
                         error: e.to_string(),
                     };
                     errors.push(error);
                     failed_items += 1;
+                    progress.item_done(item_id, 0).await;
+                    if let Some(order) = order.as_mut() {
+                        all_chunks.extend(order.push(item_id, Vec::new()));
+                    }
 
                     if !self.config.continue_on_error {
                         return Err(e);
                     }
 
-                    warn!(item_id = %item.id, error = %e, "Failed to process item");
+                    warn!(item_id = %item_id, error = %e, "Failed to process item");
                 }
             }
         }
+        if let Some(order) = order {
+            all_chunks.extend(order.finish());
+        }
 
-        let result = BatchResult {
+        let mut duplicate_chunks_removed = 0;
+        if self.config.deduplicate {
+            let before = all_chunks.len();
+            all_chunks = Self::deduplicate_chunks(all_chunks);
+            duplicate_chunks_removed = before - all_chunks.len();
+        }
+        if self.config.deterministic_order {
+            sort_chunks(&mut all_chunks);
+        }
+
+        let mut result = BatchResult {
             total_items,
             processed_items,
             failed_items,
-            total_chunks: all_chunks.len(),
+            total_chunks: all_chunks.len() + test_chunks.len(),
             errors,
+            was_cancelled,
+            test_chunks,
+            duplicate_chunks_removed,
+            merged_chunks,
         };
 
         info!(
             processed = processed_items,
             failed = failed_items,
             chunks = result.total_chunks,
+            cancelled = was_cancelled,
             "Batch processing complete"
         );
 
+        if self.config.deterministic_order {
+            result.sort_test_chunks();
+        }
         Ok((all_chunks, result))
     }
 
     /// Process a batch with streaming output.
+    ///
+    /// Chunks are sent downstream as soon as `buffer_size` chunks have been
+    /// collected, while later items are still being processed. Fails with
+    /// `DeliveryStalled` if the receiver is dropped or stays full for
+    /// `backpressure_timeout_ms`, since the output would be incomplete.
     pub async fn process_batch_streaming(
         &self,
         items: Vec<SourceItem>,
         chunk_config: &ChunkConfig,
         sender: mpsc::Sender<Vec<Chunk>>,
+    ) -> Result<BatchResult> {
+        self.stream_batch(items, chunk_config, sender, None, &CancellationToken::new()).await
+    }
+
+    /// Process a batch with streaming output and per-item progress reporting.
+    pub async fn process_batch_streaming_with_progress(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        sender: mpsc::Sender<Vec<Chunk>>,
+        progress_tx: mpsc::Sender<BatchProgress>,
+    ) -> Result<BatchResult> {
+        self.stream_batch(items, chunk_config, sender, Some(&progress_tx), &CancellationToken::new())
+            .await
+    }
+
+    /// Process a batch with streaming output that stops when `token` is cancelled.
+    ///
+    /// As with `process_batch_cancellable`, items already running when the
+    /// token fires are allowed to finish and their chunks are still sent.
+    pub async fn process_batch_streaming_cancellable(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        sender: mpsc::Sender<Vec<Chunk>>,
+        token: CancellationToken,
+    ) -> Result<BatchResult> {
+        self.stream_batch(items, chunk_config, sender, None, &token).await
+    }
+
+    async fn stream_batch(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        sender: mpsc::Sender<Vec<Chunk>>,
+        progress_tx: Option<&mpsc::Sender<BatchProgress>>,
+        token: &CancellationToken,
     ) -> Result<BatchResult> {
         let total_items = items.len();
+        let mut progress = ProgressTracker::new(total_items, progress_tx);
         let mut processed_items = 0;
         let mut failed_items = 0;
         let mut total_chunks = 0;
         let mut errors = Vec::new();
+        let mut was_cancelled = false;
+        let mut test_chunks = Vec::new();
         let mut buffer = Vec::with_capacity(self.config.buffer_size);
+        let mut seen = HashSet::new();
+        let mut duplicate_chunks_removed = 0;
+        let mut merged_chunks = 0;
 
-        for item in items {
-            match self.process_single_item(&item, chunk_config).await {
+        let mut order = self.config.deduplicate.then(|| InputOrder::new(&items));
+        let mut results = self.spawn_items(items, chunk_config, token);
+
+        while let Some((item_id, result)) = results.next().await {
+            match result {
+                // Items that never started; keep draining, since items
+                // already running still deliver their chunks
+                Err(e) if e.is::<Cancelled>() => {
+                    was_cancelled = true;
+                    if let Some(order) = order.as_mut() {
+                        let ready = order.push(item_id, Vec::new());
+                        let before = ready.len();
+                        let ready = retain_unique(ready, &mut seen);
+                        duplicate_chunks_removed += before - ready.len();
+                        total_chunks += ready.len();
+                        buffer.extend(ready);
+                    }
+                }
                 Ok(chunks) => {
+                    progress.item_done(item_id, chunks.len()).await;
+                    merged_chunks += merged_chunk_count(&chunks);
+                    let mut chunks = self.split_test_chunks(chunks, &mut test_chunks);
+                    if let Some(order) = order.as_mut() {
+                        let ready = order.push(item_id, chunks);
+                        let before = ready.len();
+                        chunks = retain_unique(ready, &mut seen);
+                        duplicate_chunks_removed += before - chunks.len();
+                    }
                     total_chunks += chunks.len();
                     buffer.extend(chunks);
                     processed_items += 1;
 
-                    // Send when buffer is full
-                    if buffer.len() >= self.config.buffer_size {
-                        if sender.send(buffer.clone()).await.is_err() {
-                            warn!("Receiver dropped, stopping batch processing");
-                            break;
-                        }
-                        buffer.clear();
+                    // Send when buffer is full; waiting here stops new items
+                    // from being scheduled until the receiver catches up
+                    if buffer.len() >= self.config.buffer_size
+                        && !self.send_with_backpressure(&sender, std::mem::take(&mut buffer)).await
+                    {
+                        return Err(DeliveryStalled { processed_items, total_items }.into());
                     }
                 }
                 Err(e) => {
                     errors.push(BatchError {
-                        item_id: item.id,
+                        item_id,
                         error: e.to_string(),
                     });
                     failed_items += 1;
+                    progress.item_done(item_id, 0).await;
 
                     if !self.config.continue_on_error {
                         return Err(e);
                     }
+                    if let Some(order) = order.as_mut() {
+                        // Items after this one may now be due
+                        let ready = order.push(item_id, Vec::new());
+                        let before = ready.len();
+                        let ready = retain_unique(ready, &mut seen);
+                        duplicate_chunks_removed += before - ready.len();
+                        total_chunks += ready.len();
+                        buffer.extend(ready);
+                    }
+                }
+            }
+        }
+
+        if let Some(order) = order {
+            let pending = order.finish();
+            let before = pending.len();
+            let pending = retain_unique(pending, &mut seen);
+            duplicate_chunks_removed += before - pending.len();
+            total_chunks += pending.len();
+            buffer.extend(pending);
+        }
+
+        // Send remaining chunks
+        if !buffer.is_empty() && !self.send_with_backpressure(&sender, buffer).await {
+            return Err(DeliveryStalled { processed_items, total_items }.into());
+        }
+
+        Ok(BatchResult {
+            total_items,
+            processed_items,
+            failed_items,
+            total_chunks: total_chunks + test_chunks.len(),
+            errors,
+            was_cancelled,
+            test_chunks,
+            duplicate_chunks_removed,
+            merged_chunks,
+        })
+    }
+
+    /// Check a batch against the checks processing applies and the routing,
+    /// without chunking.
+    ///
+    /// Items are rejected exactly when processing would reject them (see
+    /// `check_item`). Each accepted item is estimated at
+    /// `ceil(tokens / chunk_size)` chunks, using the chunk size the router
+    /// configures for it. Routing decisions are not counted in the router
+    /// metrics or A/B stats.
+    pub fn dry_run(&self, items: &[SourceItem]) -> DryRunReport {
+        let mut report = DryRunReport::default();
+
+        for item in items {
+            report.total_content_bytes += item.content.len();
+            if let Err(reason) = self.check_item(item) {
+                report.rejected_items.push((item.id, reason));
+                continue;
+            }
+
+            let chunker = self.router.chunker_name(item, self.config.enable_content_routing);
+            *report.per_chunker_item_count.entry(chunker).or_default() += 1;
+            *report.per_kind_item_count.entry(item.source_kind).or_default() += 1;
+
+            let chunk_size = self.router.get_config(item).chunk_size.max(1);
+            report.estimated_total_chunks += count_tokens(&item.content).div_ceil(chunk_size);
+        }
+
+        debug!(
+            items = items.len(),
+            rejected = report.rejected_items.len(),
+            estimated_chunks = report.estimated_total_chunks,
+            "Dry run complete"
+        );
+        report
+    }
+
+    /// Check an item before chunking it: `SourceItem::validate`, then the
+    /// `BatchConfig::filter` rules if set. `Err` carries the reason.
+    fn check_item(&self, item: &SourceItem) -> std::result::Result<(), String> {
+        if let Err(errors) = item.validate() {
+            let reasons: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Err(reasons.join("; "));
+        }
+        match &self.filter {
+            Some(filter) => FileProcessor::is_content_processable(item, filter),
+            None => Ok(()),
+        }
+    }
+
+    /// Build the cross-file context of the code items in a batch.
+    ///
+    /// With `BatchConfig::repository_context_cache` set, a cached context
+    /// younger than `REPOSITORY_CONTEXT_MAX_AGE` is loaded instead, and a
+    /// freshly built one is saved for the next run. Cache errors are
+    /// logged and never fail the build.
+    pub fn build_repository_context(&self, items: &[SourceItem]) -> RepositoryContext {
+        let cache = self.config.repository_context_cache.as_deref();
+        if let Some(path) = cache.filter(|p| !RepositoryContext::is_stale(p, REPOSITORY_CONTEXT_MAX_AGE)) {
+            match RepositoryContext::load_from_file(path) {
+                Ok(context) => {
+                    debug!(path = %path.display(), files = context.files_processed, "Loaded repository context");
+                    return context;
+                }
+                Err(e) => warn!(path = %path.display(), error = %e, "Failed to load repository context"),
+            }
+        }
+
+        let files: Vec<(&str, &str, &str)> = items
+            .iter()
+            .filter(|item| item.source_kind == SourceKind::CodeRepo || item.content_type.starts_with("text/code:"))
+            .filter_map(|item| {
+                let path = item.extract_path()?;
+                let language = item.extract_language().unwrap_or_default();
+                Some((path, item.content.as_str(), language))
+            })
+            .collect();
+
+        let mut context = RepositoryContext::new();
+        for file in AstParser::parallel_parse_files(files) {
+            match file {
+                Ok(file) => context.register_parsed_file(file),
+                Err(e) => warn!(error = %e, "Skipping file in repository context"),
+            }
+        }
+
+        if let Some(path) = cache {
+            if let Err(e) = context.save_to_file(path) {
+                warn!(path = %path.display(), error = %e, "Failed to save repository context");
+            }
+        }
+        context
+    }
+
+    /// Drop chunks whose content repeats an earlier chunk, keeping the first.
+    pub fn deduplicate_chunks(chunks: Vec<Chunk>) -> Vec<Chunk> {
+        let before = chunks.len();
+        let unique = retain_unique(chunks, &mut HashSet::new());
+        debug!(duplicates = before - unique.len(), kept = unique.len(), "Deduplicated chunks");
+        unique
+    }
+
+    /// Move test chunks into `test_chunks` if configured, returning the rest.
+    fn split_test_chunks(&self, chunks: Vec<Chunk>, test_chunks: &mut Vec<Chunk>) -> Vec<Chunk> {
+        if !self.config.chunk_tests_separately {
+            return chunks;
+        }
+        let (tests, chunks): (Vec<Chunk>, Vec<Chunk>) =
+            chunks.into_iter().partition(|c| c.metadata.is_test);
+        test_chunks.extend(tests);
+        chunks
+    }
+
+    /// Create a bounded channel sized for `process_batch_streaming`.
+    ///
+    /// The channel holds at most `buffer_size` pending batches, so a slow
+    /// receiver applies backpressure to the processor.
+    pub fn channel(&self) -> (mpsc::Sender<Vec<Chunk>>, mpsc::Receiver<Vec<Chunk>>) {
+        mpsc::channel(self.config.buffer_size.max(1))
+    }
+
+    /// Rough upper bound on the memory needed to hold the items' content.
+    pub fn estimate_memory_usage(items: &[SourceItem]) -> usize {
+        items.iter().map(|item| item.content.len()).sum()
+    }
+
+    /// Send a batch downstream, waiting up to `backpressure_timeout_ms`.
+    ///
+    /// Returns `false` if the receiver was dropped or stayed full for the
+    /// whole timeout, in which case processing should stop.
+    async fn send_with_backpressure(&self, sender: &mpsc::Sender<Vec<Chunk>>, batch: Vec<Chunk>) -> bool {
+        let timeout = std::time::Duration::from_millis(self.config.backpressure_timeout_ms);
+
+        match tokio::time::timeout(timeout, sender.send(batch)).await {
+            Ok(Ok(())) => true,
+            Ok(Err(_)) => {
+                warn!("Receiver dropped, stopping batch processing");
+                false
+            }
+            Err(_) => {
+                warn!(
+                    timeout_ms = self.config.backpressure_timeout_ms,
+                    "Receiver stalled, stopping batch processing"
+                );
+                false
+            }
+        }
+    }
+
+    /// Spawn a chunking task per item and yield results as they complete.
+    ///
+    /// Items that have not started when `token` is cancelled fail with
+    /// `Cancelled` instead of being processed.
+    fn spawn_items(
+        &self,
+        items: Vec<SourceItem>,
+        chunk_config: &ChunkConfig,
+        token: &CancellationToken,
+    ) -> impl Stream<Item = (Uuid, Result<Vec<Chunk>>)> + Send + 'static {
+        let processor = self.clone();
+        let chunk_config = chunk_config.clone();
+        let token = token.clone();
+
+        self.run_concurrently(items, move |item| {
+            let processor = processor.clone();
+            let chunk_config = chunk_config.clone();
+            let token = token.clone();
+            async move {
+                if token.is_cancelled() {
+                    return Err(Cancelled.into());
+                }
+                processor
+                    .retry_with_backoff(item.id, || processor.process_single_item(&item, &chunk_config))
+                    .await
+            }
+        })
+    }
+
+    /// Run `operation`, retrying errors wrapped in `RetryableError`.
+    ///
+    /// Errors are passed through `RetryableError::wrap_transient` first, so
+    /// transient I/O failures are retried without being wrapped by hand.
+    ///
+    /// Attempt `n` (0-indexed) is followed by a delay of
+    /// `retry_base_delay_ms * 2^n` before the next try.
+    async fn retry_with_backoff<F, Fut, T>(&self, item_id: Uuid, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt: u32 = 0;
+
+        loop {
+            match operation().await.map_err(RetryableError::wrap_transient) {
+                Ok(value) => return Ok(value),
+                Err(e) if RetryableError::is_retryable(&e) && attempt < self.config.max_retries => {
+                    let delay_ms = self
+                        .config
+                        .retry_base_delay_ms
+                        .saturating_mul(2u64.saturating_pow(attempt));
+                    attempt += 1;
+                    warn!(
+                        item_id = %item_id,
+                        attempt,
+                        max_retries = self.config.max_retries,
+                        delay_ms,
+                        error = %e,
+                        "Retrying item after transient error"
+                    );
+                    tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
                 }
+                Err(e) => return Err(e),
             }
         }
+    }
 
-        // Send remaining chunks
-        if !buffer.is_empty() {
-            let _ = sender.send(buffer).await;
+    /// Run `worker` over `items` in spawned tasks, at most `concurrency` at a time.
+    fn run_concurrently<F, Fut>(
+        &self,
+        mut items: Vec<SourceItem>,
+        worker: F,
+    ) -> impl Stream<Item = (Uuid, Result<Vec<Chunk>>)> + Send + 'static
+    where
+        F: Fn(SourceItem) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<Chunk>>> + Send + 'static,
+    {
+        if self.config.respect_priority {
+            // Stable, so equal priorities keep their input order
+            items.sort_by_key(|item| std::cmp::Reverse(item.priority));
         }
+        let semaphore = Arc::clone(&self.semaphore);
 
-        Ok(BatchResult {
-            total_items,
-            processed_items,
-            failed_items,
-            total_chunks,
-            errors,
-        })
+        stream::iter(items)
+            .map(move |item| {
+                let semaphore = Arc::clone(&semaphore);
+                let worker = worker.clone();
+                async move {
+                    let item_id = item.id;
+                    let handle = tokio::spawn(async move {
+                        let _permit = semaphore
+                            .acquire_owned()
+                            .await
+                            .map_err(|e| anyhow!("Batch semaphore closed: {}", e))?;
+                        worker(item).await
+                    });
+
+                    let result = handle
+                        .await
+                        .unwrap_or_else(|e| Err(anyhow!("Chunking task failed: {}", e)));
+                    (item_id, result)
+                }
+            })
+            .buffer_unordered(self.config.concurrency.max(1))
     }
 
     /// Process a single item, splitting large content if necessary.
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            item.source_kind = %item.source_kind,
+            item.content_size = item.content.len(),
+            chunk.count = tracing::field::Empty,
+        )
+    )]
     async fn process_single_item(
         &self,
         item: &SourceItem,
         config: &ChunkConfig,
     ) -> Result<Vec<Chunk>> {
+        let pre_processed;
+        let item = match &self.config.pre_processor {
+            Some(pre_processor) => {
+                let mut owned = item.clone();
+                pre_processor.process(&mut owned)?;
+                pre_processed = owned;
+                &pre_processed
+            }
+            None => item,
+        };
+        self.check_item(item).map_err(|reason| anyhow!("Item {} rejected: {}", item.id, reason))?;
+
+        let cache_key = match (&self.config.cache, item.extract_path()) {
+            (Some(cache), Some(path)) => Some((cache, path, item_content_hash(item))),
+            _ => None,
+        };
+
+        if let Some((cache, path, hash)) = &cache_key {
+            let cached = cache
+                .lock()
+                .map_err(|_| anyhow!("File hash cache lock poisoned"))?
+                .get(None, hash, config)
+                .cloned();
+
+            if let Some(mut chunks) = cached {
+                debug!(item_id = %item.id, path, "File unchanged, using cached chunks");
+                reuse_cached_chunks(&mut chunks, item, path);
+                if config.use_deterministic_ids {
+                    assign_content_hash_ids(&mut chunks);
+                }
+                return Ok(chunks);
+            }
+        }
+
+        let started = Instant::now();
+        let (chunker_name, result) = self.chunk_item(item, config).await;
+        metrics::record_item_processed(chunker_name, result.is_ok(), started.elapsed());
+        let mut chunks = result?;
+        if let Some(post_processor) = &self.config.post_processor {
+            post_processor.process(&mut chunks)?;
+        }
+        metrics::record_chunk_sizes(&chunks);
+        if config.use_deterministic_ids {
+            assign_content_hash_ids(&mut chunks);
+        }
+
+        if let Some((cache, path, hash)) = cache_key {
+            cache
+                .lock()
+                .map_err(|_| anyhow!("File hash cache lock poisoned"))?
+                .insert(None, path, hash, config, chunks.clone());
+        }
+
+        tracing::Span::current().record("chunk.count", chunks.len());
+        Ok(chunks)
+    }
+
+    /// Route and chunk a single item.
+    ///
+    /// Uses the router's chunk stream so chunkers that produce chunks
+    /// incrementally are not forced to materialise them up front. Returns
+    /// the name of the chunker that ran with the result.
+    async fn chunk_item(&self, item: &SourceItem, config: &ChunkConfig) -> (&'static str, Result<Vec<Chunk>>) {
         // Check if content is too large and needs pre-splitting
         if item.content.len() > self.config.max_content_size {
             debug!(
@@ -189,10 +1426,10 @@ impl BatchProcessor {
                 content_size = item.content.len(),
                 "Content exceeds max size, pre-splitting"
             );
-            return self.process_large_item(item, config);
+            let chunker = self.router.get_chunker_with(item, self.config.enable_content_routing);
+            return (chunker.name(), self.process_large_item(item, chunker.as_ref(), config));
         }
 
-        let chunker = self.router.get_chunker(item);
         let item_config = self.router.get_config(item);
 
         // Merge configs
@@ -202,14 +1439,19 @@ impl BatchProcessor {
             min_chars_per_sentence: config.min_chars_per_sentence,
             preserve_whitespace: config.preserve_whitespace,
             language: item_config.language.or(config.language.clone()),
+            use_deterministic_ids: config.use_deterministic_ids,
+            token_count_mode: config.token_count_mode,
+            language_size_overrides: merge_language_sizes(item_config.language_size_overrides, config),
         };
 
-        chunker.chunk(item, &merged_config)
+        let (name, stream) = self
+            .router
+            .route_stream_named(item, &merged_config, self.config.enable_content_routing);
+        (name, stream.try_collect().await)
     }
 
     /// Process a large item by splitting it first.
-    fn process_large_item(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
-        let chunker = self.router.get_chunker(item);
+    fn process_large_item(&self, item: &SourceItem, chunker: &dyn Chunker, config: &ChunkConfig) -> Result<Vec<Chunk>> {
         let item_config = self.router.get_config(item);
 
         // For large items, we split content into manageable pieces first
@@ -231,6 +1473,7 @@ impl BatchProcessor {
                 content: piece.content.clone(),
                 metadata: item.metadata.clone(),
                 created_at: item.created_at,
+                priority: item.priority,
             };
 
             let merged_config = ChunkConfig {
@@ -239,6 +1482,9 @@ impl BatchProcessor {
                 min_chars_per_sentence: config.min_chars_per_sentence,
                 preserve_whitespace: config.preserve_whitespace,
                 language: item_config.language.clone().or(config.language.clone()),
+                use_deterministic_ids: config.use_deterministic_ids,
+                token_count_mode: config.token_count_mode,
+                language_size_overrides: merge_language_sizes(item_config.language_size_overrides.clone(), config),
             };
 
             match chunker.chunk(&sub_item, &merged_config) {
@@ -253,6 +1499,7 @@ impl BatchProcessor {
                     all_chunks.extend(chunks);
                 }
                 Err(e) => {
+                    self.router.metrics().record_error(chunker.name());
                     warn!(
                         item_id = %item.id,
                         piece_idx,
@@ -314,26 +1561,55 @@ fn split_large_content(content: &str, max_size: usize) -> Vec<ContentPiece> {
     pieces
 }
 
+/// Priority of entry-point files such as `main.rs` or `index.ts`.
+pub const ENTRY_POINT_PRIORITY: u8 = 192;
+
+/// Check if a path names an entry-point file (`main.*`, `index.*`,
+/// `lib.*` or `__init__.py`).
+fn is_entry_point(path: &str) -> bool {
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    if file_name == "__init__.py" {
+        return true;
+    }
+    matches!(file_name.split_once('.'), Some(("main" | "index" | "lib", ext)) if !ext.is_empty())
+}
+
 /// Create `SourceItem` objects from repository file entries.
+///
+/// See `FileEntry::to_source_item`.
 pub fn files_to_source_items(
     files: Vec<FileEntry>,
     source_id: Uuid,
 ) -> Vec<SourceItem> {
-    files
-        .into_iter()
-        .map(|file| SourceItem {
-            id: Uuid::new_v4(),
-            source_id,
-            source_kind: SourceKind::CodeRepo,
-            content_type: format!("text/code:{}", file.language.as_deref().unwrap_or("text")),
-            content: file.content,
-            metadata: serde_json::json!({
-                "path": file.path,
-                "language": file.language,
-            }),
-            created_at: None,
-        })
-        .collect()
+    files.iter().map(|file| file.to_source_item(source_id)).collect()
+}
+
+/// Language sizes from the router's item config, overridden by those of
+/// the batch's config.
+fn merge_language_sizes(mut item_sizes: HashMap<String, usize>, config: &ChunkConfig) -> HashMap<String, usize> {
+    item_sizes.extend(config.language_size_overrides.iter().map(|(language, size)| (language.clone(), *size)));
+    item_sizes
+}
+
+/// Content hash of an item for `FileHashCache` lookups.
+///
+/// Always computed from the content: a `content_hash` in the item's
+/// metadata comes from the caller, and trusting it would let one file's
+/// chunks be served for different content.
+pub(crate) fn item_content_hash(item: &SourceItem) -> String {
+    FileProcessor::fingerprint_hex(item.content.as_bytes())
+}
+
+/// Point chunks taken from a `FileHashCache` at the item being processed,
+/// which may be a copy of the cached file under another path.
+pub(crate) fn reuse_cached_chunks(chunks: &mut [Chunk], item: &SourceItem, path: &str) {
+    for chunk in chunks {
+        chunk.source_item_id = item.id;
+        chunk.source_id = item.source_id;
+        if chunk.metadata.path.is_some() {
+            chunk.metadata.path = Some(path.to_string());
+        }
+    }
 }
 
 /// A file entry for batch processing.
@@ -342,44 +1618,68 @@ pub struct FileEntry {
     pub path: String,
     pub content: String,
     pub language: Option<String>,
+    /// Hex SHA-256 of `content`, set by `with_content_hash`
+    pub content_hash: Option<String>,
+}
+
+impl FileEntry {
+    /// Compute `content_hash`, e.g. to compare files between runs.
+    ///
+    /// The hash is informational: `FileHashCache` lookups always hash the
+    /// content themselves.
+    pub fn with_content_hash(mut self) -> Self {
+        self.content_hash = Some(FileProcessor::fingerprint_hex(self.content.as_bytes()));
+        self
+    }
+
+    /// Convert to a `SourceItem` of a code repository.
+    ///
+    /// The content type comes from `content_type_for` (`text/code:rust`,
+    /// `text/markdown`, ...). Entry-point files get `ENTRY_POINT_PRIORITY`,
+    /// everything else the default priority. The `content_hash`, if
+    /// computed, is kept in the item metadata for callers; it is not used by
+    /// `FileHashCache` lookups, which rehash the content.
+    pub fn to_source_item(&self, source_id: Uuid) -> SourceItem {
+        let priority = if is_entry_point(&self.path) {
+            ENTRY_POINT_PRIORITY
+        } else {
+            SourceItem::DEFAULT_PRIORITY
+        };
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id,
+            source_kind: SourceKind::CodeRepo,
+            content_type: content_type_for(self.language.as_deref()),
+            content: self.content.clone(),
+            metadata: serde_json::json!({
+                "path": self.path,
+                "language": self.language,
+                "content_hash": self.content_hash,
+            }),
+            created_at: None,
+            priority,
+        }
+    }
+
+    /// Convert to a `SourceItem` and chunk it with the chunker a default
+    /// `ChunkingRouter` selects.
+    pub fn to_source_items_with_chunks(&self, source_id: Uuid, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let item = self.to_source_item(source_id);
+        ChunkingRouter::default().get_chunker(&item).chunk(&item, config)
+    }
+}
+
+impl From<FileEntry> for SourceItem {
+    /// Convert with a new random source ID.
+    fn from(file: FileEntry) -> Self {
+        file.to_source_item(Uuid::new_v4())
+    }
 }
 
 /// Detect programming language from file extension.
 pub fn detect_language(path: &str) -> Option<String> {
     let ext = path.rsplit('.').next()?;
-    
-    let lang = match ext.to_lowercase().as_str() {
-        "rs" => "rust",
-        "py" => "python",
-        "js" => "javascript",
-        "jsx" => "javascript",
-        "ts" => "typescript",
-        "tsx" => "typescript",
-        "go" => "go",
-        "java" => "java",
-        "c" => "c",
-        "cpp" | "cc" | "cxx" => "cpp",
-        "h" | "hpp" => "c",
-        "rb" => "ruby",
-        "php" => "php",
-        "swift" => "swift",
-        "kt" | "kts" => "kotlin",
-        "scala" => "scala",
-        "cs" => "csharp",
-        "md" | "markdown" => "markdown",
-        "json" => "json",
-        "yaml" | "yml" => "yaml",
-        "toml" => "toml",
-        "xml" => "xml",
-        "html" | "htm" => "html",
-        "css" => "css",
-        "sql" => "sql",
-        "sh" | "bash" => "bash",
-        "ps1" => "powershell",
-        _ => return None,
-    };
-
-    Some(lang.to_string())
+    language_from_extension(ext).map(String::from)
 }
 
 #[cfg(test)]
@@ -398,6 +1698,703 @@ mod tests {
         }
     }
 
+    fn create_item(content: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::Document,
+            content_type: "text/plain".to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_items_run_concurrently() {
+        let config = BatchConfig {
+            concurrency: 4,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items: Vec<SourceItem> = (0..4).map(|i| create_item(&format!("item {}", i))).collect();
+
+        let start = std::time::Instant::now();
+        let results: Vec<_> = processor
+            .run_concurrently(items, |_item| async {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                Ok(Vec::new())
+            })
+            .collect()
+            .await;
+        let elapsed = start.elapsed();
+
+        assert_eq!(results.len(), 4);
+        // Sequential execution would take at least 40ms
+        assert!(elapsed < std::time::Duration::from_millis(35), "took {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_chunks_stay_ordered_per_item() {
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), BatchConfig::default());
+        let paragraph = "This sentence is long enough to count as one full sentence. ";
+        let items: Vec<SourceItem> = (0..8).map(|_| create_item(&paragraph.repeat(40))).collect();
+
+        let (chunks, result) = processor
+            .process_batch(items, &ChunkConfig::with_size(64))
+            .await
+            .unwrap();
+
+        assert_eq!(result.processed_items, 8);
+        // Each item's chunks are contiguous and in their original order
+        for window in chunks.windows(2) {
+            if window[0].source_item_id == window[1].source_item_id {
+                assert_eq!(window[1].chunk_index, window[0].chunk_index + 1);
+            } else {
+                assert_eq!(window[1].chunk_index, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_repository_context_cache() {
+        let mut item = create_item("pub struct Config {\n    path: String,\n}\n\npub fn load() -> Config {\n    todo!()\n}\n");
+        item.source_kind = SourceKind::CodeRepo;
+        item.content_type = "text/code:rust".to_string();
+        item.metadata = serde_json::json!({"path": "src/config.rs"});
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = BatchConfig {
+            repository_context_cache: Some(dir.path().join("context.msgpack.gz")),
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+
+        let built = processor.build_repository_context(std::slice::from_ref(&item));
+        assert_eq!(built.files_processed, 1);
+        assert_eq!(built.find_symbol_locations("Config"), vec!["src/config.rs"]);
+
+        // A fresh cache is used as is, whatever the items
+        let cached = processor.build_repository_context(&[]);
+        assert_eq!(cached.files_processed, 1);
+        assert_eq!(cached.find_symbol_locations("load"), vec!["src/config.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_deduplicate_identical_items() {
+        let content = "Vendored license header, copied into every file of the project.";
+        let items = vec![create_item(content), create_item(content), create_item("Something else entirely.")];
+
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), BatchConfig::default());
+        let (chunks, result) = processor.process_batch(items.clone(), &ChunkConfig::default()).await.unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(result.duplicate_chunks_removed, 0);
+
+        let config = BatchConfig {
+            deduplicate: true,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let (chunks, result) = processor.process_batch(items.clone(), &ChunkConfig::default()).await.unwrap();
+        assert_eq!(chunks.len(), 2);
+        // Only the copy from the first item is kept
+        let copies: Vec<_> = chunks.iter().filter(|c| c.source_item_id != items[2].id).collect();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].source_item_id, items[0].id);
+        assert_eq!(result.total_chunks, 2);
+        assert_eq!(result.duplicate_chunks_removed, 1);
+
+        let (tx, mut rx) = mpsc::channel(10);
+        let result = processor.process_batch_streaming(items.clone(), &ChunkConfig::default(), tx).await.unwrap();
+        let mut streamed = Vec::new();
+        while let Some(batch) = rx.recv().await {
+            streamed.extend(batch);
+        }
+        assert_eq!(streamed.len(), 2);
+        assert_eq!(streamed[0].source_item_id, items[0].id);
+        assert_eq!(result.duplicate_chunks_removed, 1);
+    }
+
+    #[test]
+    fn test_input_order_releases_results_in_order() {
+        let items = vec![create_item("first"), create_item("second"), create_item("third")];
+        let chunks = |item: &SourceItem| vec![Chunk::new(item.id, item.source_id, item.source_kind, item.content.clone(), 1, 0, 5, 0)];
+        let mut order = InputOrder::new(&items);
+
+        // The third item finishing first waits for the others
+        assert!(order.push(items[2].id, chunks(&items[2])).is_empty());
+        // A failed first item releases nothing of its own
+        assert!(order.push(items[0].id, Vec::new()).is_empty());
+        let ready = order.push(items[1].id, chunks(&items[1]));
+        let contents: Vec<_> = ready.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(contents, ["second", "third"]);
+        assert!(order.finish().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_order_across_runs() {
+        let config = BatchConfig {
+            concurrency: 4,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let paragraph = "This sentence is long enough to count as one full sentence. ";
+        // Varying sizes make items finish in a different order than they start
+        let items: Vec<SourceItem> = (0..12)
+            .map(|i| create_item(&paragraph.repeat(10 + (i * 7) % 30)))
+            .collect();
+
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let (chunks, _) = processor
+                .process_batch(items.clone(), &ChunkConfig::with_size(64))
+                .await
+                .unwrap();
+            let order: Vec<_> = chunks.iter().map(|c| (c.source_item_id, c.chunk_index)).collect();
+            runs.push(order);
+        }
+
+        assert_eq!(runs[0], runs[1]);
+        assert!(runs[0].windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_sort_test_chunks_is_stable() {
+        let item = Uuid::new_v4();
+        let chunk = |content: &str, start: usize| {
+            Chunk::new(item, Uuid::nil(), SourceKind::CodeRepo, content.to_string(), 1, start, start + 1, 0)
+        };
+        let mut result = BatchResult {
+            test_chunks: vec![chunk("second", 10), chunk("parent", 0), chunk("child", 0)],
+            ..Default::default()
+        };
+
+        let sorted: Vec<_> = result.sorted_test_chunks().map(|c| c.content.clone()).collect();
+        assert_eq!(sorted, vec!["parent", "child", "second"]);
+
+        result.sort_test_chunks();
+        let contents: Vec<_> = result.test_chunks.iter().map(|c| c.content.clone()).collect();
+        assert_eq!(contents, sorted);
+    }
+
+    #[tokio::test]
+    async fn test_test_chunks_returned_separately() {
+        let config = BatchConfig {
+            chunk_tests_separately: true,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+
+        let code_item = |path: &str, content: &str| {
+            let mut item = create_item(content);
+            item.source_kind = SourceKind::CodeRepo;
+            item.content_type = "text/code:python".to_string();
+            item.metadata = serde_json::json!({"path": path});
+            item
+        };
+        let items = vec![
+            code_item("src/math.py", "def add(a, b):\n    return a + b\n"),
+            code_item("tests/test_math.py", "def test_add():\n    assert add(1, 2) == 3\n"),
+        ];
+
+        let (chunks, result) = processor.process_batch(items, &ChunkConfig::default()).await.unwrap();
+
+        assert_eq!(result.total_chunks, 2);
+        assert_eq!(chunks.len(), 1);
+        assert!(!chunks[0].metadata.is_test);
+        assert_eq!(result.test_chunks.len(), 1);
+        assert!(result.test_chunks[0].content.contains("test_add"));
+    }
+
+    #[tokio::test]
+    async fn test_progress_reporting() {
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), BatchConfig::default());
+        let items: Vec<SourceItem> = (0..5).map(|i| create_item(&format!("Item number {}.", i))).collect();
+        let (tx, mut rx) = mpsc::channel(16);
+
+        let (chunks, _) = processor
+            .process_batch_with_progress(items, &ChunkConfig::default(), tx)
+            .await
+            .unwrap();
+
+        let mut updates = Vec::new();
+        while let Some(update) = rx.recv().await {
+            updates.push(update);
+        }
+
+        assert_eq!(updates.len(), 5);
+        assert_eq!(updates.last().unwrap().items_processed, 5);
+        assert_eq!(updates.last().unwrap().chunks_emitted, chunks.len());
+        assert_eq!(updates.last().unwrap().percentage(), 100.0);
+        assert!(updates.windows(2).all(|w| w[0].items_processed < w[1].items_processed));
+    }
+
+    #[tokio::test]
+    async fn test_file_hash_cache_hit() {
+        let cache = Arc::new(Mutex::new(FileHashCache::new()));
+        let config = BatchConfig {
+            cache: Some(Arc::clone(&cache)),
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+
+        let mut item = create_item("fn main() {\n    println!(\"hello\");\n}\n");
+        item.metadata = serde_json::json!({"path": "src/main.rs"});
+
+        let (first, _) = processor
+            .process_batch(vec![item.clone()], &ChunkConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(cache.lock().unwrap().len(), 1);
+
+        item.id = Uuid::new_v4();
+        let (second, _) = processor
+            .process_batch(vec![item.clone()], &ChunkConfig::default())
+            .await
+            .unwrap();
+
+        // Chunk ids are random, so identical ids prove the cache was used
+        assert_eq!(first.len(), second.len());
+        assert_eq!(first[0].id, second[0].id);
+        assert_eq!(second[0].source_item_id, item.id);
+
+        // A different chunk config misses the cache
+        let (resized, _) = processor
+            .process_batch(vec![item.clone()], &ChunkConfig::with_size(64))
+            .await
+            .unwrap();
+        assert_ne!(first[0].id, resized[0].id);
+
+        // Changed content misses the cache
+        item.content.push_str("// changed\n");
+        let (third, _) = processor
+            .process_batch(vec![item], &ChunkConfig::default())
+            .await
+            .unwrap();
+        assert_ne!(first[0].id, third[0].id);
+    }
+
+    #[test]
+    fn test_file_hash_cache_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+
+        let chunk = Chunk::new(Uuid::new_v4(), Uuid::new_v4(), SourceKind::CodeRepo, "x".to_string(), 1, 0, 1, 0);
+        let mut cache = FileHashCache::new();
+        let hash = FileProcessor::fingerprint_hex(b"x");
+        let config = ChunkConfig::default();
+        cache.insert(None, "a.rs", hash.clone(), &config, vec![chunk.clone()]);
+        cache.save_to_file(&path).unwrap();
+
+        let mut loaded = FileHashCache::load_from_file(&path).unwrap();
+        assert_eq!(loaded.get(None, &hash, &config).unwrap()[0].id, chunk.id);
+        assert!(loaded.get(None, "stale", &config).is_none());
+        assert!(loaded.get(None, &hash, &config.clone().with_overlap(0)).is_none());
+    }
+
+    #[test]
+    fn test_file_hash_cache_scoping_and_bounds() {
+        let config = ChunkConfig::default();
+        let hash = |content: &str| FileProcessor::fingerprint_hex(content.as_bytes());
+
+        // A caller-supplied hash is ignored
+        let mut item = create_item("abc");
+        item.metadata = serde_json::json!({"path": "src/lib.rs", "content_hash": hash("other")});
+        assert_eq!(item_content_hash(&item), hash("abc"));
+
+        // Entries are private to their owner
+        let mut cache = FileHashCache::new().with_capacity(2);
+        cache.insert(Some("alice"), "src/lib.rs", hash("abc"), &config, Vec::new());
+        assert!(cache.get(Some("alice"), &hash("abc"), &config).is_some());
+        assert!(cache.get(Some("bob"), &hash("abc"), &config).is_none());
+        assert!(cache.get(None, &hash("abc"), &config).is_none());
+        assert_eq!(cache.find_by_hash(Some("alice"), &hash("abc")).unwrap().0, "src/lib.rs");
+        assert!(cache.find_by_hash(Some("bob"), &hash("abc")).is_none());
+
+        // The least recently used entry is evicted once full
+        cache.insert(None, "b.rs", hash("b"), &config, Vec::new());
+        cache.get(Some("alice"), &hash("abc"), &config);
+        cache.insert(None, "c.rs", hash("c"), &config, Vec::new());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.find_by_hash(None, &hash("b")).is_none());
+        assert!(cache.find_by_hash(Some("alice"), &hash("abc")).is_some());
+
+        // Expired entries are not used
+        let mut cache = FileHashCache::new().with_ttl(Duration::ZERO);
+        cache.insert(None, "a.rs", hash("a"), &config, Vec::new());
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.find_by_hash(None, &hash("a")).is_none());
+        assert!(cache.get(None, &hash("a"), &config).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retryable_errors_are_retried() {
+        let config = BatchConfig {
+            max_retries: 2,
+            retry_base_delay_ms: 1,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result = processor
+            .retry_with_backoff(Uuid::new_v4(), || async {
+                if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                    Err(RetryableError::new(anyhow!("connection reset")).into())
+                } else {
+                    Ok(42)
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // Exhausted retries surface the last error
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result: Result<()> = processor
+            .retry_with_backoff(Uuid::new_v4(), || async {
+                attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Err(RetryableError::new(anyhow!("timeout")).into())
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_transient_io_errors_are_retried() {
+        struct FlakyRead(std::sync::atomic::AtomicU32);
+
+        impl PreProcessor for FlakyRead {
+            fn process(&self, _item: &mut SourceItem) -> Result<()> {
+                if self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "read timed out").into());
+                }
+                Ok(())
+            }
+        }
+
+        let config = BatchConfig {
+            retry_base_delay_ms: 1,
+            pre_processor: Some(Arc::new(FlakyRead(std::sync::atomic::AtomicU32::new(0)))),
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+
+        let (chunks, result) = processor
+            .process_batch(vec![create_item("Some content.")], &ChunkConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(result.failed_items, 0);
+        assert_eq!(chunks.len(), 1);
+
+        let error = RetryableError::wrap_transient(
+            std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset").into(),
+        );
+        let source = std::error::Error::source(error.downcast_ref::<RetryableError>().unwrap()).unwrap();
+        assert!(source.is::<std::io::Error>());
+    }
+
+    #[tokio::test]
+    async fn test_logic_errors_are_not_retried() {
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), BatchConfig::default());
+
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result: Result<()> = processor
+            .retry_with_backoff(Uuid::new_v4(), || async {
+                attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Err(anyhow!("unsupported language"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_bounds_buffering() {
+        let config = BatchConfig {
+            buffer_size: 2,
+            concurrency: 2,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items: Vec<SourceItem> = (0..30).map(|i| create_item(&format!("Item number {}.", i))).collect();
+        let (tx, mut rx) = processor.channel();
+
+        let consumer = tokio::spawn(async move {
+            let mut max_pending = 0;
+            let mut received = 0;
+            while let Some(batch) = rx.recv().await {
+                max_pending = max_pending.max(rx.len());
+                received += batch.len();
+                tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+            }
+            (max_pending, received)
+        });
+
+        let result = processor
+            .process_batch_streaming(items, &ChunkConfig::default(), tx)
+            .await
+            .unwrap();
+        let (max_pending, received) = consumer.await.unwrap();
+
+        assert_eq!(received, result.total_chunks);
+        assert!(max_pending <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_consumer_times_out() {
+        let config = BatchConfig {
+            buffer_size: 1,
+            backpressure_timeout_ms: 20,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items: Vec<SourceItem> = (0..10).map(|i| create_item(&format!("Item number {}.", i))).collect();
+        // Keep the receiver alive but never read from it
+        let (tx, _rx) = processor.channel();
+
+        let error = processor
+            .process_batch_streaming(items, &ChunkConfig::default(), tx)
+            .await
+            .unwrap_err();
+
+        let stalled = error.downcast_ref::<DeliveryStalled>().unwrap();
+        assert!(stalled.processed_items < 10);
+        assert_eq!(stalled.total_items, 10);
+        assert_eq!(BatchProcessor::estimate_memory_usage(&[create_item("abcd")]), 4);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint_path = dir.path().join("batch.checkpoint");
+        let items: Vec<SourceItem> = (0..6).map(|i| create_item(&format!("Item number {}.", i))).collect();
+        let config = BatchConfig {
+            checkpoint_interval: 1,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let (earlier_chunks, _) = processor
+            .process_batch(items[..3].to_vec(), &ChunkConfig::default())
+            .await
+            .unwrap();
+
+        // Simulate a run that was killed after the first three items, one
+        // more having failed
+        let mut checkpoint = BatchCheckpoint::default();
+        for item in &items[..3] {
+            checkpoint.completed_item_ids.insert(item.id);
+        }
+        checkpoint.chunks = earlier_chunks.clone();
+        checkpoint.failed_item_ids.insert(items[3].id);
+        checkpoint.last_item_id = Some(items[3].id);
+        checkpoint.result.processed_items = 3;
+        checkpoint.result.failed_items = 1;
+        checkpoint.result.total_chunks = 3;
+        checkpoint.result.errors.push(BatchError {
+            item_id: items[3].id,
+            error: "connection reset".to_string(),
+        });
+        save_checkpoint(&checkpoint_path, &checkpoint).unwrap();
+
+        let loaded = load_checkpoint(&checkpoint_path).unwrap();
+        assert_eq!(loaded.last_item_id, Some(items[3].id));
+
+        let (chunks, result) = processor
+            .process_batch_resumable(items.clone(), &ChunkConfig::default(), &checkpoint_path)
+            .await
+            .unwrap();
+
+        // Chunks from the earlier run are restored, not chunked again, and
+        // the failed item was retried
+        assert_eq!(chunks.len(), 6);
+        assert!(earlier_chunks.iter().all(|e| chunks.iter().any(|c| c.id == e.id)));
+        assert!(chunks.iter().any(|c| c.source_item_id == items[3].id));
+        assert_eq!(result.processed_items, 6);
+        assert_eq!(result.failed_items, 0);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.total_chunks, 6);
+        assert!(!checkpoint_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_first_item() {
+        let config = BatchConfig {
+            concurrency: 1,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items: Vec<SourceItem> = (0..5).map(|i| create_item(&format!("Item number {}.", i))).collect();
+
+        let token = CancellationToken::new();
+        let (progress_tx, mut progress_rx) = mpsc::channel(1);
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            if progress_rx.recv().await.is_some() {
+                canceller.cancel();
+            }
+        });
+
+        let (chunks, result) = processor
+            .collect_batch(items, &ChunkConfig::default(), Some(&progress_tx), &token)
+            .await
+            .unwrap();
+
+        assert!(result.was_cancelled);
+        assert_eq!(result.processed_items, 1);
+        assert_eq!(chunks.len(), 1);
+    }
+
+    /// Cancels `token` once the first three items are all running, and
+    /// holds them until it has. The third item then takes long enough for
+    /// later items to be skipped before it finishes.
+    struct CancelMidBatch {
+        token: CancellationToken,
+        started: std::sync::Barrier,
+        cancelled: std::sync::Barrier,
+    }
+
+    impl CancelMidBatch {
+        fn new(token: &CancellationToken) -> Self {
+            Self {
+                token: token.clone(),
+                started: std::sync::Barrier::new(3),
+                cancelled: std::sync::Barrier::new(3),
+            }
+        }
+    }
+
+    impl PreProcessor for CancelMidBatch {
+        fn process(&self, item: &mut SourceItem) -> Result<()> {
+            if !["Item number 0.", "Item number 1.", "Item number 2."].contains(&item.content.as_str()) {
+                return Ok(());
+            }
+            self.started.wait();
+            if item.content == "Item number 0." {
+                self.token.cancel();
+            }
+            self.cancelled.wait();
+            if item.content == "Item number 2." {
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_cancel_drains_running_items() {
+        let token = CancellationToken::new();
+        let config = BatchConfig {
+            concurrency: 3,
+            pre_processor: Some(Arc::new(CancelMidBatch::new(&token))),
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items: Vec<SourceItem> = (0..8).map(|i| create_item(&format!("Item number {}.", i))).collect();
+
+        let (chunks, result) = processor
+            .process_batch_cancellable(items, &ChunkConfig::default(), token)
+            .await
+            .unwrap();
+
+        assert!(result.was_cancelled);
+        assert_eq!(result.processed_items, 3);
+        let mut contents: Vec<_> = chunks.iter().map(|c| c.content.trim()).collect();
+        contents.sort();
+        assert_eq!(contents, ["Item number 0.", "Item number 1.", "Item number 2."]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_cancel_drains_running_items_when_streaming() {
+        let token = CancellationToken::new();
+        let config = BatchConfig {
+            concurrency: 3,
+            pre_processor: Some(Arc::new(CancelMidBatch::new(&token))),
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items: Vec<SourceItem> = (0..8).map(|i| create_item(&format!("Item number {}.", i))).collect();
+        let (tx, mut rx) = processor.channel();
+
+        let result = processor
+            .process_batch_streaming_cancellable(items, &ChunkConfig::default(), tx, token)
+            .await
+            .unwrap();
+
+        assert!(result.was_cancelled);
+        assert_eq!(result.processed_items, 3);
+        let mut streamed = 0;
+        while let Some(chunks) = rx.recv().await {
+            streamed += chunks.len();
+        }
+        assert_eq!(streamed, 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_token_stops_streaming() {
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), BatchConfig::default());
+        let items: Vec<SourceItem> = (0..5).map(|i| create_item(&format!("Item number {}.", i))).collect();
+        let (tx, mut rx) = processor.channel();
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = processor
+            .process_batch_streaming_cancellable(items, &ChunkConfig::default(), tx, token)
+            .await
+            .unwrap();
+
+        assert!(result.was_cancelled);
+        assert_eq!(result.processed_items, 0);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_respect_priority() {
+        let config = BatchConfig {
+            concurrency: 1,
+            respect_priority: true,
+            deterministic_order: false,
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items = vec![
+            create_item("low").with_priority(10),
+            create_item("high").with_priority(250),
+            create_item("default"),
+        ];
+
+        let (chunks, _) = processor
+            .process_batch(items, &ChunkConfig::with_size(64))
+            .await
+            .unwrap();
+
+        let order: Vec<_> = chunks.iter().map(|c| c.content.trim()).collect();
+        assert_eq!(order, vec!["high", "default", "low"]);
+    }
+
+    #[test]
+    fn test_entry_points_get_higher_priority() {
+        let files = ["src/main.rs", "web/index.ts", "pkg/__init__.py", "src/lib.rs", "src/util.rs", "domain.rs"]
+            .into_iter()
+            .map(|path| FileEntry {
+                path: path.to_string(),
+                content: "x".to_string(),
+                language: None,
+                content_hash: None,
+            })
+            .collect();
+
+        let priorities: Vec<_> = files_to_source_items(files, Uuid::new_v4())
+            .iter()
+            .map(|item| item.priority)
+            .collect();
+        assert_eq!(priorities[..4], [ENTRY_POINT_PRIORITY; 4]);
+        assert_eq!(priorities[4..], [SourceItem::DEFAULT_PRIORITY; 2]);
+    }
+
     #[test]
     fn test_detect_language() {
         assert_eq!(detect_language("main.rs"), Some("rust".to_string()));
@@ -405,4 +2402,77 @@ mod tests {
         assert_eq!(detect_language("index.tsx"), Some("typescript".to_string()));
         assert_eq!(detect_language("unknown.xyz"), None);
     }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_routing_and_rejections() {
+        let config = BatchConfig {
+            filter: Some(FilterConfig::default()),
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        processor.router.set_ab_route(SourceKind::Document, "document", "sentence", 0.5).unwrap();
+
+        let mut code = create_item("fn main() {\n    println!(\"hello\");\n}\n");
+        code.source_kind = SourceKind::CodeRepo;
+        code.content_type = "text/code:rust".to_string();
+        code.metadata = serde_json::json!({"path": "src/main.rs"});
+        let mut document = create_item(&"Plenty of words in this document. ".repeat(40));
+        document.metadata = serde_json::json!({"chunk_size": 20});
+        let mut vendored = create_item("module.exports = {};");
+        vendored.metadata = serde_json::json!({"path": "web/node_modules/lib/index.js"});
+        let blank = create_item("  \n");
+        let empty = create_item("");
+        let items = vec![code.clone(), document.clone(), vendored.clone(), blank.clone(), empty.clone()];
+
+        let report = processor.dry_run(&items);
+        let document_chunker = processor.router.chunker_name(&document, true);
+        assert_eq!(report.per_chunker_item_count, HashMap::from([("code", 1), (document_chunker, 1)]));
+        assert_eq!(report.per_kind_item_count, HashMap::from([(SourceKind::CodeRepo, 1), (SourceKind::Document, 1)]));
+        assert_eq!(
+            report.rejected_items,
+            vec![
+                (vendored.id, "in excluded directory \"node_modules\"".to_string()),
+                (blank.id, "empty content".to_string()),
+                (empty.id, "content is empty".to_string()),
+            ]
+        );
+        assert_eq!(report.estimated_total_chunks, 1 + count_tokens(&document.content).div_ceil(20));
+        assert_eq!(report.total_content_bytes, items.iter().map(|item| item.content.len()).sum::<usize>());
+        assert_eq!(processor.router.metrics().routed("code"), 0);
+        assert!(processor.router.ab_stats().is_empty());
+
+        // Processing rejects the same items
+        let (_, result) = processor.process_batch(items, &ChunkConfig::default()).await.unwrap();
+        let failed: HashSet<Uuid> = result.errors.iter().map(|e| e.item_id).collect();
+        assert_eq!(failed, HashSet::from([vendored.id, blank.id, empty.id]));
+    }
+
+    struct Uppercase;
+
+    impl PreProcessor for Uppercase {
+        fn process(&self, item: &mut SourceItem) -> Result<()> {
+            item.content = item.content.to_uppercase();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pre_and_post_processors() {
+        let config = BatchConfig {
+            pre_processor: Some(Arc::new(Uppercase)),
+            post_processor: Some(Arc::new(RedactionProcessor::new(vec![
+                Regex::new(r"[A-Z0-9._]+@[A-Z0-9.]+").unwrap(),
+            ]))),
+            ..Default::default()
+        };
+        let processor = BatchProcessor::new(Arc::new(ChunkingRouter::default()), config);
+        let items = vec![create_item("Contact jane.doe@example.com about the rollout.")];
+
+        let (chunks, result) = processor.process_batch(items, &ChunkConfig::default()).await.unwrap();
+        assert_eq!(result.failed_items, 0);
+        let content: String = chunks.iter().map(|c| c.content.as_str()).collect();
+        // The uppercase pattern only matches because pre-processing ran first
+        assert!(content.contains("CONTACT [REDACTED] ABOUT THE ROLLOUT."), "{}", content);
+        assert!(!content.contains("EXAMPLE.COM"));
+    }
 }
@@ -9,11 +9,16 @@
 //! - **Adaptive sizing**: Adjusts chunk sizes based on content complexity
 
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
 
 use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+use uuid::Uuid;
 
 use super::base::{count_tokens, Chunker};
-use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem, SourceKind};
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
 
 /// Agentic chunker that uses intelligent heuristics for optimal chunking.
 ///
@@ -30,11 +35,21 @@ pub struct AgenticChunker {
     /// Maximum chunk size before forcing a split
     max_chunk_tokens: usize,
     /// Minimum chunk size (to avoid tiny fragments)
+    #[allow(dead_code)]
     min_chunk_tokens: usize,
     /// Enable smart boundary detection
+    #[allow(dead_code)]
     smart_boundaries: bool,
     /// Enable context injection for code chunks
     inject_context: bool,
+    /// User-defined boundary patterns and their strengths, checked first
+    custom_boundaries: Vec<(Regex, f32)>,
+    /// Per-language boundary strength overrides, keyed by lowercase language
+    language_weights: HashMap<String, HashMap<BoundaryType, f32>>,
+    /// Number of splitting passes, from coarse sections down to chunk size
+    passes: usize,
+    /// Boundary patterns to use instead of those for the item's language
+    classifier: Option<LanguageAwareBoundaryClassifier>,
 }
 
 impl AgenticChunker {
@@ -46,7 +61,13 @@ impl AgenticChunker {
             min_chunk_tokens: 50,
             smart_boundaries: true,
             inject_context: true,
+            custom_boundaries: Vec::new(),
+            language_weights: HashMap::new(),
+            passes: 1,
+            classifier: None,
         }
+        // Python has no braces, so a `def` is the clearest place to split
+        .with_language_weights("python", HashMap::from([(BoundaryType::FunctionDef, 0.95)]))
     }
 
     /// Builder: set context overlap.
@@ -61,8 +82,51 @@ impl AgenticChunker {
         self
     }
 
+    /// Builder: add domain-specific boundaries.
+    ///
+    /// Each line is matched against the patterns in order before the
+    /// built-in rules; the first match becomes a `BoundaryType::Custom`
+    /// boundary with the pattern's strength.
+    pub fn with_custom_boundaries(mut self, patterns: Vec<(Regex, f32)>) -> Self {
+        self.custom_boundaries.extend(patterns);
+        self
+    }
+
+    /// Builder: split hierarchical content in several passes.
+    ///
+    /// Each pass before the last splits only at strong boundaries into
+    /// large sections: the pass before the last keeps boundaries of
+    /// strength 0.8 or more with a budget of 4 × `chunk_size`, and every
+    /// earlier pass raises the threshold by 0.05 and the budget by another
+    /// 4×. Sections still over `chunk_size` are split again by the next
+    /// pass, and their pieces share a virtual parent chunk id.
+    pub fn with_multi_pass(mut self, passes: usize) -> Self {
+        self.passes = passes.max(1);
+        self
+    }
+
+    /// Builder: detect boundaries with the patterns for `language`.
+    ///
+    /// Without this, the patterns follow each item's language, falling
+    /// back to language-agnostic ones.
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.classifier = Some(LanguageAwareBoundaryClassifier::for_language(language));
+        self
+    }
+
+    /// Builder: override boundary strengths for a language.
+    ///
+    /// Weights are merged into any already set for the language.
+    pub fn with_language_weights(mut self, language: &str, type_weights: HashMap<BoundaryType, f32>) -> Self {
+        self.language_weights
+            .entry(language.to_lowercase())
+            .or_default()
+            .extend(type_weights);
+        self
+    }
+
     /// Analyze content and determine optimal chunking strategy.
-    fn analyze_content(&self, content: &str) -> ContentAnalysis {
+    fn analyze_content(&self, content: &str, language: Option<&str>) -> ContentAnalysis {
         let lines: Vec<&str> = content.lines().collect();
         let total_tokens = count_tokens(content);
         
@@ -89,7 +153,7 @@ impl AgenticChunker {
             has_imports,
             avg_line_length,
             nesting_depth,
-            semantic_boundaries: self.find_semantic_boundaries(content),
+            semantic_boundaries: self.find_semantic_boundaries(content, language),
         }
     }
 
@@ -115,16 +179,23 @@ impl AgenticChunker {
     }
 
     /// Find semantic boundaries in content.
-    fn find_semantic_boundaries(&self, content: &str) -> Vec<SemanticBoundary> {
+    fn find_semantic_boundaries(&self, content: &str, language: Option<&str>) -> Vec<SemanticBoundary> {
         let mut boundaries = Vec::new();
         let mut current_byte = 0;
+        let weights = language.and_then(|language| self.language_weights.get(&language.to_lowercase()));
+        let classifier = self
+            .classifier
+            .unwrap_or_else(|| language.map_or_else(Default::default, LanguageAwareBoundaryClassifier::for_language));
+        let mut state = ClassifierState::default();
         
         for (line_num, line) in content.lines().enumerate() {
             let line_len = line.len() + 1; // +1 for newline
             
             // Check for various boundary types
-            if let Some(boundary_type) = self.classify_line(line) {
-                let strength = self.boundary_strength(line, &boundary_type);
+            if let Some(boundary_type) = self.classify_line(line, classifier, &mut state) {
+                let strength = weights
+                    .and_then(|weights| weights.get(&boundary_type).copied())
+                    .unwrap_or_else(|| self.boundary_strength(line, &boundary_type));
                 boundaries.push(SemanticBoundary {
                     line_number: line_num,
                     byte_offset: current_byte,
@@ -140,54 +211,20 @@ impl AgenticChunker {
     }
 
     /// Classify a line to determine if it's a semantic boundary.
-    fn classify_line(&self, line: &str) -> Option<BoundaryType> {
-        let trimmed = line.trim();
-        
-        // Empty lines are potential boundaries
-        if trimmed.is_empty() {
-            return Some(BoundaryType::EmptyLine);
-        }
-        
-        // Heading-style boundaries
-        if trimmed.starts_with('#') {
-            return Some(BoundaryType::Heading);
-        }
-        
-        // Code structure boundaries
-        if trimmed.starts_with("fn ") || trimmed.starts_with("pub fn ") ||
-           trimmed.starts_with("async fn ") || trimmed.starts_with("pub async fn ") {
-            return Some(BoundaryType::FunctionDef);
-        }
-        
-        if trimmed.starts_with("impl ") || trimmed.starts_with("pub impl ") {
-            return Some(BoundaryType::ImplBlock);
-        }
-        
-        if trimmed.starts_with("struct ") || trimmed.starts_with("pub struct ") ||
-           trimmed.starts_with("enum ") || trimmed.starts_with("pub enum ") {
-            return Some(BoundaryType::TypeDef);
-        }
-        
-        if trimmed.starts_with("class ") || trimmed.starts_with("interface ") {
-            return Some(BoundaryType::ClassDef);
-        }
-        
-        if trimmed.starts_with("def ") || trimmed.starts_with("async def ") {
-            return Some(BoundaryType::FunctionDef);
-        }
-        
-        // Module boundaries
-        if trimmed.starts_with("mod ") || trimmed.starts_with("pub mod ") {
-            return Some(BoundaryType::ModuleDef);
-        }
-        
-        // Comment blocks
-        if trimmed.starts_with("///") || trimmed.starts_with("//!") ||
-           trimmed.starts_with("/**") || trimmed.starts_with("/*") {
-            return Some(BoundaryType::DocComment);
+    ///
+    /// `state` carries what the classifier has seen of earlier lines, such
+    /// as an unterminated comment or string.
+    fn classify_line(
+        &self,
+        line: &str,
+        classifier: LanguageAwareBoundaryClassifier,
+        state: &mut ClassifierState,
+    ) -> Option<BoundaryType> {
+        let language_boundary = classifier.classify(line, state);
+        if let Some((_, strength)) = self.custom_boundaries.iter().find(|(pattern, _)| pattern.is_match(line)) {
+            return Some(BoundaryType::Custom(*strength));
         }
-        
-        None
+        language_boundary
     }
 
     /// Calculate boundary strength (higher = stronger boundary).
@@ -206,7 +243,55 @@ impl AgenticChunker {
             BoundaryType::ModuleDef => 0.95,
             BoundaryType::DocComment => 0.3,
             BoundaryType::EmptyLine => 0.2,
+            BoundaryType::Custom(strength) => *strength,
+        }
+    }
+
+    /// Score how cleanly a chunk is bounded, from 0.0 to 1.0.
+    ///
+    /// `boundaries` must be relative to `content`. The score adds up:
+    /// - 0.2 × the share of non-empty lines that are strong boundaries
+    ///   (strength ≥ 0.5)
+    /// - 0.4 if the first non-empty line is a strong boundary
+    /// - 0.4 if the chunk ends on `.`, `?`, `!`, `{` or `}` rather than
+    ///   mid-sentence
+    pub(crate) fn score_chunk(content: &str, boundaries: &[SemanticBoundary]) -> f32 {
+        const STRONG: f32 = 0.5;
+
+        let lines: Vec<(usize, &str)> = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .collect();
+        let Some(&(first_line, _)) = lines.first() else {
+            return 0.0;
+        };
+
+        let is_strong = |line: usize| boundaries.iter().any(|b| b.line_number == line && b.strength >= STRONG);
+        let strong_lines = lines.iter().filter(|(line, _)| is_strong(*line)).count();
+        let mut score = 0.2 * strong_lines as f32 / lines.len() as f32;
+
+        if is_strong(first_line) {
+            score += 0.4;
         }
+
+        let ends_cleanly = content.trim_end().ends_with(['.', '?', '!', '{', '}']);
+        if ends_cleanly {
+            score += 0.4;
+        }
+
+        score.clamp(0.0, 1.0)
+    }
+
+    /// The strongest boundary on the first non-empty line of `content`.
+    ///
+    /// `boundaries` must be relative to `content`.
+    fn opening_boundary<'a>(content: &str, boundaries: &'a [SemanticBoundary]) -> Option<&'a SemanticBoundary> {
+        let first_line = content.lines().position(|line| !line.trim().is_empty())?;
+        boundaries
+            .iter()
+            .filter(|b| b.line_number == first_line)
+            .max_by(|a, b| a.strength.partial_cmp(&b.strength).unwrap_or(std::cmp::Ordering::Equal))
     }
 
     /// Split content at semantic boundaries.
@@ -246,23 +331,20 @@ impl AgenticChunker {
         }
 
         let mut last_split = 0;
-        let mut current_end_line = 0;
 
         for (line_idx, line) in lines.iter().enumerate() {
             let line_tokens = count_tokens(line);
             current_tokens += line_tokens;
-            current_end_line = line_idx;
 
             // Check if we should split here
             if current_tokens >= config.chunk_size {
-                // Find best boundary near here
-                let split_line = self.find_best_boundary(
-                    &sorted_boundaries,
-                    last_split,
-                    line_idx,
-                );
-
-                let split_byte = line_byte_offsets.get(split_line + 1).copied().unwrap_or(content.len());
+                // A boundary line opens the next chunk, so split just before
+                // the strongest one, or after this line if there is none
+                let split_line = self
+                    .find_best_boundary(&sorted_boundaries, last_split + 1, line_idx)
+                    .unwrap_or(line_idx + 1);
+
+                let split_byte = line_byte_offsets.get(split_line).copied().unwrap_or(content.len()).min(content.len());
                 
                 // Create chunk
                 let chunk_content = &content[current_start..split_byte];
@@ -278,8 +360,8 @@ impl AgenticChunker {
                 }
 
                 current_start = split_byte;
-                current_tokens = 0;
-                last_split = split_line + 1;
+                current_tokens = lines[split_line..=line_idx].iter().map(|l| count_tokens(l)).sum();
+                last_split = split_line;
             }
         }
 
@@ -306,20 +388,60 @@ impl AgenticChunker {
         candidates
     }
 
+    /// Split content over `passes` passes, pairing each piece with the byte
+    /// range of the section it was split from, if that section was split.
+    fn split_multi_pass(
+        &self,
+        content: &str,
+        language: Option<&str>,
+        config: &ChunkConfig,
+        passes: usize,
+    ) -> Vec<(ChunkCandidate, Option<Range<usize>>)> {
+        let mut analysis = self.analyze_content(content, language);
+        if passes <= 1 || analysis.total_tokens <= config.chunk_size {
+            return self
+                .split_at_boundaries(content, &analysis, config)
+                .into_iter()
+                .map(|candidate| (candidate, None))
+                .collect();
+        }
+
+        // Coarser passes keep only stronger boundaries and allow larger sections
+        let level = passes - 1;
+        let threshold = (0.8 + 0.05 * (level - 1) as f32).min(0.95);
+        analysis.semantic_boundaries.retain(|b| b.strength >= threshold);
+        let mut coarse_config = config.clone();
+        coarse_config.chunk_size = config.chunk_size.saturating_mul(4usize.saturating_pow(level as u32));
+
+        let mut pieces = Vec::new();
+        for section in self.split_at_boundaries(content, &analysis, &coarse_config) {
+            let sub_pieces = self.split_multi_pass(&section.content, language, config, passes - 1);
+            let offset = section.start_byte;
+            let parent = (sub_pieces.len() > 1).then_some(section.start_byte..section.end_byte);
+
+            for (mut piece, sub_parent) in sub_pieces {
+                piece.start_byte += offset;
+                piece.end_byte += offset;
+                let sub_parent = sub_parent.map(|range| range.start + offset..range.end + offset);
+                pieces.push((piece, sub_parent.or(parent.clone())));
+            }
+        }
+        pieces
+    }
+
     /// Find the best boundary line to split at.
     fn find_best_boundary(
         &self,
         boundaries: &[SemanticBoundary],
         start_line: usize,
         end_line: usize,
-    ) -> usize {
+    ) -> Option<usize> {
         // Find strongest boundary in range
-        let candidate = boundaries
+        boundaries
             .iter()
             .filter(|b| b.line_number >= start_line && b.line_number <= end_line)
-            .max_by(|a, b| a.strength.partial_cmp(&b.strength).unwrap_or(std::cmp::Ordering::Equal));
-
-        candidate.map(|b| b.line_number).unwrap_or(end_line)
+            .max_by(|a, b| a.strength.partial_cmp(&b.strength).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|b| b.line_number)
     }
 
     /// Inject context information into chunk candidates.
@@ -379,15 +501,15 @@ impl Chunker for AgenticChunker {
             return Ok(vec![]);
         }
 
-        // Analyze content
-        let analysis = self.analyze_content(content);
-
-        // Split at semantic boundaries
-        let candidates = self.split_at_boundaries(content, &analysis, config);
+        // Analyze content and split at semantic boundaries
+        let language = config.language.as_deref().or_else(|| item.extract_language());
+        let candidates = self.split_multi_pass(content, language, config, self.passes);
 
-        // Convert candidates to chunks
+        // Convert candidates to chunks. A split section's virtual parent ID is
+        // derived from its first piece, as `assign_content_hash_ids` does.
         let mut chunks = Vec::new();
-        for (idx, candidate) in candidates.iter().enumerate() {
+        let mut parents: HashMap<Range<usize>, Uuid> = HashMap::new();
+        for (idx, (candidate, section)) in candidates.iter().enumerate() {
             // Prepend context if available
             let final_content = if let Some(ctx) = &candidate.context_before {
                 format!("// Context:\n{}\n\n{}", ctx, candidate.content)
@@ -409,12 +531,23 @@ impl Chunker for AgenticChunker {
             );
 
             // Add metadata
+            let boundaries = self.find_semantic_boundaries(&candidate.content, language);
+            let opening = Self::opening_boundary(&candidate.content, &boundaries);
             chunk.metadata = ChunkMetadata {
                 content_type: Some("agentic".to_string()),
                 path: item.extract_path().map(String::from),
                 language: config.language.clone(),
+                coherence_score: Some(Self::score_chunk(&candidate.content, &boundaries)),
+                boundary_type: opening.map(|b| b.boundary_type.as_str().to_string()),
+                boundary_strength: opening.map(|b| b.strength),
                 ..Default::default()
             };
+            chunk.parent_chunk_id = section.clone().map(|section| {
+                let child = chunk.id;
+                *parents
+                    .entry(section)
+                    .or_insert_with(|| Uuid::new_v5(&Uuid::NAMESPACE_OID, child.as_bytes()))
+            });
 
             chunks.push(chunk);
         }
@@ -425,6 +558,7 @@ impl Chunker for AgenticChunker {
 
 /// Analysis of content characteristics.
 #[derive(Debug)]
+#[allow(dead_code)]
 struct ContentAnalysis {
     total_tokens: usize,
     total_lines: usize,
@@ -438,7 +572,8 @@ struct ContentAnalysis {
 
 /// A semantic boundary in the content.
 #[derive(Debug, Clone)]
-struct SemanticBoundary {
+#[allow(dead_code)]
+pub(crate) struct SemanticBoundary {
     line_number: usize,
     byte_offset: usize,
     boundary_type: BoundaryType,
@@ -446,8 +581,8 @@ struct SemanticBoundary {
 }
 
 /// Types of semantic boundaries.
-#[derive(Debug, Clone, PartialEq)]
-enum BoundaryType {
+#[derive(Debug, Clone, Copy)]
+pub enum BoundaryType {
     EmptyLine,
     Heading,
     FunctionDef,
@@ -456,6 +591,347 @@ enum BoundaryType {
     ImplBlock,
     ModuleDef,
     DocComment,
+    /// A line matching a user-defined pattern, with its strength
+    Custom(f32),
+}
+
+impl BoundaryType {
+    /// Snake-case name of the boundary type, as recorded in chunk metadata.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BoundaryType::EmptyLine => "empty_line",
+            BoundaryType::Heading => "heading",
+            BoundaryType::FunctionDef => "function_def",
+            BoundaryType::ClassDef => "class_def",
+            BoundaryType::TypeDef => "type_def",
+            BoundaryType::ImplBlock => "impl_block",
+            BoundaryType::ModuleDef => "module_def",
+            BoundaryType::DocComment => "doc_comment",
+            BoundaryType::Custom(_) => "custom",
+        }
+    }
+}
+
+// Custom strengths are compared bitwise so boundary types can key weight maps
+impl PartialEq for BoundaryType {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (BoundaryType::Custom(a), BoundaryType::Custom(b)) => a.to_bits() == b.to_bits(),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for BoundaryType {}
+
+impl Hash for BoundaryType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let BoundaryType::Custom(strength) = self {
+            strength.to_bits().hash(state);
+        }
+    }
+}
+
+/// Boundary patterns for one language, chosen with
+/// `AgenticChunker::with_language` or from an item's language.
+///
+/// The language-specific classifiers only report boundaries on lines that
+/// start outside comments and string literals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LanguageAwareBoundaryClassifier {
+    /// Patterns shared by all languages (`fn `, `class `, `def `, ...)
+    #[default]
+    Universal,
+    /// Rust items; `#[...]` attributes are not headings
+    Rust,
+    /// Top-level functions and classes, and methods; functions nested in
+    /// function bodies are not boundaries
+    Python,
+    /// `function` declarations, but not `function` expressions
+    JavaScript,
+}
+
+impl LanguageAwareBoundaryClassifier {
+    /// Classifier for a language name, `Universal` if it has none.
+    pub fn for_language(language: &str) -> Self {
+        match language.to_lowercase().as_str() {
+            "rust" | "rs" => Self::Rust,
+            "python" | "py" => Self::Python,
+            "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => Self::JavaScript,
+            _ => Self::Universal,
+        }
+    }
+
+    fn classify(self, line: &str, state: &mut ClassifierState) -> Option<BoundaryType> {
+        let starts_in_code = !state.in_block_comment && state.string_end.is_none();
+        match self {
+            Self::Universal => return classify_universal(line.trim()),
+            Self::Rust => scan_c_like_line(line, state, true),
+            Self::JavaScript => scan_c_like_line(line, state, false),
+            Self::Python => scan_python_line(line, state),
+        }
+        if !starts_in_code {
+            return None;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return Some(BoundaryType::EmptyLine);
+        }
+        match self {
+            Self::Rust => classify_rust(trimmed),
+            Self::Python => classify_python(line, trimmed, &mut state.python_blocks),
+            Self::JavaScript => classify_javascript(trimmed),
+            Self::Universal => None,
+        }
+    }
+}
+
+/// What a classifier has seen of the lines before the current one.
+#[derive(Debug, Default)]
+struct ClassifierState {
+    /// Inside a `/* */` comment
+    in_block_comment: bool,
+    /// Inside a string literal spanning lines, closed by this delimiter
+    string_end: Option<&'static str>,
+    /// Python: indentation of the enclosing `def`/`class` blocks, and
+    /// whether each is a class
+    python_blocks: Vec<(usize, bool)>,
+}
+
+lazy_static! {
+    static ref RUST_FN_RE: Regex =
+        Regex::new(r#"^(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern(?:\s+"[^"]*")?)\s+)*fn\s"#).unwrap();
+    static ref RUST_TYPE_RE: Regex =
+        Regex::new(r"^(?:pub(?:\([^)]*\))?\s+)?(?:unsafe\s+)?(?:struct|enum|union|trait|type)\s").unwrap();
+    static ref RUST_IMPL_RE: Regex = Regex::new(r"^(?:unsafe\s+)?impl\b").unwrap();
+    static ref RUST_MOD_RE: Regex = Regex::new(r"^(?:pub(?:\([^)]*\))?\s+)?mod\s").unwrap();
+    static ref JS_FUNCTION_RE: Regex =
+        Regex::new(r"^(?:export\s+(?:default\s+)?)?(?:async\s+)?function\b").unwrap();
+    static ref JS_CLASS_RE: Regex =
+        Regex::new(r"^(?:export\s+(?:default\s+)?)?(?:abstract\s+)?(?:class|interface)\s").unwrap();
+}
+
+fn classify_rust(trimmed: &str) -> Option<BoundaryType> {
+    if RUST_FN_RE.is_match(trimmed) {
+        Some(BoundaryType::FunctionDef)
+    } else if RUST_IMPL_RE.is_match(trimmed) {
+        Some(BoundaryType::ImplBlock)
+    } else if RUST_TYPE_RE.is_match(trimmed) {
+        Some(BoundaryType::TypeDef)
+    } else if RUST_MOD_RE.is_match(trimmed) {
+        Some(BoundaryType::ModuleDef)
+    } else if ["///", "//!", "/*"].iter().any(|p| trimmed.starts_with(p)) {
+        Some(BoundaryType::DocComment)
+    } else {
+        None
+    }
+}
+
+fn classify_javascript(trimmed: &str) -> Option<BoundaryType> {
+    // A line starting with `function` is a declaration; expressions follow
+    // `=`, `(`, `:` or `return`
+    if JS_FUNCTION_RE.is_match(trimmed) {
+        Some(BoundaryType::FunctionDef)
+    } else if JS_CLASS_RE.is_match(trimmed) {
+        Some(BoundaryType::ClassDef)
+    } else if trimmed.starts_with("/**") {
+        Some(BoundaryType::DocComment)
+    } else {
+        None
+    }
+}
+
+fn classify_python(line: &str, trimmed: &str, blocks: &mut Vec<(usize, bool)>) -> Option<BoundaryType> {
+    if trimmed.starts_with('#') || trimmed.starts_with('@') {
+        return None;
+    }
+    let indent = line.len() - line.trim_start().len();
+    while blocks.last().is_some_and(|(block_indent, _)| *block_indent >= indent) {
+        blocks.pop();
+    }
+    let in_function = blocks.iter().any(|(_, is_class)| !is_class);
+
+    let boundary = if trimmed.starts_with("def ") || trimmed.starts_with("async def ") {
+        blocks.push((indent, false));
+        BoundaryType::FunctionDef
+    } else if trimmed.starts_with("class ") {
+        blocks.push((indent, true));
+        BoundaryType::ClassDef
+    } else {
+        return None;
+    };
+    (!in_function).then_some(boundary)
+}
+
+/// Track comments and strings spanning lines in Rust or JavaScript.
+fn scan_c_like_line(line: &str, state: &mut ClassifierState, rust: bool) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = state.string_end {
+            match find_string_end(&bytes[i..], end) {
+                Some(len) => {
+                    i += len;
+                    state.string_end = None;
+                }
+                None => return,
+            }
+            continue;
+        }
+        if state.in_block_comment {
+            match bytes[i..].windows(2).position(|w| w == b"*/") {
+                Some(pos) => {
+                    i += pos + 2;
+                    state.in_block_comment = false;
+                }
+                None => return,
+            }
+            continue;
+        }
+
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => return,
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                state.in_block_comment = true;
+                i += 2;
+            }
+            b'"' => {
+                state.string_end = Some("\"");
+                i += 1;
+            }
+            b'`' if !rust => {
+                state.string_end = Some("`");
+                i += 1;
+            }
+            b'\'' if !rust => {
+                state.string_end = Some("'");
+                i += 1;
+            }
+            // Rust char literals, told apart from lifetimes
+            b'\'' => {
+                i += match (bytes.get(i + 1), bytes.get(i + 2)) {
+                    (Some(b'\\'), _) => bytes[i + 2..].iter().position(|&b| b == b'\'').map_or(1, |pos| pos + 3),
+                    (Some(_), Some(b'\'')) => 3,
+                    _ => 1,
+                };
+            }
+            b'r' if rust && (i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_')) => {
+                let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                    state.string_end = Some(RAW_STRING_ENDS[hashes.min(RAW_STRING_ENDS.len() - 1)]);
+                    i += hashes + 2;
+                } else {
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Closing delimiters of Rust raw strings by number of `#`s.
+const RAW_STRING_ENDS: [&str; 4] = ["\"", "\"#", "\"##", "\"###"];
+
+/// Bytes up to and including `end`, skipping backslash escapes in
+/// ordinary string literals.
+fn find_string_end(bytes: &[u8], end: &str) -> Option<usize> {
+    let end = end.as_bytes();
+    if end.len() > 1 {
+        return bytes.windows(end.len()).position(|w| w == end).map(|pos| pos + end.len());
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == end[0] => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Track triple-quoted strings spanning lines in Python.
+fn scan_python_line(line: &str, state: &mut ClassifierState) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = state.string_end {
+            match bytes[i..].windows(3).position(|w| w == end.as_bytes()) {
+                Some(pos) => {
+                    i += pos + 3;
+                    state.string_end = None;
+                }
+                None => return,
+            }
+            continue;
+        }
+
+        match bytes[i] {
+            b'#' => return,
+            quote @ (b'"' | b'\'') => {
+                if bytes[i..].starts_with(&[quote; 3]) {
+                    state.string_end = Some(if quote == b'"' { "\"\"\"" } else { "\'\'\'" });
+                    i += 3;
+                } else {
+                    // Single-quoted strings end on the same line
+                    i += 1 + find_string_end(&bytes[i + 1..], if quote == b'"' { "\"" } else { "'" })
+                        .unwrap_or(bytes.len() - i - 1);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Language-agnostic boundary patterns for a trimmed line.
+fn classify_universal(trimmed: &str) -> Option<BoundaryType> {
+    // Empty lines are potential boundaries
+    if trimmed.is_empty() {
+        return Some(BoundaryType::EmptyLine);
+    }
+    
+    // Heading-style boundaries
+    if trimmed.starts_with('#') {
+        return Some(BoundaryType::Heading);
+    }
+    
+    // Code structure boundaries
+    if trimmed.starts_with("fn ") || trimmed.starts_with("pub fn ") ||
+       trimmed.starts_with("async fn ") || trimmed.starts_with("pub async fn ") {
+        return Some(BoundaryType::FunctionDef);
+    }
+    
+    if trimmed.starts_with("impl ") || trimmed.starts_with("pub impl ") {
+        return Some(BoundaryType::ImplBlock);
+    }
+    
+    if trimmed.starts_with("struct ") || trimmed.starts_with("pub struct ") ||
+       trimmed.starts_with("enum ") || trimmed.starts_with("pub enum ") {
+        return Some(BoundaryType::TypeDef);
+    }
+    
+    if trimmed.starts_with("class ") || trimmed.starts_with("interface ") {
+        return Some(BoundaryType::ClassDef);
+    }
+    
+    if trimmed.starts_with("def ") || trimmed.starts_with("async def ") {
+        return Some(BoundaryType::FunctionDef);
+    }
+    
+    // Module boundaries
+    if trimmed.starts_with("mod ") || trimmed.starts_with("pub mod ") {
+        return Some(BoundaryType::ModuleDef);
+    }
+    
+    // Comment blocks
+    if trimmed.starts_with("///") || trimmed.starts_with("//!") ||
+       trimmed.starts_with("/**") || trimmed.starts_with("/*") {
+        return Some(BoundaryType::DocComment);
+    }
+    
+    None
 }
 
 /// A chunk candidate before final processing.
@@ -474,6 +950,7 @@ struct ChunkCandidate {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::SourceKind;
     use uuid::Uuid;
 
     fn create_test_item(content: &str, kind: SourceKind) -> SourceItem {
@@ -485,6 +962,7 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
@@ -517,7 +995,7 @@ struct MyStruct {
     value: i32,
 }
 "#;
-        let analysis = chunker.analyze_content(content);
+        let analysis = chunker.analyze_content(content, None);
         
         // Should detect function and struct boundaries
         assert!(analysis.semantic_boundaries.iter().any(|b| b.boundary_type == BoundaryType::FunctionDef));
@@ -546,4 +1024,188 @@ struct MyStruct {
             assert!(!chunk.content.trim().is_empty());
         }
     }
+
+    #[test]
+    fn test_custom_boundaries_split_yaml_documents() {
+        let content = (0..6)
+            .map(|i| {
+                format!(
+                    "---\n# Service {i}\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: service-{i}\n\nspec:\n  replicas: {i}\n  template:\n    containers:\n      - image: registry/service-{i}:latest\n"
+                )
+            })
+            .collect::<String>();
+        let item = create_test_item(&content, SourceKind::Other);
+        let config = ChunkConfig::with_size(60);
+        let separator = Regex::new(r"^---\s*$").unwrap();
+
+        let starts_documents = |chunks: &[Chunk]| chunks.iter().all(|c| content[c.start_index..].starts_with("---\n"));
+
+        let chunks = AgenticChunker::new().chunk(&item, &config).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(!starts_documents(&chunks));
+
+        let chunker = AgenticChunker::new().with_custom_boundaries(vec![(separator, 1.0)]);
+        let analysis = chunker.analyze_content(&content, Some("yaml"));
+        assert_eq!(
+            analysis.semantic_boundaries.iter().filter(|b| b.boundary_type == BoundaryType::Custom(1.0)).count(),
+            6
+        );
+
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(starts_documents(&chunks));
+    }
+
+    #[test]
+    fn test_language_weights() {
+        let content = "def load():\n    pass\n";
+        let strength = |chunker: &AgenticChunker, language| {
+            let analysis = chunker.analyze_content(content, Some(language));
+            analysis.semantic_boundaries.iter().find(|b| b.boundary_type == BoundaryType::FunctionDef).unwrap().strength
+        };
+
+        let chunker = AgenticChunker::new();
+        assert_eq!(strength(&chunker, "python"), 0.95);
+        assert_eq!(strength(&chunker, "ruby"), 0.8);
+
+        let chunker = chunker.with_language_weights("Ruby", HashMap::from([(BoundaryType::FunctionDef, 0.9)]));
+        assert_eq!(strength(&chunker, "ruby"), 0.9);
+        assert_eq!(strength(&chunker, "python"), 0.95);
+    }
+
+    #[test]
+    fn test_coherence_score() {
+        let chunker = AgenticChunker::new();
+        let score = |content: &str| {
+            let boundaries = chunker.find_semantic_boundaries(content, Some("rust"));
+            AgenticChunker::score_chunk(content, &boundaries)
+        };
+
+        let aligned = "fn parse(input: &str) -> Config {\n    let config = Config::default();\n    config\n}\n\nfn load() {\n    parse(\"\");\n}\n";
+        assert!(score(aligned) > 0.8, "{}", score(aligned));
+
+        let ragged = "    let config = Config::default();\n    config\n}\n\nfn load() {\n    parse(\"\")";
+        assert!(score(ragged) < 0.5, "{}", score(ragged));
+        assert_eq!(score("\n\n"), 0.0);
+
+        let item = create_test_item(aligned, SourceKind::CodeRepo);
+        let chunks = chunker.chunk(&item, &ChunkConfig::default()).unwrap();
+        assert!(chunks[0].metadata.coherence_score.unwrap() > 0.8);
+    }
+
+    #[test]
+    fn test_chunks_record_opening_boundary() {
+        let mut content = String::new();
+        for name in ["setup", "process", "finish"] {
+            content.push_str(&format!("fn {name}() {{\n"));
+            for i in 0..12 {
+                content.push_str(&format!("    let value_{i} = compute_the_next_value(previous, {i});\n"));
+            }
+            content.push_str("}\n\n");
+        }
+
+        let item = create_test_item(&content, SourceKind::CodeRepo);
+        let chunks = AgenticChunker::new().chunk(&item, &ChunkConfig::with_size(200)).unwrap();
+
+        let process = chunks
+            .iter()
+            .find(|c| c.content.trim_start().starts_with("fn process()"))
+            .expect("a chunk should start at fn process()");
+        assert_eq!(process.metadata.boundary_type.as_deref(), Some("function_def"));
+        assert!(process.metadata.boundary_strength.unwrap() >= 0.8);
+        assert_eq!(BoundaryType::Custom(0.5).as_str(), "custom");
+    }
+
+    #[test]
+    fn test_multi_pass_hierarchy() {
+        let mut content = String::from("# Operations Guide\n\n");
+        for chapter in 0..10 {
+            content.push_str(&format!("## Chapter {chapter}\n\n"));
+            for topic in 0..5 {
+                content.push_str(&format!("### Topic {chapter}.{topic}\n\n"));
+                for sentence in 0..8 {
+                    content.push_str(&format!(
+                        "Step {sentence} explains how the on-call engineer checks the replica lag, drains traffic and restarts the service safely. "
+                    ));
+                }
+                content.push_str(&format!("\n\nfn check_{chapter}_{topic}() {{\n    restart(\"service\");\n}}\n\n"));
+            }
+        }
+        assert!(count_tokens(&content) >= 10_000);
+
+        let item = create_test_item(&content, SourceKind::Document);
+        let config = ChunkConfig::with_size(512);
+
+        let single = AgenticChunker::new().chunk(&item, &config).unwrap();
+        assert!(single.iter().all(|c| c.parent_chunk_id.is_none()));
+
+        let chunks = AgenticChunker::new().with_multi_pass(2).chunk(&item, &config).unwrap();
+        let graph = crate::types::ChunkGraph::from_chunks(&chunks);
+        let parents: Vec<_> = graph.to_adjacency_list().into_keys().collect();
+        assert!(parents.len() >= 2);
+
+        for parent in parents {
+            // Parents are virtual sections, not emitted chunks
+            assert!(chunks.iter().all(|c| c.id != parent));
+            let children: Vec<_> = chunks.iter().filter(|c| c.parent_chunk_id == Some(parent)).collect();
+            assert!(children.len() >= 2);
+            assert_eq!(parent, Uuid::new_v5(&Uuid::NAMESPACE_OID, children[0].id.as_bytes()));
+            // A section's pieces are contiguous
+            for pair in children.windows(2) {
+                assert_eq!(pair[0].end_index, pair[1].start_index);
+            }
+        }
+
+        let indices: Vec<_> = chunks.iter().map(|c| c.chunk_index).collect();
+        assert_eq!(indices, (0..chunks.len()).collect::<Vec<_>>());
+
+        // With deterministic IDs, parents are stable across runs
+        let chunk_ids = || {
+            let mut chunks = AgenticChunker::new().with_multi_pass(2).chunk(&item, &config).unwrap();
+            crate::types::assign_content_hash_ids(&mut chunks);
+            chunks.into_iter().map(|c| (c.id, c.parent_chunk_id)).collect::<Vec<_>>()
+        };
+        assert_eq!(chunk_ids(), chunk_ids());
+    }
+
+    fn boundaries_of(chunker: &AgenticChunker, content: &str, boundary_type: BoundaryType) -> Vec<usize> {
+        chunker
+            .find_semantic_boundaries(content, None)
+            .into_iter()
+            .filter(|b| b.boundary_type == boundary_type)
+            .map(|b| b.line_number)
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_boundaries_skip_comments_and_strings() {
+        let content = "// fn fake() {}\n#[derive(Debug)]\nstruct Real;\n/* fn fake()\n   fn fake() */\nconst S: &str = r#\"\nfn fake() \"quoted\"\n\"#;\npub(crate) fn real() {}\nlet c = '\"';\nfn after_char() {}";
+        let chunker = AgenticChunker::new().with_language("rust");
+
+        assert_eq!(boundaries_of(&chunker, content, BoundaryType::FunctionDef), vec![8, 10]);
+        assert_eq!(boundaries_of(&chunker, content, BoundaryType::TypeDef), vec![2]);
+        assert!(boundaries_of(&chunker, content, BoundaryType::Heading).is_empty());
+
+        // The universal patterns take the comment at face value
+        let universal = AgenticChunker::new();
+        assert!(boundaries_of(&universal, content, BoundaryType::Heading).contains(&1));
+    }
+
+    #[test]
+    fn test_python_boundaries_skip_nested_functions() {
+        let content = "def outer():\n    def helper():\n        pass\n    return helper\n\nclass Service:\n    \"\"\"\n    def not_code():\n    \"\"\"\n    def method(self):\n        def inner():\n            pass\n\nasync def top():\n    pass";
+        let chunker = AgenticChunker::new().with_language("python");
+
+        assert_eq!(boundaries_of(&chunker, content, BoundaryType::FunctionDef), vec![0, 9, 13]);
+        assert_eq!(boundaries_of(&chunker, content, BoundaryType::ClassDef), vec![5]);
+    }
+
+    #[test]
+    fn test_javascript_boundaries_skip_function_expressions() {
+        let content = "const f = function() {};\nexport function real() {}\nitems.map(function (x) { return x; });\nconst s = `\nfunction fake() {}\n`;\nasync function* gen() {}\nexport default class App {}";
+        let chunker = AgenticChunker::new().with_language("typescript");
+
+        assert_eq!(boundaries_of(&chunker, content, BoundaryType::FunctionDef), vec![1, 6]);
+        assert_eq!(boundaries_of(&chunker, content, BoundaryType::ClassDef), vec![7]);
+    }
 }
@@ -1,36 +1,58 @@
 //! Chat chunker for conversation windows.
 
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::base::{count_tokens, Chunker};
 use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
 
+/// Configuration for [`ChatChunker`].
+#[derive(Debug, Clone)]
+pub struct ChatChunkerConfig {
+    /// Maximum messages per chunk (0 = no limit)
+    pub max_messages_per_chunk: usize,
+    /// Include speaker names in output
+    pub include_speakers: bool,
+    /// Start a new chunk when consecutive messages are more than this many
+    /// minutes apart, regardless of token count
+    pub max_gap_minutes: Option<u64>,
+    /// Combine adjacent messages from the same user into one message, up to
+    /// the chunk size
+    pub merge_consecutive_same_user: bool,
+    /// Don't merge messages sent more than this many seconds apart
+    pub max_merge_gap_seconds: u64,
+}
+
+impl Default for ChatChunkerConfig {
+    fn default() -> Self {
+        Self {
+            max_messages_per_chunk: 0, // No message limit, use token limit
+            include_speakers: true,
+            max_gap_minutes: None,
+            merge_consecutive_same_user: false,
+            max_merge_gap_seconds: 60,
+        }
+    }
+}
+
 /// Chat chunker for conversation-based content like Slack, Discord, or Teams.
 ///
 /// This chunker groups messages into conversation windows that maintain
 /// context while respecting token limits.
 pub struct ChatChunker {
-    /// Maximum messages per chunk (0 = no limit)
-    max_messages_per_chunk: usize,
-    /// Include speaker names in output
-    include_speakers: bool,
+    config: ChatChunkerConfig,
 }
 
 impl ChatChunker {
     /// Create a new chat chunker with default settings.
     pub fn new() -> Self {
-        Self {
-            max_messages_per_chunk: 0, // No message limit, use token limit
-            include_speakers: true,
-        }
+        Self::with_config(ChatChunkerConfig::default())
     }
 
-    /// Set the maximum messages per chunk.
-    pub fn with_max_messages(mut self, max: usize) -> Self {
-        self.max_messages_per_chunk = max;
-        self
+    /// Create a chat chunker with the given configuration.
+    pub fn with_config(config: ChatChunkerConfig) -> Self {
+        Self { config }
     }
 
     /// Parse chat content from JSON format.
@@ -87,9 +109,90 @@ impl ChatChunker {
         }
     }
 
+    /// Create a chunk from a window of messages and their timestamps.
+    fn create_chunk(
+        &self,
+        item: &SourceItem,
+        thread: &ChatThread,
+        messages: &[&TimedMessage],
+        text: &str,
+        chunk_index: usize,
+    ) -> Chunk {
+        let mut chunk = Chunk::new(
+            item.id,
+            item.source_id,
+            item.source_kind,
+            text.to_string(),
+            count_tokens(text),
+            0,
+            text.len(),
+            chunk_index,
+        );
+
+        let start = messages.iter().find_map(|msg| msg.first_time);
+        chunk.metadata = ChunkMetadata::for_chat(
+            messages.first().map(|msg| msg.message.user.as_str()),
+            thread.thread_ts.as_deref(),
+            start,
+        );
+        chunk.metadata.conversation_start = start;
+        chunk.metadata.conversation_end = messages.iter().rev().find_map(|msg| msg.last_time);
+
+        let merged: usize = messages.iter().filter(|msg| msg.count > 1).map(|msg| msg.count).sum();
+        if merged > 0 {
+            chunk.metadata.extra = Some(serde_json::json!({ "merged_messages": merged }));
+        }
+        chunk
+    }
+
+    /// Pair messages with their timestamps, merging runs from the same user
+    /// when `merge_consecutive_same_user` is set. A merged message never grows
+    /// past `max_tokens`.
+    fn merge_messages(
+        &self,
+        messages: &[ChatMessage],
+        times: &[Option<DateTime<Utc>>],
+        max_tokens: usize,
+    ) -> Vec<TimedMessage> {
+        let max_merge_gap = Duration::seconds(self.config.max_merge_gap_seconds as i64);
+        let mut merged: Vec<TimedMessage> = Vec::with_capacity(messages.len());
+
+        for (msg, &time) in messages.iter().zip(times) {
+            if self.config.merge_consecutive_same_user {
+                if let Some(previous) = merged.last_mut() {
+                    let close_enough = match (previous.last_time, time) {
+                        (Some(previous), Some(time)) => time - previous <= max_merge_gap,
+                        _ => true,
+                    };
+                    let fits = || {
+                        let mut combined = previous.message.clone();
+                        combined.text.push('\n');
+                        combined.text.push_str(&msg.text);
+                        count_tokens(&self.format_message(&combined)) <= max_tokens
+                    };
+                    if previous.message.user == msg.user && close_enough && fits() {
+                        previous.message.text.push('\n');
+                        previous.message.text.push_str(&msg.text);
+                        previous.last_time = time.or(previous.last_time);
+                        previous.count += 1;
+                        continue;
+                    }
+                }
+            }
+
+            merged.push(TimedMessage {
+                message: msg.clone(),
+                first_time: time,
+                last_time: time,
+                count: 1,
+            });
+        }
+        merged
+    }
+
     /// Format a message for inclusion in a chunk.
     fn format_message(&self, msg: &ChatMessage) -> String {
-        if self.include_speakers {
+        if self.config.include_speakers {
             format!("{}: {}", msg.user, msg.text)
         } else {
             msg.text.clone()
@@ -116,6 +219,48 @@ struct ChatMessage {
     ts: Option<String>,
 }
 
+/// A message, possibly merged from several, with the times it spans.
+struct TimedMessage {
+    message: ChatMessage,
+    first_time: Option<DateTime<Utc>>,
+    last_time: Option<DateTime<Utc>>,
+    /// Number of original messages merged into this one
+    count: usize,
+}
+
+/// Parse a chat timestamp.
+///
+/// Accepts RFC 3339, Slack-style epoch seconds (`"1512085950.000216"`),
+/// epoch milliseconds, and common export formats such as
+/// `2024-01-15 09:30:00` and Discord's `01/15/2024 9:30 AM`. Timestamps
+/// without a zone are taken as UTC.
+fn parse_chat_timestamp(ts: &str) -> Option<DateTime<Utc>> {
+    const FORMATS: &[&str] = &[
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%m/%d/%Y %I:%M %p",
+        "%m/%d/%Y %I:%M:%S %p",
+        "%d-%b-%y %I:%M %p",
+    ];
+
+    let ts = ts.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(ts) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    if let Ok(epoch) = ts.parse::<f64>() {
+        // Anything past the year 5138 in seconds is really milliseconds
+        let millis = if epoch.abs() < 1e11 { epoch * 1000.0 } else { epoch };
+        return Utc.timestamp_millis_opt(millis as i64).single();
+    }
+
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(ts, format).ok())
+        .map(|time| time.and_utc())
+}
+
 impl Default for ChatChunker {
     fn default() -> Self {
         Self::new()
@@ -148,51 +293,45 @@ impl Chunker for ChatChunker {
             return Ok(vec![]);
         }
 
+        // Messages without a timestamp inherit the previous message's
+        let times: Vec<Option<DateTime<Utc>>> = thread
+            .messages
+            .iter()
+            .scan(None, |last, msg| {
+                if let Some(time) = msg.ts.as_deref().and_then(parse_chat_timestamp) {
+                    *last = Some(time);
+                }
+                Some(*last)
+            })
+            .collect();
+        let messages = self.merge_messages(&thread.messages, &times, config.chunk_size);
+        let max_gap = self.config.max_gap_minutes.map(|minutes| Duration::minutes(minutes as i64));
+
         // Group messages into chunks
         let mut chunks = Vec::new();
-        let mut current_messages: Vec<&ChatMessage> = Vec::new();
+        let mut current_messages: Vec<&TimedMessage> = Vec::new();
         let mut current_text = String::new();
         let mut current_tokens = 0;
-        let mut chunk_index = 0;
 
-        for msg in &thread.messages {
-            let msg_text = self.format_message(msg);
+        for msg in &messages {
+            let msg_text = self.format_message(&msg.message);
             let msg_tokens = count_tokens(&msg_text);
 
+            // A long silence starts a new conversation
+            let previous_time = current_messages.last().and_then(|previous| previous.last_time);
+            let is_gap = match (max_gap, previous_time, msg.first_time) {
+                (Some(max_gap), Some(previous), Some(time)) => time - previous > max_gap,
+                _ => false,
+            };
+
             // Check if we should start a new chunk
-            let should_split = 
-                (current_tokens + msg_tokens > config.chunk_size && !current_messages.is_empty())
-                || (self.max_messages_per_chunk > 0 
-                    && current_messages.len() >= self.max_messages_per_chunk);
+            let should_split = is_gap
+                || (current_tokens + msg_tokens > config.chunk_size && !current_messages.is_empty())
+                || (self.config.max_messages_per_chunk > 0
+                    && current_messages.len() >= self.config.max_messages_per_chunk);
 
             if should_split {
-                // Create chunk from current messages
-                let token_count = count_tokens(&current_text);
-
-                let mut chunk = Chunk::new(
-                    item.id,
-                    item.source_id,
-                    item.source_kind,
-                    current_text.clone(),
-                    token_count,
-                    0,
-                    current_text.len(),
-                    chunk_index,
-                );
-
-                // Add chat metadata
-                let first_ts = current_messages.first()
-                    .and_then(|m| m.ts.as_ref())
-                    .and_then(|ts| ts.parse::<DateTime<Utc>>().ok());
-
-                chunk.metadata = ChunkMetadata::for_chat(
-                    current_messages.first().map(|m| m.user.as_str()),
-                    thread.thread_ts.as_deref(),
-                    first_ts,
-                );
-
-                chunks.push(chunk);
-                chunk_index += 1;
+                chunks.push(self.create_chunk(item, &thread, &current_messages, &current_text, chunks.len()));
 
                 current_messages.clear();
                 current_text.clear();
@@ -210,30 +349,7 @@ impl Chunker for ChatChunker {
 
         // Don't forget the last chunk
         if !current_messages.is_empty() {
-            let token_count = count_tokens(&current_text);
-
-            let mut chunk = Chunk::new(
-                item.id,
-                item.source_id,
-                item.source_kind,
-                current_text.clone(),
-                token_count,
-                0,
-                current_text.len(),
-                chunk_index,
-            );
-
-            let first_ts = current_messages.first()
-                .and_then(|m| m.ts.as_ref())
-                .and_then(|ts| ts.parse::<DateTime<Utc>>().ok());
-
-            chunk.metadata = ChunkMetadata::for_chat(
-                current_messages.first().map(|m| m.user.as_str()),
-                thread.thread_ts.as_deref(),
-                first_ts,
-            );
-
-            chunks.push(chunk);
+            chunks.push(self.create_chunk(item, &thread, &current_messages, &current_text, chunks.len()));
         }
 
         Ok(chunks)
@@ -255,6 +371,7 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
@@ -300,4 +417,100 @@ charlie: Hey there!"#;
         let chunks = chunker.chunk(&item, &config).unwrap();
         assert!(chunks.len() > 1);
     }
+
+    #[test]
+    fn test_time_gap_splits_conversation() {
+        let content = r#"{"messages":[
+            {"user":"alice","text":"Deploy is going out now","ts":"2024-03-01T09:00:00Z"},
+            {"user":"bob","text":"Watching the dashboards","ts":"2024-03-01T09:05:00Z"},
+            {"user":"alice","text":"Looks healthy"},
+            {"user":"carol","text":"Anyone seeing 502s?","ts":"2024-03-01T11:10:00Z"},
+            {"user":"bob","text":"Yes, rolling back","ts":"1709291700.000200"}
+        ]}"#;
+        let item = create_chat_item(content, "application/json");
+        let config = ChunkConfig::with_size(1000);
+
+        // Without a gap limit everything fits in one chunk
+        assert_eq!(ChatChunker::new().chunk(&item, &config).unwrap().len(), 1);
+
+        let chunker = ChatChunker::with_config(ChatChunkerConfig {
+            max_gap_minutes: Some(60),
+            ..Default::default()
+        });
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].content.ends_with("alice: Looks healthy"));
+        assert!(chunks[1].content.starts_with("carol: Anyone seeing 502s?"));
+
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(chunks[0].metadata.conversation_start, Some(at("2024-03-01T09:00:00Z")));
+        // "Looks healthy" has no timestamp and inherits 09:05
+        assert_eq!(chunks[0].metadata.conversation_end, Some(at("2024-03-01T09:05:00Z")));
+        assert_eq!(chunks[1].metadata.conversation_start, Some(at("2024-03-01T11:10:00Z")));
+        assert_eq!(chunks[1].metadata.conversation_end, Some(at("2024-03-01T11:15:00.000Z")));
+    }
+
+    #[test]
+    fn test_parse_chat_timestamp() {
+        let expected = "2024-01-15T09:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        for ts in ["2024-01-15T09:30:00Z", "2024-01-15T10:30:00+01:00", "1705311000", "1705311000000", "2024-01-15 09:30:00", "01/15/2024 9:30 AM"] {
+            assert_eq!(parse_chat_timestamp(ts), Some(expected), "{ts}");
+        }
+        assert!(parse_chat_timestamp("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_merge_consecutive_same_user() {
+        let content = r#"{"messages":[
+            {"user":"alice","text":"ok","ts":"2024-03-01T09:00:00Z"},
+            {"user":"alice","text":"so","ts":"2024-03-01T09:00:05Z"},
+            {"user":"alice","text":"the deploy","ts":"2024-03-01T09:00:10Z"},
+            {"user":"alice","text":"is stuck","ts":"2024-03-01T09:00:20Z"},
+            {"user":"alice","text":"again","ts":"2024-03-01T09:00:30Z"},
+            {"user":"bob","text":"looking","ts":"2024-03-01T09:01:00Z"},
+            {"user":"bob","text":"found it","ts":"2024-03-01T09:10:00Z"}
+        ]}"#;
+        let item = create_chat_item(content, "application/json");
+        let config = ChunkConfig::with_size(1000);
+        let chunker = ChatChunker::with_config(ChatChunkerConfig {
+            merge_consecutive_same_user: true,
+            ..Default::default()
+        });
+
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        assert_eq!(chunks.len(), 1);
+        // Bob's messages are too far apart to merge
+        assert_eq!(chunks[0].content, "alice: ok\nso\nthe deploy\nis stuck\nagain\nbob: looking\nbob: found it");
+        assert_eq!(chunks[0].metadata.extra, Some(serde_json::json!({"merged_messages": 5})));
+
+        // Merging is opt-in
+        let chunks = ChatChunker::new().chunk(&item, &config).unwrap();
+        assert_eq!(chunks[0].content.lines().filter(|l| l.starts_with("alice: ")).count(), 5);
+        assert!(chunks[0].metadata.extra.is_none());
+    }
+
+    #[test]
+    fn test_merged_burst_respects_chunk_size() {
+        let messages: Vec<String> = (0..40)
+            .map(|i| format!(r#"{{"user":"alice","text":"line number {i} of a long burst","ts":"2024-03-01T09:00:{:02}Z"}}"#, i))
+            .collect();
+        let content = format!(r#"{{"messages":[{}]}}"#, messages.join(","));
+        let item = create_chat_item(&content, "application/json");
+        let config = ChunkConfig::with_size(50);
+        let chunker = ChatChunker::with_config(ChatChunkerConfig {
+            merge_consecutive_same_user: true,
+            ..Default::default()
+        });
+
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.token_count <= 50, "{} tokens", chunk.token_count);
+        }
+        let merged: u64 = chunks
+            .iter()
+            .filter_map(|chunk| chunk.metadata.extra.as_ref()?["merged_messages"].as_u64())
+            .sum();
+        assert_eq!(merged, 40);
+    }
 }
@@ -3,10 +3,16 @@
 //! This chunker receives pre-parsed/normalized code from code-normalize-fetch
 //! and creates intelligent chunks based on the provided entity boundaries.
 
+use std::pin::Pin;
+
 use anyhow::Result;
+use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use super::base::{count_tokens, Chunker};
+use super::base::{count_tokens, count_tokens_with_mode, Chunker};
+use super::repo_chunker::{detect_test_functions, is_test_path};
+use crate::enrichment::CodeComplexityAnalyzer;
 use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
 
 /// Entity boundary provided by code-normalize-fetch.
@@ -23,6 +29,33 @@ pub struct EntityBoundary {
     /// Optional signature
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signature: Option<String>,
+    /// Decorators, annotations or attributes, e.g. `derive(Debug)`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decorators: Vec<String>,
+}
+
+/// Configuration for `CodeChunker`.
+#[derive(Debug, Clone)]
+pub struct CodeChunkerConfig {
+    /// Prepend the file's top-level imports to every chunk that doesn't
+    /// already start with imports
+    pub inject_file_header: bool,
+    /// Maximum tokens of imports to inject; the header doesn't count toward
+    /// the chunk size
+    pub header_token_budget: usize,
+    /// Chunks smaller than this are merged into the chunk after them; see
+    /// `CodeChunker::merge_tiny_chunks`
+    pub min_chunk_tokens: usize,
+}
+
+impl Default for CodeChunkerConfig {
+    fn default() -> Self {
+        Self {
+            inject_file_header: false,
+            header_token_budget: 100,
+            min_chunk_tokens: 50,
+        }
+    }
 }
 
 /// Code chunker that uses pre-parsed entity boundaries.
@@ -32,16 +65,23 @@ pub struct EntityBoundary {
 pub struct CodeChunker {
     /// Languages supported for semantic chunking
     supported_languages: Vec<String>,
+    config: CodeChunkerConfig,
 }
 
 impl CodeChunker {
     /// Create a new code chunker.
     pub fn new() -> Self {
+        Self::with_config(CodeChunkerConfig::default())
+    }
+
+    /// Create a code chunker with the given configuration.
+    pub fn with_config(config: CodeChunkerConfig) -> Self {
         Self {
             supported_languages: vec![
                 "python", "javascript", "typescript", "rust", "go",
                 "java", "c", "cpp", "ruby", "tsx", "jsx",
             ].into_iter().map(String::from).collect(),
+            config,
         }
     }
 
@@ -54,8 +94,7 @@ impl CodeChunker {
     ) -> Result<Vec<Chunk>> {
         let content = &item.content;
         let lines: Vec<&str> = content.lines().collect();
-        let chunk_size = config.chunk_size;
-        let overlap = config.chunk_overlap;
+        let chunk_size = config.effective_chunk_size();
         let language = item.extract_language().unwrap_or("unknown");
 
         if entities.is_empty() {
@@ -75,7 +114,7 @@ impl CodeChunker {
             }
 
             let entity_text: String = lines[start_idx..end_idx].join("\n");
-            let token_count = count_tokens(&entity_text);
+            let token_count = count_tokens_with_mode(&entity_text, config.token_count_mode, chunk_size);
 
             if token_count <= chunk_size {
                 // Entity fits in one chunk
@@ -96,8 +135,7 @@ impl CodeChunker {
                 let sub_chunks = self.split_large_entity(
                     &entity_text,
                     entity.start_line,
-                    chunk_size,
-                    overlap,
+                    config,
                     item,
                     &mut chunk_index,
                     language,
@@ -116,10 +154,51 @@ impl CodeChunker {
         // Sort chunks by start line
         chunks.sort_by_key(|c| c.metadata.line_range.map(|(s, _)| s).unwrap_or(0));
 
+        let tests = TestLines::new(item, language);
+        let header = self.file_header(&item.content);
+        for chunk in &mut chunks {
+            chunk.metadata.is_test = tests.covers(chunk.metadata.line_range);
+            inject_header(chunk, header.as_deref());
+        }
+
         Ok(chunks)
     }
 
+    /// Top-level import lines of `content` within the header token budget,
+    /// or `None` when header injection is off or the file has no imports.
+    fn file_header(&self, content: &str) -> Option<String> {
+        if !self.config.inject_file_header {
+            return None;
+        }
+
+        let mut header = String::new();
+        let mut lines = content.lines();
+        while let Some(line) = lines.next() {
+            if !is_import_line(line) {
+                continue;
+            }
+            // Imports such as `use a::{\n b,\n};` continue until brackets close
+            let mut statement = line.to_string();
+            let mut depth = bracket_delta(line);
+            while depth > 0 {
+                let Some(next) = lines.next() else { break };
+                statement.push('\n');
+                statement.push_str(next);
+                depth += bracket_delta(next);
+            }
+
+            let candidate = format!("{}{}\n", header, statement);
+            if count_tokens(&candidate) > self.config.header_token_budget {
+                break;
+            }
+            header = candidate;
+        }
+
+        (!header.is_empty()).then_some(header)
+    }
+
     /// Create a chunk from text.
+    #[allow(clippy::too_many_arguments)]
     fn create_chunk(
         &self,
         text: &str,
@@ -145,7 +224,8 @@ impl CodeChunker {
             author: None,
             thread_id: None,
             timestamp: None,
-            extra: None,
+            complexity_score: CodeComplexityAnalyzer::analyze(text, language),
+            ..Default::default()
         };
 
         Chunk::new(
@@ -161,12 +241,15 @@ impl CodeChunker {
     }
 
     /// Split a large entity into multiple chunks.
+    ///
+    /// All sub-chunks point to the same virtual parent chunk id, which
+    /// stands for the whole entity.
+    #[allow(clippy::too_many_arguments)]
     fn split_large_entity(
         &self,
         text: &str,
         base_start_line: usize,
-        chunk_size: usize,
-        overlap: usize,
+        config: &ChunkConfig,
         item: &SourceItem,
         chunk_index: &mut usize,
         language: &str,
@@ -174,15 +257,17 @@ impl CodeChunker {
         entity_type: &str,
     ) -> Vec<Chunk> {
         let lines: Vec<&str> = text.lines().collect();
+        let (chunk_size, overlap, mode) = (config.effective_chunk_size(), config.chunk_overlap, config.token_count_mode);
         let mut chunks = Vec::new();
         let mut start = 0;
+        let parent_chunk_id = Uuid::new_v4();
 
         while start < lines.len() {
             // Find end point based on token count
             let mut end = start;
             let mut accumulated = String::new();
 
-            while end < lines.len() && count_tokens(&accumulated) < chunk_size {
+            while end < lines.len() && count_tokens_with_mode(&accumulated, mode, chunk_size) < chunk_size {
                 accumulated.push_str(lines[end]);
                 accumulated.push('\n');
                 end += 1;
@@ -206,7 +291,8 @@ impl CodeChunker {
                 language,
                 Some(entity_name),
                 Some(entity_type),
-            );
+            )
+            .with_parent(parent_chunk_id);
             chunks.push(chunk);
             *chunk_index += 1;
 
@@ -225,8 +311,8 @@ impl CodeChunker {
         for entity in entities {
             let start = entity.start_line.saturating_sub(1);
             let end = entity.end_line.min(total_lines);
-            for i in start..end {
-                covered[i] = true;
+            if start < end {
+                covered[start..end].iter_mut().for_each(|c| *c = true);
             }
         }
         covered
@@ -293,22 +379,61 @@ impl CodeChunker {
         chunks
     }
 
+    /// Merge consecutive chunks smaller than `min_tokens` into their
+    /// successor, as long as the merged chunk stays within `chunk_size`.
+    ///
+    /// Merged chunks are joined with a blank line, span the lines of both
+    /// and count the merged chunks in `ChunkMetadata::merged_chunks`.
+    /// Chunk indices are renumbered.
+    pub fn merge_tiny_chunks(chunks: Vec<Chunk>, min_tokens: usize, chunk_size: usize) -> Vec<Chunk> {
+        merge_tiny(chunks.into_iter(), min_tokens, chunk_size).collect()
+    }
+
     /// Fallback: simple line-based chunking when no entities provided.
     fn fallback_chunk(&self, item: &SourceItem, config: &ChunkConfig, language: &str) -> Result<Vec<Chunk>> {
-        let content = &item.content;
-        let lines: Vec<&str> = content.lines().collect();
-        let chunk_size = config.chunk_size;
+        Ok(self.fallback_pipeline(item, config, language).collect())
+    }
+
+    /// Fallback windows with tiny chunks merged and the file header injected.
+    fn fallback_pipeline<'a>(
+        &'a self,
+        item: &'a SourceItem,
+        config: &ChunkConfig,
+        language: &'a str,
+    ) -> impl Iterator<Item = Chunk> + Send + 'a {
+        let header = self.file_header(&item.content);
+        let windows = self.fallback_iter(item, config, language);
+        merge_tiny(windows, self.config.min_chunk_tokens, config.effective_chunk_size()).map(move |mut chunk| {
+            inject_header(&mut chunk, header.as_deref());
+            chunk
+        })
+    }
+
+    /// Lazily produce fallback chunks one window of lines at a time.
+    fn fallback_iter<'a>(
+        &'a self,
+        item: &'a SourceItem,
+        config: &ChunkConfig,
+        language: &'a str,
+    ) -> impl Iterator<Item = Chunk> + Send + 'a {
+        let lines: Vec<&str> = item.content.lines().collect();
+        let chunk_size = config.effective_chunk_size();
         let overlap = config.chunk_overlap;
+        let mode = config.token_count_mode;
 
-        let mut chunks = Vec::new();
+        let tests = TestLines::new(item, language);
         let mut chunk_index = 0;
         let mut start = 0;
 
-        while start < lines.len() {
+        std::iter::from_fn(move || {
+            if start >= lines.len() {
+                return None;
+            }
+
             let mut end = start;
             let mut accumulated = String::new();
 
-            while end < lines.len() && count_tokens(&accumulated) < chunk_size {
+            while end < lines.len() && count_tokens_with_mode(&accumulated, mode, chunk_size) < chunk_size {
                 accumulated.push_str(lines[end]);
                 accumulated.push('\n');
                 end += 1;
@@ -319,7 +444,7 @@ impl CodeChunker {
             }
 
             let chunk_text = lines[start..end].join("\n");
-            let chunk = self.create_chunk(
+            let mut chunk = self.create_chunk(
                 &chunk_text,
                 start + 1,
                 end,
@@ -329,15 +454,118 @@ impl CodeChunker {
                 None,
                 None,
             );
-            chunks.push(chunk);
+            chunk.metadata.is_test = tests.covers(chunk.metadata.line_range);
             chunk_index += 1;
 
             let overlap_lines = (overlap as f32 / 10.0).ceil() as usize;
             let next_start = end.saturating_sub(overlap_lines.min(end - start));
             start = if next_start <= start { end } else { next_start };
+
+            Some(chunk)
+        })
+    }
+}
+
+/// Whether a line is a top-level `use`, `import` or `#include`.
+fn is_import_line(line: &str) -> bool {
+    ["use ", "pub use ", "import ", "from ", "#include"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+/// Net count of opened `{` and `(` on a line.
+fn bracket_delta(line: &str) -> i32 {
+    line.chars()
+        .map(|c| match c {
+            '{' | '(' => 1,
+            '}' | ')' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Lazily merge tiny chunks into their successors; see
+/// `CodeChunker::merge_tiny_chunks`.
+fn merge_tiny(
+    chunks: impl Iterator<Item = Chunk> + Send,
+    min_tokens: usize,
+    chunk_size: usize,
+) -> impl Iterator<Item = Chunk> + Send {
+    let mut chunks = chunks.peekable();
+    let mut chunk_index = 0;
+
+    std::iter::from_fn(move || {
+        let mut chunk = chunks.next()?;
+        while chunk.token_count < min_tokens {
+            let tokens = chunk.token_count;
+            let Some(next) = chunks.next_if(|next| tokens + next.token_count <= chunk_size) else {
+                break;
+            };
+            chunk = merge_pair(chunk, next);
         }
+        chunk.chunk_index = chunk_index;
+        chunk_index += 1;
+        Some(chunk)
+    })
+}
 
-        Ok(chunks)
+/// Merge `next` into `chunk`, keeping the first chunk's id and metadata.
+fn merge_pair(mut chunk: Chunk, next: Chunk) -> Chunk {
+    chunk.content = format!("{}\n\n{}", chunk.content, next.content);
+    chunk.token_count = count_tokens(&chunk.content);
+    chunk.end_index = next.end_index.max(chunk.end_index);
+
+    let metadata = &mut chunk.metadata;
+    metadata.line_range = match (metadata.line_range, next.metadata.line_range) {
+        (Some((start, _)), Some((_, end))) => Some((start, end)),
+        (range, other) => range.or(other),
+    };
+    if metadata.symbol_name != next.metadata.symbol_name {
+        metadata.symbol_name = None;
+    }
+    metadata.is_test |= next.metadata.is_test;
+    metadata.merged_chunks += next.metadata.merged_chunks + 1;
+    let language = metadata.language.as_deref().unwrap_or("unknown");
+    metadata.complexity_score = CodeComplexityAnalyzer::analyze(&chunk.content, language);
+    chunk
+}
+
+/// Prepend the file header to a chunk that doesn't already start with
+/// imports, keeping its token count to the original content.
+fn inject_header(chunk: &mut Chunk, header: Option<&str>) {
+    let Some(header) = header else { return };
+    let first_line = chunk.content.lines().find(|line| !line.trim().is_empty());
+    if first_line.is_some_and(is_import_line) {
+        return;
+    }
+    chunk.content = format!("{}\n{}", header, chunk.content);
+    chunk.metadata.has_injected_header = true;
+}
+
+/// Lines on which test functions start, used to flag test chunks.
+struct TestLines {
+    /// The whole file is test code (e.g. `foo_test.go`, `tests/`)
+    test_file: bool,
+    /// 1-indexed start lines of detected test functions
+    lines: Vec<usize>,
+}
+
+impl TestLines {
+    fn new(item: &SourceItem, language: &str) -> Self {
+        let path = item.extract_path();
+        Self {
+            test_file: path.is_some_and(is_test_path),
+            lines: detect_test_functions(&item.content, Some(language), path)
+                .iter()
+                .map(|s| s.line_range.0 + 1)
+                .collect(),
+        }
+    }
+
+    /// Whether a chunk spanning `line_range` contains test code.
+    fn covers(&self, line_range: Option<(usize, usize)>) -> bool {
+        self.test_file
+            || line_range.is_some_and(|(start, end)| self.lines.iter().any(|l| (start..=end).contains(l)))
     }
 }
 
@@ -363,10 +591,31 @@ impl Chunker for CodeChunker {
         }
     }
 
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            chunker.name = "code",
+            item.source_kind = %item.source_kind,
+            item.content_size = item.content.len(),
+            chunk.count = tracing::field::Empty,
+        )
+    )]
     fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
         // When called without entities, use fallback
         let language = item.extract_language().unwrap_or("unknown");
-        self.fallback_chunk(item, config, language)
+        let chunks = self.fallback_chunk(item, config, language)?;
+        tracing::Span::current().record("chunk.count", chunks.len());
+        Ok(chunks)
+    }
+
+    fn chunk_stream<'a>(
+        &'a self,
+        item: &'a SourceItem,
+        config: &'a ChunkConfig,
+    ) -> Pin<Box<dyn Stream<Item = Result<Chunk>> + Send + 'a>> {
+        // Yield each window as soon as it is built rather than collecting
+        let language = item.extract_language().unwrap_or("unknown");
+        Box::pin(stream::iter(self.fallback_pipeline(item, config, language).map(Ok)))
     }
 }
 
@@ -374,7 +623,6 @@ impl Chunker for CodeChunker {
 mod tests {
     use super::*;
     use crate::types::SourceKind;
-    use uuid::Uuid;
 
     fn create_code_item(content: &str, language: &str) -> SourceItem {
         let metadata = serde_json::json!({
@@ -390,9 +638,29 @@ mod tests {
             content: content.to_string(),
             metadata,
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
+    #[test]
+    fn test_language_size_override() {
+        let chunker = CodeChunker::new();
+        let code: String = (0..400).map(|i| format!("value_{i} = compute(value_{i}, factor={i})\n")).collect();
+        let item = create_code_item(&code, "python");
+        let config = ChunkConfig {
+            token_count_mode: crate::types::TokenCountMode::Exact,
+            ..ChunkConfig::default().with_language("python")
+        };
+
+        let default_sizes: Vec<usize> = chunker.chunk(&item, &config).unwrap().iter().map(|c| c.token_count).collect();
+        let config = config.with_language_size("python", 768);
+        let python_sizes: Vec<usize> = chunker.chunk(&item, &config).unwrap().iter().map(|c| c.token_count).collect();
+
+        assert!(default_sizes.iter().all(|&tokens| tokens <= 512 + 20));
+        assert!(python_sizes[0] > 700 && python_sizes[0] <= 768 + 20);
+        assert!(python_sizes.len() < default_sizes.len());
+    }
+
     #[test]
     fn test_chunk_with_entities() {
         let chunker = CodeChunker::new();
@@ -415,6 +683,7 @@ def world():
                 start_line: 3,
                 end_line: 4,
                 signature: Some("def hello()".to_string()),
+                decorators: Vec::new(),
             },
             EntityBoundary {
                 name: "world".to_string(),
@@ -422,6 +691,7 @@ def world():
                 start_line: 6,
                 end_line: 7,
                 signature: Some("def world()".to_string()),
+                decorators: Vec::new(),
             },
         ];
 
@@ -430,6 +700,141 @@ def world():
         assert!(!chunks.is_empty());
         assert!(chunks.iter().any(|c| c.content.contains("hello")));
         assert!(chunks.iter().any(|c| c.content.contains("world")));
+        // Straight-line functions have no decision points
+        assert!(chunks.iter().all(|c| c.metadata.complexity_score == 0.0));
+    }
+
+    #[test]
+    fn test_split_entity_shares_parent() {
+        let chunker = CodeChunker::new();
+        let config = ChunkConfig::with_size(20);
+        let body: String = (0..40).map(|i| format!("    x{i} = compute({i})\n")).collect();
+        let code = format!("def big():\n{body}\ndef small():\n    pass\n");
+        let item = create_code_item(&code, "python");
+
+        let entities = vec![
+            EntityBoundary {
+                name: "big".to_string(),
+                entity_type: "function".to_string(),
+                start_line: 1,
+                end_line: 41,
+                signature: None,
+                decorators: Vec::new(),
+            },
+            EntityBoundary {
+                name: "small".to_string(),
+                entity_type: "function".to_string(),
+                start_line: 43,
+                end_line: 44,
+                signature: None,
+                decorators: Vec::new(),
+            },
+        ];
+
+        let chunks = chunker.chunk_with_entities(&item, &config, &entities).unwrap();
+        let big: Vec<_> = chunks.iter().filter(|c| c.metadata.symbol_name.as_deref() == Some("big")).collect();
+        assert!(big.len() > 1);
+        assert!(big[0].parent_chunk_id.is_some());
+        assert!(big.iter().all(|c| c.parent_chunk_id == big[0].parent_chunk_id));
+
+        let small = chunks.iter().find(|c| c.metadata.symbol_name.as_deref() == Some("small")).unwrap();
+        assert_eq!(small.parent_chunk_id, None);
+    }
+
+    #[test]
+    fn test_test_functions_are_flagged() {
+        let chunker = CodeChunker::new();
+        let code = "def add(a, b):\n    return a + b\n\ndef test_add():\n    assert add(1, 2) == 3\n";
+        let item = create_code_item(code, "python");
+
+        let entities = vec![
+            EntityBoundary {
+                name: "add".to_string(),
+                entity_type: "function".to_string(),
+                start_line: 1,
+                end_line: 2,
+                signature: None,
+                decorators: Vec::new(),
+            },
+            EntityBoundary {
+                name: "test_add".to_string(),
+                entity_type: "function".to_string(),
+                start_line: 4,
+                end_line: 5,
+                signature: None,
+                decorators: Vec::new(),
+            },
+        ];
+
+        let chunks = chunker.chunk_with_entities(&item, &ChunkConfig::default(), &entities).unwrap();
+        let flags: Vec<(Option<&str>, bool)> = chunks
+            .iter()
+            .map(|c| (c.metadata.symbol_name.as_deref(), c.metadata.is_test))
+            .collect();
+        assert_eq!(flags, vec![(Some("add"), false), (Some("test_add"), true)]);
+    }
+
+    #[test]
+    fn test_file_header_injected_into_later_chunks() {
+        let modules = ["fmt", "fs", "io", "env", "mem", "ptr", "rc", "sync", "thread", "time"];
+        let imports: String = modules.iter().map(|m| format!("use std::{m};\n")).collect();
+        let functions: String = (0..5)
+            .map(|i| format!("\nfn step_{i}(input: u32) -> u32 {{\n    let doubled = input * 2;\n    doubled + {i}\n}}\n"))
+            .collect();
+        let item = create_code_item(&format!("{imports}{functions}"), "rust");
+        let config = ChunkConfig::with_size(100).with_overlap(0);
+
+        let chunker = CodeChunker::with_config(CodeChunkerConfig {
+            inject_file_header: true,
+            ..Default::default()
+        });
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        let plain = CodeChunker::new().chunk(&item, &config).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert!(!chunks[0].metadata.has_injected_header);
+        for (chunk, original) in chunks.iter().zip(&plain).skip(1) {
+            assert!(chunk.metadata.has_injected_header);
+            assert!(modules.iter().all(|m| chunk.content.contains(&format!("use std::{m};"))));
+            assert!(chunk.content.ends_with(&original.content));
+            // The header doesn't count toward the chunk's size
+            assert_eq!(chunk.token_count, original.token_count);
+        }
+        assert!(plain.iter().all(|c| !c.metadata.has_injected_header));
+    }
+
+    #[test]
+    fn test_merge_tiny_chunks() {
+        let chunker = CodeChunker::new();
+        let code: String = (0..10).map(|i| format!("const LIMIT_{i}: usize = {i};\n")).collect();
+        let item = create_code_item(&code, "rust");
+        let entities: Vec<EntityBoundary> = (1..=10)
+            .map(|line| EntityBoundary {
+                name: format!("LIMIT_{}", line - 1),
+                entity_type: "const".to_string(),
+                start_line: line,
+                end_line: line,
+                signature: None,
+                decorators: Vec::new(),
+            })
+            .collect();
+        let chunks = chunker.chunk_with_entities(&item, &ChunkConfig::default(), &entities).unwrap();
+        assert_eq!(chunks.len(), 10);
+
+        let merged = CodeChunker::merge_tiny_chunks(chunks, 50, 40);
+        assert!(merged.len() > 1 && merged.len() < 10);
+        assert_eq!(merged.iter().map(|c| c.chunk_index).collect::<Vec<_>>(), (0..merged.len()).collect::<Vec<_>>());
+        assert_eq!(merged.iter().map(|c| c.metadata.merged_chunks + 1).sum::<usize>(), 10);
+
+        let first = &merged[0];
+        assert!(first.content.starts_with("const LIMIT_0: usize = 0;\n\nconst LIMIT_1: usize = 1;"));
+        let (start, end) = first.metadata.line_range.unwrap();
+        assert_eq!((start, end), (1, first.metadata.merged_chunks + 1));
+        assert_eq!(first.metadata.symbol_name, None);
+
+        let all = CodeChunker::merge_tiny_chunks(merged, 512, 512);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].metadata.line_range, Some((1, 10)));
     }
 
     #[test]
@@ -2,35 +2,256 @@
 
 use anyhow::Result;
 use regex::Regex;
+use uuid::Uuid;
 
 use super::base::{count_tokens, Chunker};
+use super::code_chunker::CodeChunker;
+use crate::types::language::{detect_from_content, language_from_name};
 use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
 
+/// Configuration for `DocumentChunker`.
+#[derive(Debug, Clone)]
+pub struct DocumentChunkerConfig {
+    /// Prefix the first chunk with a `[title by author, date]` line built
+    /// from the document's front matter
+    pub inject_front_matter_prefix: bool,
+    /// Chunk fenced code blocks separately with `CodeChunker`, leaving a
+    /// `[code block N]` placeholder in the prose
+    pub extract_code_blocks: bool,
+    /// Deepest heading level that starts a new section; deeper headings
+    /// are kept as paragraph text (`1` splits on H1 only, `6` on all)
+    pub min_split_heading_level: usize,
+    /// Deepest heading level used for `ChunkMetadata::section`; sections
+    /// under a deeper heading take the nearest shallower one
+    pub max_heading_level_in_metadata: usize,
+}
+
+impl Default for DocumentChunkerConfig {
+    fn default() -> Self {
+        Self {
+            inject_front_matter_prefix: false,
+            extract_code_blocks: false,
+            min_split_heading_level: 2,
+            max_heading_level_in_metadata: 6,
+        }
+    }
+}
+
+/// Metadata block at the start of a document.
+///
+/// YAML front matter is delimited by `---` lines, TOML front matter by
+/// `+++` (or `---`, if the block is not valid YAML).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    pub tags: Vec<String>,
+    /// Text between the delimiters
+    pub raw: String,
+}
+
+impl FrontMatter {
+    /// One-line summary such as `[Release Notes by Jane Doe, 2024-03-01]`,
+    /// or `None` if there is no title, author or date.
+    pub fn summary(&self) -> Option<String> {
+        let mut summary = self.title.clone().unwrap_or_default();
+        if let Some(author) = &self.author {
+            if !summary.is_empty() {
+                summary.push(' ');
+            }
+            summary.push_str("by ");
+            summary.push_str(author);
+        }
+        if let Some(date) = &self.date {
+            if !summary.is_empty() {
+                summary.push_str(", ");
+            }
+            summary.push_str(date);
+        }
+        (!summary.is_empty()).then(|| format!("[{}]", summary))
+    }
+
+    fn from_yaml(raw: &str) -> Option<Self> {
+        let serde_yaml::Value::Mapping(fields) = serde_yaml::from_str(raw).ok()? else {
+            return None;
+        };
+        let text = |key: &str| match fields.get(key)? {
+            serde_yaml::Value::String(s) => Some(s.clone()),
+            serde_yaml::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+        let tags = match fields.get("tags") {
+            Some(serde_yaml::Value::Sequence(tags)) => {
+                tags.iter().filter_map(|t| t.as_str().map(String::from)).collect()
+            }
+            Some(serde_yaml::Value::String(tags)) => split_tags(tags),
+            _ => Vec::new(),
+        };
+        Some(Self {
+            title: text("title"),
+            author: text("author"),
+            date: text("date"),
+            tags,
+            raw: raw.to_string(),
+        })
+    }
+
+    fn from_toml(raw: &str) -> Option<Self> {
+        let fields: toml::Table = toml::from_str(raw).ok()?;
+        let text = |key: &str| match fields.get(key)? {
+            toml::Value::String(s) => Some(s.clone()),
+            toml::Value::Datetime(d) => Some(d.to_string()),
+            _ => None,
+        };
+        let tags = match fields.get("tags") {
+            Some(toml::Value::Array(tags)) => tags.iter().filter_map(|t| t.as_str().map(String::from)).collect(),
+            Some(toml::Value::String(tags)) => split_tags(tags),
+            _ => Vec::new(),
+        };
+        Some(Self {
+            title: text("title"),
+            author: text("author"),
+            date: text("date"),
+            tags,
+            raw: raw.to_string(),
+        })
+    }
+}
+
+/// Split a comma-separated tag list.
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect()
+}
+
 /// Document chunker for markdown, wiki, and structured text content.
 ///
 /// This chunker is aware of document structure like headings, code blocks,
-/// and lists, ensuring chunks respect these boundaries.
+/// and lists, ensuring chunks respect these boundaries. Front matter is
+/// stripped before splitting and its author and tags are copied to every
+/// chunk's metadata.
 pub struct DocumentChunker {
     /// Regex for matching markdown headings
     heading_regex: Regex,
-    /// Regex for matching code blocks (reserved for future use)
-    #[allow(dead_code)]
+    /// Regex for matching fenced code blocks and their info string
     code_block_regex: Regex,
+    code_chunker: CodeChunker,
+    config: DocumentChunkerConfig,
 }
 
 impl DocumentChunker {
     /// Create a new document chunker.
     pub fn new() -> Self {
+        Self::with_config(DocumentChunkerConfig::default())
+    }
+
+    /// Create a document chunker with the given configuration.
+    pub fn with_config(config: DocumentChunkerConfig) -> Self {
         Self {
             heading_regex: Regex::new(r"(?m)^(#{1,6})\s+(.+)$").unwrap(),
-            code_block_regex: Regex::new(r"(?s)```[\w]*\n.*?```").unwrap(),
+            code_block_regex: Regex::new(r"(?ms)^```[ \t]*([\w+#.-]*)[^\n]*\n(.*?)^```[ \t]*$").unwrap(),
+            code_chunker: CodeChunker::new(),
+            config,
+        }
+    }
+
+    /// Replace fenced code blocks with `[code block N]` placeholders.
+    ///
+    /// Returns the prose, the extracted blocks, and an offset map from the
+    /// prose back to `content`.
+    fn extract_code_blocks(&self, content: &str) -> (String, Vec<CodeBlock>, OffsetMap) {
+        let mut prose = String::with_capacity(content.len());
+        let mut blocks = Vec::new();
+        let mut offsets = OffsetMap::default();
+        let mut last_end = 0;
+
+        for caps in self.code_block_regex.captures_iter(content) {
+            let (fence, code) = (caps.get(0).unwrap(), caps.get(2).unwrap());
+            if code.as_str().trim().is_empty() {
+                continue;
+            }
+            let info = caps.get(1).map_or("", |m| m.as_str());
+            let language = language_from_name(info)
+                .or_else(|| detect_from_content(code.as_str()).map(|(language, _)| language))
+                .map(String::from);
+
+            prose.push_str(&content[last_end..fence.start()]);
+            prose.push_str(&format!("[code block {}]", blocks.len() + 1));
+            offsets.push(prose.len(), fence.end());
+            last_end = fence.end();
+
+            blocks.push(CodeBlock {
+                language,
+                code: code.as_str().to_string(),
+                start: code.start(),
+            });
+        }
+        prose.push_str(&content[last_end..]);
+
+        (prose, blocks, offsets)
+    }
+
+    /// Chunk an extracted code block with `CodeChunker`.
+    fn chunk_code_block(&self, item: &SourceItem, block: &CodeBlock, offset: usize, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let language = block.language.as_deref().unwrap_or("unknown");
+        let block_item = SourceItem {
+            content_type: format!("text/code:{}", language),
+            content: block.code.clone(),
+            ..item.clone()
+        };
+        let mut config = config.clone();
+        config.language = block.language.clone();
+
+        let mut chunks = self.code_chunker.chunk(&block_item, &config)?;
+        for chunk in &mut chunks {
+            chunk.start_index += offset;
+            chunk.end_index += offset;
+            chunk.metadata.is_code_block = true;
+            chunk.metadata.language = block.language.clone();
         }
+        Ok(chunks)
+    }
+
+    /// Split front matter off the start of a document.
+    ///
+    /// Returns the parsed front matter, if any, and the rest of the
+    /// document. A block that is not valid YAML or TOML is left in place.
+    pub fn extract_front_matter(content: &str) -> (Option<FrontMatter>, &str) {
+        let Some(delimiter) = ["---", "+++"].into_iter().find(|d| {
+            content.strip_prefix(d).is_some_and(|rest| rest.starts_with('\n') || rest.starts_with("\r\n"))
+        }) else {
+            return (None, content);
+        };
+
+        let after_open = &content[delimiter.len()..].trim_start_matches('\r')[1..];
+        // The closing delimiter must be on its own line
+        let mut offset = 0;
+        for line in after_open.split_inclusive('\n') {
+            if line.trim_end() == delimiter {
+                let raw = &after_open[..offset];
+                let body = &after_open[offset + line.len()..];
+                let front_matter = if delimiter == "+++" {
+                    FrontMatter::from_toml(raw)
+                } else {
+                    FrontMatter::from_yaml(raw).or_else(|| FrontMatter::from_toml(raw))
+                };
+                return match front_matter {
+                    Some(front_matter) => (Some(front_matter), body),
+                    None => (None, content),
+                };
+            }
+            offset += line.len();
+        }
+
+        (None, content)
     }
 
     /// Split document into sections based on headings.
     fn split_by_headings(&self, content: &str) -> Vec<Section> {
         let mut sections = Vec::new();
         let mut current_section = Section::new(None, 0, 0);
+        // (level, title) of the headings enclosing the current line
+        let mut heading_stack: Vec<(usize, String)> = Vec::new();
         let mut in_code_block = false;
         let mut line_start = 0;
 
@@ -44,20 +265,33 @@ impl DocumentChunker {
 
             // Check for heading (not in code block)
             if !in_code_block {
-                if let Some(caps) = self.heading_regex.captures(line) {
+                let split_heading = self
+                    .heading_regex
+                    .captures(line)
+                    .filter(|caps| caps[1].len() <= self.config.min_split_heading_level);
+                if let Some(caps) = split_heading {
                     // Save current section if it has content
                     if !current_section.content.trim().is_empty() {
                         sections.push(current_section);
                     }
 
                     let level = caps.get(1).map(|m| m.as_str().len()).unwrap_or(1);
-                    let title = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+                    let title = caps.get(2).map(|m| m.as_str().trim()).unwrap_or("");
+
+                    heading_stack.retain(|(l, _)| *l < level);
+                    heading_stack.push((level, title.to_string()));
 
                     current_section = Section::new(
                         Some(title.to_string()),
                         level,
                         line_start,
                     );
+                    current_section.hierarchy = heading_stack.iter().map(|(_, t)| t.clone()).collect();
+                    current_section.metadata_heading = heading_stack
+                        .iter()
+                        .rev()
+                        .find(|(l, _)| *l <= self.config.max_heading_level_in_metadata)
+                        .map(|(_, t)| t.clone());
                     current_section.content.push_str(line);
                     current_section.content.push('\n');
                     line_start = line_end;
@@ -79,11 +313,11 @@ impl DocumentChunker {
     }
 
     /// Split a section into smaller chunks if it exceeds the token limit.
-    fn split_section(&self, section: &Section, chunk_size: usize) -> Vec<(String, Option<String>)> {
+    fn split_section(&self, section: &Section, chunk_size: usize) -> Vec<String> {
         let tokens = count_tokens(&section.content);
 
         if tokens <= chunk_size {
-            return vec![(section.content.clone(), section.heading.clone())];
+            return vec![section.content.clone()];
         }
 
         // Split by paragraphs first
@@ -105,7 +339,7 @@ impl DocumentChunker {
             if para_tokens > chunk_size {
                 // Flush current chunk
                 if !current_chunk.is_empty() {
-                    chunks.push((current_chunk, section.heading.clone()));
+                    chunks.push(current_chunk);
                     current_chunk = String::new();
                     current_tokens = 0;
                 }
@@ -116,7 +350,7 @@ impl DocumentChunker {
                     let sent_tokens = count_tokens(&sentence);
 
                     if current_tokens + sent_tokens > chunk_size && !current_chunk.is_empty() {
-                        chunks.push((current_chunk, section.heading.clone()));
+                        chunks.push(current_chunk);
                         current_chunk = String::new();
                         current_tokens = 0;
                     }
@@ -127,7 +361,7 @@ impl DocumentChunker {
                 }
             } else if current_tokens + para_tokens > chunk_size {
                 // Current chunk is full
-                chunks.push((current_chunk, section.heading.clone()));
+                chunks.push(current_chunk);
                 current_chunk = para;
                 current_tokens = para_tokens;
             } else {
@@ -141,11 +375,11 @@ impl DocumentChunker {
 
         // Last chunk
         if !current_chunk.is_empty() {
-            chunks.push((current_chunk, section.heading.clone()));
+            chunks.push(current_chunk);
         }
 
         // Prepend header to first chunk if we split
-        if let (Some(prefix), Some((first, _))) = (header_prefix, chunks.first_mut()) {
+        if let (Some(prefix), Some(first)) = (header_prefix, chunks.first_mut()) {
             *first = format!("{}{}", prefix, first);
         }
 
@@ -186,9 +420,41 @@ impl DocumentChunker {
     }
 }
 
+/// A fenced code block extracted from a document.
+struct CodeBlock {
+    language: Option<String>,
+    code: String,
+    /// Byte offset of the code (after the opening fence) in the document
+    start: usize,
+}
+
+/// Maps byte offsets in text with code blocks replaced back to the original.
+#[derive(Default)]
+struct OffsetMap {
+    /// (offset after a placeholder, offset after the replaced block)
+    points: Vec<(usize, usize)>,
+}
+
+impl OffsetMap {
+    fn push(&mut self, replaced: usize, original: usize) {
+        self.points.push((replaced, original));
+    }
+
+    fn original(&self, offset: usize) -> usize {
+        match self.points.iter().rev().find(|(replaced, _)| *replaced <= offset) {
+            Some(&(replaced, original)) => original + (offset - replaced),
+            None => offset,
+        }
+    }
+}
+
 /// A section of a document defined by a heading.
 struct Section {
     heading: Option<String>,
+    /// Heading reported in chunk metadata, per `max_heading_level_in_metadata`
+    metadata_heading: Option<String>,
+    /// Titles of the enclosing headings, ending with `heading`
+    hierarchy: Vec<String>,
     level: usize,
     #[allow(dead_code)]
     start_byte: usize,
@@ -199,6 +465,8 @@ impl Section {
     fn new(heading: Option<String>, level: usize, start_byte: usize) -> Self {
         Self {
             heading,
+            metadata_heading: None,
+            hierarchy: Vec::new(),
             level,
             start_byte,
             content: String::new(),
@@ -227,21 +495,39 @@ impl Chunker for DocumentChunker {
             return Ok(vec![]);
         }
 
+        let (front_matter, body) = Self::extract_front_matter(content);
+        let body_offset = content.len() - body.len();
+
+        let (prose, code_blocks, offsets) = if self.config.extract_code_blocks {
+            self.extract_code_blocks(body)
+        } else {
+            (body.to_string(), Vec::new(), OffsetMap::default())
+        };
+
         // Split into sections by headings
-        let sections = self.split_by_headings(content);
+        let sections = self.split_by_headings(&prose);
 
         // Split each section into chunks
         let mut chunks = Vec::new();
         let mut chunk_index = 0;
         let mut current_byte = 0;
+        // (heading level, first chunk id) of the enclosing sections
+        let mut section_stack: Vec<(usize, Uuid)> = Vec::new();
 
         for section in sections {
             let section_chunks = self.split_section(&section, config.chunk_size);
 
-            for (chunk_text, heading) in section_chunks {
+            // Sub-section chunks point to the first chunk of their parent section
+            if section.heading.is_some() {
+                section_stack.retain(|&(level, _)| level < section.level);
+            }
+            let parent_chunk_id = section_stack.last().map(|&(_, id)| id);
+            let first_chunk = chunks.len();
+
+            for chunk_text in section_chunks {
                 let token_count = count_tokens(&chunk_text);
-                let start_index = current_byte;
-                let end_index = start_index + chunk_text.len();
+                let start_index = body_offset + offsets.original(current_byte);
+                let end_index = body_offset + offsets.original(current_byte + chunk_text.len());
 
                 let mut chunk = Chunk::new(
                     item.id,
@@ -256,13 +542,50 @@ impl Chunker for DocumentChunker {
 
                 // Add document metadata
                 chunk.metadata = ChunkMetadata::for_document(
-                    heading.as_deref(),
+                    section.metadata_heading.as_deref(),
                     item.extract_path(),
                 );
+                chunk.metadata.section_hierarchy = section.hierarchy.clone();
+                if let Some(front_matter) = &front_matter {
+                    chunk.metadata.author = front_matter.author.clone();
+                    chunk.metadata.tags = front_matter.tags.clone();
+                }
+                chunk.parent_chunk_id = parent_chunk_id;
 
+                current_byte += chunk.content.len();
                 chunks.push(chunk);
                 chunk_index += 1;
-                current_byte = end_index;
+            }
+
+            if let Some(first) = chunks.get(first_chunk).filter(|_| section.heading.is_some()) {
+                section_stack.push((section.level, first.id));
+            }
+        }
+
+        if !code_blocks.is_empty() {
+            for block in &code_blocks {
+                let mut block_chunks = self.chunk_code_block(item, block, body_offset + block.start, config)?;
+                if let Some(front_matter) = &front_matter {
+                    for chunk in &mut block_chunks {
+                        chunk.metadata.author = front_matter.author.clone();
+                        chunk.metadata.tags = front_matter.tags.clone();
+                    }
+                }
+                chunks.extend(block_chunks);
+            }
+
+            // Interleave code with the prose around it
+            chunks.sort_by_key(|chunk| chunk.start_index);
+            for (index, chunk) in chunks.iter_mut().enumerate() {
+                chunk.chunk_index = index;
+            }
+        }
+
+        if self.config.inject_front_matter_prefix {
+            let summary = front_matter.as_ref().and_then(FrontMatter::summary);
+            if let (Some(summary), Some(first)) = (summary, chunks.first_mut()) {
+                first.content = format!("{}\n{}", summary, first.content);
+                first.token_count = count_tokens(&first.content);
             }
         }
 
@@ -274,7 +597,6 @@ impl Chunker for DocumentChunker {
 mod tests {
     use super::*;
     use crate::types::SourceKind;
-    use uuid::Uuid;
 
     fn create_doc_item(content: &str) -> SourceItem {
         SourceItem {
@@ -285,9 +607,17 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
+    fn split_on_all_headings() -> DocumentChunker {
+        DocumentChunker::with_config(DocumentChunkerConfig {
+            min_split_heading_level: 6,
+            ..Default::default()
+        })
+    }
+
     #[test]
     fn test_heading_splitting() {
         let chunker = DocumentChunker::new();
@@ -321,4 +651,173 @@ This is the installation section.
         let chunks = chunker.chunk(&item, &config).unwrap();
         assert_eq!(chunks.len(), 1);
     }
+
+    #[test]
+    fn test_subsections_point_to_parent_section() {
+        let chunker = split_on_all_headings();
+        let content = "Preamble text.\n\n# Guide\n\nOverview.\n\n## Install\n\nRun it.\n\n### Linux\n\nUse apt.\n\n## Usage\n\nCall it.\n\n# Reference\n\nDetails.\n";
+        let item = create_doc_item(content);
+        let config = ChunkConfig::with_size(1000);
+
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        let by_section = |name: &str| chunks.iter().find(|c| c.metadata.section.as_deref() == Some(name)).unwrap();
+
+        assert_eq!(chunks[0].parent_chunk_id, None);
+        assert_eq!(by_section("Guide").parent_chunk_id, None);
+        assert_eq!(by_section("Install").parent_chunk_id, Some(by_section("Guide").id));
+        assert_eq!(by_section("Linux").parent_chunk_id, Some(by_section("Install").id));
+        assert_eq!(by_section("Usage").parent_chunk_id, Some(by_section("Guide").id));
+        assert_eq!(by_section("Reference").parent_chunk_id, None);
+    }
+
+    #[test]
+    fn test_min_split_heading_level() {
+        let content = "# Guide\n\nOverview.\n\n## Install\n\nRun it.\n\n### Linux\n\nUse apt.\n\n#### Debian\n\nUse apt-get.\n";
+        let item = create_doc_item(content);
+        let config = ChunkConfig::with_size(1000);
+        let sections = |chunker: DocumentChunker| {
+            chunker
+                .chunk(&item, &config)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.metadata.section.unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+
+        let chunker = DocumentChunker::with_config(DocumentChunkerConfig {
+            min_split_heading_level: 3,
+            ..Default::default()
+        });
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[2].content.contains("#### Debian\n\nUse apt-get."));
+        assert_eq!(sections(chunker), ["Guide", "Install", "Linux"]);
+
+        assert_eq!(sections(DocumentChunker::new()), ["Guide", "Install"]);
+        let h1_only = DocumentChunker::with_config(DocumentChunkerConfig {
+            min_split_heading_level: 1,
+            ..Default::default()
+        });
+        assert_eq!(sections(h1_only), ["Guide"]);
+        assert_eq!(sections(split_on_all_headings()).len(), 4);
+
+        let shallow_metadata = DocumentChunker::with_config(DocumentChunkerConfig {
+            min_split_heading_level: 6,
+            max_heading_level_in_metadata: 2,
+            ..Default::default()
+        });
+        assert_eq!(sections(shallow_metadata), ["Guide", "Install", "Install", "Install"]);
+    }
+
+    #[test]
+    fn test_section_hierarchy() {
+        let chunker = split_on_all_headings();
+        let content = "# Introduction\n\nHello.\n\n## Getting Started\n\nSteps.\n\n### Prerequisites\n\nRust.\n\n## Configuration\n\nOptions.\n";
+        let item = create_doc_item(content);
+        let config = ChunkConfig::with_size(1000);
+
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        let hierarchy = |name: &str| {
+            chunks
+                .iter()
+                .find(|c| c.metadata.section.as_deref() == Some(name))
+                .map(|c| c.metadata.section_hierarchy.clone())
+                .unwrap()
+        };
+
+        assert_eq!(hierarchy("Introduction"), vec!["Introduction"]);
+        assert_eq!(hierarchy("Getting Started"), vec!["Introduction", "Getting Started"]);
+        assert_eq!(hierarchy("Prerequisites"), vec!["Introduction", "Getting Started", "Prerequisites"]);
+        assert_eq!(hierarchy("Configuration"), vec!["Introduction", "Configuration"]);
+    }
+
+    #[test]
+    fn test_yaml_front_matter() {
+        let content = "---\ntitle: Release Notes\nauthor: Jane Doe\ndate: 2024-03-01\ntags: [release, changelog]\n---\n# Changes\n\nFixed bugs.\n";
+        let (front_matter, body) = DocumentChunker::extract_front_matter(content);
+        let front_matter = front_matter.unwrap();
+
+        assert_eq!(front_matter.title.as_deref(), Some("Release Notes"));
+        assert_eq!(front_matter.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(front_matter.date.as_deref(), Some("2024-03-01"));
+        assert_eq!(front_matter.tags, vec!["release", "changelog"]);
+        assert!(front_matter.raw.starts_with("title: Release Notes\n"));
+        assert_eq!(body, "# Changes\n\nFixed bugs.\n");
+
+        let chunker = DocumentChunker::with_config(DocumentChunkerConfig {
+            inject_front_matter_prefix: true,
+            ..Default::default()
+        });
+        let chunks = chunker.chunk(&create_doc_item(content), &ChunkConfig::with_size(512)).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].content.starts_with("[Release Notes by Jane Doe, 2024-03-01]\n# Changes"));
+        assert_eq!(chunks[0].metadata.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(chunks[0].metadata.tags, vec!["release", "changelog"]);
+        assert_eq!(chunks[0].start_index, content.len() - body.len());
+    }
+
+    #[test]
+    fn test_toml_front_matter() {
+        let content = "+++\ntitle = \"Design\"\ndate = 2024-01-15\ntags = \"rfc, storage\"\n+++\nBody text.\n";
+        let (front_matter, body) = DocumentChunker::extract_front_matter(content);
+        let front_matter = front_matter.unwrap();
+
+        assert_eq!(front_matter.title.as_deref(), Some("Design"));
+        assert_eq!(front_matter.author, None);
+        assert_eq!(front_matter.date.as_deref(), Some("2024-01-15"));
+        assert_eq!(front_matter.tags, vec!["rfc", "storage"]);
+        assert_eq!(front_matter.summary().as_deref(), Some("[Design, 2024-01-15]"));
+        assert_eq!(body, "Body text.\n");
+
+        // TOML between `---` delimiters is accepted too
+        let (front_matter, _) = DocumentChunker::extract_front_matter("---\nauthor = \"Sam\"\n---\nText\n");
+        assert_eq!(front_matter.unwrap().author.as_deref(), Some("Sam"));
+
+        // Without injection, chunk content is untouched
+        let chunks = DocumentChunker::new().chunk(&create_doc_item(content), &ChunkConfig::with_size(512)).unwrap();
+        assert_eq!(chunks[0].content, "Body text.\n");
+    }
+
+    #[test]
+    fn test_no_front_matter() {
+        for content in ["# Title\n\nText\n", "---\n\nA horizontal rule, not front matter.\n"] {
+            let (front_matter, body) = DocumentChunker::extract_front_matter(content);
+            assert!(front_matter.is_none());
+            assert_eq!(body, content);
+        }
+    }
+
+    #[test]
+    fn test_code_blocks_chunked_separately() {
+        let content = "# Setup\n\nInstall the crate first.\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\nThen run it.\n";
+        let chunker = DocumentChunker::with_config(DocumentChunkerConfig {
+            extract_code_blocks: true,
+            ..Default::default()
+        });
+        let item = create_doc_item(content);
+        let chunks = chunker.chunk(&item, &ChunkConfig::default()).unwrap();
+
+        let code: Vec<_> = chunks.iter().filter(|c| c.metadata.is_code_block).collect();
+        assert_eq!(code.len(), 1);
+        assert_eq!(code[0].metadata.language.as_deref(), Some("rust"));
+        assert!(code[0].content.contains("println!"));
+        assert_eq!(&content[code[0].start_index..code[0].end_index], code[0].content);
+
+        let prose = chunks.iter().find(|c| !c.metadata.is_code_block).unwrap();
+        assert!(prose.content.contains("[code block 1]"));
+        assert!(!prose.content.contains("println!"));
+        assert!(prose.chunk_index < code[0].chunk_index);
+
+        let indices: Vec<_> = chunks.iter().map(|c| c.chunk_index).collect();
+        assert_eq!(indices, (0..chunks.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_code_blocks_left_inline_by_default() {
+        let content = "# Setup\n\n```python\nprint('hi')\n```\n";
+        let chunks = DocumentChunker::new().chunk(&create_doc_item(content), &ChunkConfig::default()).unwrap();
+
+        assert!(chunks.iter().all(|c| !c.metadata.is_code_block));
+        assert!(chunks.iter().any(|c| c.content.contains("print('hi')")));
+    }
 }
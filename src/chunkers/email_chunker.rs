@@ -0,0 +1,460 @@
+//! Email chunker for RFC 2822 / MIME messages and threads.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use mailparse::{MailHeaderMap, ParsedMail};
+use regex::Regex;
+
+use super::base::Chunker;
+use super::sentence_chunker::SentenceChunker;
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
+
+lazy_static! {
+    static ref MBOX_SEPARATOR_RE: Regex =
+        Regex::new(r"(?m)^From \S+ +(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun) .*$").unwrap();
+    static ref SCRIPT_STYLE_RE: Regex = Regex::new(r"(?is)<(script|style)[^>]*>.*?</(script|style)>").unwrap();
+    static ref BLOCK_TAG_RE: Regex = Regex::new(r"(?i)<(br|/p|/div|/li|/tr|/h[1-6])[^>]*>").unwrap();
+    static ref TAG_RE: Regex = Regex::new(r"(?s)<[^>]+>").unwrap();
+    static ref BLANK_LINES_RE: Regex = Regex::new(r"\n{3,}").unwrap();
+    static ref MESSAGE_ID_RE: Regex = Regex::new(r"<[^<>\s]+>").unwrap();
+}
+
+/// Email chunker for single messages and mbox-style threads.
+///
+/// Headers are copied into chunk metadata and the plain-text body is chunked
+/// with the `SentenceChunker`. HTML-only messages are reduced to text first.
+/// When the content holds several messages, they are grouped into threads
+/// using `References`/`In-Reply-To` chains or the Outlook `Thread-Index`.
+pub struct EmailChunker {
+    /// Sentence chunker used for message bodies
+    sentence_chunker: SentenceChunker,
+    /// Drop quoted reply lines instead of emitting them as quoted chunks
+    exclude_quoted: bool,
+}
+
+impl EmailChunker {
+    /// Create a new email chunker that keeps and marks quoted text.
+    pub fn new() -> Self {
+        Self {
+            sentence_chunker: SentenceChunker::new(),
+            exclude_quoted: false,
+        }
+    }
+
+    /// Exclude quoted reply text (lines starting with `>`) from the output.
+    pub fn with_exclude_quoted(mut self, exclude: bool) -> Self {
+        self.exclude_quoted = exclude;
+        self
+    }
+
+    /// Split raw content into individual messages.
+    ///
+    /// Content in mbox format is split on `From ` separator lines; anything
+    /// else is treated as a single message. Each message is returned with its
+    /// byte offset in `content`.
+    fn split_messages<'a>(&self, content: &'a str) -> Vec<(usize, &'a str)> {
+        let starts: Vec<usize> = MBOX_SEPARATOR_RE
+            .find_iter(content)
+            .map(|m| m.start())
+            .collect();
+
+        if starts.is_empty() {
+            return vec![(0, content)];
+        }
+
+        let mut messages = Vec::new();
+        for (i, &start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(content.len());
+            // Skip the separator line itself
+            let body_start = content[start..end]
+                .find('\n')
+                .map(|n| start + n + 1)
+                .unwrap_or(end);
+            let message = &content[body_start..end];
+            if !message.trim().is_empty() {
+                messages.push((body_start, message));
+            }
+        }
+        messages
+    }
+
+    /// Parse a raw message starting at `offset` in the source into an `Email`.
+    fn parse_email(&self, raw: &str, offset: usize) -> Option<Email> {
+        let parsed = mailparse::parse_mail(raw.as_bytes()).ok()?;
+        let body_offset = mailparse::parse_headers(raw.as_bytes())
+            .map(|(_, body_start)| offset + body_start)
+            .unwrap_or(offset);
+        let end_offset = offset + raw.len();
+        let headers = &parsed.headers;
+
+        let header = |name: &str| {
+            headers
+                .get_first_value(name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let date = header("Date")
+            .and_then(|d| mailparse::dateparse(&d).ok())
+            .and_then(|secs| Utc.timestamp_opt(secs, 0).single());
+
+        let references = header("References")
+            .map(|r| {
+                MESSAGE_ID_RE
+                    .find_iter(&r)
+                    .map(|m| m.as_str().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let body = extract_body(&parsed).unwrap_or_default();
+
+        Some(Email {
+            message_id: header("Message-ID"),
+            from: header("From"),
+            to: header("To")
+                .map(|t| t.split(',').map(|a| a.trim().to_string()).collect())
+                .unwrap_or_default(),
+            subject: header("Subject"),
+            date,
+            in_reply_to: header("In-Reply-To"),
+            references,
+            thread_index: header("Thread-Index"),
+            body,
+            body_offset,
+            end_offset,
+        })
+    }
+
+    /// Assign a thread id to every email.
+    ///
+    /// The thread id is the first message id in the reply chain, or the
+    /// conversation prefix of `Thread-Index` for Outlook messages.
+    fn assign_threads(&self, emails: &[Email]) -> Vec<String> {
+        let mut root_of: HashMap<String, String> = HashMap::new();
+        let mut threads = Vec::with_capacity(emails.len());
+
+        for (i, email) in emails.iter().enumerate() {
+            let parent = email
+                .references
+                .first()
+                .cloned()
+                .or_else(|| email.in_reply_to.clone());
+
+            let root = if let Some(index) = &email.thread_index {
+                // The first 22 bytes (30 base64 chars) identify the conversation
+                index.chars().take(30).collect::<String>()
+            } else if let Some(parent) = parent {
+                root_of.get(&parent).cloned().unwrap_or(parent)
+            } else {
+                email
+                    .message_id
+                    .clone()
+                    .unwrap_or_else(|| format!("message-{}", i))
+            };
+
+            if let Some(id) = &email.message_id {
+                root_of.insert(id.clone(), root.clone());
+            }
+            threads.push(root);
+        }
+
+        threads
+    }
+
+    /// Split a body into runs of quoted and unquoted lines.
+    fn split_quoted(&self, body: &str) -> Vec<(String, bool)> {
+        let mut segments: Vec<(String, bool)> = Vec::new();
+
+        for line in body.lines() {
+            let quoted = line.trim_start().starts_with('>');
+            match segments.last_mut() {
+                Some((text, q)) if *q == quoted => {
+                    text.push('\n');
+                    text.push_str(line);
+                }
+                _ => segments.push((line.to_string(), quoted)),
+            }
+        }
+
+        segments
+            .into_iter()
+            .filter(|(text, _)| !text.trim().is_empty())
+            .filter(|(_, quoted)| !(self.exclude_quoted && *quoted))
+            .collect()
+    }
+}
+
+/// A parsed email message.
+#[derive(Debug, Clone)]
+struct Email {
+    message_id: Option<String>,
+    from: Option<String>,
+    to: Vec<String>,
+    subject: Option<String>,
+    date: Option<DateTime<Utc>>,
+    in_reply_to: Option<String>,
+    references: Vec<String>,
+    thread_index: Option<String>,
+    body: String,
+    /// Byte offset of the body in the source content
+    body_offset: usize,
+    /// Byte offset of the end of the message in the source content
+    end_offset: usize,
+}
+
+/// Extract the text body, preferring `text/plain` over `text/html` parts.
+fn extract_body(mail: &ParsedMail) -> Option<String> {
+    if let Some(text) = find_part(mail, "text/plain") {
+        return Some(text);
+    }
+    find_part(mail, "text/html").map(|html| strip_html(&html))
+}
+
+fn find_part(mail: &ParsedMail, mimetype: &str) -> Option<String> {
+    if mail.subparts.is_empty() {
+        if mail.ctype.mimetype.eq_ignore_ascii_case(mimetype) {
+            return mail.get_body().ok();
+        }
+        return None;
+    }
+    mail.subparts.iter().find_map(|part| find_part(part, mimetype))
+}
+
+/// Reduce HTML to plain text by removing tags and decoding common entities.
+fn strip_html(html: &str) -> String {
+    let text = SCRIPT_STYLE_RE.replace_all(html, "");
+    let text = BLOCK_TAG_RE.replace_all(&text, "\n");
+    let text = TAG_RE.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    BLANK_LINES_RE.replace_all(text.trim(), "\n\n").to_string()
+}
+
+impl Default for EmailChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for EmailChunker {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn description(&self) -> &'static str {
+        "Email chunker for RFC 2822 / MIME messages with header metadata and thread grouping"
+    }
+
+    fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let content = &item.content;
+        if content.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let emails: Vec<Email> = self
+            .split_messages(content)
+            .into_iter()
+            .filter_map(|(offset, raw)| self.parse_email(raw, offset))
+            .collect();
+
+        if emails.is_empty() {
+            return self.sentence_chunker.chunk(item, config);
+        }
+
+        let threads = self.assign_threads(&emails);
+
+        // Keep messages of the same thread together, oldest first
+        let mut order: Vec<usize> = (0..emails.len()).collect();
+        order.sort_by(|&a, &b| {
+            threads[a]
+                .cmp(&threads[b])
+                .then_with(|| emails[a].date.cmp(&emails[b].date))
+        });
+
+        let mut chunks = Vec::new();
+
+        for idx in order {
+            let email = &emails[idx];
+            let mut cursor = email.body_offset;
+
+            for (segment, quoted) in self.split_quoted(&email.body) {
+                // Locate the segment in the source so chunk offsets point into
+                // `item.content`. Decoded or HTML bodies don't appear verbatim;
+                // their chunks are anchored at the start of the body instead.
+                let first_line = segment.lines().next().unwrap_or("");
+                let base = content
+                    .get(cursor..email.end_offset)
+                    .and_then(|rest| rest.find(first_line))
+                    .map(|pos| cursor + pos)
+                    .unwrap_or(email.body_offset);
+                cursor = cursor.max(base + first_line.len());
+
+                let mut body_item = item.clone();
+                body_item.content = segment;
+
+                for mut chunk in self.sentence_chunker.chunk(&body_item, config)? {
+                    chunk.chunk_index = chunks.len();
+                    chunk.start_index += base;
+                    chunk.end_index += base;
+                    chunk.metadata = ChunkMetadata {
+                        content_type: Some("email".to_string()),
+                        author: email.from.clone(),
+                        thread_id: Some(threads[idx].clone()),
+                        timestamp: email.date,
+                        subject: email.subject.clone(),
+                        recipients: email.to.clone(),
+                        message_id: email.message_id.clone(),
+                        in_reply_to: email.in_reply_to.clone(),
+                        references: email.references.clone(),
+                        is_quoted: quoted,
+                        ..Default::default()
+                    };
+                    chunks.push(chunk);
+                }
+            }
+        }
+
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+    use uuid::Uuid;
+
+    fn create_email_item(content: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::Email,
+            content_type: "message/rfc822".to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    const MULTIPART: &str = "From: Alice <alice@example.com>\r
+To: Bob <bob@example.com>, Carol <carol@example.com>\r
+Subject: Quarterly report\r
+Date: Mon, 1 Jan 2024 10:00:00 +0000\r
+Message-ID: <msg1@example.com>\r
+Content-Type: multipart/alternative; boundary=\"XYZ\"\r
+\r
+--XYZ\r
+Content-Type: text/html; charset=utf-8\r
+\r
+<html><body><p>The report is <b>attached</b>.</p></body></html>\r
+--XYZ--\r
+";
+
+    #[test]
+    fn test_headers_and_html_body() {
+        let chunker = EmailChunker::new();
+        let item = create_email_item(MULTIPART);
+        let chunks = chunker.chunk(&item, &ChunkConfig::with_size(500)).unwrap();
+
+        assert_eq!(chunks.len(), 1);
+        let meta = &chunks[0].metadata;
+        assert_eq!(meta.author.as_deref(), Some("Alice <alice@example.com>"));
+        assert_eq!(meta.subject.as_deref(), Some("Quarterly report"));
+        assert_eq!(meta.recipients.len(), 2);
+        assert!(meta.timestamp.is_some());
+        assert!(chunks[0].content.contains("The report is attached."));
+        assert!(!chunks[0].content.contains("<b>"));
+    }
+
+    #[test]
+    fn test_quoted_text_marked_or_excluded() {
+        let raw = "From: Bob <bob@example.com>\n\
+Subject: Re: Quarterly report\n\
+Message-ID: <msg2@example.com>\n\
+In-Reply-To: <msg1@example.com>\n\
+\n\
+Thanks, looks good to me.\n\
+\n\
+> The report is attached.\n\
+> Let me know what you think.\n";
+
+        let marked = EmailChunker::new()
+            .chunk(&create_email_item(raw), &ChunkConfig::with_size(500))
+            .unwrap();
+        assert_eq!(marked.len(), 2);
+        assert!(!marked[0].metadata.is_quoted);
+        assert!(marked[1].metadata.is_quoted);
+
+        let excluded = EmailChunker::new()
+            .with_exclude_quoted(true)
+            .chunk(&create_email_item(raw), &ChunkConfig::with_size(500))
+            .unwrap();
+        assert_eq!(excluded.len(), 1);
+        assert!(!excluded[0].content.contains('>'));
+    }
+
+    #[test]
+    fn test_thread_reconstruction() {
+        let mbox = "From alice@example.com Mon Jan  1 10:00:00 2024
+From: alice@example.com
+Subject: Deploy plan
+Date: Mon, 1 Jan 2024 10:00:00 +0000
+Message-ID: <a1@example.com>
+
+We deploy on Friday.
+From dave@example.com Mon Jan  1 10:30:00 2024
+From: dave@example.com
+Subject: Lunch
+Date: Mon, 1 Jan 2024 10:30:00 +0000
+Message-ID: <d1@example.com>
+
+Pizza today?
+From bob@example.com Mon Jan  1 11:00:00 2024
+From: bob@example.com
+Subject: Re: Deploy plan
+Date: Mon, 1 Jan 2024 11:00:00 +0000
+Message-ID: <b1@example.com>
+In-Reply-To: <a1@example.com>
+
+Friday works.
+From carol@example.com Mon Jan  1 12:00:00 2024
+From: carol@example.com
+Subject: Re: Deploy plan
+Date: Mon, 1 Jan 2024 12:00:00 +0000
+Message-ID: <c1@example.com>
+In-Reply-To: <b1@example.com>
+References: <a1@example.com> <b1@example.com>
+
+Agreed.
+";
+        let chunks = EmailChunker::new()
+            .chunk(&create_email_item(mbox), &ChunkConfig::with_size(500))
+            .unwrap();
+
+        assert_eq!(chunks.len(), 4);
+        let deploy: Vec<_> = chunks
+            .iter()
+            .filter(|c| c.metadata.thread_id.as_deref() == Some("<a1@example.com>"))
+            .collect();
+        assert_eq!(deploy.len(), 3);
+        // Thread members are contiguous and ordered by date
+        assert!(deploy[0].content.contains("We deploy on Friday."));
+        assert!(deploy[2].content.contains("Agreed."));
+        assert_eq!(deploy[2].chunk_index, deploy[0].chunk_index + 2);
+
+        // Offsets point into the mbox, not into each message body
+        for chunk in &chunks {
+            assert_eq!(&mbox[chunk.start_index..chunk.end_index], chunk.content);
+        }
+    }
+}
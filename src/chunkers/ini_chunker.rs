@@ -0,0 +1,250 @@
+//! INI chunker for configuration files split by section.
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::base::{count_tokens, Chunker};
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
+
+lazy_static! {
+    static ref SECTION_RE: Regex = Regex::new(r"^\s*\[([^\[\]]+)\]\s*(?:[;#].*)?$").unwrap();
+    static ref KEY_RE: Regex = Regex::new(r"^[^\s=:;#\[][^=:]*[=:]").unwrap();
+}
+
+/// INI chunker for `.ini`, `.cfg` and `.conf` files.
+///
+/// `[section]` headers are chunk boundaries, and consecutive small
+/// sections are grouped until `chunk_size` is reached. A section larger
+/// than `chunk_size` is split between its properties (`key = value` or
+/// `key: value`); indented continuation lines stay with their key.
+pub struct IniChunker;
+
+/// A section of the file, in bytes. The preamble before the first header
+/// has no name.
+#[derive(Debug)]
+struct IniSection {
+    name: Option<String>,
+    start: usize,
+    end: usize,
+    /// Start offsets of the header and each property, in order
+    entry_starts: Vec<usize>,
+}
+
+impl IniChunker {
+    /// Create a new INI chunker.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Split content into sections at `[section]` headers.
+    fn sections(content: &str) -> Vec<IniSection> {
+        let mut sections = vec![IniSection {
+            name: None,
+            start: 0,
+            end: 0,
+            entry_starts: vec![0],
+        }];
+        let mut offset = 0;
+
+        for line in content.split_inclusive('\n') {
+            if let Some(caps) = SECTION_RE.captures(line.trim_end()) {
+                sections.push(IniSection {
+                    name: Some(caps[1].trim().to_string()),
+                    start: offset,
+                    end: offset,
+                    entry_starts: vec![offset],
+                });
+            } else if KEY_RE.is_match(line) {
+                sections.last_mut().unwrap().entry_starts.push(offset);
+            }
+            offset += line.len();
+            sections.last_mut().unwrap().end = offset;
+        }
+
+        if content[..sections[0].end].trim().is_empty() {
+            sections.remove(0);
+        }
+        sections
+    }
+
+    /// Split an oversized section into byte ranges between properties, each
+    /// of at most `chunk_size` tokens unless one property alone is larger.
+    fn split_section(content: &str, section: &IniSection, chunk_size: usize) -> Vec<(usize, usize)> {
+        let mut bounds: Vec<usize> = section.entry_starts.clone();
+        bounds.push(section.end);
+        bounds.dedup();
+
+        let mut ranges = Vec::new();
+        let mut range_start = section.start;
+        let mut range_end = section.start;
+        for &entry_end in &bounds[1..] {
+            if range_end > range_start && count_tokens(&content[range_start..entry_end]) > chunk_size {
+                ranges.push((range_start, range_end));
+                range_start = range_end;
+            }
+            range_end = entry_end;
+        }
+        if range_end > range_start {
+            ranges.push((range_start, range_end));
+        }
+        ranges
+    }
+
+    /// Build a chunk for the bytes `start..end` covering `names` sections.
+    fn create_chunk(item: &SourceItem, start: usize, end: usize, names: Vec<String>, chunk_index: usize) -> Chunk {
+        let text = &item.content[start..end];
+        let mut chunk = Chunk::new(
+            item.id,
+            item.source_id,
+            item.source_kind,
+            text.to_string(),
+            count_tokens(text),
+            start,
+            end,
+            chunk_index,
+        );
+        chunk.metadata = ChunkMetadata {
+            content_type: Some("ini".to_string()),
+            language: Some("ini".to_string()),
+            path: item.extract_path().map(String::from),
+            section: (names.len() == 1).then(|| names[0].clone()),
+            keywords: names,
+            ..Default::default()
+        };
+        chunk
+    }
+}
+
+impl Default for IniChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for IniChunker {
+    fn name(&self) -> &'static str {
+        "ini"
+    }
+
+    fn description(&self) -> &'static str {
+        "INI chunker that splits configuration files at [section] headers"
+    }
+
+    fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let content = &item.content;
+        if content.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let chunk_size = config.effective_chunk_size();
+        let mut chunks = Vec::new();
+        // Byte range and section names grouped so far
+        let mut group: Option<(usize, usize)> = None;
+        let mut group_names: Vec<String> = Vec::new();
+
+        for section in Self::sections(content) {
+            let names: Vec<String> = section.name.iter().cloned().collect();
+            if let Some((group_start, group_end)) = group {
+                if count_tokens(&content[group_start..section.end]) <= chunk_size {
+                    group = Some((group_start, section.end));
+                    group_names.extend(names);
+                    continue;
+                }
+                let chunk = Self::create_chunk(item, group_start, group_end, std::mem::take(&mut group_names), chunks.len());
+                chunks.push(chunk);
+                group = None;
+            }
+
+            if count_tokens(&content[section.start..section.end]) <= chunk_size {
+                group = Some((section.start, section.end));
+                group_names = names;
+                continue;
+            }
+            for (start, end) in Self::split_section(content, &section, chunk_size) {
+                let chunk = Self::create_chunk(item, start, end, names.clone(), chunks.len());
+                chunks.push(chunk);
+            }
+        }
+
+        if let Some((group_start, group_end)) = group {
+            let chunk = Self::create_chunk(item, group_start, group_end, group_names, chunks.len());
+            chunks.push(chunk);
+        }
+
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+    use uuid::Uuid;
+
+    fn create_ini_item(content: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::CodeRepo,
+            content_type: "text/x-ini".to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({"path": "pytest.ini"}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    const PYTEST_INI: &str = "; Shared test settings\n[pytest]\nminversion = 7.0\naddopts =\n    -ra\n    --strict-markers\ntestpaths: tests integration\n\n[coverage:run]\nbranch = true\nsource = src\n\n[coverage:report]\n# Lines that never run\nexclude_lines =\n    pragma: no cover\n    raise NotImplementedError\nshow_missing = true\n";
+
+    #[test]
+    fn test_pytest_ini_sections() {
+        let item = create_ini_item(PYTEST_INI);
+
+        let grouped = IniChunker::new().chunk(&item, &ChunkConfig::default()).unwrap();
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].metadata.keywords, ["pytest", "coverage:run", "coverage:report"]);
+        assert_eq!(grouped[0].content, PYTEST_INI);
+
+        let chunks = IniChunker::new().chunk(&item, &ChunkConfig::with_size(30)).unwrap();
+        let sections: Vec<_> = chunks.iter().map(|c| c.metadata.section.as_deref()).collect();
+        assert_eq!(sections, [None, Some("pytest"), Some("coverage:run"), Some("coverage:report")]);
+        assert!(chunks[1].content.contains("addopts =\n    -ra\n    --strict-markers\ntestpaths: tests"));
+        for chunk in &chunks {
+            assert_eq!(&PYTEST_INI[chunk.start_index..chunk.end_index], chunk.content);
+            assert_eq!(chunk.metadata.language.as_deref(), Some("ini"));
+        }
+    }
+
+    #[test]
+    fn test_section_keywords_survive_enrichment() {
+        use crate::enrichment::{ChunkContext, ContextBuilder};
+
+        let item = create_ini_item(PYTEST_INI);
+        let chunk = IniChunker::new().chunk(&item, &ChunkConfig::default()).unwrap().remove(0);
+        let enriched = ContextBuilder::new().enrich(chunk, ChunkContext::new("pytest.ini", "ini"));
+
+        let keywords = &enriched.chunk.metadata.keywords;
+        assert_eq!(keywords[..3], ["pytest", "coverage:run", "coverage:report"]);
+        assert!(keywords.len() > 3);
+        assert!(!keywords[3..].iter().any(|k| k == "pytest"));
+    }
+
+    #[test]
+    fn test_large_section_split_on_keys() {
+        let mut content = String::from("[env]\n");
+        for i in 0..40 {
+            content.push_str(&format!("SERVICE_{i}_URL = https://service-{i}.internal.example.com:8443/api\n"));
+        }
+        let item = create_ini_item(&content);
+        let chunks = IniChunker::new().chunk(&item, &ChunkConfig::with_size(100)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert!(chunks[0].content.starts_with("[env]\n"));
+        for chunk in &chunks {
+            assert!(chunk.token_count <= 100);
+            assert!(chunk.content.ends_with("/api\n"));
+            assert_eq!(chunk.metadata.section.as_deref(), Some("env"));
+        }
+    }
+}
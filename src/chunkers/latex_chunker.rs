@@ -0,0 +1,338 @@
+//! LaTeX chunker for academic papers and technical documentation.
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::base::{count_tokens, Chunker};
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
+
+lazy_static! {
+    static ref SECTION_RE: Regex =
+        Regex::new(r"^\s*\\(section|subsection|subsubsection)\*?\s*(?:\[[^\]]*\])?\s*\{([^{}]*(?:\{[^{}]*\}[^{}]*)*)\}").unwrap();
+    static ref BEGIN_RE: Regex = Regex::new(r"^\s*\\begin\{((?:algorithm|proof|theorem|figure)\*?)\}").unwrap();
+    static ref LABEL_RE: Regex = Regex::new(r"\\label\{([^{}]*)\}").unwrap();
+    static ref COMMENT_RE: Regex = Regex::new(r"(?m)(^|[^\\])%.*$").unwrap();
+    static ref CITE_RE: Regex = Regex::new(r"\\[A-Za-z]*cite[A-Za-z]*\*?(?:\[[^\]]*\])*\{([^{}]*)\}").unwrap();
+    static ref COMMAND_WITH_ARG_RE: Regex = Regex::new(r"\\[A-Za-z]+\*?(?:\[[^\]]*\])?\{([^{}]*)\}").unwrap();
+    static ref COMMAND_RE: Regex = Regex::new(r"\\(?:[A-Za-z]+\*?|.)").unwrap();
+    static ref SPACES_RE: Regex = Regex::new(r"[ \t]+").unwrap();
+}
+
+/// LaTeX chunker.
+///
+/// Splits at `\section`, `\subsection` and `\subsubsection` headings and
+/// around `algorithm`, `proof`, `theorem` and `figure` environments.
+/// Sections larger than `chunk_size` are split further at blank lines.
+/// Chunk content keeps the LaTeX source, but sizes are measured on the
+/// text with commands stripped (`\textbf{x}` counts as `x`, `\cite{ref}`
+/// as `[ref]`). `\label{}` identifiers become chunk keywords.
+pub struct LatexChunker;
+
+/// A run of lines between boundaries, in bytes.
+#[derive(Debug)]
+struct LatexSegment {
+    start: usize,
+    end: usize,
+    /// Titles of the enclosing sections, outermost first
+    hierarchy: Vec<String>,
+}
+
+impl LatexChunker {
+    /// Create a new LaTeX chunker.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Text of a LaTeX fragment without comments, labels and commands.
+    pub fn strip_commands(latex: &str) -> String {
+        let mut text = COMMENT_RE.replace_all(latex, "$1").into_owned();
+        text = LABEL_RE.replace_all(&text, "").into_owned();
+        text = CITE_RE.replace_all(&text, "[$1]").into_owned();
+        // Unwrap innermost arguments first until nested commands are gone
+        loop {
+            let unwrapped = COMMAND_WITH_ARG_RE.replace_all(&text, "$1").into_owned();
+            if unwrapped == text {
+                break;
+            }
+            text = unwrapped;
+        }
+        text = COMMAND_RE.replace_all(&text, "").replace(['{', '}'], "");
+        SPACES_RE.replace_all(&text, " ").into_owned()
+    }
+
+    /// Split content into segments at headings and boundary environments.
+    fn segments(content: &str) -> Vec<LatexSegment> {
+        let mut segments = Vec::new();
+        let mut hierarchy: Vec<(usize, String)> = Vec::new();
+        let mut start = 0;
+        let mut offset = 0;
+        // `\end{...}` line closing the current boundary environment
+        let mut closing: Option<String> = None;
+
+        let titles = |hierarchy: &[(usize, String)]| hierarchy.iter().map(|(_, t)| t.clone()).collect();
+
+        for line in content.split_inclusive('\n') {
+            let line_start = offset;
+            offset += line.len();
+
+            if let Some(end) = &closing {
+                if line.trim_start().starts_with(end.as_str()) {
+                    segments.push(LatexSegment { start, end: offset, hierarchy: titles(&hierarchy) });
+                    start = offset;
+                    closing = None;
+                }
+                continue;
+            }
+
+            let heading = SECTION_RE.captures(line);
+            let environment = BEGIN_RE.captures(line);
+            if heading.is_none() && environment.is_none() {
+                continue;
+            }
+
+            if line_start > start {
+                segments.push(LatexSegment { start, end: line_start, hierarchy: titles(&hierarchy) });
+            }
+            start = line_start;
+
+            if let Some(caps) = heading {
+                let level = match &caps[1] {
+                    "section" => 1,
+                    "subsection" => 2,
+                    _ => 3,
+                };
+                hierarchy.retain(|(l, _)| *l < level);
+                hierarchy.push((level, Self::strip_commands(&caps[2]).trim().to_string()));
+            } else if let Some(caps) = environment {
+                closing = Some(format!("\\end{{{}}}", &caps[1]));
+            }
+        }
+
+        if start < content.len() {
+            segments.push(LatexSegment { start, end: content.len(), hierarchy: titles(&hierarchy) });
+        }
+        segments
+    }
+
+    /// Split a segment into byte ranges of at most `chunk_size` stripped
+    /// tokens, at blank lines. A single oversized paragraph is kept whole.
+    fn split_segment(content: &str, segment: &LatexSegment, chunk_size: usize) -> Vec<(usize, usize)> {
+        let text = &content[segment.start..segment.end];
+        if count_tokens(&Self::strip_commands(text)) <= chunk_size {
+            return vec![(segment.start, segment.end)];
+        }
+
+        let mut ranges = Vec::new();
+        let mut range_start = segment.start;
+        let mut range_end = segment.start;
+        let mut offset = segment.start;
+
+        for paragraph in text.split_inclusive("\n\n") {
+            let paragraph_end = offset + paragraph.len();
+            let grown = Self::strip_commands(&content[range_start..paragraph_end]);
+            if range_end > range_start && count_tokens(&grown) > chunk_size {
+                ranges.push((range_start, range_end));
+                range_start = range_end;
+            }
+            range_end = paragraph_end;
+            offset = paragraph_end;
+        }
+        if range_end > range_start {
+            ranges.push((range_start, range_end));
+        }
+        ranges
+    }
+}
+
+impl Default for LatexChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for LatexChunker {
+    fn name(&self) -> &'static str {
+        "latex"
+    }
+
+    fn description(&self) -> &'static str {
+        "LaTeX chunker that splits at sections and theorem, proof, algorithm and figure environments"
+    }
+
+    fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let content = &item.content;
+        if content.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let chunk_size = config.effective_chunk_size();
+        let mut chunks = Vec::new();
+
+        for segment in Self::segments(content) {
+            for (start, end) in Self::split_segment(content, &segment, chunk_size) {
+                let text = &content[start..end];
+                if text.trim().is_empty() {
+                    continue;
+                }
+
+                let mut chunk = Chunk::new(
+                    item.id,
+                    item.source_id,
+                    item.source_kind,
+                    text.to_string(),
+                    count_tokens(&Self::strip_commands(text)),
+                    start,
+                    end,
+                    chunks.len(),
+                );
+                chunk.metadata = ChunkMetadata {
+                    content_type: Some("latex".to_string()),
+                    language: Some("latex".to_string()),
+                    path: item.extract_path().map(String::from),
+                    section: segment.hierarchy.last().cloned(),
+                    section_hierarchy: segment.hierarchy.clone(),
+                    keywords: LABEL_RE.captures_iter(text).map(|caps| caps[1].to_string()).collect(),
+                    ..Default::default()
+                };
+                chunks.push(chunk);
+            }
+        }
+
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+    use uuid::Uuid;
+
+    fn create_latex_item(content: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::Document,
+            content_type: "text/x-latex".to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({"path": "paper/main.tex"}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    const PAPER: &str = r"\documentclass{article}
+\usepackage{amsmath}
+\title{Bounded Replication Lag}
+\begin{document}
+\maketitle
+\begin{abstract}
+We bound the \textbf{replication lag} of leader-based stores.
+\end{abstract}
+
+\section{Introduction}\label{sec:intro}
+Replicated stores trade consistency for latency~\cite{brewer2000}.
+We study the lag between leader and followers. % TODO: cite Raft
+
+\section{Model}\label{sec:model}
+A cluster has $n$ replicas and one \emph{leader}.
+\begin{theorem}\label{thm:bound}
+The lag is at most $2\delta$ under partial synchrony.
+\end{theorem}
+\begin{proof}
+Follows from the heartbeat interval.
+\end{proof}
+Further assumptions follow.
+
+\subsection{Failures}
+Followers may crash and recover.
+
+\section{Evaluation}\label{sec:eval}
+\begin{figure}[t]
+\centering
+\caption{Lag over time.}\label{fig:lag}
+\end{figure}
+The measured lag stays within the bound.
+\end{document}
+";
+
+    #[test]
+    fn test_strip_commands() {
+        let text = LatexChunker::strip_commands(r"The \textbf{bold \emph{claim}} holds~\cite{knuth84}.\label{eq:1} % note");
+        assert_eq!(text.trim(), "The bold claim holds~[knuth84].");
+    }
+
+    #[test]
+    fn test_paper_sections() {
+        let item = create_latex_item(PAPER);
+        let chunks = LatexChunker::new().chunk(&item, &ChunkConfig::default()).unwrap();
+
+        let sections: Vec<Option<&str>> = chunks.iter().map(|c| c.metadata.section.as_deref()).collect();
+        assert_eq!(
+            sections,
+            [
+                None,
+                Some("Introduction"),
+                Some("Model"),
+                Some("Model"),
+                Some("Model"),
+                Some("Model"),
+                Some("Failures"),
+                Some("Evaluation"),
+                Some("Evaluation"),
+                Some("Evaluation"),
+            ]
+        );
+
+        // The abstract stays in the front matter
+        assert!(chunks[0].content.contains("\\begin{abstract}"));
+        assert!(chunks[0].metadata.keywords.is_empty());
+
+        assert!(chunks[1].metadata.keywords == ["sec:intro"]);
+        assert!(chunks[3].content.starts_with("\\begin{theorem}") && chunks[3].content.trim_end().ends_with("\\end{theorem}"));
+        assert_eq!(chunks[3].metadata.keywords, ["thm:bound"]);
+        assert!(chunks[4].content.starts_with("\\begin{proof}"));
+        assert_eq!(chunks[6].metadata.section_hierarchy, ["Model", "Failures"]);
+        assert_eq!(chunks[8].metadata.keywords, ["fig:lag"]);
+
+        for chunk in &chunks {
+            assert_eq!(&PAPER[chunk.start_index..chunk.end_index], chunk.content);
+            assert_eq!(chunk.metadata.language.as_deref(), Some("latex"));
+            // Sizes are measured without the markup
+            assert_eq!(chunk.token_count, count_tokens(&LatexChunker::strip_commands(&chunk.content)));
+        }
+        assert!(chunks[1].token_count < count_tokens(&chunks[1].content));
+    }
+
+    #[test]
+    fn test_label_keywords_survive_enrichment() {
+        use crate::enrichment::{ChunkContext, ContextBuilder};
+
+        let item = create_latex_item(PAPER);
+        let chunks = LatexChunker::new().chunk(&item, &ChunkConfig::default()).unwrap();
+        let theorem = chunks.into_iter().nth(3).unwrap();
+        let enriched = ContextBuilder::new().enrich(theorem, ChunkContext::new("paper.tex", "latex"));
+
+        let keywords = &enriched.chunk.metadata.keywords;
+        assert_eq!(keywords[0], "thm:bound");
+        assert!(keywords.len() > 1, "{keywords:?}");
+    }
+
+    #[test]
+    fn test_large_section_split_at_paragraphs() {
+        let mut paper = String::from("\\section{Results}\n");
+        for i in 0..20 {
+            paper.push_str(&format!("Paragraph {i} reports the \\textbf{{median}} lag across all replicas and regions.\n\n"));
+        }
+        let item = create_latex_item(&paper);
+        let chunks = LatexChunker::new().chunk(&item, &ChunkConfig::with_size(60)).unwrap();
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.token_count <= 60);
+            assert!(chunk.content.ends_with("\n\n"));
+            assert_eq!(chunk.metadata.section.as_deref(), Some("Results"));
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! Log chunker for application and access logs.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::base::{count_tokens, Chunker};
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
+
+lazy_static! {
+    static ref BRACKETED_RE: Regex = Regex::new(r"\[([^\[\]]{8,40})\]").unwrap();
+    static ref ISO_RE: Regex = Regex::new(
+        r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?"
+    ).unwrap();
+    static ref EPOCH_RE: Regex = Regex::new(r"^(\d{10}(?:\.\d+)?|\d{13})\b").unwrap();
+    static ref LEVEL_RE: Regex = Regex::new(
+        r"(?i)\b(trace|debug|info|notice|warn|warning|error|err|crit|critical|fatal|alert|emerg)\b"
+    ).unwrap();
+    static ref LOGFMT_PAIR_RE: Regex = Regex::new(r#"(\w+)=("[^"]*"|\S+)"#).unwrap();
+}
+
+/// Keys commonly used for the timestamp in structured log lines.
+const TIME_KEYS: &[&str] = &["timestamp", "@timestamp", "time", "ts", "datetime", "date"];
+
+/// Keys commonly used for the severity in structured log lines.
+const LEVEL_KEYS: &[&str] = &["level", "lvl", "severity", "log_level", "loglevel"];
+
+/// Structured log format detected for a log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One JSON object per line (NDJSON)
+    JsonLines,
+    /// `key=value` pairs (logfmt)
+    Logfmt,
+    /// Free-form text lines (syslog, Apache, application logs)
+    PlainText,
+}
+
+/// Log chunker that groups log lines into time windows.
+///
+/// Lines are grouped so that a chunk never spans more than the configured
+/// time window. When no timestamps can be detected, lines are grouped by
+/// token count only. Continuation lines without a timestamp (e.g. stack
+/// traces) stay attached to the entry that precedes them.
+pub struct LogChunker {
+    /// Maximum time span covered by a single chunk
+    window: Duration,
+}
+
+impl LogChunker {
+    /// Create a new log chunker with a 5 minute window.
+    pub fn new() -> Self {
+        Self {
+            window: Duration::minutes(5),
+        }
+    }
+
+    /// Set the time window for grouping lines.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Detect the structured format of the log content from its first lines.
+    pub fn detect_format(content: &str) -> LogFormat {
+        let sample: Vec<&str> = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .take(10)
+            .collect();
+
+        if sample.is_empty() {
+            return LogFormat::PlainText;
+        }
+
+        let json_lines = sample
+            .iter()
+            .filter(|l| l.starts_with('{') && serde_json::from_str::<serde_json::Value>(l).is_ok())
+            .count();
+        if json_lines * 2 > sample.len() {
+            return LogFormat::JsonLines;
+        }
+
+        let logfmt_lines = sample
+            .iter()
+            .filter(|l| LOGFMT_PAIR_RE.find_iter(l).count() >= 2)
+            .count();
+        if logfmt_lines * 2 > sample.len() {
+            return LogFormat::Logfmt;
+        }
+
+        LogFormat::PlainText
+    }
+
+    /// Parse a single line into its timestamp and level.
+    fn parse_line(&self, line: &str, format: LogFormat) -> (Option<DateTime<Utc>>, Option<String>) {
+        match format {
+            LogFormat::JsonLines => match serde_json::from_str::<serde_json::Value>(line) {
+                Ok(value) => {
+                    let timestamp = TIME_KEYS
+                        .iter()
+                        .find_map(|k| value.get(*k))
+                        .and_then(|v| match v {
+                            serde_json::Value::String(s) => parse_timestamp(s),
+                            serde_json::Value::Number(n) => n.as_f64().and_then(from_epoch),
+                            _ => None,
+                        });
+                    let level = LEVEL_KEYS
+                        .iter()
+                        .find_map(|k| value.get(*k))
+                        .and_then(|v| v.as_str())
+                        .and_then(normalize_level);
+                    (timestamp, level)
+                }
+                Err(_) => (detect_timestamp(line), detect_level(line)),
+            },
+            LogFormat::Logfmt => {
+                let mut timestamp = None;
+                let mut level = None;
+                for cap in LOGFMT_PAIR_RE.captures_iter(line) {
+                    let key = cap[1].to_lowercase();
+                    let value = cap[2].trim_matches('"');
+                    if timestamp.is_none() && TIME_KEYS.contains(&key.as_str()) {
+                        timestamp = parse_timestamp(value);
+                    } else if level.is_none() && LEVEL_KEYS.contains(&key.as_str()) {
+                        level = normalize_level(value);
+                    }
+                }
+                (
+                    timestamp.or_else(|| detect_timestamp(line)),
+                    level.or_else(|| detect_level(line)),
+                )
+            }
+            LogFormat::PlainText => (detect_timestamp(line), detect_level(line)),
+        }
+    }
+
+    /// Build a chunk from a group of lines.
+    fn create_chunk(
+        &self,
+        item: &SourceItem,
+        group: &LogGroup,
+        format: LogFormat,
+        chunk_index: usize,
+    ) -> Chunk {
+        let text = &item.content[group.start..group.end];
+        let token_count = count_tokens(text);
+
+        let mut chunk = Chunk::new(
+            item.id,
+            item.source_id,
+            item.source_kind,
+            text.to_string(),
+            token_count,
+            group.start,
+            group.end,
+            chunk_index,
+        );
+
+        chunk.metadata = ChunkMetadata {
+            content_type: Some(
+                match format {
+                    LogFormat::JsonLines => "log_json",
+                    LogFormat::Logfmt => "log_logfmt",
+                    LogFormat::PlainText => "log",
+                }
+                .to_string(),
+            ),
+            path: item.extract_path().map(String::from),
+            line_range: Some((group.first_line, group.last_line)),
+            log_level: group.level.map(|l| LEVEL_ORDER[l].to_string()),
+            start_time: group.start_time,
+            end_time: group.end_time,
+            ..Default::default()
+        };
+
+        chunk
+    }
+}
+
+/// Accumulated state for the chunk being built.
+struct LogGroup {
+    start: usize,
+    end: usize,
+    first_line: usize,
+    last_line: usize,
+    tokens: usize,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    /// Index into `LEVEL_ORDER` of the most severe level seen
+    level: Option<usize>,
+}
+
+impl LogGroup {
+    fn new(start: usize, line: usize) -> Self {
+        Self {
+            start,
+            end: start,
+            first_line: line,
+            last_line: line,
+            tokens: 0,
+            start_time: None,
+            end_time: None,
+            level: None,
+        }
+    }
+}
+
+/// Canonical level names ordered by severity.
+const LEVEL_ORDER: &[&str] = &["trace", "debug", "info", "warn", "error", "fatal"];
+
+fn normalize_level(raw: &str) -> Option<String> {
+    let level = match raw.to_lowercase().as_str() {
+        "trace" => "trace",
+        "debug" => "debug",
+        "info" | "notice" => "info",
+        "warn" | "warning" => "warn",
+        "error" | "err" => "error",
+        "crit" | "critical" | "fatal" | "alert" | "emerg" => "fatal",
+        _ => return None,
+    };
+    Some(level.to_string())
+}
+
+fn level_rank(level: &str) -> Option<usize> {
+    LEVEL_ORDER.iter().position(|l| *l == level)
+}
+
+fn detect_level(line: &str) -> Option<String> {
+    LEVEL_RE
+        .captures(line)
+        .and_then(|cap| normalize_level(&cap[1]))
+}
+
+fn from_epoch(secs: f64) -> Option<DateTime<Utc>> {
+    // Values this large are milliseconds rather than seconds
+    let millis = if secs > 1e11 { secs } else { secs * 1000.0 };
+    Utc.timestamp_millis_opt(millis as i64).single()
+}
+
+/// Parse a timestamp string in any of the supported formats.
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+
+    if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+        return Some(ts.with_timezone(&Utc));
+    }
+
+    // Apache access log: 10/Oct/2000:13:55:36 -0700
+    if let Ok(ts) = DateTime::parse_from_str(raw, "%d/%b/%Y:%H:%M:%S %z") {
+        return Some(ts.with_timezone(&Utc));
+    }
+
+    for fmt in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S,%3f",
+        "%a %b %d %H:%M:%S%.f %Y",
+    ] {
+        if let Ok(ts) = NaiveDateTime::parse_from_str(raw, fmt) {
+            return Some(ts.and_utc());
+        }
+    }
+
+    for fmt in ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z"] {
+        if let Ok(ts) = DateTime::parse_from_str(raw, fmt) {
+            return Some(ts.with_timezone(&Utc));
+        }
+    }
+
+    if raw.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return raw.parse::<f64>().ok().and_then(from_epoch);
+    }
+
+    None
+}
+
+/// Find a timestamp anywhere in a plain-text line.
+fn detect_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    for cap in BRACKETED_RE.captures_iter(line) {
+        if let Some(ts) = parse_timestamp(&cap[1]) {
+            return Some(ts);
+        }
+    }
+
+    if let Some(m) = ISO_RE.find(line) {
+        if let Some(ts) = parse_timestamp(m.as_str()) {
+            return Some(ts);
+        }
+    }
+
+    EPOCH_RE
+        .captures(line.trim_start())
+        .and_then(|cap| parse_timestamp(&cap[1]))
+}
+
+impl Default for LogChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for LogChunker {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn description(&self) -> &'static str {
+        "Log chunker that groups lines into time windows for JSON, logfmt, and plaintext logs"
+    }
+
+    fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let content = &item.content;
+        if content.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let format = Self::detect_format(content);
+        let mut chunks = Vec::new();
+        let mut group: Option<LogGroup> = None;
+        let mut offset = 0;
+
+        for (line_num, line) in content.split_inclusive('\n').enumerate() {
+            let line_start = offset;
+            offset += line.len();
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                if let Some(g) = group.as_mut() {
+                    g.end = offset;
+                }
+                continue;
+            }
+
+            let (timestamp, level) = self.parse_line(trimmed, format);
+            let line_tokens = count_tokens(line);
+
+            if let Some(g) = group.as_ref() {
+                let outside_window = match (g.start_time, timestamp) {
+                    (Some(start), Some(ts)) => (ts - start).abs() > self.window,
+                    _ => false,
+                };
+                let over_budget = g.tokens + line_tokens > config.chunk_size;
+
+                if outside_window || over_budget {
+                    chunks.push(self.create_chunk(item, g, format, chunks.len()));
+                    group = None;
+                }
+            }
+
+            let g = group.get_or_insert_with(|| LogGroup::new(line_start, line_num + 1));
+            g.end = offset;
+            g.last_line = line_num + 1;
+            g.tokens += line_tokens;
+
+            if let Some(ts) = timestamp {
+                if g.start_time.is_none() {
+                    g.start_time = Some(ts);
+                }
+                g.end_time = Some(ts);
+            }
+
+            if let Some(rank) = level.as_deref().and_then(level_rank) {
+                g.level = Some(g.level.map_or(rank, |current| current.max(rank)));
+            }
+        }
+
+        if let Some(g) = group {
+            chunks.push(self.create_chunk(item, &g, format, chunks.len()));
+        }
+
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+    use uuid::Uuid;
+
+    fn create_log_item(content: &str, content_type: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::Other,
+            content_type: content_type.to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({"path": "/var/log/apache2/access.log"}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    const APACHE_LOG: &str = r#"127.0.0.1 - frank [10/Oct/2023:13:55:36 +0000] "GET /index.html HTTP/1.1" 200 2326 "http://example.com/start.html" "Mozilla/5.0"
+[Tue Oct 10 13:56:02 2023] [error] [client 10.0.0.5] File does not exist: /var/www/favicon.ico
+10.0.0.5 - - [10/Oct/2023:13:57:12 +0000] "GET /favicon.ico HTTP/1.1" 404 209 "-" "Mozilla/5.0"
+[Tue Oct 10 13:58:40 2023] [warn] [client 10.0.0.7] mod_fcgid: read data timeout in 40 seconds
+10.0.0.7 - - [10/Oct/2023:14:10:01 +0000] "POST /api/login HTTP/1.1" 200 512 "-" "curl/8.0"
+[Tue Oct 10 14:11:30 2023] [info] [client 10.0.0.7] Session established
+10.0.0.8 - - [10/Oct/2023:14:12:44 +0000] "GET /dashboard HTTP/1.1" 200 10240 "http://example.com/" "Mozilla/5.0"
+"#;
+
+    #[test]
+    fn test_apache_log_time_windows() {
+        let chunker = LogChunker::new();
+        let item = create_log_item(APACHE_LOG, "text/x-log");
+        let config = ChunkConfig::with_size(1000);
+
+        let chunks = chunker.chunk(&item, &config).unwrap();
+
+        // 13:55-13:58 and 14:10-14:12 fall into separate 5 minute windows
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].content.contains("/index.html"));
+        assert!(chunks[0].content.contains("mod_fcgid"));
+        assert!(chunks[1].content.contains("/api/login"));
+
+        assert_eq!(chunks[0].metadata.log_level.as_deref(), Some("error"));
+        assert_eq!(chunks[1].metadata.log_level.as_deref(), Some("info"));
+
+        let start = chunks[0].metadata.start_time.unwrap();
+        let end = chunks[0].metadata.end_time.unwrap();
+        assert!(end > start);
+        assert!(end - start <= Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_json_lines_format() {
+        let content = r#"{"ts":"2024-01-01T12:00:00Z","level":"INFO","msg":"started"}
+{"ts":"2024-01-01T12:01:00Z","level":"WARN","msg":"slow query"}
+{"ts":"2024-01-01T12:30:00Z","level":"ERROR","msg":"connection lost"}
+"#;
+        assert_eq!(LogChunker::detect_format(content), LogFormat::JsonLines);
+
+        let chunker = LogChunker::new();
+        let item = create_log_item(content, "application/x-ndjson");
+        let chunks = chunker.chunk(&item, &ChunkConfig::with_size(1000)).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].metadata.log_level.as_deref(), Some("warn"));
+        assert_eq!(chunks[1].metadata.log_level.as_deref(), Some("error"));
+    }
+
+    #[test]
+    fn test_logfmt_and_epoch_detection() {
+        let content = "time=1704110400 level=debug msg=\"cache warm\"\ntime=1704110460 level=info msg=done\n";
+        assert_eq!(LogChunker::detect_format(content), LogFormat::Logfmt);
+
+        let chunker = LogChunker::new();
+        let item = create_log_item(content, "text/x-log");
+        let chunks = chunker.chunk(&item, &ChunkConfig::with_size(1000)).unwrap();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            chunks[0].metadata.start_time,
+            Utc.timestamp_opt(1_704_110_400, 0).single()
+        );
+    }
+
+    #[test]
+    fn test_token_fallback_without_timestamps() {
+        let content = (0..200)
+            .map(|i| format!("worker {} finished processing batch without issues", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let chunker = LogChunker::new();
+        let item = create_log_item(&content, "text/x-log");
+        let chunks = chunker.chunk(&item, &ChunkConfig::with_size(100)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.metadata.start_time.is_none()));
+        assert!(chunks.iter().all(|c| c.token_count <= 100));
+    }
+}
@@ -4,30 +4,49 @@ mod base;
 mod chat_chunker;
 mod code_chunker;
 mod document_chunker;
+mod email_chunker;
+mod ini_chunker;
+mod latex_chunker;
+mod log_chunker;
+mod patch_chunker;
 mod recursive_chunker;
 mod sentence_chunker;
+mod shell_chunker;
 mod table_chunker;
 mod ticketing_chunker;
 mod token_chunker;
+mod xml_chunker;
+#[cfg(feature = "token-cache")]
+mod token_cache;
 
 // Advanced chunking modules
 mod agentic_chunker;
 pub mod repo_chunker;
 
-pub use base::{Chunker, TiktokenCounter, TokenCounter, count_tokens};
-pub use chat_chunker::ChatChunker;
-pub use code_chunker::CodeChunker;
-pub use document_chunker::DocumentChunker;
-pub use recursive_chunker::RecursiveChunker;
-pub use sentence_chunker::SentenceChunker;
+pub use base::{Chunker, TiktokenCounter, TokenCounter, count_tokens, count_tokens_fast, count_tokens_with_mode, init_token_counter};
+pub use chat_chunker::{ChatChunker, ChatChunkerConfig};
+pub use code_chunker::{CodeChunker, CodeChunkerConfig, EntityBoundary};
+pub use document_chunker::{DocumentChunker, DocumentChunkerConfig, FrontMatter};
+pub use email_chunker::EmailChunker;
+pub use ini_chunker::IniChunker;
+pub use latex_chunker::LatexChunker;
+pub use log_chunker::{LogChunker, LogFormat};
+pub use patch_chunker::PatchChunker;
+pub use recursive_chunker::{RecursiveChunker, RecursiveChunkerConfig};
+pub use sentence_chunker::{SentenceBoundaryDetector, SentenceChunker};
+pub use shell_chunker::ShellChunker;
+pub(crate) use shell_chunker::SHELL_LANGUAGES;
 pub use table_chunker::TableChunker;
 pub use ticketing_chunker::TicketingChunker;
 pub use token_chunker::TokenChunker;
+pub use xml_chunker::XmlChunker;
+#[cfg(feature = "token-cache")]
+pub use token_cache::{token_cache_stats, TokenCache, TokenCacheStats, DEFAULT_TOKEN_CACHE_CAPACITY};
 
 // Advanced chunkers
-pub use agentic_chunker::AgenticChunker;
+pub use agentic_chunker::{AgenticChunker, BoundaryType, LanguageAwareBoundaryClassifier};
 pub use repo_chunker::{
-    RepositoryContext, Symbol, SymbolType, Import, 
-    RepoChunkConfig, LargeFileStrategy,
-    extract_symbols, extract_rust_symbols, extract_python_symbols, extract_js_symbols,
+    RepositoryContext, Symbol, SymbolType, SymbolVisibility, Import, CallSite, TypeReference, TypeReferenceKind, UsageSite, UsageKind,
+    RepoChunkConfig, LargeFileStrategy, AstParser, ParsedFile, ParseError,
+    extract_symbols, extract_call_sites, extract_type_references, detect_test_functions, is_test_path, extract_rust_symbols, extract_python_symbols, extract_js_symbols, extract_cpp_symbols,
 };
@@ -0,0 +1,394 @@
+//! Patch chunker for unified diffs and `git format-patch` output.
+
+use std::ops::Range;
+
+use anyhow::Result;
+
+use super::base::{count_tokens, Chunker};
+use crate::types::language::language_from_extension;
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, DiffStats, SourceItem};
+
+/// Patch chunker for unified diff files.
+///
+/// Emits one chunk per changed file, split between hunks when the file's
+/// changes exceed the chunk size; every piece repeats the file header so
+/// it stands on its own. Text before the first diff, such as the commit
+/// message of a `git format-patch` email, becomes its own chunk.
+pub struct PatchChunker {
+    /// Split files larger than the chunk size at `@@` hunk boundaries
+    split_hunks: bool,
+}
+
+impl PatchChunker {
+    /// Create a new patch chunker that splits large files between hunks.
+    pub fn new() -> Self {
+        Self { split_hunks: true }
+    }
+
+    /// Split files larger than the chunk size between hunks (the default),
+    /// or keep every file whole.
+    pub fn with_split_hunks(mut self, split: bool) -> Self {
+        self.split_hunks = split;
+        self
+    }
+}
+
+impl Default for PatchChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for PatchChunker {
+    fn name(&self) -> &'static str {
+        "patch"
+    }
+
+    fn description(&self) -> &'static str {
+        "Patch chunker for unified diffs, one chunk per changed file split at hunk boundaries"
+    }
+
+    fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let content = &item.content;
+        if content.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let line_starts = line_starts(content);
+        let line_start = |line: usize| line_starts.get(line).copied().unwrap_or(content.len()).min(content.len());
+
+        let (_, files) = split_diff(content);
+        let chunk_size = if self.split_hunks { config.effective_chunk_size() } else { usize::MAX };
+        let mut chunks = Vec::new();
+
+        // Commit message and other text before the first file
+        let preamble_end = files.first().map_or(content.len(), |file| line_start(file.start_line));
+        let preamble = &content[..preamble_end];
+        if !preamble.trim().is_empty() {
+            let mut chunk = Chunk::new(
+                item.id,
+                item.source_id,
+                item.source_kind,
+                preamble.to_string(),
+                count_tokens(preamble),
+                0,
+                preamble_end,
+                0,
+            );
+            chunk.metadata = ChunkMetadata {
+                content_type: Some("patch_message".to_string()),
+                ..Default::default()
+            };
+            chunks.push(chunk);
+        }
+
+        for file in &files {
+            let language = file.language();
+            for piece in file.pieces(chunk_size) {
+                let span = file.piece_span(&piece, &line_starts, content.len());
+                let mut chunk = Chunk::new(
+                    item.id,
+                    item.source_id,
+                    item.source_kind,
+                    piece.content.clone(),
+                    count_tokens(&piece.content),
+                    span.start,
+                    span.end,
+                    chunks.len(),
+                );
+                chunk.metadata = ChunkMetadata {
+                    content_type: Some("diff".to_string()),
+                    path: file.path.clone(),
+                    changed_file: file.path.clone(),
+                    language: language.clone(),
+                    diff_stats: Some(piece.stats),
+                    ..Default::default()
+                };
+                chunks.push(chunk);
+            }
+        }
+
+        Ok(chunks)
+    }
+}
+
+/// Part of a file's diff: the header and a run of its hunks.
+#[derive(Debug)]
+pub(crate) struct DiffPiece {
+    pub(crate) content: String,
+    /// Indices into `FileDiff::hunks`
+    pub(crate) hunks: Range<usize>,
+    pub(crate) stats: DiffStats,
+}
+
+impl FileDiff {
+    /// Language of the changed file, from its extension.
+    pub(crate) fn language(&self) -> Option<String> {
+        self.path
+            .as_deref()
+            .and_then(|path| path.rsplit_once('.'))
+            .and_then(|(_, ext)| language_from_extension(ext))
+            .map(String::from)
+    }
+
+    /// Byte range of `piece` in the text the diff was parsed from, given
+    /// that text's `line_starts` and length. Pieces after the first start
+    /// at their first hunk, without the repeated header.
+    pub(crate) fn piece_span(&self, piece: &DiffPiece, line_starts: &[usize], text_len: usize) -> Range<usize> {
+        let line_start = |line: usize| line_starts.get(line).copied().unwrap_or(text_len).min(text_len);
+        let start = if piece.hunks.start == 0 {
+            line_start(self.start_line)
+        } else {
+            line_start(self.hunk_lines[piece.hunks.start])
+        };
+        let end = match piece.hunks.end.checked_sub(1) {
+            Some(last) => line_start(self.hunk_lines[last] + self.hunks[last].lines().count()),
+            None => start + self.header.len(),
+        };
+        start..end.min(text_len)
+    }
+
+    /// Split the file's changes into pieces of about `chunk_size` tokens
+    /// at hunk boundaries, each starting with the file header. A single
+    /// hunk larger than `chunk_size` is kept whole.
+    pub(crate) fn pieces(&self, chunk_size: usize) -> Vec<DiffPiece> {
+        let mut pieces = vec![DiffPiece {
+            content: self.header.clone(),
+            hunks: 0..0,
+            stats: DiffStats::default(),
+        }];
+        for (idx, hunk) in self.hunks.iter().enumerate() {
+            let piece = pieces.last_mut().unwrap();
+            if !piece.hunks.is_empty() && count_tokens(&piece.content).saturating_add(count_tokens(hunk)) > chunk_size {
+                pieces.push(DiffPiece {
+                    content: format!("{}{}", self.header, hunk),
+                    hunks: idx..idx + 1,
+                    stats: hunk_stats(hunk),
+                });
+            } else {
+                piece.content.push_str(hunk);
+                piece.hunks.end = idx + 1;
+                let stats = hunk_stats(hunk);
+                piece.stats.added += stats.added;
+                piece.stats.removed += stats.removed;
+            }
+        }
+        pieces
+    }
+}
+
+/// Added and removed lines in a hunk, skipping its `@@` line.
+fn hunk_stats(hunk: &str) -> DiffStats {
+    let mut stats = DiffStats::default();
+    for line in hunk.lines().skip(1) {
+        match line.chars().next() {
+            Some('+') => stats.added += 1,
+            Some('-') => stats.removed += 1,
+            _ => {}
+        }
+    }
+    stats
+}
+
+/// One file's changes in a unified diff.
+#[derive(Debug, Default)]
+pub(crate) struct FileDiff {
+    /// Path of the changed file (the new path for renames)
+    pub(crate) path: Option<String>,
+    /// `diff --git`, `index`, `---` and `+++` lines
+    pub(crate) header: String,
+    /// Hunks, each starting with its `@@` line
+    pub(crate) hunks: Vec<String>,
+    /// Line index of the first header line in the parsed text
+    pub(crate) start_line: usize,
+    /// Line index of each hunk's `@@` line in the parsed text
+    pub(crate) hunk_lines: Vec<usize>,
+}
+
+/// Byte offset of the start of each line of `text`.
+pub(crate) fn line_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+    starts
+}
+
+/// Split text into prose and the unified diffs embedded in it.
+///
+/// A diff starts at a `diff --git` line, or a `---` line followed by `+++`.
+/// Hunk lengths are read from the `@@` headers, so prose right after a diff
+/// (even a `- ` bullet list) is not mistaken for removed lines. ```` ```diff ````
+/// fences around a diff are dropped from the prose.
+pub(crate) fn split_diff(text: &str) -> (String, Vec<FileDiff>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let starts_diff = |i: usize| {
+        lines[i].starts_with("diff --git ")
+            || (lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|next| next.starts_with("+++ ")))
+    };
+
+    let mut prose = String::new();
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut skip_closing_fence = false;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if (trimmed == "```diff" || trimmed == "```patch") && i + 1 < lines.len() && starts_diff(i + 1) {
+            skip_closing_fence = true;
+            i += 1;
+            continue;
+        }
+        if skip_closing_fence && trimmed == "```" {
+            skip_closing_fence = false;
+            i += 1;
+            continue;
+        }
+        if !starts_diff(i) {
+            prose.push_str(line);
+            prose.push('\n');
+            i += 1;
+            continue;
+        }
+
+        // File header
+        let mut file = FileDiff {
+            start_line: i,
+            ..Default::default()
+        };
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            file.path = paths.split_once(" b/").map(|(_, new)| new.to_string());
+            file.header.push_str(line);
+            file.header.push('\n');
+            i += 1;
+        }
+        while i < lines.len() && !lines[i].starts_with("@@") && !lines[i].starts_with("diff --git ") {
+            let header_line = lines[i];
+            if let Some(path) = header_line.strip_prefix("+++ ").or_else(|| header_line.strip_prefix("--- ")) {
+                let path = path.split('\t').next().unwrap_or(path).trim();
+                if path != "/dev/null" && (header_line.starts_with("+++") || file.path.is_none()) {
+                    file.path = Some(path.strip_prefix("b/").or_else(|| path.strip_prefix("a/")).unwrap_or(path).to_string());
+                }
+            } else if !is_diff_header(header_line) {
+                break;
+            }
+            file.header.push_str(header_line);
+            file.header.push('\n');
+            i += 1;
+        }
+
+        // Hunks
+        while i < lines.len() {
+            let Some((mut old, mut new)) = hunk_lengths(lines[i]) else {
+                break;
+            };
+            file.hunk_lines.push(i);
+            let mut hunk = format!("{}\n", lines[i]);
+            i += 1;
+            while i < lines.len() && (old > 0 || new > 0 || lines[i].starts_with('\\')) {
+                let hunk_line = lines[i];
+                match hunk_line.chars().next() {
+                    Some('+') => new = new.saturating_sub(1),
+                    Some('-') => old = old.saturating_sub(1),
+                    Some('\\') => {}
+                    // Context, including blank lines stripped of their leading space
+                    _ => {
+                        old = old.saturating_sub(1);
+                        new = new.saturating_sub(1);
+                    }
+                }
+                hunk.push_str(hunk_line);
+                hunk.push('\n');
+                i += 1;
+            }
+            file.hunks.push(hunk);
+        }
+
+        files.push(file);
+    }
+
+    (prose, files)
+}
+
+/// Extended header lines between `diff --git` and the first hunk.
+fn is_diff_header(line: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "index ", "new file mode", "deleted file mode", "old mode", "new mode", "similarity index",
+        "dissimilarity index", "rename from", "rename to", "copy from", "copy to", "Binary files",
+    ];
+    PREFIXES.iter().any(|prefix| line.starts_with(prefix))
+}
+
+/// Old and new line counts from a `@@ -a,b +c,d @@` hunk header.
+fn hunk_lengths(line: &str) -> Option<(usize, usize)> {
+    let ranges = line.strip_prefix("@@ ")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(' ')?;
+    let length = |range: &str| match range.split_once(',') {
+        Some((_, length)) => length.parse().ok(),
+        None => Some(1),
+    };
+    Some((length(old.strip_prefix('-')?)?, length(new.strip_prefix('+')?)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+    use uuid::Uuid;
+
+    fn create_patch_item(content: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::CodeRepo,
+            content_type: "text/x-patch".to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({"path": "patches/0001-backoff.patch"}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    const PATCH: &str = "From 3f2a Mon Sep 17 00:00:00 2001\nFrom: Dana <dana@example.com>\nSubject: [PATCH] Add exponential backoff\n\n---\n src/retry.rs | 4 +++-\n\ndiff --git a/src/retry.rs b/src/retry.rs\nindex 1111111..2222222 100644\n--- a/src/retry.rs\n+++ b/src/retry.rs\n@@ -1,3 +1,4 @@\n use std::time::Duration;\n-const DELAY: u64 = 100;\n+const BASE_DELAY: u64 = 100;\n+const MAX_DELAY: u64 = 5_000;\n \n@@ -20,2 +21,2 @@ fn retry() {\n-    sleep(DELAY);\n+    sleep(backoff(attempt));\n }\ndiff --git a/old.txt b/old.txt\ndeleted file mode 100644\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-obsolete\n-- \n2.43.0\n";
+
+    #[test]
+    fn test_patch_by_file() {
+        let item = create_patch_item(PATCH);
+        let chunks = PatchChunker::new().chunk(&item, &ChunkConfig::default()).unwrap();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].metadata.content_type.as_deref(), Some("patch_message"));
+        assert!(chunks[0].content.contains("Subject: [PATCH] Add exponential backoff"));
+
+        let retry = &chunks[1];
+        assert_eq!(retry.metadata.path.as_deref(), Some("src/retry.rs"));
+        assert_eq!(retry.metadata.changed_file.as_deref(), Some("src/retry.rs"));
+        assert_eq!(retry.metadata.language.as_deref(), Some("rust"));
+        assert_eq!(retry.metadata.diff_stats, Some(DiffStats { added: 3, removed: 2 }));
+        assert_eq!(&PATCH[retry.start_index..retry.end_index], retry.content);
+
+        let deleted = &chunks[2];
+        assert_eq!(deleted.metadata.changed_file.as_deref(), Some("old.txt"));
+        assert_eq!(deleted.metadata.diff_stats, Some(DiffStats { added: 0, removed: 1 }));
+        assert!(!deleted.content.contains("2.43.0"));
+    }
+
+    #[test]
+    fn test_large_file_split_at_hunks() {
+        let item = create_patch_item(PATCH);
+        let chunks = PatchChunker::new().chunk(&item, &ChunkConfig::with_size(40)).unwrap();
+
+        let retry: Vec<_> = chunks.iter().filter(|c| c.metadata.changed_file.as_deref() == Some("src/retry.rs")).collect();
+        assert_eq!(retry.len(), 2);
+        for chunk in &retry {
+            assert!(chunk.content.starts_with("diff --git a/src/retry.rs b/src/retry.rs\n"));
+            assert_eq!(chunk.content.matches("@@ -").count(), 1);
+        }
+        assert_eq!(retry[0].metadata.diff_stats, Some(DiffStats { added: 2, removed: 1 }));
+        assert_eq!(retry[1].metadata.diff_stats, Some(DiffStats { added: 1, removed: 1 }));
+        assert!(PATCH[retry[1].start_index..retry[1].end_index].starts_with("@@ -20,2"));
+
+        let whole = PatchChunker::new().with_split_hunks(false).chunk(&item, &ChunkConfig::with_size(40)).unwrap();
+        assert_eq!(whole.len(), 3);
+    }
+}
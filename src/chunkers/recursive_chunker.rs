@@ -2,9 +2,56 @@
 
 use anyhow::Result;
 
-use super::base::{count_tokens, Chunker};
+use super::base::{count_tokens, tail_tokens, Chunker};
 use crate::types::{Chunk, ChunkConfig, SourceItem};
 
+/// Separators for prose, from paragraphs down to words.
+const PROSE_SEPARATORS: &[&str] = &[
+    "\n\n", // Paragraphs
+    "\n",   // Lines
+    ". ",   // Sentences
+    "! ",   // Exclamations
+    "? ",   // Questions
+    "; ",   // Semicolons
+    ", ",   // Commas
+    " ",    // Words
+];
+
+/// Separators for source code, from top-level definitions down to words.
+const CODE_SEPARATORS: &[&str] = &[
+    "\nclass ",     // Classes
+    "\nfn ",        // Rust functions
+    "\npub fn ",    // Public Rust functions
+    "\ndef ",       // Python functions
+    "\nfunction ",  // JavaScript functions
+    "\nfunc ",      // Go functions
+    "\n\n",         // Blank lines
+    "\n",           // Lines
+    " ",            // Words
+];
+
+/// Configuration for `RecursiveChunker`.
+#[derive(Debug, Clone)]
+pub struct RecursiveChunkerConfig {
+    /// Separators in order of preference (most to least preferred)
+    pub separators: Vec<String>,
+    /// Tokens from the end of each chunk to repeat at the start of the next
+    pub overlap_tokens: usize,
+    /// Start each split with its separator instead of dropping the
+    /// separator at chunk boundaries
+    pub keep_separator: bool,
+}
+
+impl Default for RecursiveChunkerConfig {
+    fn default() -> Self {
+        Self {
+            separators: PROSE_SEPARATORS.iter().map(|s| s.to_string()).collect(),
+            overlap_tokens: 0,
+            keep_separator: false,
+        }
+    }
+}
+
 /// Recursive chunker that splits text hierarchically.
 ///
 /// This chunker tries multiple split strategies in order of preference:
@@ -17,47 +64,62 @@ use crate::types::{Chunk, ChunkConfig, SourceItem};
 ///
 /// For each level, it only proceeds to more granular splitting if
 /// the current chunks are still too large.
+///
+/// With `RecursiveChunkerConfig::overlap_tokens` set, every chunk after
+/// the first also starts with the tail of the previous one, so chunks form
+/// a sliding window over the text.
 pub struct RecursiveChunker {
-    /// Separators in order of preference (most to least preferred)
-    separators: Vec<&'static str>,
+    config: RecursiveChunkerConfig,
 }
 
 impl RecursiveChunker {
     /// Create a new recursive chunker with default separators.
     pub fn new() -> Self {
-        Self {
-            separators: vec![
-                "\n\n",  // Paragraphs
-                "\n",    // Lines
-                ". ",    // Sentences
-                "! ",    // Exclamations
-                "? ",    // Questions
-                "; ",    // Semicolons
-                ", ",    // Commas
-                " ",     // Words
-            ],
-        }
+        Self::with_config(RecursiveChunkerConfig::default())
+    }
+
+    /// Create a recursive chunker with the given configuration.
+    pub fn with_config(config: RecursiveChunkerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Create a recursive chunker for prose.
+    pub fn new_for_prose() -> Self {
+        Self::new()
+    }
+
+    /// Create a recursive chunker for source code.
+    ///
+    /// Splits before top-level definitions and keeps the separator, so a
+    /// chunk starts with its `fn`/`def`/`class` line.
+    pub fn new_for_code() -> Self {
+        Self::with_config(RecursiveChunkerConfig {
+            separators: CODE_SEPARATORS.iter().map(|s| s.to_string()).collect(),
+            keep_separator: true,
+            ..Default::default()
+        })
     }
 
     /// Create a recursive chunker for markdown content.
     pub fn for_markdown() -> Self {
-        Self {
-            separators: vec![
-                "\n\n\n",  // Section breaks
-                "\n\n",    // Paragraphs
-                "\n# ",    // Headers
-                "\n## ",   // Subheaders
-                "\n### ",  // Sub-subheaders
-                "\n",      // Lines
-                ". ",      // Sentences
-                " ",       // Words
-            ],
-        }
+        Self::with_separators(vec![
+            "\n\n\n",  // Section breaks
+            "\n\n",    // Paragraphs
+            "\n# ",    // Headers
+            "\n## ",   // Subheaders
+            "\n### ",  // Sub-subheaders
+            "\n",      // Lines
+            ". ",      // Sentences
+            " ",       // Words
+        ])
     }
 
     /// Create a recursive chunker with custom separators.
     pub fn with_separators(separators: Vec<&'static str>) -> Self {
-        Self { separators }
+        Self::with_config(RecursiveChunkerConfig {
+            separators: separators.into_iter().map(String::from).collect(),
+            ..Default::default()
+        })
     }
 
     /// Split text using the given separator.
@@ -72,6 +134,16 @@ impl RecursiveChunker {
                 let end = start + c.len_utf8();
                 &text[start..end]
             }).collect()
+        } else if self.config.keep_separator {
+            // Each split after the first starts with its separator
+            let mut starts: Vec<usize> = text.match_indices(separator).map(|(i, _)| i).collect();
+            starts.insert(0, 0);
+            starts.dedup();
+            starts
+                .iter()
+                .zip(starts.iter().skip(1).chain(std::iter::once(&text.len())))
+                .map(|(&start, &end)| &text[start..end])
+                .collect()
         } else {
             text.split(separator).collect()
         }
@@ -95,12 +167,14 @@ impl RecursiveChunker {
         }
 
         // If we've exhausted all separators, split by characters
-        if separator_index >= self.separators.len() {
+        if separator_index >= self.config.separators.len() {
             return self.split_by_chars(text, chunk_size);
         }
 
-        let separator = self.separators[separator_index];
+        let separator = self.config.separators[separator_index].as_str();
         let splits: Vec<&str> = self.split_by_separator(text, separator);
+        // Kept separators are already part of the splits
+        let joiner = if self.config.keep_separator { "" } else { separator };
 
         // If we only got one split, try the next separator
         if splits.len() <= 1 {
@@ -111,11 +185,11 @@ impl RecursiveChunker {
         let mut chunks = Vec::new();
         let mut current_chunk = String::new();
 
-        for (_i, split) in splits.iter().enumerate() {
+        for split in splits.iter() {
             let test_chunk = if current_chunk.is_empty() {
                 split.to_string()
             } else {
-                format!("{}{}{}", current_chunk, separator, split)
+                format!("{}{}{}", current_chunk, joiner, split)
             };
 
             let test_tokens = count_tokens(&test_chunk);
@@ -195,27 +269,41 @@ impl Chunker for RecursiveChunker {
         // Recursively split the content
         let text_chunks = self.recursive_chunk(content, config.chunk_size, 0);
 
-        // Convert to Chunk objects
+        // Convert to Chunk objects, locating each piece in the content
         let mut chunks = Vec::new();
-        let mut current_index = 0;
+        let mut cursor = 0;
+        let mut previous: Option<(usize, usize)> = None;
 
         for (chunk_index, text) in text_chunks.iter().enumerate() {
-            let token_count = count_tokens(text);
-            let start_index = current_index;
-            let end_index = start_index + text.len();
+            let core_start = content[cursor..].find(text.as_str()).map_or(cursor, |i| cursor + i);
+            let core_end = core_start + text.len();
+
+            // Extend back over the previous chunk's last tokens and any
+            // separator between them
+            let start_index = match previous {
+                Some((prev_start, prev_end)) if self.config.overlap_tokens > 0 => {
+                    let tail = tail_tokens(&content[prev_start..prev_end], self.config.overlap_tokens);
+                    prev_end - tail.len()
+                }
+                _ => core_start,
+            };
+            let text = &content[start_index..core_end];
 
-            chunks.push(Chunk::new(
+            let mut chunk = Chunk::new(
                 item.id,
                 item.source_id,
                 item.source_kind,
-                text.clone(),
-                token_count,
+                text.to_string(),
+                count_tokens(text),
                 start_index,
-                end_index,
+                core_end,
                 chunk_index,
-            ));
+            );
+            chunk.metadata.has_overlap_prefix = start_index < core_start;
+            chunks.push(chunk);
 
-            current_index = end_index;
+            cursor = core_end;
+            previous = Some((core_start, core_end));
         }
 
         Ok(chunks)
@@ -237,6 +325,7 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
@@ -282,4 +371,46 @@ mod tests {
         assert!(total_content.contains("First"));
         assert!(total_content.contains("Fourth"));
     }
+
+    #[test]
+    fn test_overlap_prefix() {
+        let chunker = RecursiveChunker::with_config(RecursiveChunkerConfig {
+            overlap_tokens: 3,
+            ..Default::default()
+        });
+        let content = "Alpha beta gamma delta.\n\nEpsilon zeta eta theta.\n\nIota kappa lambda mu.";
+        let item = create_test_item(content);
+
+        let chunks = chunker.chunk(&item, &ChunkConfig::with_size(8)).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(!chunks[0].metadata.has_overlap_prefix);
+        for pair in chunks.windows(2) {
+            let (previous, chunk) = (&pair[0], &pair[1]);
+            assert!(chunk.metadata.has_overlap_prefix);
+            // The prefix is the previous chunk's tail, and indices still
+            // point at the chunk's text in the source
+            assert!(chunk.start_index < previous.end_index);
+            assert_eq!(&content[chunk.start_index..chunk.end_index], chunk.content);
+        }
+        assert!(chunks[1].content.starts_with(" gamma delta.\n\nEpsilon"));
+    }
+
+    #[test]
+    fn test_keep_separator() {
+        let content = "fn a() {\n    1\n}\nfn b() {\n    2\n}\nfn c() {\n    3\n}";
+        let item = create_test_item(content);
+        let config = ChunkConfig::with_size(10);
+
+        let chunks = RecursiveChunker::new_for_code().chunk(&item, &config).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].content.starts_with("fn a()"));
+        assert!(chunks[1].content.starts_with("\nfn b()"));
+        assert!(chunks[2].content.starts_with("\nfn c()"));
+        assert_eq!(chunks.iter().map(|c| c.content.as_str()).collect::<String>(), content);
+
+        // Without keeping it, the separator is dropped at chunk boundaries
+        let chunker = RecursiveChunker::with_separators(vec!["\nfn "]);
+        let chunks = chunker.chunk(&item, &config).unwrap();
+        assert!(chunks[1].content.starts_with("b()"));
+    }
 }
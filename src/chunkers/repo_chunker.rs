@@ -9,13 +9,85 @@
 //! - **Parallel processing**: Efficient handling of large codebases
 
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem, SourceKind};
+use crate::chunkers::EntityBoundary;
+use crate::types::language::{language_from_extension, mask_comments_and_strings};
+
+lazy_static! {
+    /// An identifier immediately followed by an argument list, optionally
+    /// with a Rust turbofish (`parse::<T>(`).
+    static ref CALL_RE: Regex =
+        Regex::new(r"([A-Za-z_][A-Za-z0-9_]*)\s*(?:::<[^>]*>)?\s*\(").unwrap();
+
+    /// JS/TS test blocks: `it("...")`, `test('...')`, `describe(`...`)`.
+    static ref JS_TEST_RE: Regex =
+        Regex::new(r#"^(?:it|test|describe)(?:\.\w+)?\(\s*['"`]([^'"`]*)['"`]"#).unwrap();
+
+    /// Go test functions: `func TestXxx(t *testing.T)`.
+    static ref GO_TEST_RE: Regex = Regex::new(r"^func\s+(Test\w*)\s*\(").unwrap();
+
+    /// Java method declaration name (line following an `@Test` annotation).
+    static ref JAVA_METHOD_RE: Regex = Regex::new(r"(\w+)\s*\(").unwrap();
+
+    /// JS/TS class method: optional access modifier, then `name(...) {`.
+    static ref JS_METHOD_RE: Regex = Regex::new(
+        r"^(?:(public|private|protected)\s+)?(?:static\s+)?(?:async\s+)?(?:[gs]et\s+)?(#?[A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\([^)]*\)\s*(?::[^{]*)?\{"
+    ).unwrap();
+
+    /// Type-like identifiers (UpperCamelCase).
+    static ref TYPE_NAME_RE: Regex = Regex::new(r"\b([A-Z][A-Za-z0-9_]*)\b").unwrap();
+
+    /// C++ class, struct, union or enum head, with optional attributes.
+    static ref CPP_TYPE_RE: Regex = Regex::new(
+        r"^(class|struct|union|enum(?:\s+class|\s+struct)?)\s+(?:(?:\[\[[^\]]*\]\]|alignas\([^)]*\))\s*)*([A-Za-z_]\w*)"
+    ).unwrap();
+
+    /// C++ function head: specifiers and return type, then a possibly
+    /// qualified name (`ns::Type::method`, `~Type`, `operator==`) and `(`.
+    static ref CPP_FUNCTION_RE: Regex = Regex::new(
+        r"^((?:[\w:<>,\*&\[\]]+\s+|[\*&]+\s*)*?)((?:\w+::)*(?:~?[A-Za-z_]\w*|operator\s*(?:\(\)|[^\s(]+)))\s*\("
+    ).unwrap();
+
+    /// Python function or class definition.
+    static ref PY_DEFINITION_RE: Regex = Regex::new(r"^(?:async\s+)?(?:def|class)\s+(\w+)").unwrap();
+
+    /// Java/TypeScript class-like declaration.
+    static ref CLASS_LIKE_RE: Regex =
+        Regex::new(r"\b(?:class|interface|enum|record)\s+([A-Za-z_$][\w$]*)").unwrap();
+
+    /// Java/TypeScript method: the first name followed by `(`.
+    static ref METHOD_NAME_RE: Regex = Regex::new(r"([A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\(").unwrap();
+
+    /// Java/TypeScript field, or a Rust field or enum variant.
+    static ref FIELD_NAME_RE: Regex = Regex::new(r"([A-Za-z_$][\w$]*)\s*(?:[?!]?:|[;=,({]|$)").unwrap();
+}
+
+/// Decorators kept per entity in `EntityBoundary::decorators` and
+/// `CodeEntity::decorators`.
+pub const MAX_DECORATORS_PER_ENTITY: usize = 5;
+
+/// Keywords that are followed by `(` but are not calls.
+const NON_CALL_KEYWORDS: &[&str] = &[
+    "if", "while", "for", "match", "return", "switch", "catch", "fn", "def",
+    "function", "elif", "except", "with", "in", "not", "and", "or", "await",
+    "yield", "new", "typeof", "sizeof",
+];
 
 /// Repository-wide chunking context for tracking cross-file relationships.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct RepositoryContext {
     /// Map of file path -> defined symbols
     pub symbols: HashMap<String, Vec<Symbol>>,
@@ -23,6 +95,13 @@ pub struct RepositoryContext {
     pub imports: HashMap<String, Vec<Import>>,
     /// Map of symbol name -> defining file paths
     pub symbol_locations: HashMap<String, Vec<String>>,
+    /// Map of callee name -> (caller file, caller function)
+    pub callers: HashMap<String, Vec<(String, String)>>,
+    /// Map of file path -> type references made in that file
+    pub type_references: HashMap<String, Vec<TypeReference>>,
+    /// Map of symbol name -> places it is used
+    #[serde(default)]
+    pub usages: HashMap<String, Vec<UsageSite>>,
     /// Total files processed
     pub files_processed: usize,
     /// Total chunks created
@@ -68,10 +147,203 @@ impl RepositoryContext {
     pub fn get_file_symbols(&self, file_path: &str) -> &[Symbol] {
         self.symbols.get(file_path).map(|v| v.as_slice()).unwrap_or(&[])
     }
+
+    /// Get the public symbols defined in a file.
+    pub fn get_public_api(&self, file_path: &str) -> Vec<&Symbol> {
+        self.get_file_symbols(file_path)
+            .iter()
+            .filter(|s| s.visibility == SymbolVisibility::Public)
+            .collect()
+    }
+
+    /// Register a call from `caller_name` in `caller_file` to `callee_name`.
+    pub fn register_call(&mut self, caller_file: &str, caller_name: &str, callee_name: &str) {
+        let callers = self.callers.entry(callee_name.to_string()).or_default();
+        let entry = (caller_file.to_string(), caller_name.to_string());
+        if !callers.contains(&entry) {
+            callers.push(entry);
+        }
+    }
+
+    /// Register a type reference made in a file.
+    pub fn register_type_reference(&mut self, file_path: &str, reference: TypeReference) {
+        self.type_references
+            .entry(file_path.to_string())
+            .or_default()
+            .push(reference);
+    }
+
+    /// Find all references to a type across the repository.
+    pub fn find_type_usages(&self, type_name: &str) -> Vec<&TypeReference> {
+        self.type_references
+            .values()
+            .flatten()
+            .filter(|r| r.type_name == type_name)
+            .collect()
+    }
+
+    /// Find the (file, function) pairs that call a given symbol.
+    pub fn find_callers(&self, symbol_name: &str) -> Vec<(&str, &str)> {
+        self.callers
+            .get(symbol_name)
+            .map(|v| v.iter().map(|(file, caller)| (file.as_str(), caller.as_str())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Register a place where `symbol_name` is used.
+    pub fn register_usage(&mut self, symbol_name: &str, usage: UsageSite) {
+        let usages = self.usages.entry(symbol_name.to_string()).or_default();
+        if !usages.contains(&usage) {
+            usages.push(usage);
+        }
+    }
+
+    /// Find where a symbol is used across the repository, in registration
+    /// order.
+    pub fn find_usages(&self, symbol_name: &str) -> Vec<&UsageSite> {
+        self.usages.get(symbol_name).map(|v| v.iter().collect()).unwrap_or_default()
+    }
+
+    /// Mark test code among the registered symbols with `Symbol::is_test`.
+    ///
+    /// - Rust: symbols inside a `#[cfg(test)]` module, following the parent
+    ///   chain, and functions in test paths such as `tests/`
+    /// - Python: `test_*` functions and methods, and classes inheriting
+    ///   `TestCase` together with their methods
+    /// - Go: `Test*` and `Benchmark*` functions in `_test.go` files
+    ///
+    /// Call it after registering files; symbols already marked stay marked.
+    pub fn tag_test_symbols(&mut self) {
+        for (path, symbols) in self.symbols.iter_mut() {
+            let language = path.rsplit_once('.').and_then(|(_, ext)| language_from_extension(ext));
+            let test_classes: Vec<&str> = self
+                .type_references
+                .get(path)
+                .into_iter()
+                .flatten()
+                .filter(|r| r.reference_kind == TypeReferenceKind::Extends)
+                .filter(|r| r.type_name == "TestCase")
+                .map(|r| r.referencing_entity.as_str())
+                .collect();
+
+            let is_function = |symbol: &Symbol| matches!(symbol.symbol_type, SymbolType::Function | SymbolType::Method);
+            let marked: Vec<bool> = symbols
+                .iter()
+                .map(|symbol| {
+                    symbol.is_test
+                        || match language {
+                            Some("rust") => symbol.symbol_type == SymbolType::Function && is_test_path(path),
+                            Some("python") => {
+                                (is_function(symbol) && symbol.name.starts_with("test_"))
+                                    || (symbol.symbol_type == SymbolType::Class && test_classes.contains(&symbol.name.as_str()))
+                            }
+                            Some("go") => {
+                                path.ends_with("_test.go")
+                                    && symbol.symbol_type == SymbolType::Function
+                                    && (symbol.name.starts_with("Test") || symbol.name.starts_with("Benchmark"))
+                            }
+                            _ => false,
+                        }
+                })
+                .collect();
+
+            // Members of test modules and classes are test code too
+            let tagged: Vec<bool> = (0..symbols.len())
+                .map(|idx| {
+                    let mut current = idx;
+                    for _ in 0..=symbols.len() {
+                        if marked[current] {
+                            return true;
+                        }
+                        let Some(parent) = symbols[current].parent.as_deref() else {
+                            return false;
+                        };
+                        let Some(next) = symbols.iter().position(|s| s.name == parent && !is_function(s)) else {
+                            return false;
+                        };
+                        current = next;
+                    }
+                    false
+                })
+                .collect();
+
+            for (symbol, is_test) in symbols.iter_mut().zip(tagged) {
+                symbol.is_test = is_test;
+            }
+        }
+    }
+
+    /// Symbols in a file marked as test code by `tag_test_symbols`.
+    pub fn get_test_symbols(&self, file_path: &str) -> Vec<&Symbol> {
+        self.symbols
+            .get(file_path)
+            .map(|symbols| symbols.iter().filter(|s| s.is_test).collect())
+            .unwrap_or_default()
+    }
+
+    /// Symbols in a file not marked as test code.
+    pub fn get_production_symbols(&self, file_path: &str) -> Vec<&Symbol> {
+        self.symbols
+            .get(file_path)
+            .map(|symbols| symbols.iter().filter(|s| !s.is_test).collect())
+            .unwrap_or_default()
+    }
+
+    /// Register the symbols, calls and type references of a parsed file.
+    ///
+    /// Calls and type references are also registered as usages of the
+    /// called function or referenced type.
+    pub fn register_parsed_file(&mut self, file: ParsedFile) {
+        for symbol in file.symbols {
+            self.register_symbol(&file.path, symbol);
+        }
+        for call in &file.call_sites {
+            self.register_call(&file.path, &call.caller, &call.callee);
+            self.register_usage(&call.callee, UsageSite::new(&file.path, call.line, UsageKind::CallSite));
+        }
+        for reference in file.type_references {
+            let usage_kind = match reference.reference_kind {
+                TypeReferenceKind::Extends => UsageKind::Inheritance,
+                _ => UsageKind::TypeAnnotation,
+            };
+            self.register_usage(&reference.type_name, UsageSite::new(&file.path, reference.line, usage_kind));
+            self.register_type_reference(&file.path, reference);
+        }
+        self.files_processed += 1;
+    }
+
+    /// Save the context as gzip-compressed MessagePack.
+    ///
+    /// Building a context for a large repository is slow, so it can be
+    /// cached between runs and reloaded with `load_from_file`.
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::fast());
+        rmp_serde::encode::write_named(&mut encoder, self)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Load a context previously written with `save_to_file`.
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let decoder = GzDecoder::new(BufReader::new(file));
+        rmp_serde::from_read(decoder).with_context(|| format!("Invalid repository context in {}", path.display()))
+    }
+
+    /// Whether a saved context is missing or was written more than
+    /// `max_age` ago.
+    pub fn is_stale(path: &Path, max_age: Duration) -> bool {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified());
+        match modified {
+            Ok(modified) => SystemTime::now().duration_since(modified).is_ok_and(|age| age > max_age),
+            Err(_) => true,
+        }
+    }
 }
 
 /// A symbol extracted from code.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Symbol {
     /// Symbol name (function, class, struct, etc.)
     pub name: String,
@@ -85,10 +357,36 @@ pub struct Symbol {
     pub parent: Option<String>,
     /// Documentation if present
     pub documentation: Option<String>,
+    /// Template or generic type parameters, e.g. `T` in `template<typename T>`
+    #[serde(default)]
+    pub type_parameters: Vec<String>,
+    /// Access level
+    pub visibility: SymbolVisibility,
+    /// Whether the symbol is test code; see `RepositoryContext::tag_test_symbols`
+    #[serde(default)]
+    pub is_test: bool,
+}
+
+/// Access level of a symbol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolVisibility {
+    /// Visible everywhere (`pub`, `export`, `public`)
+    Public,
+    /// Visible only in its defining scope
+    Private,
+    /// Visible to subclasses (`protected`, or a Python `_name`)
+    Protected,
+    /// Visible to the parent module or package (`pub(super)`)
+    Package,
+    /// Visible within the crate or assembly (`pub(crate)`)
+    Internal,
+    /// Not determined
+    #[default]
+    Unknown,
 }
 
 /// Types of code symbols.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SymbolType {
     Function,
     Method,
@@ -104,7 +402,7 @@ pub enum SymbolType {
 }
 
 /// An import statement from code.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Import {
     /// The module/package being imported
     pub module_path: String,
@@ -114,6 +412,81 @@ pub struct Import {
     pub is_wildcard: bool,
 }
 
+/// A function call found inside a function body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallSite {
+    /// Enclosing function or method
+    pub caller: String,
+    /// Name of the called function or method
+    pub callee: String,
+    /// Line of the call (0-indexed, like `Symbol::line_range`)
+    pub line: usize,
+    /// Byte offset of the callee name in the file
+    pub byte_offset: usize,
+}
+
+/// A use of a named type by a function, struct or impl.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeReference {
+    /// The referenced type
+    pub type_name: String,
+    /// Function, struct or type that references it
+    pub referencing_entity: String,
+    /// Where the type appears
+    pub reference_kind: TypeReferenceKind,
+    /// Line of the reference (0-indexed, like `Symbol::line_range`)
+    pub line: usize,
+}
+
+/// Position in which a type is referenced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypeReferenceKind {
+    /// Function parameter type
+    Parameter,
+    /// Function return type
+    Return,
+    /// Struct field type
+    Field,
+    /// Type argument nested inside another type (e.g. `Chunk` in `Vec<Chunk>`)
+    Generic,
+    /// Implemented trait or supertrait
+    Extends,
+}
+
+/// A place where a symbol is used, from `RepositoryContext::find_usages`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageSite {
+    pub file_path: String,
+    /// Line of the usage (0-indexed, like `Symbol::line_range`)
+    pub line: usize,
+    pub usage_kind: UsageKind,
+}
+
+impl UsageSite {
+    /// Create a usage site.
+    pub fn new(file_path: &str, line: usize, usage_kind: UsageKind) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            line,
+            usage_kind,
+        }
+    }
+}
+
+/// How a symbol is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UsageKind {
+    /// Called as a function or method
+    CallSite,
+    /// Named in a parameter, return, field or generic type
+    TypeAnnotation,
+    /// Imported; the parser does not record import lines, so these are
+    /// only present when registered with `register_usage`
+    Import,
+    /// Implemented or extended as a trait or base class
+    Inheritance,
+}
+
 /// Strategy for handling large files.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LargeFileStrategy {
@@ -161,56 +534,134 @@ impl Default for RepoChunkConfig {
 }
 
 /// Extract symbols from Rust code without tree-sitter (regex-based fallback).
+///
+/// Inline `mod name { ... }` blocks become `Module` symbols and parent the
+/// items inside them; a module under `#[cfg(test)]` is marked `is_test`.
 pub fn extract_rust_symbols(content: &str) -> Vec<Symbol> {
-    let mut symbols = Vec::new();
+    let mut symbols: Vec<Symbol> = Vec::new();
     let mut current_parent: Option<String> = None;
+    let mut in_trait_impl = false;
+    // Open modules: index into `symbols` and the brace depth inside them
+    let mut modules: Vec<(usize, i32)> = Vec::new();
+    let mut depth = 0i32;
+    let mut cfg_test = false;
     
     for (line_num, line) in content.lines().enumerate() {
         let trimmed = line.trim();
+        let module_parent = modules.last().map(|&(idx, _)| symbols[idx].name.clone());
         
         // Track impl blocks for method parents
         if trimmed.starts_with("impl ") {
             if let Some(name) = extract_impl_name(trimmed) {
                 current_parent = Some(name);
+                in_trait_impl = trimmed.contains(" for ");
             }
         } else if trimmed == "}" && current_parent.is_some() {
             current_parent = None;
         }
+
+        let (visibility, item) = split_rust_visibility(trimmed);
         
         // Extract function symbols
-        if let Some(name) = extract_function_name(trimmed) {
+        if let Some(name) = extract_function_name(item) {
             let sym_type = if current_parent.is_some() {
                 SymbolType::Method
             } else {
                 SymbolType::Function
             };
+            // Trait methods are as visible as the trait itself
+            let visibility = if current_parent.is_some() && in_trait_impl {
+                SymbolVisibility::Public
+            } else {
+                visibility
+            };
             
             symbols.push(Symbol {
                 name,
                 symbol_type: sym_type,
                 byte_range: (0, 0), // Would need proper byte tracking
                 line_range: (line_num, line_num),
-                parent: current_parent.clone(),
+                parent: current_parent.clone().or_else(|| module_parent.clone()),
                 documentation: None,
+                type_parameters: Vec::new(),
+                visibility,
+                is_test: false,
             });
         }
         
         // Extract struct/enum symbols
-        if let Some((name, sym_type)) = extract_type_def(trimmed) {
+        if let Some((name, sym_type)) = extract_type_def(item) {
             symbols.push(Symbol {
                 name,
                 symbol_type: sym_type,
                 byte_range: (0, 0),
                 line_range: (line_num, line_num),
-                parent: None,
+                parent: module_parent.clone(),
+                documentation: None,
+                type_parameters: Vec::new(),
+                visibility,
+                is_test: false,
+            });
+        }
+
+        // Inline modules run until the brace depth drops below their body
+        let module_name = item.strip_prefix("mod ").and_then(|rest| rest.strip_suffix('{')).map(str::trim);
+        if let Some(name) = module_name.filter(|name| !name.is_empty()) {
+            symbols.push(Symbol {
+                name: name.to_string(),
+                symbol_type: SymbolType::Module,
+                byte_range: (0, 0),
+                line_range: (line_num, line_num),
+                parent: module_parent,
                 documentation: None,
+                type_parameters: Vec::new(),
+                visibility,
+                is_test: cfg_test,
             });
+            modules.push((symbols.len() - 1, depth + 1));
+        }
+        depth += brace_delta(trimmed);
+        while modules.last().is_some_and(|&(_, inner)| depth < inner) {
+            let (idx, _) = modules.pop().unwrap();
+            symbols[idx].line_range.1 = line_num;
+        }
+
+        // `#[cfg(test)]` applies to the next item
+        if trimmed.starts_with("#[cfg(test)]") {
+            cfg_test = true;
+        } else if !trimmed.is_empty() && !trimmed.starts_with("#[") && !trimmed.starts_with("//") {
+            cfg_test = false;
         }
     }
     
     symbols
 }
 
+/// Split a Rust visibility modifier (`pub`, `pub(crate)`, `pub(super)`,
+/// ...) off the start of an item.
+fn split_rust_visibility(line: &str) -> (SymbolVisibility, &str) {
+    let Some(rest) = line.strip_prefix("pub") else {
+        return (SymbolVisibility::Private, line);
+    };
+    if let Some(rest) = rest.strip_prefix('(') {
+        let Some((scope, item)) = rest.split_once(')') else {
+            return (SymbolVisibility::Private, line);
+        };
+        let visibility = match scope.trim() {
+            "crate" => SymbolVisibility::Internal,
+            "self" => SymbolVisibility::Private,
+            // `pub(super)` and `pub(in path)`
+            _ => SymbolVisibility::Package,
+        };
+        (visibility, item.trim_start())
+    } else if rest.starts_with(char::is_whitespace) {
+        (SymbolVisibility::Public, rest.trim_start())
+    } else {
+        // An identifier that starts with "pub", e.g. `publish()`
+        (SymbolVisibility::Private, line)
+    }
+}
+
 fn extract_function_name(line: &str) -> Option<String> {
     let patterns = [
         "pub async fn ", "async fn ", "pub fn ", "fn ",
@@ -218,8 +669,7 @@ fn extract_function_name(line: &str) -> Option<String> {
     ];
     
     for pattern in patterns {
-        if line.starts_with(pattern) {
-            let rest = &line[pattern.len()..];
+        if let Some(rest) = line.strip_prefix(pattern) {
             let name = rest.split(|c: char| c == '(' || c == '<' || c.is_whitespace())
                 .next()?
                 .to_string();
@@ -264,9 +714,8 @@ fn extract_type_def(line: &str) -> Option<(String, SymbolType)> {
     ];
     
     for (pattern, sym_type) in patterns {
-        if line.starts_with(pattern) {
-            let rest = &line[pattern.len()..];
-            let name = rest.split(|c: char| c == '<' || c == '{' || c == '(' || c.is_whitespace())
+        if let Some(rest) = line.strip_prefix(pattern) {
+            let name = rest.split(|c: char| c == '<' || c == '{' || c == '(' || c == ';' || c.is_whitespace())
                 .next()?
                 .to_string();
             if !name.is_empty() {
@@ -293,12 +742,15 @@ pub fn extract_python_symbols(content: &str) -> Vec<Symbol> {
                 current_class = Some(name.clone());
                 class_indent = indent;
                 symbols.push(Symbol {
+                    visibility: python_visibility(&name),
                     name,
                     symbol_type: SymbolType::Class,
                     byte_range: (0, 0),
                     line_range: (line_num, line_num),
                     parent: None,
                     documentation: None,
+                    type_parameters: Vec::new(),
+                    is_test: false,
                 });
             }
         } else if current_class.is_some() && indent <= class_indent && !trimmed.is_empty() {
@@ -314,12 +766,15 @@ pub fn extract_python_symbols(content: &str) -> Vec<Symbol> {
             };
             
             symbols.push(Symbol {
+                visibility: python_visibility(&name),
                 name,
                 symbol_type: sym_type,
                 byte_range: (0, 0),
                 line_range: (line_num, line_num),
                 parent: current_class.clone(),
                 documentation: None,
+                type_parameters: Vec::new(),
+                is_test: false,
             });
         }
     }
@@ -327,6 +782,20 @@ pub fn extract_python_symbols(content: &str) -> Vec<Symbol> {
     symbols
 }
 
+/// Python visibility by naming convention: `__name` is private, `_name`
+/// protected, and everything else (including `__dunder__`) public.
+fn python_visibility(name: &str) -> SymbolVisibility {
+    if name.starts_with("__") && name.ends_with("__") {
+        SymbolVisibility::Public
+    } else if name.starts_with("__") {
+        SymbolVisibility::Private
+    } else if name.starts_with('_') {
+        SymbolVisibility::Protected
+    } else {
+        SymbolVisibility::Public
+    }
+}
+
 fn extract_python_class_name(line: &str) -> Option<String> {
     let rest = line.strip_prefix("class ")?;
     let name = rest.split(|c: char| c == '(' || c == ':' || c.is_whitespace())
@@ -339,8 +808,7 @@ fn extract_python_function_name(line: &str) -> Option<String> {
     let patterns = ["async def ", "def "];
     
     for pattern in patterns {
-        if line.starts_with(pattern) {
-            let rest = &line[pattern.len()..];
+        if let Some(rest) = line.strip_prefix(pattern) {
             let name = rest.split(|c: char| c == '(' || c.is_whitespace())
                 .next()?
                 .to_string();
@@ -353,17 +821,29 @@ fn extract_python_function_name(line: &str) -> Option<String> {
 }
 
 /// Extract symbols from JavaScript/TypeScript code.
+///
+/// Exported top-level symbols are public and the rest private. Class
+/// members are public unless marked `private`/`protected` or named `#name`.
 pub fn extract_js_symbols(content: &str) -> Vec<Symbol> {
     let mut symbols = Vec::new();
     let mut current_class: Option<String> = None;
+    // Brace depth outside the current class body
+    let mut class_depth = 0;
+    let mut depth = 0usize;
     
     for (line_num, line) in content.lines().enumerate() {
         let trimmed = line.trim();
+        let visibility = if trimmed.starts_with("export ") {
+            SymbolVisibility::Public
+        } else {
+            SymbolVisibility::Private
+        };
         
         // Class definitions
         if trimmed.starts_with("class ") || trimmed.starts_with("export class ") {
             if let Some(name) = extract_js_class_name(trimmed) {
                 current_class = Some(name.clone());
+                class_depth = depth;
                 symbols.push(Symbol {
                     name,
                     symbol_type: SymbolType::Class,
@@ -371,6 +851,24 @@ pub fn extract_js_symbols(content: &str) -> Vec<Symbol> {
                     line_range: (line_num, line_num),
                     parent: None,
                     documentation: None,
+                    type_parameters: Vec::new(),
+                    visibility,
+                    is_test: false,
+                });
+            }
+        } else if current_class.is_some() && depth == class_depth + 1 {
+            // Methods directly inside the class body
+            if let Some((name, visibility)) = extract_js_method(trimmed) {
+                symbols.push(Symbol {
+                    name,
+                    symbol_type: SymbolType::Method,
+                    byte_range: (0, 0),
+                    line_range: (line_num, line_num),
+                    parent: current_class.clone(),
+                    documentation: None,
+                    type_parameters: Vec::new(),
+                    visibility,
+                    is_test: false,
                 });
             }
         }
@@ -390,6 +888,9 @@ pub fn extract_js_symbols(content: &str) -> Vec<Symbol> {
                 line_range: (line_num, line_num),
                 parent: current_class.clone(),
                 documentation: None,
+                type_parameters: Vec::new(),
+                visibility,
+                is_test: false,
             });
         }
         
@@ -402,11 +903,16 @@ pub fn extract_js_symbols(content: &str) -> Vec<Symbol> {
                 line_range: (line_num, line_num),
                 parent: None,
                 documentation: None,
+                type_parameters: Vec::new(),
+                visibility,
+                is_test: false,
             });
         }
         
-        // End of class block (simple heuristic)
-        if trimmed == "}" && current_class.is_some() {
+        // End of class block
+        let closes = trimmed.matches('}').count();
+        depth = (depth + trimmed.matches('{').count()).saturating_sub(closes);
+        if closes > 0 && depth <= class_depth {
             current_class = None;
         }
     }
@@ -414,6 +920,24 @@ pub fn extract_js_symbols(content: &str) -> Vec<Symbol> {
     symbols
 }
 
+/// Extract a class method and its visibility from a class body line.
+fn extract_js_method(line: &str) -> Option<(String, SymbolVisibility)> {
+    let caps = JS_METHOD_RE.captures(line)?;
+    let name = caps.get(2)?.as_str();
+    if NON_CALL_KEYWORDS.contains(&name) {
+        return None;
+    }
+
+    let visibility = match caps.get(1).map(|m| m.as_str()) {
+        Some("private") => SymbolVisibility::Private,
+        Some("protected") => SymbolVisibility::Protected,
+        Some(_) => SymbolVisibility::Public,
+        None if name.starts_with('#') => SymbolVisibility::Private,
+        None => SymbolVisibility::Public,
+    };
+    Some((name.to_string(), visibility))
+}
+
 fn extract_js_class_name(line: &str) -> Option<String> {
     let rest = if line.starts_with("export ") {
         line.strip_prefix("export class ")?
@@ -421,7 +945,7 @@ fn extract_js_class_name(line: &str) -> Option<String> {
         line.strip_prefix("class ")?
     };
     
-    let name = rest.split(|c: char| c == '{' || c == ' ' || c == '<')
+    let name = rest.split(['{', ' ', '<'])
         .next()?
         .trim()
         .to_string();
@@ -433,7 +957,7 @@ fn extract_js_function_name(line: &str) -> Option<String> {
     for kw in ["const ", "let ", "var "] {
         if line.starts_with(kw) && line.contains("=>") {
             let rest = &line[kw.len()..];
-            let name = rest.split(|c: char| c == ' ' || c == '=' || c == ':')
+            let name = rest.split([' ', '=', ':'])
                 .next()?
                 .trim()
                 .to_string();
@@ -452,8 +976,7 @@ fn extract_js_function_name(line: &str) -> Option<String> {
     ];
     
     for pattern in patterns {
-        if line.starts_with(pattern) {
-            let rest = &line[pattern.len()..];
+        if let Some(rest) = line.strip_prefix(pattern) {
             let name = rest.split(|c: char| c == '(' || c == '<' || c.is_whitespace())
                 .next()?
                 .to_string();
@@ -470,8 +993,7 @@ fn extract_ts_interface(line: &str) -> Option<String> {
     let patterns = ["export interface ", "interface ", "export type ", "type "];
     
     for pattern in patterns {
-        if line.starts_with(pattern) {
-            let rest = &line[pattern.len()..];
+        if let Some(rest) = line.strip_prefix(pattern) {
             let name = rest.split(|c: char| c == '<' || c == '{' || c == '=' || c.is_whitespace())
                 .next()?
                 .trim()
@@ -484,11 +1006,395 @@ fn extract_ts_interface(line: &str) -> Option<String> {
     None
 }
 
+/// Scope opened by a C++ namespace, type or `extern "C"` block.
+struct CppScope {
+    /// Empty for anonymous namespaces and linkage blocks
+    name: String,
+    is_type: bool,
+    /// Members of anonymous namespaces are private to the file
+    is_anonymous: bool,
+    /// Brace depth outside the scope
+    depth: usize,
+    /// Whether the scope's `{` has been seen
+    entered: bool,
+    /// Current access level, for types
+    access: SymbolVisibility,
+}
+
+/// Extract symbols from C++ (and C) code.
+///
+/// Handles namespaces (nested, `A::B`, anonymous), classes, structs,
+/// unions and enums, methods declared in a class body or defined out of
+/// line as `Type::method(`, free functions, `using` aliases and `#define`
+/// macros (as constants). Parents are the enclosing scope path, e.g.
+/// `A::B` for a class in `namespace A { namespace B { ... } }`. A
+/// preceding `template<...>` clause fills `type_parameters`, and `//` or
+/// `/* */` comments directly above a definition become its documentation.
+pub fn extract_cpp_symbols(content: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut scopes: Vec<CppScope> = Vec::new();
+    let mut depth = 0usize;
+    let mut doc: Vec<String> = Vec::new();
+    let mut template: Option<Vec<String>> = None;
+    let mut in_block_comment = false;
+    let mut in_macro = false;
+
+    for (line_num, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+
+        if in_block_comment {
+            if let Some((text, _)) = trimmed.split_once("*/") {
+                push_doc_line(&mut doc, text);
+                in_block_comment = false;
+            } else {
+                push_doc_line(&mut doc, trimmed);
+            }
+            continue;
+        }
+        if in_macro {
+            in_macro = trimmed.ends_with('\\');
+            continue;
+        }
+        if trimmed.is_empty() {
+            doc.clear();
+            template = None;
+            continue;
+        }
+        if let Some(text) = trimmed.strip_prefix("//") {
+            push_doc_line(&mut doc, text);
+            continue;
+        }
+        if let Some(text) = trimmed.strip_prefix("/*") {
+            match text.split_once("*/") {
+                None => {
+                    push_doc_line(&mut doc, text);
+                    in_block_comment = true;
+                    continue;
+                }
+                Some((text, rest)) if rest.trim().is_empty() => {
+                    push_doc_line(&mut doc, text);
+                    continue;
+                }
+                // Code follows the comment on the same line
+                Some(_) => {}
+            }
+        }
+
+        let mut documentation = (!doc.is_empty()).then(|| doc.join("\n"));
+        doc.clear();
+
+        if let Some(directive) = trimmed.strip_prefix('#') {
+            in_macro = trimmed.ends_with('\\');
+            if let Some(rest) = directive.trim_start().strip_prefix("define") {
+                let name: String = rest.trim_start().chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+                if !name.is_empty() && rest.starts_with(char::is_whitespace) {
+                    symbols.push(Symbol {
+                        name,
+                        symbol_type: SymbolType::Constant,
+                        byte_range: (0, 0),
+                        line_range: (line_num, line_num),
+                        parent: None,
+                        documentation,
+                        type_parameters: Vec::new(),
+                        visibility: SymbolVisibility::Public,
+                        is_test: false,
+                    });
+                }
+            }
+            continue;
+        }
+
+        let code = strip_cpp_noise(trimmed);
+        let mut code = code.trim();
+
+        if let Some(scope) = scopes.last_mut().filter(|s| s.is_type) {
+            let access = match code {
+                "public:" => Some(SymbolVisibility::Public),
+                "protected:" => Some(SymbolVisibility::Protected),
+                "private:" => Some(SymbolVisibility::Private),
+                _ => None,
+            };
+            if let Some(access) = access {
+                scope.access = access;
+                continue;
+            }
+        }
+
+        if let Some((params, rest)) = split_cpp_template(code) {
+            template = Some(params);
+            code = rest;
+            if code.is_empty() {
+                // Documentation above the clause belongs to the definition
+                doc.extend(documentation);
+                continue;
+            }
+        }
+
+        // Anything after a namespace's `{` is parsed like a line of its own,
+        // e.g. `namespace A { namespace B { class C {}; } }`
+        while let Some((name, body)) = split_cpp_namespace(code).filter(|_| depth == cpp_scope_depth(&scopes)) {
+            let is_anonymous = name.is_empty() || scopes.iter().any(|s| s.is_anonymous);
+            if !name.is_empty() {
+                symbols.push(Symbol {
+                    name: name.to_string(),
+                    symbol_type: SymbolType::Module,
+                    byte_range: (0, 0),
+                    line_range: (line_num, line_num),
+                    parent: cpp_scope_path(&scopes),
+                    documentation: documentation.take(),
+                    type_parameters: Vec::new(),
+                    visibility: if is_anonymous { SymbolVisibility::Private } else { SymbolVisibility::Public },
+                    is_test: false,
+                });
+            }
+            scopes.push(CppScope {
+                name: name.to_string(),
+                is_type: false,
+                is_anonymous: name.is_empty(),
+                depth,
+                entered: body.is_some(),
+                access: SymbolVisibility::Public,
+            });
+            match body {
+                Some(body) => {
+                    depth += 1;
+                    code = body.trim();
+                }
+                None => code = "",
+            }
+        }
+        if code.is_empty() {
+            continue;
+        }
+
+        let type_parameters = template.take().unwrap_or_default();
+        if depth == cpp_scope_depth(&scopes) {
+            let path = cpp_scope_path(&scopes);
+            let (in_type, anonymous) = match scopes.last() {
+                Some(scope) => (scope.is_type, scopes.iter().any(|s| s.is_anonymous)),
+                None => (false, false),
+            };
+            let visibility = match scopes.last() {
+                Some(scope) if scope.is_type => scope.access,
+                _ if anonymous || code.starts_with("static ") => SymbolVisibility::Private,
+                _ => SymbolVisibility::Public,
+            };
+            let mut push = |name: String, symbol_type, parent: Option<String>| {
+                symbols.push(Symbol {
+                    name,
+                    symbol_type,
+                    byte_range: (0, 0),
+                    line_range: (line_num, line_num),
+                    parent,
+                    documentation: documentation.clone(),
+                    type_parameters: type_parameters.clone(),
+                    visibility,
+                    is_test: false,
+                });
+            };
+
+            let type_head = code.strip_prefix("typedef ").unwrap_or(code);
+            if code.starts_with("extern \"C") && code.ends_with('{') {
+                scopes.push(CppScope {
+                    name: String::new(),
+                    is_type: false,
+                    is_anonymous: false,
+                    depth,
+                    entered: false,
+                    access: SymbolVisibility::Public,
+                });
+            } else if let Some(caps) = CPP_TYPE_RE.captures(type_head) {
+                // The pattern is anchored, so the match is a prefix
+                let rest = &type_head[caps[0].len()..];
+                // Forward declarations and variables of the type are not definitions
+                let is_definition = code.contains('{') || !code.ends_with(';');
+                if is_definition && !rest.trim_start().starts_with(['*', '&']) {
+                    let symbol_type = match &caps[1] {
+                        "class" => SymbolType::Class,
+                        "struct" | "union" => SymbolType::Struct,
+                        _ => SymbolType::Enum,
+                    };
+                    push(caps[2].to_string(), symbol_type, path.clone());
+                    scopes.push(CppScope {
+                        name: caps[2].to_string(),
+                        is_type: true,
+                        is_anonymous: false,
+                        depth,
+                        entered: false,
+                        access: if &caps[1] == "class" { SymbolVisibility::Private } else { SymbolVisibility::Public },
+                    });
+                }
+            } else if let Some(rest) = code.strip_prefix("using ") {
+                if let Some((name, _)) = rest.split_once('=') {
+                    push(name.trim().to_string(), SymbolType::Type, path.clone());
+                }
+            } else if let Some(caps) = CPP_FUNCTION_RE.captures(code) {
+                let qualified = &caps[2];
+                let (qualifier, name) = match qualified.rsplit_once("::") {
+                    Some((qualifier, name)) => (Some(qualifier), name),
+                    None => (None, qualified),
+                };
+                let is_constructor = in_type && scopes.last().is_some_and(|s| s.name == name);
+                let has_return_type = !caps[1].trim().is_empty();
+                let is_keyword = NON_CALL_KEYWORDS.contains(&name)
+                    || ["static_assert", "decltype", "alignas", "template", "delete"].contains(&name);
+
+                if !is_keyword && (has_return_type || qualifier.is_some() || is_constructor || name.starts_with('~')) {
+                    let parent = match (path.clone(), qualifier) {
+                        (Some(path), Some(qualifier)) => Some(format!("{}::{}", path, qualifier)),
+                        (path, qualifier) => qualifier.map(String::from).or(path),
+                    };
+                    let symbol_type = if in_type || qualifier.is_some() { SymbolType::Method } else { SymbolType::Function };
+                    push(name.to_string(), symbol_type, parent);
+                }
+            }
+        }
+
+        let opens = code.matches('{').count();
+        let closes = code.matches('}').count();
+        if let Some(scope) = scopes.last_mut() {
+            if !scope.entered && depth == scope.depth && opens > 0 {
+                scope.entered = true;
+            }
+        }
+        depth = (depth + opens).saturating_sub(closes);
+        while scopes.last().is_some_and(|s| (s.entered && depth <= s.depth) || (!s.entered && code.ends_with(';'))) {
+            scopes.pop();
+        }
+    }
+
+    symbols
+}
+
+/// Add a comment line to a pending docstring, without comment markers.
+fn push_doc_line(doc: &mut Vec<String>, text: &str) {
+    let text = text.trim_start_matches(['/', '*', '!']).trim();
+    if !text.is_empty() {
+        doc.push(text.to_string());
+    }
+}
+
+/// Remove string and character literals and comments from a line of C++,
+/// so braces inside them are not counted.
+fn strip_cpp_noise(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if next == '\\' {
+                        escaped = true;
+                    } else if next == c {
+                        break;
+                    }
+                }
+                out.push(c);
+                out.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => break,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Split a leading `template<...>` clause into its parameter names and
+/// the rest of the line.
+fn split_cpp_template(code: &str) -> Option<(Vec<String>, &str)> {
+    let rest = code.strip_prefix("template")?.trim_start();
+    let inner = rest.strip_prefix('<')?;
+
+    let mut nesting = 0usize;
+    let mut end = None;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' | '(' => nesting += 1,
+            '>' | ')' if nesting > 0 => nesting -= 1,
+            '>' => {
+                end = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+
+    let mut params = Vec::new();
+    let mut nesting = 0usize;
+    let mut start = 0;
+    let clause = &inner[..end];
+    for (i, c) in clause.char_indices().chain([(clause.len(), ',')]) {
+        match c {
+            '<' | '(' => nesting += 1,
+            '>' | ')' => nesting = nesting.saturating_sub(1),
+            ',' if nesting == 0 => {
+                let param = clause[start..i].split('=').next().unwrap_or("");
+                let name = param
+                    .trim()
+                    .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or("");
+                if !name.is_empty() && !["typename", "class"].contains(&name) {
+                    params.push(name.to_string());
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    Some((params, inner[end + 1..].trim_start()))
+}
+
+/// Split a namespace head into its name (empty if anonymous) and the
+/// code after its `{`, if the brace is on the same line. Namespace
+/// aliases (`namespace fs = std::filesystem;`) are not heads.
+fn split_cpp_namespace(code: &str) -> Option<(&str, Option<&str>)> {
+    let rest = code.strip_prefix("inline ").unwrap_or(code).strip_prefix("namespace")?;
+    if !(rest.is_empty() || rest.starts_with([' ', '\t', '{'])) {
+        return None;
+    }
+    let (name, body) = match rest.split_once('{') {
+        Some((name, body)) => (name, Some(body)),
+        None => (rest, None),
+    };
+    if name.contains(['=', ';']) {
+        return None;
+    }
+    Some((name.trim(), body))
+}
+
+/// Brace depth of definitions directly inside the innermost C++ scope.
+fn cpp_scope_depth(scopes: &[CppScope]) -> usize {
+    scopes.last().map_or(0, |s| s.depth + usize::from(s.entered))
+}
+
+/// `A::B` path of the named scopes enclosing a C++ definition.
+fn cpp_scope_path(scopes: &[CppScope]) -> Option<String> {
+    let names: Vec<&str> = scopes.iter().map(|s| s.name.as_str()).filter(|n| !n.is_empty()).collect();
+    (!names.is_empty()).then(|| names.join("::"))
+}
+
 /// Extract symbols based on detected language.
 pub fn extract_symbols(content: &str, language: Option<&str>) -> Vec<Symbol> {
     match language {
         Some("rust") => extract_rust_symbols(content),
         Some("python") => extract_python_symbols(content),
+        Some("cpp") | Some("c") => extract_cpp_symbols(content),
         Some("javascript") | Some("typescript") | Some("jsx") | Some("tsx") => {
             extract_js_symbols(content)
         }
@@ -507,37 +1413,769 @@ pub fn extract_symbols(content: &str, language: Option<&str>) -> Vec<Symbol> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Extract type references from Rust code (regex-based fallback).
+///
+/// Covers single-line function signatures (parameter and return types),
+/// named struct fields, `impl Trait for Type` and supertraits. Types nested
+/// in angle brackets are reported as `Generic`; the function's own type
+/// parameters and `Self` are skipped. For Python, the base classes in
+/// `class Name(Base):` are reported as `Extends`. Other languages return
+/// no references.
+pub fn extract_type_references(content: &str, language: Option<&str>) -> Vec<TypeReference> {
+    if language == Some("python") {
+        return extract_python_base_classes(content);
+    }
+    if language != Some("rust") {
+        return vec![];
+    }
 
-    #[test]
-    fn test_extract_rust_symbols() {
-        let content = r#"
-use std::io;
+    let mut refs = Vec::new();
+    let mut current_struct: Option<String> = None;
+    let mut struct_depth = 0i32;
 
-pub struct MyStruct {
-    value: i32,
+    for (line_num, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        let mut push = |text: &str, entity: &str, kind: TypeReferenceKind, skip: &[&str]| {
+            for (name, nested) in type_names(text) {
+                if name != "Self" && !skip.contains(&name) {
+                    refs.push(TypeReference {
+                        type_name: name.to_string(),
+                        referencing_entity: entity.to_string(),
+                        reference_kind: if nested { TypeReferenceKind::Generic } else { kind },
+                        line: line_num,
+                    });
+                }
+            }
+        };
+
+        if let Some(name) = current_struct.clone() {
+            if struct_depth == 1 && !trimmed.starts_with("//") && !trimmed.starts_with("#[") {
+                if let Some((_, ty)) = trimmed.split_once(':') {
+                    push(ty, &name, TypeReferenceKind::Field, &[]);
+                }
+            }
+            struct_depth += brace_delta(trimmed);
+            if struct_depth <= 0 {
+                current_struct = None;
+            }
+            continue;
+        }
+
+        if let Some(name) = extract_function_name(trimmed) {
+            let (Some(open), Some(close)) = (trimmed.find('('), trimmed.rfind(')')) else { continue };
+            if close < open {
+                continue;
+            }
+            let type_params = generic_params(&trimmed[..open]);
+            let skip: Vec<&str> = type_params.iter().map(String::as_str).collect();
+            push(&trimmed[open + 1..close], &name, TypeReferenceKind::Parameter, &skip);
+
+            if let Some(ret) = trimmed[close..].split_once("->").map(|(_, r)| r) {
+                let ret = ret.split(['{', ';']).next().unwrap_or(ret);
+                let ret = ret.split(" where ").next().unwrap_or(ret);
+                push(ret, &name, TypeReferenceKind::Return, &skip);
+            }
+        } else if let Some((name, SymbolType::Struct)) = extract_type_def(trimmed) {
+            if trimmed.contains('{') && !trimmed.ends_with('}') {
+                current_struct = Some(name);
+                struct_depth = brace_delta(trimmed);
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("impl") {
+            if let (Some(ty), Some((trait_part, _))) = (extract_impl_name(trimmed), rest.split_once(" for ")) {
+                // Drop the impl's own generics: `impl<T: Bound> Trait for Type`
+                let trait_part = match trait_part.strip_prefix('<') {
+                    Some(generics) => generics.split_once('>').map_or("", |(_, t)| t),
+                    None => trait_part,
+                };
+                push(trait_part, &ty, TypeReferenceKind::Extends, &[]);
+            }
+        } else if let Some((name, SymbolType::Trait)) = extract_type_def(trimmed) {
+            if let Some((_, bounds)) = trimmed.split_once(':') {
+                let bounds = bounds.split('{').next().unwrap_or(bounds);
+                push(bounds, &name, TypeReferenceKind::Extends, &[]);
+            }
+        }
+    }
+
+    refs
 }
 
-impl MyStruct {
-    pub fn new() -> Self {
-        Self { value: 0 }
+/// Base classes of Python classes, as `Extends` references.
+fn extract_python_base_classes(content: &str) -> Vec<TypeReference> {
+    let mut refs = Vec::new();
+    for (line_num, line) in content.lines().enumerate() {
+        let Some(rest) = line.trim().strip_prefix("class ") else { continue };
+        let Some((name, bases)) = rest.split_once('(') else { continue };
+        let bases = bases.split(')').next().unwrap_or(bases);
+        for base in bases.split(',').map(str::trim).filter(|b| !b.is_empty() && !b.contains('=')) {
+            refs.push(TypeReference {
+                type_name: base.rsplit('.').next().unwrap_or(base).to_string(),
+                referencing_entity: name.trim().to_string(),
+                reference_kind: TypeReferenceKind::Extends,
+                line: line_num,
+            });
+        }
     }
-    
-    fn private_method(&self) {}
+    refs
 }
 
-pub fn standalone_function() {
-    println!("hello");
+/// UpperCamelCase identifiers in `text`, with whether each is nested in `<>`.
+fn type_names(text: &str) -> Vec<(&str, bool)> {
+    TYPE_NAME_RE
+        .find_iter(text)
+        .map(|m| {
+            let before = &text[..m.start()];
+            let depth = before.matches('<').count() as i32 - before.matches('>').count() as i32
+                + before.matches("->").count() as i32;
+            (m.as_str(), depth > 0)
+        })
+        .collect()
 }
 
-pub enum MyEnum {
-    Variant1,
-    Variant2,
+/// Names of the type parameters declared in `fn name<A, B: Bound>`.
+fn generic_params(signature: &str) -> Vec<String> {
+    let Some(start) = signature.find('<') else { return vec![] };
+    let end = signature.rfind('>').unwrap_or(signature.len());
+    if end <= start {
+        return vec![];
+    }
+    signature[start + 1..end]
+        .split(',')
+        .filter_map(|p| p.split(':').next())
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty() && !p.starts_with('\''))
+        .collect()
 }
-"#;
-        let symbols = extract_rust_symbols(content);
+
+fn brace_delta(line: &str) -> i32 {
+    line.matches('{').count() as i32 - line.matches('}').count() as i32
+}
+
+/// Check whether a file path follows a test file naming convention.
+pub fn is_test_path(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    path.starts_with("tests/")
+        || path.contains("/tests/")
+        || path.contains("/__tests__/")
+        || file_name.ends_with("_test.go")
+        || file_name.ends_with("_test.py")
+        || (file_name.starts_with("test_") && file_name.ends_with(".py"))
+        || file_name.contains(".test.")
+        || file_name.contains(".spec.")
+}
+
+/// Detect test functions using language-specific conventions.
+///
+/// - Rust: functions annotated with `#[test]` (or `#[tokio::test]` etc.) or
+///   `#[cfg(test)]`, and every function inside a `#[cfg(test)]` module
+/// - Python: `test_*` functions and methods of `TestCase` subclasses
+/// - JavaScript/TypeScript: `it(`, `test(` and `describe(` blocks, named by
+///   their description
+/// - Go: `Test*` functions in `_test.go` files
+/// - Java: methods annotated with `@Test`
+pub fn detect_test_functions(content: &str, language: Option<&str>, path: Option<&str>) -> Vec<Symbol> {
+    let test_symbol = |name: String, line_num: usize, parent: Option<String>| Symbol {
+        name,
+        symbol_type: if parent.is_some() { SymbolType::Method } else { SymbolType::Function },
+        byte_range: (0, 0),
+        line_range: (line_num, line_num),
+        parent,
+        documentation: None,
+        type_parameters: Vec::new(),
+        visibility: SymbolVisibility::Unknown,
+        is_test: true,
+    };
+
+    match language {
+        Some("rust") => {
+            let mut tests = Vec::new();
+            // Brace depth inside the outermost `#[cfg(test)]` module, while in one
+            let mut test_module: Option<i32> = None;
+            let mut depth = 0i32;
+            let mut cfg_test = false;
+            let mut test_attr = false;
+            for (line_num, line) in content.lines().enumerate() {
+                let trimmed = line.trim();
+                let (_, item) = split_rust_visibility(trimmed);
+                if trimmed.starts_with("#[cfg(test)]") {
+                    cfg_test = true;
+                } else if trimmed.starts_with("#[") && trimmed.contains("test]") {
+                    test_attr = true;
+                } else if let Some(name) = extract_function_name(item) {
+                    if test_attr || cfg_test || test_module.is_some() {
+                        tests.push(test_symbol(name, line_num, None));
+                    }
+                    test_attr = false;
+                    cfg_test = false;
+                } else if cfg_test && item.starts_with("mod ") && item.ends_with('{') {
+                    if test_module.is_none() {
+                        test_module = Some(depth + 1);
+                    }
+                    cfg_test = false;
+                } else if !trimmed.is_empty() && !trimmed.starts_with("#[") && !trimmed.starts_with("//") {
+                    cfg_test = false;
+                }
+
+                depth += brace_delta(trimmed);
+                if test_module.is_some_and(|inner| depth < inner) {
+                    test_module = None;
+                }
+            }
+            tests
+        }
+        Some("python") => extract_python_symbols(content)
+            .into_iter()
+            .filter(|s| matches!(s.symbol_type, SymbolType::Function | SymbolType::Method))
+            .filter(|s| {
+                s.name.starts_with("test")
+                    || s.parent.as_deref().is_some_and(|class| is_python_test_case(content, class))
+            })
+            .collect(),
+        Some("javascript") | Some("typescript") | Some("jsx") | Some("tsx") => content
+            .lines()
+            .enumerate()
+            .filter_map(|(line_num, line)| {
+                let caps = JS_TEST_RE.captures(line.trim())?;
+                Some(test_symbol(caps[1].to_string(), line_num, None))
+            })
+            .collect(),
+        Some("go") if path.is_none_or(|p| p.ends_with("_test.go")) => content
+            .lines()
+            .enumerate()
+            .filter_map(|(line_num, line)| {
+                let caps = GO_TEST_RE.captures(line.trim())?;
+                Some(test_symbol(caps[1].to_string(), line_num, None))
+            })
+            .collect(),
+        Some("java") => {
+            let mut tests = Vec::new();
+            let mut test_attr = false;
+            for (line_num, line) in content.lines().enumerate() {
+                let trimmed = line.trim();
+                if trimmed.starts_with("@Test") {
+                    test_attr = true;
+                } else if test_attr && !trimmed.starts_with('@') && !trimmed.is_empty() {
+                    if let Some(caps) = JAVA_METHOD_RE.captures(trimmed) {
+                        tests.push(test_symbol(caps[1].to_string(), line_num, None));
+                    }
+                    test_attr = false;
+                }
+            }
+            tests
+        }
+        _ => vec![],
+    }
+}
+
+/// Check whether a Python class is declared as a `unittest.TestCase` subclass.
+fn is_python_test_case(content: &str, class_name: &str) -> bool {
+    content.lines().any(|line| {
+        line.trim()
+            .strip_prefix("class ")
+            .and_then(|rest| rest.strip_prefix(class_name))
+            .is_some_and(|rest| rest.starts_with('(') && rest.contains("TestCase"))
+    })
+}
+
+/// Extract call sites from code (regex-based fallback).
+///
+/// Each call is attributed to the innermost function or method found by
+/// `extract_symbols` whose body contains it. Bodies end at the matching
+/// closing brace, or for Python at the next line indented no deeper than
+/// the `def`. Calls outside any body (module-level code) are skipped, as
+/// are macro invocations and keywords such as `if (` or `while (`. Comments
+/// and string literals are masked first, so `//` in a URL or a call in a
+/// comment is not mistaken for code.
+pub fn extract_call_sites(content: &str, language: Option<&str>) -> Vec<CallSite> {
+    let definitions: Vec<Symbol> = extract_symbols(content, language)
+        .into_iter()
+        .filter(|s| matches!(s.symbol_type, SymbolType::Function | SymbolType::Method))
+        .collect();
+    let masked = mask_comments_and_strings(content, language.unwrap_or_default());
+    let indented = language == Some("python");
+
+    let mut calls = Vec::new();
+    let mut line_offset = 0;
+    // Enclosing definitions, innermost last: the symbol, the brace depth
+    // (or indentation) it was defined at, and whether its body has opened
+    let mut scopes: Vec<(&Symbol, i32, bool)> = Vec::new();
+    let mut depth = 0;
+
+    for (line_num, line) in masked.lines().enumerate() {
+        let line_start = line_offset;
+        line_offset += line.len() + 1;
+
+        if indented && !line.trim().is_empty() {
+            let indent = (line.len() - line.trim_start().len()) as i32;
+            while scopes.last().is_some_and(|&(_, level, _)| indent <= level) {
+                scopes.pop();
+            }
+            depth = indent;
+        }
+
+        // Calls on a definition line only count after the body opens; the
+        // parameter list is not a call
+        let mut code_start = 0;
+        if let Some(def) = definitions.iter().rev().find(|s| s.line_range.0 == line_num) {
+            scopes.push((def, depth, indented));
+            code_start = if indented { line.len() } else { line.find('{').unwrap_or(line.len()) };
+        }
+
+        // The innermost body open on this line; a one-line function still
+        // owns its calls after its closing brace pops it
+        let mut caller = scopes.last().filter(|&&(_, _, opened)| opened).map(|&(def, _, _)| def);
+        if !indented {
+            for c in line.chars() {
+                match c {
+                    '{' => {
+                        depth += 1;
+                        if let Some(scope) = scopes.last_mut() {
+                            scope.2 = true;
+                            caller = Some(scope.0);
+                        }
+                    }
+                    '}' => {
+                        depth -= 1;
+                        while scopes.last().is_some_and(|&(_, level, opened)| opened && depth <= level) {
+                            scopes.pop();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // A declaration without a body, e.g. a trait method
+            if scopes.last().is_some_and(|&(_, _, opened)| !opened) && line.trim_end().ends_with(';') {
+                scopes.pop();
+            }
+        }
+
+        let Some(caller) = caller else { continue };
+        let code = &line[code_start..];
+
+        for caps in CALL_RE.captures_iter(code) {
+            let name = caps.get(1).unwrap();
+            let is_macro = code[name.end()..].starts_with('!');
+            if is_macro || NON_CALL_KEYWORDS.contains(&name.as_str()) {
+                continue;
+            }
+            calls.push(CallSite {
+                caller: caller.name.clone(),
+                callee: name.as_str().to_string(),
+                line: line_num,
+                byte_offset: line_start + code_start + name.start(),
+            });
+        }
+    }
+
+    calls
+}
+
+/// A decorator, annotation or attribute and the entity it applies to:
+/// Python `@property`, Java `@Override`, TypeScript `@Injectable()`,
+/// Rust `#[derive(Debug)]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecoratorAssociation {
+    /// Function, class, field or other item the decorator applies to
+    pub entity_name: String,
+    /// Name without `@` or `#[...]`, e.g. `app.route` or `derive`
+    pub decorator_name: String,
+    /// Arguments split at top-level commas, e.g. `["Debug", "Clone"]`
+    pub decorator_args: Vec<String>,
+    /// Line of the decorator (0-indexed)
+    pub line: usize,
+    /// Line declaring the entity (0-indexed)
+    pub entity_line: usize,
+}
+
+impl std::fmt::Display for DecoratorAssociation {
+    /// The decorator without its sigil: `name` or `name(arg, ...)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.decorator_args.is_empty() {
+            write!(f, "{}", self.decorator_name)
+        } else {
+            write!(f, "{}({})", self.decorator_name, self.decorator_args.join(", "))
+        }
+    }
+}
+
+/// Find the decorators of each entity in a file.
+///
+/// Supports Python decorators, Java and TypeScript annotations and Rust
+/// outer attributes, including ones spanning several lines. Decorators
+/// must directly precede their entity; blank and comment lines may come
+/// between. Other languages have none.
+pub fn extract_decorator_associations(content: &str, language: Option<&str>) -> Vec<DecoratorAssociation> {
+    let language = language.map(str::to_lowercase).unwrap_or_default();
+    let rust = match language.as_str() {
+        "rust" | "rs" => true,
+        "python" | "py" | "java" | "kotlin" | "typescript" | "ts" | "tsx" | "javascript" | "js" | "jsx" => false,
+        _ => return Vec::new(),
+    };
+    let python = matches!(language.as_str(), "python" | "py");
+    let is_comment = |line: &str| {
+        line.starts_with("//") || line.starts_with("/*") || line.starts_with('*') || (python && line.starts_with('#'))
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut associations = Vec::new();
+    // Decorators seen since the last entity: (name, args, line)
+    let mut pending: Vec<(String, Vec<String>, usize)> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line_num = i;
+        let mut text = lines[i].trim().to_string();
+        i += 1;
+        if text.is_empty() || (is_comment(&text) && !text.starts_with("#[")) {
+            continue;
+        }
+
+        // A decorator may continue on the following lines
+        if starts_decorator(&text, rust) {
+            while !brackets_balanced(&text) && i < lines.len() {
+                text.push(' ');
+                text.push_str(lines[i].trim());
+                i += 1;
+            }
+        }
+
+        let mut rest = text.as_str();
+        while starts_decorator(rest, rust) {
+            let Some((name, args, remainder)) = parse_decorator(rest, rust) else {
+                break;
+            };
+            pending.push((name, args, line_num));
+            rest = remainder.trim_start();
+        }
+        if rest.is_empty() {
+            continue;
+        }
+
+        if let Some(entity_name) = decorated_entity_name(rest, &language) {
+            associations.extend(pending.drain(..).map(|(decorator_name, decorator_args, line)| DecoratorAssociation {
+                entity_name: entity_name.clone(),
+                decorator_name,
+                decorator_args,
+                line,
+                entity_line: line_num,
+            }));
+        }
+        pending.clear();
+    }
+
+    associations
+}
+
+/// The decorators of the `entity_name` entity starting on `start_line`
+/// (1-indexed), rendered as in `Display`, at most
+/// `MAX_DECORATORS_PER_ENTITY`.
+///
+/// The entity may start on its declaration or on its first decorator, so
+/// overloads and same-named methods of different classes are told apart.
+pub fn decorators_for(associations: &[DecoratorAssociation], entity_name: &str, start_line: usize) -> Vec<String> {
+    let start = start_line.saturating_sub(1);
+    let Some(entity_line) = associations
+        .iter()
+        .find(|a| a.entity_name == entity_name && (a.entity_line == start || a.line == start))
+        .map(|a| a.entity_line)
+    else {
+        return Vec::new();
+    };
+    associations
+        .iter()
+        .filter(|a| a.entity_name == entity_name && a.entity_line == entity_line)
+        .take(MAX_DECORATORS_PER_ENTITY)
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn starts_decorator(text: &str, rust: bool) -> bool {
+    if rust {
+        text.starts_with("#[")
+    } else {
+        // `@interface` declares a Java annotation type
+        text.starts_with('@')
+            && text[1..].starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && !text.starts_with("@interface")
+    }
+}
+
+/// Split the decorator at the start of `text` into its name, arguments
+/// and the text after it.
+fn parse_decorator(text: &str, rust: bool) -> Option<(String, Vec<String>, &str)> {
+    let (body, remainder) = if rust {
+        let close = matching_close(text, 1)?;
+        (&text[2..close], &text[close + 1..])
+    } else {
+        (&text[1..], "")
+    };
+
+    let name_len = body
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '$')))
+        .unwrap_or(body.len());
+    let name = body[..name_len].to_string();
+    let after_name = &body[name_len..];
+    let trimmed = after_name.trim_start();
+
+    let (args, rest) = if trimmed.starts_with('(') {
+        let open = name_len + (after_name.len() - trimmed.len());
+        let close = matching_close(body, open)?;
+        (split_top_level_args(&body[open + 1..close]), &body[close + 1..])
+    } else if rust && trimmed.starts_with('=') {
+        // `#[doc = "..."]`, `#[path = "..."]`
+        (vec![trimmed[1..].trim().to_string()], "")
+    } else {
+        (Vec::new(), after_name)
+    };
+
+    Some((name, args, if rust { remainder } else { rest }))
+}
+
+/// Index of the bracket closing the one at `open`, skipping string literals.
+fn matching_close(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip_while(|(i, _)| *i < open) {
+        if let Some(q) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn brackets_balanced(text: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '`') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    // Braces opening an entity's body do not count
+    depth <= 0 || text.trim_end().ends_with('{') && depth == 1
+}
+
+/// Split decorator arguments at commas outside brackets and strings.
+fn split_top_level_args(args: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '(' | '[' | '{' | '<') => depth += 1,
+            (None, ')' | ']' | '}' | '>') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(args[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(args[start..].trim().to_string());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Name of the entity declared on `line`, the line after its decorators.
+fn decorated_entity_name(line: &str, language: &str) -> Option<String> {
+    let capture = |re: &Regex| re.captures(line).map(|c| c[1].to_string());
+    match language {
+        "python" | "py" => capture(&PY_DEFINITION_RE),
+        "rust" | "rs" => {
+            let (_, item) = split_rust_visibility(line);
+            extract_function_name(item)
+                .or_else(|| extract_type_def(item).map(|(name, _)| name))
+                .or_else(|| extract_impl_name(item))
+                .or_else(|| {
+                    let rest = ["mod ", "const ", "static mut ", "static "]
+                        .iter()
+                        .find_map(|keyword| item.strip_prefix(keyword))?;
+                    rest.split(|c: char| !(c.is_alphanumeric() || c == '_')).next().map(String::from)
+                })
+                .or_else(|| capture(&FIELD_NAME_RE))
+                .filter(|name| !name.is_empty())
+        }
+        _ => capture(&CLASS_LIKE_RE)
+            .or_else(|| {
+                METHOD_NAME_RE
+                    .captures_iter(line)
+                    .map(|c| c[1].to_string())
+                    .find(|name| !NON_CALL_KEYWORDS.contains(&name.as_str()))
+            })
+            .or_else(|| capture(&FIELD_NAME_RE)),
+    }
+}
+
+/// Symbols, calls and type references extracted from one file.
+///
+/// `ParsedFile` keeps no source text, so decorators are extracted while
+/// parsing, like the other facts.
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    /// File path, as given to the parser
+    pub path: String,
+    /// Language used for extraction
+    pub language: String,
+    pub symbols: Vec<Symbol>,
+    pub call_sites: Vec<CallSite>,
+    pub type_references: Vec<TypeReference>,
+    pub decorators: Vec<DecoratorAssociation>,
+}
+
+impl ParsedFile {
+    /// Copy the file's decorators onto `entities` that have none, matching
+    /// by name and start line.
+    pub fn attach_decorators(&self, entities: &mut [EntityBoundary]) {
+        for entity in entities.iter_mut().filter(|e| e.decorators.is_empty()) {
+            entity.decorators = decorators_for(&self.decorators, &entity.name, entity.start_line);
+        }
+    }
+}
+
+/// Time allowed for parsing one file in `AstParser::parallel_parse_files`.
+pub const DEFAULT_PARSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Errors from [`AstParser`].
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    /// Parsing took longer than the allowed time.
+    #[error("parsing {path} timed out after {timeout:?}")]
+    Timeout { path: String, timeout: Duration },
+}
+
+/// Runs the symbol, call site and type reference extractors over files.
+pub struct AstParser;
+
+impl AstParser {
+    /// Parse a single file.
+    pub fn parse(path: &str, content: &str, language: &str) -> ParsedFile {
+        let lang = Some(language).filter(|l| !l.is_empty());
+        ParsedFile {
+            path: path.to_string(),
+            language: language.to_string(),
+            symbols: extract_symbols(content, lang),
+            call_sites: extract_call_sites(content, lang),
+            type_references: extract_type_references(content, lang),
+            decorators: extract_decorator_associations(content, lang),
+        }
+    }
+
+    /// Decorators of the entities in a parsed file, in source order.
+    pub fn extract_decorator_associations(parsed: &ParsedFile) -> Vec<DecoratorAssociation> {
+        parsed.decorators.clone()
+    }
+
+    /// Parse `(path, content, language)` files in parallel on the rayon
+    /// thread pool, allowing each file `DEFAULT_PARSE_TIMEOUT`. Results are
+    /// in input order.
+    pub fn parallel_parse_files(files: Vec<(&str, &str, &str)>) -> Vec<Result<ParsedFile, ParseError>> {
+        files
+            .into_par_iter()
+            .map(|(path, content, language)| Self::parse_with_deadline(path, content, language, DEFAULT_PARSE_TIMEOUT))
+            .collect()
+    }
+
+    /// Whether `parallel_parse_files` runs on multiple threads.
+    pub fn supported_by_rayon() -> bool {
+        true
+    }
+
+    /// Parse a file, giving up after `timeout`.
+    pub fn parse_timeout(content: &str, language: &str, timeout: Duration) -> Result<ParsedFile, ParseError> {
+        Self::parse_with_deadline("", content, language, timeout)
+    }
+
+    /// Parse a file on the calling thread, checking the deadline between
+    /// extraction passes. A pass that is already running is not interrupted,
+    /// so a parse overruns `timeout` by at most one pass.
+    fn parse_with_deadline(path: &str, content: &str, language: &str, timeout: Duration) -> Result<ParsedFile, ParseError> {
+        let deadline = Instant::now() + timeout;
+        let check = || {
+            if Instant::now() >= deadline {
+                return Err(ParseError::Timeout { path: path.to_string(), timeout });
+            }
+            Ok(())
+        };
+
+        let lang = Some(language).filter(|l| !l.is_empty());
+        let symbols = extract_symbols(content, lang);
+        check()?;
+        let call_sites = extract_call_sites(content, lang);
+        check()?;
+        let type_references = extract_type_references(content, lang);
+        check()?;
+        let decorators = extract_decorator_associations(content, lang);
+        check()?;
+
+        Ok(ParsedFile {
+            path: path.to_string(),
+            language: language.to_string(),
+            symbols,
+            call_sites,
+            type_references,
+            decorators,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_rust_symbols() {
+        let content = r#"
+use std::io;
+
+pub struct MyStruct {
+    value: i32,
+}
+
+impl MyStruct {
+    pub fn new() -> Self {
+        Self { value: 0 }
+    }
+    
+    fn private_method(&self) {}
+}
+
+pub fn standalone_function() {
+    println!("hello");
+}
+
+pub enum MyEnum {
+    Variant1,
+    Variant2,
+}
+"#;
+        let symbols = extract_rust_symbols(content);
         
         let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
         assert!(names.contains(&"MyStruct"));
@@ -611,6 +2249,328 @@ export interface MyInterface {
         assert!(names.contains(&"MyInterface"));
     }
 
+    #[test]
+    fn test_symbol_visibility() {
+        let visibility = |symbols: &[Symbol], name: &str| {
+            symbols.iter().find(|s| s.name == name).map(|s| s.visibility)
+        };
+
+        let rust = "pub fn a() {}\npub(crate) fn b() {}\npub(super) fn c() {}\nfn d() {}\n";
+        let symbols = extract_rust_symbols(rust);
+        assert_eq!(visibility(&symbols, "a"), Some(SymbolVisibility::Public));
+        assert_eq!(visibility(&symbols, "b"), Some(SymbolVisibility::Internal));
+        assert_eq!(visibility(&symbols, "c"), Some(SymbolVisibility::Package));
+        assert_eq!(visibility(&symbols, "d"), Some(SymbolVisibility::Private));
+
+        let python = "def run():\n    pass\n\ndef _helper():\n    pass\n\ndef __secret():\n    pass\n";
+        let symbols = extract_python_symbols(python);
+        assert_eq!(visibility(&symbols, "run"), Some(SymbolVisibility::Public));
+        assert_eq!(visibility(&symbols, "_helper"), Some(SymbolVisibility::Protected));
+        assert_eq!(visibility(&symbols, "__secret"), Some(SymbolVisibility::Private));
+
+        let ts = r#"
+export class Service {
+    private cache: Map<string, string>;
+
+    public fetch(id: string): string {
+        if (id) {
+            return id;
+        }
+    }
+
+    protected retry(): void {
+    }
+
+    #reset() {
+    }
+}
+
+function internal() {}
+"#;
+        let symbols = extract_js_symbols(ts);
+        assert_eq!(visibility(&symbols, "Service"), Some(SymbolVisibility::Public));
+        assert_eq!(visibility(&symbols, "fetch"), Some(SymbolVisibility::Public));
+        assert_eq!(visibility(&symbols, "retry"), Some(SymbolVisibility::Protected));
+        assert_eq!(visibility(&symbols, "#reset"), Some(SymbolVisibility::Private));
+        assert_eq!(visibility(&symbols, "internal"), Some(SymbolVisibility::Private));
+        // `if (id) {` is inside a method body, not a member
+        assert_eq!(visibility(&symbols, "if"), None);
+        assert_eq!(symbols.iter().find(|s| s.name == "internal").unwrap().parent, None);
+    }
+
+    #[test]
+    fn test_extract_call_sites() {
+        let content = r#"
+fn helper(x: i32) -> i32 {
+    x * 2
+}
+
+pub fn run() {
+    let v = helper(1);
+    let n = "42".parse::<i32>().unwrap();
+    if (v > 0) {
+        println!("{}", v);
+    }
+}
+"#;
+        let calls = extract_call_sites(content, Some("rust"));
+        let callees: Vec<(&str, &str)> = calls.iter().map(|c| (c.caller.as_str(), c.callee.as_str())).collect();
+
+        assert!(callees.contains(&("run", "helper")));
+        assert!(callees.contains(&("run", "parse")));
+        assert!(callees.contains(&("run", "unwrap")));
+        assert!(!callees.iter().any(|(_, callee)| *callee == "if" || *callee == "println"));
+
+        let call = calls.iter().find(|c| c.callee == "helper").unwrap();
+        assert_eq!(call.line, 6);
+        assert_eq!(&content[call.byte_offset..call.byte_offset + 6], "helper");
+    }
+
+    #[test]
+    fn test_call_sites_follow_scopes() {
+        let content = r#"
+fn outer() {
+    fn inner() {
+        nested();
+    }
+    let url = "http://example.com"; after_url();
+    // commented(x)
+    tail();
+}
+
+static TABLE: Lazy<Vec<u8>> = Lazy::new(|| build());
+
+fn one_liner() { direct(); }
+"#;
+        let calls = extract_call_sites(content, Some("rust"));
+        let callees: Vec<(&str, &str)> = calls.iter().map(|c| (c.caller.as_str(), c.callee.as_str())).collect();
+
+        assert!(callees.contains(&("inner", "nested")));
+        assert!(callees.contains(&("outer", "after_url")));
+        assert!(callees.contains(&("outer", "tail")));
+        assert!(callees.contains(&("one_liner", "direct")));
+        assert!(!callees.iter().any(|(_, callee)| *callee == "commented"));
+        // Module-level code after `outer` closes has no caller
+        assert!(!callees.iter().any(|(_, callee)| *callee == "build" || *callee == "new"));
+
+        let python = "def a():\n    b()\n\nc()\n\nclass K:\n    def m(self):\n        d()\n";
+        let calls = extract_call_sites(python, Some("python"));
+        let callees: Vec<(&str, &str)> = calls.iter().map(|c| (c.caller.as_str(), c.callee.as_str())).collect();
+        assert_eq!(callees, vec![("a", "b"), ("m", "d")]);
+    }
+
+    #[test]
+    fn test_detect_test_functions() {
+        let rust = "fn prod() {}\n\n#[test]\nfn checks_prod() {}\n\n#[cfg(test)]\nmod tests {\n    fn helper() {\n    }\n}\n\nfn after_tests() {}\n";
+        let names: Vec<String> = detect_test_functions(rust, Some("rust"), None).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["checks_prod", "helper"]);
+
+        let python = "import unittest\n\nclass Suite(unittest.TestCase):\n    def setUp(self):\n        pass\n\ndef test_add():\n    pass\n\ndef add():\n    pass\n";
+        let names: Vec<String> = detect_test_functions(python, Some("python"), None).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["setUp", "test_add"]);
+
+        let js = "describe('math', () => {\n  it(\"adds\", () => {});\n});\n";
+        let names: Vec<String> = detect_test_functions(js, Some("javascript"), None).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["math", "adds"]);
+
+        let go = "func TestAdd(t *testing.T) {}\nfunc add() {}\n";
+        assert_eq!(detect_test_functions(go, Some("go"), Some("math_test.go")).len(), 1);
+        assert!(detect_test_functions(go, Some("go"), Some("math.go")).is_empty());
+
+        let java = "@Test\npublic void addsNumbers() {}\npublic void add() {}\n";
+        let names: Vec<String> = detect_test_functions(java, Some("java"), None).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["addsNumbers"]);
+    }
+
+    #[test]
+    fn test_extract_type_references() {
+        let content = r#"
+pub struct Batch {
+    pub items: Vec<SourceItem>,
+    pub config: BatchConfig,
+}
+
+impl Chunker for CodeChunker {
+    fn chunk<T: Into<String>>(&self, item: &SourceItem, label: T) -> Result<Vec<Chunk>> {
+        todo!()
+    }
+}
+"#;
+        let refs = extract_type_references(content, Some("rust"));
+        let found: Vec<(&str, &str, TypeReferenceKind)> = refs
+            .iter()
+            .map(|r| (r.type_name.as_str(), r.referencing_entity.as_str(), r.reference_kind))
+            .collect();
+
+        assert!(found.contains(&("Vec", "Batch", TypeReferenceKind::Field)));
+        assert!(found.contains(&("SourceItem", "Batch", TypeReferenceKind::Generic)));
+        assert!(found.contains(&("BatchConfig", "Batch", TypeReferenceKind::Field)));
+        assert!(found.contains(&("Chunker", "CodeChunker", TypeReferenceKind::Extends)));
+        assert!(found.contains(&("SourceItem", "chunk", TypeReferenceKind::Parameter)));
+        assert!(found.contains(&("Result", "chunk", TypeReferenceKind::Return)));
+        assert!(found.contains(&("Chunk", "chunk", TypeReferenceKind::Generic)));
+        assert!(!found.iter().any(|(name, _, _)| *name == "T"));
+
+        let mut ctx = RepositoryContext::new();
+        for r in refs {
+            ctx.register_type_reference("src/batch.rs", r);
+        }
+        assert_eq!(ctx.find_type_usages("SourceItem").len(), 2);
+    }
+
+    #[test]
+    fn test_extract_cpp_symbols() {
+        let content = r#"#pragma once
+#include <vector>
+
+/// Maximum number of widgets.
+#define MAX_WIDGETS 64
+#define SQUARE(x) \
+    ((x) * (x))
+
+namespace engine {
+namespace render {
+
+/**
+ * Static polymorphism base for shapes.
+ */
+template <typename Derived>
+class Shape {
+public:
+    double area() const { return static_cast<const Derived*>(this)->area_impl(); }
+
+protected:
+    ~Shape() = default;
+};
+
+class Square;
+
+// A square with a side length.
+class Square final : public Shape<Square> {
+public:
+    explicit Square(double side);
+    double area_impl() const;
+    bool operator==(const Square& other) const;
+
+private:
+    double side_;
+    std::string label_ = "{";
+};
+
+template <typename T, int N = 4>
+struct Buffer {
+    T data[N];
+};
+
+using SquareList = std::vector<Square>;
+
+}  // namespace render
+
+namespace {
+int helper(int x) { return x * 2; }
+}
+
+double total_area(const std::vector<render::Square>& squares);
+
+}  // namespace engine
+
+double engine::render::Square::area_impl() const {
+    if (side_ < 0) {
+        return 0;
+    }
+    return side_ * side_;
+}
+
+namespace A { namespace B { class C {}; } }
+"#;
+        let symbols = extract_cpp_symbols(content);
+        let find = |name: &str| symbols.iter().find(|s| s.name == name).unwrap_or_else(|| panic!("missing {}", name));
+        let described: Vec<(&str, Option<&str>)> =
+            symbols.iter().map(|s| (s.name.as_str(), s.parent.as_deref())).collect();
+
+        assert_eq!(
+            described,
+            vec![
+                ("MAX_WIDGETS", None),
+                ("SQUARE", None),
+                ("engine", None),
+                ("render", Some("engine")),
+                ("Shape", Some("engine::render")),
+                ("area", Some("engine::render::Shape")),
+                ("~Shape", Some("engine::render::Shape")),
+                ("Square", Some("engine::render")),
+                ("Square", Some("engine::render::Square")),
+                ("area_impl", Some("engine::render::Square")),
+                ("operator==", Some("engine::render::Square")),
+                ("Buffer", Some("engine::render")),
+                ("SquareList", Some("engine::render")),
+                ("helper", Some("engine")),
+                ("total_area", Some("engine")),
+                ("area_impl", Some("engine::render::Square")),
+                ("A", None),
+                ("B", Some("A")),
+                ("C", Some("A::B")),
+            ]
+        );
+
+        assert_eq!(find("MAX_WIDGETS").symbol_type, SymbolType::Constant);
+        assert_eq!(find("MAX_WIDGETS").documentation.as_deref(), Some("Maximum number of widgets."));
+        assert_eq!(find("Shape").documentation.as_deref(), Some("Static polymorphism base for shapes."));
+        assert_eq!(find("Shape").type_parameters, vec!["Derived"]);
+        assert_eq!(find("Buffer").type_parameters, vec!["T", "N"]);
+        assert_eq!(find("Buffer").symbol_type, SymbolType::Struct);
+        assert_eq!(symbols[7].documentation.as_deref(), Some("A square with a side length."));
+        assert_eq!(symbols[7].symbol_type, SymbolType::Class);
+        assert_eq!(find("~Shape").visibility, SymbolVisibility::Protected);
+        assert_eq!(find("area").symbol_type, SymbolType::Method);
+        assert_eq!(find("helper").visibility, SymbolVisibility::Private);
+        assert_eq!(find("total_area").symbol_type, SymbolType::Function);
+        assert_eq!(extract_symbols(content, Some("cpp")).len(), symbols.len());
+    }
+
+    #[test]
+    fn test_repository_context_roundtrip() {
+        let mut ctx = RepositoryContext::new();
+        for i in 0..10_000 {
+            ctx.register_symbol(&format!("src/module_{}.rs", i % 100), Symbol {
+                name: format!("function_{}", i),
+                symbol_type: if i % 2 == 0 { SymbolType::Function } else { SymbolType::Struct },
+                byte_range: (i * 10, i * 10 + 9),
+                line_range: (i, i + 1),
+                parent: (i % 3 == 0).then(|| "Parent".to_string()),
+                documentation: None,
+                type_parameters: Vec::new(),
+                visibility: SymbolVisibility::Public,
+                is_test: false,
+            });
+        }
+        ctx.register_import("src/module_0.rs", Import {
+            module_path: "std::io".to_string(),
+            symbols: vec!["Read".to_string()],
+            is_wildcard: false,
+        });
+        ctx.register_call("src/main.rs", "main", "function_1");
+        ctx.files_processed = 100;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("context.msgpack.gz");
+        assert!(RepositoryContext::is_stale(&path, Duration::from_secs(3600)));
+        ctx.save_to_file(&path).unwrap();
+        assert!(!RepositoryContext::is_stale(&path, Duration::from_secs(3600)));
+
+        let loaded = RepositoryContext::load_from_file(&path).unwrap();
+        assert_eq!(loaded.symbols.values().map(Vec::len).sum::<usize>(), 10_000);
+        assert_eq!(loaded.find_symbol_locations("function_4321"), vec!["src/module_21.rs"]);
+        let symbol = &loaded.get_file_symbols("src/module_21.rs")[43];
+        assert_eq!((symbol.symbol_type.clone(), symbol.line_range), (SymbolType::Struct, (4321, 4322)));
+        assert_eq!(loaded.imports["src/module_0.rs"][0].symbols, vec!["Read"]);
+        assert_eq!(loaded.find_callers("function_1"), vec![("src/main.rs", "main")]);
+        assert_eq!(loaded.files_processed, 100);
+
+        std::fs::write(&path, b"not a context").unwrap();
+        assert!(RepositoryContext::load_from_file(&path).is_err());
+    }
+
     #[test]
     fn test_repository_context() {
         let mut ctx = RepositoryContext::new();
@@ -622,6 +2582,9 @@ export interface MyInterface {
             line_range: (1, 10),
             parent: None,
             documentation: None,
+            type_parameters: Vec::new(),
+            visibility: SymbolVisibility::Public,
+            is_test: false,
         });
         
         ctx.register_symbol("src/lib.rs", Symbol {
@@ -631,9 +2594,276 @@ export interface MyInterface {
             line_range: (1, 5),
             parent: None,
             documentation: None,
+            type_parameters: Vec::new(),
+            visibility: SymbolVisibility::Public,
+            is_test: false,
         });
         
         assert_eq!(ctx.find_symbol_locations("main"), vec!["src/main.rs"]);
         assert_eq!(ctx.get_file_symbols("src/lib.rs").len(), 1);
+
+        ctx.register_symbol("src/lib.rs", Symbol {
+            name: "helper".to_string(),
+            symbol_type: SymbolType::Function,
+            byte_range: (50, 80),
+            line_range: (6, 8),
+            parent: None,
+            documentation: None,
+            type_parameters: Vec::new(),
+            visibility: SymbolVisibility::Private,
+            is_test: false,
+        });
+        let public: Vec<&str> = ctx.get_public_api("src/lib.rs").iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(public, vec!["process"]);
+
+        ctx.register_call("src/main.rs", "main", "process");
+        ctx.register_call("src/main.rs", "main", "process");
+        assert_eq!(ctx.find_callers("process"), vec![("src/main.rs", "main")]);
+        assert!(ctx.find_callers("main").is_empty());
     }
+
+    #[test]
+    fn test_parallel_parse_preserves_order() {
+        let sources: Vec<(String, String)> = (0..50)
+            .map(|i| (format!("src/f{}.rs", i), format!("pub fn function_{}() -> Config {{ helper() }}\n", i)))
+            .collect();
+        let files = sources.iter().map(|(path, content)| (path.as_str(), content.as_str(), "rust")).collect();
+
+        let parsed = AstParser::parallel_parse_files(files);
+        assert!(AstParser::supported_by_rayon());
+        assert_eq!(parsed.len(), 50);
+        for (i, file) in parsed.iter().enumerate() {
+            let file = file.as_ref().unwrap();
+            assert_eq!(file.path, format!("src/f{}.rs", i));
+            assert_eq!(file.symbols[0].name, format!("function_{}", i));
+            assert_eq!(file.type_references[0].type_name, "Config");
+        }
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        let parsed = AstParser::parse_timeout("def run(self):\n    go()\n", "python", Duration::from_secs(10)).unwrap();
+        assert_eq!(parsed.symbols[0].name, "run");
+        assert_eq!(parsed.call_sites[0].callee, "go");
+
+        let huge = "fn f() { g(); }\n".repeat(20_000);
+        let err = AstParser::parse_timeout(&huge, "rust", Duration::from_micros(1)).unwrap_err();
+        assert!(matches!(err, ParseError::Timeout { .. }));
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[test]
+    fn test_find_usages() {
+        let mut ctx = RepositoryContext::new();
+        ctx.register_parsed_file(AstParser::parse("src/b.rs", "pub fn function_b(config: Config) {}\n", "rust"));
+        ctx.register_parsed_file(AstParser::parse(
+            "src/a.rs",
+            "impl Handler for Service {}\n\nfn function_a() {\n    function_b(load());\n}\n",
+            "rust",
+        ));
+
+        assert_eq!(
+            ctx.find_usages("function_b"),
+            vec![&UsageSite::new("src/a.rs", 3, UsageKind::CallSite)]
+        );
+        assert_eq!(ctx.find_usages("Config")[0], &UsageSite::new("src/b.rs", 0, UsageKind::TypeAnnotation));
+        assert_eq!(ctx.find_usages("Handler")[0].usage_kind, UsageKind::Inheritance);
+        assert!(ctx.find_usages("function_a").is_empty());
+
+        ctx.register_usage("function_b", UsageSite::new("src/c.rs", 0, UsageKind::Import));
+        assert_eq!(ctx.find_usages("function_b").len(), 2);
+    }
+
+    #[test]
+    fn test_tag_test_symbols() {
+        let rust = "pub struct Parser;\n\nimpl Parser {\n    pub fn parse(&self) {}\n}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    struct Fixture;\n\n    impl Fixture {\n        fn build() {}\n    }\n\n    #[test]\n    fn parses() {\n        Parser.parse();\n    }\n}\n\nmod util {\n    pub fn helper() {}\n}\n";
+        let python = "class TestLogin(unittest.TestCase):\n    def setUp(self):\n        pass\n\ndef test_logout():\n    pass\n\ndef login():\n    pass\n";
+
+        let mut ctx = RepositoryContext::new();
+        ctx.register_parsed_file(AstParser::parse("src/parser.rs", rust, "rust"));
+        ctx.register_parsed_file(AstParser::parse("app/test_auth.py", python, "python"));
+        // There is no Go symbol extractor, so register Go functions directly
+        for (line, name) in ["TestParse", "BenchmarkParse", "parseHelper"].into_iter().enumerate() {
+            ctx.register_symbol(
+                "parse_test.go",
+                Symbol {
+                    name: name.to_string(),
+                    symbol_type: SymbolType::Function,
+                    byte_range: (0, 0),
+                    line_range: (line, line),
+                    parent: None,
+                    documentation: None,
+                    type_parameters: Vec::new(),
+                    visibility: SymbolVisibility::Unknown,
+                    is_test: false,
+                },
+            );
+        }
+        ctx.tag_test_symbols();
+
+        let names = |symbols: Vec<&Symbol>| symbols.iter().map(|s| s.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(ctx.get_test_symbols("src/parser.rs")), ["tests", "Fixture", "build", "parses"]);
+        assert_eq!(names(ctx.get_production_symbols("src/parser.rs")), ["Parser", "parse", "util", "helper"]);
+
+        let python_tests = names(ctx.get_test_symbols("app/test_auth.py"));
+        assert!(python_tests.contains(&"TestLogin".to_string()) && python_tests.contains(&"setUp".to_string()));
+        assert!(python_tests.contains(&"test_logout".to_string()) && !python_tests.contains(&"login".to_string()));
+
+        assert_eq!(names(ctx.get_test_symbols("parse_test.go")), ["TestParse", "BenchmarkParse"]);
+        assert!(ctx.get_test_symbols("missing.rs").is_empty());
+    }
+
+    fn decorator_pairs(content: &str, language: &str) -> Vec<(String, String)> {
+        let parsed = AstParser::parse("f", content, language);
+        AstParser::extract_decorator_associations(&parsed)
+            .iter()
+            .map(|a| (a.entity_name.clone(), a.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_attach_decorators_matches_name_and_line() {
+        let python = "class Reader:
+    @property
+    def size(self): pass
+
+class Writer:
+    @staticmethod
+    @cache
+    def size(): pass
+";
+        let parsed = AstParser::parse("io.py", python, "python");
+        let entity = |name: &str, start_line, end_line| EntityBoundary {
+            name: name.to_string(),
+            entity_type: "method".to_string(),
+            start_line,
+            end_line,
+            signature: None,
+            decorators: Vec::new(),
+        };
+        // Writer.size starts on its first decorator, Reader.size on its `def`
+        let mut entities = vec![entity("size", 3, 3), entity("size", 6, 8), entity("Writer", 5, 8)];
+        parsed.attach_decorators(&mut entities);
+        assert_eq!(entities[0].decorators, vec!["property"]);
+        assert_eq!(entities[1].decorators, vec!["staticmethod", "cache"]);
+        assert!(entities[2].decorators.is_empty());
+    }
+
+    #[test]
+    fn test_extract_decorator_associations() {
+        let python = "class User:
+    @property
+    def name(self):
+        pass
+
+    # cached
+    @classmethod
+
+    def load(cls):
+        pass
+
+@app.route('/users', methods=['GET'])
+async def list_users():
+    pass
+
+@app.route(
+    '/users/<id>',
+    methods=['POST'],
+)
+def create_user():
+    pass
+";
+        let parsed = AstParser::parse("app.py", python, "python");
+        assert_eq!(parsed.decorators[0].decorator_name, "property");
+        assert_eq!(parsed.decorators[0].line, 1);
+        assert_eq!(parsed.decorators[2].decorator_args, vec!["'/users'", "methods=['GET']"]);
+        assert_eq!(
+            decorator_pairs(python, "python")[1..].to_vec(),
+            vec![
+                ("load".to_string(), "classmethod".to_string()),
+                ("list_users".to_string(), "app.route('/users', methods=['GET'])".to_string()),
+                ("create_user".to_string(), "app.route('/users/<id>', methods=['POST'])".to_string()),
+            ]
+        );
+
+        let java = "@RestController
+public class UserController {
+    @Autowired
+    private UserService service;
+
+    @Override
+    public String toString() {
+        return \"users\";
+    }
+}
+";
+        assert_eq!(
+            decorator_pairs(java, "java"),
+            vec![
+                ("UserController".to_string(), "RestController".to_string()),
+                ("service".to_string(), "Autowired".to_string()),
+                ("toString".to_string(), "Override".to_string()),
+            ]
+        );
+
+        let ts = "@Injectable()
+export class UserService {
+  @Input() name: string;
+}
+";
+        assert_eq!(
+            decorator_pairs(ts, "typescript"),
+            vec![
+                ("UserService".to_string(), "Injectable".to_string()),
+                ("name".to_string(), "Input".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_rust_attributes() {
+        let rust = "#![allow(dead_code)]
+#[derive(
+    Debug,
+    Clone,
+)]
+#[serde(rename_all = \"camelCase\")]
+pub struct Config {
+    #[serde(default)]
+    pub name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parses() {}
+}
+";
+        assert_eq!(
+            decorator_pairs(rust, "rust"),
+            vec![
+                ("Config".to_string(), "derive(Debug, Clone)".to_string()),
+                ("Config".to_string(), "serde(rename_all = \"camelCase\")".to_string()),
+                ("name".to_string(), "serde(default)".to_string()),
+                ("tests".to_string(), "cfg(test)".to_string()),
+                ("parses".to_string(), "test".to_string()),
+            ]
+        );
+
+        let parsed = AstParser::parse("lib.rs", "#[a]
+#[b]
+#[c]
+#[d]
+#[e]
+#[f]
+fn busy() {}
+", "rust");
+        assert_eq!(decorators_for(&parsed.decorators, "busy", 1), vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(decorators_for(&parsed.decorators, "busy", 7), vec!["a", "b", "c", "d", "e"]);
+        assert!(decorators_for(&parsed.decorators, "busy", 8).is_empty());
+        assert!(decorator_pairs("@decorator
+def f(): pass
+", "go").is_empty());
+    }
+
 }
@@ -1,107 +1,274 @@
 //! Sentence-based chunker that respects sentence boundaries.
 
+use std::collections::HashSet;
+
 use anyhow::Result;
 
 use super::base::{count_tokens, Chunker};
 use crate::types::{Chunk, ChunkConfig, SourceItem};
 
-/// Sentence-based chunker that splits text at sentence boundaries.
+/// Abbreviations that do not end a sentence, lowercase without the final period.
+const ENGLISH_ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "cf", "al", "approx",
+    "fig", "figs", "eq", "eqs", "ref", "refs", "sec", "ch", "vol", "no", "pp", "ca", "resp", "inc", "ltd",
+    "co", "corp", "dept", "est", "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct",
+    "nov", "dec", "u.s", "ph.d", "m.sc", "b.sc",
+];
+
+const GERMAN_ABBREVIATIONS: &[&str] = &[
+    "z.b", "bzw", "usw", "d.h", "u.a", "vgl", "ca", "nr", "dr", "prof", "bspw", "ggf", "evtl", "z.t",
+    "s", "abb", "hrsg", "jh", "str", "inkl", "zzgl",
+];
+
+const FRENCH_ABBREVIATIONS: &[&str] = &[
+    "m", "mme", "mlle", "dr", "pr", "etc", "cf", "p.ex", "env", "av", "apr", "j.-c", "fig", "vol", "éd",
+];
+
+const SPANISH_ABBREVIATIONS: &[&str] = &[
+    "sr", "sra", "srta", "dr", "dra", "ud", "uds", "etc", "p.ej", "pág", "núm", "aprox", "fig", "vol",
+    "ej", "cap",
+];
+
+/// Closing quotes and brackets that may follow sentence-ending punctuation.
+const CLOSERS: &[char] = &['"', '\'', '”', '’', ')', ']', '»'];
+
+/// Finds sentence boundaries using Punkt-style heuristics.
 ///
-/// This chunker identifies sentence endings and groups sentences into
-/// chunks that respect the token limit while maintaining readability.
-pub struct SentenceChunker {
+/// A delimiter ends a sentence only when it is followed by whitespace and
+/// the next word does not start with a lowercase letter, so decimals
+/// (`1.5`) and quoted speech followed by its attribution (`"Hello." said
+/// John`) stay in one sentence. A period after a known abbreviation or a
+/// single-letter initial never ends a sentence.
+#[derive(Debug, Clone)]
+pub struct SentenceBoundaryDetector {
     /// Sentence-ending delimiters
     delimiters: Vec<char>,
+    /// Lowercase abbreviations without their final period
+    abbreviations: HashSet<String>,
 }
 
-impl SentenceChunker {
-    /// Create a new sentence chunker with default delimiters.
+impl SentenceBoundaryDetector {
+    /// Create a detector with English abbreviations.
     pub fn new() -> Self {
+        Self::for_language("en")
+    }
+
+    /// Create a detector with abbreviations for a language (`en`, `de`,
+    /// `fr`, `es`, or the English name). Other languages use English.
+    pub fn for_language(language: &str) -> Self {
+        let abbreviations = match language.to_lowercase().as_str() {
+            "de" | "german" => GERMAN_ABBREVIATIONS,
+            "fr" | "french" => FRENCH_ABBREVIATIONS,
+            "es" | "spanish" => SPANISH_ABBREVIATIONS,
+            _ => ENGLISH_ABBREVIATIONS,
+        };
         Self {
             delimiters: vec!['.', '!', '?'],
+            abbreviations: HashSet::new(),
         }
+        .with_abbreviations(abbreviations)
     }
 
-    /// Create a sentence chunker with custom delimiters.
-    pub fn with_delimiters(delimiters: Vec<char>) -> Self {
-        Self { delimiters }
+    /// Add abbreviations, with or without their final period (`"approx."`).
+    pub fn with_abbreviations(mut self, abbreviations: &[&str]) -> Self {
+        self.abbreviations.extend(
+            abbreviations
+                .iter()
+                .map(|a| a.trim_end_matches('.').to_lowercase()),
+        );
+        self
     }
 
-    /// Split text into sentences.
-    fn split_sentences(&self, text: &str) -> Vec<Sentence> {
-        let mut sentences = Vec::new();
-        let mut current_start = 0;
-        let mut current_text = String::new();
-        let chars: Vec<char> = text.chars().collect();
+    /// Set the sentence-ending delimiters.
+    pub fn with_delimiters(mut self, delimiters: Vec<char>) -> Self {
+        self.delimiters = delimiters;
+        self
+    }
+
+    /// Split text into sentence spans `(start, end)` in bytes.
+    ///
+    /// Spans cover the whole text; each includes the spaces after its final
+    /// punctuation but not a following newline.
+    pub fn split(&self, text: &str) -> Vec<(usize, usize)> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut spans = Vec::new();
+        let mut start = 0;
         let mut i = 0;
 
         while i < chars.len() {
-            let c = chars[i];
-            current_text.push(c);
-
-            if self.delimiters.contains(&c) {
-                // Check if followed by whitespace or end of string
-                let is_sentence_end = i + 1 >= chars.len() || chars[i + 1].is_whitespace();
-
-                if is_sentence_end {
-                    // Include trailing whitespace in the sentence
-                    let mut j = i + 1;
-                    while j < chars.len() && chars[j].is_whitespace() && chars[j] != '\n' {
-                        current_text.push(chars[j]);
-                        j += 1;
-                    }
+            if !self.delimiters.contains(&chars[i].1) {
+                i += 1;
+                continue;
+            }
 
-                    let trimmed = current_text.trim();
-                    if !trimmed.is_empty() {
-                        sentences.push(Sentence {
-                            text: current_text.clone(),
-                            start_index: current_start,
-                            end_index: current_start + current_text.len(),
-                            token_count: count_tokens(&current_text),
-                        });
-                    }
+            // The punctuation run, e.g. `?!`, `...` or `."`
+            let run_start = i;
+            while i < chars.len() && self.delimiters.contains(&chars[i].1) {
+                i += 1;
+            }
+            let delimiter_count = i - run_start;
+            while i < chars.len() && CLOSERS.contains(&chars[i].1) {
+                i += 1;
+            }
 
-                    current_start += current_text.len();
-                    current_text = String::new();
-                    i = j;
-                    continue;
-                }
+            if !self.is_boundary(text, &chars, run_start, delimiter_count, i) {
+                continue;
+            }
+
+            // Include trailing whitespace up to the next line
+            while i < chars.len() && chars[i].1.is_whitespace() && chars[i].1 != '\n' {
+                i += 1;
             }
+            let end = chars.get(i).map_or(text.len(), |&(pos, _)| pos);
+            if !text[start..end].trim().is_empty() {
+                spans.push((start, end));
+                start = end;
+            }
+        }
+
+        if !text[start..].trim().is_empty() {
+            spans.push((start, text.len()));
+        } else if let Some(last) = spans.last_mut() {
+            last.1 = text.len();
+        }
+        spans
+    }
+
+    /// Whether the punctuation run starting at `run_start` ends a sentence,
+    /// given that the run (with closers) ends before `next`.
+    fn is_boundary(
+        &self,
+        text: &str,
+        chars: &[(usize, char)],
+        run_start: usize,
+        delimiter_count: usize,
+        next: usize,
+    ) -> bool {
+        // End of text always ends a sentence
+        let Some(&(_, after)) = chars.get(next) else {
+            return true;
+        };
+        // Decimals, URLs and `e.g.x`-style tokens
+        if !after.is_whitespace() {
+            return false;
+        }
+
+        // A blank line ends a sentence regardless of what follows
+        let rest = &chars[next..];
+        let newlines = rest.iter().take_while(|(_, c)| c.is_whitespace()).filter(|(_, c)| *c == '\n').count();
+        if newlines >= 2 {
+            return true;
+        }
+
+        // The next word must not start lowercase (skipping opening quotes)
+        let next_word = rest
+            .iter()
+            .map(|&(_, c)| c)
+            .find(|c| !c.is_whitespace() && !matches!(c, '"' | '\'' | '“' | '‘' | '(' | '«'));
+        if next_word.is_some_and(char::is_lowercase) {
+            return false;
+        }
+
+        // A single period may belong to an abbreviation or initial
+        if delimiter_count == 1 && chars[run_start].1 == '.' {
+            let word_start = chars[..run_start]
+                .iter()
+                .rposition(|(_, c)| c.is_whitespace() || matches!(c, '(' | '"' | '“'))
+                .map_or(0, |p| p + 1);
+            let word = &text[chars.get(word_start).map_or(0, |&(pos, _)| pos)..chars[run_start].0];
+            let word = word.to_lowercase();
+            if self.abbreviations.contains(&word) {
+                return false;
+            }
+            // Initials like `J. Smith`
+            let mut letters = word.chars();
+            if letters.next().is_some_and(char::is_alphabetic) && letters.next().is_none() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for SentenceBoundaryDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sentence-based chunker that splits text at sentence boundaries.
+///
+/// This chunker identifies sentence endings with a
+/// `SentenceBoundaryDetector` and groups sentences into chunks that respect
+/// the token limit while maintaining readability.
+pub struct SentenceChunker {
+    detector: SentenceBoundaryDetector,
+}
 
-            i += 1;
+impl SentenceChunker {
+    /// Create a new sentence chunker with default delimiters.
+    pub fn new() -> Self {
+        Self {
+            detector: SentenceBoundaryDetector::new(),
         }
+    }
+
+    /// Create a sentence chunker with custom delimiters.
+    pub fn with_delimiters(delimiters: Vec<char>) -> Self {
+        Self {
+            detector: SentenceBoundaryDetector::new().with_delimiters(delimiters),
+        }
+    }
+
+    /// Create a sentence chunker that also treats the given words as
+    /// abbreviations, in addition to the English defaults.
+    pub fn with_abbreviations(abbrevs: &[&str]) -> Self {
+        Self {
+            detector: SentenceBoundaryDetector::new().with_abbreviations(abbrevs),
+        }
+    }
 
-        // Add remaining text as final sentence
-        if !current_text.trim().is_empty() {
-            sentences.push(Sentence {
-                text: current_text.clone(),
-                start_index: current_start,
-                end_index: current_start + current_text.len(),
-                token_count: count_tokens(&current_text),
-            });
+    /// Create a sentence chunker with abbreviations for a language.
+    pub fn for_language(language: &str) -> Self {
+        Self {
+            detector: SentenceBoundaryDetector::for_language(language),
         }
+    }
 
-        sentences
+    /// Split text into sentences.
+    fn split_sentences(&self, text: &str) -> Vec<Sentence> {
+        self.detector
+            .split(text)
+            .into_iter()
+            .map(|(start, end)| Sentence {
+                text: text[start..end].to_string(),
+                start_index: start,
+                end_index: end,
+                token_count: count_tokens(&text[start..end]),
+            })
+            .collect()
     }
 
     /// Merge short sentences to meet minimum character requirement.
+    ///
+    /// A short sentence is merged into the following one, or into the
+    /// previous one if it is last.
     fn merge_short_sentences(&self, sentences: Vec<Sentence>, min_chars: usize) -> Vec<Sentence> {
         if sentences.is_empty() {
             return sentences;
         }
 
-        let mut result = Vec::new();
+        let mut result: Vec<Sentence> = Vec::new();
         let mut current: Option<Sentence> = None;
 
         for sentence in sentences {
             current = match current {
                 None => Some(sentence),
                 Some(mut curr) => {
-                    if curr.text.len() < min_chars {
+                    if curr.text.trim().len() < min_chars {
                         // Merge with current
-                        curr.text.push_str(&sentence.text);
-                        curr.end_index = sentence.end_index;
-                        curr.token_count = count_tokens(&curr.text);
+                        curr.append(sentence);
                         Some(curr)
                     } else {
                         result.push(curr);
@@ -112,7 +279,10 @@ impl SentenceChunker {
         }
 
         if let Some(curr) = current {
-            result.push(curr);
+            match result.last_mut() {
+                Some(previous) if curr.text.trim().len() < min_chars => previous.append(curr),
+                _ => result.push(curr),
+            }
         }
 
         result
@@ -133,6 +303,15 @@ struct Sentence {
     token_count: usize,
 }
 
+impl Sentence {
+    /// Extend this sentence with the one following it.
+    fn append(&mut self, next: Sentence) {
+        self.text.push_str(&next.text);
+        self.end_index = next.end_index;
+        self.token_count = count_tokens(&self.text);
+    }
+}
+
 impl Chunker for SentenceChunker {
     fn name(&self) -> &'static str {
         "sentence"
@@ -229,6 +408,7 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
@@ -246,6 +426,62 @@ mod tests {
         assert!(chunks[0].content.contains("second sentence"));
     }
 
+    fn sentences(chunker: &SentenceChunker, text: &str) -> Vec<String> {
+        chunker.split_sentences(text).into_iter().map(|s| s.text.trim().to_string()).collect()
+    }
+
+    #[test]
+    fn test_scientific_abbreviations() {
+        let chunker = SentenceChunker::new();
+        let text = "Dr. Smith et al. measured a 1.5 mm shift (see Fig. 3), i.e. approx. twice the expected value. \
+                    The effect was confirmed by J. Doe in the U.S. lab... Results vary, e.g. with temperature. \
+                    Why? Nobody knows!";
+
+        assert_eq!(
+            sentences(&chunker, text),
+            vec![
+                "Dr. Smith et al. measured a 1.5 mm shift (see Fig. 3), i.e. approx. twice the expected value.",
+                "The effect was confirmed by J. Doe in the U.S. lab...",
+                "Results vary, e.g. with temperature.",
+                "Why?",
+                "Nobody knows!",
+            ]
+        );
+
+        // Custom abbreviations extend the defaults
+        let text = "We sampled Bacillus spp. Several colonies grew. Then we stopped.";
+        assert_eq!(sentences(&chunker, text).len(), 3);
+        let chunker = SentenceChunker::with_abbreviations(&["spp."]);
+        assert_eq!(
+            sentences(&chunker, text),
+            vec!["We sampled Bacillus spp. Several colonies grew.", "Then we stopped."]
+        );
+    }
+
+    #[test]
+    fn test_quoted_speech_and_languages() {
+        let chunker = SentenceChunker::new();
+        assert_eq!(
+            sentences(&chunker, "\"Hello.\" said John. \"Stop!\" He ran."),
+            vec!["\"Hello.\" said John.", "\"Stop!\"", "He ran."]
+        );
+
+        let german = "Das gilt z.B. für Wasser. Vgl. Abschnitt 2 usw. Danach folgt mehr.";
+        assert_eq!(sentences(&SentenceChunker::for_language("de"), german).len(), 2);
+        assert_eq!(sentences(&SentenceChunker::for_language("en"), german).len(), 4);
+    }
+
+    #[test]
+    fn test_short_sentences_merge_with_neighbors() {
+        let chunker = SentenceChunker::new();
+        let text = "Yes. This sentence is long enough on its own. Ok.";
+        let merged = chunker.merge_short_sentences(chunker.split_sentences(text), 10);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].text, text);
+        assert_eq!((merged[0].start_index, merged[0].end_index), (0, text.len()));
+    }
+
     #[test]
     fn test_multiple_chunks() {
         let chunker = SentenceChunker::new();
@@ -0,0 +1,499 @@
+//! Shell script chunker that splits on functions, heredocs and case blocks.
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::base::{count_tokens, Chunker};
+use super::recursive_chunker::RecursiveChunker;
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
+
+lazy_static! {
+    static ref FUNCTION_RE: Regex = Regex::new(
+        r"^\s*(?:function\s+([A-Za-z_][\w:.-]*)\s*(?:\(\s*\))?|([A-Za-z_][\w:.-]*)\s*\(\s*\))\s*(?:\{|$)"
+    ).unwrap();
+    static ref HEREDOC_RE: Regex = Regex::new(r#"(?:^|[^<])<<-?\s*(['"]?)([A-Za-z_][A-Za-z0-9_]*)['"]?"#).unwrap();
+    static ref CASE_RE: Regex = Regex::new(r"^\s*case\s+.+\s+in\b").unwrap();
+    static ref CASE_WORD_RE: Regex = Regex::new(r"(?:^|[\s;])(case|esac)(?:$|[\s;)])").unwrap();
+}
+
+/// Languages and file extensions handled by the shell chunker.
+pub(crate) const SHELL_LANGUAGES: &[&str] = &["shell", "bash", "sh", "zsh"];
+
+/// Shell script chunker.
+///
+/// Function definitions (`name() {` and `function name`), top-level
+/// heredocs and `case`/`esac` blocks are kept whole, and consecutive small
+/// ones are grouped until `chunk_size` is reached. Comment lines directly
+/// above a function stay with it. Other top-level commands, and blocks too
+/// large for one chunk, are split with the `RecursiveChunker`.
+pub struct ShellChunker {
+    /// Fallback for content outside functions and oversized blocks
+    recursive_chunker: RecursiveChunker,
+}
+
+/// A top-level span of the script, in lines (0-based, inclusive).
+#[derive(Debug)]
+struct ShellBlock {
+    kind: ShellBlockKind,
+    start_line: usize,
+    end_line: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ShellBlockKind {
+    Function(String),
+    Heredoc,
+    Case,
+    /// Commands outside any function, heredoc or case block
+    Other,
+}
+
+impl ShellChunker {
+    /// Create a new shell chunker.
+    pub fn new() -> Self {
+        Self {
+            recursive_chunker: RecursiveChunker::new(),
+        }
+    }
+
+    /// Split a script into top-level blocks, in order.
+    ///
+    /// Blank gaps between blocks are dropped.
+    fn find_blocks(lines: &[&str]) -> Vec<ShellBlock> {
+        let mut blocks = Vec::new();
+        let mut other_start: Option<usize> = None;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let structured = if let Some(name) = function_name(line) {
+                Some((ShellBlockKind::Function(name), function_end(lines, i)))
+            } else if CASE_RE.is_match(line) {
+                Some((ShellBlockKind::Case, case_end(lines, i)))
+            } else {
+                heredoc_delimiter(line).map(|delimiter| (ShellBlockKind::Heredoc, heredoc_end(lines, i, &delimiter)))
+            };
+
+            let Some((kind, end_line)) = structured else {
+                if other_start.is_none() && !line.trim().is_empty() {
+                    other_start = Some(i);
+                }
+                i += 1;
+                continue;
+            };
+
+            // Comments directly above a function document it
+            let mut start_line = i;
+            if matches!(kind, ShellBlockKind::Function(_)) {
+                while start_line > other_start.unwrap_or(i)
+                    && is_comment(lines[start_line - 1])
+                    && !(start_line == 1 && lines[0].starts_with("#!"))
+                {
+                    start_line -= 1;
+                }
+            }
+            if let Some(start) = other_start.take() {
+                Self::push_other(&mut blocks, lines, start, start_line);
+            }
+            blocks.push(ShellBlock { kind, start_line, end_line });
+            i = end_line + 1;
+        }
+
+        if let Some(start) = other_start {
+            Self::push_other(&mut blocks, lines, start, lines.len());
+        }
+        blocks
+    }
+
+    /// Push the lines `start..end` as an `Other` block, trimming blank lines.
+    fn push_other(blocks: &mut Vec<ShellBlock>, lines: &[&str], start: usize, end: usize) {
+        let Some(last) = (start..end).rev().find(|&l| !lines[l].trim().is_empty()) else {
+            return;
+        };
+        if let Some(first) = (start..=last).find(|&l| !lines[l].trim().is_empty()) {
+            blocks.push(ShellBlock {
+                kind: ShellBlockKind::Other,
+                start_line: first,
+                end_line: last,
+            });
+        }
+    }
+
+    /// Build a chunk for the bytes `start..end` of the item.
+    fn create_chunk(
+        &self,
+        item: &SourceItem,
+        line_starts: &[usize],
+        start: usize,
+        end: usize,
+        function_names: Vec<String>,
+        chunk_index: usize,
+    ) -> Chunk {
+        let text = &item.content[start..end];
+        let mut chunk = Chunk::new(
+            item.id,
+            item.source_id,
+            item.source_kind,
+            text.to_string(),
+            count_tokens(text),
+            start,
+            end,
+            chunk_index,
+        );
+
+        chunk.metadata = ChunkMetadata {
+            content_type: Some("shell".to_string()),
+            language: Some("shell".to_string()),
+            path: item.extract_path().map(String::from),
+            symbol_name: (function_names.len() == 1).then(|| function_names[0].clone()),
+            line_range: Some((line_of(line_starts, start), line_of(line_starts, end.saturating_sub(1).max(start)))),
+            keywords: function_names,
+            ..Default::default()
+        };
+        chunk
+    }
+
+    /// Split the bytes `start..end` with the recursive chunker.
+    #[allow(clippy::too_many_arguments)]
+    fn split_with_fallback(
+        &self,
+        item: &SourceItem,
+        config: &ChunkConfig,
+        line_starts: &[usize],
+        start: usize,
+        end: usize,
+        function_names: &[String],
+        chunks: &mut Vec<Chunk>,
+    ) -> Result<()> {
+        let mut section = item.clone();
+        section.content = item.content[start..end].to_string();
+
+        for piece in self.recursive_chunker.chunk(&section, config)? {
+            let piece_start = start + piece.start_index;
+            let piece_end = (start + piece.end_index).min(end);
+            let chunk = self.create_chunk(item, line_starts, piece_start, piece_end, function_names.to_vec(), chunks.len());
+            chunks.push(chunk);
+        }
+        Ok(())
+    }
+}
+
+impl Default for ShellChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for ShellChunker {
+    fn name(&self) -> &'static str {
+        "shell"
+    }
+
+    fn description(&self) -> &'static str {
+        "Shell script chunker that keeps functions, heredocs and case blocks whole"
+    }
+
+    fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let content = &item.content;
+        if content.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut config = config.clone();
+        config.chunk_size = config.effective_chunk_size();
+        let chunk_size = config.chunk_size;
+
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let mut line_starts = Vec::with_capacity(lines.len() + 1);
+        let mut offset = 0;
+        for line in &lines {
+            line_starts.push(offset);
+            offset += line.len();
+        }
+        line_starts.push(offset);
+
+        let mut chunks = Vec::new();
+        // Byte range and function names of the blocks grouped so far
+        let mut group: Option<(usize, usize)> = None;
+        let mut group_names: Vec<String> = Vec::new();
+
+        for block in Self::find_blocks(&lines) {
+            let start = line_starts[block.start_line];
+            let end = line_starts[block.end_line + 1];
+            let tokens = count_tokens(&content[start..end]);
+
+            let fits = match group {
+                Some((group_start, _)) => count_tokens(&content[group_start..end]) <= chunk_size,
+                None => tokens <= chunk_size,
+            };
+            if block.kind == ShellBlockKind::Other || !fits {
+                if let Some((group_start, group_end)) = group.take() {
+                    let names = std::mem::take(&mut group_names);
+                    let chunk = self.create_chunk(item, &line_starts, group_start, group_end, names, chunks.len());
+                    chunks.push(chunk);
+                }
+            }
+
+            let names = match &block.kind {
+                ShellBlockKind::Function(name) => vec![name.clone()],
+                _ => vec![],
+            };
+            if block.kind == ShellBlockKind::Other || tokens > chunk_size {
+                self.split_with_fallback(item, &config, &line_starts, start, end, &names, &mut chunks)?;
+                continue;
+            }
+
+            group = Some((group.map_or(start, |(group_start, _)| group_start), end));
+            group_names.extend(names);
+        }
+
+        if let Some((group_start, group_end)) = group {
+            let chunk = self.create_chunk(item, &line_starts, group_start, group_end, group_names, chunks.len());
+            chunks.push(chunk);
+        }
+
+        Ok(chunks)
+    }
+}
+
+/// Name of the function a line defines, if it starts a definition.
+fn function_name(line: &str) -> Option<String> {
+    let caps = FUNCTION_RE.captures(line)?;
+    let name = caps.get(1).or_else(|| caps.get(2))?.as_str();
+    // `if (` and friends are not definitions
+    if matches!(name, "if" | "then" | "else" | "elif" | "while" | "until" | "for" | "do" | "case") {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+/// Delimiter of a heredoc opened on this line; `<<<` here-strings have none.
+fn heredoc_delimiter(line: &str) -> Option<String> {
+    HEREDOC_RE.captures(&strip_comment(line)).map(|caps| caps[2].to_string())
+}
+
+/// Last line of a heredoc opened on line `start`.
+fn heredoc_end(lines: &[&str], start: usize, delimiter: &str) -> usize {
+    (start + 1..lines.len())
+        .find(|&l| lines[l].trim() == delimiter)
+        .unwrap_or(lines.len() - 1)
+}
+
+/// Last line of the `case` block opened on line `start`.
+fn case_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0i32;
+    for (l, line) in lines.iter().enumerate().skip(start) {
+        for caps in CASE_WORD_RE.captures_iter(&strip_comment(line)) {
+            depth += if &caps[1] == "case" { 1 } else { -1 };
+        }
+        if depth <= 0 {
+            return l;
+        }
+    }
+    lines.len() - 1
+}
+
+/// Last line of the function defined on line `start`.
+///
+/// Braces are counted outside quotes and comments, skipping heredoc
+/// bodies. A definition whose body never opens ends on its own line.
+fn function_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0i32;
+    let mut opened = false;
+    let mut l = start;
+
+    while l < lines.len() {
+        let code = strip_comment(lines[l]);
+        for c in unquoted_chars(&code) {
+            match c {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if opened && depth <= 0 {
+            return l;
+        }
+        if !opened && l > start + 1 {
+            return start;
+        }
+        if let Some(delimiter) = heredoc_delimiter(lines[l]) {
+            l = heredoc_end(lines, l, &delimiter);
+        }
+        l += 1;
+    }
+    lines.len() - 1
+}
+
+fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+/// The line without a trailing `#` comment outside quotes.
+fn strip_comment(line: &str) -> String {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return line[..i].to_string(),
+            None => {}
+        }
+        previous = c;
+    }
+    line.to_string()
+}
+
+/// Characters of a line outside single and double quotes.
+fn unquoted_chars(line: &str) -> impl Iterator<Item = char> + '_ {
+    let mut quote: Option<char> = None;
+    line.chars().filter(move |&c| match quote {
+        Some(q) => {
+            if c == q {
+                quote = None;
+            }
+            false
+        }
+        None if c == '\'' || c == '"' => {
+            quote = Some(c);
+            false
+        }
+        None => true,
+    })
+}
+
+/// 1-based line number of a byte offset.
+fn line_of(line_starts: &[usize], byte: usize) -> usize {
+    line_starts.partition_point(|&start| start <= byte).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+    use uuid::Uuid;
+
+    fn create_script_item(content: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::CodeRepo,
+            content_type: "text/x-shellscript".to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({"path": "scripts/deploy.sh"}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    /// A deployment script with 5 functions, a heredoc and a case block.
+    fn deploy_script() -> String {
+        let mut script = String::from("#!/usr/bin/env bash\nset -euo pipefail\n\nAPP=checkout\nREGION=${REGION:-eu-west-1}\n\n");
+        for name in ["log", "require"] {
+            script.push_str(&format!("# {name} helper\n{name}() {{\n    echo \"[{name}] $*\" >&2\n}}\n\n"));
+        }
+        script.push_str("function build_image {\n");
+        for step in 0..12 {
+            script.push_str(&format!("    log \"build step {step}\"\n    docker build --target stage{step} -t \"$APP:$step\" .\n"));
+        }
+        script.push_str("}\n\n");
+        script.push_str("push_image() {\n    local tag=\"$1\"\n    if [ -z \"$tag\" ]; then\n        log \"missing tag {\"\n        return 1\n    fi\n");
+        for registry in 0..6 {
+            script.push_str(&format!("    docker push \"registry{registry}.example.com/$APP:$tag\"\n"));
+        }
+        script.push_str("}\n\n");
+        script.push_str("function rollout() {\n    kubectl apply -f - <<EOF\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: }broken{\nEOF\n");
+        for check in 0..6 {
+            script.push_str(&format!("    kubectl rollout status deployment/$APP --timeout={check}0s\n"));
+        }
+        script.push_str("}\n\n");
+        script.push_str("cat > /tmp/release-notes.txt <<'NOTES'\nRelease of the checkout service.\nfunction fake() {\nNOTES\n\n");
+        script.push_str("case \"${1:-deploy}\" in\n    build) build_image ;;\n    push) push_image latest ;;\n    deploy)\n        build_image\n        push_image latest\n        rollout\n        ;;\n    *) echo \"usage\" ;;\nesac\n");
+        while script.lines().count() < 100 {
+            script.push_str("log \"done\"\n");
+        }
+        script
+    }
+
+    #[test]
+    fn test_deploy_script_blocks() {
+        let script = deploy_script();
+        assert_eq!(script.lines().count(), 100);
+
+        let lines: Vec<&str> = script.split_inclusive('\n').collect();
+        let blocks = ShellChunker::find_blocks(&lines);
+        let functions: Vec<&str> = blocks
+            .iter()
+            .filter_map(|b| match &b.kind {
+                ShellBlockKind::Function(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(functions, ["log", "require", "build_image", "push_image", "rollout"]);
+        assert_eq!(blocks.iter().filter(|b| b.kind == ShellBlockKind::Heredoc).count(), 1);
+        assert_eq!(blocks.iter().filter(|b| b.kind == ShellBlockKind::Case).count(), 1);
+
+        // The rollout heredoc and its unbalanced braces stay inside the function
+        let rollout = blocks.iter().find(|b| b.kind == ShellBlockKind::Function("rollout".into())).unwrap();
+        assert_eq!(lines[rollout.end_line].trim(), "}");
+        assert!(lines[rollout.start_line..=rollout.end_line].iter().any(|l| l.contains("kubectl rollout status")));
+
+        // Helper comments stay with their function
+        let log = &blocks[1];
+        assert_eq!(lines[log.start_line].trim(), "# log helper");
+    }
+
+    #[test]
+    fn test_deploy_script_chunks() {
+        let item = create_script_item(&deploy_script());
+        let chunks = ShellChunker::new().chunk(&item, &ChunkConfig::with_size(120)).unwrap();
+
+        // Small helpers are grouped into one chunk
+        let helpers = chunks.iter().find(|c| c.metadata.keywords.contains(&"log".to_string())).unwrap();
+        assert_eq!(helpers.metadata.keywords, ["log", "require"]);
+
+        for name in ["build_image", "push_image", "rollout"] {
+            assert!(chunks.iter().any(|c| c.metadata.keywords.contains(&name.to_string())), "{name}");
+        }
+        let rollout = chunks.iter().find(|c| c.metadata.keywords == ["rollout"]).unwrap();
+        assert!(rollout.content.contains("<<EOF") && rollout.content.contains("\nEOF\n"));
+
+        let notes = chunks.iter().find(|c| c.content.contains("<<'NOTES'")).unwrap();
+        assert!(notes.content.contains("function fake() {\nNOTES\n"));
+        assert!(chunks.iter().all(|c| !c.metadata.keywords.contains(&"fake".to_string())));
+
+        for (idx, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.chunk_index, idx);
+            assert_eq!(chunk.metadata.language.as_deref(), Some("shell"));
+            assert_eq!(&item.content[chunk.start_index..chunk.end_index], chunk.content);
+        }
+        for pair in chunks.windows(2) {
+            assert!(pair[0].end_index <= pair[1].start_index);
+        }
+    }
+
+    #[test]
+    fn test_function_keywords_survive_enrichment() {
+        use crate::enrichment::{ChunkContext, ContextBuilder};
+
+        let item = create_script_item(&deploy_script());
+        let chunks = ShellChunker::new().chunk(&item, &ChunkConfig::with_size(120)).unwrap();
+        let builder = ContextBuilder::new();
+        let enriched: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| builder.enrich(chunk, ChunkContext::new("scripts/deploy.sh", "shell")))
+            .collect();
+
+        let helpers = enriched.iter().find(|e| e.chunk.content.contains("# log helper")).unwrap();
+        assert_eq!(helpers.chunk.metadata.keywords[..2], ["log", "require"]);
+        let rollout = enriched.iter().find(|e| e.chunk.metadata.symbol_name.as_deref() == Some("rollout")).unwrap();
+        assert_eq!(rollout.chunk.metadata.keywords[0], "rollout");
+        assert_eq!(rollout.chunk.metadata.keywords.iter().filter(|k| *k == "rollout").count(), 1);
+    }
+}
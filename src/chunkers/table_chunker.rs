@@ -1,4 +1,4 @@
-//! Table chunker for markdown and CSV tables.
+//! Table chunker for markdown, CSV and JSON tables.
 
 use anyhow::Result;
 use regex::Regex;
@@ -6,7 +6,7 @@ use regex::Regex;
 use super::base::{count_tokens, Chunker};
 use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
 
-/// Table chunker for markdown tables and CSV data.
+/// Table chunker for markdown tables, CSV data and JSON arrays of records.
 ///
 /// This chunker understands table structure and preserves headers
 /// when splitting large tables into smaller chunks.
@@ -84,6 +84,44 @@ impl TableChunker {
         Some((header, data_rows))
     }
 
+    /// Parse a JSON array of uniform objects into column names and rows.
+    ///
+    /// Columns are the first object's keys, sorted. Later objects may omit
+    /// columns (leaving an empty cell) but may not add new ones. Strings are
+    /// used as-is, `null` becomes an empty cell, and other values are
+    /// rendered as JSON.
+    pub fn detect_and_parse_json_table(content: &str) -> Option<(Vec<String>, Vec<Vec<String>>)> {
+        let trimmed = content.trim_start();
+        if !trimmed.starts_with('[') {
+            return None;
+        }
+
+        let records: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(trimmed).ok()?;
+        let mut columns: Vec<String> = records.first()?.keys().cloned().collect();
+        columns.sort();
+        if columns.is_empty() {
+            return None;
+        }
+
+        let mut rows = Vec::with_capacity(records.len());
+        for record in &records {
+            if record.keys().any(|key| columns.binary_search(key).is_err()) {
+                return None;
+            }
+            let row = columns
+                .iter()
+                .map(|column| match record.get(column) {
+                    None | Some(serde_json::Value::Null) => String::new(),
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect();
+            rows.push(row);
+        }
+
+        Some((columns, rows))
+    }
+
     /// Detect if content is markdown table or CSV.
     fn is_markdown_table(&self, content: &str) -> bool {
         let first_line = content.lines().next().unwrap_or("");
@@ -179,11 +217,12 @@ impl TableChunker {
         chunks
     }
 
-    /// Chunk CSV content.
+    /// Chunk CSV content, repeating the header line in every chunk.
     fn chunk_csv(
         &self,
         header: &str,
         data_rows: Vec<String>,
+        content_type: &str,
         item: &SourceItem,
         config: &ChunkConfig,
     ) -> Vec<Chunk> {
@@ -218,7 +257,7 @@ impl TableChunker {
                 );
 
                 chunk.metadata = ChunkMetadata {
-                    content_type: Some("csv".to_string()),
+                    content_type: Some(content_type.to_string()),
                     ..Default::default()
                 };
 
@@ -252,7 +291,7 @@ impl TableChunker {
             );
 
             chunk.metadata = ChunkMetadata {
-                content_type: Some("csv".to_string()),
+                content_type: Some(content_type.to_string()),
                 ..Default::default()
             };
 
@@ -263,6 +302,21 @@ impl TableChunker {
     }
 }
 
+/// Join fields into a CSV line, quoting fields that need it.
+fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 impl Default for TableChunker {
     fn default() -> Self {
         Self::new()
@@ -275,7 +329,7 @@ impl Chunker for TableChunker {
     }
 
     fn description(&self) -> &'static str {
-        "Chunks tables (markdown/CSV) while preserving headers in each chunk"
+        "Chunks tables (markdown/CSV/JSON) while preserving headers in each chunk"
     }
 
     fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
@@ -285,12 +339,22 @@ impl Chunker for TableChunker {
         }
 
         // Detect table type and parse
+        if let Some((columns, rows)) = Self::detect_and_parse_json_table(content) {
+            let header = csv_line(&columns);
+            let data_rows = rows.iter().map(|row| csv_line(row)).collect();
+            let mut chunks = self.chunk_csv(&header, data_rows, "json_table", item, config);
+            for chunk in &mut chunks {
+                chunk.metadata.keywords = columns.clone();
+            }
+            return Ok(chunks);
+        }
+
         if self.is_markdown_table(content) {
             if let Some((header, separator, data_rows)) = self.parse_markdown_table(content) {
                 return Ok(self.chunk_markdown_table(&header, &separator, data_rows, item, config));
             }
         } else if let Some((header, data_rows)) = self.parse_csv(content) {
-            return Ok(self.chunk_csv(&header, data_rows, item, config));
+            return Ok(self.chunk_csv(&header, data_rows, "csv", item, config));
         }
 
         // Fallback: treat as single chunk
@@ -323,6 +387,7 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
@@ -354,4 +419,59 @@ mod tests {
         let chunks = chunker.chunk(&item, &config).unwrap();
         assert!(!chunks.is_empty());
     }
+
+    #[test]
+    fn test_json_table_export() {
+        let records: Vec<_> = (0..500)
+            .map(|i| serde_json::json!({"name": format!("user{i}"), "age": 20 + i % 50, "city": "New York, NY", "manager": null}))
+            .collect();
+        let item = SourceItem {
+            content_type: "application/json".to_string(),
+            ..create_table_item(&serde_json::to_string_pretty(&records).unwrap())
+        };
+
+        let chunks = TableChunker::new().chunk(&item, &ChunkConfig::with_size(200)).unwrap();
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.content.starts_with("age,city,manager,name\n"));
+            assert_eq!(chunk.metadata.content_type.as_deref(), Some("json_table"));
+            assert_eq!(chunk.metadata.keywords, ["age", "city", "manager", "name"]);
+        }
+
+        let rows: Vec<_> = chunks.iter().flat_map(|c| c.content.lines().skip(1)).collect();
+        assert_eq!(rows.len(), 500);
+        assert_eq!(rows[0], "20,\"New York, NY\",,user0");
+    }
+
+    #[test]
+    fn test_column_keywords_survive_enrichment() {
+        use crate::enrichment::{ChunkContext, ContextBuilder};
+
+        let records: Vec<_> = (0..20)
+            .map(|i| serde_json::json!({"name": format!("user{i}"), "city": "Lisbon", "team": "platform"}))
+            .collect();
+        let item = SourceItem {
+            content_type: "application/json".to_string(),
+            ..create_table_item(&serde_json::to_string(&records).unwrap())
+        };
+        let chunk = TableChunker::new().chunk(&item, &ChunkConfig::default()).unwrap().remove(0);
+        let enriched = ContextBuilder::new().enrich(chunk, ChunkContext::new("users.json", "json"));
+
+        let keywords = &enriched.chunk.metadata.keywords;
+        assert_eq!(keywords[..3], ["city", "name", "team"]);
+        assert!(keywords[3..].iter().any(|k| k == "platform"), "{keywords:?}");
+    }
+
+    #[test]
+    fn test_json_table_detection() {
+        let (columns, rows) =
+            TableChunker::detect_and_parse_json_table(r#"[{"b": 1, "a": "x"}, {"a": "y"}]"#).unwrap();
+        assert_eq!(columns, ["a", "b"]);
+        assert_eq!(rows, [vec!["x", "1"], vec!["y", ""]]);
+
+        // Extra keys, non-objects and non-arrays are not tables
+        assert!(TableChunker::detect_and_parse_json_table(r#"[{"a": 1}, {"a": 2, "b": 3}]"#).is_none());
+        assert!(TableChunker::detect_and_parse_json_table("[1, 2, 3]").is_none());
+        assert!(TableChunker::detect_and_parse_json_table(r#"{"a": 1}"#).is_none());
+    }
 }
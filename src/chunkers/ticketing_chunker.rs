@@ -1,9 +1,12 @@
 //! Ticketing chunker for issues, PRs, and tickets.
 
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use tracing::warn;
 
 use super::base::{count_tokens, Chunker};
+use super::patch_chunker::{line_starts, split_diff};
 use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
 
 /// Ticketing chunker for issues, PRs, Jira tickets, and similar content.
@@ -29,8 +32,31 @@ impl TicketingChunker {
     }
 
     /// Parse ticket from JSON format.
-    fn parse_ticket_json(&self, content: &str) -> Option<Ticket> {
-        serde_json::from_str(content).ok()
+    ///
+    /// GitHub Issues and Linear payloads are detected by their
+    /// format-specific fields; anything else is read as a generic `Ticket`.
+    /// Fails if the content is not JSON or does not match the detected
+    /// format's schema.
+    fn parse_ticket_json(&self, content: &str) -> Result<Ticket> {
+        let mut value: Value = serde_json::from_str(content).context("Ticket is not valid JSON")?;
+
+        // Linear GraphQL responses wrap the issue in `data.issue`
+        if let Some(issue) = value.pointer_mut("/data/issue") {
+            value = issue.take();
+        }
+
+        let ticket = if is_linear_issue(&value) {
+            serde_json::from_value::<LinearIssue>(value)
+                .context("Ticket looks like a Linear issue but does not match its schema")?
+                .into()
+        } else if is_github_issue(&value) {
+            serde_json::from_value::<GithubIssue>(value)
+                .context("Ticket looks like a GitHub issue but does not match its schema")?
+                .into()
+        } else {
+            serde_json::from_value(value).context("Ticket JSON does not match the generic ticket schema")?
+        };
+        Ok(ticket)
     }
 
     /// Parse ticket from structured text format.
@@ -98,14 +124,12 @@ impl TicketingChunker {
     fn save_section(&self, ticket: &mut Ticket, section: &str, content: &str) {
         match section {
             "description" => ticket.description = Some(content.to_string()),
-            "comments" => {
-                // If content is present but no comments yet, add as single comment
-                if !content.is_empty() && ticket.comments.is_empty() {
-                    ticket.comments.push(Comment {
-                        author: None,
-                        body: content.to_string(),
-                    });
-                }
+            // If content is present but no comments yet, add as single comment
+            "comments" if !content.is_empty() && ticket.comments.is_empty() => {
+                ticket.comments.push(Comment {
+                    author: None,
+                    body: content.to_string(),
+                });
             }
             _ => {}
         }
@@ -135,6 +159,10 @@ impl TicketingChunker {
             parts.push(format!("**Assignee**: {}", assignee));
         }
 
+        if !ticket.labels.is_empty() {
+            parts.push(format!("**Labels**: {}", ticket.labels.join(", ")));
+        }
+
         parts.join("\n")
     }
 
@@ -145,6 +173,60 @@ impl TicketingChunker {
         })
     }
 
+    /// Chunk the unified diffs in a ticket's description and `diff` field.
+    ///
+    /// Each changed file becomes one chunk, split between hunks when it
+    /// exceeds the chunk size. Every piece repeats the file header so it
+    /// stands on its own.
+    fn extract_and_chunk_diff(&self, ticket: &Ticket, item: &SourceItem, config: &ChunkConfig) -> Vec<Chunk> {
+        // Text tickets are parsed line by line with whitespace trimmed, which
+        // would strip the leading space of context lines, so their diffs are
+        // read from the raw content
+        let texts: Vec<&str> = if item.content_type.contains("json") {
+            ticket.description.iter().chain(&ticket.diff).map(String::as_str).collect()
+        } else {
+            vec![&item.content]
+        };
+
+        let mut chunks = Vec::new();
+        for text in texts {
+            let (_, files) = split_diff(text);
+            if files.is_empty() {
+                continue;
+            }
+            let location = TextLocation::find(&item.content, text);
+            let starts = line_starts(text);
+
+            for file in files {
+                let language = file.language();
+                for piece in file.pieces(config.chunk_size) {
+                    let span = file.piece_span(&piece, &starts, text.len());
+                    let content = piece.content.trim_end().to_string();
+                    let mut chunk = Chunk::new(
+                        item.id,
+                        item.source_id,
+                        item.source_kind,
+                        content.clone(),
+                        count_tokens(&content),
+                        location.offset(text, span.start, item.content.len()),
+                        location.offset(text, span.end, item.content.len()),
+                        chunks.len(),
+                    );
+                    chunk.metadata = ChunkMetadata {
+                        content_type: Some("diff".to_string()),
+                        path: file.path.clone(),
+                        changed_file: file.path.clone(),
+                        language: language.clone(),
+                        diff_stats: Some(piece.stats),
+                        ..Default::default()
+                    };
+                    chunks.push(chunk);
+                }
+            }
+        }
+        chunks
+    }
+
     /// Format comments section.
     fn format_comments(&self, ticket: &Ticket) -> Option<String> {
         if ticket.comments.is_empty() {
@@ -165,6 +247,40 @@ impl TicketingChunker {
     }
 }
 
+/// Where a text parsed out of an item sits in the item's content.
+enum TextLocation {
+    /// Copied verbatim, starting at this offset
+    Verbatim(usize),
+    /// Escaped inside a JSON string whose contents start at this offset
+    JsonString(usize),
+    /// Not found; offsets cover the whole content
+    Unknown,
+}
+
+impl TextLocation {
+    fn find(content: &str, text: &str) -> Self {
+        if let Some(start) = content.find(text) {
+            return Self::Verbatim(start);
+        }
+        let escaped = serde_json::to_string(text).unwrap_or_default();
+        match escaped.get(1..escaped.len().saturating_sub(1)).and_then(|inner| content.find(inner)) {
+            Some(start) => Self::JsonString(start),
+            None => Self::Unknown,
+        }
+    }
+
+    /// Offset in the content of byte `pos` of `text`.
+    fn offset(&self, text: &str, pos: usize, content_len: usize) -> usize {
+        match self {
+            Self::Verbatim(start) => start + pos,
+            // Escaping the prefix gives its length inside the JSON string
+            Self::JsonString(start) => start + serde_json::to_string(&text[..pos]).map_or(0, |s| s.len() - 2),
+            Self::Unknown if pos == 0 => 0,
+            Self::Unknown => content_len,
+        }
+    }
+}
+
 /// Represents a ticket/issue.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Ticket {
@@ -183,6 +299,11 @@ struct Ticket {
     #[serde(skip_serializing_if = "Option::is_none")]
     reporter: Option<String>,
     #[serde(default)]
+    labels: Vec<String>,
+    /// Unified diff attached to a pull request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+    #[serde(default)]
     comments: Vec<Comment>,
 }
 
@@ -194,6 +315,165 @@ struct Comment {
     body: String,
 }
 
+/// Linear issues have an `identifier` (e.g. `ENG-123`), and nest the state
+/// and comment connection in objects.
+fn is_linear_issue(value: &Value) -> bool {
+    value.get("identifier").is_some_and(Value::is_string)
+        || value.get("state").is_some_and(Value::is_object)
+        || value.pointer("/comments/nodes").is_some()
+}
+
+/// GitHub issues and pull requests are identified by a numeric `number`.
+fn is_github_issue(value: &Value) -> bool {
+    value.get("number").is_some_and(Value::is_u64)
+}
+
+/// An issue or pull request from the GitHub REST API.
+#[derive(Debug, Deserialize)]
+struct GithubIssue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    state: String,
+    #[serde(default)]
+    labels: Vec<GithubLabel>,
+    #[serde(default)]
+    assignees: Vec<GithubUser>,
+    user: Option<GithubUser>,
+    /// The REST API returns a comment count here; comments fetched
+    /// separately can be inlined as a list
+    #[serde(default, deserialize_with = "github_comments")]
+    comments: Vec<GithubComment>,
+    pull_request: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubLabel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubComment {
+    user: Option<GithubUser>,
+    #[serde(default)]
+    body: String,
+}
+
+fn github_comments<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<GithubComment>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        comments @ Value::Array(_) => serde_json::from_value(comments).map_err(serde::de::Error::custom),
+        _ => Ok(Vec::new()),
+    }
+}
+
+impl From<GithubIssue> for Ticket {
+    fn from(issue: GithubIssue) -> Self {
+        let prefix = if issue.pull_request.is_some() { "PR #" } else { "#" };
+        let assignees: Vec<_> = issue.assignees.into_iter().map(|user| user.login).collect();
+
+        Ticket {
+            key: Some(format!("{}{}", prefix, issue.number)),
+            title: Some(issue.title),
+            description: issue.body.filter(|body| !body.trim().is_empty()),
+            status: Some(issue.state),
+            priority: None,
+            assignee: (!assignees.is_empty()).then(|| assignees.join(", ")),
+            reporter: issue.user.map(|user| user.login),
+            labels: issue.labels.into_iter().map(|label| label.name).collect(),
+            diff: None,
+            comments: issue
+                .comments
+                .into_iter()
+                .map(|comment| Comment {
+                    author: comment.user.map(|user| user.login),
+                    body: comment.body,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An issue from the Linear GraphQL API.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinearIssue {
+    id: String,
+    /// Human-readable key such as `ENG-123`
+    identifier: Option<String>,
+    title: String,
+    description: Option<String>,
+    state: Option<LinearNamed>,
+    /// 0 = none, 1 = urgent, 2 = high, 3 = medium, 4 = low
+    priority: Option<u8>,
+    priority_label: Option<String>,
+    assignee: Option<LinearNamed>,
+    creator: Option<LinearNamed>,
+    labels: Option<LinearConnection<LinearNamed>>,
+    comments: Option<LinearConnection<LinearComment>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinearNamed {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinearConnection<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinearComment {
+    user: Option<LinearNamed>,
+    #[serde(default)]
+    body: String,
+}
+
+impl From<LinearIssue> for Ticket {
+    fn from(issue: LinearIssue) -> Self {
+        let priority = issue.priority_label.or_else(|| {
+            let label = match issue.priority? {
+                1 => "Urgent",
+                2 => "High",
+                3 => "Medium",
+                4 => "Low",
+                _ => return None,
+            };
+            Some(label.to_string())
+        });
+
+        Ticket {
+            key: Some(issue.identifier.unwrap_or(issue.id)),
+            title: Some(issue.title),
+            description: issue.description.filter(|description| !description.trim().is_empty()),
+            status: issue.state.map(|state| state.name),
+            priority,
+            assignee: issue.assignee.map(|assignee| assignee.name),
+            reporter: issue.creator.map(|creator| creator.name),
+            labels: issue
+                .labels
+                .map(|labels| labels.nodes.into_iter().map(|label| label.name).collect())
+                .unwrap_or_default(),
+            diff: None,
+            comments: issue
+                .comments
+                .map(|comments| comments.nodes)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|comment| Comment {
+                    author: comment.user.map(|user| user.name),
+                    body: comment.body,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl Default for TicketingChunker {
     fn default() -> Self {
         Self::new()
@@ -216,12 +496,26 @@ impl Chunker for TicketingChunker {
         }
 
         // Parse the ticket
-        let ticket = if item.content_type.contains("json") {
-            self.parse_ticket_json(content).unwrap_or_else(|| self.parse_ticket_text(content))
+        let mut ticket = if item.content_type.contains("json") {
+            self.parse_ticket_json(content).unwrap_or_else(|e| {
+                warn!(item_id = %item.id, error = format!("{:#}", e), "Failed to parse ticket JSON, reading it as text");
+                self.parse_ticket_text(content)
+            })
         } else {
             self.parse_ticket_text(content)
         };
 
+        // Diffs get their own chunks; the description keeps only the prose
+        let diff_chunks = self.extract_and_chunk_diff(&ticket, item, config);
+        if let Some(description) = ticket.description.take() {
+            let (prose, files) = split_diff(&description);
+            ticket.description = if files.is_empty() {
+                Some(description)
+            } else {
+                Some(prose).filter(|prose| !prose.trim().is_empty())
+            };
+        }
+
         let mut chunks = Vec::new();
         let mut chunk_index = 0;
 
@@ -324,6 +618,12 @@ impl Chunker for TicketingChunker {
             }
         }
 
+        for mut chunk in diff_chunks {
+            chunk.chunk_index = chunk_index;
+            chunks.push(chunk);
+            chunk_index += 1;
+        }
+
         // Create comment chunks
         if self.separate_comments && !ticket.comments.is_empty() {
             let comments_content = self.format_comments(&ticket).unwrap_or_default();
@@ -396,6 +696,10 @@ impl Chunker for TicketingChunker {
             ));
         }
 
+        for chunk in &mut chunks {
+            chunk.metadata.label_names = ticket.labels.clone();
+        }
+
         Ok(chunks)
     }
 }
@@ -415,6 +719,7 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
@@ -463,10 +768,213 @@ Comments:
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         };
 
         let config = ChunkConfig::with_size(1000);
         let chunks = chunker.chunk(&item, &config).unwrap();
         assert!(!chunks.is_empty());
     }
+
+    fn create_json_item(content: &str) -> SourceItem {
+        SourceItem {
+            content_type: "application/json".to_string(),
+            ..create_ticket_item(content)
+        }
+    }
+
+    #[test]
+    fn test_github_issue() {
+        let content = r#"{
+            "url": "https://api.github.com/repos/octocat/hello-world/issues/1347",
+            "html_url": "https://github.com/octocat/hello-world/issues/1347",
+            "number": 1347,
+            "state": "open",
+            "title": "Found a bug",
+            "body": "I'm having a problem with this.",
+            "user": {"login": "octocat", "id": 1, "type": "User"},
+            "labels": [
+                {"id": 208045946, "name": "bug", "color": "f29513", "default": true},
+                {"id": 208045947, "name": "good first issue", "color": "7057ff", "default": true}
+            ],
+            "assignees": [{"login": "octocat", "id": 1}, {"login": "hubot", "id": 2}],
+            "comments": [
+                {"id": 1, "user": {"login": "hubot"}, "body": "Me too"}
+            ],
+            "pull_request": null,
+            "created_at": "2011-04-22T13:33:48Z"
+        }"#;
+
+        let ticket = TicketingChunker::new().parse_ticket_json(content).unwrap();
+        assert_eq!(ticket.key.as_deref(), Some("#1347"));
+        assert_eq!(ticket.status.as_deref(), Some("open"));
+        assert_eq!(ticket.assignee.as_deref(), Some("octocat, hubot"));
+        assert_eq!(ticket.reporter.as_deref(), Some("octocat"));
+        assert_eq!(ticket.comments[0].author.as_deref(), Some("hubot"));
+
+        let chunks = TicketingChunker::new()
+            .chunk(&create_json_item(content), &ChunkConfig::with_size(1000))
+            .unwrap();
+        assert!(chunks[0].content.contains("**Labels**: bug, good first issue"));
+        assert!(chunks.iter().all(|c| c.metadata.label_names == ["bug", "good first issue"]));
+    }
+
+    #[test]
+    fn test_mismatched_ticket_json_is_an_error() {
+        // A GitHub issue without its required title
+        let content = r#"{"number": 7, "state": "open", "body": "Crash on start"}"#;
+        let error = TicketingChunker::new().parse_ticket_json(content).unwrap_err();
+        assert!(error.to_string().contains("GitHub issue"));
+        assert!(TicketingChunker::new().parse_ticket_json("not json").is_err());
+
+        // Chunking falls back to reading the content as text
+        let chunks = TicketingChunker::new()
+            .chunk(&create_json_item(content), &ChunkConfig::with_size(1000))
+            .unwrap();
+        assert!(chunks.iter().any(|c| c.content.contains("Crash on start")));
+    }
+
+    #[test]
+    fn test_github_pull_request_with_comment_count() {
+        let content = r#"{
+            "number": 42,
+            "state": "closed",
+            "title": "Add retries",
+            "body": null,
+            "labels": [],
+            "assignees": [],
+            "comments": 3,
+            "pull_request": {"url": "https://api.github.com/repos/octocat/hello-world/pulls/42"}
+        }"#;
+
+        let ticket = TicketingChunker::new().parse_ticket_json(content).unwrap();
+        assert_eq!(ticket.key.as_deref(), Some("PR #42"));
+        assert!(ticket.description.is_none());
+        assert!(ticket.comments.is_empty());
+    }
+
+    #[test]
+    fn test_linear_issue() {
+        let content = r#"{
+            "data": {
+                "issue": {
+                    "id": "2174add1-f7c8-44e3-bbf3-2d60b5ea8bc9",
+                    "identifier": "ENG-512",
+                    "title": "Sync fails for large workspaces",
+                    "description": "Sync times out after 30s when a workspace has more than 10k issues.",
+                    "priority": 2,
+                    "state": {"id": "a1", "name": "In Progress", "type": "started"},
+                    "assignee": {"id": "u1", "name": "Ada Lovelace"},
+                    "labels": {"nodes": [{"name": "Bug"}, {"name": "Performance"}]},
+                    "comments": {
+                        "nodes": [
+                            {"user": {"name": "Grace Hopper"}, "body": "Reproduced on the staging workspace."}
+                        ]
+                    }
+                }
+            }
+        }"#;
+
+        let ticket = TicketingChunker::new().parse_ticket_json(content).unwrap();
+        assert_eq!(ticket.key.as_deref(), Some("ENG-512"));
+        assert_eq!(ticket.status.as_deref(), Some("In Progress"));
+        assert_eq!(ticket.priority.as_deref(), Some("High"));
+        assert_eq!(ticket.assignee.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(ticket.comments[0].author.as_deref(), Some("Grace Hopper"));
+
+        let chunks = TicketingChunker::new()
+            .chunk(&create_json_item(content), &ChunkConfig::with_size(1000))
+            .unwrap();
+        assert_eq!(chunks[0].metadata.label_names, ["Bug", "Performance"]);
+    }
+
+    /// A PR description wrapping a 200-line, two-file diff in prose.
+    fn pr_description_with_diff() -> String {
+        let mut diff = String::from("diff --git a/src/retry.rs b/src/retry.rs\nindex 3b18e51..a9c2f3d 100644\n--- a/src/retry.rs\n+++ b/src/retry.rs\n");
+        for hunk in 0..3 {
+            let start = hunk * 100 + 1;
+            diff.push_str(&format!("@@ -{start},40 +{start},40 @@ impl Retry {{\n"));
+            for line in 0..20 {
+                diff.push_str(&format!("     let attempt_{hunk}_{line} = self.attempts;\n"));
+            }
+            for line in 0..20 {
+                diff.push_str(&format!("-    sleep(Duration::from_millis({line}));\n"));
+            }
+            for line in 0..20 {
+                diff.push_str(&format!("+    sleep(self.backoff.delay({line}));\n"));
+            }
+        }
+        diff.push_str("diff --git a/README.md b/README.md\n--- a/README.md\n+++ b/README.md\n@@ -1,7 +1,8 @@\n # Retry\n-Retries failed calls.\n+Retries failed calls\n+with exponential backoff.\n \n Usage:\n \n     cargo add retry\n \n");
+        assert_eq!(diff.lines().count(), 200);
+
+        format!("Switches retries to exponential backoff.\n\n```diff\n{diff}```\n\n- Tested against the staging API\n")
+    }
+
+    #[test]
+    fn test_split_diff() {
+        let (prose, files) = split_diff(&pr_description_with_diff());
+
+        assert_eq!(prose, "Switches retries to exponential backoff.\n\n\n- Tested against the staging API\n");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path.as_deref(), Some("src/retry.rs"));
+        assert_eq!(files[0].hunks.len(), 3);
+        assert_eq!(files[1].path.as_deref(), Some("README.md"));
+        assert_eq!(files[1].hunks[0].lines().count(), 10);
+    }
+
+    #[test]
+    fn test_pr_diff_chunks() {
+        let content = serde_json::json!({
+            "key": "PR-7",
+            "title": "Exponential backoff",
+            "description": pr_description_with_diff(),
+        })
+        .to_string();
+        let item = create_json_item(&content);
+
+        let chunks = TicketingChunker::new().chunk(&item, &ChunkConfig::with_size(10_000)).unwrap();
+        assert_eq!(chunks[0].metadata.content_type.as_deref(), Some("description"));
+        assert!(chunks[0].content.contains("Tested against the staging API"));
+        assert!(!chunks[0].content.contains("diff --git"));
+
+        let diffs: Vec<_> = chunks.iter().filter(|c| c.metadata.content_type.as_deref() == Some("diff")).collect();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].metadata.path.as_deref(), Some("src/retry.rs"));
+        assert_eq!(diffs[0].metadata.language.as_deref(), Some("rust"));
+        assert_eq!(diffs[1].metadata.path.as_deref(), Some("README.md"));
+
+        // Small chunks split the large file at hunk boundaries, keeping the header
+        let chunks = TicketingChunker::new().chunk(&item, &ChunkConfig::with_size(500)).unwrap();
+        let retry: Vec<_> = chunks.iter().filter(|c| c.metadata.path.as_deref() == Some("src/retry.rs")).collect();
+        assert_eq!(retry.len(), 3);
+        for chunk in retry {
+            assert!(chunk.content.starts_with("diff --git a/src/retry.rs b/src/retry.rs\n"));
+            assert_eq!(chunk.content.matches("\n@@ ").count(), 1);
+        }
+        let indices: Vec<_> = chunks.iter().map(|c| c.chunk_index).collect();
+        assert_eq!(indices, (0..chunks.len()).collect::<Vec<_>>());
+
+        // Offsets point at the escaped diff inside the JSON description
+        for chunk in chunks.iter().filter(|c| c.metadata.content_type.as_deref() == Some("diff")) {
+            let escaped = &content[chunk.start_index..chunk.end_index];
+            let source: String = serde_json::from_str(&format!("\"{}\"", escaped)).unwrap();
+            assert!(chunk.content.ends_with(source.trim_end()));
+        }
+    }
+
+    #[test]
+    fn test_text_ticket_diff_offsets() {
+        let content = format!("Title: Exponential backoff\nDescription:\n{}", pr_description_with_diff());
+        let mut item = create_json_item(&content);
+        item.content_type = "text/plain".to_string();
+
+        let chunks = TicketingChunker::new().chunk(&item, &ChunkConfig::with_size(10_000)).unwrap();
+        let diffs: Vec<_> = chunks.iter().filter(|c| c.metadata.content_type.as_deref() == Some("diff")).collect();
+        assert_eq!(diffs.len(), 2);
+        for chunk in diffs {
+            assert_eq!(content[chunk.start_index..chunk.end_index].trim_end(), chunk.content);
+        }
+        // Context lines keep their leading space
+        assert!(chunks.iter().any(|c| c.content.contains("\n     let attempt_0_0")));
+    }
 }
@@ -0,0 +1,174 @@
+//! Per-thread cache of token counts.
+//!
+//! Chunkers count the same text repeatedly, e.g. a file's import block
+//! while probing chunk boundaries. Counts are cached per thread, keyed by
+//! the FNV-1a hash and byte length of the text, so lookups never contend.
+
+use std::cell::RefCell;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use lru::LruCache;
+use serde::Serialize;
+
+/// Default number of cached counts per thread.
+pub const DEFAULT_TOKEN_CACHE_CAPACITY: usize = 1024;
+
+/// Hits and misses across all threads.
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    pub(crate) static THREAD_TOKEN_CACHE: RefCell<TokenCache> = RefCell::new(TokenCache::default());
+}
+
+/// LRU cache of token counts keyed by content hash and length.
+pub struct TokenCache {
+    counts: LruCache<(u64, usize), usize>,
+    hits: u64,
+    misses: u64,
+}
+
+impl TokenCache {
+    /// Create a cache holding up to `capacity` counts (at least one).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            counts: LruCache::new(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Cached count for `text`, computing and caching it with `count` on a miss.
+    pub fn get_or_insert_with(&mut self, text: &str, count: impl FnOnce() -> usize) -> usize {
+        // The length guards against hash collisions between texts of different sizes
+        let key = (fnv1a(text.as_bytes()), text.len());
+        if let Some(&tokens) = self.counts.get(&key) {
+            self.hits += 1;
+            HITS.fetch_add(1, Ordering::Relaxed);
+            return tokens;
+        }
+
+        self.misses += 1;
+        MISSES.fetch_add(1, Ordering::Relaxed);
+        let tokens = count();
+        self.counts.put(key, tokens);
+        tokens
+    }
+
+    /// Fraction of lookups served from this cache, 0.0 before any lookup.
+    pub fn hit_rate(&self) -> f32 {
+        hit_rate(self.hits, self.misses)
+    }
+
+    /// Number of cached counts.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+impl Default for TokenCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_TOKEN_CACHE_CAPACITY)
+    }
+}
+
+/// Token cache lookups across all threads.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TokenCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f32,
+}
+
+/// Lookups made through `count_tokens` since startup.
+pub fn token_cache_stats() -> TokenCacheStats {
+    let (hits, misses) = (HITS.load(Ordering::Relaxed), MISSES.load(Ordering::Relaxed));
+    TokenCacheStats {
+        hits,
+        misses,
+        hit_rate: hit_rate(hits, misses),
+    }
+}
+
+fn hit_rate(hits: u64, misses: u64) -> f32 {
+    match hits + misses {
+        0 => 0.0,
+        total => hits as f32 / total as f32,
+    }
+}
+
+/// 64-bit FNV-1a hash.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes
+        .iter()
+        .fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunkers::{count_tokens, CodeChunker, Chunker, TokenCounter};
+    use crate::enrichment::{ChunkContext, ContextBuilder};
+    use crate::types::{ChunkConfig, SourceItem, SourceKind};
+
+    #[test]
+    fn test_lru_eviction_and_hit_rate() {
+        let mut cache = TokenCache::new(2);
+        assert_eq!(cache.hit_rate(), 0.0);
+
+        assert_eq!(cache.get_or_insert_with("a", || 1), 1);
+        assert_eq!(cache.get_or_insert_with("b", || 2), 2);
+        assert_eq!(cache.get_or_insert_with("a", || unreachable!()), 1);
+        // "b" is least recently used
+        cache.get_or_insert_with("c", || 3);
+        assert_eq!(cache.get_or_insert_with("b", || 20), 20);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.hit_rate(), 0.2);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_enrichment_hits_cache() {
+        let functions: String = (0..200)
+            .map(|i| format!("pub fn handler_{i}(input: &str) -> usize {{\n    let trimmed = input.trim();\n    trimmed.len() + {i}\n}}\n\n"))
+            .collect();
+        let item = SourceItem {
+            id: uuid::Uuid::new_v4(),
+            source_id: uuid::Uuid::new_v4(),
+            source_kind: SourceKind::CodeRepo,
+            content_type: "text/code:rust".to_string(),
+            content: format!("use std::collections::HashMap;\nuse std::sync::Arc;\n\n{}", functions),
+            metadata: serde_json::json!({}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        };
+        let builder = ContextBuilder::new().with_token_budget(256);
+        let context = ChunkContext::new("src/handlers.rs", "rust").with_dependencies(vec!["std".to_string()]);
+
+        // Enriching re-counts each chunk's content, plus the shared file
+        // prefix and separator
+        let chunks = CodeChunker::new().chunk(&item, &ChunkConfig::with_size(128)).unwrap();
+        let before = THREAD_TOKEN_CACHE.with(|cache| (cache.borrow().hits, cache.borrow().misses));
+        for chunk in chunks {
+            builder.enrich(chunk, context.clone());
+        }
+        let after = THREAD_TOKEN_CACHE.with(|cache| (cache.borrow().hits, cache.borrow().misses));
+
+        let (hits, misses) = (after.0 - before.0, after.1 - before.1);
+        assert!(hit_rate(hits, misses) > 0.6, "{} hits, {} misses", hits, misses);
+        // Cached counts match the tokenizer
+        let text = "fn main() {}";
+        let exact = crate::chunkers::TiktokenCounter::new().count_tokens(text);
+        assert_eq!(count_tokens(text), exact);
+        assert_eq!(count_tokens(text), exact);
+    }
+}
@@ -69,7 +69,7 @@ impl Chunker for TokenChunker {
                 0
             } else {
                 // Find the approximate start by decoding tokens before this chunk
-                self.counter.decode(&tokens[..start_token].to_vec()).len()
+                self.counter.decode(&tokens[..start_token]).len()
             };
             let end_char = start_char + chunk_text.len();
 
@@ -114,6 +114,7 @@ mod tests {
             content: content.to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
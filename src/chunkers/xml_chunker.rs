@@ -0,0 +1,420 @@
+//! XML chunker that splits on named elements or at a fixed depth.
+
+use anyhow::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::base::{count_tokens, Chunker};
+use super::recursive_chunker::RecursiveChunker;
+use crate::types::{Chunk, ChunkConfig, ChunkMetadata, SourceItem};
+
+/// XML chunker.
+///
+/// With `split_element` set, emits one chunk per element with that name
+/// (matched on the qualified or local name); matching elements nested in
+/// another match stay inside it. Otherwise emits one chunk per subtree at
+/// `max_depth`, where the root element is at depth 0. Content between the
+/// selected elements (text, comments and other elements) becomes chunks of
+/// its own; runs of bare start and end tags are skipped. Elements larger
+/// than `chunk_size` are split with the `RecursiveChunker`.
+///
+/// Chunk content is the verbatim slice at its offsets. The root start tag,
+/// carrying namespace declarations and root attributes, is recorded in the
+/// `root_tag` key of `ChunkMetadata::extra`. Documents that fail to parse,
+/// or have no matching elements, fall back to the `RecursiveChunker`.
+pub struct XmlChunker {
+    /// Element to emit one chunk per, empty to split by depth
+    split_element: String,
+    /// Depth of the subtrees to emit when `split_element` is empty
+    max_depth: usize,
+    /// Fallback for malformed documents and oversized elements
+    recursive_chunker: RecursiveChunker,
+}
+
+/// An element selected for a chunk, or content between selected elements.
+#[derive(Debug)]
+struct XmlElement {
+    /// Element name, `None` for content between selected elements
+    name: Option<String>,
+    /// Names from the root down to the element, or to the root for content
+    /// between elements
+    path: Vec<String>,
+    start: usize,
+    end: usize,
+}
+
+/// Elements selected from a document, in document order, and its root
+/// start tag.
+#[derive(Debug)]
+struct XmlSelection {
+    elements: Vec<XmlElement>,
+    /// Root start tag, e.g. `<beans xmlns="...">`
+    root_tag: String,
+}
+
+impl XmlChunker {
+    /// Create a new XML chunker that emits the children of the root element.
+    pub fn new() -> Self {
+        Self {
+            split_element: String::new(),
+            max_depth: 1,
+            recursive_chunker: RecursiveChunker::new(),
+        }
+    }
+
+    /// Chunker for Maven `pom.xml` files, one chunk per `<dependency>`.
+    pub fn for_maven_pom() -> Self {
+        Self::new().with_split_element("dependency")
+    }
+
+    /// Chunker for Spring XML configuration, one chunk per `<bean>`.
+    pub fn for_spring_config() -> Self {
+        Self::new().with_split_element("bean")
+    }
+
+    /// Emit one chunk per element with this name.
+    pub fn with_split_element(mut self, element: impl Into<String>) -> Self {
+        self.split_element = element.into();
+        self
+    }
+
+    /// Emit one chunk per subtree at this depth (0 is the root element).
+    ///
+    /// Ignored while a split element is set.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Whether an element starting at `depth` should become a chunk.
+    fn selects(&self, name: &str, depth: usize) -> bool {
+        if self.split_element.is_empty() {
+            return depth == self.max_depth;
+        }
+        name == self.split_element || name.rsplit(':').next() == Some(self.split_element.as_str())
+    }
+
+    /// Parse a document and select the elements to emit, along with the
+    /// content between them.
+    fn select(&self, content: &str) -> Result<XmlSelection> {
+        let mut reader = Reader::from_str(content);
+        let mut stack: Vec<String> = Vec::new();
+        let mut elements = Vec::new();
+        // Start offset and depth of the element being captured
+        let mut open: Option<(usize, usize)> = None;
+        let mut root_tag = String::new();
+        let mut root_name = String::new();
+        // Start of the content since the last selected element, and whether
+        // it holds more than whitespace and bare tags
+        let mut gap_start = 0;
+        let mut gap_has_content = false;
+
+        loop {
+            let event_start = reader.buffer_position() as usize;
+            let event = reader.read_event()?;
+            let event_end = reader.buffer_position() as usize;
+
+            match event {
+                Event::Start(ref tag) | Event::Empty(ref tag) => {
+                    let name = String::from_utf8_lossy(tag.name().as_ref()).into_owned();
+                    let depth = stack.len();
+                    if depth == 0 && root_tag.is_empty() {
+                        root_tag = content[event_start..event_end].to_string();
+                        root_name = name.clone();
+                    }
+
+                    let is_empty = matches!(event, Event::Empty(_));
+                    if open.is_none() && self.selects(&name, depth) {
+                        if gap_has_content {
+                            elements.push(Self::gap(content, gap_start, event_start, &root_name));
+                        }
+                        gap_has_content = false;
+                        if is_empty {
+                            let mut path = stack.clone();
+                            path.push(name.clone());
+                            elements.push(XmlElement { name: Some(name), path, start: event_start, end: event_end });
+                            gap_start = event_end;
+                            continue;
+                        }
+                        open = Some((event_start, depth));
+                    } else if open.is_none() && depth > 0 && (is_empty || tag.attributes().next().is_some()) {
+                        gap_has_content = true;
+                    }
+                    if !is_empty {
+                        stack.push(name);
+                    }
+                }
+                Event::End(_) => {
+                    let Some(name) = stack.pop() else {
+                        continue;
+                    };
+                    if let Some((start, depth)) = open {
+                        if stack.len() == depth {
+                            let mut path = stack.clone();
+                            path.push(name.clone());
+                            elements.push(XmlElement { name: Some(name), path, start, end: event_end });
+                            open = None;
+                            gap_start = event_end;
+                        }
+                    } else if stack.is_empty() {
+                        // Content closing the root ends before its end tag
+                        if gap_has_content && elements.iter().any(|e| e.name.is_some()) {
+                            elements.push(Self::gap(content, gap_start, event_start, &root_name));
+                        }
+                        gap_start = event_end;
+                        gap_has_content = false;
+                    }
+                }
+                Event::Text(ref text) if open.is_none() => {
+                    gap_has_content |= text.iter().any(|b| !b.is_ascii_whitespace());
+                }
+                Event::CData(_) | Event::Comment(_) if open.is_none() => gap_has_content = true,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if !stack.is_empty() {
+            anyhow::bail!("unclosed element <{}>", stack.join("><"));
+        }
+        if gap_has_content && elements.iter().any(|e| e.name.is_some()) {
+            elements.push(Self::gap(content, gap_start, content.len(), &root_name));
+        }
+        Ok(XmlSelection { elements, root_tag })
+    }
+
+    /// Content between selected elements, without surrounding whitespace.
+    /// `root_name` is empty for content before the root element.
+    fn gap(content: &str, start: usize, end: usize, root_name: &str) -> XmlElement {
+        let text = &content[start..end];
+        let start = start + (text.len() - text.trim_start().len());
+        let end = end - (text.len() - text.trim_end().len());
+        let path = if root_name.is_empty() { vec![] } else { vec![root_name.to_string()] };
+        XmlElement { name: None, path, start, end }
+    }
+
+    /// Byte ranges of an element, split with the recursive chunker if it is
+    /// larger than `chunk_size` tokens.
+    fn element_ranges(&self, item: &SourceItem, config: &ChunkConfig, element: &XmlElement) -> Result<Vec<(usize, usize)>> {
+        let text = &item.content[element.start..element.end];
+        if count_tokens(text) <= config.chunk_size {
+            return Ok(vec![(element.start, element.end)]);
+        }
+
+        let mut section = item.clone();
+        section.content = text.to_string();
+        let ranges = self
+            .recursive_chunker
+            .chunk(&section, config)?
+            .into_iter()
+            .map(|piece| (element.start + piece.start_index, (element.start + piece.end_index).min(element.end)))
+            .filter(|(start, end)| start < end)
+            .collect();
+        Ok(ranges)
+    }
+}
+
+impl Default for XmlChunker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for XmlChunker {
+    fn name(&self) -> &'static str {
+        "xml"
+    }
+
+    fn description(&self) -> &'static str {
+        "XML chunker that splits on named elements or at a fixed depth, recording the root element as context"
+    }
+
+    fn chunk(&self, item: &SourceItem, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+        let content = &item.content;
+        if content.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let selection = match self.select(content) {
+            Ok(selection) if !selection.elements.is_empty() => selection,
+            _ => return self.recursive_chunker.chunk(item, config),
+        };
+
+        let mut config = config.clone();
+        config.chunk_size = config.effective_chunk_size();
+        let root_tag = serde_json::json!({ "root_tag": selection.root_tag });
+
+        let mut chunks = Vec::new();
+        for element in &selection.elements {
+            for (start, end) in self.element_ranges(item, &config, element)? {
+                let text = &content[start..end];
+                let mut chunk = Chunk::new(
+                    item.id,
+                    item.source_id,
+                    item.source_kind,
+                    text.to_string(),
+                    count_tokens(text),
+                    start,
+                    end,
+                    chunks.len(),
+                );
+                chunk.metadata = ChunkMetadata {
+                    content_type: Some("xml".to_string()),
+                    language: Some("xml".to_string()),
+                    path: item.extract_path().map(String::from),
+                    symbol_name: element.name.clone(),
+                    section: Some(format!("/{}", element.path.join("/"))),
+                    section_hierarchy: element.path.clone(),
+                    // The root itself needs no context
+                    extra: (element.path.len() > 1 || element.name.is_none()).then(|| root_tag.clone()),
+                    ..Default::default()
+                };
+                chunks.push(chunk);
+            }
+        }
+
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+    use uuid::Uuid;
+
+    fn create_xml_item(content: &str) -> SourceItem {
+        SourceItem {
+            id: Uuid::new_v4(),
+            source_id: Uuid::new_v4(),
+            source_kind: SourceKind::CodeRepo,
+            content_type: "application/xml".to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({}),
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+
+    const POM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <modelVersion>4.0.0</modelVersion>
+  <artifactId>checkout</artifactId>
+  <dependencies>
+    <dependency>
+      <groupId>org.slf4j</groupId>
+      <artifactId>slf4j-api</artifactId>
+    </dependency>
+    <dependency>
+      <groupId>junit</groupId>
+      <artifactId>junit</artifactId>
+      <scope>test</scope>
+    </dependency>
+  </dependencies>
+</project>
+"#;
+
+    #[test]
+    fn test_maven_pom_dependencies() {
+        let item = create_xml_item(POM);
+        let chunks = XmlChunker::for_maven_pom().chunk(&item, &ChunkConfig::default()).unwrap();
+
+        // The project's own coordinates come first, then one chunk per dependency
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].metadata.symbol_name.is_none());
+        assert!(chunks[0].content.contains("<artifactId>checkout</artifactId>"));
+        assert_eq!(chunks[0].metadata.section.as_deref(), Some("/project"));
+        for chunk in &chunks {
+            assert_eq!(chunk.content, POM[chunk.start_index..chunk.end_index]);
+            let root_tag = chunk.metadata.extra.as_ref().unwrap()["root_tag"].as_str().unwrap();
+            assert!(root_tag.starts_with("<project xmlns=\"http://maven.apache.org/POM/4.0.0\" xmlns:xsi="));
+        }
+        for chunk in &chunks[1..] {
+            assert_eq!(chunk.metadata.symbol_name.as_deref(), Some("dependency"));
+            assert_eq!(chunk.metadata.section.as_deref(), Some("/project/dependencies/dependency"));
+            assert!(chunk.content.starts_with("<dependency>"));
+            assert!(chunk.content.ends_with("</dependency>"));
+        }
+        assert!(chunks[2].content.contains("<scope>test</scope>"));
+        assert!(!chunks[1].content.contains("junit"));
+    }
+
+    #[test]
+    fn test_spring_beans_and_depth() {
+        let config = r#"<beans xmlns="http://www.springframework.org/schema/beans">
+  <bean id="dataSource" class="com.zaxxer.hikari.HikariDataSource">
+    <property name="pool">
+      <bean class="com.example.PoolConfig"/>
+    </property>
+  </bean>
+  <bean id="clock" class="java.time.Clock" factory-method="systemUTC"/>
+  <import resource="security.xml"/>
+</beans>"#;
+        let item = create_xml_item(config);
+
+        // Nested beans stay inside their parent, and the import after the
+        // beans is kept as content between them
+        let beans = XmlChunker::for_spring_config().chunk(&item, &ChunkConfig::default()).unwrap();
+        assert_eq!(beans.len(), 3);
+        assert!(beans[0].content.contains("com.example.PoolConfig"));
+        assert!(beans[1].content.contains("id=\"clock\""));
+        assert_eq!(beans[2].content, "<import resource=\"security.xml\"/>");
+        assert!(beans[2].metadata.symbol_name.is_none());
+
+        let children = XmlChunker::new().chunk(&item, &ChunkConfig::default()).unwrap();
+        let names: Vec<_> = children.iter().map(|c| c.metadata.symbol_name.as_deref().unwrap()).collect();
+        assert_eq!(names, ["bean", "bean", "import"]);
+
+        let properties = XmlChunker::new().with_max_depth(2).chunk(&item, &ChunkConfig::default()).unwrap();
+        let properties: Vec<_> = properties.iter().filter(|c| c.metadata.symbol_name.is_some()).collect();
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].metadata.section_hierarchy, ["beans", "bean", "property"]);
+    }
+
+    #[test]
+    fn test_interleaved_content_is_kept() {
+        let doc = r#"<catalog>
+  <!-- Spring 2025 -->
+  <book id="1"><title>Dune</title></book>
+  Loose note about the shelf
+  <magazine issue="12"/>
+  <book id="2"><title>Emma</title></book>
+</catalog>"#;
+        let item = create_xml_item(doc);
+        let chunks = XmlChunker::new().with_split_element("book").chunk(&item, &ChunkConfig::default()).unwrap();
+
+        let names: Vec<_> = chunks.iter().map(|c| c.metadata.symbol_name.as_deref()).collect();
+        assert_eq!(names, [None, Some("book"), None, Some("book")]);
+        assert_eq!(chunks[0].content, "<catalog>\n  <!-- Spring 2025 -->");
+        assert_eq!(chunks[2].content, "Loose note about the shelf\n  <magazine issue=\"12\"/>");
+        for chunk in &chunks {
+            assert_eq!(chunk.content, doc[chunk.start_index..chunk.end_index]);
+        }
+    }
+
+    #[test]
+    fn test_oversized_element_is_split() {
+        let entries: String = (0..200).map(|i| format!("    <entry key=\"k{i}\">value number {i}</entry>\n")).collect();
+        let doc = format!("<config>\n  <section name=\"big\">\n{entries}  </section>\n</config>");
+        let item = create_xml_item(&doc);
+        let config = ChunkConfig::with_size(100);
+        let chunks = XmlChunker::new().chunk(&item, &config).unwrap();
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.token_count <= config.chunk_size, "{} tokens", chunk.token_count);
+            assert_eq!(chunk.content, doc[chunk.start_index..chunk.end_index]);
+            assert_eq!(chunk.metadata.symbol_name.as_deref(), Some("section"));
+        }
+        assert!(chunks[0].content.starts_with("<section name=\"big\">"));
+        assert!(chunks.last().unwrap().content.ends_with("</section>"));
+    }
+
+    #[test]
+    fn test_malformed_xml_falls_back() {
+        let item = create_xml_item("<config><entry>unterminated</config>");
+        let chunks = XmlChunker::new().chunk(&item, &ChunkConfig::default()).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].metadata.symbol_name.is_none());
+    }
+}
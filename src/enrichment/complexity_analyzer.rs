@@ -0,0 +1,143 @@
+//! Cyclomatic complexity scoring for code chunks.
+//!
+//! Counts decision points (branches, loops, match arms, early returns via
+//! `?`, and short-circuit boolean operators) and maps the count `d` to
+//! `d / (d + 1)`, so straight-line code scores 0.0 and the score approaches
+//! 1.0 as branching grows.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Scores how much branching a piece of code contains.
+pub struct CodeComplexityAnalyzer;
+
+impl CodeComplexityAnalyzer {
+    /// Complexity of `content` between 0.0 (no decision points) and 1.0.
+    pub fn analyze(content: &str, language: &str) -> f32 {
+        let decision_points = Self::decision_points(content, language);
+        (decision_points as f32 / (decision_points as f32 + 1.0)).clamp(0.0, 1.0)
+    }
+
+    /// Count the decision points in `content`.
+    ///
+    /// String literals and line comments are ignored.
+    pub fn decision_points(content: &str, language: &str) -> usize {
+        let code = strip_strings_and_comments(content, language);
+        let count = |re: &Regex| re.find_iter(&code).count();
+
+        let mut points = count(branch_regex()) + count(boolean_regex());
+        match language.to_lowercase().as_str() {
+            "rust" => {
+                // Every match arm is a branch, as is every `?`
+                points += count(match_arm_regex()) + count(try_operator_regex());
+            }
+            "python" => points += count(python_boolean_regex()),
+            _ => points += count(case_regex()) + count(ternary_regex()),
+        }
+        points
+    }
+}
+
+fn strip_strings_and_comments(content: &str, language: &str) -> String {
+    static DOUBLE_QUOTED: OnceLock<Regex> = OnceLock::new();
+    static SINGLE_QUOTED: OnceLock<Regex> = OnceLock::new();
+    static SLASH_COMMENTS: OnceLock<Regex> = OnceLock::new();
+    static HASH_COMMENTS: OnceLock<Regex> = OnceLock::new();
+
+    let language = language.to_lowercase();
+    let double_quoted = DOUBLE_QUOTED.get_or_init(|| Regex::new(r#""(?:[^"\\\n]|\\.)*""#).unwrap());
+    let mut code = double_quoted.replace_all(content, "\"\"").into_owned();
+    // In Rust a single quote usually starts a lifetime, not a string
+    if language != "rust" {
+        let single_quoted = SINGLE_QUOTED.get_or_init(|| Regex::new(r"'(?:[^'\\\n]|\\.)*'").unwrap());
+        code = single_quoted.replace_all(&code, "''").into_owned();
+    }
+
+    let comments = match language.as_str() {
+        "python" | "ruby" | "bash" | "shell" | "yaml" => HASH_COMMENTS.get_or_init(|| Regex::new(r"#.*").unwrap()),
+        _ => SLASH_COMMENTS.get_or_init(|| Regex::new(r"//.*").unwrap()),
+    };
+    comments.replace_all(&code, "").into_owned()
+}
+
+/// `if`/`elif` branches, loops and exception handlers.
+fn branch_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b(?:if|elif|for|while|loop|catch|except)\b").unwrap())
+}
+
+fn boolean_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"&&|\|\|").unwrap())
+}
+
+fn python_boolean_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b(?:and|or)\b").unwrap())
+}
+
+fn match_arm_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"=>").unwrap())
+}
+
+/// `?` directly after an expression, e.g. `read(path)?`.
+fn try_operator_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[\w)\]]\?").unwrap())
+}
+
+fn case_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\bcase\b").unwrap())
+}
+
+/// Ternary `cond ? a : b`, but not optional chaining (`?.`) or `??`.
+fn ternary_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\s\?\s").unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pure_function_is_simple() {
+        let code = "fn add(a: i32, b: i32) -> i32 {\n    // if this overflows, so be it\n    a + b\n}\n";
+        assert_eq!(CodeComplexityAnalyzer::decision_points(code, "rust"), 0);
+        assert!(CodeComplexityAnalyzer::analyze(code, "rust") < 0.2);
+    }
+
+    #[test]
+    fn test_nested_match_is_complex() {
+        let code = r#"
+fn describe(value: Option<Result<u8, String>>) -> Result<&'static str, String> {
+    let label = match value {
+        Some(Ok(n)) => match n {
+            0 => "zero",
+            n if n < 10 && n % 2 == 0 => "small even",
+            _ => "big",
+        },
+        Some(Err(e)) => return Err(e),
+        None => "missing",
+    };
+    validate(label)?;
+    Ok(label)
+}
+"#;
+        // 6 arms, 1 guard `if`, 1 `&&`, 1 `?`
+        assert_eq!(CodeComplexityAnalyzer::decision_points(code, "rust"), 9);
+        assert!(CodeComplexityAnalyzer::analyze(code, "rust") > 0.7);
+    }
+
+    #[test]
+    fn test_language_specific_decisions() {
+        let python = "if a and not b:\n    pass\nfor x in xs:\n    print('if or while')\n";
+        assert_eq!(CodeComplexityAnalyzer::decision_points(python, "python"), 3);
+
+        let js = "const f = (x) => x?.y ?? (x > 1 ? 'a' : 'b');\nswitch (x) { case 1: break; case 2: break; }";
+        assert_eq!(CodeComplexityAnalyzer::decision_points(js, "javascript"), 3);
+    }
+}
@@ -7,8 +7,15 @@
 //! adds context prefixes for better embedding quality.
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use tera::Tera;
+use tracing::warn;
 
+use crate::chunkers::{count_tokens, EntityBoundary};
+use crate::chunkers::repo_chunker::{RepositoryContext, MAX_DECORATORS_PER_ENTITY};
+use super::keyword_extractor::KeywordExtractor;
 use crate::types::Chunk;
 
 /// Type of entity for context display.
@@ -43,6 +50,68 @@ impl EntityType {
             EntityType::Constant => "constant",
         }
     }
+
+    /// Parse an entity type name as used by `EntityBoundary`.
+    ///
+    /// Unknown names are treated as functions.
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "method" => EntityType::Method,
+            "class" => EntityType::Class,
+            "struct" => EntityType::Struct,
+            "enum" => EntityType::Enum,
+            "interface" => EntityType::Interface,
+            "trait" => EntityType::Trait,
+            "module" | "mod" | "namespace" => EntityType::Module,
+            "variable" | "var" | "let" => EntityType::Variable,
+            "constant" | "const" | "static" => EntityType::Constant,
+            _ => EntityType::Function,
+        }
+    }
+}
+
+/// Name under which a custom prefix template is registered.
+const PREFIX_TEMPLATE: &str = "prefix";
+
+/// Built-in template wrapping each section in XML tags.
+const XML_TEMPLATE: &str = r#"<context file="{{ file_path | xml_escape }}"{% if language %} language="{{ language | xml_escape }}"{% endif %}{% if repository %} repository="{{ repository | xml_escape }}"{% endif %}>
+{% if scope %}<scope>{{ scope | xml_escape }}</scope>
+{% endif %}{% if definitions %}<definitions>{{ definitions | xml_escape }}</definitions>
+{% endif %}{% if dependencies %}<dependencies>{{ dependencies | xml_escape }}</dependencies>
+{% endif %}</context>"#;
+
+/// Built-in template producing a single comment line.
+const MINIMAL_TEMPLATE: &str = "# {{ file_path }}{% if scope %} > {{ scope }}{% endif %}";
+
+/// Tera filter escaping `&`, `<`, `>` and `"` (Tera's `escape` also escapes `/`).
+fn xml_escape(value: &tera::Value, _: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+    let text = tera::try_get_value!("xml_escape", "value", String, value);
+    let escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+    Ok(tera::Value::String(escaped))
+}
+
+/// A section of the context prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextSection {
+    /// File path, language and repository
+    FilePath,
+    /// Scope path
+    Scope,
+    /// Definitions summary
+    Definitions,
+    /// Dependencies/imports
+    Dependencies,
+    /// Callers of the chunk's entities
+    Callers,
+    /// Files defining types the chunk's entities reference
+    CrossReferences,
+    /// Related documentation
+    Documentation,
 }
 
 /// Summary of an entity for context.
@@ -55,6 +124,81 @@ pub struct EntitySummary {
     /// Signature (for functions/methods).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signature: Option<String>,
+    /// Decorators, annotations or attributes (e.g. `derive(Debug)`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decorators: Vec<String>,
+}
+
+impl From<&EntityBoundary> for EntitySummary {
+    fn from(entity: &EntityBoundary) -> Self {
+        Self {
+            name: entity.name.clone(),
+            entity_type: EntityType::from_name(&entity.entity_type),
+            signature: entity.signature.clone(),
+            decorators: entity.decorators.iter().take(MAX_DECORATORS_PER_ENTITY).cloned().collect(),
+        }
+    }
+}
+
+/// Entities of one file sorted by start line.
+///
+/// Built once per file with `ContextBuilder::build_line_index`, so finding
+/// the entities of each chunk is two binary searches and a scan of the
+/// entities between them, instead of a scan of every entity.
+#[derive(Debug, Clone, Default)]
+pub struct LineEntityIndex<'a> {
+    /// Entities by start line; entities starting on the same line keep
+    /// their original order
+    by_start_line: Vec<&'a EntityBoundary>,
+    /// Latest end line of `by_start_line[..=i]`, never decreasing
+    max_end_line: Vec<usize>,
+    entities: &'a [EntityBoundary],
+}
+
+impl<'a> LineEntityIndex<'a> {
+    /// Entities overlapping the 1-indexed, inclusive line range, by start line.
+    pub fn entities_in(&self, start_line: usize, end_line: usize) -> impl Iterator<Item = &'a EntityBoundary> + '_ {
+        // Entities from `last` on start after the range, and every entity
+        // before `first` ends before it
+        let last = self.by_start_line.partition_point(|entity| entity.start_line <= end_line);
+        let first = self.max_end_line[..last].partition_point(|&end| end < start_line);
+        self.by_start_line[first..last]
+            .iter()
+            .copied()
+            .filter(move |entity| entity.end_line >= start_line)
+    }
+
+    /// Entities enclosing the whole 1-indexed, inclusive line range,
+    /// outermost first.
+    pub fn scope_path(&self, start_line: usize, end_line: usize) -> Vec<&'a EntityBoundary> {
+        let mut path: Vec<&EntityBoundary> = self
+            .entities_in(start_line, end_line)
+            .filter(|entity| entity.start_line <= start_line && entity.end_line >= end_line)
+            .collect();
+        // Outer entities start earlier, or end later when they start together
+        path.sort_by_key(|entity| (entity.start_line, std::cmp::Reverse(entity.end_line)));
+        path
+    }
+
+    /// Entities enclosing a line, outermost first: its scope path.
+    pub fn scope_path_at_line(&self, line: usize) -> Vec<&'a EntityBoundary> {
+        self.scope_path(line, line)
+    }
+
+    /// All indexed entities, in their original order.
+    pub fn entities(&self) -> &'a [EntityBoundary] {
+        self.entities
+    }
+
+    /// Number of indexed entities.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Whether the index has no entities.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
 }
 
 /// Context information for a chunk.
@@ -74,12 +218,21 @@ pub struct ChunkContext {
     /// Current scope path (e.g., "Module.Class.method").
     #[serde(default)]
     pub scope: String,
+    /// Each level of the scope path, outermost first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scope_ancestors: Vec<String>,
+    /// Enclosing document headings, outermost first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub section_hierarchy: Vec<String>,
     /// Entities defined in this chunk.
     #[serde(default)]
     pub definitions: Vec<EntitySummary>,
     /// Dependencies/imports used.
     #[serde(default)]
     pub dependencies: Vec<String>,
+    /// Functions that call the entities in this chunk (e.g., "src/main.rs::run").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub callers: Vec<String>,
     /// Related documentation (if any).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub documentation: Option<String>,
@@ -98,12 +251,42 @@ impl ChunkContext {
         }
     }
 
+    /// Build a context from a chunk's own metadata.
+    pub fn from_chunk(chunk: &Chunk) -> Self {
+        let metadata = &chunk.metadata;
+        let scope = match (&metadata.parent_symbol, &metadata.symbol_name) {
+            (Some(parent), Some(name)) => format!("{}.{}", parent, name),
+            (None, Some(name)) => name.clone(),
+            (Some(parent), None) => parent.clone(),
+            (None, None) => String::new(),
+        };
+
+        Self::new(
+            metadata.path.clone().unwrap_or_default(),
+            metadata.language.clone().unwrap_or_default(),
+        )
+        .with_scope(scope)
+    }
+
     /// Set scope path.
     pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
         self.scope = scope.into();
         self
     }
 
+    /// Set the scope path from its levels, outermost first.
+    pub fn with_scope_ancestors(mut self, ancestors: Vec<String>) -> Self {
+        self.scope = ancestors.join(".");
+        self.scope_ancestors = ancestors;
+        self
+    }
+
+    /// Set the enclosing document headings.
+    pub fn with_section_hierarchy(mut self, hierarchy: Vec<String>) -> Self {
+        self.section_hierarchy = hierarchy;
+        self
+    }
+
     /// Add a definition.
     pub fn with_definition(mut self, entity: EntitySummary) -> Self {
         self.definitions.push(entity);
@@ -115,6 +298,25 @@ impl ChunkContext {
         self.dependencies = deps;
         self
     }
+
+    /// Add callers of the entities in this chunk.
+    pub fn with_callers(mut self, callers: Vec<String>) -> Self {
+        self.callers = callers;
+        self
+    }
+
+    /// Fill `callers` from the call graph in a repository context.
+    pub fn with_callers_from(mut self, repo: &RepositoryContext) -> Self {
+        for def in &self.definitions {
+            for (file, caller) in repo.find_callers(&def.name) {
+                let caller = format!("{}::{}", file, caller);
+                if !self.callers.contains(&caller) {
+                    self.callers.push(caller);
+                }
+            }
+        }
+        self
+    }
 }
 
 /// A chunk enriched with context.
@@ -150,10 +352,26 @@ pub struct ContextBuilder {
     include_definitions: bool,
     /// Whether to include dependencies.
     include_dependencies: bool,
+    /// Whether to include callers.
+    include_callers: bool,
     /// Maximum prefix length (in characters).
     max_prefix_length: usize,
+    /// Total token limit for prefix, separator and content.
+    token_budget: Option<usize>,
+    /// Order in which sections are dropped to fit the token budget.
+    priority_order: Vec<ContextSection>,
+    /// Custom prefix template (replaces the default format).
+    template: Option<Tera>,
     /// Separator between prefix and content.
     separator: String,
+    /// Separator between the levels of a scope path.
+    scope_separator: String,
+    /// Maximum keywords extracted per chunk.
+    max_keywords: usize,
+    /// Repository symbols used to resolve cross-file type references.
+    repository_context: Option<Arc<RepositoryContext>>,
+    /// Maximum cross-file references in the prefix.
+    max_cross_references: usize,
 }
 
 impl Default for ContextBuilder {
@@ -163,8 +381,24 @@ impl Default for ContextBuilder {
             include_scope: true,
             include_definitions: true,
             include_dependencies: true,
+            include_callers: false,
             max_prefix_length: 500,
+            token_budget: None,
+            priority_order: vec![
+                ContextSection::Documentation,
+                ContextSection::CrossReferences,
+                ContextSection::Callers,
+                ContextSection::Dependencies,
+                ContextSection::Definitions,
+                ContextSection::Scope,
+                ContextSection::FilePath,
+            ],
+            template: None,
             separator: "\n---\n".to_string(),
+            scope_separator: " > ".to_string(),
+            max_keywords: 10,
+            repository_context: None,
+            max_cross_references: 5,
         }
     }
 }
@@ -175,6 +409,35 @@ impl ContextBuilder {
         Self::default()
     }
 
+    /// Create a context builder using a built-in template.
+    ///
+    /// Available templates are `"default"` (the standard `# Key: value`
+    /// lines), `"xml"` and `"minimal"`.
+    pub fn new_with_named_template(name: &str) -> Result<Self> {
+        match name {
+            "default" => Ok(Self::new()),
+            "xml" => Self::new().with_template(XML_TEMPLATE),
+            "minimal" => Self::new().with_template(MINIMAL_TEMPLATE),
+            _ => Err(anyhow!("Unknown prefix template: {}", name)),
+        }
+    }
+
+    /// Use a Tera template for the prefix.
+    ///
+    /// The template can use `file_path`, `language`, `repository`, `scope`,
+    /// `definitions`, `decorators`, `dependencies`, `callers`,
+    /// `cross_references` and `documentation`. Lists
+    /// are joined with `", "`; disabled or dropped sections render as empty
+    /// strings. An `xml_escape` filter is available. Template output is not
+    /// subject to `max_prefix_length`.
+    pub fn with_template(mut self, template: &str) -> Result<Self> {
+        let mut tera = Tera::default();
+        tera.register_filter("xml_escape", xml_escape);
+        tera.add_raw_template(PREFIX_TEMPLATE, template)?;
+        self.template = Some(tera);
+        Ok(self)
+    }
+
     /// Set whether to include file path.
     pub fn with_file_path(mut self, include: bool) -> Self {
         self.include_file_path = include;
@@ -199,38 +462,123 @@ impl ContextBuilder {
         self
     }
 
+    /// Set whether to include callers.
+    pub fn with_callers(mut self, include: bool) -> Self {
+        self.include_callers = include;
+        self
+    }
+
     /// Set maximum prefix length.
     pub fn with_max_prefix_length(mut self, max_length: usize) -> Self {
         self.max_prefix_length = max_length;
         self
     }
 
+    /// Set the total token limit for prefix plus content.
+    ///
+    /// When enriching, sections are dropped in `priority_order` until the
+    /// prefix, separator and content fit within `budget` tokens.
+    pub fn with_token_budget(mut self, budget: usize) -> Self {
+        self.token_budget = Some(budget);
+        self
+    }
+
+    /// Set the order in which sections are dropped, first dropped first.
+    ///
+    /// Sections not listed are never dropped.
+    pub fn with_priority_order(mut self, order: Vec<ContextSection>) -> Self {
+        self.priority_order = order;
+        self
+    }
+
     /// Set the separator between prefix and content.
     pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
         self.separator = separator.into();
         self
     }
 
+    /// Set the separator between scope levels (default `" > "`).
+    pub fn with_scope_separator(mut self, separator: impl Into<String>) -> Self {
+        self.scope_separator = separator.into();
+        self
+    }
+
+    /// Set the maximum number of keywords extracted per chunk.
+    pub fn with_max_keywords(mut self, max_keywords: usize) -> Self {
+        self.max_keywords = max_keywords;
+        self
+    }
+
+    /// Resolve types referenced by the chunk's definitions against a
+    /// repository context, adding a `# Cross-file references:` line.
+    pub fn with_repository_context(mut self, repository_context: Arc<RepositoryContext>) -> Self {
+        self.repository_context = Some(repository_context);
+        self
+    }
+
+    /// Set the maximum number of cross-file references in the prefix.
+    pub fn with_max_cross_references(mut self, max_cross_references: usize) -> Self {
+        self.max_cross_references = max_cross_references;
+        self
+    }
+
     /// Build context prefix for a chunk.
     pub fn build_prefix(&self, context: &ChunkContext) -> String {
+        self.render_prefix(context, &self.prefix_sections(context), &[])
+    }
+
+    /// Build a context prefix that fits the token budget together with `content`.
+    ///
+    /// Without a token budget this is the same as `build_prefix`. If the
+    /// content alone exceeds the budget the prefix is empty.
+    pub fn build_prefix_for(&self, context: &ChunkContext, content: &str) -> String {
+        let sections = self.prefix_sections(context);
+        let mut prefix = self.render_prefix(context, &sections, &[]);
+
+        let Some(budget) = self.token_budget else {
+            return prefix;
+        };
+        let content_tokens = count_tokens(content) + count_tokens(&self.separator);
+        let fits = |prefix: &str| prefix.is_empty() || count_tokens(prefix) + content_tokens <= budget;
+
+        let mut dropped = Vec::new();
+        for section in &self.priority_order {
+            if fits(&prefix) {
+                return prefix;
+            }
+            dropped.push(*section);
+            prefix = self.render_prefix(context, &sections, &dropped);
+        }
+
+        if fits(&prefix) { prefix } else { String::new() }
+    }
+
+    /// Collect the enabled prefix lines, tagged with their section.
+    fn prefix_sections(&self, context: &ChunkContext) -> Vec<(ContextSection, String)> {
         let mut parts = Vec::new();
 
         // File path
         if self.include_file_path {
-            parts.push(format!("# File: {}", context.file_path));
+            parts.push((ContextSection::FilePath, format!("# File: {}", context.file_path)));
             if !context.language.is_empty() {
-                parts.push(format!("# Language: {}", context.language));
+                parts.push((ContextSection::FilePath, format!("# Language: {}", context.language)));
             }
         }
 
         // Repository info
         if let Some(ref repo) = context.repository {
-            parts.push(format!("# Repository: {}", repo));
+            parts.push((ContextSection::FilePath, format!("# Repository: {}", repo)));
         }
 
         // Scope
         if self.include_scope && !context.scope.is_empty() {
-            parts.push(format!("# Scope: {}", context.scope));
+            parts.push((ContextSection::Scope, format!("# Scope: {}", self.scope_display(context))));
+        }
+        if self.include_scope && !context.section_hierarchy.is_empty() {
+            parts.push((
+                ContextSection::Scope,
+                format!("# Breadcrumb: {}", context.section_hierarchy.join(" > ")),
+            ));
         }
 
         // Definitions
@@ -248,9 +596,14 @@ impl ContextBuilder {
                 .collect();
             
             if defs.len() == 1 {
-                parts.push(format!("# Defines: {}", defs[0]));
+                parts.push((ContextSection::Definitions, format!("# Defines: {}", defs[0])));
             } else if !defs.is_empty() {
-                parts.push(format!("# Defines: {}", defs.join(", ")));
+                parts.push((ContextSection::Definitions, format!("# Defines: {}", defs.join(", "))));
+            }
+
+            let decorators = Self::decorators(context);
+            if !decorators.is_empty() {
+                parts.push((ContextSection::Definitions, format!("# Decorators: {}", decorators.join(", "))));
             }
         }
 
@@ -258,14 +611,30 @@ impl ContextBuilder {
         if self.include_dependencies && !context.dependencies.is_empty() {
             let deps = context.dependencies.join(", ");
             if deps.len() <= 100 {
-                parts.push(format!("# Dependencies: {}", deps));
+                parts.push((ContextSection::Dependencies, format!("# Dependencies: {}", deps)));
             } else {
                 // Truncate long dependency lists
                 let truncated: Vec<_> = context.dependencies.iter().take(5).cloned().collect();
-                parts.push(format!("# Dependencies: {} ...", truncated.join(", ")));
+                parts.push((ContextSection::Dependencies, format!("# Dependencies: {} ...", truncated.join(", "))));
             }
         }
 
+        // Callers
+        if self.include_callers && !context.callers.is_empty() {
+            let shown: Vec<_> = context.callers.iter().take(5).cloned().collect();
+            let more = if context.callers.len() > shown.len() { " ..." } else { "" };
+            parts.push((ContextSection::Callers, format!("# Called by: {}{}", shown.join(", "), more)));
+        }
+
+        // Cross-file references
+        let references = self.cross_references(context);
+        if !references.is_empty() {
+            parts.push((
+                ContextSection::CrossReferences,
+                format!("# Cross-file references: {}", references.join(", ")),
+            ));
+        }
+
         // Documentation
         if let Some(ref doc) = context.documentation {
             let doc_line = if doc.len() > 100 {
@@ -273,9 +642,77 @@ impl ContextBuilder {
             } else {
                 doc.clone()
             };
-            parts.push(format!("# Doc: {}", doc_line));
+            parts.push((ContextSection::Documentation, format!("# Doc: {}", doc_line)));
         }
 
+        parts
+    }
+
+    /// Types used by the chunk's definitions that are defined in other files,
+    /// as `"Type defined in path"`.
+    ///
+    /// Types come from the repository's recorded type references and from
+    /// capitalized identifiers in definition signatures.
+    fn cross_references(&self, context: &ChunkContext) -> Vec<String> {
+        let Some(ref repo) = self.repository_context else {
+            return Vec::new();
+        };
+
+        let mut type_names: Vec<&str> = Vec::new();
+        for def in &context.definitions {
+            let referenced = repo
+                .type_references
+                .get(&context.file_path)
+                .into_iter()
+                .flatten()
+                .filter(|reference| reference.referencing_entity == def.name)
+                .map(|reference| reference.type_name.as_str());
+            let in_signature = def
+                .signature
+                .as_deref()
+                .unwrap_or_default()
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|word| word.starts_with(char::is_uppercase));
+            for name in referenced.chain(in_signature) {
+                if !type_names.contains(&name) {
+                    type_names.push(name);
+                }
+            }
+        }
+
+        type_names
+            .into_iter()
+            .filter_map(|name| {
+                let file = repo
+                    .find_symbol_locations(name)
+                    .into_iter()
+                    .find(|file| *file != context.file_path)?;
+                Some(format!("{} defined in {}", name, file))
+            })
+            .take(self.max_cross_references)
+            .collect()
+    }
+
+    /// Join prefix lines, skipping dropped sections and enforcing the max length.
+    fn render_prefix(
+        &self,
+        context: &ChunkContext,
+        sections: &[(ContextSection, String)],
+        dropped: &[ContextSection],
+    ) -> String {
+        if let Some(ref tera) = self.template {
+            match self.render_template(tera, context, sections, dropped) {
+                Ok(prefix) => return prefix,
+                Err(e) => warn!(error = %e, "Failed to render prefix template, using default format"),
+            }
+        }
+
+        let parts: Vec<&str> = sections
+            .iter()
+            .filter(|(section, _)| !dropped.contains(section))
+            .map(|(_, line)| line.as_str())
+            .collect();
+
         // Enforce max length
         let mut prefix = parts.join("\n");
         if prefix.len() > self.max_prefix_length {
@@ -289,9 +726,93 @@ impl ContextBuilder {
         prefix
     }
 
-    /// Enrich a chunk with context.
-    pub fn enrich(&self, chunk: Chunk, context: ChunkContext) -> EnrichedChunk {
-        let prefix = self.build_prefix(&context);
+    /// Render the custom template with the sections that are still present.
+    fn render_template(
+        &self,
+        tera: &Tera,
+        context: &ChunkContext,
+        sections: &[(ContextSection, String)],
+        dropped: &[ContextSection],
+    ) -> Result<String> {
+        let present = |section| sections.iter().any(|(s, _)| *s == section) && !dropped.contains(&section);
+        let value = |section, value: String| if present(section) { value } else { String::new() };
+
+        let definitions = context
+            .definitions
+            .iter()
+            .map(|d| d.signature.clone().unwrap_or_else(|| format!("{} {}", d.entity_type.as_str(), d.name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut vars = tera::Context::new();
+        vars.insert("file_path", &value(ContextSection::FilePath, context.file_path.clone()));
+        vars.insert("language", &value(ContextSection::FilePath, context.language.clone()));
+        vars.insert(
+            "repository",
+            &value(ContextSection::FilePath, context.repository.clone().unwrap_or_default()),
+        );
+        vars.insert("scope", &value(ContextSection::Scope, self.scope_display(context)));
+        vars.insert(
+            "breadcrumb",
+            &value(ContextSection::Scope, context.section_hierarchy.join(" > ")),
+        );
+        vars.insert("definitions", &value(ContextSection::Definitions, definitions));
+        vars.insert(
+            "decorators",
+            &value(ContextSection::Definitions, Self::decorators(context).join(", ")),
+        );
+        vars.insert("dependencies", &value(ContextSection::Dependencies, context.dependencies.join(", ")));
+        vars.insert("callers", &value(ContextSection::Callers, context.callers.join(", ")));
+        vars.insert(
+            "cross_references",
+            &value(ContextSection::CrossReferences, self.cross_references(context).join(", ")),
+        );
+        vars.insert(
+            "documentation",
+            &value(ContextSection::Documentation, context.documentation.clone().unwrap_or_default()),
+        );
+
+        Ok(tera.render(PREFIX_TEMPLATE, &vars)?.trim().to_string())
+    }
+
+    /// Decorators of the chunk's definitions, without duplicates.
+    fn decorators(context: &ChunkContext) -> Vec<String> {
+        let mut decorators: Vec<String> = Vec::new();
+        for decorator in context.definitions.iter().flat_map(|d| &d.decorators) {
+            if !decorators.contains(decorator) {
+                decorators.push(decorator.clone());
+            }
+        }
+        decorators
+    }
+
+    /// The scope path with `scope_separator` between its levels, or
+    /// `scope` as set when the levels are unknown.
+    fn scope_display(&self, context: &ChunkContext) -> String {
+        if context.scope_ancestors.is_empty() {
+            context.scope.clone()
+        } else {
+            context.scope_ancestors.join(&self.scope_separator)
+        }
+    }
+
+    /// Enrich a chunk with context and extracted keywords.
+    ///
+    /// The chunk's `section_hierarchy` is used as the breadcrumb unless the
+    /// context already has one. Keywords the chunker set (section names,
+    /// labels, ...) are kept first; extracted ones are added after them,
+    /// skipping duplicates.
+    pub fn enrich(&self, mut chunk: Chunk, mut context: ChunkContext) -> EnrichedChunk {
+        if context.section_hierarchy.is_empty() {
+            context.section_hierarchy = chunk.metadata.section_hierarchy.clone();
+        }
+        for keyword in KeywordExtractor::extract(&chunk.content, &context.language, self.max_keywords) {
+            if !chunk.metadata.keywords.iter().any(|k| k.eq_ignore_ascii_case(&keyword)) {
+                chunk.metadata.keywords.push(keyword);
+            }
+        }
+
+        let prefix = self.build_prefix_for(&context, &chunk.content);
         let enriched_content = if prefix.is_empty() {
             chunk.content.clone()
         } else {
@@ -305,7 +826,11 @@ impl ContextBuilder {
         }
     }
 
-    /// Enrich multiple chunks with file-level context.
+    /// Enrich multiple chunks with file-level context, giving every chunk
+    /// all of `definitions`.
+    ///
+    /// Use `enrich_all_with_entities` when the entities' line ranges are
+    /// known, to give each chunk only its own definitions.
     pub fn enrich_all(
         &self,
         chunks: Vec<Chunk>,
@@ -317,24 +842,84 @@ impl ContextBuilder {
         chunks
             .into_iter()
             .map(|chunk| {
-                // Find definitions in this chunk's line range
-                let chunk_defs: Vec<_> = definitions
-                    .iter()
-                    .filter(|_d| {
-                        // Would filter by line range if we had that info
-                        // For now, include all defs
-                        true
-                    })
-                    .cloned()
-                    .collect();
-
                 let context = ChunkContext {
                     file_path: file_path.to_string(),
                     language: language.to_string(),
-                    definitions: chunk_defs,
+                    definitions: definitions.clone(),
                     dependencies: dependencies.clone(),
                     ..Default::default()
                 };
+                self.enrich(chunk, context)
+            })
+            .collect()
+    }
+
+    /// Enrich multiple chunks with file-level context.
+    ///
+    /// Each chunk's definitions are the entities overlapping its line
+    /// range; chunks without a line range get every entity of the file.
+    pub fn enrich_all_with_entities(
+        &self,
+        chunks: Vec<Chunk>,
+        file_path: &str,
+        language: &str,
+        entities: &[EntityBoundary],
+        dependencies: Vec<String>,
+    ) -> Vec<EnrichedChunk> {
+        let index = Self::build_line_index(entities);
+        self.build_with_index(chunks, &index, file_path, language, &dependencies)
+    }
+
+    /// Index a file's entities by line for `build_with_index`.
+    pub fn build_line_index(entities: &[EntityBoundary]) -> LineEntityIndex<'_> {
+        let mut by_start_line: Vec<&EntityBoundary> = entities.iter().collect();
+        by_start_line.sort_by_key(|entity| entity.start_line);
+        let max_end_line = by_start_line
+            .iter()
+            .scan(0, |max_end, entity| {
+                *max_end = entity.end_line.max(*max_end);
+                Some(*max_end)
+            })
+            .collect();
+
+        LineEntityIndex {
+            by_start_line,
+            max_end_line,
+            entities,
+        }
+    }
+
+    /// Enrich chunks of one file using a prebuilt line index.
+    ///
+    /// Lets callers that enrich a file's chunks in several batches build
+    /// the index once; otherwise the same as `enrich_all_with_entities`.
+    pub fn build_with_index(
+        &self,
+        chunks: Vec<Chunk>,
+        index: &LineEntityIndex<'_>,
+        file_path: &str,
+        language: &str,
+        dependencies: &[String],
+    ) -> Vec<EnrichedChunk> {
+        chunks
+            .into_iter()
+            .map(|chunk| {
+                let (definitions, scope_ancestors) = match chunk.metadata.line_range {
+                    Some((start, end)) => (
+                        index.entities_in(start, end).map(EntitySummary::from).collect(),
+                        index.scope_path(start, end).into_iter().map(|e| e.name.clone()).collect(),
+                    ),
+                    None => (index.entities().iter().map(EntitySummary::from).collect(), Vec::new()),
+                };
+
+                let context = ChunkContext {
+                    file_path: file_path.to_string(),
+                    language: language.to_string(),
+                    definitions,
+                    dependencies: dependencies.to_vec(),
+                    ..Default::default()
+                }
+                .with_scope_ancestors(scope_ancestors);
 
                 self.enrich(chunk, context)
             })
@@ -345,7 +930,8 @@ impl ContextBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Chunk, ChunkMetadata};
+    use crate::types::{Chunk, SourceKind};
+    use uuid::Uuid;
 
     #[test]
     fn test_context_prefix() {
@@ -358,6 +944,7 @@ mod tests {
                 name: "process".to_string(),
                 entity_type: EntityType::Function,
                 signature: Some("def process(data: list) -> dict".to_string()),
+                decorators: vec!["staticmethod".to_string()],
             }],
             dependencies: vec!["json".to_string(), "os".to_string()],
             ..Default::default()
@@ -369,18 +956,323 @@ mod tests {
         assert!(prefix.contains("Language: python"));
         assert!(prefix.contains("Scope: main"));
         assert!(prefix.contains("def process(data: list) -> dict"));
+        assert!(prefix.contains("# Decorators: staticmethod"));
         assert!(prefix.contains("Dependencies:"));
     }
 
+    #[test]
+    fn test_breadcrumb_in_prefix() {
+        let context = ChunkContext::new("docs/guide.md", "markdown").with_section_hierarchy(vec![
+            "Introduction".to_string(),
+            "Getting Started".to_string(),
+            "Prerequisites".to_string(),
+        ]);
+
+        let prefix = ContextBuilder::new().build_prefix(&context);
+        assert!(prefix.contains("# Breadcrumb: Introduction > Getting Started > Prerequisites"));
+        assert!(!ContextBuilder::new().with_scope(false).build_prefix(&context).contains("Breadcrumb"));
+    }
+
+    #[test]
+    fn test_callers_in_prefix() {
+        let mut repo = RepositoryContext::new();
+        repo.register_call("src/main.rs", "run", "process");
+
+        let context = ChunkContext::new("src/lib.rs", "rust")
+            .with_definition(EntitySummary {
+                name: "process".to_string(),
+                entity_type: EntityType::Function,
+                signature: None,
+                decorators: Vec::new(),
+            })
+            .with_callers_from(&repo);
+
+        assert!(!ContextBuilder::new().build_prefix(&context).contains("Called by"));
+        let prefix = ContextBuilder::new().with_callers(true).build_prefix(&context);
+        assert!(prefix.contains("# Called by: src/main.rs::run"));
+    }
+
+    #[test]
+    fn test_token_budget_never_exceeded() {
+        let builder = ContextBuilder::new()
+            .with_max_prefix_length(100_000)
+            .with_token_budget(512);
+        let content = "let value = compute(input);\n".repeat(60);
+
+        for count in [0, 5, 50, 500] {
+            let context = ChunkContext {
+                file_path: "src/lib.rs".to_string(),
+                language: "rust".to_string(),
+                scope: "crate::module::Type".to_string(),
+                definitions: (0..count)
+                    .map(|i| EntitySummary {
+                        name: format!("function_{}", i),
+                        entity_type: EntityType::Function,
+                        signature: Some(format!("pub fn function_{}(a: u32, b: &str) -> Result<()>", i)),
+                        decorators: Vec::new(),
+                    })
+                    .collect(),
+                dependencies: (0..count).map(|i| format!("crate::dep_{}", i)).collect(),
+                ..Default::default()
+            };
+            let chunk = Chunk::new(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                SourceKind::CodeRepo,
+                content.clone(),
+                count_tokens(&content),
+                0,
+                content.len(),
+                0,
+            );
+
+            let enriched = builder.enrich(chunk, context);
+            assert!(count_tokens(&enriched.enriched_content) <= 512, "{} entities", count);
+            assert!(enriched.enriched_content.contains("# File: src/lib.rs"));
+        }
+    }
+
+    #[test]
+    fn test_budget_drops_sections_in_priority_order() {
+        let context = ChunkContext::new("src/lib.rs", "rust")
+            .with_scope("Parser")
+            .with_dependencies(vec!["serde".to_string()]);
+
+        let full = ContextBuilder::new().build_prefix(&context);
+        let budget = count_tokens(&full) + count_tokens("\n---\n") + count_tokens("x");
+        let builder = ContextBuilder::new().with_token_budget(budget - 1);
+
+        let prefix = builder.build_prefix_for(&context, "x");
+        assert!(!prefix.contains("Dependencies"));
+        assert!(prefix.contains("# Scope: Parser"));
+
+        let prefix = builder
+            .with_priority_order(vec![ContextSection::Scope, ContextSection::Dependencies])
+            .build_prefix_for(&context, "x");
+        assert!(!prefix.contains("Scope"));
+        assert!(prefix.contains("# Dependencies: serde"));
+    }
+
+    #[test]
+    fn test_cross_file_references() {
+        use crate::chunkers::repo_chunker::{Symbol, SymbolType, SymbolVisibility, TypeReference, TypeReferenceKind};
+
+        let symbol = |name: &str| Symbol {
+            name: name.to_string(),
+            symbol_type: SymbolType::Struct,
+            byte_range: (0, 0),
+            line_range: (0, 0),
+            parent: None,
+            documentation: None,
+            type_parameters: Vec::new(),
+            visibility: SymbolVisibility::Public,
+            is_test: false,
+        };
+        let mut repo = RepositoryContext::new();
+        repo.register_symbol("src/models.rs", symbol("User"));
+        repo.register_symbol("src/db.rs", symbol("Pool"));
+        repo.register_symbol("src/error.rs", symbol("AppError"));
+        repo.register_symbol("src/handlers.rs", symbol("Handler"));
+        repo.register_type_reference(
+            "src/handlers.rs",
+            TypeReference {
+                type_name: "AppError".to_string(),
+                referencing_entity: "load_user".to_string(),
+                reference_kind: TypeReferenceKind::Return,
+                line: 3,
+            },
+        );
+
+        let context = ChunkContext::new("src/handlers.rs", "rust").with_definition(EntitySummary {
+            name: "load_user".to_string(),
+            entity_type: EntityType::Function,
+            signature: Some("fn load_user(pool: &Pool, handler: Handler) -> Option<User>".to_string()),
+            decorators: Vec::new(),
+        });
+
+        assert!(!ContextBuilder::new().build_prefix(&context).contains("Cross-file"));
+
+        let builder = ContextBuilder::new().with_repository_context(Arc::new(repo));
+        let prefix = builder.build_prefix(&context);
+        // Handler is defined in the chunk's own file; Option is unknown
+        assert!(prefix.contains(
+            "# Cross-file references: AppError defined in src/error.rs, Pool defined in src/db.rs, \
+             User defined in src/models.rs"
+        ));
+
+        let prefix = builder.with_max_cross_references(1).build_prefix(&context);
+        assert!(prefix.ends_with("# Cross-file references: AppError defined in src/error.rs"));
+    }
+
+    fn template_context() -> ChunkContext {
+        ChunkContext::new("src/parser.rs", "rust")
+            .with_scope("Parser")
+            .with_definition(EntitySummary {
+                name: "parse".to_string(),
+                entity_type: EntityType::Method,
+                signature: Some("fn parse(&self) -> Ast".to_string()),
+                decorators: Vec::new(),
+            })
+            .with_dependencies(vec!["std::io".to_string()])
+    }
+
+    #[test]
+    fn test_named_templates() {
+        let context = template_context();
+
+        let default = ContextBuilder::new_with_named_template("default").unwrap();
+        assert_eq!(default.build_prefix(&context), ContextBuilder::new().build_prefix(&context));
+
+        let xml = ContextBuilder::new_with_named_template("xml").unwrap().build_prefix(&context);
+        assert!(xml.starts_with(r#"<context file="src/parser.rs" language="rust">"#));
+        assert!(xml.contains("<scope>Parser</scope>"));
+        assert!(xml.contains("<definitions>fn parse(&amp;self) -&gt; Ast</definitions>"));
+        assert!(xml.ends_with("</context>"));
+
+        let minimal = ContextBuilder::new_with_named_template("minimal").unwrap().build_prefix(&context);
+        assert_eq!(minimal, "# src/parser.rs > Parser");
+
+        assert!(ContextBuilder::new_with_named_template("nope").is_err());
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let builder = ContextBuilder::new()
+            .with_dependencies(false)
+            .with_template("{{ language }}:{{ file_path }} uses [{{ dependencies }}]")
+            .unwrap();
+        assert_eq!(builder.build_prefix(&template_context()), "rust:src/parser.rs uses []");
+
+        assert!(ContextBuilder::new().with_template("{{ unclosed").is_err());
+    }
+
     #[test]
     fn test_enrich_chunk() {
         let builder = ContextBuilder::new();
-        let chunk = Chunk::new("def hello():\n    print('Hello')");
+        let content = "def hello():\n    print('Hello')".to_string();
+        let chunk = Chunk::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            SourceKind::CodeRepo,
+            content.clone(),
+            8,
+            0,
+            content.len(),
+            0,
+        );
         let context = ChunkContext::new("hello.py", "python");
         
         let enriched = builder.enrich(chunk, context);
         
         assert!(enriched.enriched_content.contains("File: hello.py"));
         assert!(enriched.enriched_content.contains("def hello()"));
+        assert_eq!(enriched.chunk.metadata.keywords, vec!["hello", "print"]);
+    }
+
+    #[test]
+    fn test_line_index_definitions() {
+        let entity = |name: &str, entity_type: &str, start_line, end_line| EntityBoundary {
+            name: name.to_string(),
+            entity_type: entity_type.to_string(),
+            start_line,
+            end_line,
+            signature: None,
+            decorators: Vec::new(),
+        };
+        // A class spanning two methods, then a free function
+        let entities = vec![
+            entity("Parser", "class", 1, 40),
+            entity("parse", "method", 2, 20),
+            entity("reset", "method", 22, 39),
+            entity("helper", "function", 42, 50),
+        ];
+        let index = ContextBuilder::build_line_index(&entities);
+        let names = |start, end| index.entities_in(start, end).map(|e| e.name.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(names(25, 30), vec!["Parser", "reset"]);
+        assert_eq!(names(38, 45), vec!["Parser", "reset", "helper"]);
+        assert_eq!(names(51, 60), Vec::<&str>::new());
+
+        let chunk = |start, end| {
+            Chunk::new(Uuid::new_v4(), Uuid::new_v4(), SourceKind::CodeRepo, "x".to_string(), 1, 0, 1, 0)
+                .with_metadata(crate::types::ChunkMetadata {
+                    line_range: Some((start, end)),
+                    ..Default::default()
+                })
+        };
+        let mut unranged = chunk(0, 0);
+        unranged.metadata.line_range = None;
+
+        let enriched = ContextBuilder::new().enrich_all_with_entities(
+            vec![chunk(42, 50), unranged],
+            "parser.py",
+            "python",
+            &entities,
+            vec![],
+        );
+        let defs: Vec<&str> = enriched[0].context.definitions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(defs, vec!["helper"]);
+        assert_eq!(enriched[0].context.definitions[0].entity_type, EntityType::Function);
+        assert_eq!(enriched[1].context.definitions.len(), 4);
+
+        // A long module listed last still covers chunks far from its start
+        let mut entities = entities;
+        entities.push(entity("parsing", "module", 1, 500));
+        let index = ContextBuilder::build_line_index(&entities);
+        let names: Vec<_> = index.entities_in(300, 310).map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["parsing"]);
+        let names: Vec<_> = index.entities_in(45, 45).map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["parsing", "helper"]);
+
+        // Without line ranges every chunk gets every definition
+        let summaries: Vec<EntitySummary> = entities.iter().map(EntitySummary::from).collect();
+        let enriched = ContextBuilder::new().enrich_all(vec![chunk(42, 50)], "parser.py", "python", summaries, vec![]);
+        assert_eq!(enriched[0].context.definitions.len(), 5);
+    }
+
+    #[test]
+    fn test_scope_ancestors_from_impl_block() {
+        let source = "mod lexer {\n    pub struct Tokenizer;\n\n    impl Tokenizer {\n        pub fn next_token(&mut self) -> Token {\n            self.advance()\n        }\n\n        fn advance(&mut self) -> Token {\n            Token::Eof\n        }\n    }\n}\n";
+        // Boundaries as the parser reports them for `source`
+        let entity = |name: &str, entity_type: &str, start_line, end_line| EntityBoundary {
+            name: name.to_string(),
+            entity_type: entity_type.to_string(),
+            start_line,
+            end_line,
+            signature: None,
+            decorators: Vec::new(),
+        };
+        let entities = vec![
+            entity("lexer", "module", 1, 13),
+            entity("Tokenizer", "struct", 2, 2),
+            entity("Tokenizer", "impl", 4, 12),
+            entity("next_token", "method", 5, 7),
+            entity("advance", "method", 9, 11),
+        ];
+        assert_eq!(source.lines().nth(4).unwrap().trim(), "pub fn next_token(&mut self) -> Token {");
+
+        let index = ContextBuilder::build_line_index(&entities);
+        let path = |line| index.scope_path_at_line(line).iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(path(6), vec!["lexer", "Tokenizer", "next_token"]);
+        assert_eq!(path(3), vec!["lexer"]);
+
+        let chunk = |start, end| {
+            Chunk::new(Uuid::new_v4(), Uuid::new_v4(), SourceKind::CodeRepo, "x".to_string(), 1, 0, 1, 0)
+                .with_metadata(crate::types::ChunkMetadata {
+                    line_range: Some((start, end)),
+                    ..Default::default()
+                })
+        };
+        let enriched = ContextBuilder::new().enrich_all_with_entities(vec![chunk(5, 7), chunk(5, 11)], "src/lexer.rs", "rust", &entities, vec![]);
+
+        let method = &enriched[0].context;
+        assert_eq!(method.scope_ancestors, vec!["lexer", "Tokenizer", "next_token"]);
+        assert_eq!(method.scope, "lexer.Tokenizer.next_token");
+        assert!(enriched[0].enriched_content.contains("# Scope: lexer > Tokenizer > next_token\n"));
+        // A chunk spanning both methods is scoped to the impl block
+        assert_eq!(enriched[1].context.scope_ancestors, vec!["lexer", "Tokenizer"]);
+
+        let prefix = ContextBuilder::new().with_scope_separator("::").build_prefix(method);
+        assert!(prefix.contains("# Scope: lexer::Tokenizer::next_token"));
     }
 }
@@ -0,0 +1,143 @@
+//! Newline-delimited JSON (JSONL) export of enriched chunks.
+
+use std::io::{BufRead, Write};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::context_builder::{EnrichedChunk, EntitySummary};
+use crate::types::{ChunkMetadata, SourceKind};
+
+/// An enriched chunk as read back from JSONL.
+///
+/// Holds the exported fields only, without the token counts, offsets and
+/// embedding of the original `Chunk`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialEnrichedChunk {
+    /// Chunk ID.
+    pub id: Uuid,
+    /// ID of the source item the chunk came from.
+    pub source_item_id: Uuid,
+    /// ID of the source.
+    pub source_id: Uuid,
+    /// Kind of source.
+    pub source_kind: SourceKind,
+    /// Original chunk content.
+    pub content: String,
+    /// Content with the context prefix.
+    pub enriched_content: String,
+    /// Chunk metadata.
+    pub metadata: ChunkMetadata,
+    /// File path from the context.
+    pub file_path: String,
+    /// Scope path from the context.
+    #[serde(default)]
+    pub scope: String,
+    /// Definitions from the context.
+    #[serde(default)]
+    pub definitions: Vec<EntitySummary>,
+    /// Dependencies from the context.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+impl From<&EnrichedChunk> for PartialEnrichedChunk {
+    fn from(enriched: &EnrichedChunk) -> Self {
+        Self {
+            id: enriched.chunk.id,
+            source_item_id: enriched.chunk.source_item_id,
+            source_id: enriched.chunk.source_id,
+            source_kind: enriched.chunk.source_kind,
+            content: enriched.chunk.content.clone(),
+            enriched_content: enriched.enriched_content.clone(),
+            metadata: enriched.chunk.metadata.clone(),
+            file_path: enriched.context.file_path.clone(),
+            scope: enriched.context.scope.clone(),
+            definitions: enriched.context.definitions.clone(),
+            dependencies: enriched.context.dependencies.clone(),
+        }
+    }
+}
+
+impl EnrichedChunk {
+    /// Serialize this chunk as a single JSONL line (without the newline).
+    pub fn to_jsonl_entry(&self) -> Result<String> {
+        Ok(serde_json::to_string(&PartialEnrichedChunk::from(self))?)
+    }
+}
+
+/// Write enriched chunks as JSONL, returning the number of lines written.
+pub fn write_enriched_chunks_to_jsonl(chunks: &[EnrichedChunk], writer: &mut impl Write) -> Result<usize> {
+    for chunk in chunks {
+        writeln!(writer, "{}", chunk.to_jsonl_entry()?)?;
+    }
+    writer.flush()?;
+    Ok(chunks.len())
+}
+
+/// Read enriched chunks written by `write_enriched_chunks_to_jsonl`.
+///
+/// Blank lines are skipped.
+pub fn read_enriched_chunks_from_jsonl(reader: &mut impl BufRead) -> Result<Vec<PartialEnrichedChunk>> {
+    let mut chunks = Vec::new();
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let chunk = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid JSONL entry on line {}", line_num + 1))?;
+        chunks.push(chunk);
+    }
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrichment::{ChunkContext, ContextBuilder};
+    use crate::types::Chunk;
+
+    fn enriched(content: &str) -> EnrichedChunk {
+        let chunk = Chunk::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            SourceKind::CodeRepo,
+            content.to_string(),
+            4,
+            0,
+            content.len(),
+            0,
+        );
+        let context = ChunkContext::new("src/lib.rs", "rust")
+            .with_scope("Parser")
+            .with_dependencies(vec!["serde".to_string()]);
+        ContextBuilder::new().enrich(chunk, context)
+    }
+
+    #[test]
+    fn test_jsonl_entry_is_single_line() {
+        let line = enriched("fn a() {}\nfn b() {}").to_jsonl_entry().unwrap();
+        assert!(!line.contains('\n'));
+
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["file_path"], "src/lib.rs");
+        assert_eq!(value["scope"], "Parser");
+        assert!(value["enriched_content"].as_str().unwrap().starts_with("# File: src/lib.rs"));
+    }
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let chunks = vec![enriched("fn a() {}"), enriched("fn b() {}")];
+        let mut buf = Vec::new();
+        assert_eq!(write_enriched_chunks_to_jsonl(&chunks, &mut buf).unwrap(), 2);
+
+        let read = read_enriched_chunks_from_jsonl(&mut buf.as_slice()).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].id, chunks[0].chunk.id);
+        assert_eq!(read[0].enriched_content, chunks[0].enriched_content);
+        assert_eq!(read[0].dependencies, vec!["serde"]);
+        assert_eq!(read[1].content, "fn b() {}");
+    }
+}
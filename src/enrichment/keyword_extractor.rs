@@ -0,0 +1,193 @@
+//! Keyword extraction for hybrid (keyword + vector) search.
+//!
+//! Code chunks yield their most frequent identifiers, with defined names
+//! (functions, classes, ...) weighted up. Text chunks are scored with
+//! TF-IDF, using the ranks of the 10,000 most common English words as the
+//! IDF corpus.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::types::language::language_from_name;
+
+/// The 10,000 most common English words, most frequent first. The head of
+/// the list follows Wikipedia frequency order; the tail follows the word
+/// order of a BPE tokenizer vocabulary, with subword fragments removed.
+const COMMON_WORDS: &str = include_str!("common_english_words.txt");
+
+/// Size of the IDF corpus; words not in `COMMON_WORDS` are ranked here.
+const IDF_CORPUS_SIZE: usize = 10_000;
+
+/// Weight of a name at its definition site relative to a plain use.
+const DEFINITION_WEIGHT: usize = 3;
+
+/// Keywords too common in code to be useful.
+const CODE_STOP_WORDS: &[&str] = &[
+    "async", "await", "bool", "break", "case", "catch", "char", "class", "const", "continue", "default",
+    "defer", "elif", "else", "enum", "except", "export", "extends", "false", "final", "finally", "float",
+    "from", "func", "function", "impl", "implements", "import", "interface", "lambda", "none", "null",
+    "package", "pass", "private", "protected", "public", "raise", "return", "self", "static", "string",
+    "struct", "super", "switch", "this", "throw", "throws", "trait", "true", "type", "typeof", "undefined",
+    "void", "where", "while", "with", "yield",
+];
+
+fn word_ranks() -> &'static HashMap<&'static str, usize> {
+    static RANKS: OnceLock<HashMap<&'static str, usize>> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        COMMON_WORDS
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .enumerate()
+            .map(|(rank, word)| (word, rank + 1))
+            .collect()
+    })
+}
+
+fn identifier_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap())
+}
+
+fn definition_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\b(?:fn|def|func|function|class|struct|enum|trait|interface|type)\s+([A-Za-z_][A-Za-z0-9_]*)")
+            .unwrap()
+    })
+}
+
+fn word_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z][A-Za-z'-]*[A-Za-z]").unwrap())
+}
+
+/// Extracts keywords from chunk content.
+pub struct KeywordExtractor;
+
+impl KeywordExtractor {
+    /// Extract up to `max_keywords` lowercased, deduplicated keywords,
+    /// most relevant first.
+    ///
+    /// `language` selects identifier extraction for code languages
+    /// (e.g. `"rust"`) and TF-IDF for anything else.
+    pub fn extract(content: &str, language: &str, max_keywords: usize) -> Vec<String> {
+        if max_keywords == 0 {
+            return Vec::new();
+        }
+
+        let scores = if is_code(language) {
+            code_scores(content)
+        } else {
+            text_scores(content)
+        };
+
+        // (score, first occurrence, keyword), highest score first
+        let mut ranked: Vec<_> = scores
+            .into_iter()
+            .map(|(keyword, (score, first))| (score, first, keyword))
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        ranked.into_iter().take(max_keywords).map(|(.., keyword)| keyword).collect()
+    }
+}
+
+fn is_code(language: &str) -> bool {
+    language_from_name(language).is_some_and(|name| name != "markdown")
+}
+
+/// Add `weight` to a keyword's score, recording where it first appeared.
+fn add(scores: &mut HashMap<String, (f64, usize)>, keyword: String, weight: f64, position: usize) {
+    let entry = scores.entry(keyword).or_insert((0.0, position));
+    entry.0 += weight;
+}
+
+/// Identifier frequencies, with definitions weighted up.
+fn code_scores(content: &str) -> HashMap<String, (f64, usize)> {
+    let mut scores = HashMap::new();
+    let keep = |name: &str| name.len() > 3 && !CODE_STOP_WORDS.contains(&name);
+
+    for m in identifier_regex().find_iter(content) {
+        let name = m.as_str().to_lowercase();
+        if keep(&name) {
+            add(&mut scores, name, 1.0, m.start());
+        }
+    }
+    for caps in definition_regex().captures_iter(content) {
+        let m = caps.get(1).unwrap();
+        let name = m.as_str().to_lowercase();
+        if keep(&name) {
+            add(&mut scores, name, (DEFINITION_WEIGHT - 1) as f64, m.start());
+        }
+    }
+    scores
+}
+
+/// TF-IDF scores of the words in `content`.
+fn text_scores(content: &str) -> HashMap<String, (f64, usize)> {
+    let ranks = word_ranks();
+    let words: Vec<_> = word_regex()
+        .find_iter(content)
+        .map(|m| (m.as_str().to_lowercase(), m.start()))
+        .filter(|(word, _)| word.len() > 2)
+        .collect();
+
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for (word, position) in &words {
+        counts.entry(word.clone()).or_insert((0, *position)).0 += 1;
+    }
+
+    let total = words.len() as f64;
+    counts
+        .into_iter()
+        .map(|(word, (count, first))| {
+            // Zipf: document frequency falls off with frequency rank
+            let rank = ranks.get(word.as_str()).copied().unwrap_or(IDF_CORPUS_SIZE);
+            let idf = (1.0 + rank as f64).ln();
+            (word, (count as f64 / total * idf, first))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_keywords() {
+        let code = "def parse_config(path):\n    config = load_yaml(path)\n    return validate_config(config)\n\ndef main():\n    parse_config('app.yaml')\n";
+        let keywords = KeywordExtractor::extract(code, "python", 4);
+
+        // Definitions outrank plain uses; ties go to the earliest occurrence
+        assert_eq!(keywords, vec!["parse_config", "main", "path", "config"]);
+        assert!(!KeywordExtractor::extract(code, "python", 10).iter().any(|k| k == "return" || k == "def"));
+    }
+
+    #[test]
+    fn test_text_keywords_prefer_rare_words() {
+        let text = "The Kubernetes scheduler places pods on nodes. The scheduler was built for the cluster, \
+                    and the scheduler is one of the most important parts of the system.";
+        let keywords = KeywordExtractor::extract(text, "en", 3);
+
+        assert_eq!(keywords[0], "scheduler");
+        assert!(keywords.contains(&"kubernetes".to_string()));
+        assert!(!keywords.contains(&"the".to_string()));
+    }
+
+    #[test]
+    fn test_keywords_lowercased_and_deduplicated() {
+        let keywords = KeywordExtractor::extract("Tokio tokio TOKIO runtime", "en", 10);
+        assert_eq!(keywords, vec!["tokio", "runtime"]);
+        assert!(KeywordExtractor::extract("anything", "en", 0).is_empty());
+    }
+
+    #[test]
+    fn test_common_word_list_is_complete() {
+        let ranks = word_ranks();
+        assert_eq!(ranks.len(), IDF_CORPUS_SIZE);
+        assert_eq!(ranks["the"], 1);
+        assert!(ranks.values().all(|&rank| rank <= IDF_CORPUS_SIZE));
+    }
+}
@@ -4,7 +4,16 @@
 //! - Context prefix generation for code chunks
 //! - Scope and dependency processing
 //! - Rich metadata for improved embedding quality
+//! - Keyword extraction for hybrid search
+//! - Complexity scoring for code chunks
+//! - JSONL export of enriched chunks
 
+pub mod complexity_analyzer;
 pub mod context_builder;
+pub mod jsonl;
+pub mod keyword_extractor;
 
-pub use context_builder::{ChunkContext, ContextBuilder, EnrichedChunk};
+pub use complexity_analyzer::CodeComplexityAnalyzer;
+pub use context_builder::{ChunkContext, ContextBuilder, ContextSection, EnrichedChunk, LineEntityIndex};
+pub use keyword_extractor::KeywordExtractor;
+pub use jsonl::{read_enriched_chunks_from_jsonl, write_enriched_chunks_to_jsonl, PartialEnrichedChunk};
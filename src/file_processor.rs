@@ -0,0 +1,649 @@
+//! Repository walking for batch processing.
+//!
+//! Turns a directory tree into `FileEntry` values ready for
+//! `files_to_source_items`:
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! # use chunker::batch::files_to_source_items;
+//! # use chunker::file_processor::{FileProcessor, FilterConfig};
+//! # fn main() -> anyhow::Result<()> {
+//! let files = FileProcessor::walk_repository(Path::new("."), &FilterConfig::default())?;
+//! let items = files_to_source_items(files, uuid::Uuid::new_v4());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures::Stream;
+use ignore::gitignore::Gitignore;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use walkdir::WalkDir;
+
+use crate::batch::{detect_language, FileEntry};
+use crate::types::SourceItem;
+
+/// Bytes inspected when deciding whether a file is binary.
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Rules for which files a repository walk keeps.
+#[derive(Debug, Clone)]
+pub struct FilterConfig {
+    /// File extensions to keep, without the dot (empty = any extension)
+    pub include_extensions: Vec<String>,
+    /// Directory names never descended into
+    pub exclude_dirs: Vec<String>,
+    /// Keep files and directories whose names start with `.`
+    pub include_hidden: bool,
+    /// Skip files larger than this many bytes
+    pub max_file_size: u64,
+    /// Skip files nested in more than this many directories below the root
+    pub max_path_depth: Option<usize>,
+    /// `.gitignore` files whose patterns are checked before every other
+    /// rule; each applies to the directory it is in
+    pub gitignore_paths: Vec<PathBuf>,
+    /// Project-specific rules, checked after the static ones
+    pub custom_predicates: Vec<CustomPredicate>,
+    /// Set `FileEntry::content_hash` on every file kept
+    pub compute_hashes: bool,
+}
+
+/// A file predicate: given a path relative to the walk root and the file
+/// size, `Err` carries the reason the file is skipped.
+pub type PathPredicate = Arc<dyn Fn(&str, usize) -> Result<(), String> + Send + Sync>;
+
+/// A custom rule added with `FileFilter::with_custom_predicate`.
+#[derive(Clone)]
+pub struct CustomPredicate {
+    description: String,
+    predicate: PathPredicate,
+}
+
+impl CustomPredicate {
+    /// What the rule does, as listed by `FilterConfig::describe`.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl fmt::Debug for CustomPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CustomPredicate").field(&self.description).finish()
+    }
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            include_extensions: Vec::new(),
+            exclude_dirs: ["node_modules", "target", "vendor", "dist", "build", "__pycache__", ".git", ".venv"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            include_hidden: false,
+            max_file_size: 1024 * 1024,
+            max_path_depth: None,
+            gitignore_paths: Vec::new(),
+            custom_predicates: Vec::new(),
+            compute_hashes: false,
+        }
+    }
+}
+
+impl FilterConfig {
+    /// One line per rule, static rules first, for debugging.
+    pub fn describe(&self) -> Vec<String> {
+        let mut rules = Vec::new();
+        if self.include_extensions.is_empty() {
+            rules.push("extensions: any".to_string());
+        } else {
+            rules.push(format!("extensions: {}", self.include_extensions.join(", ")));
+        }
+        if !self.exclude_dirs.is_empty() {
+            rules.push(format!("excluded directories: {}", self.exclude_dirs.join(", ")));
+        }
+        rules.push(format!("hidden files: {}", if self.include_hidden { "included" } else { "skipped" }));
+        rules.push(format!("max file size: {} bytes", self.max_file_size));
+        if let Some(depth) = self.max_path_depth {
+            rules.push(format!("max path depth: {}", depth));
+        }
+        rules.extend(self.gitignore_paths.iter().map(|path| format!("gitignore: {}", path.display())));
+        rules.extend(self.custom_predicates.iter().map(|p| format!("custom: {}", p.description)));
+        rules
+    }
+}
+
+/// Applies `FilterConfig` rules to paths found during a walk.
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    config: FilterConfig,
+    /// Parsed `gitignore_paths`
+    gitignores: Vec<Gitignore>,
+    /// Directory that checked paths are relative to: the walk root, or the
+    /// directory of the shallowest `.gitignore` outside a walk
+    root: PathBuf,
+}
+
+impl FileFilter {
+    /// Create a filter from a configuration.
+    ///
+    /// `.gitignore` files that can't be read are skipped with a warning;
+    /// invalid lines in them are ignored. Checked paths are taken to be
+    /// relative to the directory of the shallowest one, the repository
+    /// root; use `with_root` to set another.
+    pub fn new(config: FilterConfig) -> Self {
+        let gitignores: Vec<Gitignore> = config
+            .gitignore_paths
+            .iter()
+            .map(|path| {
+                let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+                let (gitignore, error) = Gitignore::new(&path);
+                if let Some(e) = error {
+                    tracing::warn!(path = %path.display(), error = %e, "Problem loading .gitignore");
+                }
+                gitignore
+            })
+            .collect();
+        let root = gitignores
+            .iter()
+            .map(Gitignore::path)
+            .min_by_key(|dir| dir.components().count())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Self { config, gitignores, root }
+    }
+
+    /// Create a filter with the default rules plus every `.gitignore` under
+    /// `repo_root`, for paths relative to `repo_root`.
+    ///
+    /// Directories the default rules exclude (`.git`, `node_modules`, ...)
+    /// are not searched.
+    pub fn with_gitignore(repo_root: &Path) -> Result<FileFilter> {
+        let defaults = FileFilter::new(FilterConfig {
+            gitignore_paths: Vec::new(),
+            ..FilterConfig::default()
+        });
+
+        let mut gitignore_paths = Vec::new();
+        let walker = WalkDir::new(repo_root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| !entry.file_type().is_dir() || entry.depth() == 0 || defaults.accepts_dir(entry.path()));
+        for entry in walker {
+            let entry = entry.with_context(|| format!("Failed to walk {}", repo_root.display()))?;
+            if entry.file_type().is_file() && entry.file_name() == ".gitignore" {
+                gitignore_paths.push(entry.into_path());
+            }
+        }
+
+        let config = FilterConfig {
+            gitignore_paths,
+            ..defaults.config
+        };
+        Ok(FileFilter::new(config).with_root(repo_root))
+    }
+
+    /// Resolve checked paths against `root`, the repository they come
+    /// from, when matching `.gitignore` rules.
+    pub fn with_root(mut self, root: &Path) -> Self {
+        self.root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        self
+    }
+
+    /// Add a predicate run on every file that passes the static rules.
+    ///
+    /// It receives the path relative to the walk root and the file size;
+    /// returning `Err(reason)` skips the file.
+    pub fn with_custom_predicate(self, predicate: PathPredicate) -> Self {
+        let description = format!("predicate #{}", self.config.custom_predicates.len() + 1);
+        self.with_described_predicate(description, predicate)
+    }
+
+    /// Keep only paths matching `pattern` (`include`), or skip them.
+    pub fn with_custom_path_regex(self, pattern: Regex, include: bool) -> Self {
+        let description = format!("{} paths matching /{}/", if include { "keep only" } else { "skip" }, pattern);
+        let predicate: PathPredicate = Arc::new(move |path, _| match (pattern.is_match(path), include) {
+            (true, true) | (false, false) => Ok(()),
+            (false, true) => Err(format!("path does not match /{}/", pattern)),
+            (true, false) => Err(format!("path matches /{}/", pattern)),
+        });
+        self.with_described_predicate(description, predicate)
+    }
+
+    fn with_described_predicate(mut self, description: String, predicate: PathPredicate) -> Self {
+        self.config.custom_predicates.push(CustomPredicate { description, predicate });
+        self
+    }
+
+    /// The rules this filter applies.
+    pub fn config(&self) -> &FilterConfig {
+        &self.config
+    }
+
+    /// Whether a walk should descend into a directory.
+    pub fn accepts_dir(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return true;
+        };
+        !self.config.exclude_dirs.iter().any(|dir| dir == name) && (self.config.include_hidden || !is_hidden(name))
+    }
+
+    /// Whether a file of `size` bytes should be kept.
+    ///
+    /// `path` should be relative to the walk root for `max_path_depth`
+    /// and custom predicates to see the same paths as a walk.
+    pub fn accepts_file(&self, path: &Path, size: u64) -> bool {
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.should_process(&path, size).is_ok()
+    }
+
+    /// Check a file against every rule, static rules first.
+    ///
+    /// `path` is relative to the walk root with `/` separators; `Err`
+    /// carries the reason the file is skipped.
+    pub fn should_process(&self, path: &str, size: u64) -> Result<(), String> {
+        let config = &self.config;
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if let Some(pattern) = self.gitignore_match(path) {
+            return Err(format!("ignored by .gitignore pattern {:?}", pattern));
+        }
+        if size > config.max_file_size {
+            return Err(format!("larger than {} bytes", config.max_file_size));
+        }
+        if !config.include_hidden && is_hidden(name) {
+            return Err("hidden file".to_string());
+        }
+        if !config.include_extensions.is_empty() {
+            let extension = Path::new(name).extension().and_then(|e| e.to_str()).unwrap_or_default();
+            if !config.include_extensions.iter().any(|e| e.eq_ignore_ascii_case(extension)) {
+                return Err(format!("extension {:?} not included", extension));
+            }
+        }
+        if let Some(max_depth) = config.max_path_depth {
+            let depth = path.matches('/').count();
+            if depth > max_depth {
+                return Err(format!("{} directories deep, more than {}", depth, max_depth));
+            }
+        }
+
+        let size = usize::try_from(size).unwrap_or(usize::MAX);
+        config.custom_predicates.iter().try_for_each(|p| (p.predicate)(path, size))
+    }
+
+    /// The `.gitignore` pattern that ignores `path` (or one of its parent
+    /// directories), if any. A negated match in a deeper `.gitignore`
+    /// overrides its parents.
+    fn gitignore_match(&self, path: &str) -> Option<String> {
+        let path = self.root.join(path);
+        let mut ignored = None;
+        // Shallower files first, so deeper ones get the last word
+        let mut gitignores: Vec<&Gitignore> = self.gitignores.iter().filter(|g| path.starts_with(g.path())).collect();
+        gitignores.sort_by_key(|g| g.path().components().count());
+        for gitignore in gitignores {
+            match gitignore.matched_path_or_any_parents(&path, false) {
+                ignore::Match::Ignore(glob) => ignored = Some(glob.original().to_string()),
+                ignore::Match::Whitelist(_) => ignored = None,
+                ignore::Match::None => {}
+            }
+        }
+        ignored
+    }
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.') && name != "." && name != ".."
+}
+
+/// Walks repositories into `FileEntry` values.
+pub struct FileProcessor;
+
+impl FileProcessor {
+    /// SHA-256 fingerprint of file content, for detecting unchanged files.
+    pub fn fingerprint(content: &[u8]) -> [u8; 32] {
+        Sha256::digest(content).into()
+    }
+
+    /// Lowercase hex encoding of `fingerprint`.
+    pub fn fingerprint_hex(content: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(content))
+    }
+
+    /// Check whether two contents have the same fingerprint.
+    pub fn fingerprints_match(a: &[u8], b: &[u8]) -> bool {
+        Self::fingerprint(a) == Self::fingerprint(b)
+    }
+
+    /// Walk `root`, keeping text files accepted by `config`, sorted by path.
+    ///
+    /// Paths are relative to `root` with `/` separators. Binary files are
+    /// skipped and invalid UTF-8 is replaced.
+    pub fn walk_repository(root: &Path, config: &FilterConfig) -> Result<Vec<FileEntry>> {
+        let mut files = Self::walk(root, FileFilter::new(config.clone()).with_root(root))
+            .filter_map(|file| file.transpose())
+            .collect::<Result<Vec<_>>>()?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// Stream the files under `root` with the default `FilterConfig`.
+    ///
+    /// The walk runs on a blocking thread and only reads ahead a few files,
+    /// so large repositories can be processed as they are found. Files come
+    /// out in walk order: each directory's entries sorted by name.
+    pub fn walk_repository_stream(root: &Path) -> impl Stream<Item = Result<FileEntry>> {
+        let (tx, rx) = mpsc::channel(16);
+        let root = root.to_path_buf();
+
+        tokio::task::spawn_blocking(move || {
+            for file in Self::walk(&root, FileFilter::new(FilterConfig::default()).with_root(&root)) {
+                let Some(file) = file.transpose() else {
+                    continue;
+                };
+                if tx.blocking_send(file).is_err() {
+                    break;
+                }
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+
+    /// Like `walk_repository` with the default `FilterConfig`, but also
+    /// honouring `.gitignore`, `.ignore` and global git excludes.
+    pub fn walk_with_gitignore(root: &Path) -> Result<Vec<FileEntry>> {
+        let filter = FileFilter::new(FilterConfig::default()).with_root(root);
+        let dir_filter = filter.clone();
+        let walker = ignore::WalkBuilder::new(root)
+            .hidden(false)
+            .require_git(false)
+            .filter_entry(move |entry| {
+                !entry.file_type().is_some_and(|t| t.is_dir()) || entry.depth() == 0 || dir_filter.accepts_dir(entry.path())
+            })
+            .build();
+
+        let mut files = Vec::new();
+        for entry in walker {
+            let entry = entry.with_context(|| format!("Failed to walk {}", root.display()))?;
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                if let Some(file) = Self::read_file(root, entry.path(), &filter)? {
+                    files.push(file);
+                }
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+
+    /// Check an item against `filter` as if a walk had found it.
+    ///
+    /// Items with a `path` in their metadata get every file and directory
+    /// rule; other items only the size limit. Empty and binary content is
+    /// rejected too. `Err` carries the reason.
+    pub fn is_content_processable(item: &SourceItem, filter: &FileFilter) -> Result<(), String> {
+        if item.content.trim().is_empty() {
+            return Err("empty content".to_string());
+        }
+        if item.content.as_bytes()[..item.content.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+            return Err("binary content".to_string());
+        }
+
+        let size = item.content.len() as u64;
+        let Some(path) = item.extract_path() else {
+            let max_file_size = filter.config().max_file_size;
+            if size > max_file_size {
+                return Err(format!("larger than {} bytes", max_file_size));
+            }
+            return Ok(());
+        };
+        let path = path.trim_start_matches("./").trim_start_matches('/');
+        if let Some((dirs, _)) = path.rsplit_once('/') {
+            if let Some(dir) = dirs.split('/').find(|dir| !filter.accepts_dir(Path::new(dir))) {
+                return Err(format!("in excluded directory {:?}", dir));
+            }
+        }
+        filter.should_process(path, size)
+    }
+
+    /// Lazily walk `root`, yielding `None` for files the filter rejects.
+    fn walk(root: &Path, filter: FileFilter) -> impl Iterator<Item = Result<Option<FileEntry>>> {
+        let root: PathBuf = root.to_path_buf();
+        let dir_filter = filter.clone();
+
+        WalkDir::new(&root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(move |entry| !entry.file_type().is_dir() || entry.depth() == 0 || dir_filter.accepts_dir(entry.path()))
+            .filter_map(move |entry| match entry {
+                Ok(entry) if entry.file_type().is_file() => Some(Self::read_file(&root, entry.path(), &filter)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e).with_context(|| format!("Failed to walk {}", root.display()))),
+            })
+    }
+
+    /// Read and decode a file, or `None` if it is filtered out or binary.
+    fn read_file(root: &Path, path: &Path, filter: &FileFilter) -> Result<Option<FileEntry>> {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let size = fs::metadata(path).with_context(|| format!("Failed to stat {}", path.display()))?.len();
+        if let Err(reason) = filter.should_process(&relative, size) {
+            tracing::trace!(path = %relative, reason = %reason, "Skipping file");
+            return Ok(None);
+        }
+
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+            return Ok(None);
+        }
+
+        let content_hash = filter.config().compute_hashes.then(|| Self::fingerprint_hex(&bytes));
+        Ok(Some(FileEntry {
+            language: detect_language(&relative),
+            content: String::from_utf8_lossy(&bytes).into_owned(),
+            path: relative,
+            content_hash,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn create_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &[u8]| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("src/main.rs", b"fn main() {}\n");
+        write("src/lib.rs", b"pub mod util;\n");
+        write("src/util/strings.rs", b"pub fn trim() {}\n");
+        write("README.md", b"# Demo\n");
+        write("logo.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        write("node_modules/left-pad/index.js", b"module.exports = 1;\n");
+        write(".env", b"SECRET=1\n");
+        write("generated/schema.rs", b"// generated\n");
+        write(".gitignore", b"generated/\n");
+        dir
+    }
+
+    fn paths(files: &[FileEntry]) -> Vec<&str> {
+        files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_walk_repository() {
+        let repo = create_repo();
+        let files = FileProcessor::walk_repository(repo.path(), &FilterConfig::default()).unwrap();
+
+        assert_eq!(
+            paths(&files),
+            ["README.md", "generated/schema.rs", "src/lib.rs", "src/main.rs", "src/util/strings.rs"]
+        );
+        assert_eq!(files[2].language.as_deref(), Some("rust"));
+        assert_eq!(files[2].content, "pub mod util;\n");
+
+        let config = FilterConfig {
+            include_extensions: vec!["md".to_string()],
+            ..Default::default()
+        };
+        let files = FileProcessor::walk_repository(repo.path(), &config).unwrap();
+        assert_eq!(paths(&files), ["README.md"]);
+        assert!(files[0].content_hash.is_none());
+    }
+
+    #[test]
+    fn test_fingerprints() {
+        let fingerprint = FileProcessor::fingerprint(b"abc");
+        assert_eq!(fingerprint[..4], [0xba, 0x78, 0x16, 0xbf]);
+        assert_eq!(
+            FileProcessor::fingerprint_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(FileProcessor::fingerprints_match(b"abc", b"abc"));
+        assert!(!FileProcessor::fingerprints_match(b"abc", b"abd"));
+
+        let repo = create_repo();
+        let config = FilterConfig {
+            include_extensions: vec!["md".to_string()],
+            compute_hashes: true,
+            ..Default::default()
+        };
+        let files = FileProcessor::walk_repository(repo.path(), &config).unwrap();
+        assert_eq!(files[0].content_hash, Some(FileProcessor::fingerprint_hex(b"# Demo\n")));
+    }
+
+    #[test]
+    fn test_custom_predicates_and_depth() {
+        let repo = create_repo();
+        let config = FilterConfig {
+            max_path_depth: Some(1),
+            ..Default::default()
+        };
+        let filter = FileFilter::new(config)
+            .with_custom_path_regex(Regex::new(r"^generated/").unwrap(), false)
+            .with_custom_predicate(Arc::new(|path, size| {
+                if path.ends_with(".md") && size < 100 {
+                    Err("stub readme".to_string())
+                } else {
+                    Ok(())
+                }
+            }));
+
+        assert_eq!(filter.should_process("src/util/strings.rs", 10), Err("2 directories deep, more than 1".to_string()));
+        assert_eq!(filter.should_process("generated/schema.rs", 10), Err("path matches /^generated//".to_string()));
+        assert_eq!(filter.should_process("README.md", 10), Err("stub readme".to_string()));
+        assert!(filter.should_process("README.md", 500).is_ok());
+
+        let files = FileProcessor::walk_repository(repo.path(), filter.config()).unwrap();
+        assert_eq!(paths(&files), ["src/lib.rs", "src/main.rs"]);
+
+        let rules = filter.config().describe();
+        assert!(rules.contains(&"max path depth: 1".to_string()));
+        assert_eq!(rules[rules.len() - 2..], ["custom: skip paths matching /^generated//", "custom: predicate #2"]);
+    }
+
+    #[test]
+    fn test_file_filter_with_gitignore() {
+        let repo = create_repo();
+        fs::write(repo.path().join(".gitignore"), "generated/\n*.log\n").unwrap();
+        fs::write(repo.path().join("src/.gitignore"), "scratch.rs\n!keep.log\n").unwrap();
+        fs::write(repo.path().join("debug.log"), "x\n").unwrap();
+        fs::write(repo.path().join("src/scratch.rs"), "x\n").unwrap();
+        fs::write(repo.path().join("src/keep.log"), "x\n").unwrap();
+
+        let filter = FileFilter::with_gitignore(repo.path()).unwrap();
+        assert_eq!(filter.config().gitignore_paths.len(), 2);
+        assert_eq!(filter.should_process("debug.log", 1), Err("ignored by .gitignore pattern \"*.log\"".to_string()));
+        assert!(filter.should_process("generated/schema.rs", 1).is_err());
+        assert!(filter.should_process("src/scratch.rs", 1).is_err());
+        assert!(filter.should_process("src/keep.log", 1).is_ok());
+        assert!(filter.should_process("src/main.rs", 1).is_ok());
+        // Nested patterns only apply below their own directory
+        assert!(filter.should_process("scratch.rs", 1).is_ok());
+
+        let files = FileProcessor::walk_repository(repo.path(), filter.config()).unwrap();
+        assert_eq!(
+            paths(&files),
+            ["README.md", "src/keep.log", "src/lib.rs", "src/main.rs", "src/util/strings.rs"]
+        );
+    }
+
+    #[test]
+    fn test_gitignores_apply_only_when_configured() {
+        assert!(FilterConfig::default().gitignore_paths.is_empty());
+
+        let repo = create_repo();
+        let item = |path: &str| {
+            SourceItem::builder()
+                .with_content("x\n")
+                .with_metadata(serde_json::json!({ "path": path }))
+                .build()
+                .unwrap()
+        };
+        let defaults = FileFilter::new(FilterConfig::default());
+        assert!(FileProcessor::is_content_processable(&item("generated/schema.rs"), &defaults).is_ok());
+
+        // Item paths are matched against the configured repository, not the
+        // directory the service runs in
+        let configured = FileFilter::new(FilterConfig {
+            gitignore_paths: vec![repo.path().join(".gitignore")],
+            ..Default::default()
+        });
+        assert!(FileProcessor::is_content_processable(&item("generated/schema.rs"), &configured).is_err());
+        assert!(FileProcessor::is_content_processable(&item("src/main.rs"), &configured).is_ok());
+    }
+
+    #[test]
+    fn test_walk_with_gitignore() {
+        let repo = create_repo();
+        let files = FileProcessor::walk_with_gitignore(repo.path()).unwrap();
+        assert_eq!(paths(&files), ["README.md", "src/lib.rs", "src/main.rs", "src/util/strings.rs"]);
+
+        let source_id = uuid::Uuid::new_v4();
+        let items = crate::batch::files_to_source_items(files.clone(), source_id);
+        assert_eq!(items[0].content_type, "text/markdown");
+        assert_eq!(items[2].content_type, "text/code:rust");
+        assert!(items.iter().all(|item| item.source_id == source_id));
+
+        let chunks = files[2].to_source_items_with_chunks(source_id, &crate::types::ChunkConfig::default()).unwrap();
+        assert!(!chunks.is_empty());
+        assert!(chunks.iter().all(|chunk| chunk.source_id == source_id));
+        let item = SourceItem::from(files[2].clone());
+        assert_eq!(item.extract_path(), Some("src/main.rs"));
+        assert_ne!(item.source_id, source_id);
+    }
+
+    #[tokio::test]
+    async fn test_walk_repository_stream() {
+        let repo = create_repo();
+        let streamed: Vec<_> = FileProcessor::walk_repository_stream(repo.path())
+            .map(|file| file.unwrap().path)
+            .collect()
+            .await;
+        let walked = FileProcessor::walk_repository(repo.path(), &FilterConfig::default()).unwrap();
+
+        let mut sorted = streamed.clone();
+        sorted.sort();
+        assert_eq!(sorted, paths(&walked));
+    }
+}
@@ -3,5 +3,5 @@
 mod processor;
 mod store;
 
-pub use processor::JobProcessor;
+pub use processor::{JobEvent, JobProcessor};
 pub use store::JobStore;
@@ -1,21 +1,54 @@
 //! Job processor for async chunk processing.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use tokio::sync::RwLock;
-use tracing::{error, info, warn};
+use futures::future::join_all;
+use serde::Serialize;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use super::store::JobStore;
-use crate::output::{EmbeddingClient, RelationGraphClient};
+use crate::batch::{item_content_hash, reuse_cached_chunks, FileHashCache};
+use crate::output::{check_webhook_url, deliver_webhook, EmbeddingClient, RelationGraphClient, WebhookEvent, WebhookStore};
 use crate::router::ChunkingRouter;
-use crate::types::{Chunk, SourceItem, StartChunkJobRequest};
+use crate::telemetry::metrics;
+use crate::types::{assign_content_hash_ids, Chunk, SourceItem, StartChunkJobRequest};
+
+/// Buffered events per job before slow subscribers start lagging.
+const JOB_EVENT_CAPACITY: usize = 64;
+
+/// A progress event published while a job runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobEvent {
+    /// An item finished processing
+    Progress {
+        chunks_created: usize,
+        items_processed: usize,
+        items_total: usize,
+    },
+    /// The job completed
+    Complete,
+    /// The job failed
+    Failed { error: String },
+}
 
 /// Processor that handles chunking jobs asynchronously.
 pub struct JobProcessor {
     router: Arc<ChunkingRouter>,
     embedding_client: Option<Arc<EmbeddingClient>>,
     relation_graph_client: Option<Arc<RelationGraphClient>>,
+    /// Event channels for jobs with subscribers or in progress
+    events: Mutex<HashMap<Uuid, broadcast::Sender<JobEvent>>>,
+    /// Webhooks notified when jobs finish
+    webhooks: Option<Arc<RwLock<WebhookStore>>>,
+    /// Deliver to webhooks on loopback and private networks
+    allow_private_webhook_urls: bool,
+    /// Chunks of previously processed files, reused when a file is unchanged
+    file_cache: Option<Arc<Mutex<FileHashCache>>>,
+    http_client: reqwest::Client,
 }
 
 impl JobProcessor {
@@ -29,10 +62,101 @@ impl JobProcessor {
             router,
             embedding_client,
             relation_graph_client,
+            events: Mutex::new(HashMap::new()),
+            webhooks: None,
+            allow_private_webhook_urls: false,
+            file_cache: None,
+            http_client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                // A redirect could point a public webhook at an internal host
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("Failed to create HTTP client"),
         }
     }
 
+    /// Notify webhooks registered in the given store about job events.
+    pub fn with_webhooks(mut self, webhooks: Arc<RwLock<WebhookStore>>) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Allow deliveries to webhooks that resolve to loopback or private
+    /// addresses, which are skipped by default.
+    pub fn with_private_webhook_urls(mut self, allow: bool) -> Self {
+        self.allow_private_webhook_urls = allow;
+        self
+    }
+
+    /// Reuse chunks of files whose path and content hash are in `cache`, and
+    /// record newly chunked files in it.
+    pub fn with_file_cache(mut self, cache: Arc<Mutex<FileHashCache>>) -> Self {
+        self.file_cache = Some(cache);
+        self
+    }
+
+    /// POST an event to every webhook of `owner` subscribed to it.
+    ///
+    /// Deliveries run concurrently; failures are logged after retries.
+    /// Unless private URLs are allowed, each webhook's host is resolved
+    /// again first, so a DNS change cannot redirect it to an internal host.
+    pub async fn notify_webhooks(&self, event: WebhookEvent, owner: Option<&str>, payload: &impl Serialize) {
+        let Some(store) = &self.webhooks else {
+            return;
+        };
+        let subscribers = store.read().await.owned_subscribers(event, owner);
+        if subscribers.is_empty() {
+            return;
+        }
+
+        let body = match serde_json::to_vec(&serde_json::json!({ "event": event, "payload": payload })) {
+            Ok(body) => body,
+            Err(e) => {
+                error!(error = %e, "Failed to serialize webhook payload");
+                return;
+            }
+        };
+
+        let deliveries = subscribers.iter().map(|webhook| async {
+            if !self.allow_private_webhook_urls {
+                if let Err(e) = check_webhook_url(&webhook.registration.url).await {
+                    warn!(webhook_id = %webhook.id, error = %e, "Skipping webhook");
+                    return;
+                }
+            }
+            if let Err(e) = deliver_webhook(&self.http_client, webhook, &body).await {
+                error!(webhook_id = %webhook.id, url = %webhook.registration.url, error = %e, "Webhook delivery failed");
+            }
+        });
+        join_all(deliveries).await;
+    }
+
+    /// Subscribe to progress events for a job.
+    ///
+    /// Events are only delivered while the job runs; callers should check
+    /// the job store for jobs that have already finished.
+    pub fn subscribe(&self, job_id: Uuid) -> broadcast::Receiver<JobEvent> {
+        self.event_sender(job_id).subscribe()
+    }
+
+    /// Drop the event channel for a job that has finished.
+    pub(crate) fn release(&self, job_id: Uuid) {
+        self.events.lock().unwrap().remove(&job_id);
+    }
+
+    fn event_sender(&self, job_id: Uuid) -> broadcast::Sender<JobEvent> {
+        self.events
+            .lock()
+            .unwrap()
+            .entry(job_id)
+            .or_insert_with(|| broadcast::channel(JOB_EVENT_CAPACITY).0)
+            .clone()
+    }
+
     /// Process a chunking job.
+    ///
+    /// The job fails if every one of its items fails to chunk; subscribers
+    /// then receive `JobEvent::Failed`.
     pub async fn process_job(
         &self,
         job_id: Uuid,
@@ -40,6 +164,12 @@ impl JobProcessor {
         job_store: Arc<RwLock<JobStore>>,
     ) {
         info!(job_id = %job_id, items = request.items.len(), "Starting job processing");
+        let mut events = JobEvents {
+            processor: self,
+            job_id,
+            sender: Some(self.event_sender(job_id)),
+        };
+        metrics::job_started();
 
         // Mark job as started
         {
@@ -50,10 +180,17 @@ impl JobProcessor {
         let mut total_chunks = 0;
         let mut processed = 0;
         let mut all_chunks = Vec::new();
+        let mut failed = 0;
+        let mut last_error = None;
 
         for item in &request.items {
-            match self.process_item(item) {
-                Ok(chunks) => {
+            match self.process_item(item, request.owner_id.as_deref()) {
+                Ok(mut chunks) => {
+                    if let Some(owner_id) = &request.owner_id {
+                        for chunk in &mut chunks {
+                            chunk.metadata.owner_id = Some(owner_id.clone());
+                        }
+                    }
                     total_chunks += chunks.len();
                     all_chunks.extend(chunks);
                 }
@@ -64,6 +201,8 @@ impl JobProcessor {
                         error = %e,
                         "Failed to process item, continuing with others"
                     );
+                    failed += 1;
+                    last_error = Some(e.to_string());
                 }
             }
 
@@ -74,6 +213,32 @@ impl JobProcessor {
                 let mut store = job_store.write().await;
                 store.update_job_progress(job_id, processed, total_chunks);
             }
+            events.send(JobEvent::Progress {
+                chunks_created: total_chunks,
+                items_processed: processed,
+                items_total: request.items.len(),
+            });
+        }
+
+        if failed > 0 && failed == request.items.len() {
+            let error = format!(
+                "All {} items failed to chunk: {}",
+                failed,
+                last_error.unwrap_or_default()
+            );
+            error!(job_id = %job_id, error = %error, "Job failed");
+            let status = {
+                let mut store = job_store.write().await;
+                store.fail_job(job_id, error.clone());
+                store.get_job_status(job_id)
+            };
+            events.finish(JobEvent::Failed { error });
+            metrics::job_finished();
+            if let Some(status) = status {
+                self.notify_webhooks(WebhookEvent::JobFailed, request.owner_id.as_deref(), &status)
+                    .await;
+            }
+            return;
         }
 
         info!(
@@ -86,10 +251,25 @@ impl JobProcessor {
         // Send chunks to downstream services in PARALLEL
         self.send_chunks_to_downstream_services(job_id, &all_chunks).await;
 
-        // Mark job as completed
-        {
+        // Mark job as completed, keeping its chunks for export
+        let status = {
             let mut store = job_store.write().await;
+            store.set_job_chunks(job_id, all_chunks);
             store.complete_job(job_id);
+            store.get_job_status(job_id)
+        };
+        events.finish(JobEvent::Complete);
+        metrics::job_finished();
+
+        let owner = request.owner_id.as_deref();
+        self.notify_webhooks(
+            WebhookEvent::ChunksBatchReady,
+            owner,
+            &serde_json::json!({ "job_id": job_id, "chunks_created": total_chunks }),
+        )
+        .await;
+        if let Some(status) = status {
+            self.notify_webhooks(WebhookEvent::JobCompleted, owner, &status).await;
         }
     }
 
@@ -188,11 +368,33 @@ impl JobProcessor {
         );
     }
 
-    /// Process a single source item.
-    fn process_item(&self, item: &SourceItem) -> anyhow::Result<Vec<Chunk>> {
+    /// Process a single source item for `owner_id`.
+    ///
+    /// Cached chunks are only reused for files chunked for the same owner.
+    fn process_item(&self, item: &SourceItem, owner_id: Option<&str>) -> anyhow::Result<Vec<Chunk>> {
         let chunker = self.router.get_chunker(item);
         let config = self.router.get_config(item);
 
+        let cache_key = match (&self.file_cache, item.extract_path()) {
+            (Some(cache), Some(path)) => Some((cache, path, item_content_hash(item))),
+            _ => None,
+        };
+        if let Some((cache, path, hash)) = &cache_key {
+            let cached = cache
+                .lock()
+                .map_err(|_| anyhow::anyhow!("File hash cache lock poisoned"))?
+                .get(owner_id, hash, &config)
+                .cloned();
+            if let Some(mut chunks) = cached {
+                debug!(item_id = %item.id, path, "File unchanged, using cached chunks");
+                reuse_cached_chunks(&mut chunks, item, path);
+                if config.use_deterministic_ids {
+                    assign_content_hash_ids(&mut chunks);
+                }
+                return Ok(chunks);
+            }
+        }
+
         info!(
             item_id = %item.id,
             chunker = chunker.name(),
@@ -200,11 +402,236 @@ impl JobProcessor {
             "Processing item"
         );
 
-        chunker.chunk(item, &config)
+        let started = Instant::now();
+        let result = chunker.chunk(item, &config);
+        metrics::record_item_processed(chunker.name(), result.is_ok(), started.elapsed());
+        let mut chunks = result.inspect_err(|_| {
+            self.router.metrics().record_error(chunker.name());
+        })?;
+        metrics::record_chunk_sizes(&chunks);
+        if config.use_deterministic_ids {
+            assign_content_hash_ids(&mut chunks);
+        }
+        if let Some(variant) = self.router.ab_variant(item) {
+            for chunk in &mut chunks {
+                chunk.metadata.ab_variant = Some(variant);
+            }
+        }
+        if let Some((cache, path, hash)) = cache_key {
+            cache
+                .lock()
+                .map_err(|_| anyhow::anyhow!("File hash cache lock poisoned"))?
+                .insert(owner_id, path, hash, &config, chunks.clone());
+        }
+        Ok(chunks)
     }
 
     /// Process a single item synchronously (for testing/simple use).
     pub fn process_item_sync(&self, item: &SourceItem) -> anyhow::Result<Vec<Chunk>> {
-        self.process_item(item)
+        self.process_owned_item_sync(item, None)
+    }
+
+    /// Process a single item for `owner_id` synchronously.
+    pub fn process_owned_item_sync(&self, item: &SourceItem, owner_id: Option<&str>) -> anyhow::Result<Vec<Chunk>> {
+        self.process_item(item, owner_id)
+    }
+}
+
+/// The event channel of a running job.
+///
+/// Finishing publishes the terminal event and releases the channel, so
+/// subscribers see it close; if processing stops early, e.g. on a panic,
+/// dropping it publishes `JobEvent::Failed` instead.
+struct JobEvents<'a> {
+    processor: &'a JobProcessor,
+    job_id: Uuid,
+    /// Taken once the job finishes
+    sender: Option<broadcast::Sender<JobEvent>>,
+}
+
+impl JobEvents<'_> {
+    fn send(&self, event: JobEvent) {
+        if let Some(sender) = &self.sender {
+            // No subscribers is not an error
+            let _ = sender.send(event);
+        }
+    }
+
+    fn finish(&mut self, event: JobEvent) {
+        self.send(event);
+        self.sender = None;
+        self.processor.release(self.job_id);
+    }
+}
+
+impl Drop for JobEvents<'_> {
+    fn drop(&mut self) {
+        if self.sender.is_some() {
+            self.finish(JobEvent::Failed {
+                error: "Job processing was interrupted".to_string(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChunkingConfig, SourceKind};
+
+    #[tokio::test]
+    async fn test_subscribe_receives_progress_then_complete() {
+        let router = Arc::new(ChunkingRouter::new(&ChunkingConfig::default()));
+        let processor = JobProcessor::new(router, None, None);
+        let store = Arc::new(RwLock::new(JobStore::new()));
+        let job_id = store.write().await.create_job(2);
+
+        let items = (0..2)
+            .map(|i| SourceItem {
+                id: Uuid::new_v4(),
+                source_id: Uuid::nil(),
+                source_kind: SourceKind::Document,
+                content_type: "text/plain".to_string(),
+                content: format!("Document number {}.", i),
+                metadata: serde_json::Value::Null,
+                created_at: None,
+                priority: SourceItem::DEFAULT_PRIORITY,
+            })
+            .collect();
+        let request = StartChunkJobRequest {
+            source_id: Uuid::nil(),
+            source_kind: SourceKind::Document,
+            items,
+            owner_id: Some("user-1".to_string()),
+        };
+
+        let mut rx = processor.subscribe(job_id);
+        processor.process_job(job_id, request, Arc::clone(&store)).await;
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[1], JobEvent::Progress { items_processed: 2, items_total: 2, .. }));
+        assert_eq!(events[2], JobEvent::Complete);
+
+        let store = store.read().await;
+        let chunks = &store.get_job(job_id).unwrap().chunks;
+        assert!(!chunks.is_empty());
+        assert!(chunks.iter().all(|c| c.metadata.owner_id.as_deref() == Some("user-1")));
+    }
+
+    /// A plugin whose chunking always fails.
+    struct FailingChunker;
+
+    impl crate::chunkers::Chunker for FailingChunker {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn chunk(&self, _item: &SourceItem, _config: &crate::types::ChunkConfig) -> anyhow::Result<Vec<Chunk>> {
+            Err(anyhow::anyhow!("cannot chunk"))
+        }
+
+        fn description(&self) -> &'static str {
+            "Always fails"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_publishes_failed_and_closes_channel() {
+        let router = Arc::new(ChunkingRouter::new(&ChunkingConfig::default()));
+        router
+            .register_chunker("failing", Arc::new(FailingChunker), vec!["text/x-failing"], vec![])
+            .unwrap();
+        let processor = JobProcessor::new(router, None, None);
+        let store = Arc::new(RwLock::new(JobStore::new()));
+        let job_id = store.write().await.create_job(1);
+        let request = StartChunkJobRequest {
+            source_id: Uuid::nil(),
+            source_kind: SourceKind::Other,
+            items: vec![SourceItem {
+                id: Uuid::new_v4(),
+                source_id: Uuid::nil(),
+                source_kind: SourceKind::Other,
+                content_type: "text/x-failing".to_string(),
+                content: "Unchunkable".to_string(),
+                metadata: serde_json::Value::Null,
+                created_at: None,
+                priority: SourceItem::DEFAULT_PRIORITY,
+            }],
+            owner_id: None,
+        };
+
+        let mut rx = processor.subscribe(job_id);
+        processor.process_job(job_id, request, Arc::clone(&store)).await;
+
+        assert!(matches!(rx.try_recv(), Ok(JobEvent::Progress { .. })));
+        match rx.try_recv() {
+            Ok(JobEvent::Failed { error }) => assert!(error.contains("cannot chunk"), "{}", error),
+            other => panic!("expected a failed event, got {:?}", other),
+        }
+        // The sender is dropped once the job finishes
+        assert_eq!(rx.try_recv(), Err(broadcast::error::TryRecvError::Closed));
+        assert_eq!(
+            store.read().await.get_job(job_id).unwrap().status,
+            crate::types::ChunkJobStatus::Failed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_completed_job_notifies_signed_webhook() {
+        use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+
+        use crate::output::{sign_payload, WebhookRegistration, SIGNATURE_HEADER};
+
+        // (signature header, body) of each delivery
+        type Deliveries = Arc<Mutex<Vec<(Option<String>, Bytes)>>>;
+        let received: Deliveries = Arc::default();
+        let sink = Arc::clone(&received);
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                let signature = headers
+                    .get(SIGNATURE_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                sink.lock().unwrap().push((signature, body));
+            }),
+        );
+        let url = crate::test_support::serve_mock(app).await;
+
+        let mut webhooks = WebhookStore::new();
+        webhooks
+            .register(WebhookRegistration {
+                url: format!("{}/hook", url),
+                events: vec![WebhookEvent::JobCompleted],
+                secret: Some("s3cret".to_string()),
+            })
+            .unwrap();
+
+        let router = Arc::new(ChunkingRouter::new(&ChunkingConfig::default()));
+        let processor = JobProcessor::new(router, None, None)
+            .with_webhooks(Arc::new(RwLock::new(webhooks)))
+            .with_private_webhook_urls(true);
+        let store = Arc::new(RwLock::new(JobStore::new()));
+        let job_id = store.write().await.create_job(0);
+        let request = StartChunkJobRequest {
+            source_id: Uuid::nil(),
+            source_kind: SourceKind::Document,
+            items: vec![],
+            owner_id: None,
+        };
+        processor.process_job(job_id, request, store).await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (signature, body) = &received[0];
+        assert_eq!(signature.as_deref(), Some(format!("sha256={}", sign_payload("s3cret", body)).as_str()));
+
+        let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(body["event"], "job_completed");
+        assert_eq!(body["payload"]["job_id"], job_id.to_string());
     }
 }
@@ -1,15 +1,42 @@
 //! Job store for tracking chunking job status.
+//!
+//! Jobs are kept in memory. With a SQLite database configured, every
+//! change is also queued for a background writer that saves it to a `jobs`
+//! table, so job status survives a restart without callers holding the
+//! store's lock across database writes. Chunks are not persisted; exporting
+//! a job restored after a restart returns 410.
 
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
 
-use chrono::{DateTime, Utc};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::Row;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::types::{ChunkJobStatus, ChunkJobStatusResponse};
+use crate::types::{Chunk, ChunkJobStatus, ChunkJobStatusResponse};
 
-/// In-memory job store for tracking chunking jobs.
+/// Error recorded on jobs that were running when the service stopped.
+const RESTART_ERROR: &str = "service restart";
+
+/// Job store for tracking chunking jobs, optionally backed by SQLite.
 pub struct JobStore {
     jobs: HashMap<Uuid, JobRecord>,
+    db: Option<SqlitePool>,
+    /// Queue of the background database writer, if persisted
+    writes: Option<mpsc::UnboundedSender<DbOp>>,
+}
+
+/// A change for the background writer to apply, in queue order.
+enum DbOp {
+    Write(JobRow),
+    DeleteExpired(DateTime<Utc>),
+    Flush(oneshot::Sender<()>),
 }
 
 /// Internal record for tracking a job.
@@ -24,6 +51,14 @@ pub struct JobRecord {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// Subject of the token the job was submitted with, if any
+    pub owner_id: Option<String>,
+    /// Chunks produced by the job, kept for export until cleanup
+    pub chunks: Vec<Chunk>,
+    /// Priority of each of the job's items, by item id
+    pub item_priorities: HashMap<Uuid, u8>,
+    /// Loaded from the database after a restart, so its chunks are gone
+    pub restored: bool,
 }
 
 impl JobRecord {
@@ -39,6 +74,10 @@ impl JobRecord {
             started_at: None,
             completed_at: None,
             created_at: Utc::now(),
+            owner_id: None,
+            chunks: Vec::new(),
+            item_priorities: HashMap::new(),
+            restored: false,
         }
     }
 
@@ -67,6 +106,17 @@ impl JobRecord {
         self.completed_at = Some(Utc::now());
     }
 
+    /// Whether a caller may read or modify the job.
+    ///
+    /// Jobs submitted without a token are visible to everyone; owned jobs
+    /// only to a caller with the same subject.
+    pub fn is_visible_to(&self, subject: Option<&str>) -> bool {
+        match &self.owner_id {
+            Some(owner) => subject == Some(owner.as_str()),
+            None => true,
+        }
+    }
+
     /// Convert to response type.
     pub fn to_response(&self) -> ChunkJobStatusResponse {
         ChunkJobStatusResponse {
@@ -83,18 +133,132 @@ impl JobRecord {
 }
 
 impl JobStore {
-    /// Create a new job store.
+    /// Create a new in-memory job store.
     pub fn new() -> Self {
         Self {
             jobs: HashMap::new(),
+            db: None,
+            writes: None,
+        }
+    }
+
+    /// Create a job store persisted to SQLite, e.g. `sqlite://jobs.db`.
+    ///
+    /// The database and `jobs` table are created if missing, and existing
+    /// jobs are loaded into memory.
+    pub async fn with_sqlite(db_url: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(db_url)
+            .with_context(|| format!("Invalid SQLite URL {}", db_url))?
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options)
+            .await
+            .context("Failed to open job database")?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                completed_at TEXT,
+                result TEXT,
+                error TEXT
+            )",
+        )
+        .execute(&pool)
+        .await
+        .context("Failed to create jobs table")?;
+
+        let rows = sqlx::query("SELECT id, status, created_at, completed_at, result, error FROM jobs")
+            .fetch_all(&pool)
+            .await
+            .context("Failed to load jobs")?;
+        let jobs = rows
+            .iter()
+            .map(|row| row_to_record(row).map(|record| (record.job_id, record)))
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let (writes, queue) = mpsc::unbounded_channel();
+        tokio::spawn(run_writer(pool.clone(), queue));
+
+        info!(url = %db_url, jobs = jobs.len(), "Job store opened");
+        Ok(Self {
+            jobs,
+            db: Some(pool),
+            writes: Some(writes),
+        })
+    }
+
+    /// Queue a job's current state for the database writer.
+    fn persist(&self, record: &JobRecord) {
+        let Some(writes) = &self.writes else {
+            return;
+        };
+        match JobRow::from_record(record) {
+            Ok(row) => {
+                let _ = writes.send(DbOp::Write(row));
+            }
+            Err(e) => warn!(job_id = %record.job_id, error = %e, "Failed to persist job"),
+        }
+    }
+
+    /// Wait until every queued database write has been applied.
+    pub async fn flush(&self) {
+        let Some(writes) = &self.writes else {
+            return;
+        };
+        let (done, applied) = oneshot::channel();
+        if writes.send(DbOp::Flush(done)).is_ok() {
+            let _ = applied.await;
         }
     }
 
+    /// Insert or replace a job and queue it for the database.
+    pub fn save(&mut self, record: JobRecord) {
+        self.persist(&record);
+        self.jobs.insert(record.job_id, record);
+    }
+
+    /// Modify a job in place and queue it for the database.
+    ///
+    /// Returns `false` if the job does not exist.
+    pub fn update(&mut self, job_id: Uuid, f: impl FnOnce(&mut JobRecord)) -> bool {
+        let Some(job) = self.jobs.get_mut(&job_id) else {
+            return false;
+        };
+        f(job);
+        let job = &self.jobs[&job_id];
+        self.persist(job);
+        true
+    }
+
+    /// Get a job, falling back to the database for jobs not in memory.
+    pub async fn get(&self, job_id: Uuid) -> Result<Option<JobRecord>> {
+        if let Some(job) = self.jobs.get(&job_id) {
+            return Ok(Some(job.clone()));
+        }
+        let Some(db) = &self.db else {
+            return Ok(None);
+        };
+        let row = sqlx::query("SELECT id, status, created_at, completed_at, result, error FROM jobs WHERE id = ?")
+            .bind(job_id.to_string())
+            .fetch_optional(db)
+            .await
+            .context("Failed to read job")?;
+        row.as_ref().map(row_to_record).transpose()
+    }
+
     /// Create a new job and return its ID.
     pub fn create_job(&mut self, total_items: usize) -> Uuid {
+        self.create_owned_job(total_items, None)
+    }
+
+    /// Create a new job owned by `owner_id` and return its ID.
+    pub fn create_owned_job(&mut self, total_items: usize, owner_id: Option<String>) -> Uuid {
         let job_id = Uuid::new_v4();
-        let record = JobRecord::new(job_id, total_items);
-        self.jobs.insert(job_id, record);
+        let mut record = JobRecord::new(job_id, total_items);
+        record.owner_id = owner_id;
+        self.save(record);
         job_id
     }
 
@@ -104,48 +268,80 @@ impl JobStore {
     }
 
     /// Get a mutable reference to a job.
+    ///
+    /// Changes made through it are not persisted; use `update` instead.
     pub fn get_job_mut(&mut self, job_id: Uuid) -> Option<&mut JobRecord> {
         self.jobs.get_mut(&job_id)
     }
 
     /// Start a job.
     pub fn start_job(&mut self, job_id: Uuid) -> bool {
-        if let Some(job) = self.jobs.get_mut(&job_id) {
-            job.start();
-            true
-        } else {
-            false
-        }
+        self.update(job_id, JobRecord::start)
     }
 
     /// Update job progress.
     pub fn update_job_progress(&mut self, job_id: Uuid, processed: usize, chunks: usize) -> bool {
+        self.update(job_id, |job| job.update_progress(processed, chunks))
+    }
+
+    /// Store the chunks produced by a job.
+    pub fn set_job_chunks(&mut self, job_id: Uuid, chunks: Vec<Chunk>) -> bool {
         if let Some(job) = self.jobs.get_mut(&job_id) {
-            job.update_progress(processed, chunks);
+            job.chunks = chunks;
             true
         } else {
             false
         }
     }
 
-    /// Complete a job.
-    pub fn complete_job(&mut self, job_id: Uuid) -> bool {
+    /// Record the priorities of a job's items.
+    pub fn set_job_priorities(&mut self, job_id: Uuid, priorities: HashMap<Uuid, u8>) -> bool {
         if let Some(job) = self.jobs.get_mut(&job_id) {
-            job.complete();
+            job.item_priorities = priorities;
             true
         } else {
             false
         }
     }
 
+    /// Get the priority of each item in a job, empty if the job is unknown.
+    pub fn get_job_priority_map(&self, job_id: Uuid) -> HashMap<Uuid, u8> {
+        self.jobs
+            .get(&job_id)
+            .map(|job| job.item_priorities.clone())
+            .unwrap_or_default()
+    }
+
+    /// Complete a job.
+    pub fn complete_job(&mut self, job_id: Uuid) -> bool {
+        self.update(job_id, JobRecord::complete)
+    }
+
     /// Fail a job.
     pub fn fail_job(&mut self, job_id: Uuid, error: String) -> bool {
-        if let Some(job) = self.jobs.get_mut(&job_id) {
-            job.fail(error);
-            true
-        } else {
-            false
+        self.update(job_id, |job| job.fail(error))
+    }
+
+    /// Mark jobs that were pending or running when the service stopped as
+    /// failed; their requests were lost with the process.
+    ///
+    /// Call once at startup, after `with_sqlite`. Returns the failed jobs.
+    pub fn restore_in_progress_jobs(&mut self) -> Vec<Uuid> {
+        let mut interrupted: Vec<Uuid> = self
+            .jobs
+            .values()
+            .filter(|job| matches!(job.status, ChunkJobStatus::Pending | ChunkJobStatus::Running))
+            .map(|job| job.job_id)
+            .collect();
+        interrupted.sort();
+
+        for &job_id in &interrupted {
+            self.fail_job(job_id, RESTART_ERROR.to_string());
+        }
+        if !interrupted.is_empty() {
+            warn!(jobs = interrupted.len(), "Marked jobs interrupted by restart as failed");
         }
+        interrupted
     }
 
     /// Get job status as response.
@@ -156,14 +352,21 @@ impl JobStore {
     /// Clean up old completed jobs (older than 1 hour).
     pub fn cleanup_old_jobs(&mut self) {
         let cutoff = Utc::now() - chrono::Duration::hours(1);
-        self.jobs.retain(|_, job| {
-            match job.status {
-                ChunkJobStatus::Completed | ChunkJobStatus::Failed => {
-                    job.completed_at.map_or(true, |t| t > cutoff)
-                }
-                _ => true,
-            }
-        });
+        self.jobs.retain(|_, job| !is_expired(job, cutoff));
+    }
+
+    /// Delete completed and failed jobs that finished more than `max_age`
+    /// ago, from memory and (queued) the database. Returns how many were
+    /// deleted.
+    pub fn cleanup_expired(&mut self, max_age: Duration) -> Result<usize> {
+        let cutoff = Utc::now() - chrono::Duration::from_std(max_age).map_err(|e| anyhow!("Invalid max age: {}", e))?;
+
+        let before = self.jobs.len();
+        self.jobs.retain(|_, job| !is_expired(job, cutoff));
+        if let Some(writes) = &self.writes {
+            let _ = writes.send(DbOp::DeleteExpired(cutoff));
+        }
+        Ok(before - self.jobs.len())
     }
 
     /// Get count of jobs by status.
@@ -176,8 +379,266 @@ impl JobStore {
     }
 }
 
+/// Check if a finished job completed at or before `cutoff`.
+fn is_expired(job: &JobRecord, cutoff: DateTime<Utc>) -> bool {
+    match job.status {
+        ChunkJobStatus::Completed | ChunkJobStatus::Failed => job.completed_at.is_some_and(|t| t <= cutoff),
+        _ => false,
+    }
+}
+
+/// Progress counters stored in the `result` column.
+#[derive(Serialize, Deserialize)]
+struct StoredResult {
+    total_items: usize,
+    processed_items: usize,
+    chunks_created: usize,
+    started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    owner_id: Option<String>,
+}
+
+fn status_to_text(status: ChunkJobStatus) -> String {
+    serde_json::to_value(status)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+/// Format a timestamp for the database: RFC 3339 in UTC with nanosecond
+/// precision, e.g. `2026-01-02T03:04:05.000000000Z`. Every timestamp has
+/// the same width, so SQLite can compare them as strings.
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+fn parse_time(text: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(text)?.with_timezone(&Utc))
+}
+
+/// Column values of a job row, captured when the change is queued.
+struct JobRow {
+    id: String,
+    status: String,
+    created_at: String,
+    completed_at: Option<String>,
+    result: String,
+    error: Option<String>,
+}
+
+impl JobRow {
+    fn from_record(record: &JobRecord) -> Result<Self> {
+        let result = StoredResult {
+            total_items: record.total_items,
+            processed_items: record.processed_items,
+            chunks_created: record.chunks_created,
+            started_at: record.started_at,
+            owner_id: record.owner_id.clone(),
+        };
+        Ok(Self {
+            id: record.job_id.to_string(),
+            status: status_to_text(record.status),
+            created_at: format_time(record.created_at),
+            completed_at: record.completed_at.map(format_time),
+            result: serde_json::to_string(&result)?,
+            error: record.error.clone(),
+        })
+    }
+}
+
+/// Apply queued changes to the database in order, until the store is
+/// dropped.
+async fn run_writer(db: SqlitePool, mut queue: mpsc::UnboundedReceiver<DbOp>) {
+    while let Some(op) = queue.recv().await {
+        let result = match op {
+            DbOp::Write(row) => write_row(&db, &row).await,
+            DbOp::DeleteExpired(cutoff) => delete_expired(&db, cutoff).await,
+            DbOp::Flush(done) => {
+                let _ = done.send(());
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to persist job");
+        }
+    }
+}
+
+/// Insert or replace a job row.
+async fn write_row(db: &SqlitePool, row: &JobRow) -> Result<()> {
+    sqlx::query("INSERT OR REPLACE INTO jobs (id, status, created_at, completed_at, result, error) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&row.id)
+        .bind(&row.status)
+        .bind(&row.created_at)
+        .bind(&row.completed_at)
+        .bind(&row.result)
+        .bind(&row.error)
+        .execute(db)
+        .await
+        .with_context(|| format!("Failed to write job {}", row.id))?;
+    Ok(())
+}
+
+/// Delete finished jobs that completed at or before `cutoff`, as
+/// `is_expired` does in memory.
+async fn delete_expired(db: &SqlitePool, cutoff: DateTime<Utc>) -> Result<()> {
+    sqlx::query("DELETE FROM jobs WHERE status IN (?, ?) AND completed_at <= ?")
+        .bind(status_to_text(ChunkJobStatus::Completed))
+        .bind(status_to_text(ChunkJobStatus::Failed))
+        .bind(format_time(cutoff))
+        .execute(db)
+        .await
+        .context("Failed to delete expired jobs")?;
+    Ok(())
+}
+
+/// Rebuild a job record from a row. Chunks are not persisted, so the
+/// record is marked `restored`.
+fn row_to_record(row: &SqliteRow) -> Result<JobRecord> {
+    let id: String = row.try_get("id")?;
+    let status: String = row.try_get("status")?;
+    let created_at: String = row.try_get("created_at")?;
+    let completed_at: Option<String> = row.try_get("completed_at")?;
+    let result: Option<String> = row.try_get("result")?;
+
+    let result: StoredResult = match result {
+        Some(json) => serde_json::from_str(&json)?,
+        None => StoredResult {
+            total_items: 0,
+            processed_items: 0,
+            chunks_created: 0,
+            started_at: None,
+            owner_id: None,
+        },
+    };
+
+    Ok(JobRecord {
+        job_id: Uuid::parse_str(&id)?,
+        status: serde_json::from_value(serde_json::Value::String(status))?,
+        total_items: result.total_items,
+        processed_items: result.processed_items,
+        chunks_created: result.chunks_created,
+        error: row.try_get("error")?,
+        started_at: result.started_at,
+        completed_at: completed_at.as_deref().map(parse_time).transpose()?,
+        created_at: parse_time(&created_at)?,
+        owner_id: result.owner_id,
+        chunks: Vec::new(),
+        item_priorities: HashMap::new(),
+        restored: true,
+    })
+}
+
 impl Default for JobStore {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db_url(dir: &tempfile::TempDir) -> String {
+        format!("sqlite://{}", dir.path().join("jobs.db").display())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_jobs_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (running, pending, completed) = {
+            let mut store = JobStore::with_sqlite(&db_url(&dir)).await.unwrap();
+            let running = store.create_job(3);
+            store.start_job(running);
+            store.update_job_progress(running, 1, 4);
+            let pending = store.create_job(2);
+            let completed = store.create_job(1);
+            store.complete_job(completed);
+            store.flush().await;
+            (running, pending, completed)
+        };
+
+        let mut store = JobStore::with_sqlite(&db_url(&dir)).await.unwrap();
+        assert_eq!(store.get_job(running).unwrap().chunks_created, 4);
+        let mut interrupted = vec![running, pending];
+        interrupted.sort();
+        assert_eq!(store.restore_in_progress_jobs(), interrupted);
+
+        for job_id in [running, pending] {
+            let job = store.get(job_id).await.unwrap().unwrap();
+            assert_eq!(job.status, ChunkJobStatus::Failed);
+            assert_eq!(job.error.as_deref(), Some(RESTART_ERROR));
+        }
+        let completed = store.get_job(completed).unwrap();
+        assert_eq!(completed.status, ChunkJobStatus::Completed);
+        // Chunks did not survive the restart
+        assert!(completed.restored);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = JobStore::with_sqlite(&db_url(&dir)).await.unwrap();
+        let old = store.create_job(1);
+        store.update(old, |job| {
+            job.complete();
+            job.completed_at = Some(Utc::now() - chrono::Duration::hours(2));
+        });
+        let recent = store.create_job(1);
+        store.complete_job(recent);
+        let pending = store.create_job(1);
+
+        assert_eq!(store.cleanup_expired(Duration::from_secs(3600)).unwrap(), 1);
+        store.flush().await;
+        assert!(store.get(old).await.unwrap().is_none());
+        assert!(store.get(recent).await.unwrap().is_some());
+        assert!(store.get(pending).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired_includes_cutoff() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = JobStore::with_sqlite(&db_url(&dir)).await.unwrap();
+        let cutoff = Utc::now() - chrono::Duration::hours(1);
+        let at_cutoff = store.create_job(1);
+        store.update(at_cutoff, |job| {
+            job.complete();
+            job.completed_at = Some(cutoff);
+        });
+        let after_cutoff = store.create_job(1);
+        store.update(after_cutoff, |job| {
+            job.complete();
+            job.completed_at = Some(cutoff + chrono::Duration::nanoseconds(1));
+        });
+        store.flush().await;
+
+        let db = store.db.clone().unwrap();
+        delete_expired(&db, cutoff).await.unwrap();
+        assert!(is_expired(&store.jobs[&at_cutoff], cutoff));
+        assert!(!is_expired(&store.jobs[&after_cutoff], cutoff));
+
+        // Read back from the database
+        store.jobs.clear();
+        assert!(store.get(at_cutoff).await.unwrap().is_none());
+        assert!(store.get(after_cutoff).await.unwrap().is_some());
+    }
+
+    #[test]
+    fn test_format_time_is_fixed_width() {
+        let whole = DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z").unwrap().with_timezone(&Utc);
+        let fraction = whole + chrono::Duration::milliseconds(5);
+        assert_eq!(format_time(whole), "2026-01-02T03:04:05.000000000Z");
+        assert_eq!(format_time(whole).len(), format_time(fraction).len());
+        assert!(format_time(whole) < format_time(fraction));
+        assert_eq!(parse_time(&format_time(fraction)).unwrap(), fraction);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_by_default() {
+        let mut store = JobStore::new();
+        let job_id = store.create_job(1);
+        assert!(store.fail_job(job_id, "boom".to_string()));
+        assert_eq!(store.cleanup_expired(Duration::from_secs(3600)).unwrap(), 0);
+        assert!(!store.start_job(Uuid::new_v4()));
+    }
+}
@@ -11,17 +11,27 @@ pub mod api;
 pub mod batch;
 pub mod chunkers;
 pub mod enrichment;
+pub mod file_processor;
 pub mod jobs;
 pub mod output;
 pub mod router;
+pub mod telemetry;
+pub mod messaging;
 pub mod types;
+#[cfg(any(test, feature = "test-support"))]
+#[doc(hidden)]
+pub mod test_support;
 
 pub use types::{Chunk, ChunkMetadata, SourceItem, SourceKind};
 pub use chunkers::{Chunker, AgenticChunker};
-pub use chunkers::repo_chunker::{RepositoryContext, Symbol, SymbolType, extract_symbols};
+pub use chunkers::repo_chunker::{RepositoryContext, Symbol, SymbolType, SymbolVisibility, extract_symbols};
 pub use router::ChunkingRouter;
-pub use batch::{BatchProcessor, BatchConfig, BatchResult};
+pub use batch::{
+    BatchProcessor, BatchConfig, BatchCheckpoint, BatchProgress, BatchResult, DeliveryStalled, DryRunReport, FileHashCache,
+    PostProcessor, PreProcessor, RedactionProcessor, RetryableError,
+};
 pub use enrichment::{ContextBuilder, ChunkContext, EnrichedChunk};
+pub use file_processor::{FileFilter, FileProcessor, FilterConfig};
 
 /// Re-export commonly used types
 pub mod prelude {
@@ -42,5 +52,11 @@ pub const DEFAULT_CHUNK_OVERLAP: usize = 50;
 /// Default minimum characters per sentence
 pub const DEFAULT_MIN_CHARS_PER_SENTENCE: usize = 12;
 
+/// Default model whose tokenizer counts chunk tokens
+pub const DEFAULT_TOKENIZER_MODEL: &str = "gpt-4";
+
 /// Maximum content size for single-pass processing (10MB)
 pub const DEFAULT_MAX_CONTENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// Maximum content size accepted by synchronous chunking (100KB)
+pub const DEFAULT_MAX_SYNC_CONTENT_SIZE: usize = 100 * 1024;
@@ -4,64 +4,141 @@
 
 use anyhow::Result;
 use axum::{
-    routing::{get, post, put},
-    Router,
+    error_handling::HandleErrorLayer,
+    http::StatusCode,
+    middleware,
+    routing::{delete, get, post, put},
+    BoxError, Router,
 };
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
-use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing::{info, warn};
 
+use chunker::api::auth::{self, JwtConfig};
 use chunker::api::handlers::{self, AppState};
+use chunker::api::openapi;
+use chunker::chunkers::init_token_counter;
 use chunker::jobs::JobStore;
-use chunker::router::ChunkingRouter;
+use chunker::telemetry;
 use chunker::types::ChunkingConfig;
 
+/// How often finished jobs past `job_retention_secs` are deleted.
+const JOB_CLEANUP_INTERVAL: Duration = Duration::from_secs(300);
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "chunker=info,tower_http=debug".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     // Load configuration
     dotenvy::dotenv().ok();
-    let config = ChunkingConfig::from_env();
+
+    // Initialize tracing, exporting spans when an OTLP endpoint is set
+    telemetry::init_tracing()?;
+    telemetry::metrics::install_recorder()?;
+
+    // Read CHUNKER_CONFIG_FILE if set, with environment variables taking precedence
+    let config = match std::env::var("CHUNKER_CONFIG_FILE") {
+        Ok(path) => ChunkingConfig::from_toml_with_env_override(Path::new(&path))?,
+        Err(_) => ChunkingConfig::from_env(),
+    };
+    match config.validate() {
+        Ok(warnings) => {
+            for warning in warnings {
+                warn!("Configuration: {}", warning);
+            }
+        }
+        Err(errors) => anyhow::bail!("Invalid configuration: {}", errors.join("; ")),
+    }
+    init_token_counter(&config.tokenizer_model)?;
 
     info!("Starting Chunker Service v{}", env!("CARGO_PKG_VERSION"));
     info!("Default chunk size: {} tokens", config.default_chunk_size);
 
     // Initialize components
-    let router = ChunkingRouter::new(&config);
-    let job_store = JobStore::new();
+    let enable_swagger_ui = config.enable_swagger_ui;
+    let jwt = JwtConfig::from_env();
+    jwt.check_secret()?;
+    if jwt.required {
+        info!("JWT authentication required");
+    }
+    let mut job_store = match &config.job_store_url {
+        Some(url) => JobStore::with_sqlite(url).await?,
+        None => JobStore::new(),
+    };
+    job_store.restore_in_progress_jobs();
+    let job_retention = Duration::from_secs(config.job_retention_secs);
+    let state = Arc::new(AppState::new(config).with_jwt(jwt).with_job_store(job_store));
 
-    let state = Arc::new(AppState {
-        router,
-        job_store: RwLock::new(job_store),
-        config,
+    // Delete finished jobs once they are past the retention period
+    let job_store = Arc::clone(&state.job_store);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(JOB_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match job_store.write().await.cleanup_expired(job_retention) {
+                Ok(0) => {}
+                Ok(deleted) => info!(deleted, "Deleted expired jobs"),
+                Err(e) => warn!(error = %e, "Failed to delete expired jobs"),
+            }
+        }
     });
 
-    // Build HTTP routes
-    let app = Router::new()
-        // Health check
-        .route("/health", get(handlers::health_check))
+    // Build HTTP routes; everything except health and API docs is authenticated
+    let protected = Router::new()
+        .route("/metrics", get(handlers::metrics))
+        // Lists internal embedding URLs, so unlike /health it is authenticated
+        .route("/health/embedding", get(handlers::embedding_health))
         // Chunking jobs
         .route("/chunk/jobs", post(handlers::start_chunk_job))
+        .route("/chunk/jobs/dry-run", post(handlers::dry_run_chunk_job))
         .route("/chunk/jobs/:job_id", get(handlers::get_job_status))
+        .route("/chunk/jobs/:job_id/stream", get(handlers::stream_job))
+        .route("/chunk/jobs/:job_id/export", post(handlers::export_job))
+        .route("/chunk/files/:hash", get(handlers::get_processed_file))
+        .route("/chunk/analyze", get(handlers::analyze_languages))
+        // Webhooks
+        .route("/chunk/webhooks", post(handlers::register_webhook).get(handlers::list_webhooks))
+        .route("/chunk/webhooks/:id", delete(handlers::delete_webhook))
+        // Synchronous chunking, rate limited to 100 req/s
+        .route(
+            "/chunk/sync",
+            post(handlers::sync_chunk).layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(|err: BoxError| async move {
+                        (StatusCode::SERVICE_UNAVAILABLE, format!("Sync chunking unavailable: {}", err))
+                    }))
+                    .buffer(1024)
+                    .rate_limit(100, Duration::from_secs(1)),
+            ),
+        )
         // Profiles
         .route("/chunk/profiles", get(handlers::list_profiles))
         .route("/chunk/profiles/active", get(handlers::get_active_profile))
         .route("/chunk/profiles/active", put(handlers::set_active_profile))
+        .route("/chunk/config", get(handlers::get_config));
+    #[cfg(feature = "token-cache")]
+    let protected = protected.route("/metrics/token-cache", get(handlers::token_cache_metrics));
+    let protected = protected.route_layer(middleware::from_fn_with_state(Arc::clone(&state), auth::require_jwt));
+
+    let mut app = Router::new()
+        // Health check
+        .route("/health", get(handlers::health_check))
+        .route("/openapi.json", get(openapi::openapi_json))
+        .merge(protected)
         // State
-        .with_state(state)
+        .with_state(state);
+
+    if enable_swagger_ui {
+        app = app.merge(openapi::swagger_ui());
+    }
+
+    let app = app
         // Middleware
-        .layer(TraceLayer::new_for_http())
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::http_request_span))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -78,8 +155,23 @@ async fn main() -> Result<()> {
     
     info!("Listening on http://{}", addr);
 
+    // Cancelled on Ctrl+C; in-flight requests finish before shutdown
+    let shutdown = CancellationToken::new();
+    let signal_token = shutdown.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("Shutdown signal received");
+            signal_token.cancel();
+        }
+    });
+
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
+
+    info!("Chunker Service stopped");
+    telemetry::shutdown_tracing();
 
     Ok(())
 }
@@ -3,11 +3,11 @@
 //! Prevents cascading failures by stopping requests to failing services.
 //! Uses exponential backoff with jitter for recovery attempts.
 
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::RwLock;
 use std::time::{Duration, Instant};
 
-use tracing::{info, warn};
+use tracing::warn;
 
 /// Circuit breaker states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,16 +23,20 @@ pub enum CircuitState {
 /// Circuit breaker configuration
 #[derive(Debug, Clone)]
 pub struct CircuitConfig {
-    /// Number of failures before opening circuit
+    /// Number of consecutive failures before opening circuit
     pub failure_threshold: u32,
     /// Base recovery timeout in seconds
     pub recovery_timeout_secs: u64,
-    /// Calls allowed in half-open state
+    /// Calls allowed in half-open state, concurrently and in total; this
+    /// many must succeed to close the circuit
     pub half_open_max_calls: u32,
     /// Maximum backoff time in seconds
     pub max_backoff_secs: u64,
     /// Use exponential backoff
     pub exponential_backoff: bool,
+    /// Base recovery timeout with sub-second precision, overriding
+    /// `recovery_timeout_secs` when set
+    pub recovery_timeout_override: Option<Duration>,
 }
 
 impl Default for CircuitConfig {
@@ -43,17 +47,34 @@ impl Default for CircuitConfig {
             half_open_max_calls: 3,
             max_backoff_secs: 300,
             exponential_backoff: true,
+            recovery_timeout_override: None,
         }
     }
 }
 
+impl CircuitConfig {
+    /// Base recovery timeout.
+    pub fn recovery_timeout(&self) -> Duration {
+        self.recovery_timeout_override
+            .unwrap_or_else(|| Duration::from_secs(self.recovery_timeout_secs))
+    }
+
+    /// Maximum backoff time.
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_secs(self.max_backoff_secs)
+    }
+}
+
 /// Thread-safe circuit breaker
 pub struct CircuitBreaker {
     config: CircuitConfig,
     state: RwLock<CircuitState>,
     failures: AtomicU32,
     successes: AtomicU32,
+    /// Probes admitted since the circuit went half-open
     half_open_calls: AtomicU32,
+    /// Probes that succeeded since the circuit went half-open
+    half_open_successes: AtomicU32,
     retry_count: AtomicU32,
     last_failure_time: RwLock<Option<Instant>>,
     next_retry_time: RwLock<Option<Instant>>,
@@ -68,6 +89,7 @@ impl CircuitBreaker {
             failures: AtomicU32::new(0),
             successes: AtomicU32::new(0),
             half_open_calls: AtomicU32::new(0),
+            half_open_successes: AtomicU32::new(0),
             retry_count: AtomicU32::new(0),
             last_failure_time: RwLock::new(None),
             next_retry_time: RwLock::new(None),
@@ -75,37 +97,61 @@ impl CircuitBreaker {
     }
     
     /// Check if request should be allowed
+    ///
+    /// In the half-open state at most `half_open_max_calls` probes are
+    /// admitted until the circuit closes or opens again; an admitted
+    /// request must be followed by `record_success` or `record_failure`.
     pub fn allow_request(&self) -> bool {
         let state = *self.state.read().unwrap();
         
         match state {
             CircuitState::Closed => true,
             CircuitState::Open => {
-                let next_retry = self.next_retry_time.read().unwrap();
-                if let Some(time) = *next_retry {
-                    if Instant::now() >= time {
-                        self.transition_to(CircuitState::HalfOpen);
-                        return true;
-                    }
+                let retry_due = self.next_retry_time.read().unwrap().is_some_and(|time| Instant::now() >= time);
+                if retry_due {
+                    // Only the first caller past the deadline moves to half-open
+                    self.transition_from(CircuitState::Open, CircuitState::HalfOpen);
+                    return self.admit_probe();
                 }
                 false
             }
-            CircuitState::HalfOpen => {
-                self.half_open_calls.load(Ordering::SeqCst) < self.config.half_open_max_calls
-            }
+            CircuitState::HalfOpen => self.admit_probe(),
         }
     }
+
+    /// Whether the circuit is open and not yet due for a trial request.
+    ///
+    /// Unlike `allow_request` this admits nothing, so it can be used to
+    /// check the circuit without taking a half-open probe.
+    pub fn is_open(&self) -> bool {
+        *self.state.read().unwrap() == CircuitState::Open
+            && !self.next_retry_time.read().unwrap().is_some_and(|time| Instant::now() >= time)
+    }
+
+    /// Admit a half-open probe if the limit has not been reached.
+    fn admit_probe(&self) -> bool {
+        self.half_open_calls
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |calls| {
+                (calls < self.config.half_open_max_calls).then_some(calls + 1)
+            })
+            .is_ok()
+    }
     
     /// Record a successful call
     pub fn record_success(&self) {
         self.successes.fetch_add(1, Ordering::SeqCst);
         
         let state = *self.state.read().unwrap();
-        if state == CircuitState::HalfOpen {
-            let calls = self.half_open_calls.fetch_add(1, Ordering::SeqCst) + 1;
-            if calls >= self.config.half_open_max_calls {
-                self.transition_to(CircuitState::Closed);
+        match state {
+            CircuitState::HalfOpen => {
+                let successes = self.half_open_successes.fetch_add(1, Ordering::SeqCst) + 1;
+                if successes >= self.config.half_open_max_calls {
+                    self.transition_from(CircuitState::HalfOpen, CircuitState::Closed);
+                }
             }
+            // Only consecutive failures count towards opening
+            CircuitState::Closed => self.failures.store(0, Ordering::SeqCst),
+            CircuitState::Open => {}
         }
     }
     
@@ -118,16 +164,19 @@ impl CircuitBreaker {
         
         if state == CircuitState::HalfOpen {
             // Single failure in half-open triggers open
-            self.transition_to(CircuitState::Open);
+            self.transition_from(CircuitState::HalfOpen, CircuitState::Open);
         } else if state == CircuitState::Closed && failures >= self.config.failure_threshold {
-            self.transition_to(CircuitState::Open);
+            self.transition_from(CircuitState::Closed, CircuitState::Open);
         }
     }
     
-    /// Transition to a new state
-    fn transition_to(&self, new_state: CircuitState) {
+    /// Transition to a new state if the circuit is still in `old_state`,
+    /// so concurrent callers observing the same state transition it once
+    fn transition_from(&self, old_state: CircuitState, new_state: CircuitState) {
         let mut state = self.state.write().unwrap();
-        let old_state = *state;
+        if *state != old_state {
+            return;
+        }
         *state = new_state;
         
         match new_state {
@@ -136,6 +185,7 @@ impl CircuitBreaker {
                 *self.next_retry_time.write().unwrap() = Some(Instant::now() + backoff);
                 self.retry_count.fetch_add(1, Ordering::SeqCst);
                 warn!(
+                    from = ?old_state,
                     "Circuit OPENED after {} failures. Retry in {:?}",
                     self.failures.load(Ordering::SeqCst),
                     backoff
@@ -143,12 +193,13 @@ impl CircuitBreaker {
             }
             CircuitState::HalfOpen => {
                 self.half_open_calls.store(0, Ordering::SeqCst);
-                info!("Circuit HALF-OPEN, testing recovery");
+                self.half_open_successes.store(0, Ordering::SeqCst);
+                warn!(from = ?old_state, "Circuit HALF-OPEN, testing recovery");
             }
             CircuitState::Closed => {
                 self.retry_count.store(0, Ordering::SeqCst);
                 self.failures.store(0, Ordering::SeqCst);
-                info!("Circuit CLOSED, normal operation resumed");
+                warn!(from = ?old_state, "Circuit CLOSED, normal operation resumed");
             }
         }
     }
@@ -156,18 +207,16 @@ impl CircuitBreaker {
     /// Calculate backoff time with exponential increase and jitter
     fn calculate_backoff(&self) -> Duration {
         if !self.config.exponential_backoff {
-            return Duration::from_secs(self.config.recovery_timeout_secs);
+            return self.config.recovery_timeout();
         }
         
         let retry_count = self.retry_count.load(Ordering::SeqCst);
-        let base_delay = self.config.recovery_timeout_secs * (2_u64.pow(retry_count));
-        let capped_delay = base_delay.min(self.config.max_backoff_secs);
+        let base_delay = self.config.recovery_timeout().saturating_mul(2_u32.saturating_pow(retry_count));
+        let capped_delay = base_delay.min(self.config.max_backoff());
         
         // Add jitter (50-100% of delay)
         let jitter_factor = 0.5 + (rand::random::<f64>() * 0.5);
-        let final_delay = (capped_delay as f64 * jitter_factor) as u64;
-        
-        Duration::from_secs(final_delay)
+        capped_delay.mul_f64(jitter_factor)
     }
     
     /// Execute a function with circuit breaker protection
@@ -222,3 +271,82 @@ pub struct CircuitStats {
     pub successes: u32,
     pub retry_count: u32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(failure_threshold: u32) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitConfig {
+            failure_threshold,
+            recovery_timeout_override: Some(Duration::from_millis(20)),
+            half_open_max_calls: 1,
+            exponential_backoff: false,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = breaker(2);
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow_request());
+    }
+
+    #[test]
+    fn test_half_open_success_closes_circuit() {
+        let breaker = breaker(1);
+        breaker.record_failure();
+        assert!(!breaker.allow_request());
+
+        assert!(breaker.is_open());
+
+        std::thread::sleep(Duration::from_millis(30));
+        // Checking does not take the probe
+        assert!(!breaker.is_open());
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.allow_request());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_limits_concurrent_probes() {
+        let breaker = CircuitBreaker::new(CircuitConfig {
+            failure_threshold: 1,
+            recovery_timeout_override: Some(Duration::from_millis(20)),
+            half_open_max_calls: 2,
+            exponential_backoff: false,
+            ..Default::default()
+        });
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+
+        // Two probes in flight, a third is refused
+        assert!(breaker.allow_request());
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(!breaker.allow_request());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_config_durations() {
+        let config = CircuitConfig::default();
+        assert_eq!(config.recovery_timeout(), Duration::from_secs(30));
+        assert_eq!(config.max_backoff(), Duration::from_secs(300));
+    }
+}
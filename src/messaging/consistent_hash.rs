@@ -8,7 +8,7 @@
 //! - Build ring: O(n log n) for initial setup
 
 use std::collections::BTreeMap;
-use std::hash::{Hash, Hasher};
+use std::hash::Hasher;
 use siphasher::sip::SipHasher24;
 
 /// Consistent hash ring for partition assignment
@@ -52,8 +52,15 @@ impl ConsistentHashPartitioner {
     
     /// Hash a key using SipHash for good distribution
     fn hash_key(key: &str) -> u64 {
+        Self::hash_bytes(key.as_bytes())
+    }
+
+    /// Hash raw bytes the same way `hash_key` hashes a string with them
+    fn hash_bytes(key: &[u8]) -> u64 {
         let mut hasher = SipHasher24::new();
-        key.hash(&mut hasher);
+        hasher.write(key);
+        // Terminator written by `str::hash`
+        hasher.write_u8(0xff);
         hasher.finish()
     }
     
@@ -62,19 +69,32 @@ impl ConsistentHashPartitioner {
     /// Uses binary search (O(log n)) via BTreeMap to find the
     /// first hash value >= key's hash, implementing consistent hashing.
     pub fn get_partition(&self, key: &str) -> usize {
+        self.partition_for(key.as_bytes()) as usize
+    }
+
+    /// Get the partition for a key given as bytes, e.g. a serialized UUID
+    ///
+    /// Agrees with `get_partition` for the bytes of a string key.
+    pub fn partition_for(&self, key: &[u8]) -> u32 {
         if self.ring.is_empty() {
             return 0;
         }
         
-        let hash = Self::hash_key(key);
+        let hash = Self::hash_bytes(key);
         
         // Find the first entry with hash >= key's hash
         // BTreeMap::range is O(log n)
-        match self.ring.range(hash..).next() {
+        let partition = match self.ring.range(hash..).next() {
             Some((_, &partition)) => partition,
             // Wrap around to first entry if we're past the last
             None => *self.ring.values().next().unwrap(),
-        }
+        };
+        partition as u32
+    }
+
+    /// Number of partitions keys are spread over
+    pub fn partition_count(&self) -> usize {
+        self.num_partitions
     }
     
     /// Get partition distribution statistics
@@ -155,4 +175,20 @@ mod tests {
             assert!(count > 50, "Partition {} has only {} keys", partition, count);
         }
     }
+
+    #[test]
+    fn test_source_id_always_maps_to_same_partition() {
+        let partitioner = ConsistentHashPartitioner::with_virtual_nodes(12, 100);
+        let source_id = "5f0c6a4e-8d1b-4b7e-9a2f-3c1d2e4f5a6b";
+        let expected = partitioner.partition_for(source_id.as_bytes());
+        assert!(expected < 12);
+        assert_eq!(partitioner.get_partition(source_id), expected as usize);
+
+        for _ in 0..10_000 {
+            assert_eq!(partitioner.partition_for(source_id.as_bytes()), expected);
+        }
+        // A freshly built ring agrees
+        let rebuilt = ConsistentHashPartitioner::with_virtual_nodes(12, 100);
+        assert_eq!(rebuilt.partition_for(source_id.as_bytes()), expected);
+    }
 }
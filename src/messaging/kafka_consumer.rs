@@ -1,20 +1,28 @@
 //! Kafka Consumer for Chunker Service
 //!
 //! Consumes `code.normalized` events from Kafka and processes them
-//! through the chunking pipeline.
+//! through the chunking pipeline. Messages that keep failing are moved to
+//! an optional dead letter topic.
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use rdkafka::config::ClientConfig;
-use rdkafka::consumer::{Consumer, StreamConsumer, CommitMode};
-use rdkafka::message::Message;
+use rdkafka::consumer::{BaseConsumer, Consumer, StreamConsumer, CommitMode};
+use rdkafka::message::{BorrowedMessage, Message};
 use rdkafka::error::KafkaError;
+use rdkafka::{Offset, TopicPartitionList};
 use tokio::sync::mpsc;
 use tracing::{info, error, warn, instrument};
 use serde::{Deserialize, Serialize};
 
+use crate::chunkers::repo_chunker::{decorators_for, extract_decorator_associations};
+
+use super::kafka_producer::KafkaChunkProducer;
+use super::schema_registry::{KafkaMessageDecoder, SchemaRegistryClient};
+
 /// Event received when code is normalized
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeNormalizedEvent {
@@ -26,6 +34,26 @@ pub struct CodeNormalizedEvent {
     pub entities: Vec<CodeEntity>,
     pub metadata: HashMap<String, serde_json::Value>,
     pub timestamp: String,
+    /// Position of the event among its source's events
+    #[serde(default)]
+    pub sequence_number: Option<u64>,
+    /// Whether this is the last event of its source's sequence
+    #[serde(default)]
+    pub is_last: bool,
+}
+
+impl CodeNormalizedEvent {
+    /// Fill in the decorators of entities the producer sent without any,
+    /// from the decorators found in `normalized_content`
+    pub fn attach_decorators(&mut self) {
+        if self.entities.iter().all(|e| !e.decorators.is_empty()) {
+            return;
+        }
+        let associations = extract_decorator_associations(&self.normalized_content, Some(&self.language));
+        for entity in self.entities.iter_mut().filter(|e| e.decorators.is_empty()) {
+            entity.decorators = decorators_for(&associations, &entity.name, entity.start_line as usize);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +63,158 @@ pub struct CodeEntity {
     pub start_line: u32,
     pub end_line: u32,
     pub content: String,
+    /// Decorators, annotations or attributes, e.g. `derive(Debug)` (at most 5)
+    #[serde(default)]
+    pub decorators: Vec<String>,
+}
+
+/// A message that could not be processed, published to the dead letter topic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterRecord {
+    pub source_topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    /// Source of the event, if the payload could be decoded
+    pub source_id: Option<String>,
+    /// Original message payload
+    pub payload: String,
+    pub error_reason: String,
+    pub retry_count: u32,
+    pub failed_at: String,
+}
+
+/// Default number of buffered events that flushes a source's group
+pub const DEFAULT_ORDERED_BUFFER_SIZE: usize = 100;
+
+/// Default age at which a source's group is flushed incomplete
+pub const DEFAULT_ORDERED_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A consumed event waiting in an `OrderedEventBuffer`
+#[derive(Debug, Clone)]
+pub struct BufferedMessage {
+    pub event: CodeNormalizedEvent,
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+/// Events of one source, buffered since `started`
+struct SourceGroup {
+    messages: Vec<BufferedMessage>,
+    started: Instant,
+}
+
+/// Groups consumed events by source so each source's events reach the
+/// pipeline together and in `sequence_number` order.
+///
+/// A group is flushed when an event with `is_last` arrives, when it
+/// holds `max_buffer_size` events, or when it is older than
+/// `flush_timeout`.
+pub struct OrderedEventBuffer {
+    /// Field holding the source id: `source_id`, or a metadata key
+    source_id_key: String,
+    max_buffer_size: usize,
+    flush_timeout: Duration,
+    groups: HashMap<String, SourceGroup>,
+    /// Next offset to read per topic partition
+    consumed: HashMap<(String, i32), i64>,
+}
+
+impl OrderedEventBuffer {
+    /// Create an empty buffer grouping by `source_id_key`
+    pub fn new(source_id_key: &str, max_buffer_size: usize, flush_timeout: Duration) -> Self {
+        Self {
+            source_id_key: source_id_key.to_string(),
+            max_buffer_size: max_buffer_size.max(1),
+            flush_timeout,
+            groups: HashMap::new(),
+            consumed: HashMap::new(),
+        }
+    }
+
+    /// Source id of an event, falling back to `source_id` when the
+    /// metadata key is missing
+    pub fn source_id_of<'a>(&self, event: &'a CodeNormalizedEvent) -> &'a str {
+        if self.source_id_key == "source_id" {
+            return &event.source_id;
+        }
+        event
+            .metadata
+            .get(&self.source_id_key)
+            .and_then(|v| v.as_str())
+            .unwrap_or(&event.source_id)
+    }
+
+    /// Buffer a message, returning its source's group if this completes it
+    pub fn push(&mut self, message: BufferedMessage) -> Option<Vec<BufferedMessage>> {
+        let next = self.consumed.entry((message.topic.clone(), message.partition)).or_insert(0);
+        *next = (*next).max(message.offset + 1);
+
+        let source_id = self.source_id_of(&message.event).to_string();
+        let is_last = message.event.is_last;
+        let group = self.groups.entry(source_id.clone()).or_insert_with(|| SourceGroup {
+            messages: Vec::new(),
+            started: Instant::now(),
+        });
+        group.messages.push(message);
+
+        if is_last || group.messages.len() >= self.max_buffer_size {
+            return self.flush(&source_id);
+        }
+        None
+    }
+
+    /// Flush the groups older than `flush_timeout` at `now`
+    pub fn flush_expired(&mut self, now: Instant) -> Vec<Vec<BufferedMessage>> {
+        let expired: Vec<String> = self
+            .groups
+            .iter()
+            .filter(|(_, group)| now.duration_since(group.started) >= self.flush_timeout)
+            .map(|(source_id, _)| source_id.clone())
+            .collect();
+        expired.iter().filter_map(|source_id| self.flush(source_id)).collect()
+    }
+
+    /// Flush every group, e.g. on shutdown
+    pub fn flush_all(&mut self) -> Vec<Vec<BufferedMessage>> {
+        let sources: Vec<String> = self.groups.keys().cloned().collect();
+        sources.iter().filter_map(|source_id| self.flush(source_id)).collect()
+    }
+
+    fn flush(&mut self, source_id: &str) -> Option<Vec<BufferedMessage>> {
+        let mut messages = self.groups.remove(source_id)?.messages;
+        // Stable, so events without a sequence number keep arrival order
+        messages.sort_by_key(|message| message.event.sequence_number);
+        Some(messages)
+    }
+
+    /// Offsets safe to commit per topic partition: everything before the
+    /// oldest message still buffered there
+    pub fn committable_offsets(&self) -> Vec<(String, i32, i64)> {
+        let mut offsets: HashMap<(&str, i32), i64> = self
+            .consumed
+            .iter()
+            .map(|((topic, partition), next)| ((topic.as_str(), *partition), *next))
+            .collect();
+        for message in self.groups.values().flat_map(|group| &group.messages) {
+            if let Some(offset) = offsets.get_mut(&(message.topic.as_str(), message.partition)) {
+                *offset = (*offset).min(message.offset);
+            }
+        }
+        offsets
+            .into_iter()
+            .map(|((topic, partition), offset)| (topic.to_string(), partition, offset))
+            .collect()
+    }
+
+    /// Number of buffered messages
+    pub fn len(&self) -> usize {
+        self.groups.values().map(|group| group.messages.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
 }
 
 /// Configuration for the Kafka consumer
@@ -61,10 +241,37 @@ impl Default for ConsumerConfig {
     }
 }
 
+/// Default delay before retrying a failed message
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Longest delay between retries of a failed message
+pub const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Delay before the `retry`th retry (1-based): `base` doubled per retry,
+/// capped at `MAX_RETRY_BACKOFF`
+fn retry_delay(base: Duration, retry: u32) -> Duration {
+    base.saturating_mul(2_u32.saturating_pow(retry.saturating_sub(1)))
+        .min(MAX_RETRY_BACKOFF)
+}
+
 /// Kafka consumer for the chunker service
 pub struct KafkaChunkConsumer {
     consumer: Arc<StreamConsumer>,
     config: ConsumerConfig,
+    /// Topic messages are moved to after `max_retries` failures
+    dead_letter_topic: Option<String>,
+    dead_letter_producer: Option<Arc<KafkaChunkProducer>>,
+    /// Processing attempts before a message is dead-lettered
+    max_retries: u32,
+    /// Delay before the first retry, doubled on each further attempt
+    retry_backoff: Duration,
+    /// Field holding the source id events are grouped by, if
+    /// `consume_to_channel` should keep each source's events together
+    ordered_source_id_key: Option<String>,
+    max_buffer_size: usize,
+    flush_timeout: Duration,
+    /// Registry for Avro messages in the Confluent wire format
+    schema_registry: Option<Arc<SchemaRegistryClient>>,
 }
 
 impl KafkaChunkConsumer {
@@ -88,9 +295,82 @@ impl KafkaChunkConsumer {
         Ok(Self {
             consumer: Arc::new(consumer),
             config,
+            dead_letter_topic: None,
+            dead_letter_producer: None,
+            max_retries: 3,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            ordered_source_id_key: None,
+            max_buffer_size: DEFAULT_ORDERED_BUFFER_SIZE,
+            flush_timeout: DEFAULT_ORDERED_FLUSH_TIMEOUT,
+            schema_registry: None,
         })
     }
+
+    /// Publish messages that keep failing to a dead letter topic
+    pub fn with_dead_letter_queue(
+        mut self,
+        topic: impl Into<String>,
+        producer: Arc<KafkaChunkProducer>,
+    ) -> Self {
+        self.dead_letter_topic = Some(topic.into());
+        self.dead_letter_producer = Some(producer);
+        self
+    }
+
+    /// Set the processing attempts before a message is dead-lettered
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries.max(1);
+        self
+    }
+
+    /// Set the delay before retrying a failed message, doubled on each
+    /// further attempt up to `MAX_RETRY_BACKOFF`
+    pub fn with_retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
     
+    /// Group events by source in `consume_to_channel`, so that a source's
+    /// events are sent together, in `sequence_number` order, instead of
+    /// interleaved with other sources
+    ///
+    /// `source_id_key` is `source_id` or a metadata key holding the source
+    /// id. See `OrderedEventBuffer` for when a group is sent.
+    pub fn with_ordered_processing(mut self, source_id_key: &str) -> Self {
+        self.ordered_source_id_key = Some(source_id_key.to_string());
+        self
+    }
+
+    /// Set when ordered processing sends an incomplete group
+    pub fn with_ordered_buffer_limits(mut self, max_buffer_size: usize, flush_timeout: Duration) -> Self {
+        self.max_buffer_size = max_buffer_size.max(1);
+        self.flush_timeout = flush_timeout;
+        self
+    }
+
+    /// Decode Avro messages with schemas from a Confluent Schema Registry
+    ///
+    /// Each message's encoding is detected from its first byte, so topics
+    /// mixing JSON and Avro messages are consumed too.
+    pub fn with_schema_registry(mut self, url: &str, subject: &str) -> Self {
+        self.schema_registry = Some(Arc::new(SchemaRegistryClient::new(url, subject)));
+        self
+    }
+
+    /// Decode a message payload, JSON or Avro, and attach decorators to
+    /// its entities
+    pub async fn decode_event(&self, payload: &[u8]) -> anyhow::Result<CodeNormalizedEvent> {
+        let mut event: CodeNormalizedEvent = match KafkaMessageDecoder::detect(payload) {
+            KafkaMessageDecoder::Json => serde_json::from_slice(payload)?,
+            KafkaMessageDecoder::Avro { schema_id } => match &self.schema_registry {
+                Some(registry) => registry.decode(payload).await?,
+                None => return Err(anyhow::anyhow!("Avro message with schema {} but no schema registry configured", schema_id)),
+            },
+        };
+        event.attach_decorators();
+        Ok(event)
+    }
+
     /// Subscribe to configured topics
     pub fn subscribe(&self) -> Result<(), KafkaError> {
         let topics: Vec<&str> = self.config.topics.iter().map(|s| s.as_str()).collect();
@@ -100,13 +380,21 @@ impl KafkaChunkConsumer {
     }
     
     /// Consume messages and send them to a channel for processing
+    ///
+    /// With `with_ordered_processing`, events are buffered per source and
+    /// offsets are only committed once every earlier message of their
+    /// partition has been sent.
     #[instrument(skip(self, sender))]
     pub async fn consume_to_channel(
         &self,
         sender: mpsc::Sender<CodeNormalizedEvent>,
     ) -> Result<(), KafkaError> {
-        use rdkafka::message::BorrowedMessage;
         use tokio_stream::StreamExt;
+
+        if let Some(source_id_key) = &self.ordered_source_id_key {
+            let buffer = OrderedEventBuffer::new(source_id_key, self.max_buffer_size, self.flush_timeout);
+            return self.consume_ordered(sender, buffer).await;
+        }
         
         info!("Starting Kafka consumer loop");
         
@@ -117,7 +405,7 @@ impl KafkaChunkConsumer {
             match result {
                 Ok(message) => {
                     if let Some(payload) = message.payload() {
-                        match serde_json::from_slice::<CodeNormalizedEvent>(payload) {
+                        match self.decode_event(payload).await {
                             Ok(event) => {
                                 if sender.send(event.clone()).await.is_err() {
                                     warn!("Channel closed, stopping consumer");
@@ -149,14 +437,237 @@ impl KafkaChunkConsumer {
         Ok(())
     }
     
+    /// `consume_to_channel` with events grouped by source
+    async fn consume_ordered(
+        &self,
+        sender: mpsc::Sender<CodeNormalizedEvent>,
+        mut buffer: OrderedEventBuffer,
+    ) -> Result<(), KafkaError> {
+        use tokio_stream::StreamExt;
+
+        info!(source_id_key = %buffer.source_id_key, "Starting ordered Kafka consumer loop");
+
+        let stream = self.consumer.stream();
+        tokio::pin!(stream);
+        let mut ticker = tokio::time::interval((self.flush_timeout / 2).max(Duration::from_millis(10)));
+        let mut ended = false;
+
+        while !ended {
+            let flushed = tokio::select! {
+                result = stream.next() => match result {
+                    Some(Ok(message)) => {
+                        let Some(payload) = message.payload() else { continue };
+                        match self.decode_event(payload).await {
+                            Ok(event) => buffer
+                                .push(BufferedMessage {
+                                    event,
+                                    topic: message.topic().to_string(),
+                                    partition: message.partition(),
+                                    offset: message.offset(),
+                                })
+                                .into_iter()
+                                .collect(),
+                            Err(e) => {
+                                error!(
+                                    error = %e,
+                                    topic = %message.topic(),
+                                    partition = %message.partition(),
+                                    "Failed to deserialize message"
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                    Some(Err(e)) => {
+                        error!(error = %e, "Kafka consumer error");
+                        continue;
+                    }
+                    None => {
+                        ended = true;
+                        buffer.flush_all()
+                    }
+                },
+                _ = ticker.tick() => buffer.flush_expired(Instant::now()),
+            };
+
+            for message in flushed.into_iter().flatten() {
+                if sender.send(message.event).await.is_err() {
+                    warn!("Channel closed, stopping consumer");
+                    return Ok(());
+                }
+            }
+            self.commit_offsets(&buffer.committable_offsets());
+        }
+        Ok(())
+    }
+
+    fn commit_offsets(&self, offsets: &[(String, i32, i64)]) {
+        if offsets.is_empty() {
+            return;
+        }
+        let mut list = TopicPartitionList::new();
+        for (topic, partition, offset) in offsets {
+            if let Err(e) = list.add_partition_offset(topic, *partition, Offset::Offset(*offset)) {
+                error!(error = %e, topic = %topic, partition, "Invalid offset to commit");
+            }
+        }
+        if let Err(e) = self.consumer.commit(&list, CommitMode::Async) {
+            error!(error = %e, "Failed to commit offsets");
+        }
+    }
+
+    /// Consume messages, processing each with `handler`
+    ///
+    /// A failing message is retried up to `max_retries` attempts with
+    /// exponential backoff, then published to the dead letter topic (if
+    /// configured) and committed. Messages that cannot be decoded are
+    /// dead-lettered immediately.
+    ///
+    /// If the dead letter topic stays unreachable, the loop stops with the
+    /// error before committing, so the message is redelivered on restart
+    /// rather than skipped by a later message's commit.
+    #[instrument(skip(self, handler))]
+    pub async fn consume_with_handler<F, Fut>(&self, mut handler: F) -> Result<(), KafkaError>
+    where
+        F: FnMut(CodeNormalizedEvent) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        use tokio_stream::StreamExt;
+
+        info!("Starting Kafka consumer loop with dead letter handling");
+
+        let stream = self.consumer.stream();
+        tokio::pin!(stream);
+
+        while let Some(result) = stream.next().await {
+            let message = match result {
+                Ok(message) => message,
+                Err(e) => {
+                    error!(error = %e, "Kafka consumer error");
+                    continue;
+                }
+            };
+
+            let payload = message.payload().unwrap_or_default();
+            let failure = match self.decode_event(payload).await {
+                Ok(event) => {
+                    let source_id = event.source_id.clone();
+                    let mut failure = None;
+                    for attempt in 1..=self.max_retries {
+                        if attempt > 1 {
+                            tokio::time::sleep(retry_delay(self.retry_backoff, attempt - 1)).await;
+                        }
+                        match handler(event.clone()).await {
+                            Ok(()) => {
+                                failure = None;
+                                break;
+                            }
+                            Err(e) => {
+                                warn!(source_id = %source_id, attempt, error = %e, "Failed to process message");
+                                failure = Some((Some(source_id.clone()), e.to_string(), attempt));
+                            }
+                        }
+                    }
+                    failure
+                }
+                Err(e) => Some((None, format!("Failed to deserialize message: {}", e), 0)),
+            };
+
+            if let Some((source_id, error_reason, retry_count)) = failure {
+                let record = DeadLetterRecord {
+                    source_topic: message.topic().to_string(),
+                    partition: message.partition(),
+                    offset: message.offset(),
+                    source_id,
+                    payload: String::from_utf8_lossy(payload).into_owned(),
+                    error_reason,
+                    retry_count,
+                    failed_at: chrono::Utc::now().to_rfc3339(),
+                };
+                self.dead_letter_with_retries(&record).await?;
+            }
+
+            self.commit(&message);
+        }
+
+        Ok(())
+    }
+
+    /// Publish a failed message to the dead letter topic, retrying with
+    /// backoff
+    async fn dead_letter_with_retries(&self, record: &DeadLetterRecord) -> Result<(), KafkaError> {
+        let mut attempt = 1;
+        loop {
+            match self.dead_letter(record).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.max_retries => {
+                    // Leave the offset uncommitted so the message is not lost
+                    error!(error = %e, offset = record.offset, "Failed to publish dead letter, stopping consumer");
+                    return Err(e);
+                }
+                Err(e) => {
+                    warn!(error = %e, offset = record.offset, attempt, "Failed to publish dead letter, retrying");
+                    tokio::time::sleep(retry_delay(self.retry_backoff, attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Publish a failed message to the dead letter topic
+    async fn dead_letter(&self, record: &DeadLetterRecord) -> Result<(), KafkaError> {
+        let (Some(topic), Some(producer)) = (&self.dead_letter_topic, &self.dead_letter_producer) else {
+            error!(
+                source_id = ?record.source_id,
+                error_reason = %record.error_reason,
+                "Dropping failed message, no dead letter topic configured"
+            );
+            return Ok(());
+        };
+
+        producer.publish_dead_letter(topic, record).await?;
+        error!(
+            source_id = ?record.source_id,
+            topic = %topic,
+            error_reason = %record.error_reason,
+            retry_count = record.retry_count,
+            "Published message to dead letter queue"
+        );
+        Ok(())
+    }
+
+    fn commit(&self, message: &BorrowedMessage<'_>) {
+        if let Err(e) = self.consumer.commit_message(message, CommitMode::Async) {
+            error!(error = %e, "Failed to commit offset");
+        }
+    }
+
+    /// Read all available records from a consumer subscribed to the dead
+    /// letter topic, for manual inspection
+    pub fn drain_dead_letters(dead_letter_consumer: &BaseConsumer) -> Vec<DeadLetterRecord> {
+        let mut records = Vec::new();
+        while let Some(result) = dead_letter_consumer.poll(Duration::from_secs(1)) {
+            match result {
+                Ok(message) => match message.payload().map(serde_json::from_slice::<DeadLetterRecord>) {
+                    Some(Ok(record)) => records.push(record),
+                    Some(Err(e)) => warn!(error = %e, offset = message.offset(), "Skipping undecodable dead letter"),
+                    None => {}
+                },
+                Err(e) => {
+                    error!(error = %e, "Error reading dead letter topic");
+                    break;
+                }
+            }
+        }
+        records
+    }
+
     /// Consume a batch of messages
     pub async fn consume_batch(
         &self,
         batch_size: usize,
         timeout: Duration,
     ) -> Vec<CodeNormalizedEvent> {
-        use rdkafka::util::Timeout;
-        
         let mut events = Vec::with_capacity(batch_size);
         let deadline = tokio::time::Instant::now() + timeout;
         
@@ -170,7 +681,7 @@ impl KafkaChunkConsumer {
             ).await {
                 Ok(Ok(message)) => {
                     if let Some(payload) = message.payload() {
-                        if let Ok(event) = serde_json::from_slice::<CodeNormalizedEvent>(payload) {
+                        if let Ok(event) = self.decode_event(payload).await {
                             events.push(event);
                             let _ = self.consumer.commit_message(&message, CommitMode::Async);
                         }
@@ -190,3 +701,194 @@ impl KafkaChunkConsumer {
         events
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rdkafka::mocking::MockCluster;
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::messaging::kafka_producer::ProducerConfig;
+
+    const SOURCE_TOPIC: &str = "code.normalized";
+    const DEAD_LETTER_TOPIC: &str = "code.normalized.dlq";
+
+    fn event(source_id: &str) -> CodeNormalizedEvent {
+        CodeNormalizedEvent {
+            event_id: "evt-1".to_string(),
+            source_id: source_id.to_string(),
+            file_path: "src/lib.rs".to_string(),
+            language: "rust".to_string(),
+            normalized_content: "fn main() {}".to_string(),
+            entities: vec![],
+            metadata: HashMap::new(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            sequence_number: None,
+            is_last: false,
+        }
+    }
+
+    #[test]
+    fn test_attach_decorators_fills_missing_entity_decorators() {
+        let mut event = event("repo-1");
+        event.normalized_content = "#[derive(Debug)]\nstruct Config;\n\n#[inline]\nfn main() {}\n".to_string();
+        let entity = |name: &str, line: u32, decorators: Vec<String>| CodeEntity {
+            entity_type: "function".to_string(),
+            name: name.to_string(),
+            start_line: line,
+            end_line: line,
+            content: String::new(),
+            decorators,
+        };
+        event.entities = vec![entity("Config", 2, vec![]), entity("main", 5, vec!["cold".to_string()])];
+        event.attach_decorators();
+        assert_eq!(event.entities[0].decorators, vec!["derive(Debug)"]);
+        // Decorators sent by the producer are kept
+        assert_eq!(event.entities[1].decorators, vec!["cold"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_failed_messages_are_dead_lettered() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic(SOURCE_TOPIC, 1, 1).unwrap();
+        cluster.create_topic(DEAD_LETTER_TOPIC, 1, 1).unwrap();
+        let bootstrap_servers = cluster.bootstrap_servers();
+
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &bootstrap_servers)
+            .create()
+            .unwrap();
+        let payload = serde_json::to_string(&event("repo-42")).unwrap();
+        for payload in [payload.as_str(), "not json"] {
+            producer
+                .send(FutureRecord::<(), _>::to(SOURCE_TOPIC).payload(payload), Duration::from_secs(5))
+                .await
+                .unwrap();
+        }
+
+        let dead_letter_producer = KafkaChunkProducer::new(ProducerConfig {
+            bootstrap_servers: bootstrap_servers.clone(),
+            compression_type: "none".to_string(),
+            num_partitions: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let consumer = KafkaChunkConsumer::new(ConsumerConfig {
+            bootstrap_servers: bootstrap_servers.clone(),
+            topics: vec![SOURCE_TOPIC.to_string()],
+            ..Default::default()
+        })
+        .unwrap()
+        .with_dead_letter_queue(DEAD_LETTER_TOPIC, Arc::new(dead_letter_producer))
+        .with_max_retries(2)
+        .with_retry_backoff(Duration::from_millis(10));
+        consumer.subscribe().unwrap();
+
+        let consumer = Arc::new(consumer);
+        let attempts = Arc::new(AtomicU32::new(0));
+        let consume = tokio::spawn({
+            let (consumer, attempts) = (consumer.clone(), attempts.clone());
+            async move {
+                consumer
+                    .consume_with_handler(|_| {
+                        attempts.fetch_add(1, Ordering::SeqCst);
+                        async { Err(anyhow::anyhow!("embedding service unavailable")) }
+                    })
+                    .await
+            }
+        });
+
+        let dead_letter_consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &bootstrap_servers)
+            .set("group.id", "dlq-inspector")
+            .create()
+            .unwrap();
+        let mut assignment = TopicPartitionList::new();
+        assignment
+            .add_partition_offset(DEAD_LETTER_TOPIC, 0, Offset::Beginning)
+            .unwrap();
+        dead_letter_consumer.assign(&assignment).unwrap();
+
+        let mut records = Vec::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(60);
+        while records.len() < 2 && tokio::time::Instant::now() < deadline {
+            records.extend(KafkaChunkConsumer::drain_dead_letters(&dead_letter_consumer));
+        }
+        consume.abort();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        records.sort_by_key(|record| record.offset);
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].source_id.as_deref(), Some("repo-42"));
+        assert_eq!(records[0].retry_count, 2);
+        assert_eq!(records[0].error_reason, "embedding service unavailable");
+        assert_eq!(records[0].payload, payload);
+
+        assert_eq!(records[1].source_id, None);
+        assert_eq!(records[1].retry_count, 0);
+        assert!(records[1].error_reason.starts_with("Failed to deserialize"));
+        assert_eq!(records[1].source_topic, SOURCE_TOPIC);
+    }
+
+    fn buffered(source_id: &str, sequence_number: u64, is_last: bool, offset: i64) -> BufferedMessage {
+        BufferedMessage {
+            event: CodeNormalizedEvent {
+                sequence_number: Some(sequence_number),
+                is_last,
+                ..event(source_id)
+            },
+            topic: SOURCE_TOPIC.to_string(),
+            partition: 0,
+            offset,
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_cap() {
+        let base = Duration::from_millis(100);
+        assert_eq!(retry_delay(base, 1), Duration::from_millis(100));
+        assert_eq!(retry_delay(base, 2), Duration::from_millis(200));
+        assert_eq!(retry_delay(base, 4), Duration::from_millis(800));
+        assert_eq!(retry_delay(base, 20), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_delay(base, u32::MAX), MAX_RETRY_BACKOFF);
+    }
+
+    #[test]
+    fn test_ordered_buffer_groups_sources() {
+        let mut buffer = OrderedEventBuffer::new("source_id", 3, Duration::from_secs(60));
+
+        // Two interleaved sources, one arriving out of order
+        assert!(buffer.push(buffered("repo-a", 1, false, 0)).is_none());
+        assert!(buffer.push(buffered("repo-b", 0, false, 1)).is_none());
+        assert!(buffer.push(buffered("repo-a", 0, false, 2)).is_none());
+        assert_eq!(buffer.committable_offsets(), vec![(SOURCE_TOPIC.to_string(), 0, 0)]);
+
+        let group = buffer.push(buffered("repo-a", 2, true, 3)).unwrap();
+        let sequence: Vec<_> = group.iter().map(|m| m.event.sequence_number.unwrap()).collect();
+        assert_eq!(sequence, [0, 1, 2]);
+        // repo-b still holds offset 1
+        assert_eq!(buffer.committable_offsets(), vec![(SOURCE_TOPIC.to_string(), 0, 1)]);
+
+        // A full group is flushed without its last event
+        assert!(buffer.push(buffered("repo-b", 1, false, 4)).is_none());
+        assert_eq!(buffer.push(buffered("repo-b", 2, false, 5)).unwrap().len(), 3);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.committable_offsets(), vec![(SOURCE_TOPIC.to_string(), 0, 6)]);
+    }
+
+    #[test]
+    fn test_ordered_buffer_flushes_expired_groups() {
+        let mut buffer = OrderedEventBuffer::new("repository", 100, Duration::from_millis(50));
+        let mut message = buffered("ignored", 0, false, 0);
+        message.event.metadata.insert("repository".to_string(), serde_json::json!("acme/api"));
+        assert_eq!(buffer.source_id_of(&message.event), "acme/api");
+        buffer.push(message);
+
+        assert!(buffer.flush_expired(Instant::now()).is_empty());
+        let flushed = buffer.flush_expired(Instant::now() + Duration::from_millis(60));
+        assert_eq!(flushed.len(), 1);
+        assert!(buffer.is_empty());
+    }
+}
@@ -2,17 +2,17 @@
 //!
 //! Publishes `chunk.created` events to Kafka for downstream embedding generation.
 
-use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
 use rdkafka::config::ClientConfig;
-use rdkafka::producer::{FutureProducer, FutureRecord, DeliveryFuture};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::error::KafkaError;
 use tracing::{info, error, instrument};
 use serde::{Deserialize, Serialize};
 
 use super::consistent_hash::ConsistentHashPartitioner;
+use super::kafka_consumer::DeadLetterRecord;
 
 /// Event published when a chunk is created
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,6 +106,22 @@ impl KafkaChunkProducer {
         })
     }
     
+    /// Select partitions with `partitioner` instead of a ring built from
+    /// `ProducerConfig::num_partitions`.
+    ///
+    /// The partitioner should have as many partitions as the topic.
+    pub fn with_partitioner(mut self, partitioner: ConsistentHashPartitioner) -> Self {
+        self.config.num_partitions = partitioner.partition_count() as u32;
+        self.partitioner = partitioner;
+        self
+    }
+
+    /// Partition for an event, chosen by its source so that all chunks of
+    /// a source reach the same consumer in order.
+    fn partition_for(&self, event: &ChunkCreatedEvent) -> u32 {
+        self.partitioner.partition_for(event.source_id.as_bytes())
+    }
+
     /// Publish a chunk created event
     #[instrument(skip(self, event), fields(chunk_id = %event.chunk_id))]
     pub async fn publish_chunk_created(
@@ -113,10 +129,10 @@ impl KafkaChunkProducer {
         event: ChunkCreatedEvent,
     ) -> Result<(), KafkaError> {
         let key = event.chunk_id.clone();
-        let partition = self.partitioner.get_partition(&key);
+        let partition = self.partition_for(&event);
         let payload = serde_json::to_string(&event)
-            .map_err(|e| KafkaError::MessageProduction(
-                rdkafka::types::RDKafkaErrorCode::InvalidArg
+            .map_err(|_| KafkaError::MessageProduction(
+                rdkafka::types::RDKafkaErrorCode::InvalidArgument
             ))?;
         
         let record = FutureRecord::to(Self::TOPIC_CHUNK_CREATED)
@@ -161,8 +177,85 @@ impl KafkaChunkProducer {
         futures::future::join_all(futures).await
     }
     
-    /// Flush all pending messages
+    /// Publish a record to a dead letter topic
+    #[instrument(skip(self, record), fields(source_id = ?record.source_id))]
+    pub async fn publish_dead_letter(
+        &self,
+        topic: &str,
+        record: &DeadLetterRecord,
+    ) -> Result<(), KafkaError> {
+        let key = record.source_id.clone().unwrap_or_else(|| record.offset.to_string());
+        let payload = serde_json::to_string(record)
+            .map_err(|_| KafkaError::MessageProduction(
+                rdkafka::types::RDKafkaErrorCode::InvalidArgument
+            ))?;
+
+        let record = FutureRecord::to(topic)
+            .key(&key)
+            .payload(&payload);
+
+        self.producer
+            .send(record, Duration::from_secs(10))
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| e)
+    }
+
+    /// Producer configuration
+    pub fn config(&self) -> &ProducerConfig {
+        &self.config
+    }
+
+    /// Flush all pending messages, logging a failure
     pub fn flush(&self, timeout: Duration) {
-        self.producer.flush(timeout);
+        if let Err(e) = self.try_flush(timeout) {
+            error!(error = %e, "Failed to flush Kafka producer");
+        }
+    }
+
+    /// Flush all pending messages, returning a failure
+    pub fn try_flush(&self, timeout: Duration) -> Result<(), KafkaError> {
+        self.producer.flush(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(source_id: &str, chunk_index: u32) -> ChunkCreatedEvent {
+        ChunkCreatedEvent {
+            event_id: uuid::Uuid::new_v4().to_string(),
+            source_id: source_id.to_string(),
+            file_path: "src/lib.rs".to_string(),
+            chunk_id: uuid::Uuid::new_v4().to_string(),
+            chunk_index,
+            total_chunks: 100,
+            content: String::new(),
+            token_count: 0,
+            metadata: ChunkMetadata {
+                language: Some("rust".to_string()),
+                entity_type: None,
+                entity_name: None,
+                start_line: None,
+                end_line: None,
+                profile: "default".to_string(),
+            },
+            timestamp: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_chunks_of_a_source_share_a_partition() {
+        // Creating a producer does not connect to the brokers
+        let producer = KafkaChunkProducer::new(ProducerConfig::default())
+            .unwrap()
+            .with_partitioner(ConsistentHashPartitioner::with_virtual_nodes(24, 50));
+        assert_eq!(producer.config().num_partitions, 24);
+
+        let source_id = uuid::Uuid::new_v4().to_string();
+        let partition = producer.partition_for(&event(&source_id, 0));
+        assert!(partition < 24);
+        assert!((1..100).all(|i| producer.partition_for(&event(&source_id, i)) == partition));
     }
 }
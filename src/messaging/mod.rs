@@ -1,21 +1,30 @@
 //! # ConFuse Messaging Module
 //! 
-//! Provides Kafka and RabbitMQ integration for the Chunker service.
+//! Provides Kafka, NATS JetStream, Redis Streams and RabbitMQ integration for the Chunker service.
 //! 
 //! ## Features
-//! - Kafka consumer for receiving code.normalized events
+//! - Kafka consumer for receiving code.normalized events, as JSON or as
+//!   Avro with a Confluent Schema Registry
 //! - Kafka producer for publishing chunk.created events
+//! - NATS JetStream consumer and producer as an alternative to Kafka
+//! - Redis Streams consumer and producer for lightweight deployments
 //! - RabbitMQ client for task queues
 //! - DSA-optimized components (consistent hashing, circuit breaker)
 
 pub mod kafka_consumer;
 pub mod kafka_producer;
+pub mod nats_client;
+pub mod redis_streams_client;
 pub mod rabbit_client;
 pub mod circuit_breaker;
 pub mod consistent_hash;
+pub mod schema_registry;
 
 pub use kafka_consumer::KafkaChunkConsumer;
 pub use kafka_producer::KafkaChunkProducer;
+pub use nats_client::{NatsChunkConsumer, NatsChunkProducer};
+pub use redis_streams_client::{RedisStreamConsumer, RedisStreamProducer, StreamEntry};
 pub use rabbit_client::RabbitClient;
 pub use circuit_breaker::CircuitBreaker;
 pub use consistent_hash::ConsistentHashPartitioner;
+pub use schema_registry::{KafkaMessageDecoder, SchemaRegistryClient};
@@ -0,0 +1,271 @@
+//! NATS JetStream Client for Chunker Service
+//!
+//! Consumes `code.normalized` events from a JetStream subject and publishes
+//! `chunk.created` events, as an alternative to the Kafka clients.
+
+use std::time::Duration;
+
+use async_nats::jetstream::{
+    self,
+    consumer::{pull, AckPolicy, PullConsumer},
+    AckKind, Context, Message,
+};
+use async_nats::header::NATS_MESSAGE_ID;
+use async_nats::HeaderMap;
+use futures::StreamExt;
+use tracing::{info, instrument, warn};
+
+use super::kafka_consumer::CodeNormalizedEvent;
+use super::kafka_producer::ChunkCreatedEvent;
+use crate::types::NatsConfig;
+
+/// JetStream stream holding all chunker subjects
+pub const STREAM_NAME: &str = "CHUNKER";
+
+/// Subjects captured by the chunker stream
+const STREAM_SUBJECTS: &str = "chunker.>";
+
+/// Default delivery attempts before a message is dropped
+const DEFAULT_MAX_DELIVER: i64 = 5;
+
+/// Get or create the chunker stream
+async fn chunker_stream(js: &Context) -> Result<jetstream::stream::Stream, async_nats::Error> {
+    let stream = js
+        .get_or_create_stream(jetstream::stream::Config {
+            name: STREAM_NAME.to_string(),
+            subjects: vec![STREAM_SUBJECTS.to_string()],
+            ..Default::default()
+        })
+        .await?;
+    Ok(stream)
+}
+
+/// Pull consumer settings for the configured subject and consumer name
+fn consumer_config(config: &NatsConfig, max_deliver: i64) -> pull::Config {
+    pull::Config {
+        name: Some(config.consumer_name.clone()),
+        durable_name: config.durable.then(|| config.consumer_name.clone()),
+        filter_subject: config.subject.clone(),
+        ack_policy: AckPolicy::Explicit,
+        max_deliver,
+        ..Default::default()
+    }
+}
+
+/// Decode a message payload as a normalized code event
+fn decode_payload(payload: &[u8]) -> Result<CodeNormalizedEvent, serde_json::Error> {
+    serde_json::from_slice(payload)
+}
+
+/// Headers for a chunk created event, keyed for JetStream deduplication
+fn chunk_created_headers(event: &ChunkCreatedEvent) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(NATS_MESSAGE_ID, event.chunk_id.as_str());
+    headers
+}
+
+/// JetStream pull consumer for normalized code events
+pub struct NatsChunkConsumer {
+    consumer: PullConsumer,
+    config: NatsConfig,
+}
+
+impl NatsChunkConsumer {
+    /// Connect and create (or bind to) the consumer
+    pub async fn new(config: NatsConfig) -> Result<Self, async_nats::Error> {
+        Self::with_max_deliver(config, DEFAULT_MAX_DELIVER).await
+    }
+
+    /// Connect with a limit on delivery attempts per message
+    ///
+    /// Messages that are `nack()`ed or not acked in time are redelivered
+    /// until `max_deliver` attempts have been made.
+    pub async fn with_max_deliver(config: NatsConfig, max_deliver: i64) -> Result<Self, async_nats::Error> {
+        let client = async_nats::connect(&config.url).await?;
+        let stream = chunker_stream(&jetstream::new(client)).await?;
+
+        let consumer = stream
+            .get_or_create_consumer(&config.consumer_name, consumer_config(&config, max_deliver))
+            .await?;
+
+        info!(
+            url = %config.url,
+            subject = %config.subject,
+            consumer = %config.consumer_name,
+            durable = config.durable,
+            "NATS consumer created"
+        );
+
+        Ok(Self { consumer, config })
+    }
+
+    /// Fetch up to `max` pending messages
+    ///
+    /// Returns whatever is available without waiting for a full batch.
+    #[instrument(skip(self), fields(subject = %self.config.subject))]
+    pub async fn pull_batch(&self, max: usize) -> Result<Vec<Message>, async_nats::Error> {
+        let mut batch = self
+            .consumer
+            .fetch()
+            .max_messages(max)
+            .expires(Duration::from_secs(1))
+            .messages()
+            .await?;
+
+        let mut messages = Vec::with_capacity(max);
+        while let Some(message) = batch.next().await {
+            messages.push(message?);
+        }
+        Ok(messages)
+    }
+
+    /// Decode a message as a normalized code event
+    pub fn decode(message: &Message) -> Result<CodeNormalizedEvent, serde_json::Error> {
+        decode_payload(&message.payload)
+    }
+
+    /// Acknowledge successful processing
+    pub async fn ack(message: &Message) -> Result<(), async_nats::Error> {
+        message.ack().await
+    }
+
+    /// Reject a message so it is redelivered (up to `max_deliver` attempts)
+    pub async fn nack(message: &Message) -> Result<(), async_nats::Error> {
+        message.ack_with(AckKind::Nak(None)).await
+    }
+}
+
+/// JetStream publisher for chunk created events
+pub struct NatsChunkProducer {
+    js: Context,
+}
+
+impl NatsChunkProducer {
+    /// Subject for chunk.created events
+    pub const SUBJECT_CHUNK_CREATED: &'static str = "chunker.chunk.created";
+
+    /// Connect and ensure the chunker stream exists
+    pub async fn new(config: &NatsConfig) -> Result<Self, async_nats::Error> {
+        let client = async_nats::connect(&config.url).await?;
+        let js = jetstream::new(client);
+        chunker_stream(&js).await?;
+
+        info!(url = %config.url, "NATS producer created");
+        Ok(Self { js })
+    }
+
+    /// Publish a chunk created event
+    ///
+    /// The chunk id is sent as `Nats-Msg-Id`, so JetStream drops
+    /// duplicates published within its deduplication window.
+    #[instrument(skip(self, event), fields(chunk_id = %event.chunk_id))]
+    pub async fn publish_chunk_created(&self, event: &ChunkCreatedEvent) -> Result<(), async_nats::Error> {
+        let payload = serde_json::to_vec(event)?;
+        let headers = chunk_created_headers(event);
+
+        let ack = self
+            .js
+            .publish_with_headers(Self::SUBJECT_CHUNK_CREATED, headers, payload.into())
+            .await?
+            .await?;
+
+        if ack.duplicate {
+            warn!(chunk_id = %event.chunk_id, "Duplicate chunk event dropped by JetStream");
+        }
+        Ok(())
+    }
+
+    /// Publish multiple chunks in batch
+    pub async fn publish_chunks_batch(
+        &self,
+        events: &[ChunkCreatedEvent],
+    ) -> Vec<Result<(), async_nats::Error>> {
+        let futures: Vec<_> = events.iter()
+            .map(|event| self.publish_chunk_created(event))
+            .collect();
+
+        futures::future::join_all(futures).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::kafka_producer::ChunkMetadata;
+
+    #[test]
+    fn test_consumer_config() {
+        let config = NatsConfig::default();
+        let durable = consumer_config(&config, 3);
+        assert_eq!(durable.name.as_deref(), Some("chunker"));
+        assert_eq!(durable.durable_name.as_deref(), Some("chunker"));
+        assert_eq!(durable.filter_subject, "chunker.code.normalized");
+        assert_eq!(durable.ack_policy, AckPolicy::Explicit);
+        assert_eq!(durable.max_deliver, 3);
+
+        let ephemeral = consumer_config(&NatsConfig { durable: false, ..config }, DEFAULT_MAX_DELIVER);
+        assert!(ephemeral.durable_name.is_none());
+        assert_eq!(ephemeral.max_deliver, DEFAULT_MAX_DELIVER);
+    }
+
+    #[test]
+    fn test_consumed_subject_is_in_stream() {
+        let prefix = STREAM_SUBJECTS.trim_end_matches('>');
+        assert!(NatsConfig::default().subject.starts_with(prefix));
+        assert!(NatsChunkProducer::SUBJECT_CHUNK_CREATED.starts_with(prefix));
+    }
+
+    #[test]
+    fn test_decode_payload() {
+        let payload = br#"{
+            "event_id": "evt-1",
+            "source_id": "repo-1",
+            "file_path": "src/lib.rs",
+            "language": "rust",
+            "normalized_content": "fn main() {}",
+            "entities": [],
+            "metadata": {},
+            "timestamp": "2024-01-01T00:00:00Z"
+        }"#;
+        let event = decode_payload(payload).unwrap();
+        assert_eq!(event.source_id, "repo-1");
+        assert_eq!(event.sequence_number, None);
+
+        assert!(decode_payload(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_chunk_created_headers_carry_message_id() {
+        let event = ChunkCreatedEvent {
+            event_id: "evt-1".to_string(),
+            source_id: "repo-1".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            chunk_id: "chunk-1".to_string(),
+            chunk_index: 0,
+            total_chunks: 1,
+            content: "fn main() {}".to_string(),
+            token_count: 4,
+            metadata: ChunkMetadata {
+                language: Some("rust".to_string()),
+                entity_type: None,
+                entity_name: None,
+                start_line: None,
+                end_line: None,
+                profile: "default".to_string(),
+            },
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+        };
+        let headers = chunk_created_headers(&event);
+        assert_eq!(headers.get(NATS_MESSAGE_ID).map(|v| v.as_str()), Some("chunk-1"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_an_error() {
+        let config = NatsConfig {
+            url: "nats://127.0.0.1:1".to_string(),
+            ..Default::default()
+        };
+        assert!(NatsChunkProducer::new(&config).await.is_err());
+        assert!(NatsChunkConsumer::new(config).await.is_err());
+    }
+}
@@ -3,16 +3,12 @@
 //! Provides async RabbitMQ operations for task coordination
 //! and worker communication.
 
-use std::sync::Arc;
-use std::time::Duration;
-
 use lapin::{
-    Connection, ConnectionProperties, Channel,
+    Channel,
     options::*, types::FieldTable,
     BasicProperties,
 };
-use deadpool_lapin::{Config, Manager, Pool, Runtime};
-use tokio::sync::RwLock;
+use deadpool_lapin::{Config, Pool, Runtime};
 use tracing::{info, error, instrument};
 use serde::{Deserialize, Serialize};
 
@@ -53,6 +49,11 @@ impl RabbitClient {
         Ok(Self { pool, config })
     }
     
+    /// Client configuration
+    pub fn config(&self) -> &RabbitConfig {
+        &self.config
+    }
+    
     /// Get a channel from the pool
     async fn get_channel(&self) -> Result<Channel, Box<dyn std::error::Error>> {
         let conn = self.pool.get().await?;
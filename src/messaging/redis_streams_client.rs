@@ -0,0 +1,246 @@
+//! Redis Streams Client for Chunker Service
+//!
+//! Publishes chunks to a Redis stream and consumes them through consumer
+//! groups, as a lightweight alternative to the Kafka clients.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use tracing::{debug, info, instrument};
+
+use crate::output::relation_graph_client::ChunkForGraph;
+use crate::types::{Chunk, RedisConfig};
+
+/// An entry read from a stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEntry {
+    /// Stream entry id, used to acknowledge the entry
+    pub id: String,
+    pub fields: HashMap<String, String>,
+}
+
+impl StreamEntry {
+    fn from_stream_id(entry: StreamId) -> Self {
+        let fields = entry
+            .map
+            .iter()
+            .filter_map(|(field, value)| {
+                redis::from_redis_value::<String>(value)
+                    .ok()
+                    .map(|value| (field.clone(), value))
+            })
+            .collect();
+        Self { id: entry.id, fields }
+    }
+}
+
+/// Stream fields for a chunk, matching the relation-graph chunk format
+fn chunk_fields(chunk: &Chunk) -> Vec<(&'static str, String)> {
+    let graph = ChunkForGraph::from_chunk(chunk);
+    let mut fields = vec![
+        ("id", graph.id),
+        ("content", graph.content),
+        ("source_kind", graph.source_kind),
+        ("source_type", graph.source_type),
+        ("source_id", graph.source_id),
+    ];
+    let optional = [
+        ("file_path", graph.file_path),
+        ("repo_name", graph.repo_name),
+        ("language", graph.language),
+        ("heading_path", graph.heading_path),
+        ("owner_id", graph.owner_id),
+        ("parent_chunk_id", graph.parent_chunk_id),
+    ];
+    fields.extend(optional.into_iter().filter_map(|(field, value)| value.map(|v| (field, v))));
+    fields.push(("metadata", graph.metadata.to_string()));
+    fields
+}
+
+async fn connect(config: &RedisConfig) -> Result<MultiplexedConnection> {
+    let client = redis::Client::open(config.url.as_str())
+        .with_context(|| format!("Invalid Redis URL {}", config.url))?;
+    client
+        .get_multiplexed_tokio_connection()
+        .await
+        .context("Failed to connect to Redis")
+}
+
+/// Consumer group reader for a chunk stream
+pub struct RedisStreamConsumer {
+    conn: MultiplexedConnection,
+    config: RedisConfig,
+}
+
+impl RedisStreamConsumer {
+    /// Connect and create the configured consumer group if it is missing
+    pub async fn new(config: RedisConfig) -> Result<Self> {
+        let conn = connect(&config).await?;
+        let consumer = Self { conn, config };
+        consumer.create_group(&consumer.config.group_name).await?;
+
+        info!(
+            url = %consumer.config.url,
+            stream = %consumer.config.stream_key,
+            group = %consumer.config.group_name,
+            "Redis stream consumer created"
+        );
+        Ok(consumer)
+    }
+
+    /// Create a consumer group, creating the stream if needed
+    ///
+    /// `XGROUP CREATE ... MKSTREAM`; an existing group is not an error.
+    pub async fn create_group(&self, group: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let created: redis::RedisResult<()> = conn
+            .xgroup_create_mkstream(&self.config.stream_key, group, "0")
+            .await;
+
+        match created {
+            Ok(()) => Ok(()),
+            Err(e) if e.code() == Some("BUSYGROUP") => {
+                debug!(group, "Consumer group already exists");
+                Ok(())
+            }
+            Err(e) => Err(e).context("Failed to create consumer group"),
+        }
+    }
+
+    /// Read up to `count` new entries for `consumer` in `group`
+    ///
+    /// Blocks for up to `block_ms`. Entries stay pending until `ack()`ed.
+    #[instrument(skip(self), fields(stream = %self.config.stream_key))]
+    pub async fn consume(&self, group: &str, consumer: &str, count: usize) -> Result<Vec<StreamEntry>> {
+        let options = StreamReadOptions::default()
+            .group(group, consumer)
+            .count(count)
+            .block(self.config.block_ms);
+
+        let mut conn = self.conn.clone();
+        let reply: Option<StreamReadReply> = conn
+            .xread_options(&[&self.config.stream_key], &[">"], &options)
+            .await
+            .context("XREADGROUP failed")?;
+
+        Ok(reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .map(StreamEntry::from_stream_id)
+            .collect())
+    }
+
+    /// Acknowledge processed entries, returning how many were pending
+    pub async fn ack(&self, group: &str, ids: &[String]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = self.conn.clone();
+        conn.xack(&self.config.stream_key, group, ids)
+            .await
+            .context("XACK failed")
+    }
+
+    /// Number of entries delivered to the configured group but not acknowledged
+    pub async fn pending_count(&self) -> Result<usize> {
+        let mut conn = self.conn.clone();
+        let reply: redis::streams::StreamPendingReply = conn
+            .xpending(&self.config.stream_key, &self.config.group_name)
+            .await
+            .context("XPENDING failed")?;
+        Ok(reply.count())
+    }
+}
+
+/// Publisher for chunk entries
+pub struct RedisStreamProducer {
+    conn: MultiplexedConnection,
+    stream_key: String,
+}
+
+impl RedisStreamProducer {
+    /// Connect to Redis
+    pub async fn new(config: &RedisConfig) -> Result<Self> {
+        let conn = connect(config).await?;
+        info!(url = %config.url, stream = %config.stream_key, "Redis stream producer created");
+        Ok(Self {
+            conn,
+            stream_key: config.stream_key.clone(),
+        })
+    }
+
+    /// Append a chunk to the stream, returning the entry id
+    #[instrument(skip(self, chunk), fields(chunk_id = %chunk.id))]
+    pub async fn publish(&self, chunk: &Chunk) -> Result<String> {
+        let mut conn = self.conn.clone();
+        conn.xadd(&self.stream_key, "*", &chunk_fields(chunk))
+            .await
+            .context("XADD failed")
+    }
+
+    /// Publish multiple chunks, stopping at the first failure
+    pub async fn publish_batch(&self, chunks: &[Chunk]) -> Result<Vec<String>> {
+        let mut ids = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            ids.push(self.publish(chunk).await?);
+        }
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChunkMetadata, SourceKind};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_chunk_fields_match_graph_format() {
+        let metadata = ChunkMetadata {
+            path: Some("src/lib.rs".to_string()),
+            language: Some("rust".to_string()),
+            ..Default::default()
+        };
+        let chunk = Chunk::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            SourceKind::CodeRepo,
+            "fn main() {}".to_string(),
+            4,
+            0,
+            12,
+            0,
+        )
+        .with_metadata(metadata);
+
+        let fields: HashMap<_, _> = chunk_fields(&chunk).into_iter().collect();
+        assert_eq!(fields["id"], chunk.id.to_string());
+        assert_eq!(fields["content"], "fn main() {}");
+        assert_eq!(fields["file_path"], "src/lib.rs");
+        assert_eq!(fields["language"], "rust");
+        assert!(!fields.contains_key("repo_name"));
+
+        let metadata: serde_json::Value = serde_json::from_str(&fields["metadata"]).unwrap();
+        assert_eq!(metadata["path"], "src/lib.rs");
+    }
+
+    #[test]
+    fn test_stream_entry_from_reply() {
+        let entry = StreamId {
+            id: "1700000000000-0".to_string(),
+            map: HashMap::from([
+                ("id".to_string(), redis::Value::Data(b"chunk-1".to_vec())),
+                ("content".to_string(), redis::Value::Data(b"hello".to_vec())),
+            ]),
+        };
+
+        let entry = StreamEntry::from_stream_id(entry);
+        assert_eq!(entry.id, "1700000000000-0");
+        assert_eq!(entry.fields["id"], "chunk-1");
+        assert_eq!(entry.fields["content"], "hello");
+    }
+}
@@ -0,0 +1,345 @@
+//! Confluent Schema Registry integration
+//!
+//! Messages in the Confluent wire format start with a zero magic byte and
+//! the 4-byte big-endian id of their writer schema, followed by the Avro
+//! datum. Writer schemas are fetched from the registry by id and cached by
+//! `schema_registry_converter`, then each datum is resolved against
+//! [`CODE_NORMALIZED_SCHEMA`] so older or newer producers still decode into
+//! [`CodeNormalizedEvent`].
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Context, Result};
+use apache_avro::types::Value as AvroValue;
+use apache_avro::Schema;
+use schema_registry_converter::async_impl::avro::{AvroDecoder, AvroEncoder};
+use schema_registry_converter::async_impl::schema_registry::{perform_sr_call, SrSettings};
+use schema_registry_converter::schema_registry_common::{SrCall, SubjectNameStrategy};
+use tokio::sync::RwLock;
+
+use super::kafka_consumer::CodeNormalizedEvent;
+
+/// Avro schema of `code.normalized` events, used as the reader schema
+pub const CODE_NORMALIZED_SCHEMA: &str = include_str!("../../schemas/code_normalized.avsc");
+
+/// First byte of a message in the Confluent wire format
+pub const CONFLUENT_MAGIC_BYTE: u8 = 0x00;
+
+/// Length of the magic byte and schema id prefix
+const CONFLUENT_HEADER_LEN: usize = 5;
+
+/// How a Kafka message payload is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KafkaMessageDecoder {
+    /// Plain JSON
+    Json,
+    /// Avro in the Confluent wire format, written with the registry's
+    /// schema `schema_id`
+    Avro { schema_id: u32 },
+}
+
+impl KafkaMessageDecoder {
+    /// Detect the encoding from the Confluent magic byte prefix
+    ///
+    /// JSON never starts with a zero byte, so anything else is JSON.
+    pub fn detect(payload: &[u8]) -> Self {
+        match payload {
+            [CONFLUENT_MAGIC_BYTE, id @ ..] if id.len() >= CONFLUENT_HEADER_LEN - 1 => Self::Avro {
+                schema_id: u32::from_be_bytes([id[0], id[1], id[2], id[3]]),
+            },
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Client for a Confluent Schema Registry
+pub struct SchemaRegistryClient {
+    settings: SrSettings,
+    /// Subject producers register `code.normalized` schemas under
+    subject: String,
+    decoder: AvroDecoder<'static>,
+    encoder: AvroEncoder<'static>,
+    reader_schema: Schema,
+    /// Writer schema ids known to be registered under the subject
+    subject_schema_ids: RwLock<HashSet<u32>>,
+}
+
+impl SchemaRegistryClient {
+    /// Create a client for the registry at `url`
+    pub fn new(url: &str, subject: &str) -> Self {
+        let url = url.trim_end_matches('/').to_string();
+        Self {
+            settings: SrSettings::new(url.clone()),
+            subject: subject.to_string(),
+            decoder: AvroDecoder::new(SrSettings::new(url.clone())),
+            encoder: AvroEncoder::new(SrSettings::new(url)),
+            reader_schema: Schema::parse_str(CODE_NORMALIZED_SCHEMA).expect("bundled code.normalized schema is valid"),
+            subject_schema_ids: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Subject of the registered schemas
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Decode an event in the Confluent wire format
+    ///
+    /// The writer schema must be registered under the client's subject, so
+    /// events of another type produced to the same topic are rejected
+    /// instead of being read as `code.normalized` events.
+    pub async fn decode(&self, payload: &[u8]) -> Result<CodeNormalizedEvent> {
+        let KafkaMessageDecoder::Avro { schema_id } = KafkaMessageDecoder::detect(payload) else {
+            bail!("Payload is not in the Confluent wire format");
+        };
+        self.ensure_subject_schema(schema_id).await?;
+        let decoded = self
+            .decoder
+            .decode(Some(payload))
+            .await
+            .with_context(|| format!("Failed to decode Avro datum with schema {}", schema_id))?;
+        let mut value = decoded
+            .value
+            .resolve(&self.reader_schema)
+            .with_context(|| format!("Schema {} is not compatible with the code.normalized schema", schema_id))?;
+        let metadata = match record_field(&mut value, "metadata") {
+            Some(field) => metadata_from_avro(std::mem::replace(field, AvroValue::Map(HashMap::new())))?,
+            None => HashMap::new(),
+        };
+        let mut event: CodeNormalizedEvent =
+            apache_avro::from_value(&value).context("Avro datum does not match CodeNormalizedEvent")?;
+        event.metadata = metadata;
+        Ok(event)
+    }
+
+    /// Encode an event with the subject's latest schema, e.g. for tests
+    /// and tools producing to the topic
+    pub async fn encode(&self, event: &CodeNormalizedEvent) -> Result<Vec<u8>> {
+        let strategy = SubjectNameStrategy::RecordNameStrategy(self.subject.clone());
+        let schema = self
+            .encoder
+            .get_schema_and_id(&self.subject, strategy.clone())
+            .await
+            .with_context(|| format!("Failed to fetch the latest {} schema", self.subject))?;
+
+        let mut value = apache_avro::to_value(event)?;
+        if let Some(field) = record_field(&mut value, "metadata") {
+            *field = metadata_to_avro(&event.metadata)?;
+        }
+        // serde maps u64 to an Avro fixed, the schema has a long
+        if let Some(field) = record_field(&mut value, "sequence_number") {
+            *field = match event.sequence_number {
+                Some(sequence) => AvroValue::Union(
+                    1,
+                    Box::new(AvroValue::Long(i64::try_from(sequence).context("Sequence number overflows an Avro long")?)),
+                ),
+                None => AvroValue::Union(0, Box::new(AvroValue::Null)),
+            };
+        }
+        let value = value
+            .resolve(&schema.parsed)
+            .with_context(|| format!("Event does not match the latest {} schema", self.subject))?;
+        self.encoder
+            .encode_value(value, &strategy)
+            .await
+            .with_context(|| format!("Failed to encode event with the latest {} schema", self.subject))
+    }
+
+    /// Check that the writer schema `schema_id` is a version of the subject
+    async fn ensure_subject_schema(&self, schema_id: u32) -> Result<()> {
+        if self.subject_schema_ids.read().await.contains(&schema_id) {
+            return Ok(());
+        }
+
+        let writer = self
+            .decoder
+            .get_schema(schema_id)
+            .await
+            .with_context(|| format!("Failed to fetch schema {}", schema_id))?;
+        let body = serde_json::json!({ "schema": writer.raw }).to_string();
+        perform_sr_call(&self.settings, SrCall::PostForVersion(&self.subject, &body))
+            .await
+            .with_context(|| format!("Schema {} is not registered under subject {}", schema_id, self.subject))?;
+        self.subject_schema_ids.write().await.insert(schema_id);
+        Ok(())
+    }
+}
+
+fn record_field<'a>(record: &'a mut AvroValue, field: &str) -> Option<&'a mut AvroValue> {
+    match record {
+        AvroValue::Record(fields) => fields.iter_mut().find(|(name, _)| name == field).map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+/// Convert event metadata to the schema's map of JSON scalars
+///
+/// Avro has no type for arbitrary JSON, so arrays and objects can only be
+/// sent in JSON messages.
+fn metadata_to_avro(metadata: &HashMap<String, serde_json::Value>) -> Result<AvroValue> {
+    let entries = metadata
+        .iter()
+        .map(|(key, value)| {
+            let (branch, value) = match value {
+                serde_json::Value::Null => (0, AvroValue::Null),
+                serde_json::Value::Bool(flag) => (1, AvroValue::Boolean(*flag)),
+                serde_json::Value::Number(number) => match number.as_i64() {
+                    Some(integer) => (2, AvroValue::Long(integer)),
+                    None => (3, AvroValue::Double(number.as_f64().unwrap_or(f64::NAN))),
+                },
+                serde_json::Value::String(text) => (4, AvroValue::String(text.clone())),
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    bail!("Metadata value {} is not a JSON scalar", key)
+                }
+            };
+            Ok((key.clone(), AvroValue::Union(branch, Box::new(value))))
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+    Ok(AvroValue::Map(entries))
+}
+
+fn metadata_from_avro(value: AvroValue) -> Result<HashMap<String, serde_json::Value>> {
+    let AvroValue::Map(entries) = value else {
+        bail!("Avro metadata is not a map");
+    };
+    entries
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                AvroValue::Union(_, value) => *value,
+                value => value,
+            };
+            let json = match value {
+                AvroValue::Null => serde_json::Value::Null,
+                AvroValue::Boolean(flag) => flag.into(),
+                AvroValue::Long(integer) => integer.into(),
+                AvroValue::Double(number) => number.into(),
+                AvroValue::String(text) => text.into(),
+                other => bail!("Unsupported Avro metadata value for {}: {:?}", key, other),
+            };
+            Ok((key, json))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::extract::Path;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+
+    use crate::messaging::kafka_consumer::{CodeEntity, ConsumerConfig};
+    use crate::messaging::KafkaChunkConsumer;
+
+    const SCHEMA_ID: u32 = 7;
+
+    /// Registry serving `CODE_NORMALIZED_SCHEMA` as id 7 under `subject`,
+    /// counting lookups by id
+    async fn mock_registry(subject: &'static str) -> (String, Arc<AtomicUsize>) {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&lookups);
+        let app = Router::new()
+            .route(
+                "/schemas/ids/:id",
+                get(move |Path(id): Path<u32>| async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    if id != SCHEMA_ID {
+                        return Err(StatusCode::NOT_FOUND);
+                    }
+                    Ok(Json(serde_json::json!({ "schema": CODE_NORMALIZED_SCHEMA })))
+                }),
+            )
+            .route(
+                "/subjects/:subject/versions/latest",
+                get(|Path(subject): Path<String>| async move {
+                    Json(serde_json::json!({ "subject": subject, "version": 1, "id": SCHEMA_ID, "schema": CODE_NORMALIZED_SCHEMA }))
+                }),
+            )
+            .route(
+                "/subjects/:subject",
+                post(move |Path(requested): Path<String>| async move {
+                    if requested != subject {
+                        return Err(StatusCode::NOT_FOUND);
+                    }
+                    Ok(Json(serde_json::json!({ "subject": subject, "version": 1, "id": SCHEMA_ID, "schema": CODE_NORMALIZED_SCHEMA })))
+                }),
+            );
+        (crate::test_support::serve_mock(app).await, lookups)
+    }
+
+    fn event() -> CodeNormalizedEvent {
+        CodeNormalizedEvent {
+            event_id: "evt-1".to_string(),
+            source_id: "repo-42".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            language: "rust".to_string(),
+            normalized_content: "fn main() {}".to_string(),
+            entities: vec![CodeEntity {
+                entity_type: "function".to_string(),
+                name: "main".to_string(),
+                start_line: 1,
+                end_line: 1,
+                content: "fn main() {}".to_string(),
+                decorators: vec!["inline".to_string()],
+            }],
+            metadata: HashMap::from([
+                ("repo".to_string(), serde_json::json!("acme/api")),
+                ("stars".to_string(), serde_json::json!(42)),
+                ("archived".to_string(), serde_json::json!(false)),
+                ("license".to_string(), serde_json::Value::Null),
+            ]),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            sequence_number: Some(4),
+            is_last: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consumer_decodes_avro_and_json() {
+        let (url, lookups) = mock_registry("code.normalized-value").await;
+        let registry = SchemaRegistryClient::new(&url, "code.normalized-value");
+        let avro = registry.encode(&event()).await.unwrap();
+        assert_eq!(KafkaMessageDecoder::detect(&avro), KafkaMessageDecoder::Avro { schema_id: SCHEMA_ID });
+
+        let consumer = KafkaChunkConsumer::new(ConsumerConfig::default())
+            .unwrap()
+            .with_schema_registry(&url, "code.normalized-value");
+        let expected = serde_json::to_value(event()).unwrap();
+        for _ in 0..2 {
+            let decoded = consumer.decode_event(&avro).await.unwrap();
+            assert_eq!(serde_json::to_value(decoded).unwrap(), expected);
+        }
+        // The schema is fetched once, then cached
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        let json = serde_json::to_vec(&event()).unwrap();
+        assert_eq!(KafkaMessageDecoder::detect(&json), KafkaMessageDecoder::Json);
+        assert_eq!(serde_json::to_value(consumer.decode_event(&json).await.unwrap()).unwrap(), expected);
+
+        let mut unknown = avro.clone();
+        unknown[4] = 99;
+        let error = consumer.decode_event(&unknown).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("404"), "{:#}", error);
+    }
+
+    #[tokio::test]
+    async fn test_decode_rejects_schema_of_another_subject() {
+        let (url, lookups) = mock_registry("code.normalized-value").await;
+        let avro = SchemaRegistryClient::new(&url, "code.normalized-value").encode(&event()).await.unwrap();
+        let registry = SchemaRegistryClient::new(&url, "code.normalized-value");
+        for _ in 0..2 {
+            let decoded = registry.decode(&avro).await.unwrap();
+            assert_eq!(decoded.metadata, event().metadata);
+            assert_eq!(decoded.sequence_number, Some(4));
+        }
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        let error = SchemaRegistryClient::new(&url, "user.events-value").decode(&avro).await.unwrap_err();
+        assert!(error.to_string().contains("not registered under subject user.events-value"), "{}", error);
+    }
+}
@@ -1,17 +1,122 @@
 //! HTTP client for sending chunks to the embedding service.
+//!
+//! Failed requests are retried with exponential backoff, and a circuit
+//! breaker stops calling the service after repeated failures. When fallback
+//! endpoints are configured, a batch that fails on the primary is retried on
+//! each fallback in turn.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use reqwest::Client;
+use rand::seq::SliceRandom;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+use crate::messaging::circuit_breaker::{CircuitBreaker, CircuitConfig};
+use crate::telemetry::{inject_trace_context, metrics};
 use crate::types::Chunk;
 
+/// Retry and circuit breaker settings for the embedding client.
+#[derive(Debug, Clone)]
+pub struct EmbeddingClientConfig {
+    /// Retries after the first attempt for 5xx and connection errors
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each further retry
+    pub base_delay_ms: u64,
+    /// Consecutive failures before the circuit opens
+    pub circuit_breaker_threshold: u32,
+    /// How long the circuit stays open before a trial request
+    pub circuit_breaker_reset_ms: u64,
+    /// Consecutive failed batches before a working fallback becomes primary
+    pub failover_threshold: u32,
+}
+
+impl Default for EmbeddingClientConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_ms: 200,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_reset_ms: 30_000,
+            failover_threshold: 3,
+        }
+    }
+}
+
+/// Errors from the embedding client.
+#[derive(Debug, thiserror::Error)]
+pub enum EmbeddingClientError {
+    /// The circuit is open; no request was made.
+    #[error("embedding service circuit is open")]
+    CircuitOpen,
+    #[error("embedding service request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("embedding service returned {status}: {body}")]
+    Status { status: StatusCode, body: String },
+}
+
+impl EmbeddingClientError {
+    /// Whether the request may succeed if retried.
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::CircuitOpen => false,
+            Self::Request(e) => e.is_connect() || e.is_timeout(),
+            Self::Status { status, .. } => status.is_server_error(),
+        }
+    }
+}
+
+/// Order in which fallback endpoints are tried.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FallbackStrategy {
+    /// In the order they were configured
+    #[default]
+    Sequential,
+    /// The endpoint whose last failure is oldest first
+    LeastRecentlyFailed,
+    /// In random order
+    Random,
+}
+
+/// A single embedding service instance.
+struct Endpoint {
+    url: String,
+    circuit: CircuitBreaker,
+    /// Consecutive batches that failed on this endpoint
+    failures: AtomicU32,
+    last_failure: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn new(url: &str, config: &EmbeddingClientConfig) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            circuit: circuit_breaker(config),
+            failures: AtomicU32::new(0),
+            last_failure: Mutex::new(None),
+        }
+    }
+
+    fn record_failure(&self) -> u32 {
+        *self.last_failure.lock().unwrap() = Some(Instant::now());
+        self.failures.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
 /// Client for sending chunks to the embedding service.
 pub struct EmbeddingClient {
     client: Client,
-    base_url: String,
+    /// Configured endpoints; fallbacks follow the initial primary
+    endpoints: Vec<Endpoint>,
+    /// Index of the current primary endpoint
+    primary: AtomicUsize,
+    strategy: FallbackStrategy,
     batch_size: usize,
+    config: EmbeddingClientConfig,
 }
 
 /// Request payload for embedding chunks.
@@ -41,16 +146,48 @@ struct EmbedChunksResponse {
 impl EmbeddingClient {
     /// Create a new embedding client.
     pub fn new(base_url: &str) -> Self {
+        Self::with_fallbacks(base_url, Vec::new())
+    }
+
+    /// Create a client that fails over to `fallbacks` when `primary` fails.
+    pub fn with_fallbacks(primary: &str, fallbacks: Vec<&str>) -> Self {
+        let config = EmbeddingClientConfig::default();
         Self {
             client: Client::builder()
                 .timeout(std::time::Duration::from_secs(30))
                 .build()
                 .expect("Failed to create HTTP client"),
-            base_url: base_url.to_string(),
+            endpoints: std::iter::once(primary)
+                .chain(fallbacks)
+                .map(|url| Endpoint::new(url, &config))
+                .collect(),
+            primary: AtomicUsize::new(0),
+            strategy: FallbackStrategy::default(),
             batch_size: 50,
+            config,
         }
     }
 
+    /// Set the retry and circuit breaker settings.
+    pub fn with_config(mut self, config: EmbeddingClientConfig) -> Self {
+        for endpoint in &mut self.endpoints {
+            endpoint.circuit = circuit_breaker(&config);
+        }
+        self.config = config;
+        self
+    }
+
+    /// Set the order in which fallback endpoints are tried.
+    pub fn with_fallback_strategy(mut self, strategy: FallbackStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// URL of the endpoint currently tried first.
+    pub fn current_primary(&self) -> &str {
+        &self.endpoints[self.primary.load(Ordering::SeqCst)].url
+    }
+
     /// Set the batch size for sending chunks.
     pub fn with_batch_size(mut self, size: usize) -> Self {
         self.batch_size = size;
@@ -58,13 +195,21 @@ impl EmbeddingClient {
     }
 
     /// Send chunks to the embedding service.
+    ///
+    /// Fails with [`EmbeddingClientError::CircuitOpen`] if the circuit of
+    /// every endpoint is open, without contacting the service.
+    #[tracing::instrument(skip_all, fields(chunk.count = chunks.len()))]
     pub async fn send_chunks(&self, chunks: &[Chunk]) -> Result<usize> {
         if chunks.is_empty() {
             return Ok(0);
         }
+        if self.endpoints.iter().all(|endpoint| endpoint.circuit.is_open()) {
+            return Err(EmbeddingClientError::CircuitOpen.into());
+        }
 
         info!(chunk_count = chunks.len(), "Sending chunks to embedding service");
 
+        let started = Instant::now();
         let mut total_embedded = 0;
 
         // Send in batches
@@ -74,6 +219,10 @@ impl EmbeddingClient {
                     total_embedded += count;
                     debug!(batch_size = batch.len(), embedded = count, "Batch sent successfully");
                 }
+                Err(EmbeddingClientError::CircuitOpen) => {
+                    error!("Embedding service circuit opened, skipping remaining batches");
+                    break;
+                }
                 Err(e) => {
                     error!(error = %e, "Failed to send batch to embedding service");
                     // Continue with other batches
@@ -81,12 +230,13 @@ impl EmbeddingClient {
             }
         }
 
+        metrics::record_embedding_send(started.elapsed());
         info!(total_embedded, "Finished sending chunks to embedding service");
         Ok(total_embedded)
     }
 
-    /// Send a single batch of chunks.
-    async fn send_batch(&self, chunks: &[Chunk]) -> Result<usize> {
+    /// Send a single batch of chunks, failing over to fallback endpoints.
+    async fn send_batch(&self, chunks: &[Chunk]) -> Result<usize, EmbeddingClientError> {
         let request = EmbedChunksRequest {
             chunks: chunks
                 .iter()
@@ -100,12 +250,110 @@ impl EmbeddingClient {
                 .collect(),
         };
 
-        let url = format!("{}/embed/chunks", self.base_url);
+        let primary = self.primary.load(Ordering::SeqCst);
+        let mut last_error = EmbeddingClientError::CircuitOpen;
+
+        for index in self.endpoint_order(primary) {
+            let endpoint = &self.endpoints[index];
+            match self.send_with_retries(endpoint, &request).await {
+                Ok(count) => {
+                    endpoint.failures.store(0, Ordering::SeqCst);
+                    if index != primary {
+                        self.promote_if_failing(primary, index);
+                    }
+                    return Ok(count);
+                }
+                Err(EmbeddingClientError::CircuitOpen) => {}
+                Err(e) if e.is_retryable() => {
+                    endpoint.record_failure();
+                    warn!(url = %endpoint.url, error = %e, "Embedding endpoint failed");
+                    last_error = e;
+                }
+                // The request itself was rejected; other endpoints would too
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Endpoint indices in the order to try them: the primary, then the
+    /// fallbacks ordered by the fallback strategy.
+    fn endpoint_order(&self, primary: usize) -> Vec<usize> {
+        let mut fallbacks: Vec<usize> = (0..self.endpoints.len()).filter(|&i| i != primary).collect();
+        match self.strategy {
+            FallbackStrategy::Sequential => {}
+            FallbackStrategy::LeastRecentlyFailed => {
+                fallbacks.sort_by_key(|&i| *self.endpoints[i].last_failure.lock().unwrap());
+            }
+            FallbackStrategy::Random => fallbacks.shuffle(&mut rand::thread_rng()),
+        }
+        std::iter::once(primary).chain(fallbacks).collect()
+    }
+
+    /// Make `fallback` the primary if the primary keeps failing.
+    fn promote_if_failing(&self, primary: usize, fallback: usize) {
+        if self.endpoints[primary].failures.load(Ordering::SeqCst) < self.config.failover_threshold {
+            return;
+        }
+        if self
+            .primary
+            .compare_exchange(primary, fallback, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            warn!(
+                from = %self.endpoints[primary].url,
+                to = %self.endpoints[fallback].url,
+                "Promoted fallback embedding endpoint to primary"
+            );
+        }
+    }
+
+    /// Send a request to one endpoint, retrying 5xx and connection errors.
+    async fn send_with_retries(
+        &self,
+        endpoint: &Endpoint,
+        request: &EmbedChunksRequest,
+    ) -> Result<usize, EmbeddingClientError> {
+        let mut attempt = 0;
+        loop {
+            if !endpoint.circuit.allow_request() {
+                return Err(EmbeddingClientError::CircuitOpen);
+            }
+
+            let error = match self.post_batch(&endpoint.url, request).await {
+                Ok(count) => {
+                    endpoint.circuit.record_success();
+                    return Ok(count);
+                }
+                Err(e) if e.is_retryable() => {
+                    endpoint.circuit.record_failure();
+                    e
+                }
+                // The endpoint answered and only rejected the request, so
+                // it counts as up and a half-open probe is not left pending
+                Err(e) => {
+                    endpoint.circuit.record_success();
+                    return Err(e);
+                }
+            };
+
+            if attempt >= self.config.max_retries {
+                return Err(error);
+            }
+            let delay = Duration::from_millis(self.config.base_delay_ms.saturating_mul(1 << attempt.min(16)));
+            warn!(url = %endpoint.url, attempt = attempt + 1, ?delay, error = %error, "Retrying embedding request");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&request)
+    /// Make a single request for a batch.
+    async fn post_batch(&self, base_url: &str, request: &EmbedChunksRequest) -> Result<usize, EmbeddingClientError> {
+        let url = format!("{}/embed/chunks", base_url);
+
+        let response = inject_trace_context(self.client.post(&url))
+            .json(request)
             .send()
             .await?;
 
@@ -119,32 +367,55 @@ impl EmbeddingClient {
             Ok(result.embedded_count)
         } else {
             let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            Err(anyhow::anyhow!(
-                "Embedding service returned {}: {}",
-                status,
-                text
-            ))
+            let body = response.text().await.unwrap_or_default();
+            Err(EmbeddingClientError::Status { status, body })
         }
     }
 
-    /// Check if the embedding service is healthy.
+    /// Check if the primary embedding endpoint is healthy.
     pub async fn health_check(&self) -> Result<bool> {
-        let url = format!("{}/health", self.base_url);
+        Ok(self.endpoint_healthy(self.current_primary()).await)
+    }
+
+    /// Check the health of every configured endpoint, keyed by URL.
+    pub async fn health_check_all(&self) -> HashMap<String, bool> {
+        let checks = self.endpoints.iter().map(|endpoint| async move {
+            (endpoint.url.clone(), self.endpoint_healthy(&endpoint.url).await)
+        });
+        futures::future::join_all(checks).await.into_iter().collect()
+    }
+
+    async fn endpoint_healthy(&self, base_url: &str) -> bool {
+        let url = format!("{}/health", base_url);
 
         match self.client.get(&url).send().await {
-            Ok(response) => Ok(response.status().is_success()),
-            Err(_) => Ok(false),
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
         }
     }
 }
 
+fn circuit_breaker(config: &EmbeddingClientConfig) -> CircuitBreaker {
+    CircuitBreaker::new(CircuitConfig {
+        failure_threshold: config.circuit_breaker_threshold,
+        recovery_timeout_override: Some(Duration::from_millis(config.circuit_breaker_reset_ms)),
+        half_open_max_calls: 1,
+        exponential_backoff: false,
+        ..Default::default()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::SourceKind;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
     use uuid::Uuid;
 
+    use crate::messaging::circuit_breaker::CircuitState;
+    use crate::types::SourceKind;
+
     #[test]
     fn test_client_creation() {
         let client = EmbeddingClient::new("http://localhost:3018");
@@ -156,4 +427,123 @@ mod tests {
         let client = EmbeddingClient::new("http://localhost:3018").with_batch_size(100);
         assert_eq!(client.batch_size, 100);
     }
+
+    /// Serve `/embed/chunks`, failing the first `failures` requests with 503.
+    async fn flaky_service(failures: usize) -> (String, Arc<AtomicUsize>) {
+        use axum::{http::StatusCode, routing::{get, post}, Json, Router};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+        let app = Router::new().route(
+            "/embed/chunks",
+            post(move || async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    Err(StatusCode::SERVICE_UNAVAILABLE)
+                } else {
+                    Ok(Json(serde_json::json!({ "embedded_count": 1 })))
+                }
+            }),
+        )
+        .route("/health", get(|| async {}));
+        (crate::test_support::serve_mock(app).await, hits)
+    }
+
+    fn chunk() -> Chunk {
+        Chunk::new(Uuid::nil(), Uuid::nil(), SourceKind::Document, "hello".to_string(), 1, 0, 5, 0)
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        let (url, hits) = flaky_service(2).await;
+        let client = EmbeddingClient::new(&url).with_config(EmbeddingClientConfig {
+            base_delay_ms: 1,
+            ..Default::default()
+        });
+
+        assert_eq!(client.send_chunks(&[chunk()]).await.unwrap(), 1);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_fails_without_request() {
+        let (url, hits) = flaky_service(usize::MAX).await;
+        let client = EmbeddingClient::new(&url).with_config(EmbeddingClientConfig {
+            max_retries: 1,
+            base_delay_ms: 1,
+            circuit_breaker_threshold: 2,
+            circuit_breaker_reset_ms: 60_000,
+            ..Default::default()
+        });
+
+        // Both attempts fail, which opens the circuit
+        assert_eq!(client.send_chunks(&[chunk()]).await.unwrap(), 0);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        let err = client.send_chunks(&[chunk()]).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<EmbeddingClientError>(),
+            Some(EmbeddingClientError::CircuitOpen)
+        ));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_closes_after_successful_trial_request() {
+        let (url, hits) = flaky_service(2).await;
+        let client = EmbeddingClient::new(&url).with_config(EmbeddingClientConfig {
+            max_retries: 1,
+            base_delay_ms: 1,
+            circuit_breaker_threshold: 2,
+            circuit_breaker_reset_ms: 20,
+            ..Default::default()
+        });
+
+        assert_eq!(client.send_chunks(&[chunk()]).await.unwrap(), 0);
+        assert_eq!(client.endpoints[0].circuit.state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(client.send_chunks(&[chunk()]).await.unwrap(), 1);
+        assert_eq!(client.endpoints[0].circuit.state(), CircuitState::Closed);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_and_promotes_fallback() {
+        let (primary, primary_hits) = flaky_service(usize::MAX).await;
+        let (fallback, fallback_hits) = flaky_service(0).await;
+        let client = EmbeddingClient::with_fallbacks(&primary, vec![&fallback]).with_config(EmbeddingClientConfig {
+            max_retries: 0,
+            failover_threshold: 2,
+            ..Default::default()
+        });
+
+        assert_eq!(client.send_chunks(&[chunk()]).await.unwrap(), 1);
+        assert_eq!(client.current_primary(), primary);
+
+        assert_eq!(client.send_chunks(&[chunk()]).await.unwrap(), 1);
+        assert_eq!(client.current_primary(), fallback);
+        assert_eq!(primary_hits.load(Ordering::SeqCst), 2);
+        assert_eq!(fallback_hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_least_recently_failed_order() {
+        let client = EmbeddingClient::with_fallbacks("http://a", vec!["http://b", "http://c", "http://d"])
+            .with_fallback_strategy(FallbackStrategy::LeastRecentlyFailed);
+        client.endpoints[1].record_failure();
+        client.endpoints[2].record_failure();
+
+        assert_eq!(client.endpoint_order(0), vec![0, 3, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_all() {
+        let (healthy, _) = flaky_service(0).await;
+        let client = EmbeddingClient::with_fallbacks(&healthy, vec!["http://127.0.0.1:1"]);
+
+        let health = client.health_check_all().await;
+        assert_eq!(health.len(), 2);
+        assert!(health[&healthy]);
+        assert!(!health["http://127.0.0.1:1"]);
+    }
 }
@@ -1,7 +1,11 @@
 //! Output module for sending chunks to downstream services.
 
 mod embedding_client;
-mod relation_graph_client;
+pub(crate) mod relation_graph_client;
+mod webhooks;
 
-pub use embedding_client::EmbeddingClient;
+pub use embedding_client::{EmbeddingClient, EmbeddingClientConfig, EmbeddingClientError, FallbackStrategy};
 pub use relation_graph_client::{RelationGraphClient, IngestChunksResponse};
+pub use webhooks::{
+    check_webhook_url, deliver_webhook, sign_payload, Webhook, WebhookEvent, WebhookRegistration, WebhookStore, SIGNATURE_HEADER,
+};
@@ -7,11 +7,17 @@
 //! - Build the knowledge graph in Neo4j
 //! - Create cross-source links between code and documentation
 
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
+use futures::future::join_all;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
 use tracing::{debug, error, info, warn};
 
+use crate::telemetry::{inject_trace_context, metrics};
 use crate::types::Chunk;
 
 /// Client for sending chunks to the relation-graph service.
@@ -20,6 +26,14 @@ pub struct RelationGraphClient {
     base_url: String,
     batch_size: usize,
     enabled: bool,
+    /// Whether chunk IDs are content-derived, so re-sent chunks replace
+    /// their previous version
+    deterministic_ids: bool,
+    /// Permits for in-flight batch requests, shared by concurrent
+    /// `send_chunks` calls
+    in_flight: Arc<Semaphore>,
+    /// Give up on a batch request after this long
+    timeout_per_batch: Option<Duration>,
 }
 
 /// Request payload for ingesting chunks into the relation-graph.
@@ -34,23 +48,28 @@ struct IngestChunksRequest {
 
 /// Chunk data sent to relation-graph service.
 #[derive(Debug, Serialize)]
-struct ChunkForGraph {
-    id: String,
-    content: String,
-    source_kind: String,
-    source_type: String,
-    source_id: String,
+pub(crate) struct ChunkForGraph {
+    pub(crate) id: String,
+    pub(crate) content: String,
+    pub(crate) source_kind: String,
+    pub(crate) source_type: String,
+    pub(crate) source_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) file_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    file_path: Option<String>,
+    pub(crate) repo_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    repo_name: Option<String>,
+    pub(crate) language: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    language: Option<String>,
+    pub(crate) heading_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    heading_path: Option<String>,
+    pub(crate) owner_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    owner_id: Option<String>,
-    metadata: serde_json::Value,
+    pub(crate) parent_chunk_id: Option<String>,
+    /// Replace an existing chunk with the same ID instead of adding a new one
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) is_upsert: bool,
+    pub(crate) metadata: serde_json::Value,
 }
 
 /// Response from relation-graph service.
@@ -74,6 +93,9 @@ impl RelationGraphClient {
             base_url: base_url.to_string(),
             batch_size: 50,
             enabled: true,
+            deterministic_ids: false,
+            in_flight: Arc::new(Semaphore::new(1)),
+            timeout_per_batch: None,
         }
     }
 
@@ -84,6 +106,9 @@ impl RelationGraphClient {
             base_url: String::new(),
             batch_size: 50,
             enabled: false,
+            deterministic_ids: false,
+            in_flight: Arc::new(Semaphore::new(1)),
+            timeout_per_batch: None,
         }
     }
 
@@ -93,12 +118,33 @@ impl RelationGraphClient {
         self
     }
 
+    /// Send up to `n` batches concurrently (default 1).
+    pub fn with_parallelism(mut self, n: usize) -> Self {
+        self.in_flight = Arc::new(Semaphore::new(n.max(1)));
+        self
+    }
+
+    /// Fail a batch whose request takes longer than `duration`, on top of
+    /// the client's overall 60s timeout.
+    pub fn with_timeout_per_batch(mut self, duration: Duration) -> Self {
+        self.timeout_per_batch = Some(duration);
+        self
+    }
+
+    /// Mark chunks as upserts, for when chunk IDs are content-derived
+    /// (`ChunkConfig::use_deterministic_ids`).
+    pub fn with_deterministic_ids(mut self, enabled: bool) -> Self {
+        self.deterministic_ids = enabled;
+        self
+    }
+
     /// Check if the client is enabled.
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
 
     /// Send chunks to the relation-graph service for knowledge graph construction.
+    #[tracing::instrument(skip_all, fields(chunk.count = chunks.len()))]
     pub async fn send_chunks(&self, chunks: &[Chunk]) -> Result<IngestChunksResponse> {
         if !self.enabled {
             debug!("Relation-graph client is disabled, skipping");
@@ -120,6 +166,7 @@ impl RelationGraphClient {
         }
 
         info!(chunk_count = chunks.len(), "Sending chunks to relation-graph service");
+        let started = Instant::now();
 
         let mut total_response = IngestChunksResponse {
             chunks_processed: 0,
@@ -128,16 +175,31 @@ impl RelationGraphClient {
             errors: vec![],
         };
 
-        // Send in batches
-        for batch in chunks.chunks(self.batch_size) {
-            match self.send_batch(batch).await {
+        // Send batches concurrently, up to the parallelism limit; a failed
+        // batch does not stop the others
+        let batch_futures = chunks.chunks(self.batch_size.max(1)).map(|batch| async move {
+            let result = async {
+                let _permit = self.in_flight.acquire().await?;
+                match self.timeout_per_batch {
+                    Some(timeout) => tokio::time::timeout(timeout, self.send_batch(batch))
+                        .await
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("Relation-graph batch timed out after {:?}", timeout))),
+                    None => self.send_batch(batch).await,
+                }
+            }
+            .await;
+            (batch.len(), result)
+        });
+
+        for (batch_size, result) in join_all(batch_futures).await {
+            match result {
                 Ok(response) => {
                     total_response.chunks_processed += response.chunks_processed;
                     total_response.entities_created += response.entities_created;
                     total_response.relationships_created += response.relationships_created;
                     total_response.errors.extend(response.errors);
                     debug!(
-                        batch_size = batch.len(),
+                        batch_size,
                         entities = response.entities_created,
                         "Batch sent successfully to relation-graph"
                     );
@@ -156,6 +218,7 @@ impl RelationGraphClient {
             relationships_created = total_response.relationships_created,
             "Finished sending chunks to relation-graph service"
         );
+        metrics::record_relation_graph_send(started.elapsed());
 
         Ok(total_response)
     }
@@ -170,9 +233,7 @@ impl RelationGraphClient {
 
         let url = format!("{}/api/graph/chunks", self.base_url);
 
-        let response = self
-            .client
-            .post(&url)
+        let response = inject_trace_context(self.client.post(&url))
             .json(&request)
             .send()
             .await?;
@@ -198,6 +259,30 @@ impl RelationGraphClient {
 
     /// Convert a Chunk to the format expected by relation-graph service.
     fn chunk_to_graph_format(&self, chunk: &Chunk) -> ChunkForGraph {
+        ChunkForGraph {
+            is_upsert: self.deterministic_ids,
+            ..ChunkForGraph::from_chunk(chunk)
+        }
+    }
+
+    /// Check if the relation-graph service is healthy.
+    pub async fn health_check(&self) -> Result<bool> {
+        if !self.enabled {
+            return Ok(false);
+        }
+
+        let url = format!("{}/health", self.base_url);
+
+        match self.client.get(&url).send().await {
+            Ok(response) => Ok(response.status().is_success()),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+impl ChunkForGraph {
+    /// Build the relation-graph representation of a chunk.
+    pub(crate) fn from_chunk(chunk: &Chunk) -> Self {
         let metadata = serde_json::to_value(&chunk.metadata).unwrap_or_default();
         
         // Extract fields from metadata if available
@@ -236,23 +321,11 @@ impl RelationGraphClient {
             language,
             heading_path,
             owner_id,
+            parent_chunk_id: chunk.parent_chunk_id.map(|id| id.to_string()),
+            is_upsert: false,
             metadata,
         }
     }
-
-    /// Check if the relation-graph service is healthy.
-    pub async fn health_check(&self) -> Result<bool> {
-        if !self.enabled {
-            return Ok(false);
-        }
-
-        let url = format!("{}/health", self.base_url);
-
-        match self.client.get(&url).send().await {
-            Ok(response) => Ok(response.status().is_success()),
-            Err(_) => Ok(false),
-        }
-    }
 }
 
 #[cfg(test)]
@@ -277,4 +350,100 @@ mod tests {
         let client = RelationGraphClient::new("http://localhost:3018").with_batch_size(100);
         assert_eq!(client.batch_size, 100);
     }
+
+    #[test]
+    fn test_upsert_with_deterministic_ids() {
+        let chunk = Chunk::new(
+            uuid::Uuid::nil(),
+            uuid::Uuid::nil(),
+            crate::types::SourceKind::Document,
+            "text".to_string(),
+            1,
+            0,
+            4,
+            0,
+        );
+
+        let plain = RelationGraphClient::new("http://localhost:3018").chunk_to_graph_format(&chunk);
+        assert!(serde_json::to_value(&plain).unwrap().get("is_upsert").is_none());
+
+        let client = RelationGraphClient::new("http://localhost:3018").with_deterministic_ids(true);
+        let graph = serde_json::to_value(client.chunk_to_graph_format(&chunk)).unwrap();
+        assert_eq!(graph["is_upsert"], true);
+    }
+
+    /// Mock relation-graph service taking `delay` per batch, failing
+    /// batches containing a `fail` chunk; returns its URL and the highest
+    /// number of requests it saw in flight
+    async fn slow_service(delay: Duration) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use axum::{http::StatusCode, routing::post, Json, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let max = Arc::clone(&max_in_flight);
+        let app = Router::new().route(
+            "/api/graph/chunks",
+            post(move |Json(request): Json<serde_json::Value>| {
+                let (in_flight, max) = (Arc::clone(&in_flight), Arc::clone(&max));
+                async move {
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(delay).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                    let chunks = request["chunks"].as_array().unwrap();
+                    if chunks.iter().any(|c| c["content"] == "fail") {
+                        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                    }
+                    Ok(Json(serde_json::json!({
+                        "chunks_processed": chunks.len(),
+                        "entities_created": 0,
+                        "relationships_created": 0,
+                    })))
+                }
+            }),
+        );
+        (crate::test_support::serve_mock(app).await, max_in_flight)
+    }
+
+    fn chunks(contents: &[&str]) -> Vec<Chunk> {
+        contents
+            .iter()
+            .map(|content| {
+                let len = content.len();
+                Chunk::new(uuid::Uuid::nil(), uuid::Uuid::nil(), crate::types::SourceKind::Document, content.to_string(), 1, 0, len, 0)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_parallel_batches_with_partial_failure() {
+        let (url, max_in_flight) = slow_service(Duration::from_millis(50)).await;
+        let client = RelationGraphClient::new(&url).with_batch_size(2).with_parallelism(4);
+
+        // 8 batches, the third of which fails
+        let mut contents = vec!["ok"; 16];
+        contents[5] = "fail";
+        let response = client.send_chunks(&chunks(&contents)).await.unwrap();
+
+        assert_eq!(response.chunks_processed, 14);
+        assert_eq!(response.errors.len(), 1);
+        assert!(response.errors[0].contains("500"), "{}", response.errors[0]);
+        assert_eq!(max_in_flight.load(std::sync::atomic::Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_batch_timeout() {
+        let (url, _) = slow_service(Duration::from_secs(5)).await;
+        let client = RelationGraphClient::new(&url)
+            .with_parallelism(2)
+            .with_timeout_per_batch(Duration::from_millis(100));
+
+        let started = Instant::now();
+        let response = client.send_chunks(&chunks(&["ok"])).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(response.chunks_processed, 0);
+        assert!(response.errors[0].contains("timed out"));
+    }
 }
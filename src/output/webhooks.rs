@@ -0,0 +1,394 @@
+//! Webhook registrations and delivery for job notifications.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Header carrying the HMAC-SHA256 signature of the request body.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Retries after a failed delivery.
+const MAX_DELIVERY_RETRIES: u32 = 3;
+
+/// Delay before the first retry; doubled on each further attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Events a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A job finished processing all items
+    JobCompleted,
+    /// A job failed
+    JobFailed,
+    /// A job's chunks are stored and available for export
+    ChunksBatchReady,
+}
+
+/// Request to register a webhook.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookRegistration {
+    /// URL notifications are POSTed to
+    pub url: String,
+    /// Events to notify about
+    pub events: Vec<WebhookEvent>,
+    /// Secret used to sign notification bodies
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+// Keeps the secret out of logs
+impl fmt::Debug for WebhookRegistration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookRegistration")
+            .field("url", &self.url)
+            .field("events", &self.events)
+            .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// A registered webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub registration: WebhookRegistration,
+    pub created_at: DateTime<Utc>,
+    /// Subject of the token the webhook was registered with, if any
+    #[serde(default)]
+    pub owner_id: Option<String>,
+}
+
+impl Webhook {
+    /// Whether this webhook subscribes to an event.
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.registration.events.contains(&event)
+    }
+
+    /// Whether this webhook belongs to `owner`; webhooks registered without
+    /// a token belong to anonymous callers.
+    pub fn is_owned_by(&self, owner: Option<&str>) -> bool {
+        self.owner_id.as_deref() == owner
+    }
+}
+
+/// Store of webhook registrations, optionally persisted to a JSON file.
+#[derive(Debug, Default)]
+pub struct WebhookStore {
+    webhooks: Vec<Webhook>,
+    path: Option<PathBuf>,
+}
+
+impl WebhookStore {
+    /// Create an empty in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a store persisted at the given path, loading any saved webhooks.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let webhooks = if path.exists() {
+            let data = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read webhook store {}", path.display()))?;
+            serde_json::from_str(&data)
+                .with_context(|| format!("Failed to parse webhook store {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            webhooks,
+            path: Some(path),
+        })
+    }
+
+    /// Register a webhook and return it.
+    pub fn register(&mut self, registration: WebhookRegistration) -> Result<Webhook> {
+        self.register_owned(registration, None)
+    }
+
+    /// Register a webhook owned by `owner_id` and return it.
+    pub fn register_owned(&mut self, registration: WebhookRegistration, owner_id: Option<String>) -> Result<Webhook> {
+        let webhook = Webhook {
+            id: Uuid::new_v4(),
+            registration,
+            created_at: Utc::now(),
+            owner_id,
+        };
+        self.webhooks.push(webhook.clone());
+        self.save()?;
+        Ok(webhook)
+    }
+
+    /// Remove a webhook. Returns whether it existed.
+    pub fn remove(&mut self, id: Uuid) -> Result<bool> {
+        self.remove_if(|w| w.id == id)
+    }
+
+    /// Remove a webhook belonging to `owner`. Returns whether it existed.
+    pub fn remove_owned(&mut self, id: Uuid, owner: Option<&str>) -> Result<bool> {
+        self.remove_if(|w| w.id == id && w.is_owned_by(owner))
+    }
+
+    fn remove_if(&mut self, f: impl Fn(&Webhook) -> bool) -> Result<bool> {
+        let before = self.webhooks.len();
+        self.webhooks.retain(|w| !f(w));
+        let removed = self.webhooks.len() != before;
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// All registered webhooks.
+    pub fn list(&self) -> &[Webhook] {
+        &self.webhooks
+    }
+
+    /// Webhooks belonging to `owner`.
+    pub fn list_owned(&self, owner: Option<&str>) -> Vec<&Webhook> {
+        self.webhooks.iter().filter(|w| w.is_owned_by(owner)).collect()
+    }
+
+    /// Webhooks subscribed to an event.
+    pub fn subscribers(&self, event: WebhookEvent) -> Vec<Webhook> {
+        self.webhooks
+            .iter()
+            .filter(|w| w.subscribes_to(event))
+            .cloned()
+            .collect()
+    }
+
+    /// Webhooks belonging to `owner` that are subscribed to an event.
+    pub fn owned_subscribers(&self, event: WebhookEvent, owner: Option<&str>) -> Vec<Webhook> {
+        self.webhooks
+            .iter()
+            .filter(|w| w.subscribes_to(event) && w.is_owned_by(owner))
+            .cloned()
+            .collect()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            let data = serde_json::to_string_pretty(&self.webhooks)?;
+            std::fs::write(path, data)
+                .with_context(|| format!("Failed to write webhook store {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Compute the hex-encoded HMAC-SHA256 signature of a body.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Check that a webhook URL is HTTP(S) and only resolves to public
+/// addresses, so registrations cannot reach internal services.
+pub async fn check_webhook_url(url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("Invalid webhook URL {}", url))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(anyhow!("Webhook URL must use http or https: {}", url));
+    }
+    let host = parsed.host_str().ok_or_else(|| anyhow!("Webhook URL has no host: {}", url))?;
+    let port = parsed.port_or_known_default().unwrap_or(80);
+
+    // Bracketed IPv6 hosts are resolved without their brackets
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<_> = tokio::net::lookup_host((host, port))
+        .await
+        .with_context(|| format!("Failed to resolve webhook host {}", host))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(anyhow!("Webhook host {} has no addresses", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(anyhow!("Webhook host {} resolves to non-public address {}", host, addr.ip()));
+    }
+    Ok(())
+}
+
+/// Whether an address is routable on the public internet.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// POST a notification body to a webhook, retrying failed deliveries.
+pub async fn deliver_webhook(client: &Client, webhook: &Webhook, body: &[u8]) -> Result<()> {
+    let signature = webhook
+        .registration
+        .secret
+        .as_deref()
+        .map(|secret| format!("sha256={}", sign_payload(secret, body)));
+
+    let mut attempt = 0;
+    loop {
+        let mut request = client
+            .post(&webhook.registration.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!(webhook_id = %webhook.id, "Delivered webhook");
+                return Ok(());
+            }
+            Ok(response) => anyhow::anyhow!("Webhook returned {}", response.status()),
+            Err(e) => e.into(),
+        };
+
+        if attempt >= MAX_DELIVERY_RETRIES {
+            return Err(error.context(format!("Webhook delivery failed after {} attempts", attempt + 1)));
+        }
+        warn!(webhook_id = %webhook.id, attempt, error = %error, "Webhook delivery failed, retrying");
+        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt)).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(events: Vec<WebhookEvent>) -> WebhookRegistration {
+        WebhookRegistration {
+            url: "http://localhost:9/hook".to_string(),
+            events,
+            secret: Some("s3cret".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_register_list_remove() {
+        let mut store = WebhookStore::new();
+        let completed = store.register(registration(vec![WebhookEvent::JobCompleted])).unwrap();
+        store.register(registration(vec![WebhookEvent::JobFailed])).unwrap();
+
+        assert_eq!(store.list().len(), 2);
+        let subscribers = store.subscribers(WebhookEvent::JobCompleted);
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(subscribers[0].id, completed.id);
+
+        assert!(store.remove(completed.id).unwrap());
+        assert!(!store.remove(completed.id).unwrap());
+        assert!(store.subscribers(WebhookEvent::JobCompleted).is_empty());
+    }
+
+    #[test]
+    fn test_webhooks_are_scoped_to_owner() {
+        let mut store = WebhookStore::new();
+        let owned = store
+            .register_owned(registration(vec![WebhookEvent::JobCompleted]), Some("user-1".to_string()))
+            .unwrap();
+        store.register(registration(vec![WebhookEvent::JobCompleted])).unwrap();
+
+        assert_eq!(store.list_owned(Some("user-1")).len(), 1);
+        assert_eq!(store.list_owned(None).len(), 1);
+        assert!(store.list_owned(Some("user-2")).is_empty());
+        let subscribers = store.owned_subscribers(WebhookEvent::JobCompleted, Some("user-1"));
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(subscribers[0].id, owned.id);
+
+        assert!(!store.remove_owned(owned.id, Some("user-2")).unwrap());
+        assert!(store.remove_owned(owned.id, Some("user-1")).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_check_webhook_url_rejects_internal_hosts() {
+        for url in [
+            "http://127.0.0.1/hook",
+            "http://localhost:8080/hook",
+            "http://10.0.0.5/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[::ffff:192.168.1.1]/hook",
+            "ftp://example.com/hook",
+            "not a url",
+        ] {
+            assert!(check_webhook_url(url).await.is_err(), "{} accepted", url);
+        }
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip("2606:2800:220:1::".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_registration_debug_redacts_secret() {
+        let debug = format!("{:?}", registration(vec![WebhookEvent::JobFailed]));
+        assert!(!debug.contains("s3cret"));
+        assert!(debug.contains("<redacted>"));
+    }
+
+    #[test]
+    fn test_store_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("webhooks.json");
+
+        let id = {
+            let mut store = WebhookStore::open(&path).unwrap();
+            store.register(registration(vec![WebhookEvent::ChunksBatchReady])).unwrap().id
+        };
+
+        let store = WebhookStore::open(&path).unwrap();
+        assert_eq!(store.list().len(), 1);
+        assert_eq!(store.list()[0].id, id);
+        assert_eq!(store.list()[0].registration.secret.as_deref(), Some("s3cret"));
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
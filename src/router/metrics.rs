@@ -0,0 +1,169 @@
+//! Per-chunker routing metrics.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Counters of items routed to, and errors from, each chunker.
+///
+/// The built-in chunkers are fixed at construction so their counters can
+/// be updated without locking; plugin chunkers get counters when they are
+/// registered (`register`).
+#[derive(Debug, Default)]
+pub struct ChunkerMetrics {
+    /// Items routed to each chunker
+    pub per_chunker_count: HashMap<&'static str, AtomicU64>,
+    /// Chunking errors from each chunker
+    pub per_chunker_errors: HashMap<&'static str, AtomicU64>,
+    /// (routed, errors) of plugin chunkers, kept after they are unregistered
+    plugins: RwLock<HashMap<&'static str, [AtomicU64; 2]>>,
+}
+
+impl ChunkerMetrics {
+    /// Create zeroed counters for the given chunker names.
+    pub fn new(chunkers: impl IntoIterator<Item = &'static str>) -> Self {
+        let names: Vec<&'static str> = chunkers.into_iter().collect();
+        Self {
+            per_chunker_count: names.iter().map(|n| (*n, AtomicU64::new(0))).collect(),
+            per_chunker_errors: names.iter().map(|n| (*n, AtomicU64::new(0))).collect(),
+            plugins: RwLock::default(),
+        }
+    }
+
+    /// Add zeroed counters for a plugin chunker. Chunkers that already
+    /// have counters keep them.
+    pub fn register(&self, chunker: &'static str) {
+        if self.per_chunker_count.contains_key(chunker) {
+            return;
+        }
+        if let Ok(mut plugins) = self.plugins.write() {
+            plugins.entry(chunker).or_default();
+        }
+    }
+
+    /// Record an item routed to a chunker.
+    pub fn record_routed(&self, chunker: &str) {
+        self.increment(&self.per_chunker_count, 0, chunker);
+    }
+
+    /// Record a chunking error from a chunker.
+    pub fn record_error(&self, chunker: &str) {
+        self.increment(&self.per_chunker_errors, 1, chunker);
+    }
+
+    /// Items routed to a chunker so far.
+    pub fn routed(&self, chunker: &str) -> u64 {
+        self.load(&self.per_chunker_count, 0, chunker)
+    }
+
+    /// Errors from a chunker so far.
+    pub fn errors(&self, chunker: &str) -> u64 {
+        self.load(&self.per_chunker_errors, 1, chunker)
+    }
+
+    /// Add one to a built-in counter, or to counter `plugin_index` of a
+    /// plugin. Unknown chunkers are ignored.
+    fn increment(&self, built_in: &HashMap<&'static str, AtomicU64>, plugin_index: usize, chunker: &str) {
+        if let Some(count) = built_in.get(chunker) {
+            count.fetch_add(1, Ordering::Relaxed);
+        } else if let Some(counts) = self.plugins.read().ok().as_ref().and_then(|p| p.get(chunker)) {
+            counts[plugin_index].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn load(&self, built_in: &HashMap<&'static str, AtomicU64>, plugin_index: usize, chunker: &str) -> u64 {
+        match built_in.get(chunker) {
+            Some(count) => count.load(Ordering::Relaxed),
+            None => self
+                .plugins
+                .read()
+                .ok()
+                .and_then(|p| p.get(chunker).map(|counts| counts[plugin_index].load(Ordering::Relaxed)))
+                .unwrap_or(0),
+        }
+    }
+
+    /// Render the counters in the Prometheus text exposition format.
+    pub fn to_prometheus_text(&self) -> String {
+        let plugins: Vec<&'static str> = self.plugins.read().map(|p| p.keys().copied().collect()).unwrap_or_default();
+        let mut out = String::new();
+        write_counter(
+            &mut out,
+            "chunker_items_routed_total",
+            "Items routed to each chunker",
+            self.values(&self.per_chunker_count, 0, &plugins),
+        );
+        write_counter(
+            &mut out,
+            "chunker_errors_total",
+            "Chunking errors per chunker",
+            self.values(&self.per_chunker_errors, 1, &plugins),
+        );
+        out
+    }
+
+    /// Current values of one counter for every chunker, sorted by name.
+    fn values(
+        &self,
+        built_in: &HashMap<&'static str, AtomicU64>,
+        plugin_index: usize,
+        plugins: &[&'static str],
+    ) -> Vec<(&'static str, u64)> {
+        let mut values: Vec<_> = built_in
+            .keys()
+            .chain(plugins)
+            .map(|chunker| (*chunker, self.load(built_in, plugin_index, chunker)))
+            .collect();
+        values.sort();
+        values
+    }
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, values: Vec<(&'static str, u64)>) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (chunker, value) in values {
+        let _ = writeln!(out, "{}{{chunker=\"{}\"}} {}", name, chunker, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_text() {
+        let metrics = ChunkerMetrics::new(["token", "code"]);
+        metrics.record_routed("code");
+        metrics.record_routed("code");
+        metrics.record_error("token");
+        metrics.record_routed("unknown");
+
+        let text = metrics.to_prometheus_text();
+        assert!(text.contains("# TYPE chunker_items_routed_total counter\n"));
+        assert!(text.contains("chunker_items_routed_total{chunker=\"code\"} 2\n"));
+        assert!(text.contains("chunker_items_routed_total{chunker=\"token\"} 0\n"));
+        assert!(text.contains("chunker_errors_total{chunker=\"token\"} 1\n"));
+        assert!(!text.contains("unknown"));
+    }
+
+    #[test]
+    fn test_plugin_counters() {
+        let metrics = ChunkerMetrics::new(["token"]);
+        metrics.record_routed("hcl");
+        assert_eq!(metrics.routed("hcl"), 0);
+
+        metrics.register("hcl");
+        metrics.register("token");
+        metrics.record_routed("hcl");
+        metrics.record_error("hcl");
+        metrics.record_routed("token");
+        assert_eq!((metrics.routed("hcl"), metrics.errors("hcl")), (1, 1));
+        assert_eq!(metrics.routed("token"), 1);
+
+        let text = metrics.to_prometheus_text();
+        assert!(text.contains("chunker_items_routed_total{chunker=\"hcl\"} 1\nchunker_items_routed_total{chunker=\"token\"} 1\n"));
+        assert!(text.contains("chunker_errors_total{chunker=\"hcl\"} 1\n"));
+    }
+}
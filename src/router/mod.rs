@@ -1,12 +1,25 @@
 //! Chunking strategy router.
 
-use std::sync::Arc;
+mod metrics;
 
+pub use metrics::ChunkerMetrics;
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::{anyhow, Result};
+use futures::{stream, Stream, StreamExt};
+
+use crate::batch::detect_language;
 use crate::chunkers::{
-    AgenticChunker, ChatChunker, CodeChunker, Chunker, DocumentChunker, 
-    RecursiveChunker, SentenceChunker, TableChunker, TicketingChunker, TokenChunker,
+    AgenticChunker, ChatChunker, CodeChunker, Chunker, DocumentChunker, EmailChunker, IniChunker, LatexChunker, LogChunker,
+    PatchChunker, RecursiveChunker, SentenceChunker, ShellChunker, TableChunker, TicketingChunker, TokenChunker, XmlChunker,
+    SHELL_LANGUAGES,
 };
-use crate::types::{ChunkConfig, ChunkingConfig, SourceItem, SourceKind};
+use crate::types::language::detect_from_content;
+use crate::types::{Chunk, ChunkConfig, ChunkConfigOverride, ChunkingConfig, SourceItem, SourceKind, TokenCountMode};
 
 /// Router that selects the appropriate chunker based on source type.
 ///
@@ -14,31 +27,105 @@ use crate::types::{ChunkConfig, ChunkingConfig, SourceItem, SourceKind};
 /// which chunking strategy to use for optimal results.
 pub struct ChunkingRouter {
     /// Token chunker (fallback)
-    token_chunker: Arc<TokenChunker>,
+    token_chunker: Arc<dyn Chunker>,
     /// Sentence chunker (for plain text)
-    sentence_chunker: Arc<SentenceChunker>,
+    sentence_chunker: Arc<dyn Chunker>,
     /// Recursive chunker (for structured text)
-    recursive_chunker: Arc<RecursiveChunker>,
+    recursive_chunker: Arc<dyn Chunker>,
     /// Code chunker (for source code)
-    code_chunker: Arc<CodeChunker>,
+    code_chunker: Arc<dyn Chunker>,
     /// Document chunker (for markdown/wiki)
-    document_chunker: Arc<DocumentChunker>,
+    document_chunker: Arc<dyn Chunker>,
     /// Chat chunker (for messages)
-    chat_chunker: Arc<ChatChunker>,
+    chat_chunker: Arc<dyn Chunker>,
     /// Ticketing chunker (for issues/PRs)
-    ticketing_chunker: Arc<TicketingChunker>,
+    ticketing_chunker: Arc<dyn Chunker>,
     /// Table chunker (for markdown tables/CSV)
-    table_chunker: Arc<TableChunker>,
+    table_chunker: Arc<dyn Chunker>,
     /// Agentic chunker (for intelligent boundary detection)
-    agentic_chunker: Arc<AgenticChunker>,
+    agentic_chunker: Arc<dyn Chunker>,
+    /// Log chunker (for application and access logs)
+    log_chunker: Arc<dyn Chunker>,
+    /// Email chunker (for RFC 2822 / MIME messages)
+    email_chunker: Arc<dyn Chunker>,
+    /// Shell chunker (for shell scripts)
+    shell_chunker: Arc<dyn Chunker>,
+    /// LaTeX chunker (for papers and technical documents)
+    latex_chunker: Arc<dyn Chunker>,
+    /// XML chunker (for configuration and data files)
+    xml_chunker: Arc<dyn Chunker>,
+    /// Patch chunker (for unified diffs)
+    patch_chunker: Arc<dyn Chunker>,
+    /// INI chunker (for configuration files)
+    ini_chunker: Arc<dyn Chunker>,
     /// Default chunk configuration
     default_config: ChunkConfig,
+    /// Chunk configuration overrides by source kind
+    per_kind_config: HashMap<SourceKind, ChunkConfigOverride>,
+    /// A/B experiments by source kind
+    ab_routes: RwLock<HashMap<SourceKind, AbRoute>>,
+    /// Items routed to (A, B) per source kind
+    ab_counts: Mutex<HashMap<SourceKind, (usize, usize)>>,
+    /// Routing and error counts per chunker
+    metrics: Arc<ChunkerMetrics>,
+    /// Chunkers registered at runtime, by lowercased name
+    plugins: RwLock<HashMap<String, PluginChunker>>,
+    /// Items chunked at once by `route_items_stream`
+    concurrency: usize,
+}
+
+/// A chunker registered at runtime and the items it claims.
+struct PluginChunker {
+    chunker: Arc<dyn Chunker>,
+    /// Lowercased MIME types, without parameters
+    content_types: Vec<String>,
+    source_kinds: Vec<SourceKind>,
+}
+
+/// Chunks of one item, as yielded by `route_stream`.
+type ChunkStream<'a> = Pin<Box<dyn Stream<Item = Result<Chunk>> + Send + 'a>>;
+
+/// A chunker available to the router, as listed by `list_chunkers`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkerDescriptor {
+    pub name: String,
+    pub description: String,
+    /// Registered with `register_chunker` rather than built in
+    pub is_plugin: bool,
+}
+
+/// A chunker selected by the router.
+///
+/// Built-in chunkers are borrowed from the router; plugins are cloned out
+/// of the registry so its lock is not held while chunking.
+enum Routed<'a> {
+    BuiltIn(&'a Arc<dyn Chunker>),
+    Plugin(Arc<dyn Chunker>),
+}
+
+impl Deref for Routed<'_> {
+    type Target = Arc<dyn Chunker>;
+
+    fn deref(&self) -> &Arc<dyn Chunker> {
+        match self {
+            Routed::BuiltIn(chunker) => chunker,
+            Routed::Plugin(chunker) => chunker,
+        }
+    }
+}
+
+/// An A/B experiment splitting one source kind between two chunkers.
+#[derive(Debug, Clone)]
+struct AbRoute {
+    chunker_a: String,
+    chunker_b: String,
+    ratio_b: f32,
 }
 
 impl ChunkingRouter {
     /// Create a new chunking router with the given configuration.
     pub fn new(config: &ChunkingConfig) -> Self {
-        Self {
+        let mut router = Self {
             token_chunker: Arc::new(TokenChunker::new()),
             sentence_chunker: Arc::new(SentenceChunker::new()),
             recursive_chunker: Arc::new(RecursiveChunker::new()),
@@ -48,64 +135,427 @@ impl ChunkingRouter {
             ticketing_chunker: Arc::new(TicketingChunker::new()),
             table_chunker: Arc::new(TableChunker::new()),
             agentic_chunker: Arc::new(AgenticChunker::new()),
+            log_chunker: Arc::new(LogChunker::new()),
+            email_chunker: Arc::new(EmailChunker::new()),
+            shell_chunker: Arc::new(ShellChunker::new()),
+            latex_chunker: Arc::new(LatexChunker::new()),
+            xml_chunker: Arc::new(XmlChunker::new()),
+            patch_chunker: Arc::new(PatchChunker::new()),
+            ini_chunker: Arc::new(IniChunker::new()),
             default_config: ChunkConfig {
                 chunk_size: config.default_chunk_size,
                 chunk_overlap: config.default_chunk_overlap,
                 min_chars_per_sentence: config.min_chars_per_sentence,
                 preserve_whitespace: false,
                 language: None,
+                use_deterministic_ids: config.use_deterministic_ids,
+                token_count_mode: TokenCountMode::default(),
+                language_size_overrides: config.language_size_overrides.clone(),
             },
-        }
+            per_kind_config: config.per_kind_config.clone(),
+            ab_routes: RwLock::new(HashMap::new()),
+            ab_counts: Mutex::default(),
+            metrics: Arc::default(),
+            plugins: RwLock::new(HashMap::new()),
+            concurrency: 1,
+        };
+        router.metrics = Arc::new(ChunkerMetrics::new(
+            router.built_in_chunkers().into_iter().map(|c| c.name()),
+        ));
+        router
+    }
+
+    /// Set how many items `route_items_stream` chunks at once (default 1).
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Routing metrics, shared with the router so they can be read separately.
+    pub fn metrics(&self) -> Arc<ChunkerMetrics> {
+        Arc::clone(&self.metrics)
     }
 
     /// Get the appropriate chunker for the given source item.
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            item.source_kind = %item.source_kind,
+            item.content_size = item.content.len(),
+            chunker.name = tracing::field::Empty,
+        )
+    )]
     pub fn get_chunker(&self, item: &SourceItem) -> Arc<dyn Chunker> {
+        let chunker = self.get_chunker_with(item, true);
+        tracing::Span::current().record("chunker.name", chunker.name());
+        chunker
+    }
+
+    /// Get the chunker for a source item, optionally skipping content inspection.
+    ///
+    /// With `inspect_content` set, items of `SourceKind::Other` whose content
+    /// type does not select a chunker are routed by `inspect_content`.
+    pub fn get_chunker_with(&self, item: &SourceItem, inspect_content: bool) -> Arc<dyn Chunker> {
+        Arc::clone(&self.select_chunker(item, inspect_content))
+    }
+
+    /// Chunk a source item as a stream, using the chunker selected for it.
+    ///
+    /// Chunkers that override `Chunker::chunk_stream` yield chunks lazily
+    /// instead of materialising the full result first.
+    pub fn route_stream<'a>(
+        &'a self,
+        item: &'a SourceItem,
+        config: &'a ChunkConfig,
+    ) -> Pin<Box<dyn Stream<Item = Result<Chunk>> + Send + 'a>> {
+        self.route_stream_with(item, config, true)
+    }
+
+    /// Route and chunk a stream of items, e.g. from Kafka or NATS,
+    /// yielding each item with its chunks.
+    ///
+    /// Each item is chunked on the blocking thread pool, up to
+    /// `with_concurrency` items at once. With a concurrency of 1 results
+    /// are yielded in arrival order; otherwise in the order items finish.
+    /// An item whose chunking fails yields `Err` without ending the stream.
+    pub fn route_items_stream(
+        self: &Arc<Self>,
+        items: impl Stream<Item = SourceItem> + Send + 'static,
+        config: &ChunkConfig,
+    ) -> impl Stream<Item = Result<(SourceItem, Vec<Chunk>)>> + Send + 'static {
+        let router = Arc::clone(self);
+        let config = Arc::new(config.clone());
+        let concurrency = self.concurrency;
+        items
+            .map(move |item| {
+                let router = Arc::clone(&router);
+                let config = Arc::clone(&config);
+                async move {
+                    tokio::task::spawn_blocking(move || -> Result<(SourceItem, Vec<Chunk>)> {
+                        let chunks = futures::executor::block_on_stream(router.route_stream(&item, &config))
+                            .collect::<Result<Vec<Chunk>>>()?;
+                        Ok((item, chunks))
+                    })
+                    .await
+                    .unwrap_or_else(|e| Err(anyhow!("Chunking task failed: {}", e)))
+                }
+            })
+            .buffer_unordered(concurrency)
+    }
+
+    /// Like `route_stream`, optionally skipping content inspection.
+    pub fn route_stream_with<'a>(
+        &'a self,
+        item: &'a SourceItem,
+        config: &'a ChunkConfig,
+        inspect_content: bool,
+    ) -> Pin<Box<dyn Stream<Item = Result<Chunk>> + Send + 'a>> {
+        self.route_stream_named(item, config, inspect_content).1
+    }
+
+    /// Like `route_stream_with`, also returning the name of the chunker
+    /// selected, e.g. to label metrics with the chunker that ran.
+    pub fn route_stream_named<'a>(
+        &'a self,
+        item: &'a SourceItem,
+        config: &'a ChunkConfig,
+        inspect_content: bool,
+    ) -> (&'static str, ChunkStream<'a>) {
+        let chunker = self.select_chunker(item, inspect_content);
+        let name = chunker.name();
+        let metrics = Arc::clone(&self.metrics);
+        let stream = match chunker {
+            Routed::BuiltIn(chunker) => chunker.chunk_stream(item, config),
+            // The plugin is owned here, so it cannot lend the stream a borrow
+            Routed::Plugin(chunker) => {
+                let results: Vec<Result<Chunk>> = match chunker.chunk(item, config) {
+                    Ok(chunks) => chunks.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                Box::pin(stream::iter(results))
+            }
+        };
+        let stream = stream.inspect(move |chunk| {
+            if chunk.is_err() {
+                metrics.record_error(name);
+            }
+        });
+        let stream: Pin<Box<dyn Stream<Item = Result<Chunk>> + Send + 'a>> = Box::pin(stream);
+
+        let stream = match self.ab_variant(item) {
+            Some(variant) => Box::pin(stream.map(move |chunk| {
+                chunk.map(|mut c| {
+                    c.metadata.ab_variant = Some(variant);
+                    c
+                })
+            })),
+            None => stream,
+        };
+        (name, stream)
+    }
+
+    /// Split items of `source_kind` between two chunkers by name.
+    ///
+    /// A fraction `ratio_b` (0.0–1.0) of items goes to `chunker_b`, the rest
+    /// to `chunker_a`. The split is a hash of the item ID, so an item is
+    /// always routed to the same variant. Overrides content-type routing.
+    pub fn set_ab_route(&self, source_kind: SourceKind, chunker_a: &str, chunker_b: &str, ratio_b: f32) -> Result<()> {
+        for name in [chunker_a, chunker_b] {
+            if self.lookup_chunker(name).is_none() {
+                return Err(anyhow!("Unknown chunker: {}", name));
+            }
+        }
+        if !(0.0..=1.0).contains(&ratio_b) {
+            return Err(anyhow!("A/B ratio must be between 0.0 and 1.0, got {}", ratio_b));
+        }
+
+        let route = AbRoute {
+            chunker_a: chunker_a.to_string(),
+            chunker_b: chunker_b.to_string(),
+            ratio_b,
+        };
+        self.ab_routes
+            .write()
+            .map_err(|_| anyhow!("A/B routes lock poisoned"))?
+            .insert(source_kind, route);
+        Ok(())
+    }
+
+    /// Remove the A/B experiment for a source kind.
+    pub fn clear_ab_route(&self, source_kind: SourceKind) {
+        if let Ok(mut routes) = self.ab_routes.write() {
+            routes.remove(&source_kind);
+        }
+    }
+
+    /// Items routed to the (A, B) chunkers so far, per source kind. An item
+    /// routed more than once is counted each time.
+    pub fn ab_stats(&self) -> HashMap<SourceKind, (usize, usize)> {
+        self.ab_counts.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// The A/B variant an item is assigned to, if its source kind has an experiment.
+    pub fn ab_variant(&self, item: &SourceItem) -> Option<char> {
+        let routes = self.ab_routes.read().ok()?;
+        let route = routes.get(&item.source_kind)?;
+        let bucket = (item.id.as_u128() % 10_000) as f32 / 10_000.0;
+        Some(if bucket < route.ratio_b { 'B' } else { 'A' })
+    }
+
+    /// Name of the chunker an item would be routed to, without counting it
+    /// as a routing decision.
+    pub fn chunker_name(&self, item: &SourceItem, inspect_content: bool) -> &'static str {
+        self.route(item, inspect_content).name()
+    }
+
+    /// Select the chunker for a source item without cloning it, counting
+    /// the routing decision in the metrics.
+    fn select_chunker(&self, item: &SourceItem, inspect_content: bool) -> Routed<'_> {
+        let chunker = self.route(item, inspect_content);
+        self.metrics.record_routed(chunker.name());
+        self.count_ab_variant(item);
+        chunker
+    }
+
+    /// Count a routing decision towards the item's A/B variant.
+    fn count_ab_variant(&self, item: &SourceItem) {
+        let Some(variant) = self.ab_variant(item) else {
+            return;
+        };
+        if let Ok(mut counts) = self.ab_counts.lock() {
+            let entry = counts.entry(item.source_kind).or_default();
+            if variant == 'B' {
+                entry.1 += 1;
+            } else {
+                entry.0 += 1;
+            }
+        }
+    }
+
+    fn route(&self, item: &SourceItem, inspect_content: bool) -> Routed<'_> {
+        if let Some(chunker) = self.select_ab_chunker(item) {
+            return chunker;
+        }
+
+        // Shell scripts are often typed as generic code
+        if is_shell_script(item) {
+            return Routed::BuiltIn(&self.shell_chunker);
+        }
+
         // First, check content type for overrides
-        if let Some(chunker) = self.match_content_type(&item.content_type) {
+        if let Some(chunker) = self.match_content_type(item) {
             return chunker;
         }
 
-        // Then, match by source kind
-        match item.source_kind {
-            SourceKind::CodeRepo => Arc::clone(&self.code_chunker) as Arc<dyn Chunker>,
-            SourceKind::Document => Arc::clone(&self.document_chunker) as Arc<dyn Chunker>,
-            SourceKind::Wiki => Arc::clone(&self.document_chunker) as Arc<dyn Chunker>,
-            SourceKind::Chat => Arc::clone(&self.chat_chunker) as Arc<dyn Chunker>,
-            SourceKind::Email => Arc::clone(&self.chat_chunker) as Arc<dyn Chunker>,
-            SourceKind::Ticketing => Arc::clone(&self.ticketing_chunker) as Arc<dyn Chunker>,
-            SourceKind::Web => Arc::clone(&self.recursive_chunker) as Arc<dyn Chunker>,
-            SourceKind::Other => Arc::clone(&self.sentence_chunker) as Arc<dyn Chunker>,
+        // Untyped content: look at the content itself
+        if inspect_content && item.source_kind == SourceKind::Other {
+            if let Some(chunker) = self.inspect_content_ref(&item.content) {
+                return Routed::BuiltIn(chunker);
+            }
+        }
+
+        // Then, match by source kind, letting plugins claim a kind first
+        if let Some(chunker) = self.plugin_for_source_kind(item.source_kind) {
+            return Routed::Plugin(chunker);
         }
+        Routed::BuiltIn(match item.source_kind {
+            SourceKind::CodeRepo => &self.code_chunker,
+            SourceKind::Document => &self.document_chunker,
+            SourceKind::Wiki => &self.document_chunker,
+            SourceKind::Chat => &self.chat_chunker,
+            SourceKind::Email => &self.email_chunker,
+            SourceKind::Ticketing => &self.ticketing_chunker,
+            SourceKind::Web => &self.recursive_chunker,
+            SourceKind::Other => &self.sentence_chunker,
+        })
     }
 
     /// Match chunker by content type.
-    fn match_content_type(&self, content_type: &str) -> Option<Arc<dyn Chunker>> {
+    ///
+    /// JSON content is peeked at to tell arrays of records (tables) apart
+    /// from other JSON. Content types not claimed by a built-in chunker
+    /// are looked up among the registered plugins.
+    fn match_content_type(&self, item: &SourceItem) -> Option<Routed<'_>> {
+        self.match_built_in_content_type(item)
+            .map(Routed::BuiltIn)
+            .or_else(|| self.plugin_for_content_type(&item.content_type).map(Routed::Plugin))
+    }
+
+    fn match_built_in_content_type(&self, item: &SourceItem) -> Option<&Arc<dyn Chunker>> {
+        let content_type = item.content_type.as_str();
+        if content_type.contains("x-latex") || content_type.contains("x-tex") || content_type == "text/code:latex" {
+            return Some(&self.latex_chunker);
+        }
+
+        if content_type.contains("x-ini") || content_type == "text/code:ini" {
+            return Some(&self.ini_chunker);
+        }
+
         if content_type.starts_with("text/code:") || content_type.contains("x-source") {
-            return Some(Arc::clone(&self.code_chunker) as Arc<dyn Chunker>);
+            return Some(&self.code_chunker);
         }
 
         if content_type.contains("markdown") || content_type.contains("x-markdown") {
-            return Some(Arc::clone(&self.document_chunker) as Arc<dyn Chunker>);
+            return Some(&self.document_chunker);
+        }
+
+        if content_type.contains("rfc822") || content_type.contains("text/email") {
+            return Some(&self.email_chunker);
+        }
+
+        if content_type.contains("x-diff") || content_type.contains("x-patch") {
+            return Some(&self.patch_chunker);
+        }
+
+        if content_type.contains("x-log") || content_type.contains("x-ndjson") {
+            return Some(&self.log_chunker);
         }
 
         if content_type.contains("json") && content_type.contains("chat") {
-            return Some(Arc::clone(&self.chat_chunker) as Arc<dyn Chunker>);
+            return Some(&self.chat_chunker);
+        }
+
+        if content_type.contains("json") {
+            let is_array_of_objects = item
+                .content
+                .trim_start()
+                .strip_prefix('[')
+                .is_some_and(|rest| rest.trim_start().starts_with('{'));
+            if is_array_of_objects {
+                // Arrays whose records don't share one set of keys aren't tables
+                if TableChunker::detect_and_parse_json_table(&item.content).is_some() {
+                    return Some(&self.table_chunker);
+                }
+                return Some(&self.recursive_chunker);
+            }
         }
 
         if content_type.contains("csv") || content_type.contains("table") {
-            return Some(Arc::clone(&self.table_chunker) as Arc<dyn Chunker>);
+            return Some(&self.table_chunker);
+        }
+
+        if content_type.starts_with("application/xml") || content_type.starts_with("text/xml") {
+            return Some(&self.xml_chunker);
+        }
+
+        None
+    }
+
+    /// Select the chunker for an item under an A/B experiment.
+    fn select_ab_chunker(&self, item: &SourceItem) -> Option<Routed<'_>> {
+        let variant = self.ab_variant(item)?;
+        let name = {
+            let routes = self.ab_routes.read().ok()?;
+            let route = routes.get(&item.source_kind)?;
+            if variant == 'B' { route.chunker_b.clone() } else { route.chunker_a.clone() }
+        };
+
+        self.lookup_chunker(&name)
+    }
+
+    /// Pick a chunker by inspecting the content itself.
+    ///
+    /// Looks at the first 512 bytes: markdown-style tables (more than 60% of
+    /// non-empty lines starting with `|`) go to the table chunker, and JSON
+    /// objects with a `"messages"` array go to the chat chunker.
+    pub fn inspect_content(&self, content: &str) -> Option<Arc<dyn Chunker>> {
+        self.inspect_content_ref(content).map(Arc::clone)
+    }
+
+    fn inspect_content_ref(&self, content: &str) -> Option<&Arc<dyn Chunker>> {
+        let mut end = content.len().min(512);
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let sample = content[..end].trim_start();
+
+        let lines: Vec<&str> = sample.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+        let table_lines = lines.iter().filter(|l| l.starts_with('|')).count();
+        if !lines.is_empty() && table_lines * 10 > lines.len() * 6 {
+            return Some(&self.table_chunker);
+        }
+
+        if sample.starts_with('{') || sample.starts_with('[') {
+            let is_chat = serde_json::from_str::<serde_json::Value>(content)
+                .ok()
+                .is_some_and(|v| v.get("messages").is_some_and(|m| m.is_array()));
+            if is_chat {
+                return Some(&self.chat_chunker);
+            }
         }
 
         None
     }
 
     /// Get the chunk configuration for a source item.
+    ///
+    /// Starts from the global defaults, overlays the fields set for the
+    /// item's source kind, then applies `chunk_size` and
+    /// `chunk_overlap` from the item's metadata.
     pub fn get_config(&self, item: &SourceItem) -> ChunkConfig {
         let mut config = self.default_config.clone();
+        if let Some(kind_config) = self.per_kind_config.get(&item.source_kind) {
+            kind_config.apply(&mut config);
+        }
+
+        let metadata_usize = |key| item.metadata.get(key).and_then(|v| v.as_u64()).map(|v| v as usize);
+        if let Some(size) = metadata_usize("chunk_size") {
+            // An item's own size beats its language's
+            config.chunk_size = size;
+            config.language_size_overrides.clear();
+        }
+        if let Some(overlap) = metadata_usize("chunk_overlap") {
+            config.chunk_overlap = overlap;
+        }
 
-        // Set language for code items
+        // Set language for code items, falling back to the file extension
+        // and then to shebangs/modelines for extensionless scripts
         if item.source_kind == SourceKind::CodeRepo || item.content_type.starts_with("text/code:") {
-            config.language = item.extract_language().map(String::from);
+            config.language = item
+                .extract_language()
+                .map(String::from)
+                .or_else(|| item.extract_path().and_then(detect_language))
+                .or_else(|| detect_from_content(&item.content).map(|(lang, _)| lang.to_string()));
         }
 
         config
@@ -116,38 +566,174 @@ impl ChunkingRouter {
         &self.default_config
     }
 
-    /// Get a chunker by name.
+    /// Get a chunker by name, built-in or registered.
     pub fn get_chunker_by_name(&self, name: &str) -> Option<Arc<dyn Chunker>> {
+        self.lookup_chunker(name).map(|chunker| Arc::clone(&chunker))
+    }
+
+    fn lookup_chunker(&self, name: &str) -> Option<Routed<'_>> {
+        if let Some(chunker) = self.chunker_ref_by_name(name) {
+            return Some(Routed::BuiltIn(chunker));
+        }
+        let plugins = self.plugins.read().ok()?;
+        plugins
+            .get(&name.to_lowercase())
+            .map(|plugin| Routed::Plugin(Arc::clone(&plugin.chunker)))
+    }
+
+    /// Register a chunker under `name` for the given content types and
+    /// source kinds.
+    ///
+    /// Built-in content type rules take precedence, so a plugin only
+    /// receives content types no built-in chunker claims; content types
+    /// are compared case-insensitively, ignoring parameters such as
+    /// `; charset=utf-8`. For its source kinds, a plugin replaces the
+    /// built-in default. Registering an existing plugin name replaces it,
+    /// but built-in names and aliases cannot be reused. Like built-in
+    /// chunkers, plugins are counted in `metrics()` under their `name()`.
+    pub fn register_chunker(
+        &self,
+        name: &str,
+        chunker: Arc<dyn Chunker>,
+        content_types: Vec<&str>,
+        source_kinds: Vec<SourceKind>,
+    ) -> Result<()> {
+        if self.chunker_ref_by_name(name).is_some() {
+            return Err(anyhow!("Chunker name is reserved for a built-in chunker: {}", name));
+        }
+
+        self.metrics.register(chunker.name());
+        let plugin = PluginChunker {
+            chunker,
+            content_types: content_types.into_iter().map(normalize_content_type).collect(),
+            source_kinds,
+        };
+        self.plugins
+            .write()
+            .map_err(|_| anyhow!("Plugin registry lock poisoned"))?
+            .insert(name.to_lowercase(), plugin);
+        Ok(())
+    }
+
+    /// Remove a registered chunker, returning whether it was registered.
+    pub fn unregister_chunker(&self, name: &str) -> bool {
+        self.plugins
+            .write()
+            .map(|mut plugins| plugins.remove(&name.to_lowercase()).is_some())
+            .unwrap_or(false)
+    }
+
+    fn plugin_for_content_type(&self, content_type: &str) -> Option<Arc<dyn Chunker>> {
+        let content_type = normalize_content_type(content_type);
+        self.find_plugin(|plugin| plugin.content_types.contains(&content_type))
+    }
+
+    fn plugin_for_source_kind(&self, source_kind: SourceKind) -> Option<Arc<dyn Chunker>> {
+        self.find_plugin(|plugin| plugin.source_kinds.contains(&source_kind))
+    }
+
+    /// First plugin matching `predicate`, in name order so routing does
+    /// not depend on hash order when several plugins overlap.
+    fn find_plugin(&self, predicate: impl Fn(&PluginChunker) -> bool) -> Option<Arc<dyn Chunker>> {
+        let plugins = self.plugins.read().ok()?;
+        plugins
+            .iter()
+            .filter(|(_, plugin)| predicate(plugin))
+            .min_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, plugin)| Arc::clone(&plugin.chunker))
+    }
+
+    fn chunker_ref_by_name(&self, name: &str) -> Option<&Arc<dyn Chunker>> {
         match name.to_lowercase().as_str() {
-            "token" => Some(Arc::clone(&self.token_chunker) as Arc<dyn Chunker>),
-            "sentence" => Some(Arc::clone(&self.sentence_chunker) as Arc<dyn Chunker>),
-            "recursive" => Some(Arc::clone(&self.recursive_chunker) as Arc<dyn Chunker>),
-            "code" => Some(Arc::clone(&self.code_chunker) as Arc<dyn Chunker>),
-            "document" | "markdown" => Some(Arc::clone(&self.document_chunker) as Arc<dyn Chunker>),
-            "chat" => Some(Arc::clone(&self.chat_chunker) as Arc<dyn Chunker>),
-            "ticketing" | "ticket" | "issue" => Some(Arc::clone(&self.ticketing_chunker) as Arc<dyn Chunker>),
-            "table" | "csv" => Some(Arc::clone(&self.table_chunker) as Arc<dyn Chunker>),
-            "agentic" | "smart" | "intelligent" => Some(Arc::clone(&self.agentic_chunker) as Arc<dyn Chunker>),
+            "token" => Some(&self.token_chunker),
+            "sentence" => Some(&self.sentence_chunker),
+            "recursive" => Some(&self.recursive_chunker),
+            "code" => Some(&self.code_chunker),
+            "document" | "markdown" => Some(&self.document_chunker),
+            "chat" => Some(&self.chat_chunker),
+            "ticketing" | "ticket" | "issue" => Some(&self.ticketing_chunker),
+            "table" | "csv" => Some(&self.table_chunker),
+            "agentic" | "smart" | "intelligent" => Some(&self.agentic_chunker),
+            "log" | "logs" => Some(&self.log_chunker),
+            "email" | "mail" => Some(&self.email_chunker),
+            "shell" | "bash" | "sh" => Some(&self.shell_chunker),
+            "latex" | "tex" => Some(&self.latex_chunker),
+            "xml" => Some(&self.xml_chunker),
+            "patch" | "diff" => Some(&self.patch_chunker),
+            "ini" => Some(&self.ini_chunker),
             _ => None,
         }
     }
 
-    /// List all available chunkers.
-    pub fn list_chunkers(&self) -> Vec<(&'static str, &'static str)> {
-        vec![
-            (self.token_chunker.name(), self.token_chunker.description()),
-            (self.sentence_chunker.name(), self.sentence_chunker.description()),
-            (self.recursive_chunker.name(), self.recursive_chunker.description()),
-            (self.code_chunker.name(), self.code_chunker.description()),
-            (self.document_chunker.name(), self.document_chunker.description()),
-            (self.chat_chunker.name(), self.chat_chunker.description()),
-            (self.ticketing_chunker.name(), self.ticketing_chunker.description()),
-            (self.table_chunker.name(), self.table_chunker.description()),
-            (self.agentic_chunker.name(), self.agentic_chunker.description()),
+    /// List all available chunkers, built-in first, then plugins by name.
+    pub fn list_chunkers(&self) -> Vec<ChunkerDescriptor> {
+        let mut chunkers: Vec<ChunkerDescriptor> = self
+            .built_in_chunkers()
+            .into_iter()
+            .map(|c| ChunkerDescriptor {
+                name: c.name().to_string(),
+                description: c.description().to_string(),
+                is_plugin: false,
+            })
+            .collect();
+
+        if let Ok(plugins) = self.plugins.read() {
+            let mut registered: Vec<ChunkerDescriptor> = plugins
+                .iter()
+                .map(|(name, plugin)| ChunkerDescriptor {
+                    name: name.clone(),
+                    description: plugin.chunker.description().to_string(),
+                    is_plugin: true,
+                })
+                .collect();
+            registered.sort_by(|a, b| a.name.cmp(&b.name));
+            chunkers.extend(registered);
+        }
+        chunkers
+    }
+
+    fn built_in_chunkers(&self) -> [&Arc<dyn Chunker>; 16] {
+        [
+            &self.token_chunker,
+            &self.sentence_chunker,
+            &self.recursive_chunker,
+            &self.code_chunker,
+            &self.document_chunker,
+            &self.chat_chunker,
+            &self.ticketing_chunker,
+            &self.table_chunker,
+            &self.agentic_chunker,
+            &self.log_chunker,
+            &self.email_chunker,
+            &self.shell_chunker,
+            &self.latex_chunker,
+            &self.xml_chunker,
+            &self.patch_chunker,
+            &self.ini_chunker,
         ]
     }
 }
 
+/// Whether an item is a shell script, by content type, language or
+/// `.sh`/`.bash`/`.zsh` extension.
+fn is_shell_script(item: &SourceItem) -> bool {
+    let content_type = item.content_type.to_lowercase();
+    if content_type.contains("x-sh") {
+        return true;
+    }
+    let is_shell = |name: &str| SHELL_LANGUAGES.contains(&name.to_lowercase().as_str());
+    item.extract_language().is_some_and(is_shell)
+        || item
+            .extract_path()
+            .and_then(|path| path.rsplit_once('.'))
+            .is_some_and(|(_, ext)| !ext.contains('/') && is_shell(ext))
+}
+
+/// Lowercased MIME type without parameters, e.g. `text/x-hcl`.
+fn normalize_content_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_lowercase()
+}
+
 impl Default for ChunkingRouter {
     fn default() -> Self {
         Self::new(&ChunkingConfig::default())
@@ -157,6 +743,7 @@ impl Default for ChunkingRouter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures::TryStreamExt;
     use uuid::Uuid;
 
     fn create_item(source_kind: SourceKind, content_type: &str) -> SourceItem {
@@ -168,6 +755,7 @@ mod tests {
             content: "test content".to_string(),
             metadata: serde_json::json!({}),
             created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
         }
     }
 
@@ -202,4 +790,365 @@ mod tests {
         let chunker = router.get_chunker(&item);
         assert_eq!(chunker.name(), "ticketing");
     }
+
+    #[test]
+    fn test_content_inspection_routing() {
+        let router = ChunkingRouter::default();
+
+        let mut item = create_item(SourceKind::Other, "text/plain");
+        item.content = "| a | b |\n|---|---|\n| 1 | 2 |\n".to_string();
+        assert_eq!(router.get_chunker(&item).name(), "table");
+        assert_eq!(router.get_chunker_with(&item, false).name(), "sentence");
+
+        item.content = r#"{"messages": [{"author": "a", "content": "hi"}]}"#.to_string();
+        assert_eq!(router.get_chunker(&item).name(), "chat");
+
+        item.content = r#"{"title": "not chat"}"#.to_string();
+        assert_eq!(router.get_chunker(&item).name(), "sentence");
+
+        // JSON arrays of records are tables whatever their source
+        let mut export = create_item(SourceKind::Ticketing, "application/json");
+        export.content = r#"[{"id": 1}, {"id": 2}]"#.to_string();
+        assert_eq!(router.get_chunker(&export).name(), "table");
+        export.content = r#"[{"id": 1}, {"title": "no id"}]"#.to_string();
+        assert_eq!(router.get_chunker(&export).name(), "recursive");
+        export.content = r#"[{"id": 1}, {"id": "#.to_string();
+        assert_eq!(router.get_chunker(&export).name(), "recursive");
+        export.content = r#"{"key": "PROJ-1"}"#.to_string();
+        assert_eq!(router.get_chunker(&export).name(), "ticketing");
+
+        // Only untyped items are inspected
+        let mut doc = create_item(SourceKind::Document, "text/plain");
+        doc.content = "| a | b |\n| 1 | 2 |\n".to_string();
+        assert_eq!(router.get_chunker(&doc).name(), "document");
+    }
+
+    #[test]
+    fn test_ab_routing() {
+        let router = ChunkingRouter::default();
+        router.set_ab_route(SourceKind::Document, "document", "recursive", 0.3).unwrap();
+        assert!(router.set_ab_route(SourceKind::Document, "document", "nope", 0.3).is_err());
+
+        let items: Vec<SourceItem> = (0..1000).map(|_| create_item(SourceKind::Document, "text/plain")).collect();
+        for item in &items {
+            let name = router.get_chunker(item).name();
+            // Same item, same variant
+            assert_eq!(router.chunker_name(item, true), name);
+            let expected = if router.ab_variant(item) == Some('B') { "recursive" } else { "document" };
+            assert_eq!(name, expected);
+        }
+
+        // Only routing decisions are counted, not naming an item's chunker
+        let (a, b) = router.ab_stats()[&SourceKind::Document];
+        assert_eq!(a + b, 1000);
+        let ratio = b as f32 / 1000.0;
+        assert!((0.25..0.35).contains(&ratio), "ratio {}", ratio);
+
+        // Other kinds are unaffected
+        assert_eq!(router.get_chunker(&create_item(SourceKind::Chat, "application/json")).name(), "chat");
+        assert!(!router.ab_stats().contains_key(&SourceKind::Chat));
+    }
+
+    #[tokio::test]
+    async fn test_ab_variant_tagged_on_chunks() {
+        use futures::TryStreamExt;
+
+        let router = ChunkingRouter::default();
+        router.set_ab_route(SourceKind::Other, "sentence", "token", 1.0).unwrap();
+        let item = create_item(SourceKind::Other, "text/plain");
+        let config = ChunkConfig::default();
+
+        let chunks: Vec<Chunk> = router.route_stream(&item, &config).try_collect().await.unwrap();
+        assert!(chunks.iter().all(|c| c.metadata.ab_variant == Some('B')));
+    }
+
+    #[tokio::test]
+    async fn test_routing_metrics() {
+        use futures::TryStreamExt;
+
+        let router = ChunkingRouter::default();
+        let metrics = router.metrics();
+        router.get_chunker(&create_item(SourceKind::Chat, "application/json"));
+        router.get_chunker(&create_item(SourceKind::Chat, "application/json"));
+
+        // Chat chunker rejects content that is not a chat thread
+        let item = create_item(SourceKind::Chat, "application/json");
+        let result: Result<Vec<Chunk>> = router.route_stream(&item, &ChunkConfig::default()).try_collect().await;
+
+        assert_eq!(metrics.routed("chat"), 3);
+        assert_eq!(metrics.errors("chat"), u64::from(result.is_err()));
+        assert_eq!(metrics.routed("code"), 0);
+    }
+
+    #[test]
+    fn test_config_language_detection() {
+        let router = ChunkingRouter::default();
+        let mut item = create_item(SourceKind::CodeRepo, "text/plain");
+        item.metadata = serde_json::json!({"path": "bin/deploy"});
+        item.content = "#!/usr/bin/env bash\nset -e\n".to_string();
+        assert_eq!(router.get_config(&item).language.as_deref(), Some("bash"));
+
+        item.metadata = serde_json::json!({"path": "src/app.mjs"});
+        assert_eq!(router.get_config(&item).language.as_deref(), Some("javascript"));
+    }
+
+    #[test]
+    fn test_config_per_source_kind() {
+        let mut config = ChunkingConfig::default();
+        config.set_kind_config(SourceKind::Email, ChunkConfigOverride::with_size(300));
+        let router = ChunkingRouter::new(&config);
+
+        let chat = router.get_config(&create_item(SourceKind::Chat, "application/json"));
+        let code = router.get_config(&create_item(SourceKind::CodeRepo, "text/code:rust"));
+        assert!(chat.chunk_size < code.chunk_size);
+        let email = router.get_config(&create_item(SourceKind::Email, "text/plain"));
+        assert_eq!(email.chunk_size, 300);
+        // Fields the kind leaves unset come from the global defaults
+        assert_eq!(email.chunk_overlap, config.default_chunk_overlap);
+        assert_eq!(email.min_chars_per_sentence, config.min_chars_per_sentence);
+        assert_eq!(router.get_config(&create_item(SourceKind::Web, "text/html")).chunk_size, config.default_chunk_size);
+
+        // Item metadata overrides the per-kind settings
+        let mut item = create_item(SourceKind::Chat, "application/json");
+        item.metadata = serde_json::json!({"chunk_size": 128, "chunk_overlap": 8});
+        let overridden = router.get_config(&item);
+        assert_eq!((overridden.chunk_size, overridden.chunk_overlap), (128, 8));
+    }
+
+    #[tokio::test]
+    async fn test_route_stream_matches_chunk() {
+        use futures::TryStreamExt;
+
+        let router = ChunkingRouter::default();
+        let mut item = create_item(SourceKind::CodeRepo, "text/code:rust");
+        item.content = (0..200).map(|i| format!("let x{} = {};", i, i)).collect::<Vec<_>>().join("\n");
+        let config = ChunkConfig { chunk_size: 50, ..Default::default() };
+
+        let streamed: Vec<Chunk> = router.route_stream(&item, &config).try_collect().await.unwrap();
+        let collected = router.get_chunker(&item).chunk(&item, &config).unwrap();
+
+        assert!(streamed.len() > 1);
+        assert_eq!(
+            streamed.iter().map(|c| &c.content).collect::<Vec<_>>(),
+            collected.iter().map(|c| &c.content).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route_items_stream() {
+        let router = Arc::new(ChunkingRouter::default().with_concurrency(1));
+        let items: Vec<SourceItem> = ["text/code:rust", "text/markdown", "text/plain"]
+            .into_iter()
+            .map(|content_type| create_item(SourceKind::Other, content_type))
+            .collect();
+        let ids: Vec<Uuid> = items.iter().map(|item| item.id).collect();
+        let config = ChunkConfig::default();
+
+        let results: Vec<(SourceItem, Vec<Chunk>)> =
+            router.route_items_stream(stream::iter(items.clone()), &config).try_collect().await.unwrap();
+        assert_eq!(results.iter().map(|(item, _)| item.id).collect::<Vec<_>>(), ids);
+        for (item, chunks) in &results {
+            assert!(!chunks.is_empty());
+            assert!(chunks.iter().all(|chunk| chunk.source_item_id == item.id));
+        }
+        assert_eq!(router.metrics().routed("code"), 1);
+
+        // The stream is Send, so it can be driven from a spawned task
+        let router = Arc::new(ChunkingRouter::default().with_concurrency(4));
+        let stream = router.route_items_stream(stream::iter(items), &config);
+        let mut returned: Vec<Uuid> =
+            tokio::spawn(stream.map_ok(|(item, _)| item.id).try_collect::<Vec<_>>()).await.unwrap().unwrap();
+        returned.sort();
+        let mut expected = ids;
+        expected.sort();
+        assert_eq!(returned, expected);
+    }
+
+    #[test]
+    fn test_log_routing() {
+        let router = ChunkingRouter::default();
+        let item = create_item(SourceKind::Other, "text/x-log");
+        assert_eq!(router.get_chunker(&item).name(), "log");
+
+        let item = create_item(SourceKind::Other, "application/x-ndjson");
+        assert_eq!(router.get_chunker(&item).name(), "log");
+    }
+
+    #[test]
+    fn test_email_routing() {
+        let router = ChunkingRouter::default();
+        let item = create_item(SourceKind::Email, "text/plain");
+        assert_eq!(router.get_chunker(&item).name(), "email");
+
+        let item = create_item(SourceKind::Other, "message/rfc822");
+        assert_eq!(router.get_chunker(&item).name(), "email");
+    }
+
+    #[test]
+    fn test_shell_routing() {
+        let router = ChunkingRouter::default();
+        let item = create_item(SourceKind::CodeRepo, "text/code:bash");
+        assert_eq!(router.get_chunker(&item).name(), "shell");
+
+        let item = create_item(SourceKind::Other, "application/x-sh");
+        assert_eq!(router.get_chunker(&item).name(), "shell");
+
+        for path in ["deploy.sh", "scripts/setup.bash", ".config/init.zsh"] {
+            let mut item = create_item(SourceKind::CodeRepo, "text/plain");
+            item.metadata = serde_json::json!({"path": path});
+            assert_eq!(router.get_chunker(&item).name(), "shell", "{path}");
+        }
+
+        let item = create_item(SourceKind::CodeRepo, "text/code:rust");
+        assert_eq!(router.get_chunker(&item).name(), "code");
+    }
+
+    #[test]
+    fn test_latex_routing() {
+        let router = ChunkingRouter::default();
+        let item = create_item(SourceKind::Document, "text/x-latex");
+        assert_eq!(router.get_chunker(&item).name(), "latex");
+
+        let item = create_item(SourceKind::Other, "application/x-tex");
+        assert_eq!(router.get_chunker(&item).name(), "latex");
+
+        let item = create_item(SourceKind::CodeRepo, "text/code:latex");
+        assert_eq!(router.get_chunker(&item).name(), "latex");
+    }
+
+    #[test]
+    fn test_xml_routing() {
+        let router = ChunkingRouter::default();
+        for content_type in ["application/xml", "text/xml; charset=utf-8"] {
+            let item = create_item(SourceKind::CodeRepo, content_type);
+            assert_eq!(router.get_chunker(&item).name(), "xml");
+        }
+    }
+
+    #[test]
+    fn test_patch_routing() {
+        let router = ChunkingRouter::default();
+        for content_type in ["text/x-diff", "text/x-patch"] {
+            let item = create_item(SourceKind::CodeRepo, content_type);
+            assert_eq!(router.get_chunker(&item).name(), "patch");
+        }
+    }
+
+    #[test]
+    fn test_ini_routing() {
+        let router = ChunkingRouter::default();
+        let item = create_item(SourceKind::CodeRepo, "text/x-ini");
+        assert_eq!(router.get_chunker(&item).name(), "ini");
+    }
+
+    /// A plugin that keeps the whole item as one chunk.
+    struct HclChunker;
+
+    impl Chunker for HclChunker {
+        fn name(&self) -> &'static str {
+            "hcl"
+        }
+
+        fn chunk(&self, item: &SourceItem, _config: &ChunkConfig) -> Result<Vec<Chunk>> {
+            Ok(vec![Chunk::new(
+                item.id,
+                item.source_id,
+                item.source_kind,
+                item.content.clone(),
+                1,
+                0,
+                item.content.len(),
+                0,
+            )])
+        }
+
+        fn description(&self) -> &'static str {
+            "Terraform/HCL blocks"
+        }
+    }
+
+    #[test]
+    fn test_plugin_routing() {
+        let router = ChunkingRouter::default();
+        router
+            .register_chunker("hcl", Arc::new(HclChunker), vec!["text/x-hcl"], vec![SourceKind::Web])
+            .unwrap();
+
+        let item = create_item(SourceKind::CodeRepo, "Text/X-HCL; charset=utf-8");
+        assert_eq!(router.get_chunker(&item).name(), "hcl");
+        assert_eq!(router.get_chunker(&create_item(SourceKind::Web, "text/html")).name(), "hcl");
+        // Built-in content types still win
+        assert_eq!(router.get_chunker(&create_item(SourceKind::Web, "text/markdown")).name(), "document");
+
+        assert_eq!(router.get_chunker_by_name("HCL").unwrap().name(), "hcl");
+        let listed = router.list_chunkers();
+        assert!(listed.iter().any(|c| c.name == "hcl" && c.is_plugin));
+        assert!(listed.iter().filter(|c| c.name != "hcl").all(|c| !c.is_plugin));
+
+        assert!(router.register_chunker("markdown", Arc::new(HclChunker), vec![], vec![]).is_err());
+
+        assert!(router.unregister_chunker("hcl"));
+        assert!(!router.unregister_chunker("hcl"));
+        assert_eq!(router.get_chunker(&item).name(), "code");
+        assert!(router.get_chunker_by_name("hcl").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_route_stream_and_ab() {
+        use futures::TryStreamExt;
+
+        let router = ChunkingRouter::default();
+        router.register_chunker("hcl", Arc::new(HclChunker), vec!["text/x-hcl"], vec![]).unwrap();
+        let mut item = create_item(SourceKind::Other, "text/x-hcl");
+        item.content = "resource \"aws_s3_bucket\" \"logs\" {}".to_string();
+        let config = ChunkConfig::default();
+
+        let streamed: Vec<Chunk> = router.route_stream(&item, &config).try_collect().await.unwrap();
+        assert_eq!(streamed.len(), 1);
+        assert_eq!(streamed[0].content, item.content);
+
+        router.set_ab_route(SourceKind::Chat, "chat", "hcl", 1.0).unwrap();
+        assert_eq!(router.get_chunker(&create_item(SourceKind::Chat, "text/plain")).name(), "hcl");
+    }
+
+    #[tokio::test]
+    async fn test_plugin_metrics() {
+        use futures::StreamExt;
+
+        /// A plugin whose chunking always fails.
+        struct FailingChunker;
+
+        impl Chunker for FailingChunker {
+            fn name(&self) -> &'static str {
+                "failing"
+            }
+
+            fn chunk(&self, _item: &SourceItem, _config: &ChunkConfig) -> Result<Vec<Chunk>> {
+                Err(anyhow!("cannot chunk"))
+            }
+
+            fn description(&self) -> &'static str {
+                "Always fails"
+            }
+        }
+
+        let router = ChunkingRouter::default();
+        router.register_chunker("hcl", Arc::new(HclChunker), vec!["text/x-hcl"], vec![]).unwrap();
+        router.register_chunker("failing", Arc::new(FailingChunker), vec!["text/x-failing"], vec![]).unwrap();
+        let config = ChunkConfig::default();
+
+        let mut item = create_item(SourceKind::Other, "text/x-hcl");
+        item.content = "variable \"region\" {}".to_string();
+        assert_eq!(router.route_stream(&item, &config).count().await, 1);
+        let failing = create_item(SourceKind::Other, "text/x-failing");
+        let results: Vec<_> = router.route_stream(&failing, &config).collect().await;
+        assert!(results[0].is_err());
+
+        let metrics = router.metrics();
+        assert_eq!((metrics.routed("hcl"), metrics.errors("hcl")), (1, 0));
+        assert_eq!((metrics.routed("failing"), metrics.errors("failing")), (1, 1));
+        let text = metrics.to_prometheus_text();
+        assert!(text.contains("chunker_items_routed_total{chunker=\"hcl\"} 1\n"));
+        assert!(text.contains("chunker_errors_total{chunker=\"failing\"} 1\n"));
+    }
 }
@@ -0,0 +1,168 @@
+//! Prometheus metrics for item processing, jobs and downstream clients.
+//!
+//! Recording functions are no-ops unless the `metrics` feature is enabled
+//! and `install_recorder` has been called.
+
+use std::time::Duration;
+
+use crate::types::Chunk;
+
+/// Items processed, labelled by `chunker` and `status`.
+pub const ITEMS_PROCESSED: &str = "chunker_items_processed_total";
+/// Token count of each produced chunk.
+pub const CHUNK_SIZE_TOKENS: &str = "chunker_chunk_size_tokens";
+/// Time to chunk a single item.
+pub const PROCESSING_DURATION: &str = "chunker_processing_duration_seconds";
+/// Jobs currently being processed.
+pub const ACTIVE_JOBS: &str = "chunker_batch_active_jobs";
+/// Time to send chunks to the embedding service.
+pub const EMBEDDING_SEND_DURATION: &str = "embedding_client_send_duration_seconds";
+/// Time to send chunks to the relation-graph service.
+pub const RELATION_GRAPH_SEND_DURATION: &str = "relation_graph_client_send_duration_seconds";
+
+#[cfg(feature = "metrics")]
+mod recorder {
+    use std::sync::OnceLock;
+
+    use anyhow::Result;
+    use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+    use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+    use super::*;
+
+    const DURATION_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0];
+    const TOKEN_BUCKETS: &[f64] = &[32.0, 64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0];
+
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+    pub fn install_recorder() -> Result<()> {
+        let duration = |name: &'static str| Matcher::Full(name.to_string());
+        let handle = PrometheusBuilder::new()
+            .set_buckets_for_metric(duration(PROCESSING_DURATION), DURATION_BUCKETS)?
+            .set_buckets_for_metric(duration(EMBEDDING_SEND_DURATION), DURATION_BUCKETS)?
+            .set_buckets_for_metric(duration(RELATION_GRAPH_SEND_DURATION), DURATION_BUCKETS)?
+            .set_buckets_for_metric(Matcher::Full(CHUNK_SIZE_TOKENS.to_string()), TOKEN_BUCKETS)?
+            .install_recorder()?;
+
+        describe_counter!(ITEMS_PROCESSED, "Items processed per chunker and status");
+        describe_histogram!(CHUNK_SIZE_TOKENS, "Token count of produced chunks");
+        describe_histogram!(PROCESSING_DURATION, Unit::Seconds, "Time to chunk a single item");
+        describe_gauge!(ACTIVE_JOBS, "Jobs currently being processed");
+        describe_histogram!(EMBEDDING_SEND_DURATION, Unit::Seconds, "Time to send chunks to the embedding service");
+        describe_histogram!(
+            RELATION_GRAPH_SEND_DURATION,
+            Unit::Seconds,
+            "Time to send chunks to the relation-graph service"
+        );
+
+        let _ = HANDLE.set(handle);
+        Ok(())
+    }
+
+    pub fn render() -> Option<String> {
+        HANDLE.get().map(PrometheusHandle::render)
+    }
+}
+
+/// Install the global Prometheus recorder.
+#[cfg(feature = "metrics")]
+pub fn install_recorder() -> anyhow::Result<()> {
+    recorder::install_recorder()
+}
+
+/// Install the global Prometheus recorder.
+#[cfg(not(feature = "metrics"))]
+pub fn install_recorder() -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Render recorded metrics in the Prometheus text format, if a recorder
+/// is installed.
+pub fn render() -> Option<String> {
+    #[cfg(feature = "metrics")]
+    return recorder::render();
+    #[cfg(not(feature = "metrics"))]
+    None
+}
+
+/// Record an item chunked by a chunker, and how long it took.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub fn record_item_processed(chunker: &'static str, success: bool, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        let status = if success { "success" } else { "error" };
+        metrics::counter!(ITEMS_PROCESSED, "chunker" => chunker, "status" => status).increment(1);
+        metrics::histogram!(PROCESSING_DURATION, "chunker" => chunker).record(duration.as_secs_f64());
+    }
+}
+
+/// Record the token counts of produced chunks.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub fn record_chunk_sizes(chunks: &[Chunk]) {
+    #[cfg(feature = "metrics")]
+    for chunk in chunks {
+        metrics::histogram!(CHUNK_SIZE_TOKENS).record(chunk.token_count as f64);
+    }
+}
+
+/// Record a job starting.
+pub fn job_started() {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!(ACTIVE_JOBS).increment(1.0);
+}
+
+/// Record a job finishing.
+pub fn job_finished() {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!(ACTIVE_JOBS).decrement(1.0);
+}
+
+/// Record a send to the embedding service.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub fn record_embedding_send(duration: Duration) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(EMBEDDING_SEND_DURATION).record(duration.as_secs_f64());
+}
+
+/// Record a send to the relation-graph service.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub fn record_relation_graph_send(duration: Duration) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(RELATION_GRAPH_SEND_DURATION).record(duration.as_secs_f64());
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use metrics_util::MetricKind;
+
+    #[test]
+    fn test_records_item_and_job_metrics() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            job_started();
+            record_item_processed("code", true, Duration::from_millis(5));
+            record_item_processed("code", false, Duration::from_millis(5));
+            job_finished();
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let counters: Vec<_> = snapshot
+            .iter()
+            .filter(|(key, ..)| key.kind() == MetricKind::Counter && key.key().name() == ITEMS_PROCESSED)
+            .collect();
+        assert_eq!(counters.len(), 2);
+        assert!(counters
+            .iter()
+            .all(|(.., value)| matches!(value, DebugValue::Counter(1))));
+
+        let active = snapshot
+            .iter()
+            .find(|(key, ..)| key.key().name() == ACTIVE_JOBS)
+            .map(|(.., value)| value);
+        assert!(matches!(active, Some(DebugValue::Gauge(v)) if v.into_inner() == 0.0));
+    }
+}
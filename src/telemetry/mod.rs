@@ -0,0 +1,138 @@
+//! Tracing setup, OTLP export, trace context propagation and metrics.
+//!
+//! Spans are exported over OTLP/HTTP when `OTEL_EXPORTER_OTLP_ENDPOINT` is
+//! set. W3C `traceparent` headers on inbound requests become the parent of
+//! the request span, and are injected into outbound requests to downstream
+//! services.
+
+pub mod metrics;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use axum::http::{HeaderMap, Request};
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Service name reported on exported spans.
+const SERVICE_NAME: &str = "chunker";
+
+/// Initialize the global tracing subscriber.
+///
+/// Logs are always written to stdout; spans are additionally exported when
+/// an OTLP endpoint is configured.
+pub fn init_tracing() -> Result<()> {
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let otel_layer = match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(_) => {
+            // The exporter reads the endpoint from the environment itself
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(opentelemetry_otlp::new_exporter().http())
+                .with_trace_config(
+                    trace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", SERVICE_NAME)])),
+                )
+                .install_batch(runtime::Tokio)?;
+            Some(tracing_opentelemetry::layer().with_tracer(tracer))
+        }
+        Err(_) => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new(
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "chunker=info,tower_http=debug".into()),
+        ))
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel_layer)
+        .init();
+
+    Ok(())
+}
+
+/// Flush and stop span export.
+pub fn shutdown_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Create the span for an inbound HTTP request, continuing any trace
+/// context from its `traceparent` header.
+pub fn http_request_span<B>(request: &Request<B>) -> Span {
+    let span = tracing::info_span!(
+        "http_request",
+        method = %request.method(),
+        uri = %request.uri(),
+    );
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(request.headers()));
+    span.set_parent(parent);
+    span
+}
+
+/// Add the current span's trace context headers to an outbound request.
+pub fn inject_trace_context(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let mut headers = HashMap::new();
+    let context = Span::current().context();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut headers);
+    });
+
+    headers
+        .into_iter()
+        .fold(request, |request, (name, value)| request.header(name, value))
+}
+
+/// Reads propagation fields from HTTP headers.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceContextExt;
+    use opentelemetry::trace::TracerProvider as _;
+    use tracing_subscriber::Registry;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_traceparent_propagates_to_outbound_request() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = trace::TracerProvider::builder().build();
+        let subscriber =
+            Registry::default().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request = Request::builder()
+                .uri("/chunk/jobs")
+                .header("traceparent", TRACEPARENT)
+                .body(())
+                .unwrap();
+            let span = http_request_span(&request);
+            assert_eq!(
+                span.context().span().span_context().trace_id().to_string(),
+                "4bf92f3577b34da6a3ce929d0e0e4736"
+            );
+
+            let _guard = span.enter();
+            let outbound = inject_trace_context(reqwest::Client::new().get("http://localhost/"))
+                .build()
+                .unwrap();
+            let traceparent = outbound.headers()["traceparent"].to_str().unwrap();
+            assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        });
+    }
+}
@@ -0,0 +1,11 @@
+//! Helpers shared by unit tests and benches (the `test-support` feature)
+
+use axum::Router;
+
+/// Serve `app` on an ephemeral local port, returning its base URL
+pub async fn serve_mock(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
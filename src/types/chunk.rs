@@ -1,16 +1,27 @@
 //! Chunk type definitions.
 
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use super::SourceKind;
 
+/// SHA-256 of a chunk's content.
+pub type ContentHash = [u8; 32];
+
 /// A chunk of content extracted from a source item.
 ///
 /// Chunks are the fundamental unit of content that gets embedded and indexed.
 /// Each chunk maintains references back to its source for traceability.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Chunks compare equal, and hash the same, when their content is
+/// identical, regardless of source or position.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Chunk {
     /// Unique identifier for this chunk
     pub id: Uuid,
@@ -39,6 +50,11 @@ pub struct Chunk {
     /// Order of this chunk within its source item (0-indexed)
     pub chunk_index: usize,
     
+    /// Chunk this one was split from, for hierarchical chunking (e.g. the
+    /// function a block belongs to, or the section of a sub-section)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_chunk_id: Option<Uuid>,
+    
     /// Additional metadata about this chunk
     pub metadata: ChunkMetadata,
     
@@ -52,6 +68,7 @@ pub struct Chunk {
 
 impl Chunk {
     /// Create a new chunk with the given parameters.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         source_item_id: Uuid,
         source_id: Uuid,
@@ -72,18 +89,89 @@ impl Chunk {
             start_index,
             end_index,
             chunk_index,
+            parent_chunk_id: None,
             metadata: ChunkMetadata::default(),
             embedding: None,
             created_at: Utc::now(),
         }
     }
 
+    /// Create a chunk whose ID is derived from its source item and content.
+    ///
+    /// Takes the same arguments as `new`; the ID is `content_hash_id(source_item_id,
+    /// start_index, &content)` instead of a random v4 UUID.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_content_hash_id(
+        source_item_id: Uuid,
+        source_id: Uuid,
+        source_kind: SourceKind,
+        content: String,
+        token_count: usize,
+        start_index: usize,
+        end_index: usize,
+        chunk_index: usize,
+    ) -> Self {
+        let mut chunk = Self::new(
+            source_item_id,
+            source_id,
+            source_kind,
+            content,
+            token_count,
+            start_index,
+            end_index,
+            chunk_index,
+        );
+        chunk.id = Self::content_hash_id(source_item_id, start_index, &chunk.content);
+        chunk
+    }
+
+    /// Deterministic chunk ID for content at `start_index` of a source item.
+    ///
+    /// The ID is `Uuid::new_v5(&Uuid::NAMESPACE_OID, digest)`, where `digest`
+    /// is the 32-byte SHA-256 of the concatenation of:
+    ///
+    /// 1. the 16 bytes of `source_item_id` (`Uuid::as_bytes`),
+    /// 2. `start_index` as a big-endian `u64` (8 bytes),
+    /// 3. the UTF-8 bytes of `content`.
+    ///
+    /// Identical content at the same offset within one item therefore
+    /// always maps to the same ID. The item ID is used rather than
+    /// `source_id`, which every file of a repository shares, so IDs are only
+    /// stable across runs if items keep their IDs.
+    pub fn content_hash_id(source_item_id: Uuid, start_index: usize, content: &str) -> Uuid {
+        let mut hasher = Sha256::new();
+        hasher.update(source_item_id.as_bytes());
+        hasher.update((start_index as u64).to_be_bytes());
+        hasher.update(content.as_bytes());
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, &hasher.finalize())
+    }
+
+    /// SHA-256 of the chunk's content, for deduplication.
+    pub fn content_hash(&self) -> ContentHash {
+        Sha256::digest(self.content.as_bytes()).into()
+    }
+
     /// Create a chunk with metadata.
     pub fn with_metadata(mut self, metadata: ChunkMetadata) -> Self {
         self.metadata = metadata;
         self
     }
 
+    /// Set the chunk this one was split from.
+    pub fn with_parent(mut self, parent_chunk_id: Uuid) -> Self {
+        self.parent_chunk_id = Some(parent_chunk_id);
+        self
+    }
+
+    /// Key giving a deterministic order across a batch: by source item,
+    /// then by position within it.
+    ///
+    /// Uses `source_item_id` rather than `source_id`, since every item of a
+    /// source (e.g. all files of a repository) shares the same `source_id`.
+    pub fn natural_order_key(&self) -> (Uuid, usize) {
+        (self.source_item_id, self.start_index)
+    }
+
     /// Get the length of the chunk content in characters.
     pub fn len(&self) -> usize {
         self.content.len()
@@ -95,11 +183,54 @@ impl Chunk {
     }
 }
 
+impl PartialEq for Chunk {
+    fn eq(&self, other: &Self) -> bool {
+        self.content == other.content
+    }
+}
+
+impl Eq for Chunk {}
+
+/// Hashes the same SHA-256 `content_hash` that `deduplicate_chunks` keys on.
+///
+/// `source_kind` and the offsets are left out: `Eq` compares content only,
+/// so copies of a file at other positions or in other sources are equal,
+/// and equal chunks must hash the same.
+impl Hash for Chunk {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.content_hash().hash(state);
+    }
+}
+
+/// Replace the IDs of chunks from one chunking run with `Chunk::content_hash_id`.
+///
+/// `parent_chunk_id` links between the chunks are rewritten to match. A
+/// parent outside the slice (e.g. the virtual parent of a split function)
+/// gets an ID derived from the new ID of its first child.
+pub fn assign_content_hash_ids(chunks: &mut [Chunk]) {
+    let mut new_ids = HashMap::new();
+    for chunk in chunks.iter_mut() {
+        let id = Chunk::content_hash_id(chunk.source_item_id, chunk.start_index, &chunk.content);
+        new_ids.insert(chunk.id, id);
+        chunk.id = id;
+    }
+
+    for chunk in chunks.iter_mut() {
+        if let Some(parent) = chunk.parent_chunk_id {
+            let child = chunk.id;
+            let id = *new_ids
+                .entry(parent)
+                .or_insert_with(|| Uuid::new_v5(&Uuid::NAMESPACE_OID, child.as_bytes()));
+            chunk.parent_chunk_id = Some(id);
+        }
+    }
+}
+
 /// Metadata associated with a chunk.
 ///
 /// Contains contextual information that helps understand the chunk's
 /// origin and structure within its source.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct ChunkMetadata {
     /// Type of content (e.g., "function", "class", "paragraph", "message")
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -117,6 +248,10 @@ pub struct ChunkMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub section: Option<String>,
     
+    /// Headings from the document root down to `section` (for documents)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub section_hierarchy: Vec<String>,
+    
     /// Function or class name (for code)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub symbol_name: Option<String>,
@@ -127,12 +262,21 @@ pub struct ChunkMetadata {
     
     /// Line numbers in original file (start, end)
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Vec<usize>>)]
     pub line_range: Option<(usize, usize)>,
     
-    /// Author or speaker (for chat/comments)
+    /// Author or speaker (for chat/comments, and documents with front matter)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
     
+    /// Tags from the document's front matter (for documents)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    
+    /// Labels attached to the issue (for tickets)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub label_names: Vec<String>,
+    
     /// Thread ID (for chat/comments)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thread_id: Option<String>,
@@ -141,11 +285,122 @@ pub struct ChunkMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<DateTime<Utc>>,
     
+    /// Timestamp of the first message in the chunk (for chat)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_start: Option<DateTime<Utc>>,
+    
+    /// Timestamp of the last message in the chunk (for chat)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_end: Option<DateTime<Utc>>,
+    
+    /// Most severe log level in the chunk (for logs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    
+    /// Timestamp of the first entry in the chunk (for logs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<DateTime<Utc>>,
+    
+    /// Timestamp of the last entry in the chunk (for logs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<Utc>>,
+    
+    /// Subject line (for emails)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    
+    /// Recipient addresses (for emails)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+    
+    /// Message-ID header (for emails)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    
+    /// In-Reply-To header (for emails)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_reply_to: Option<String>,
+    
+    /// Message ids from the References header (for emails)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    
+    /// Whether the chunk is quoted reply text (for emails)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_quoted: bool,
+    
+    /// Whether the chunk starts with the tail of the previous chunk
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub has_overlap_prefix: bool,
+    
+    /// How cleanly the chunk is bounded, from 0.0 to 1.0 (for agentic chunks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coherence_score: Option<f32>,
+    
+    /// Kind of semantic boundary the chunk starts at, e.g. `function_def` (for agentic chunks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boundary_type: Option<String>,
+    
+    /// Strength of the boundary the chunk starts at, from 0.0 to 1.0 (for agentic chunks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boundary_strength: Option<f32>,
+    
+    /// Canonical path of the file a diff chunk changes (for patches)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_file: Option<String>,
+    
+    /// Lines added and removed in a diff chunk (for patches)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff_stats: Option<DiffStats>,
+    
+    /// Whether the chunk is a fenced code block extracted from a document
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_code_block: bool,
+    
+    /// Whether the chunk contains test code (for code)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_test: bool,
+    
+    /// Whether the file's imports were prepended to the chunk (for code)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub has_injected_header: bool,
+    
+    /// Number of tiny chunks merged into this one (for code)
+    #[serde(default)]
+    pub merged_chunks: usize,
+    
+    /// Cyclomatic complexity normalized to 0.0-1.0 (for code)
+    #[serde(default)]
+    pub complexity_score: f32,
+    
+    /// Lowercased keywords for hybrid (keyword + vector) search
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    
+    /// Owner the chunk is attributed to (from the job's authenticated caller)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<String>,
+    
+    /// A/B routing variant that produced the chunk ('A' or 'B')
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub ab_variant: Option<char>,
+    
     /// Additional arbitrary metadata
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
     pub extra: Option<serde_json::Value>,
 }
 
+/// Line counts of a diff chunk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DiffStats {
+    /// Lines starting with `+`
+    pub added: usize,
+    /// Lines starting with `-`
+    pub removed: usize,
+}
+
 impl ChunkMetadata {
     /// Create metadata for a code chunk.
     pub fn for_code(language: &str, path: Option<&str>) -> Self {
@@ -188,3 +443,92 @@ impl ChunkMetadata {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(content: &str, start: usize, parent: Option<Uuid>) -> Chunk {
+        let (source_item_id, source_id) = (Uuid::from_u128(1), Uuid::from_u128(42));
+        let end = start + content.len();
+        let chunk = Chunk::new(source_item_id, source_id, SourceKind::CodeRepo, content.to_string(), 1, start, end, 0);
+        match parent {
+            Some(parent) => chunk.with_parent(parent),
+            None => chunk,
+        }
+    }
+
+    #[test]
+    fn test_content_hash_id_is_deterministic() {
+        let item_id = Uuid::from_u128(42);
+        let create = || {
+            Chunk::with_content_hash_id(item_id, Uuid::new_v4(), SourceKind::Document, "hello".to_string(), 1, 0, 5, 0)
+        };
+        let (a, b) = (create(), create());
+
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.id.get_version_num(), 5);
+        assert_ne!(a.id, Chunk::content_hash_id(item_id, 1, "hello"));
+        assert_ne!(a.id, Chunk::content_hash_id(Uuid::from_u128(7), 0, "hello"));
+        assert_ne!(a.id, Chunk::content_hash_id(item_id, 0, "hello!"));
+    }
+
+    #[test]
+    fn test_chunks_equal_by_content() {
+        use std::collections::HashSet;
+
+        let a = chunk("pub fn vendored() {}", 0, None);
+        let b = chunk("pub fn vendored() {}", 120, Some(a.id));
+        let c = chunk("pub fn original() {}", 0, None);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_eq!(HashSet::from([a, b, c]).len(), 2);
+    }
+
+    #[test]
+    fn test_assign_content_hash_ids_keeps_parent_links() {
+        let virtual_parent = Uuid::new_v4();
+        let run = || {
+            let section = chunk("# Intro", 0, None);
+            let body = chunk("body", 8, Some(section.id));
+            let block = chunk("fn a() {}", 20, Some(virtual_parent));
+            let mut chunks = vec![section, body, block];
+            assign_content_hash_ids(&mut chunks);
+            chunks
+        };
+
+        let (first, second) = (run(), run());
+        let ids = |chunks: &[Chunk]| chunks.iter().map(|c| (c.id, c.parent_chunk_id)).collect::<Vec<_>>();
+        assert_eq!(ids(&first), ids(&second));
+        assert_eq!(first[1].parent_chunk_id, Some(first[0].id));
+        assert_ne!(first[2].parent_chunk_id, Some(virtual_parent));
+    }
+
+    #[test]
+    fn test_assign_content_hash_ids_distinguishes_items_and_offsets() {
+        // e.g. a license header repeated within one file and in another file
+        let header = "// SPDX-License-Identifier: MIT";
+        let mut other_source = chunk(header, 0, None);
+        other_source.source_id = Uuid::from_u128(7);
+        other_source.source_item_id = Uuid::from_u128(8);
+        let mut chunks = vec![chunk(header, 0, None), chunk(header, 500, None), other_source];
+        assign_content_hash_ids(&mut chunks);
+
+        let ids: std::collections::HashSet<Uuid> = chunks.iter().map(|c| c.id).collect();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn test_assign_content_hash_ids_distinguishes_files_of_one_source() {
+        // Two files of one repository with identical content
+        let mut copy = chunk("pub fn vendored() {}", 0, None);
+        copy.source_item_id = Uuid::from_u128(2);
+        let mut chunks = vec![chunk("pub fn vendored() {}", 0, None), copy];
+        assert_eq!(chunks[0].source_id, chunks[1].source_id);
+        assign_content_hash_ids(&mut chunks);
+
+        assert_ne!(chunks[0].id, chunks[1].id);
+    }
+}
@@ -0,0 +1,120 @@
+//! Parent/child relationships between chunks.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use super::Chunk;
+
+/// Directed graph of parent → child chunk edges.
+///
+/// Built from `Chunk::parent_chunk_id`, so a parent may be a virtual chunk
+/// (e.g. a whole function that was split) that is not itself in the graph's
+/// source chunks.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkGraph {
+    children: HashMap<Uuid, Vec<Uuid>>,
+    parents: HashMap<Uuid, Uuid>,
+}
+
+impl ChunkGraph {
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a graph from the parent links of the given chunks.
+    pub fn from_chunks(chunks: &[Chunk]) -> Self {
+        let mut graph = Self::new();
+        for chunk in chunks {
+            if let Some(parent) = chunk.parent_chunk_id {
+                graph.add_edge(parent, chunk.id);
+            }
+        }
+        graph
+    }
+
+    /// Add a parent → child edge. A child has at most one parent; adding a
+    /// second parent replaces the first.
+    pub fn add_edge(&mut self, parent: Uuid, child: Uuid) {
+        if let Some(previous) = self.parents.insert(child, parent) {
+            if let Some(siblings) = self.children.get_mut(&previous) {
+                siblings.retain(|&id| id != child);
+            }
+        }
+        self.children.entry(parent).or_default().push(child);
+    }
+
+    /// Children of a chunk, in insertion order.
+    pub fn children(&self, parent: Uuid) -> &[Uuid] {
+        self.children.get(&parent).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Parent of a chunk, if it has one.
+    pub fn parent(&self, child: Uuid) -> Option<Uuid> {
+        self.parents.get(&child).copied()
+    }
+
+    /// Number of parent → child edges.
+    pub fn edge_count(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Check if the graph has no edges.
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Convert to an adjacency list mapping each parent to its children.
+    pub fn to_adjacency_list(&self) -> HashMap<Uuid, Vec<Uuid>> {
+        self.children
+            .iter()
+            .filter(|(_, children)| !children.is_empty())
+            .map(|(&parent, children)| (parent, children.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SourceKind;
+
+    fn chunk(parent: Option<Uuid>) -> Chunk {
+        let chunk = Chunk::new(Uuid::nil(), Uuid::nil(), SourceKind::CodeRepo, "x".to_string(), 1, 0, 1, 0);
+        match parent {
+            Some(parent) => chunk.with_parent(parent),
+            None => chunk,
+        }
+    }
+
+    #[test]
+    fn test_from_chunks() {
+        let function = chunk(None);
+        let block_a = chunk(Some(function.id));
+        let block_b = chunk(Some(function.id));
+        let graph = ChunkGraph::from_chunks(&[function.clone(), block_a.clone(), block_b.clone()]);
+
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.children(function.id), &[block_a.id, block_b.id]);
+        assert_eq!(graph.parent(block_a.id), Some(function.id));
+        assert_eq!(graph.parent(function.id), None);
+
+        let adjacency = graph.to_adjacency_list();
+        assert_eq!(adjacency.len(), 1);
+        assert_eq!(adjacency[&function.id], vec![block_a.id, block_b.id]);
+    }
+
+    #[test]
+    fn test_reparenting_moves_child() {
+        let (a, b, child) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut graph = ChunkGraph::new();
+        graph.add_edge(a, child);
+        graph.add_edge(b, child);
+
+        assert_eq!(graph.edge_count(), 1);
+        assert!(graph.children(a).is_empty());
+        assert_eq!(graph.children(b), &[child]);
+        assert_eq!(graph.to_adjacency_list().len(), 1);
+    }
+}
@@ -1,11 +1,24 @@
 //! Configuration types for chunking.
 
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use super::SourceKind;
 
-use crate::{DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_SIZE, DEFAULT_MIN_CHARS_PER_SENTENCE};
+use crate::chunkers::TiktokenCounter;
+use crate::{
+    DEFAULT_CHUNK_OVERLAP, DEFAULT_CHUNK_SIZE, DEFAULT_MAX_SYNC_CONTENT_SIZE, DEFAULT_MIN_CHARS_PER_SENTENCE,
+    DEFAULT_TOKENIZER_MODEL,
+};
 
 /// Global chunking service configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ChunkingConfig {
     /// Default chunk size in tokens
     pub default_chunk_size: usize,
@@ -16,9 +29,15 @@ pub struct ChunkingConfig {
     /// Minimum characters per sentence
     pub min_chars_per_sentence: usize,
     
+    /// Model whose tokenizer counts tokens (e.g. `gpt-4`, `text-davinci-003`)
+    pub tokenizer_model: String,
+    
     /// URL of the embedding service
     pub embedding_service_url: Option<String>,
     
+    /// Embedding service URLs to fail over to, in order
+    pub embedding_fallback_urls: Vec<String>,
+    
     /// URL of the graph service
     pub graph_service_url: Option<String>,
     
@@ -27,6 +46,44 @@ pub struct ChunkingConfig {
     
     /// Active chunking profile name
     pub active_profile: String,
+    
+    /// Maximum content size in bytes for `POST /chunk/sync`
+    pub max_sync_content_size: usize,
+    
+    /// Whether to serve the Swagger UI at `/docs`
+    pub enable_swagger_ui: bool,
+    
+    /// File webhook registrations are persisted to (in-memory if unset)
+    pub webhook_store_path: Option<String>,
+    
+    /// Accept webhook URLs on loopback and private networks (for local
+    /// development; off by default so webhooks cannot reach internal services)
+    pub allow_private_webhook_urls: bool,
+    
+    /// SQLite database URL jobs are persisted to (in-memory if unset)
+    pub job_store_url: Option<String>,
+    
+    /// How long finished jobs are kept before they are deleted
+    pub job_retention_secs: u64,
+    
+    /// Derive chunk IDs from content for every source kind (see
+    /// `ChunkConfig::use_deterministic_ids`)
+    pub use_deterministic_ids: bool,
+    
+    /// NATS JetStream connection settings
+    pub nats: NatsConfig,
+    
+    /// Redis Streams connection settings
+    pub redis: RedisConfig,
+    
+    /// Chunk sizes by language, taking precedence over the source kind and
+    /// global sizes (`[language_size_overrides]` table in TOML)
+    pub language_size_overrides: HashMap<String, usize>,
+    
+    /// Chunk settings by source kind, merged field by field over the global
+    /// defaults for items of that kind (`[per_kind.<kind>]` tables in TOML)
+    #[serde(rename = "per_kind", deserialize_with = "deserialize_per_kind_config")]
+    pub per_kind_config: HashMap<SourceKind, ChunkConfigOverride>,
 }
 
 impl Default for ChunkingConfig {
@@ -35,44 +92,362 @@ impl Default for ChunkingConfig {
             default_chunk_size: DEFAULT_CHUNK_SIZE,
             default_chunk_overlap: DEFAULT_CHUNK_OVERLAP,
             min_chars_per_sentence: DEFAULT_MIN_CHARS_PER_SENTENCE,
+            tokenizer_model: DEFAULT_TOKENIZER_MODEL.to_string(),
             embedding_service_url: None,
+            embedding_fallback_urls: Vec::new(),
             graph_service_url: None,
             max_concurrent_jobs: 4,
             active_profile: "default".to_string(),
+            max_sync_content_size: DEFAULT_MAX_SYNC_CONTENT_SIZE,
+            enable_swagger_ui: false,
+            webhook_store_path: None,
+            allow_private_webhook_urls: false,
+            job_store_url: None,
+            job_retention_secs: 3600,
+            use_deterministic_ids: false,
+            nats: NatsConfig::default(),
+            redis: RedisConfig::default(),
+            language_size_overrides: HashMap::new(),
+            per_kind_config: default_per_kind_config(),
         }
     }
 }
 
+/// Chunk sizes suited to each source kind: small for chat messages,
+/// medium for code, large for prose.
+fn default_per_kind_config() -> HashMap<SourceKind, ChunkConfigOverride> {
+    HashMap::from([
+        (SourceKind::CodeRepo, ChunkConfigOverride::with_size(512).with_overlap(50)),
+        (SourceKind::Document, ChunkConfigOverride::with_size(1024).with_overlap(100)),
+        (SourceKind::Chat, ChunkConfigOverride::with_size(256).with_overlap(25)),
+    ])
+}
+
+/// Deserialize per-kind settings on top of the built-in per-kind defaults.
+fn deserialize_per_kind_config<'de, D>(
+    deserializer: D,
+) -> std::result::Result<HashMap<SourceKind, ChunkConfigOverride>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut config = default_per_kind_config();
+    config.extend(HashMap::<SourceKind, ChunkConfigOverride>::deserialize(deserializer)?);
+    Ok(config)
+}
+
 impl ChunkingConfig {
+    /// Smallest accepted `default_chunk_size`.
+    pub const MIN_CHUNK_SIZE: usize = 64;
+
     /// Load configuration from environment variables.
     pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.apply_env();
+        config
+    }
+
+    /// Load configuration from a TOML file.
+    ///
+    /// Keys missing from the file keep their default values.
+    pub fn from_toml(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Load configuration from a TOML file, with environment variables
+    /// taking precedence over values from the file.
+    pub fn from_toml_with_env_override(path: &Path) -> Result<Self> {
+        let mut config = Self::from_toml(path)?;
+        config.apply_env();
+        Ok(config)
+    }
+
+    /// Set the chunk settings for one source kind.
+    pub fn set_kind_config(&mut self, kind: SourceKind, config: ChunkConfigOverride) -> &mut Self {
+        self.per_kind_config.insert(kind, config);
+        self
+    }
+
+    /// Write the default configuration to a TOML file.
+    pub fn write_default_config(path: &Path) -> Result<()> {
+        let text = toml::to_string_pretty(&Self::default()).context("Failed to serialize default config")?;
+        std::fs::write(path, text).with_context(|| format!("Failed to write config file {}", path.display()))
+    }
+
+    /// Check the configuration for mistakes.
+    ///
+    /// Returns the warnings if the configuration is usable, or every fatal
+    /// error otherwise.
+    pub fn validate(&self) -> std::result::Result<Vec<String>, Vec<String>> {
+        let mut warnings = Vec::new();
+        let mut errors = Vec::new();
+
+        if self.default_chunk_size < Self::MIN_CHUNK_SIZE {
+            errors.push(format!(
+                "default_chunk_size must be at least {}, got {}",
+                Self::MIN_CHUNK_SIZE,
+                self.default_chunk_size
+            ));
+        }
+        if self.default_chunk_overlap >= self.default_chunk_size {
+            errors.push(format!(
+                "default_chunk_overlap ({}) must be less than default_chunk_size ({})",
+                self.default_chunk_overlap, self.default_chunk_size
+            ));
+        } else if self.default_chunk_overlap > self.default_chunk_size / 2 {
+            warnings.push(format!(
+                "default_chunk_overlap ({}) is more than half of default_chunk_size ({})",
+                self.default_chunk_overlap, self.default_chunk_size
+            ));
+        }
+        if self.min_chars_per_sentence < 1 {
+            errors.push("min_chars_per_sentence must be at least 1".to_string());
+        }
+        if TiktokenCounter::encoding_for_model(&self.tokenizer_model).is_none() {
+            errors.push(format!("Unsupported tokenizer_model: {}", self.tokenizer_model));
+        }
+        for (kind, config) in &self.per_kind_config {
+            let chunk_size = config.chunk_size.unwrap_or(self.default_chunk_size);
+            let chunk_overlap = config.chunk_overlap.unwrap_or(self.default_chunk_overlap);
+            if chunk_size < Self::MIN_CHUNK_SIZE {
+                errors.push(format!(
+                    "per_kind.{}.chunk_size must be at least {}, got {}",
+                    kind,
+                    Self::MIN_CHUNK_SIZE,
+                    chunk_size
+                ));
+            }
+            if chunk_overlap >= chunk_size {
+                errors.push(format!(
+                    "per_kind.{}.chunk_overlap ({}) must be less than chunk_size ({})",
+                    kind, chunk_overlap, chunk_size
+                ));
+            }
+            for (language, size) in &config.language_size_overrides {
+                if *size < Self::MIN_CHUNK_SIZE {
+                    errors.push(format!(
+                        "per_kind.{}.language_size_overrides.{} must be at least {}, got {}",
+                        kind,
+                        language,
+                        Self::MIN_CHUNK_SIZE,
+                        size
+                    ));
+                }
+            }
+        }
+        for (language, size) in &self.language_size_overrides {
+            if *size < Self::MIN_CHUNK_SIZE {
+                errors.push(format!(
+                    "language_size_overrides.{} must be at least {}, got {}",
+                    language,
+                    Self::MIN_CHUNK_SIZE,
+                    size
+                ));
+            }
+        }
+
+        let embedding_urls = self.embedding_service_url.iter().chain(&self.embedding_fallback_urls);
+        for url in embedding_urls {
+            if let Err(e) = reqwest::Url::parse(url) {
+                errors.push(format!("Invalid embedding service URL {:?}: {}", url, e));
+            }
+        }
+        if self.embedding_service_url.is_none() && !self.embedding_fallback_urls.is_empty() {
+            warnings.push("embedding_fallback_urls is set without embedding_service_url and is ignored".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(warnings)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Override fields with the environment variables that are set.
+    fn apply_env(&mut self) {
+        if let Some(size) = env_parse("CHUNK_SIZE") {
+            self.default_chunk_size = size;
+        }
+        if let Some(overlap) = env_parse("CHUNK_OVERLAP") {
+            self.default_chunk_overlap = overlap;
+        }
+        if let Some(min_chars) = env_parse("MIN_CHARS_PER_SENTENCE") {
+            self.min_chars_per_sentence = min_chars;
+        }
+        if let Ok(model) = std::env::var("TOKENIZER_MODEL") {
+            self.tokenizer_model = model;
+        }
+        if let Ok(url) = std::env::var("EMBEDDING_SERVICE_URL") {
+            self.embedding_service_url = Some(url);
+        }
+        if let Ok(urls) = std::env::var("EMBEDDING_FALLBACK_URLS") {
+            self.embedding_fallback_urls =
+                urls.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect();
+        }
+        if let Ok(url) = std::env::var("RELATION_GRAPH_SERVICE_URL") {
+            self.graph_service_url = Some(url);
+        }
+        if let Some(jobs) = env_parse("MAX_CONCURRENT_JOBS") {
+            self.max_concurrent_jobs = jobs;
+        }
+        if let Ok(profile) = std::env::var("ACTIVE_PROFILE") {
+            self.active_profile = profile;
+        }
+        if let Some(size) = env_parse("MAX_SYNC_CONTENT_SIZE") {
+            self.max_sync_content_size = size;
+        }
+        if let Ok(enabled) = std::env::var("ENABLE_SWAGGER_UI") {
+            self.enable_swagger_ui = enabled == "true" || enabled == "1";
+        }
+        if let Ok(path) = std::env::var("WEBHOOK_STORE_PATH") {
+            self.webhook_store_path = Some(path);
+        }
+        if let Ok(enabled) = std::env::var("WEBHOOK_ALLOW_PRIVATE_URLS") {
+            self.allow_private_webhook_urls = enabled == "true" || enabled == "1";
+        }
+        if let Ok(url) = std::env::var("JOB_STORE_URL") {
+            self.job_store_url = Some(url);
+        }
+        if let Some(secs) = env_parse("JOB_RETENTION_SECS") {
+            self.job_retention_secs = secs;
+        }
+        if let Ok(enabled) = std::env::var("USE_DETERMINISTIC_IDS") {
+            self.use_deterministic_ids = enabled == "true" || enabled == "1";
+        }
+        if let Ok(sizes) = std::env::var("LANGUAGE_CHUNK_SIZES") {
+            self.language_size_overrides = parse_language_sizes(&sizes);
+        }
+        self.nats.apply_env();
+        self.redis.apply_env();
+    }
+}
+
+/// Parse `language=size` pairs separated by commas, e.g.
+/// `python=768,typescript=384`, skipping malformed pairs.
+fn parse_language_sizes(text: &str) -> HashMap<String, usize> {
+    text.split(',')
+        .filter_map(|pair| {
+            let (language, size) = pair.split_once('=')?;
+            Some((language.trim().to_lowercase(), size.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Parse an environment variable, ignoring it if unset or malformed.
+fn env_parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|s| s.parse().ok())
+}
+
+/// NATS JetStream connection settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NatsConfig {
+    /// Server URL
+    pub url: String,
+    
+    /// Subject normalized code events are consumed from
+    pub subject: String,
+    
+    /// Consumer name, shared by all workers in a consumer group
+    pub consumer_name: String,
+    
+    /// Whether the consumer survives disconnects
+    pub durable: bool,
+}
+
+impl Default for NatsConfig {
+    fn default() -> Self {
         Self {
-            default_chunk_size: std::env::var("CHUNK_SIZE")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(DEFAULT_CHUNK_SIZE),
-            default_chunk_overlap: std::env::var("CHUNK_OVERLAP")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(DEFAULT_CHUNK_OVERLAP),
-            min_chars_per_sentence: std::env::var("MIN_CHARS_PER_SENTENCE")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(DEFAULT_MIN_CHARS_PER_SENTENCE),
-            embedding_service_url: std::env::var("EMBEDDING_SERVICE_URL").ok(),
-            graph_service_url: std::env::var("RELATION_GRAPH_SERVICE_URL").ok(),
-            max_concurrent_jobs: std::env::var("MAX_CONCURRENT_JOBS")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(4),
-            active_profile: std::env::var("ACTIVE_PROFILE")
-                .unwrap_or_else(|_| "default".to_string()),
+            url: "nats://localhost:4222".to_string(),
+            subject: "chunker.code.normalized".to_string(),
+            consumer_name: "chunker".to_string(),
+            durable: true,
         }
     }
 }
 
-/// Configuration for individual chunk operations.
+impl NatsConfig {
+    /// Load NATS settings from environment variables.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.apply_env();
+        config
+    }
+
+    /// Override fields with the environment variables that are set.
+    fn apply_env(&mut self) {
+        if let Ok(url) = std::env::var("NATS_URL") {
+            self.url = url;
+        }
+        if let Ok(subject) = std::env::var("NATS_SUBJECT") {
+            self.subject = subject;
+        }
+        if let Ok(name) = std::env::var("NATS_CONSUMER_NAME") {
+            self.consumer_name = name;
+        }
+        if let Ok(durable) = std::env::var("NATS_DURABLE") {
+            self.durable = durable != "false" && durable != "0";
+        }
+    }
+}
+
+/// Redis Streams connection settings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedisConfig {
+    /// Server URL
+    pub url: String,
+    
+    /// Stream key chunks are published to and consumed from
+    pub stream_key: String,
+    
+    /// Consumer group name
+    pub group_name: String,
+    
+    /// How long `XREADGROUP` blocks waiting for entries, in milliseconds
+    pub block_ms: usize,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            url: "redis://localhost:6379".to_string(),
+            stream_key: "chunker:chunks".to_string(),
+            group_name: "chunker".to_string(),
+            block_ms: 5000,
+        }
+    }
+}
+
+impl RedisConfig {
+    /// Load Redis settings from environment variables.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.apply_env();
+        config
+    }
+
+    /// Override fields with the environment variables that are set.
+    fn apply_env(&mut self) {
+        if let Ok(url) = std::env::var("REDIS_URL") {
+            self.url = url;
+        }
+        if let Ok(key) = std::env::var("REDIS_STREAM_KEY") {
+            self.stream_key = key;
+        }
+        if let Ok(group) = std::env::var("REDIS_GROUP_NAME") {
+            self.group_name = group;
+        }
+        if let Some(block_ms) = env_parse("REDIS_BLOCK_MS") {
+            self.block_ms = block_ms;
+        }
+    }
+}
+
+/// Configuration for individual chunk operations.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
 pub struct ChunkConfig {
     /// Maximum tokens per chunk
     pub chunk_size: usize,
@@ -88,6 +463,33 @@ pub struct ChunkConfig {
     
     /// Language for code chunking (if applicable)
     pub language: Option<String>,
+    
+    /// Derive chunk IDs from source item and content (`Chunk::content_hash_id`)
+    /// instead of generating random ones, so re-chunking unchanged content
+    /// yields the same IDs and downstream stores can upsert
+    pub use_deterministic_ids: bool,
+    
+    /// How chunkers count tokens when checking chunk size limits
+    pub token_count_mode: TokenCountMode,
+    
+    /// Chunk sizes by language, used instead of `chunk_size` for items in
+    /// that language (see `effective_chunk_size`)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub language_size_overrides: HashMap<String, usize>,
+}
+
+/// How token counts are computed against a chunk size limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenCountMode {
+    /// Always run the tokenizer
+    Exact,
+    /// Always use the byte-scanning estimate
+    Fast,
+    /// Estimate, and run the tokenizer only when the estimate is within 10%
+    /// of the limit
+    #[default]
+    Adaptive,
 }
 
 impl Default for ChunkConfig {
@@ -98,6 +500,9 @@ impl Default for ChunkConfig {
             min_chars_per_sentence: DEFAULT_MIN_CHARS_PER_SENTENCE,
             preserve_whitespace: false,
             language: None,
+            use_deterministic_ids: false,
+            token_count_mode: TokenCountMode::default(),
+            language_size_overrides: HashMap::new(),
         }
     }
 }
@@ -122,10 +527,133 @@ impl ChunkConfig {
         self.language = Some(language.to_string());
         self
     }
+
+    /// Set the chunk size for one language.
+    pub fn with_language_size(mut self, language: &str, size: usize) -> Self {
+        self.language_size_overrides.insert(language.to_lowercase(), size);
+        self
+    }
+
+    /// Check the settings chunkers can't work without: every size must be
+    /// non-zero. `Err` lists every problem.
+    pub fn validate(&self) -> std::result::Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.chunk_size == 0 {
+            errors.push("chunk_size must be at least 1".to_string());
+        }
+        if self.min_chars_per_sentence == 0 {
+            errors.push("min_chars_per_sentence must be at least 1".to_string());
+        }
+        for (language, size) in &self.language_size_overrides {
+            if *size == 0 {
+                errors.push(format!("language_size_overrides.{} must be at least 1", language));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Maximum tokens per chunk: the override for `language` if there is
+    /// one, otherwise `chunk_size`.
+    pub fn effective_chunk_size(&self) -> usize {
+        self.language
+            .as_deref()
+            .and_then(|language| self.language_size_overrides.get(&language.to_lowercase()))
+            .copied()
+            .unwrap_or(self.chunk_size)
+    }
+
+    /// Hex SHA-256 of every setting, so caches can tell whether chunks were
+    /// produced with the same config.
+    pub fn fingerprint(&self) -> String {
+        // Serializing through `Value` sorts map keys, so equal configs
+        // always produce the same fingerprint
+        let value = serde_json::to_value(self).unwrap_or_default();
+        format!("{:x}", Sha256::digest(value.to_string().as_bytes()))
+    }
+}
+
+/// Chunk settings for one source kind. Fields left unset keep the value
+/// from the global defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ChunkConfigOverride {
+    /// Maximum tokens per chunk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_size: Option<usize>,
+    
+    /// Tokens to overlap between chunks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_overlap: Option<usize>,
+    
+    /// Minimum characters per sentence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_chars_per_sentence: Option<usize>,
+    
+    /// Whether to preserve whitespace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preserve_whitespace: Option<bool>,
+    
+    /// Derive chunk IDs from source and content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_deterministic_ids: Option<bool>,
+    
+    /// How chunkers count tokens when checking chunk size limits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_count_mode: Option<TokenCountMode>,
+    
+    /// Chunk sizes by language, added to the global ones (entries here win)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub language_size_overrides: HashMap<String, usize>,
+}
+
+impl ChunkConfigOverride {
+    /// Create an override of the chunk size.
+    pub fn with_size(size: usize) -> Self {
+        Self {
+            chunk_size: Some(size),
+            ..Default::default()
+        }
+    }
+
+    /// Set the overlap.
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.chunk_overlap = Some(overlap);
+        self
+    }
+
+    /// Apply the fields that are set on top of `config`.
+    pub fn apply(&self, config: &mut ChunkConfig) {
+        if let Some(size) = self.chunk_size {
+            config.chunk_size = size;
+        }
+        if let Some(overlap) = self.chunk_overlap {
+            config.chunk_overlap = overlap;
+        }
+        if let Some(min_chars) = self.min_chars_per_sentence {
+            config.min_chars_per_sentence = min_chars;
+        }
+        if let Some(preserve) = self.preserve_whitespace {
+            config.preserve_whitespace = preserve;
+        }
+        if let Some(deterministic) = self.use_deterministic_ids {
+            config.use_deterministic_ids = deterministic;
+        }
+        if let Some(mode) = self.token_count_mode {
+            config.token_count_mode = mode;
+        }
+        config
+            .language_size_overrides
+            .extend(self.language_size_overrides.iter().map(|(language, size)| (language.to_lowercase(), *size)));
+    }
 }
 
 /// A named chunking profile with preset configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChunkingProfile {
     /// Profile name
     pub name: String,
@@ -213,3 +741,122 @@ impl Default for ChunkingPolicy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunker.toml");
+        ChunkingConfig::write_default_config(&path).unwrap();
+
+        let config = ChunkingConfig::from_toml(&path).unwrap();
+        assert_eq!(config.default_chunk_size, DEFAULT_CHUNK_SIZE);
+        assert_eq!(config.redis.stream_key, "chunker:chunks");
+        assert_eq!(config.validate(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_partial_toml_uses_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunker.toml");
+        std::fs::write(&path, "default_chunk_size = 1024\n\n[nats]\nsubject = \"chunker.custom\"\n").unwrap();
+
+        let config = ChunkingConfig::from_toml(&path).unwrap();
+        assert_eq!(config.default_chunk_size, 1024);
+        assert_eq!(config.default_chunk_overlap, DEFAULT_CHUNK_OVERLAP);
+        assert_eq!(config.nats.subject, "chunker.custom");
+        assert_eq!(config.nats.url, "nats://localhost:4222");
+    }
+
+    #[test]
+    fn test_per_kind_toml_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunker.toml");
+        std::fs::write(&path, "[per_kind.code_repo]\nchunk_size = 768\n\n[per_kind.wiki]\nchunk_size = 2048\n").unwrap();
+
+        let config = ChunkingConfig::from_toml(&path).unwrap();
+        assert_eq!(config.per_kind_config[&SourceKind::CodeRepo].chunk_size, Some(768));
+        // Fields left out of the table fall back to the global defaults
+        assert_eq!(config.per_kind_config[&SourceKind::CodeRepo].chunk_overlap, None);
+        assert_eq!(config.per_kind_config[&SourceKind::Wiki].chunk_size, Some(2048));
+        // Kinds not in the file keep their built-in defaults
+        assert_eq!(config.per_kind_config[&SourceKind::Chat].chunk_size, Some(256));
+
+        let mut merged = ChunkConfig::with_size(config.default_chunk_size).with_overlap(40);
+        merged.min_chars_per_sentence = 7;
+        config.per_kind_config[&SourceKind::CodeRepo].apply(&mut merged);
+        assert_eq!((merged.chunk_size, merged.chunk_overlap, merged.min_chars_per_sentence), (768, 40, 7));
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = ChunkingConfig {
+            default_chunk_size: 32,
+            default_chunk_overlap: 32,
+            min_chars_per_sentence: 0,
+            embedding_service_url: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.validate().unwrap_err().len(), 4);
+
+        let config = ChunkingConfig {
+            default_chunk_overlap: 400,
+            embedding_service_url: Some("http://embeddings:8082".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.validate().unwrap().len(), 1);
+
+        let config = ChunkingConfig {
+            tokenizer_model: "gpt-2".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.validate().unwrap_err(), vec!["Unsupported tokenizer_model: gpt-2"]);
+    }
+
+    #[test]
+    fn test_validate_chunk_config() {
+        assert_eq!(ChunkConfig::default().validate(), Ok(()));
+        assert_eq!(ChunkConfig::with_size(8).validate(), Ok(()));
+        assert_eq!(
+            ChunkConfig::with_size(0).validate().unwrap_err(),
+            vec!["chunk_size must be at least 1"]
+        );
+        let config = ChunkConfig {
+            chunk_size: 0,
+            min_chars_per_sentence: 0,
+            ..Default::default()
+        };
+        assert_eq!(config.validate().unwrap_err().len(), 2);
+        assert_eq!(
+            ChunkConfig::default().with_language_size("go", 0).validate().unwrap_err(),
+            vec!["language_size_overrides.go must be at least 1"]
+        );
+    }
+
+    #[test]
+    fn test_language_size_overrides() {
+        let config = ChunkConfig::default().with_language_size("python", 768);
+        assert_eq!(config.chunk_size, 512);
+        assert_eq!(config.clone().with_language("python").effective_chunk_size(), 768);
+        assert_eq!(config.clone().with_language("rust").effective_chunk_size(), 512);
+        assert_eq!(config.effective_chunk_size(), 512);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunker.toml");
+        std::fs::write(&path, "[language_size_overrides]\npython = 768\ntypescript = 32\n").unwrap();
+        let config = ChunkingConfig::from_toml(&path).unwrap();
+        assert_eq!(config.language_size_overrides["python"], 768);
+        assert_eq!(
+            config.validate().unwrap_err(),
+            vec!["language_size_overrides.typescript must be at least 64, got 32"]
+        );
+
+        assert_eq!(
+            parse_language_sizes("Python=768, typescript = 384,bad,go=x"),
+            HashMap::from([("python".to_string(), 768), ("typescript".to_string(), 384)])
+        );
+    }
+}
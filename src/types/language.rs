@@ -0,0 +1,678 @@
+//! Language syntax helpers.
+//!
+//! Languages are identified by the lowercase names used in `text/code:`
+//! content types and `ChunkConfig::language` (e.g. `"rust"`, `"python"`).
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Known languages: (name, MIME type, file extensions).
+///
+/// This is the source of truth for extension and MIME lookups; the first
+/// language listing an extension wins.
+const LANGUAGES: &[(&str, &str, &[&str])] = &[
+    ("rust", "text/x-rust", &["rs"]),
+    ("python", "text/x-python", &["py", "pyi", "pyw"]),
+    ("javascript", "application/javascript", &["js", "mjs", "cjs", "jsx"]),
+    ("typescript", "application/typescript", &["ts", "mts", "cts", "tsx"]),
+    ("go", "text/x-go", &["go"]),
+    ("java", "text/x-java-source", &["java"]),
+    ("c", "text/x-csrc", &["c", "h", "hpp"]),
+    ("cpp", "text/x-c++src", &["cpp", "cc", "cxx", "hh", "hxx"]),
+    ("ruby", "text/x-ruby", &["rb"]),
+    ("php", "application/x-httpd-php", &["php"]),
+    ("swift", "text/x-swift", &["swift"]),
+    ("kotlin", "text/x-kotlin", &["kt", "kts"]),
+    ("scala", "text/x-scala", &["scala"]),
+    ("csharp", "text/x-csharp", &["cs"]),
+    ("markdown", "text/markdown", &["md", "markdown"]),
+    ("json", "application/json", &["json"]),
+    ("yaml", "application/yaml", &["yaml", "yml"]),
+    ("toml", "application/toml", &["toml"]),
+    ("xml", "application/xml", &["xml"]),
+    ("html", "text/html", &["html", "htm"]),
+    ("css", "text/css", &["css"]),
+    ("sql", "application/sql", &["sql"]),
+    ("bash", "application/x-sh", &["sh", "bash", "zsh"]),
+    ("powershell", "application/x-powershell", &["ps1"]),
+    ("latex", "text/x-latex", &["tex", "ltx"]),
+    ("ini", "text/x-ini", &["ini", "cfg", "conf"]),
+];
+
+/// Alternative MIME types accepted by `language_from_mime_type`.
+const MIME_ALIASES: &[(&str, &str)] = &[
+    ("text/javascript", "javascript"),
+    ("application/x-javascript", "javascript"),
+    ("text/typescript", "typescript"),
+    ("text/x-c", "c"),
+    ("text/x-c++", "cpp"),
+    ("text/x-java", "java"),
+    ("text/x-markdown", "markdown"),
+    ("text/yaml", "yaml"),
+    ("text/xml", "xml"),
+    ("text/x-sh", "bash"),
+    ("text/x-shellscript", "bash"),
+    ("application/x-latex", "latex"),
+    ("application/x-tex", "latex"),
+    ("text/x-tex", "latex"),
+];
+
+/// MIME type for a language, or `"text/plain"` if unknown.
+pub fn mime_type(language: &str) -> &'static str {
+    LANGUAGES
+        .iter()
+        .find(|(name, _, _)| *name == language)
+        .map_or("text/plain", |(_, mime, _)| mime)
+}
+
+/// Markup and data formats, which keep their own MIME type rather than
+/// a `text/code:` content type.
+const NON_CODE_LANGUAGES: &[&str] = &["markdown", "json", "yaml", "toml", "xml", "html", "css", "latex", "ini"];
+
+/// Content type for a file in `language`: `text/code:<language>` for
+/// programming languages, the MIME type for markup and data formats, and
+/// `"text/plain"` when the language is unknown.
+pub fn content_type_for(language: Option<&str>) -> String {
+    match language {
+        Some(language) if NON_CODE_LANGUAGES.contains(&language) => mime_type(language).to_string(),
+        Some(language) => format!("text/code:{}", language),
+        None => "text/plain".to_string(),
+    }
+}
+
+/// File extensions (without the dot) recognized for a language.
+pub fn file_extensions(language: &str) -> &'static [&'static str] {
+    LANGUAGES
+        .iter()
+        .find(|(name, _, _)| *name == language)
+        .map_or(&[], |(_, _, exts)| exts)
+}
+
+/// Language for a file extension (case-insensitive, without the dot).
+pub fn language_from_extension(ext: &str) -> Option<&'static str> {
+    let ext = ext.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(_, _, exts)| exts.contains(&ext.as_str()))
+        .map(|(name, _, _)| *name)
+}
+
+/// Language for a MIME type, ignoring parameters such as `charset`.
+///
+/// Also accepts the service's own `text/code:<language>` content types.
+pub fn language_from_mime_type(mime: &str) -> Option<&'static str> {
+    let mime = mime.split(';').next().unwrap_or(mime).trim().to_lowercase();
+
+    if let Some(lang) = mime.strip_prefix("text/code:") {
+        return LANGUAGES.iter().find(|(name, _, _)| *name == lang).map(|(name, _, _)| *name);
+    }
+
+    LANGUAGES
+        .iter()
+        .find(|(_, m, _)| *m == mime)
+        .map(|(name, _, _)| *name)
+        .or_else(|| MIME_ALIASES.iter().find(|(m, _)| *m == mime).map(|(_, name)| *name))
+}
+
+/// Alternative names used by shebangs and editor modelines.
+const NAME_ALIASES: &[(&str, &str)] = &[
+    ("python2", "python"),
+    ("python3", "python"),
+    ("node", "javascript"),
+    ("nodejs", "javascript"),
+    ("js", "javascript"),
+    ("ts", "typescript"),
+    ("deno", "typescript"),
+    ("rb", "ruby"),
+    ("sh", "bash"),
+    ("zsh", "bash"),
+    ("shell-script", "bash"),
+    ("pwsh", "powershell"),
+    ("c++", "cpp"),
+    ("cs", "csharp"),
+    ("yml", "yaml"),
+    ("md", "markdown"),
+    ("tex", "latex"),
+];
+
+/// Confidence of a language detected from a shebang line.
+pub const SHEBANG_CONFIDENCE: f32 = 0.9;
+
+/// Confidence of a language detected from an Emacs or Vim modeline.
+pub const MODELINE_CONFIDENCE: f32 = 0.85;
+
+/// Confidence of a language detected from a file extension alone.
+pub const EXTENSION_CONFIDENCE: f32 = 0.8;
+
+/// Canonical language for a name or alias (case-insensitive).
+pub fn language_from_name(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .map(|(lang, _, _)| *lang)
+        .find(|lang| *lang == name)
+        .or_else(|| NAME_ALIASES.iter().find(|(alias, _)| *alias == name).map(|(_, lang)| *lang))
+}
+
+/// Detect a language from file content, with a confidence score.
+///
+/// Tries the shebang line first, then Emacs/Vim modelines in the first and
+/// last three lines.
+pub fn detect_from_content(content: &str) -> Option<(&'static str, f32)> {
+    detect_from_shebang(content)
+        .map(|lang| (lang, SHEBANG_CONFIDENCE))
+        .or_else(|| detect_from_modeline(content).map(|lang| (lang, MODELINE_CONFIDENCE)))
+}
+
+/// Detect a language from a `#!` line, e.g. `#!/usr/bin/env python3`.
+pub fn detect_from_shebang(content: &str) -> Option<&'static str> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    let program = if program == "env" {
+        words.find(|w| !w.starts_with('-'))?
+    } else {
+        program
+    };
+    language_from_name(program)
+}
+
+/// Detect a language from an Emacs (`-*- mode: python -*-`) or Vim
+/// (`vim: set filetype=ruby:`) modeline in the first or last three lines.
+pub fn detect_from_modeline(content: &str) -> Option<&'static str> {
+    let lines: Vec<&str> = content.lines().collect();
+    let tail_start = lines.len().saturating_sub(3).max(3.min(lines.len()));
+    lines[..3.min(lines.len())]
+        .iter()
+        .chain(&lines[tail_start..])
+        .find_map(|line| emacs_mode(line).or_else(|| vim_filetype(line)))
+}
+
+fn emacs_mode(line: &str) -> Option<&'static str> {
+    let start = line.find("-*-")? + 3;
+    let end = start + line[start..].find("-*-")?;
+    let vars = &line[start..end];
+
+    // Either `-*- python -*-` or `-*- mode: python; coding: utf-8 -*-`
+    if !vars.contains(':') {
+        return language_from_name(vars);
+    }
+    vars.split(';').find_map(|var| {
+        let (key, value) = var.split_once(':')?;
+        (key.trim().eq_ignore_ascii_case("mode")).then(|| language_from_name(value)).flatten()
+    })
+}
+
+fn vim_filetype(line: &str) -> Option<&'static str> {
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| line.find(marker).map(|i| i + marker.len()))?;
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|opt| opt.strip_prefix("filetype=").or_else(|| opt.strip_prefix("ft=")))
+        .and_then(language_from_name)
+}
+
+/// A detected language and how sure the detection is.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LanguageInfo {
+    /// Canonical language name, or `None` if unknown
+    pub language: Option<String>,
+    /// 0.0 when unknown, up to 1.0 when extension and content agree
+    pub confidence: f32,
+}
+
+impl LanguageInfo {
+    fn new(language: Option<&str>, confidence: f32) -> Self {
+        Self {
+            language: language.map(String::from),
+            confidence: if language.is_some() { confidence } else { 0.0 },
+        }
+    }
+}
+
+/// Per-language file counts for a set of paths.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LanguageConfidenceReport {
+    pub total_files: usize,
+    /// Files with no recognized language
+    pub unknown_files: usize,
+    /// Most common language first
+    pub languages: Vec<LanguageStats>,
+}
+
+/// Detection summary for one language.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LanguageStats {
+    pub language: String,
+    pub file_count: usize,
+    pub average_confidence: f32,
+}
+
+/// Detects languages from file paths and content.
+pub struct LanguageDetector;
+
+impl LanguageDetector {
+    /// Detect the language of a file from its path and, if given, content.
+    ///
+    /// Content signals (shebangs, modelines) win over the extension. When
+    /// both agree the confidence is 1.0.
+    pub fn detect(path: &str, content: Option<&str>) -> LanguageInfo {
+        let from_extension = path
+            .rsplit(['/', '\\'])
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+            .and_then(|(_, ext)| language_from_extension(ext));
+        let from_content = content.and_then(detect_from_content);
+
+        match (from_extension, from_content) {
+            (Some(ext), Some((lang, _))) if ext == lang => LanguageInfo::new(Some(lang), 1.0),
+            (_, Some((lang, confidence))) => LanguageInfo::new(Some(lang), confidence),
+            (ext, None) => LanguageInfo::new(ext, EXTENSION_CONFIDENCE),
+        }
+    }
+
+    /// Detect languages for many `(path, content)` pairs in parallel.
+    ///
+    /// Results are in input order.
+    pub fn detect_many(paths: &[(&str, Option<&str>)]) -> Vec<LanguageInfo> {
+        paths.par_iter().map(|(path, content)| Self::detect(path, *content)).collect()
+    }
+
+    /// Detect the language of raw file bytes.
+    ///
+    /// A UTF-8 or UTF-16 byte order mark is stripped, and UTF-16 decoded,
+    /// before looking for a shebang.
+    pub fn detect_bytes(path: &str, content: &[u8]) -> LanguageInfo {
+        Self::detect(path, Some(&decode_text(content)))
+    }
+
+    /// Summarize the languages of a set of paths, detected by extension.
+    pub fn build_confidence_report(paths: Vec<&str>) -> LanguageConfidenceReport {
+        let total_files = paths.len();
+        let detected = paths.par_iter().map(|path| Self::detect(path, None)).collect::<Vec<_>>();
+
+        let mut unknown_files = 0;
+        let mut totals: HashMap<String, (usize, f32)> = HashMap::new();
+        for info in detected {
+            match info.language {
+                Some(language) => {
+                    let entry = totals.entry(language).or_default();
+                    entry.0 += 1;
+                    entry.1 += info.confidence;
+                }
+                None => unknown_files += 1,
+            }
+        }
+
+        let mut languages: Vec<_> = totals
+            .into_iter()
+            .map(|(language, (file_count, confidence))| LanguageStats {
+                language,
+                file_count,
+                average_confidence: confidence / file_count as f32,
+            })
+            .collect();
+        languages.sort_by(|a, b| b.file_count.cmp(&a.file_count).then_with(|| a.language.cmp(&b.language)));
+
+        LanguageConfidenceReport {
+            total_files,
+            unknown_files,
+            languages,
+        }
+    }
+}
+
+/// Decode text, honouring a UTF-8 or UTF-16 byte order mark.
+fn decode_text(bytes: &[u8]) -> String {
+    let utf16 = |rest: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = rest.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Line comment prefix for a language.
+pub fn comment_prefix(language: &str) -> Option<&'static str> {
+    match language {
+        "rust" | "javascript" | "typescript" | "jsx" | "tsx" | "go" | "java" | "c" | "cpp"
+        | "csharp" | "swift" | "kotlin" | "scala" | "php" | "dart" => Some("//"),
+        "python" | "ruby" | "bash" | "shell" | "perl" | "r" | "powershell" | "yaml" | "toml"
+        | "makefile" | "dockerfile" => Some("#"),
+        "lisp" | "clojure" | "scheme" | "ini" => Some(";"),
+        "sql" | "haskell" | "lua" => Some("--"),
+        "latex" | "tex" | "erlang" | "matlab" => Some("%"),
+        _ => None,
+    }
+}
+
+/// Block comment open/close delimiters for a language.
+pub fn block_comment_delimiters(language: &str) -> Option<(&'static str, &'static str)> {
+    match language {
+        "rust" | "javascript" | "typescript" | "jsx" | "tsx" | "go" | "java" | "c" | "cpp"
+        | "csharp" | "swift" | "kotlin" | "scala" | "php" | "dart" | "css" | "sql" => Some(("/*", "*/")),
+        "haskell" => Some(("{-", "-}")),
+        "lua" => Some(("--[[", "]]")),
+        "html" | "xml" | "markdown" => Some(("<!--", "-->")),
+        "python" => Some(("\"\"\"", "\"\"\"")),
+        "ruby" => Some(("=begin", "=end")),
+        "powershell" => Some(("<#", "#>")),
+        _ => None,
+    }
+}
+
+/// Remove line and block comments from source code.
+///
+/// Comment markers inside double-quoted strings, character literals such as
+/// `'"'`, and single-quoted strings for languages where `'` delimits strings
+/// are left alone. Line structure
+/// is preserved: stripped block comments keep their newlines. Python
+/// docstrings count as block comments. Unknown languages are returned
+/// unchanged.
+pub fn strip_comments(content: &str, language: &str) -> String {
+    let line = comment_prefix(language);
+    let block = block_comment_delimiters(language);
+    if line.is_none() && block.is_none() {
+        return content.to_string();
+    }
+    let single_quote_strings = has_single_quote_strings(language);
+
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(q) = quote {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            if c == '\\' {
+                if let Some(escaped) = rest.chars().next() {
+                    out.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        if let Some((open, close)) = block {
+            if let Some(after) = rest.strip_prefix(open) {
+                let end = after.find(close).map_or(after.len(), |i| i + close.len());
+                out.extend(after[..end].chars().filter(|&c| c == '\n'));
+                rest = &after[end..];
+                continue;
+            }
+        }
+
+        if let Some(prefix) = line {
+            if rest.starts_with(prefix) {
+                rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+                continue;
+            }
+        }
+
+        if c == '\'' && !single_quote_strings {
+            if let Some(len) = char_literal_len(rest) {
+                out.push_str(&rest[..len]);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        if c == '"' || (c == '\'' && single_quote_strings) {
+            quote = Some(c);
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+/// Blank out comments and the contents of string and character literals.
+///
+/// Masked characters become spaces of the same byte length and newlines are
+/// kept, so byte offsets and line numbers still match `content`. Quote
+/// delimiters are left in place. Lets regex-based extractors scan code
+/// without matching inside comments or strings such as URLs.
+pub fn mask_comments_and_strings(content: &str, language: &str) -> String {
+    let line = comment_prefix(language);
+    let block = block_comment_delimiters(language);
+    let single_quote_strings = has_single_quote_strings(language);
+
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((open, close)) = block {
+            if let Some(after) = rest.strip_prefix(open) {
+                let end = open.len() + after.find(close).map_or(after.len(), |i| i + close.len());
+                blank(&mut out, &rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+        }
+
+        if let Some(prefix) = line {
+            if rest.starts_with(prefix) {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                blank(&mut out, &rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+        }
+
+        let literal = if c == '"' || (c == '\'' && single_quote_strings) {
+            Some(quoted_len(rest, c))
+        } else if c == '\'' {
+            char_literal_len(rest)
+        } else {
+            None
+        };
+
+        match literal {
+            Some(len) => {
+                let closed = len > 1 && rest[..len].ends_with(c);
+                let inner_end = if closed { len - c.len_utf8() } else { len };
+                out.push(c);
+                blank(&mut out, &rest[c.len_utf8()..inner_end]);
+                out.push_str(&rest[inner_end..len]);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    out
+}
+
+/// Whether `'` delimits strings (rather than character literals or
+/// lifetimes) in a language.
+fn has_single_quote_strings(language: &str) -> bool {
+    matches!(
+        language,
+        "python" | "javascript" | "typescript" | "jsx" | "tsx" | "ruby" | "bash" | "shell" | "sql" | "php"
+    )
+}
+
+/// Byte length of the string literal at the start of `text`, up to and
+/// including the closing `quote`, or the rest of `text` if it is unclosed.
+fn quoted_len(text: &str, quote: char) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+/// Byte length of a character literal such as `'a'`, `'"'` or `'\n'` at
+/// the start of `text`, or `None` for a lone `'` such as a Rust lifetime.
+fn char_literal_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        // Escapes are at most `'\u{10FFFF}'`
+        return text[2..].char_indices().take(10).find(|&(_, c)| c == '\'').map(|(i, _)| 2 + i + 1);
+    }
+    match chars.next() {
+        Some((i, '\'')) => Some(i + 1),
+        _ => None,
+    }
+}
+
+fn blank(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c == '\n' {
+            out.push('\n');
+        } else {
+            out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_types_and_extensions() {
+        assert_eq!(mime_type("rust"), "text/x-rust");
+        assert_eq!(mime_type("javascript"), "application/javascript");
+        assert_eq!(mime_type("unknown"), "text/plain");
+        assert_eq!(file_extensions("javascript"), &["js", "mjs", "cjs", "jsx"]);
+        assert!(file_extensions("unknown").is_empty());
+
+        assert_eq!(language_from_extension("JSX"), Some("javascript"));
+        assert_eq!(language_from_extension("ltx"), Some("latex"));
+        assert_eq!(content_type_for(Some("latex")), "text/x-latex");
+        assert_eq!(language_from_extension("conf"), Some("ini"));
+        assert_eq!(language_from_extension("zsh"), Some("bash"));
+        assert_eq!(language_from_extension("xyz"), None);
+
+        for (name, mime, _) in LANGUAGES {
+            assert_eq!(language_from_mime_type(mime), Some(*name));
+        }
+        assert_eq!(language_from_mime_type("text/javascript; charset=utf-8"), Some("javascript"));
+        assert_eq!(language_from_mime_type("text/code:go"), Some("go"));
+        assert_eq!(language_from_mime_type("text/plain"), None);
+
+        assert_eq!(content_type_for(Some("python")), "text/code:python");
+        assert_eq!(content_type_for(Some("markdown")), "text/markdown");
+        assert_eq!(content_type_for(Some("yaml")), "application/yaml");
+        assert_eq!(content_type_for(None), "text/plain");
+    }
+
+    #[test]
+    fn test_detect_from_content() {
+        assert_eq!(detect_from_content("#!/usr/bin/env python3\nprint(1)\n"), Some(("python", SHEBANG_CONFIDENCE)));
+        assert_eq!(detect_from_shebang("#!/bin/sh\n"), Some("bash"));
+
+        let emacs = "# -*- mode: python; coding: utf-8 -*-\nx = 1\n";
+        assert_eq!(detect_from_content(emacs), Some(("python", MODELINE_CONFIDENCE)));
+        assert_eq!(detect_from_modeline("// -*- C++ -*-\nint x;\n"), Some("cpp"));
+
+        let vim = "line\n".repeat(10) + "# vim: set filetype=ruby:\n";
+        assert_eq!(detect_from_modeline(&vim), Some("ruby"));
+        assert_eq!(detect_from_modeline("a\n# vim: ts=4 ft=sh\n"), Some("bash"));
+
+        let buried = "a\nb\nc\n# vim: ft=ruby\nd\ne\nf\ng\n";
+        assert_eq!(detect_from_content(buried), None);
+    }
+
+    #[test]
+    fn test_comment_syntax() {
+        assert_eq!(comment_prefix("rust"), Some("//"));
+        assert_eq!(comment_prefix("python"), Some("#"));
+        assert_eq!(comment_prefix("sql"), Some("--"));
+        assert_eq!(comment_prefix("latex"), Some("%"));
+        assert_eq!(comment_prefix("unknown"), None);
+        assert_eq!(block_comment_delimiters("go"), Some(("/*", "*/")));
+        assert_eq!(block_comment_delimiters("haskell"), Some(("{-", "-}")));
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let rust = "/* header\n   lines */\nfn main() { // entry\n    let url = \"http://x\";\n}\n";
+        assert_eq!(
+            strip_comments(rust, "rust"),
+            "\n\nfn main() { \n    let url = \"http://x\";\n}\n"
+        );
+
+        let python = "x = '#not a comment'  # comment\n";
+        assert_eq!(strip_comments(python, "python"), "x = '#not a comment'  \n");
+
+        let quote_char = "let q = '\"'; // a\nlet s = \"//\"; fn f<'a>() {} // b\n";
+        assert_eq!(strip_comments(quote_char, "rust"), "let q = '\"'; \nlet s = \"//\"; fn f<'a>() {} \n");
+
+        assert_eq!(strip_comments("// kept", "unknown"), "// kept");
+    }
+
+    #[test]
+    fn test_mask_comments_and_strings() {
+        let rust = "let url = \"http://x\"; // call(y)\nlet q = '\"'; /* f() */ g::<'a>(z)\n";
+        let masked = mask_comments_and_strings(rust, "rust");
+        assert_eq!(masked.len(), rust.len());
+        assert_eq!(
+            masked,
+            "let url = \"        \";           \nlet q = ' ';           g::<'a>(z)\n"
+        );
+
+        let python = "x = 'a # b'  # c\n";
+        assert_eq!(mask_comments_and_strings(python, "python"), "x = '     '     \n");
+    }
+
+    #[test]
+    fn test_language_detector() {
+        let detected = LanguageDetector::detect_many(&[
+            ("src/main.rs", None),
+            ("bin/deploy", Some("#!/bin/bash\necho hi\n")),
+            ("tools/run.py", Some("#!/usr/bin/env python3\n")),
+            ("notes.txt", None),
+            ("config/aliases.zsh", None),
+        ]);
+        assert_eq!(detected[0], LanguageInfo::new(Some("rust"), EXTENSION_CONFIDENCE));
+        assert_eq!(detected[1], LanguageInfo::new(Some("bash"), SHEBANG_CONFIDENCE));
+        assert_eq!(detected[2], LanguageInfo::new(Some("python"), 1.0));
+        assert_eq!(detected[3], LanguageInfo::new(None, 0.0));
+        assert_eq!(detected[4], LanguageInfo::new(Some("bash"), EXTENSION_CONFIDENCE));
+    }
+
+    #[test]
+    fn test_detect_bytes_strips_bom() {
+        let script = "#!/bin/sh\necho hi\n";
+        let mut utf16le = vec![0xFF, 0xFE];
+        utf16le.extend(script.encode_utf16().flat_map(u16::to_le_bytes));
+        let mut utf16be = vec![0xFE, 0xFF];
+        utf16be.extend(script.encode_utf16().flat_map(u16::to_be_bytes));
+        let utf8 = [&[0xEF, 0xBB, 0xBF][..], script.as_bytes()].concat();
+
+        for bytes in [utf16le, utf16be, utf8] {
+            assert_eq!(LanguageDetector::detect_bytes("deploy", &bytes).language.as_deref(), Some("bash"));
+        }
+    }
+
+    #[test]
+    fn test_confidence_report() {
+        let report = LanguageDetector::build_confidence_report(vec!["a.rs", "b.rs", "c.py", "LICENSE"]);
+        assert_eq!(report.total_files, 4);
+        assert_eq!(report.unknown_files, 1);
+        assert_eq!(report.languages[0].language, "rust");
+        assert_eq!(report.languages[0].file_count, 2);
+        assert_eq!(report.languages[1].language, "python");
+        assert_eq!(report.languages[1].average_confidence, EXTENSION_CONFIDENCE);
+    }
+}
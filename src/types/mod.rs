@@ -1,12 +1,17 @@
 //! Core types for the chunking service.
 
 mod chunk;
+mod chunk_graph;
 mod config;
+pub mod language;
 mod source;
 
-pub use chunk::{Chunk, ChunkMetadata};
-pub use config::{ChunkConfig, ChunkingConfig, ChunkingPolicy, ChunkingProfile};
+pub use chunk::{assign_content_hash_ids, Chunk, ChunkMetadata, ContentHash, DiffStats};
+pub use chunk_graph::ChunkGraph;
+pub use config::{
+    ChunkConfig, ChunkConfigOverride, ChunkingConfig, ChunkingPolicy, ChunkingProfile, NatsConfig, RedisConfig, TokenCountMode,
+};
 pub use source::{
-    ChunkJobStatus, ChunkJobStatusResponse, SourceItem, SourceKind,
-    StartChunkJobRequest, StartChunkJobResponse,
+    ChunkJobStatus, ChunkJobStatusResponse, ItemValidationErrors, SourceItem, SourceItemBuilder, SourceKind,
+    StartChunkJobRequest, StartChunkJobResponse, ValidationError,
 };
@@ -2,12 +2,13 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// The kind of source the content comes from.
 ///
 /// This determines which chunking strategy is used.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SourceKind {
     /// Code repository (GitHub, GitLab, etc.)
@@ -72,7 +73,7 @@ impl std::fmt::Display for SourceKind {
 /// A source item to be chunked.
 ///
 /// This is the input unit received from the data service.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SourceItem {
     /// Unique identifier for this source item
     pub id: Uuid,
@@ -90,14 +91,76 @@ pub struct SourceItem {
     pub content: String,
     
     /// Additional metadata from the source
+    #[schema(value_type = Object)]
     pub metadata: serde_json::Value,
     
     /// When this item was created in the source system
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime<Utc>>,
+    
+    /// Processing priority, 0 (lowest) to 255 (highest)
+    #[serde(default = "default_priority")]
+    pub priority: u8,
+}
+
+fn default_priority() -> u8 {
+    SourceItem::DEFAULT_PRIORITY
 }
 
 impl SourceItem {
+    /// Priority of items that don't set one.
+    pub const DEFAULT_PRIORITY: u8 = 128;
+
+    /// Largest content accepted for one item (100MB). Items above
+    /// `BatchConfig::max_content_size` but below this are pre-split.
+    pub const MAX_CONTENT_SIZE: usize = 100 * 1024 * 1024;
+
+    /// Start building an item; `build()` validates it.
+    pub fn builder() -> SourceItemBuilder {
+        SourceItemBuilder::default()
+    }
+
+    /// Check the item for malformed fields, returning every problem found.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if self.content.is_empty() {
+            errors.push(ValidationError::EmptyContent);
+        } else if self.content.len() > Self::MAX_CONTENT_SIZE {
+            errors.push(ValidationError::ContentTooLarge {
+                size: self.content.len(),
+                max: Self::MAX_CONTENT_SIZE,
+            });
+        }
+        if self.content_type.trim().is_empty() {
+            errors.push(ValidationError::EmptyContentType);
+        }
+        if !(self.metadata.is_object() || self.metadata.is_null()) {
+            errors.push(ValidationError::InvalidMetadataJson);
+        }
+        // Code content types only come from code repositories
+        if self.content_type.starts_with("text/code")
+            && !matches!(self.source_kind, SourceKind::CodeRepo | SourceKind::Other)
+        {
+            errors.push(ValidationError::IncompatibleSourceKind {
+                kind: self.source_kind,
+                content_type: self.content_type.clone(),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Set the processing priority.
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
     /// Extract the language from the content type if it's code.
     ///
     /// For content types like "text/code:rust" or "text/code:python",
@@ -127,8 +190,155 @@ impl SourceItem {
     }
 }
 
+/// A problem with a `SourceItem` found by `SourceItem::validate`, or with
+/// the chunk config sent along with it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum ValidationError {
+    /// `content` is empty
+    EmptyContent,
+    /// `content_type` is empty
+    EmptyContentType,
+    /// `content` exceeds `SourceItem::MAX_CONTENT_SIZE` bytes
+    ContentTooLarge { size: usize, max: usize },
+    /// `metadata` is not a JSON object
+    InvalidMetadataJson,
+    /// `content_type` cannot come from `source_kind`
+    IncompatibleSourceKind { kind: SourceKind, content_type: String },
+    /// The requested `ChunkConfig` is unusable (see `ChunkConfig::validate`)
+    InvalidChunkConfig { reason: String },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::EmptyContent => write!(f, "content is empty"),
+            ValidationError::EmptyContentType => write!(f, "content_type is empty"),
+            ValidationError::ContentTooLarge { size, max } => {
+                write!(f, "content is {} bytes, more than the maximum of {}", size, max)
+            }
+            ValidationError::InvalidMetadataJson => write!(f, "metadata is not a JSON object"),
+            ValidationError::IncompatibleSourceKind { kind, content_type } => {
+                write!(f, "content type {} is not valid for source kind {}", content_type, kind)
+            }
+            ValidationError::InvalidChunkConfig { reason } => write!(f, "invalid chunk config: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Builder for a `SourceItem`, validated by `build()`.
+///
+/// The content type defaults to the source kind's
+/// `default_content_type()`.
+#[derive(Debug, Clone)]
+pub struct SourceItemBuilder {
+    id: Uuid,
+    source_id: Uuid,
+    source_kind: SourceKind,
+    content_type: Option<String>,
+    content: String,
+    metadata: serde_json::Value,
+    created_at: Option<DateTime<Utc>>,
+    priority: u8,
+}
+
+impl Default for SourceItemBuilder {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            source_id: Uuid::nil(),
+            source_kind: SourceKind::Other,
+            content_type: None,
+            content: String::new(),
+            metadata: serde_json::Value::Null,
+            created_at: None,
+            priority: SourceItem::DEFAULT_PRIORITY,
+        }
+    }
+}
+
+impl SourceItemBuilder {
+    /// Set the item ID (default: a random UUID).
+    pub fn with_id(mut self, id: Uuid) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Set the source ID (default: nil).
+    pub fn with_source_id(mut self, source_id: Uuid) -> Self {
+        self.source_id = source_id;
+        self
+    }
+
+    /// Set the source kind (default: `Other`).
+    pub fn with_source_kind(mut self, source_kind: SourceKind) -> Self {
+        self.source_kind = source_kind;
+        self
+    }
+
+    /// Set the content MIME type.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Set the content to chunk.
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.content = content.into();
+        self
+    }
+
+    /// Set the source metadata.
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set when the item was created in the source system.
+    pub fn with_created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Set the processing priority.
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Build and validate the item.
+    pub fn build(self) -> Result<SourceItem, Vec<ValidationError>> {
+        let item = SourceItem {
+            id: self.id,
+            source_id: self.source_id,
+            source_kind: self.source_kind,
+            content_type: self
+                .content_type
+                .unwrap_or_else(|| self.source_kind.default_content_type().to_string()),
+            content: self.content,
+            metadata: self.metadata,
+            created_at: self.created_at,
+            priority: self.priority,
+        };
+        item.validate()?;
+        Ok(item)
+    }
+}
+
+/// Validation errors of one item in a `StartChunkJobRequest`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ItemValidationErrors {
+    /// Position of the item in `items`
+    pub index: usize,
+    /// ID of the item
+    pub item_id: Uuid,
+    pub errors: Vec<ValidationError>,
+}
+
 /// Request to start a chunking job.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StartChunkJobRequest {
     /// ID of the source (connected account/integration)
     pub source_id: Uuid,
@@ -138,10 +348,39 @@ pub struct StartChunkJobRequest {
     
     /// Items to chunk
     pub items: Vec<SourceItem>,
+    
+    /// Owner of the job, taken from the authenticated token's `sub` claim
+    #[serde(default, skip_deserializing)]
+    #[schema(read_only)]
+    pub owner_id: Option<String>,
+}
+
+impl StartChunkJobRequest {
+    /// Validate every item, returning the errors of the invalid ones.
+    pub fn validate(&self) -> Result<(), Vec<ItemValidationErrors>> {
+        let invalid: Vec<ItemValidationErrors> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                item.validate().err().map(|errors| ItemValidationErrors {
+                    index,
+                    item_id: item.id,
+                    errors,
+                })
+            })
+            .collect();
+
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid)
+        }
+    }
 }
 
 /// Response when starting a chunking job.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StartChunkJobResponse {
     /// ID of the created job
     pub job_id: Uuid,
@@ -158,7 +397,7 @@ pub struct StartChunkJobResponse {
 }
 
 /// Status of a chunking job.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ChunkJobStatus {
     /// Job is queued but not started
@@ -172,7 +411,7 @@ pub enum ChunkJobStatus {
 }
 
 /// Response with job status information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChunkJobStatusResponse {
     /// ID of the job
     pub job_id: Uuid,
@@ -201,3 +440,62 @@ pub struct ChunkJobStatusResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub completed_at: Option<DateTime<Utc>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_source_item() {
+        let item = SourceItem::builder()
+            .with_source_kind(SourceKind::CodeRepo)
+            .with_content_type("text/code:rust")
+            .with_content("fn main() {}")
+            .with_metadata(serde_json::json!({ "path": "src/main.rs" }))
+            .build()
+            .unwrap();
+        assert_eq!(item.extract_language(), Some("rust"));
+        assert_eq!(
+            SourceItem::builder().with_content("hello").build().unwrap().content_type,
+            "text/plain"
+        );
+
+        let errors = SourceItem::builder()
+            .with_source_kind(SourceKind::Chat)
+            .with_content_type("text/code:rust")
+            .with_metadata(serde_json::json!(["not", "an", "object"]))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::EmptyContent,
+                ValidationError::InvalidMetadataJson,
+                ValidationError::IncompatibleSourceKind {
+                    kind: SourceKind::Chat,
+                    content_type: "text/code:rust".to_string(),
+                },
+            ]
+        );
+
+        let errors = SourceItem::builder()
+            .with_content_type(" ")
+            .with_content("x".repeat(SourceItem::MAX_CONTENT_SIZE + 1))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::ContentTooLarge {
+                    size: SourceItem::MAX_CONTENT_SIZE + 1,
+                    max: SourceItem::MAX_CONTENT_SIZE,
+                },
+                ValidationError::EmptyContentType,
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&errors[1]).unwrap(),
+            serde_json::json!({ "error": "empty_content_type" })
+        );
+    }
+}